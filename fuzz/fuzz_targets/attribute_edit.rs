@@ -0,0 +1,39 @@
+//! `cargo +nightly fuzz run attribute_edit`
+//!
+//! Feeds arbitrary before/after file contents through `attribute_edit`, twice (so the
+//! second edit starts from real attributions), and checks the line attributions stay
+//! ordered, in range and non-overlapping.
+#![no_main]
+
+use git_ai::authorship::attribution_tracker::{LineAttribution, attribute_edit};
+use libfuzzer_sys::fuzz_target;
+
+fn check(content: &str, line_attributions: &[LineAttribution]) {
+    let line_count = content.lines().count() as u32;
+    let mut previous_end = 0;
+    for attr in line_attributions {
+        assert!(attr.start_line >= 1 && attr.start_line <= attr.end_line);
+        assert!(attr.end_line <= line_count.max(1));
+        assert!(attr.start_line > previous_end, "{:?}", line_attributions);
+        previous_end = attr.end_line;
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let mut parts = text.splitn(3, '\0');
+    let first = parts.next().unwrap_or_default();
+    let second = parts.next().unwrap_or_default();
+    let third = parts.next().unwrap_or_default();
+
+    let Ok((attributions, line_attributions)) = attribute_edit(first, &[], second, "ai", 2)
+    else {
+        return;
+    };
+    check(second, &line_attributions);
+
+    if let Ok((_, line_attributions)) = attribute_edit(second, &attributions, third, "human", 4)
+    {
+        check(third, &line_attributions);
+    }
+});
@@ -1,6 +1,7 @@
 use crate::auth::{CredentialStore, OAuthClient};
 use crate::config;
 use crate::error::GitAiError;
+use crate::observability::timings::{self, Phase};
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use url::Url;
@@ -182,6 +183,8 @@ impl ApiContext {
             request = request.with_timeout(timeout);
         }
 
+        crate::network::ensure_allowed("call the git-ai API")?;
+        let _timing = timings::phase(Phase::Api);
         let response = request
             .send()
             .map_err(|e| GitAiError::Generic(format!("HTTP request failed: {}", e)))?;
@@ -210,6 +213,8 @@ impl ApiContext {
             request = request.with_timeout(timeout);
         }
 
+        crate::network::ensure_allowed("call the git-ai API")?;
+        let _timing = timings::phase(Phase::Api);
         let response = request
             .send()
             .map_err(|e| GitAiError::Generic(format!("HTTP request failed: {}", e)))?;
@@ -1,14 +1,14 @@
 use crate::authorship::authorship_log::{LineRange, PromptRecord};
 use crate::commands::diff::FileDiffJson;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// File record for API - converts LineRange annotations to API format
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ApiFileRecord {
     /// Maps prompt_hash to line numbers/ranges
     /// Example: { "prompt_abc123": [[1, 5], 10] } means lines 1-5 and line 10 attributed to prompt_abc123
-    pub annotations: HashMap<String, Vec<serde_json::Value>>,
+    pub annotations: BTreeMap<String, Vec<serde_json::Value>>,
     /// Git diff output
     pub diff: String,
     /// Original file content before changes
@@ -18,7 +18,7 @@ pub struct ApiFileRecord {
 
 impl From<&FileDiffJson> for ApiFileRecord {
     fn from(file_diff: &FileDiffJson) -> Self {
-        let annotations: HashMap<String, Vec<serde_json::Value>> = file_diff
+        let annotations: BTreeMap<String, Vec<serde_json::Value>> = file_diff
             .annotations
             .iter()
             .map(|(key, ranges)| {
@@ -48,10 +48,10 @@ impl From<&FileDiffJson> for ApiFileRecord {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BundleData {
     /// REQUIRED: At least one prompt
-    pub prompts: HashMap<String, PromptRecord>,
+    pub prompts: BTreeMap<String, PromptRecord>,
     /// OPTIONAL: File diffs and annotations
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub files: HashMap<String, ApiFileRecord>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, ApiFileRecord>,
 }
 
 /// Request body for creating a bundle
@@ -85,8 +85,8 @@ pub struct ApiErrorResponse {
 pub struct CasObject {
     pub content: serde_json::Value,
     pub hash: String,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub metadata: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 /// Request body for CAS upload
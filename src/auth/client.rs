@@ -53,6 +53,7 @@ impl OAuthClient {
 
     /// Common token exchange logic - POST to /worker/oauth/token with given body
     fn exchange_token(&self, body: serde_json::Value) -> Result<StoredCredentials, String> {
+        crate::network::ensure_allowed("sign in").map_err(|e| e.to_string())?;
         let url = format!("{}/worker/oauth/token", self.base_url);
 
         let response = ApiContext::http_post(&url)
@@ -93,6 +94,7 @@ impl OAuthClient {
     /// Start the device authorization flow
     /// Returns (device_code, user_code, verification_url, expires_in, interval)
     pub fn start_device_flow(&self) -> Result<DeviceAuthResponse, String> {
+        crate::network::ensure_allowed("sign in").map_err(|e| e.to_string())?;
         let url = format!("{}/worker/oauth/device/code", self.base_url);
 
         let response = ApiContext::http_post(&url)
@@ -141,6 +143,7 @@ impl OAuthClient {
                 "client_id": "git-ai-cli"
             });
 
+            crate::network::ensure_allowed("sign in").map_err(|e| e.to_string())?;
             let response = ApiContext::http_post(&url)
                 .with_header("Content-Type", "application/json")
                 .with_body(body.to_string())
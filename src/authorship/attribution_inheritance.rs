@@ -0,0 +1,548 @@
+//! Ownership transfer for mechanical edits. When an agent renames a symbol across many
+//! files, the lines it touched are barely changed, and a team may prefer they keep
+//! their original author instead of becoming AI lines. With inheritance enabled for a
+//! checkpoint, each line the agent replaced is compared token by token with the line it
+//! replaced; close enough, and it inherits that line's author.
+//!
+//! Reformatting is the extreme case: hunks that only reindent, re-wrap or join lines,
+//! or add blank lines, change no code at all. With `preserve_whitespace_attribution` set, every checkpoint
+//! hands the lines of such hunks back to whoever wrote them.
+
+use crate::authorship::attribution_tracker::{Attribution, attributions_to_line_attributions};
+use crate::authorship::imara_diff_utils::{DiffOp, capture_diff_slices};
+use crate::authorship::working_log::CheckpointKind;
+use crate::error::GitAiError;
+use std::collections::HashMap;
+
+/// Minimum token similarity (percent) for a replaced line to keep its author
+pub const DEFAULT_INHERIT_MIN_SIMILARITY: f64 = 70.0;
+
+/// Whose lines can be inherited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InheritFrom {
+    /// Only human lines keep their author; lines from another agent become this one's
+    #[default]
+    Human,
+    /// Any author, including other agent sessions
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InheritanceRules {
+    /// Percent (0-100) of tokens a replaced line must share, in order, with the line it
+    /// replaced
+    pub min_similarity: f64,
+    pub from: InheritFrom,
+}
+
+impl Default for InheritanceRules {
+    fn default() -> Self {
+        InheritanceRules {
+            min_similarity: DEFAULT_INHERIT_MIN_SIMILARITY,
+            from: InheritFrom::Human,
+        }
+    }
+}
+
+impl InheritanceRules {
+    /// Parse the value of `--inherit-attribution=<min-similarity>`
+    pub fn parse_min_similarity(value: &str) -> Result<f64, GitAiError> {
+        value
+            .trim()
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .ok()
+            .filter(|v| (0.0..=100.0).contains(v))
+            .ok_or_else(|| {
+                GitAiError::Generic(format!(
+                    "Invalid similarity '{}': expected a percentage between 0 and 100",
+                    value
+                ))
+            })
+    }
+
+    /// Parse the value of `--inherit-from=<human|any>`
+    pub fn parse_from(value: &str) -> Result<InheritFrom, GitAiError> {
+        match value.trim() {
+            "human" => Ok(InheritFrom::Human),
+            "any" => Ok(InheritFrom::Any),
+            other => Err(GitAiError::Generic(format!(
+                "Invalid --inherit-from '{}': expected human or any",
+                other
+            ))),
+        }
+    }
+
+    fn allows(&self, previous_author: &str) -> bool {
+        match self.from {
+            InheritFrom::Human => previous_author == CheckpointKind::Human.to_str(),
+            InheritFrom::Any => true,
+        }
+    }
+}
+
+/// Hand lines `author_id` replaced back to their previous author where the rules allow
+/// it. `attributions` are the checkpoint's attributions for `content`; returns them with
+/// the inherited lines reassigned, and how many lines were.
+pub fn inherit_attributions(
+    previous_content: &str,
+    previous_attributions: &[Attribution],
+    content: &str,
+    attributions: Vec<Attribution>,
+    author_id: &str,
+    rules: &InheritanceRules,
+    ts: u128,
+) -> (Vec<Attribution>, usize) {
+    let old_lines: Vec<&str> = previous_content.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let old_authors = line_authors(previous_attributions, previous_content);
+    let new_authors = line_authors(&attributions, content);
+    let author_of = |authors: &HashMap<u32, String>, index: usize| {
+        authors
+            .get(&(index as u32 + 1))
+            .cloned()
+            .unwrap_or_else(|| CheckpointKind::Human.to_str())
+    };
+
+    // Byte range of each new line, and the author it inherits
+    let mut inherited: Vec<(usize, usize, String)> = Vec::new();
+    for op in capture_diff_slices(&old_lines, &new_lines) {
+        let DiffOp::Replace {
+            old_index,
+            old_len,
+            new_index,
+            new_len,
+        } = op
+        else {
+            continue;
+        };
+        // Pair replaced lines in order; extra lines on either side have no counterpart
+        for offset in 0..old_len.min(new_len) {
+            let (old, new) = (old_index + offset, new_index + offset);
+            let previous_author = author_of(&old_authors, old);
+            if author_of(&new_authors, new) != author_id
+                || previous_author == author_id
+                || !rules.allows(&previous_author)
+                || token_similarity(old_lines[old], new_lines[new]) * 100.0 < rules.min_similarity
+            {
+                continue;
+            }
+            let start: usize = new_lines[..new].iter().map(|line| line.len()).sum();
+            inherited.push((start, start + new_lines[new].len(), previous_author));
+        }
+    }
+    let count = inherited.len();
+    (reassign(attributions, inherited, ts), count)
+}
+
+/// A run of replaced lines whose text is unchanged apart from whitespace (reindented,
+/// re-wrapped or joined lines), or of inserted blank lines (`old_len` 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhitespaceOnlyHunk {
+    pub old_index: usize,
+    pub old_len: usize,
+    pub new_index: usize,
+    pub new_len: usize,
+}
+
+/// The parts of an edit that only change whitespace. Lines are compared with their
+/// whitespace removed, so reformatted lines are found even next to real changes: lines
+/// that match but differ in whitespace, matched runs that were re-wrapped, and inserted
+/// blank lines.
+pub fn whitespace_only_hunks(old_lines: &[&str], new_lines: &[&str]) -> Vec<WhitespaceOnlyHunk> {
+    let strip = |lines: &[&str]| -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect())
+            .collect()
+    };
+    let (old_stripped, new_stripped) = (strip(old_lines), strip(new_lines));
+
+    let mut hunks = Vec::new();
+    for op in capture_diff_slices(&old_stripped, &new_stripped) {
+        match op {
+            DiffOp::Equal {
+                old_index,
+                new_index,
+                len,
+            } => {
+                // Runs of matched lines whose whitespace changed
+                let mut offset = 0;
+                while offset < len {
+                    let run = (offset..len)
+                        .take_while(|i| old_lines[old_index + i] != new_lines[new_index + i])
+                        .count();
+                    if run > 0 {
+                        hunks.push(WhitespaceOnlyHunk {
+                            old_index: old_index + offset,
+                            old_len: run,
+                            new_index: new_index + offset,
+                            new_len: run,
+                        });
+                    }
+                    offset += run.max(1);
+                }
+            }
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } if new_stripped[new_index..new_index + new_len]
+                .iter()
+                .all(String::is_empty) =>
+            {
+                hunks.push(WhitespaceOnlyHunk {
+                    old_index,
+                    old_len: 0,
+                    new_index,
+                    new_len,
+                });
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } if old_stripped[old_index..old_index + old_len].concat()
+                == new_stripped[new_index..new_index + new_len].concat() =>
+            {
+                hunks.push(WhitespaceOnlyHunk {
+                    old_index,
+                    old_len,
+                    new_index,
+                    new_len,
+                });
+            }
+            _ => {}
+        }
+    }
+    hunks
+}
+
+/// Give the lines of whitespace-only hunks back to the authors of the lines they were
+/// reformatted from. A re-wrapped line takes the author of the old line its first
+/// character came from, and an inserted blank line that of the line above it. Returns
+/// the attributions and how many lines changed author.
+pub fn preserve_whitespace_only_attributions(
+    previous_content: &str,
+    previous_attributions: &[Attribution],
+    content: &str,
+    attributions: Vec<Attribution>,
+    ts: u128,
+) -> (Vec<Attribution>, usize) {
+    let old_lines: Vec<&str> = previous_content.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let old_authors = line_authors(previous_attributions, previous_content);
+    let new_authors = line_authors(&attributions, content);
+    let author_of = |authors: &HashMap<u32, String>, index: usize| {
+        authors
+            .get(&(index as u32 + 1))
+            .cloned()
+            .unwrap_or_else(|| CheckpointKind::Human.to_str())
+    };
+
+    let mut preserved: Vec<(usize, usize, String)> = Vec::new();
+    for hunk in whitespace_only_hunks(&old_lines, &new_lines) {
+        if hunk.old_len == 0 {
+            let previous_author = author_of(&old_authors, hunk.old_index.saturating_sub(1));
+            for new in hunk.new_index..hunk.new_index + hunk.new_len {
+                if author_of(&new_authors, new) != previous_author {
+                    let start: usize = new_lines[..new].iter().map(|line| line.len()).sum();
+                    preserved.push((start, start + new_lines[new].len(), previous_author.clone()));
+                }
+            }
+            continue;
+        }
+        // Non-whitespace characters up to the end of each old line in the hunk
+        let mut old_ends = Vec::with_capacity(hunk.old_len);
+        let mut total = 0;
+        for line in &old_lines[hunk.old_index..hunk.old_index + hunk.old_len] {
+            total += line.chars().filter(|c| !c.is_whitespace()).count();
+            old_ends.push(total);
+        }
+
+        let mut seen = 0;
+        for new in hunk.new_index..hunk.new_index + hunk.new_len {
+            let old_offset = old_ends
+                .iter()
+                .position(|end| *end > seen)
+                .unwrap_or(hunk.old_len - 1);
+            seen += new_lines[new]
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .count();
+
+            let previous_author = author_of(&old_authors, hunk.old_index + old_offset);
+            if author_of(&new_authors, new) == previous_author {
+                continue;
+            }
+            let start: usize = new_lines[..new].iter().map(|line| line.len()).sum();
+            preserved.push((start, start + new_lines[new].len(), previous_author));
+        }
+    }
+    let count = preserved.len();
+    (reassign(attributions, preserved, ts), count)
+}
+
+/// Replace the attributions of each `(start, end, author)` byte range with `author`
+fn reassign(
+    attributions: Vec<Attribution>,
+    ranges: Vec<(usize, usize, String)>,
+    ts: u128,
+) -> Vec<Attribution> {
+    if ranges.is_empty() {
+        return attributions;
+    }
+    let mut result = attributions;
+    for (start, end, author) in ranges {
+        result = remove_range(result, start, end);
+        result.push(Attribution::new(start, end, author, ts));
+    }
+    result.sort_by_key(|a| (a.start, a.end, a.author_id.clone()));
+    result
+}
+
+/// Author of each line, keyed by 1-indexed line number; human lines are absent
+fn line_authors(attributions: &[Attribution], content: &str) -> HashMap<u32, String> {
+    let mut authors = HashMap::new();
+    for line_attribution in attributions_to_line_attributions(&attributions.to_vec(), content) {
+        for line in line_attribution.start_line..=line_attribution.end_line {
+            authors.insert(line, line_attribution.author_id.clone());
+        }
+    }
+    authors
+}
+
+/// Cut `[start, end)` out of every attribution, dropping deletion markers inside it
+fn remove_range(attributions: Vec<Attribution>, start: usize, end: usize) -> Vec<Attribution> {
+    let mut kept = Vec::with_capacity(attributions.len());
+    for attribution in attributions {
+        if attribution.start == attribution.end {
+            if !(start..end).contains(&attribution.start) {
+                kept.push(attribution);
+            }
+            continue;
+        }
+        if !attribution.overlaps(start, end) {
+            kept.push(attribution);
+            continue;
+        }
+        if attribution.start < start {
+            kept.push(Attribution::new(
+                attribution.start,
+                start,
+                attribution.author_id.clone(),
+                attribution.ts,
+            ));
+        }
+        if attribution.end > end {
+            kept.push(Attribution::new(
+                end,
+                attribution.end,
+                attribution.author_id.clone(),
+                attribution.ts,
+            ));
+        }
+    }
+    kept
+}
+
+/// Identifiers and numbers are single tokens, other non-whitespace characters are
+/// tokens on their own, and whitespace is ignored
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (index, c) in line.char_indices() {
+        let is_word = c.is_alphanumeric() || c == '_';
+        if let Some(start) = word_start
+            && !is_word
+        {
+            tokens.push(&line[start..index]);
+            word_start = None;
+        }
+        if is_word {
+            word_start.get_or_insert(index);
+        } else if !c.is_whitespace() {
+            tokens.push(&line[index..index + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Share of tokens two lines have in common, in order: 2 * LCS / (|a| + |b|), from 0.0
+/// to 1.0. Lines that differ only in whitespace are identical.
+pub fn token_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (tokenize(a), tokenize(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let common: usize = capture_diff_slices(&a, &b)
+        .iter()
+        .map(|op| match op {
+            DiffOp::Equal { len, .. } => *len,
+            _ => 0,
+        })
+        .sum();
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::attribution_tracker::attribute_edit;
+
+    #[test]
+    fn test_token_similarity() {
+        assert_eq!(token_similarity("let x = 1;", "let  x=1;"), 1.0);
+        assert_eq!(token_similarity("", "   "), 1.0);
+        // One identifier of nine tokens renamed
+        let renamed = token_similarity("total = compute(a, b);", "total = calculate(a, b);");
+        assert!((renamed - 8.0 / 9.0).abs() < 1e-9, "{}", renamed);
+        assert!(token_similarity("return a + b;", "for item in items {") < 0.2);
+    }
+
+    #[test]
+    fn test_rename_keeps_human_lines_but_not_rewrites() {
+        let previous = "fn compute(a: u32) -> u32 {\n    a * 2\n}\nlet y = compute(4);\n";
+        let current = "fn calculate(a: u32) -> u32 {\n    a * 2\n}\nprintln!(\"{}\", calculate(4) + 1 - 3);\n";
+        let (attributions, _) = attribute_edit(previous, &[], current, "ai-session", 100).unwrap();
+        let rules = InheritanceRules::default();
+
+        let (attributions, count) = inherit_attributions(
+            previous,
+            &[],
+            current,
+            attributions,
+            "ai-session",
+            &rules,
+            100,
+        );
+        assert_eq!(count, 1);
+        let lines = attributions_to_line_attributions(&attributions, current);
+        assert_eq!(lines.len(), 1, "{:?}", lines);
+        assert_eq!((lines[0].start_line, lines[0].end_line), (4, 4));
+        assert_eq!(lines[0].author_id, "ai-session");
+    }
+
+    #[test]
+    fn test_lines_from_other_agents_only_inherit_with_any() {
+        let previous = "let total = compute(a, b);\n";
+        let current = "let total = calculate(a, b);\n";
+        let other_agent = vec![Attribution::new(0, previous.len(), "other".to_string(), 50)];
+        let (attributions, _) =
+            attribute_edit(previous, &other_agent, current, "ai-session", 100).unwrap();
+
+        let (_, count) = inherit_attributions(
+            previous,
+            &other_agent,
+            current,
+            attributions.clone(),
+            "ai-session",
+            &InheritanceRules::default(),
+            100,
+        );
+        assert_eq!(count, 0);
+
+        let any = InheritanceRules {
+            from: InheritFrom::Any,
+            ..Default::default()
+        };
+        let (attributions, count) = inherit_attributions(
+            previous,
+            &other_agent,
+            current,
+            attributions,
+            "ai-session",
+            &any,
+            100,
+        );
+        assert_eq!(count, 1);
+        let lines = attributions_to_line_attributions(&attributions, current);
+        assert_eq!(lines[0].author_id, "other");
+    }
+
+    /// Author ranges of `author_id`'s lines
+    fn lines_of(attributions: &[Attribution], content: &str, author_id: &str) -> Vec<(u32, u32)> {
+        attributions_to_line_attributions(&attributions.to_vec(), content)
+            .iter()
+            .filter(|line| line.author_id == author_id)
+            .map(|line| (line.start_line, line.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_whitespace_only_hunks_keep_their_authors() {
+        let previous = "fn add(a: u32, b: u32) -> u32 { a + b }\nlet y = 0;\nlet x = add(1, 2);\n";
+        let current =
+            "fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\nlet y = 0;\nlet x = add(1, 3);\n";
+        let old_lines: Vec<&str> = previous.split_inclusive('\n').collect();
+        let new_lines: Vec<&str> = current.split_inclusive('\n').collect();
+        assert_eq!(
+            whitespace_only_hunks(&old_lines, &new_lines),
+            vec![WhitespaceOnlyHunk {
+                old_index: 0,
+                old_len: 1,
+                new_index: 0,
+                new_len: 3,
+            }]
+        );
+
+        // As if the agent's edit had claimed every line
+        let claimed = vec![Attribution::new(
+            0,
+            current.len(),
+            "ai-session".to_string(),
+            100,
+        )];
+        let (attributions, count) =
+            preserve_whitespace_only_attributions(previous, &[], current, claimed, 100);
+        assert_eq!(count, 3);
+        assert_eq!(lines_of(&attributions, current, "ai-session"), vec![(4, 5)]);
+    }
+
+    #[test]
+    fn test_rewrapped_and_blank_lines_take_neighbouring_authors() {
+        let previous = "call(first,\n     second);\n";
+        // A trailing comma is a real change
+        let current = "call(\n    first,\n    second,\n);\n";
+        let old_lines: Vec<&str> = previous.split_inclusive('\n').collect();
+        let new_lines: Vec<&str> = current.split_inclusive('\n').collect();
+        assert!(whitespace_only_hunks(&old_lines, &new_lines).is_empty());
+
+        let current = "call(\n    first,\n    second\n);\n\n";
+        let second_line = vec![Attribution::new(
+            12,
+            previous.len(),
+            "other".to_string(),
+            50,
+        )];
+        let claimed = vec![Attribution::new(
+            0,
+            current.len(),
+            "ai-session".to_string(),
+            100,
+        )];
+        let (attributions, count) =
+            preserve_whitespace_only_attributions(previous, &second_line, current, claimed, 100);
+        assert_eq!(count, 5);
+        assert!(lines_of(&attributions, current, "ai-session").is_empty());
+        // "second" came from the other agent's line, and so did the blank line below it
+        assert_eq!(lines_of(&attributions, current, "other"), vec![(3, 5)]);
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(InheritanceRules::parse_min_similarity("85").unwrap(), 85.0);
+        assert_eq!(InheritanceRules::parse_min_similarity("60%").unwrap(), 60.0);
+        assert!(InheritanceRules::parse_min_similarity("120").is_err());
+        assert!(InheritanceRules::parse_min_similarity("most").is_err());
+        assert_eq!(
+            InheritanceRules::parse_from("any").unwrap(),
+            InheritFrom::Any
+        );
+        assert!(InheritanceRules::parse_from("agents").is_err());
+    }
+}
@@ -261,15 +261,60 @@ struct DiffComputation {
     substantive_new_ranges: Vec<(usize, usize)>,
 }
 
+/// The units a changed hunk is diffed in before its text is attributed. Coarser units
+/// attribute more of the surrounding text to whoever changed it, and diff faster.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AttributionGranularity {
+    /// Whole lines: any change to a line claims all of it
+    Line,
+    /// Runs of non-whitespace text, for prose such as Markdown
+    Word,
+    /// Identifiers, literals and operators
+    #[default]
+    Token,
+    /// Statements, ended by `;`, `{`, `}` or a newline outside brackets
+    Statement,
+}
+
+impl AttributionGranularity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributionGranularity::Line => "line",
+            AttributionGranularity::Word => "word",
+            AttributionGranularity::Token => "token",
+            AttributionGranularity::Statement => "statement",
+        }
+    }
+}
+
+impl std::str::FromStr for AttributionGranularity {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_lowercase().as_str() {
+            "line" => Ok(AttributionGranularity::Line),
+            "word" => Ok(AttributionGranularity::Word),
+            "token" => Ok(AttributionGranularity::Token),
+            "statement" => Ok(AttributionGranularity::Statement),
+            other => Err(format!(
+                "invalid attribution granularity: '{}' (expected line, word, token or statement)",
+                other
+            )),
+        }
+    }
+}
+
 /// Configuration for the attribution tracker
 pub struct AttributionConfig {
     move_lines_threshold: usize,
+    granularity: AttributionGranularity,
 }
 
 impl Default for AttributionConfig {
     fn default() -> Self {
         AttributionConfig {
             move_lines_threshold: 3,
+            granularity: AttributionGranularity::default(),
         }
     }
 }
@@ -293,6 +338,16 @@ impl AttributionTracker {
         AttributionTracker { config }
     }
 
+    /// Create a new attribution tracker that diffs changed hunks in `granularity` units
+    pub fn with_granularity(granularity: AttributionGranularity) -> Self {
+        AttributionTracker {
+            config: AttributionConfig {
+                granularity,
+                ..AttributionConfig::default()
+            },
+        }
+    }
+
     fn compute_diffs(
         &self,
         old_content: &str,
@@ -329,6 +384,19 @@ impl AttributionTracker {
         let mut pending_changed: Vec<DiffOp> = Vec::new();
         let process_start = Instant::now();
 
+        // Statements can run across unchanged lines, so a changed file is diffed as one
+        // hunk for them rather than hunk by hunk
+        let line_ops = if self.config.granularity == AttributionGranularity::Statement
+            && line_ops
+                .iter()
+                .any(|op| !matches!(op, DiffOp::Equal { .. }))
+        {
+            pending_changed = line_ops;
+            Vec::new()
+        } else {
+            line_ops
+        };
+
         for op in line_ops.into_iter() {
             if matches!(op, DiffOp::Equal { .. }) {
                 if !pending_changed.is_empty() {
@@ -429,6 +497,7 @@ impl AttributionTracker {
             (new_start, new_end),
             old_start_line + 1,
             new_start_line + 1,
+            self.config.granularity,
         );
 
         computation.diffs.append(&mut hunk_diffs);
@@ -1105,6 +1174,104 @@ fn try_match_multi_char_op(ch: char, peek: Option<char>) -> Option<&'static str>
     }
 }
 
+/// Split `range` of `content` into the units of `granularity`
+fn tokenize(
+    content: &str,
+    range: (usize, usize),
+    starting_line: usize,
+    granularity: AttributionGranularity,
+) -> Vec<Token> {
+    match granularity {
+        AttributionGranularity::Token => tokenize_non_whitespace(content, range, starting_line),
+        AttributionGranularity::Line => tokenize_spans(content, range, starting_line, |ch, _| {
+            if ch == '\n' {
+                SpanBreak::Before
+            } else {
+                SpanBreak::None
+            }
+        }),
+        AttributionGranularity::Word => tokenize_spans(content, range, starting_line, |ch, _| {
+            if ch.is_whitespace() {
+                SpanBreak::Before
+            } else {
+                SpanBreak::None
+            }
+        }),
+        AttributionGranularity::Statement => {
+            tokenize_spans(content, range, starting_line, |ch, depth| match ch {
+                ';' | '{' | '}' => SpanBreak::After,
+                '\n' if depth == 0 => SpanBreak::Before,
+                _ => SpanBreak::None,
+            })
+        }
+    }
+}
+
+/// Where a character ends the span being built
+enum SpanBreak {
+    None,
+    /// The span ends before this character
+    Before,
+    /// The span ends with this character
+    After,
+}
+
+/// Tokenizer for coarse units: spans of text split where `split` says, given each
+/// character and the current `()`/`[]` nesting depth. A span's lexeme is its text
+/// without whitespace, so reindenting or re-wrapping it doesn't change it, and the span
+/// runs from its first to its last non-whitespace character.
+fn tokenize_spans(
+    content: &str,
+    range: (usize, usize),
+    starting_line: usize,
+    split: impl Fn(char, usize) -> SpanBreak,
+) -> Vec<Token> {
+    let (start, end) = range;
+    let mut tokens = Vec::new();
+    if start >= end {
+        return tokens;
+    }
+
+    let mut line = starting_line;
+    let mut depth = 0usize;
+    // (lexeme, start, end, line) of the span being built
+    let mut current: Option<(String, usize, usize, usize)> = None;
+    let mut finish = |current: &mut Option<(String, usize, usize, usize)>| {
+        if let Some((lexeme, start, end, line)) = current.take() {
+            tokens.push(Token {
+                lexeme,
+                start,
+                end,
+                line,
+            });
+        }
+    };
+
+    for (offset, ch) in content[start..end].char_indices() {
+        let index = start + offset;
+        let action = split(ch, depth);
+        if matches!(action, SpanBreak::Before) {
+            finish(&mut current);
+        }
+        if !ch.is_whitespace() {
+            let span = current.get_or_insert_with(|| (String::new(), index, index, line));
+            span.0.push(ch);
+            span.2 = index + ch.len_utf8();
+        }
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            '\n' => line += 1,
+            _ => {}
+        }
+        if matches!(action, SpanBreak::After) {
+            finish(&mut current);
+        }
+    }
+    finish(&mut current);
+    tokens
+}
+
 /// Code-optimized tokenizer that treats syntactic elements as meaningful units
 fn tokenize_non_whitespace(
     content: &str,
@@ -1371,6 +1538,7 @@ fn build_token_aligned_diffs(
     new_range: (usize, usize),
     old_start_line: usize,
     new_start_line: usize,
+    granularity: AttributionGranularity,
 ) -> (Vec<ByteDiff>, Vec<(usize, usize)>) {
     let (old_start, old_end) = old_range;
     let (new_start, new_end) = new_range;
@@ -1378,8 +1546,8 @@ fn build_token_aligned_diffs(
     let mut diffs = Vec::new();
     let mut substantive_ranges = Vec::new();
 
-    let old_tokens = tokenize_non_whitespace(old_content, old_range, old_start_line);
-    let new_tokens = tokenize_non_whitespace(new_content, new_range, new_start_line);
+    let old_tokens = tokenize(old_content, old_range, old_start_line, granularity);
+    let new_tokens = tokenize(new_content, new_range, new_start_line, granularity);
 
     if old_tokens.is_empty() && new_tokens.is_empty() {
         append_range_diffs(
@@ -1635,6 +1803,63 @@ impl Default for AttributionTracker {
     }
 }
 
+/// Attribute one edit: `previous_content` (with `previous_attributions`) becoming
+/// `content`, every change made by `author_id`. Text nobody was attributed with before
+/// the edit counts as human.
+///
+/// This is the whole diff-to-attribution mapping a checkpoint applies to a file, and
+/// depends only on its arguments, so it can be driven by generated edits in tests.
+///
+/// # Returns
+/// The character attributions for `content` and the line attributions derived from them
+#[allow(dead_code)]
+pub fn attribute_edit(
+    previous_content: &str,
+    previous_attributions: &[Attribution],
+    content: &str,
+    author_id: &str,
+    ts: u128,
+) -> Result<(Vec<Attribution>, Vec<LineAttribution>), GitAiError> {
+    attribute_edit_with_granularity(
+        previous_content,
+        previous_attributions,
+        content,
+        author_id,
+        ts,
+        AttributionGranularity::default(),
+    )
+}
+
+/// [`attribute_edit`], diffing changed hunks in `granularity` units
+pub fn attribute_edit_with_granularity(
+    previous_content: &str,
+    previous_attributions: &[Attribution],
+    content: &str,
+    author_id: &str,
+    ts: u128,
+    granularity: AttributionGranularity,
+) -> Result<(Vec<Attribution>, Vec<LineAttribution>), GitAiError> {
+    let tracker = AttributionTracker::with_granularity(granularity);
+
+    let filled_in_prev_attributions = tracker.attribute_unattributed_ranges(
+        previous_content,
+        previous_attributions,
+        &CheckpointKind::Human.to_str(),
+        ts.saturating_sub(1),
+    );
+    let attributions = tracker.update_attributions(
+        previous_content,
+        content,
+        &filled_in_prev_attributions,
+        author_id,
+        ts,
+    )?;
+    // TODO Consider discarding any "uncontentious" attributions for the human author. Any human attributions that do not share a line with any other author's attributions can be discarded.
+    let line_attributions = attributions_to_line_attributions(&attributions, content);
+
+    Ok((attributions, line_attributions))
+}
+
 /// Helper struct to track line boundaries in content
 struct LineBoundaries {
     /// Maps line number (1-indexed) to (start_byte, end_byte) exclusive end
@@ -1983,6 +2208,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn coarser_granularity_claims_the_whole_changed_unit() {
+        let old = "fn main() {\n    let value = 1;\n}\n";
+        let new = "fn main() {\n    let value = 2;\n}\n";
+        let old_attrs = vec![Attribution::new(0, old.len(), "Alice".into(), TEST_TS)];
+        let update = |granularity| {
+            AttributionTracker::with_granularity(granularity)
+                .update_attributions(old, new, &old_attrs, "Bob", TEST_TS + 1)
+                .unwrap()
+        };
+
+        let two_pos = new.find('2').unwrap();
+        let updated = update(AttributionGranularity::Word);
+        assert_range_owned_by(&updated, two_pos, two_pos + 2, "Bob");
+        assert_non_ws_owned_by(&updated, &new[..two_pos], "Alice", "other words stay Alice");
+
+        let line_start = new.find("let").unwrap();
+        let updated = update(AttributionGranularity::Line);
+        assert_range_owned_by(&updated, line_start, two_pos + 2, "Bob");
+        assert_non_ws_owned_by(
+            &updated,
+            &new[..line_start],
+            "Alice",
+            "other lines stay Alice",
+        );
+    }
+
+    #[test]
+    fn statement_granularity_spans_wrapped_arguments() {
+        let old = "call(\n    foo,\n    bar,\n);\nnext();\n";
+        let new = "call(\n    foo,\n    baz,\n);\nnext();\n";
+        let old_attrs = vec![Attribution::new(0, old.len(), "Alice".into(), TEST_TS)];
+
+        let updated = AttributionTracker::with_granularity(AttributionGranularity::Statement)
+            .update_attributions(old, new, &old_attrs, "Bob", TEST_TS + 1)
+            .unwrap();
+
+        let statement_end = new.find(';').unwrap() + 1;
+        assert_range_owned_by(&updated, 0, statement_end, "Bob");
+        let next = new.find("next").unwrap();
+        assert_range_owned_by(&updated, next, next + "next();".len(), "Alice");
+    }
+
     #[test]
     fn whitespace_only_indent_change_preserves_tokens() {
         let tracker = AttributionTracker::new();
@@ -2083,6 +2351,7 @@ mod tests {
         let tracker = AttributionTracker::with_config(AttributionConfig {
             // Test with a one-line threshold
             move_lines_threshold: 1,
+            ..Default::default()
         });
         let old = "fn helper() { println!(\"helper\"); }\nfn main() { println!(\"main\"); }\n";
         let new = "fn main() { println!(\"main\"); }\nfn helper() { println!(\"helper\"); }\n";
@@ -2258,4 +2527,22 @@ mod tests {
         assert_eq!(ai_block.start_line, 2);
         assert_eq!(ai_block.end_line, 17);
     }
+
+    #[test]
+    fn attribute_edit_accepts_zero_timestamp() {
+        // Unattributed ranges are filled in at `ts - 1`, which must not underflow
+        let (attributions, line_attributions) =
+            attribute_edit("fn a() {}\n", &[], "fn a() {}\nfn b() {}\n", "ai", 0).unwrap();
+
+        assert!(
+            attributions
+                .iter()
+                .any(|a| a.author_id == "ai" && a.ts == 0)
+        );
+        assert!(
+            line_attributions
+                .iter()
+                .any(|la| la.author_id == "ai" && la.start_line == 2)
+        );
+    }
 }
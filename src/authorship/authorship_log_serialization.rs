@@ -1,5 +1,10 @@
 use crate::authorship::authorship_log::{Author, LineRange, PromptRecord};
+use crate::authorship::commit_class::CommitClass;
+use crate::authorship::note_delta::NoteDelta;
+use crate::authorship::note_details::NoteDetails;
+use crate::authorship::security_tags::SecurityScan;
 use crate::authorship::working_log::CheckpointKind;
+use crate::commands::upgrade::is_newer_version;
 use crate::git::repository::Repository;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -20,6 +25,99 @@ pub const GIT_AI_VERSION: &str = concat!("development:", env!("CARGO_PKG_VERSION
 #[cfg(not(debug_assertions))]
 pub const GIT_AI_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Note features this binary understands, listed by `git-ai doctor`. Notes from other
+/// 3.x writers may carry more; unknown metadata fields are ignored.
+pub const AUTHORSHIP_CAPABILITIES: &[&str] = &[
+    "attestations",
+    "prompts",
+    "work_item",
+    "classification",
+    "min_reader_version",
+    "security_scan",
+    "details",
+    "delta",
+];
+
+/// A note this binary is too old to read: either a newer schema major version, or a
+/// writer that declared `min_reader_version` above our own version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncompatibleNoteError {
+    pub schema_version: String,
+    pub min_reader_version: Option<String>,
+    pub writer_version: Option<String>,
+}
+
+impl fmt::Display for IncompatibleNoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let writer = self
+            .writer_version
+            .as_deref()
+            .unwrap_or("an unknown version");
+        match &self.min_reader_version {
+            Some(min) => write!(
+                f,
+                "this authorship note was written by git-ai {} and requires git-ai {} or newer (running {}); please upgrade with `git-ai upgrade`",
+                writer,
+                min,
+                env!("CARGO_PKG_VERSION")
+            ),
+            None => write!(
+                f,
+                "this authorship note uses format {} (written by git-ai {}), which git-ai {} can't read; please upgrade with `git-ai upgrade`",
+                self.schema_version,
+                writer,
+                env!("CARGO_PKG_VERSION")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IncompatibleNoteError {}
+
+/// Check a note's metadata against this binary before deserializing it, so notes from
+/// newer writers fail with an upgrade hint instead of a parse error or misattribution.
+pub fn check_note_compatibility(metadata: &serde_json::Value) -> Result<(), IncompatibleNoteError> {
+    let schema_version = metadata
+        .get("schema_version")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let min_reader_version = metadata
+        .get("min_reader_version")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+
+    let schema_major = |schema: &str| -> Option<u32> {
+        schema
+            .strip_prefix("authorship/")?
+            .split('.')
+            .next()?
+            .parse()
+            .ok()
+    };
+    let newer_schema = match (
+        schema_major(schema_version),
+        schema_major(AUTHORSHIP_LOG_VERSION),
+    ) {
+        (Some(theirs), Some(ours)) => theirs > ours,
+        _ => false,
+    };
+    let newer_reader_required = min_reader_version.as_deref().is_some_and(|min| {
+        crate::commands::upgrade::is_newer_version(min, env!("CARGO_PKG_VERSION"))
+    });
+
+    if newer_schema || newer_reader_required {
+        return Err(IncompatibleNoteError {
+            schema_version: schema_version.to_string(),
+            min_reader_version: min_reader_version.filter(|_| newer_reader_required),
+            writer_version: metadata
+                .get("git_ai_version")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        });
+    }
+    Ok(())
+}
+
 /// Metadata section that goes below the divider as JSON
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AuthorshipMetadata {
@@ -27,6 +125,40 @@ pub struct AuthorshipMetadata {
     pub git_ai_version: Option<String>,
     pub base_commit_sha: String,
     pub prompts: BTreeMap<String, PromptRecord>,
+    /// Oldest git-ai version that can read this note. Only set by writers whose format
+    /// changes older readers would misread rather than ignore.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_reader_version: Option<String>,
+    /// Work-item ID (Jira key, issue number, ...) the commit was made for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_item: Option<String>,
+    /// Fully-AI / AI-assisted / human classification of the commit, by AI line share
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<CommitClass>,
+    /// The CI run that wrote the note, for notes rewritten and pushed by `git-ai ci`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Box<NoteProvenance>>,
+    /// The configured security scan of the commit's AI-authored lines, if one ran
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_scan: Option<Box<SecurityScan>>,
+    /// Set on a summary whose full note, with the line maps, is on `refs/notes/ai-details`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Box<NoteDetails>>,
+    /// Set on a note that leaves out what its parent commit's note already has
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<Box<NoteDelta>>,
+}
+
+/// Where a note came from when automation wrote it rather than a developer's checkout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteProvenance {
+    /// CI system that ran the rewrite ("github-actions", "gitlab-ci", ...)
+    pub ci: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_url: Option<String>,
+    /// How the notes commit carrying the note was signed ("ssh", "gpg" or "sigstore")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_with: Option<String>,
 }
 
 impl AuthorshipMetadata {
@@ -36,6 +168,26 @@ impl AuthorshipMetadata {
             git_ai_version: Some(GIT_AI_VERSION.to_string()),
             base_commit_sha: String::new(),
             prompts: BTreeMap::new(),
+            min_reader_version: None,
+            work_item: None,
+            classification: None,
+            provenance: None,
+            security_scan: None,
+            details: None,
+            delta: None,
+        }
+    }
+
+    /// Declare that reading the note takes this version of git-ai or newer, for formats
+    /// older readers would misread rather than ignore
+    pub fn require_current_reader(&mut self) {
+        let version = env!("CARGO_PKG_VERSION");
+        if self
+            .min_reader_version
+            .as_deref()
+            .is_none_or(|min| is_newer_version(version, min))
+        {
+            self.min_reader_version = Some(version.to_string());
         }
     }
 }
@@ -151,6 +303,16 @@ impl AuthorshipLog {
             .unwrap()
     }
 
+    /// Order files by path and each file's entries by prompt hash, so the same
+    /// attribution always serializes to the same note
+    pub fn sort_attestations(&mut self) {
+        self.attestations
+            .sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        for file_attestation in &mut self.attestations {
+            file_attestation.entries.sort_by(|a, b| a.hash.cmp(&b.hash));
+        }
+    }
+
     /// Serialize to the new text format
     pub fn serialize_to_string(&self) -> Result<String, fmt::Error> {
         let mut output = String::new();
@@ -204,15 +366,18 @@ impl AuthorshipLog {
             .position(|&line| line == "---")
             .ok_or("Missing divider '---' in authorship log")?;
 
+        // Parse JSON metadata section (after divider) first: a note from a newer writer
+        // should fail with an upgrade hint rather than an attestation parse error
+        let json_lines = &lines[divider_pos + 1..];
+        let json_content = json_lines.join("\n");
+        let metadata_value: serde_json::Value = serde_json::from_str(&json_content)?;
+        check_note_compatibility(&metadata_value)?;
+        let metadata: AuthorshipMetadata = serde_json::from_value(metadata_value)?;
+
         // Parse attestation section (before divider)
         let attestation_lines = &lines[..divider_pos];
         let attestations = parse_attestation_section(attestation_lines)?;
 
-        // Parse JSON metadata section (after divider)
-        let json_lines = &lines[divider_pos + 1..];
-        let json_content = json_lines.join("\n");
-        let metadata: AuthorshipMetadata = serde_json::from_str(&json_content)?;
-
         Ok(Self {
             attestations,
             metadata,
@@ -1344,4 +1509,34 @@ mod tests {
             .sum();
         assert_eq!(lines_session2, 20);
     }
+
+    #[test]
+    fn test_notes_from_newer_writers_fail_with_upgrade_hint() {
+        let note = |metadata: &str| format!("src/a.rs\n  abcd1234 1-2\n---\n{}", metadata);
+
+        // Newer minor schema and unknown fields are fine
+        let compatible = note(
+            r#"{"schema_version":"authorship/3.4.0","base_commit_sha":"","prompts":{},"future_field":1}"#,
+        );
+        assert!(AuthorshipLog::deserialize_from_string(&compatible).is_ok());
+
+        let newer_reader = note(
+            r#"{"schema_version":"authorship/3.0.0","git_ai_version":"99.0.0","min_reader_version":"99.0.0","base_commit_sha":"","prompts":{}}"#,
+        );
+        let err = AuthorshipLog::deserialize_from_string(&newer_reader).unwrap_err();
+        let err = err.downcast_ref::<IncompatibleNoteError>().unwrap();
+        assert_eq!(err.min_reader_version.as_deref(), Some("99.0.0"));
+        assert!(err.to_string().contains("please upgrade"));
+
+        // A new major format may change attestations too; the hint still wins
+        let newer_schema =
+            "garbage that 3.x can't parse\n---\n{\"schema_version\":\"authorship/4.0.0\"}";
+        let err = AuthorshipLog::deserialize_from_string(newer_schema).unwrap_err();
+        assert!(err.is::<IncompatibleNoteError>());
+
+        let old_reader_ok = note(
+            r#"{"schema_version":"authorship/3.0.0","min_reader_version":"0.1.0","base_commit_sha":"","prompts":{}}"#,
+        );
+        assert!(AuthorshipLog::deserialize_from_string(&old_reader_ok).is_ok());
+    }
 }
@@ -0,0 +1,338 @@
+//! Incremental line-origin index for blame. Blaming a large file walks history back to
+//! wherever its oldest lines came from, and every run repeats the walk. The index stores
+//! each blamed commit's full per-line origins in the internal database; blaming a later
+//! commit (or the working tree) then only walks history back to the nearest indexed
+//! ancestor, with `git blame ^<ancestor>`, and looks up the lines that ancestor already
+//! had. Runs that change what blame reports (`--since`, ranges) bypass the index;
+//! whitespace and ignored-revision options get indexes of their own.
+
+use crate::authorship::internal_db::InternalDatabase;
+use crate::commands::blame::{BlameHunk, GitAiBlameOptions, abbrev_sha};
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git};
+use crate::git::shallow::is_shallow;
+use crate::utils::debug_log;
+use sha2::{Digest, Sha256};
+
+/// Ancestors searched for an indexed commit before blaming from scratch
+const MAX_INDEX_DISTANCE: usize = 256;
+
+/// Blame `line_range` of `file_path` through the index, indexing the blamed commit if
+/// it isn't yet. Returns None when the index can't answer, and git blame should run as
+/// usual.
+pub fn indexed_blame_hunks(
+    repo: &Repository,
+    file_path: &str,
+    line_range: (u32, u32),
+    options: &GitAiBlameOptions,
+) -> Option<Vec<BlameHunk>> {
+    if options.oldest_commit.is_some() || options.oldest_date.is_some() || is_shallow(repo) {
+        return None;
+    }
+    // git refuses --contents with a final commit; leave the error to it
+    if options.contents_data.is_some() && options.newest_commit.is_some() {
+        return None;
+    }
+    match blame_through_index(repo, file_path, options) {
+        Ok(Some(hunks)) => Some(slice_hunks(&hunks, line_range, options)),
+        Ok(None) => None,
+        Err(e) => {
+            debug_log(&format!("Blame index unavailable for {}: {}", file_path, e));
+            None
+        }
+    }
+}
+
+fn blame_through_index(
+    repo: &Repository,
+    file_path: &str,
+    options: &GitAiBlameOptions,
+) -> Result<Option<Vec<BlameHunk>>, GitAiError> {
+    let Some(variant) = variant_key(options) else {
+        return Ok(None);
+    };
+    let index = BlameIndex {
+        repo,
+        repo_key: repo
+            .path()
+            .canonicalize()
+            .unwrap_or_else(|_| repo.path().to_path_buf())
+            .to_string_lossy()
+            .to_string(),
+        file_path,
+        variant,
+    };
+
+    let newest = options.newest_commit.as_deref().unwrap_or("HEAD");
+    let target = repo
+        .revparse_single(&format!("{}^{{commit}}", newest))?
+        .id();
+    let Some(target_origins) = index.origins_at(&target, options)? else {
+        return Ok(None);
+    };
+    if options.newest_commit.is_some() {
+        return Ok(Some(target_origins));
+    }
+
+    // The working tree or --contents: only changes since the target need blaming
+    let mut on_top = options.clone();
+    on_top.newest_commit = None;
+    let hunks = repo.git_blame_hunks(file_path, None, Some(&target), &on_top)?;
+    resolve_through(repo, hunks, &target, &target_origins)
+}
+
+struct BlameIndex<'a> {
+    repo: &'a Repository,
+    repo_key: String,
+    file_path: &'a str,
+    variant: String,
+}
+
+impl BlameIndex<'_> {
+    /// Origins of every line of the file at `commit`, from the index or else blamed back
+    /// to the nearest indexed ancestor (or from scratch) and indexed
+    fn origins_at(
+        &self,
+        commit: &str,
+        options: &GitAiBlameOptions,
+    ) -> Result<Option<Vec<BlameHunk>>, GitAiError> {
+        if let Some(origins) = self.load(commit)? {
+            return Ok(Some(origins));
+        }
+
+        let mut at_commit = options.clone();
+        at_commit.newest_commit = Some(commit.to_string());
+        at_commit.contents_data = None;
+
+        let base = self.nearest_indexed_ancestor(commit)?;
+        let resolved = match &base {
+            Some((base, base_origins)) => {
+                let hunks =
+                    self.repo
+                        .git_blame_hunks(self.file_path, None, Some(base), &at_commit)?;
+                resolve_through(self.repo, hunks, base, base_origins)?
+            }
+            None => None,
+        };
+        let origins = match resolved {
+            Some(origins) => origins,
+            None => self
+                .repo
+                .git_blame_hunks(self.file_path, None, None, &at_commit)?,
+        };
+
+        self.store(commit, &origins)?;
+        Ok(Some(origins))
+    }
+
+    fn nearest_indexed_ancestor(
+        &self,
+        commit: &str,
+    ) -> Result<Option<(String, Vec<BlameHunk>)>, GitAiError> {
+        let indexed = {
+            let db = InternalDatabase::global()?;
+            let db = db
+                .lock()
+                .map_err(|e| GitAiError::Generic(format!("Failed to lock database: {}", e)))?;
+            db.blame_index_commits(&self.repo_key, self.file_path, &self.variant)?
+        };
+        if indexed.is_empty() {
+            return Ok(None);
+        }
+
+        let mut args = self.repo.global_args_for_exec();
+        args.extend([
+            "rev-list".to_string(),
+            format!("--max-count={}", MAX_INDEX_DISTANCE),
+            commit.to_string(),
+        ]);
+        let output = exec_git(&args)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let Some(base) = stdout.lines().skip(1).find(|sha| indexed.contains(*sha)) else {
+            return Ok(None);
+        };
+        Ok(self.load(base)?.map(|origins| (base.to_string(), origins)))
+    }
+
+    fn load(&self, commit: &str) -> Result<Option<Vec<BlameHunk>>, GitAiError> {
+        let db = InternalDatabase::global()?;
+        let db = db
+            .lock()
+            .map_err(|e| GitAiError::Generic(format!("Failed to lock database: {}", e)))?;
+        let Some(origins) =
+            db.get_blame_index(&self.repo_key, self.file_path, &self.variant, commit)?
+        else {
+            return Ok(None);
+        };
+        // An index written by another version is rebuilt rather than trusted
+        Ok(serde_json::from_str(&origins).ok())
+    }
+
+    fn store(&self, commit: &str, origins: &[BlameHunk]) -> Result<(), GitAiError> {
+        let origins = serde_json::to_string(origins)?;
+        let db = InternalDatabase::global()?;
+        let mut db = db
+            .lock()
+            .map_err(|e| GitAiError::Generic(format!("Failed to lock database: {}", e)))?;
+        db.put_blame_index(
+            &self.repo_key,
+            self.file_path,
+            &self.variant,
+            commit,
+            &origins,
+        )
+    }
+}
+
+/// Which index a blame reads: runs that ignore whitespace or revisions blame lines on
+/// different commits. None when the options can't be keyed.
+fn variant_key(options: &GitAiBlameOptions) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    if options.ignore_whitespace {
+        parts.push("-w".to_string());
+    }
+    let mut ignore_revs = options.ignore_revs.clone();
+    ignore_revs.sort();
+    parts.extend(
+        ignore_revs
+            .into_iter()
+            .map(|rev| format!("--ignore-rev {}", rev)),
+    );
+    if let Some(file) = &options.ignore_revs_file {
+        let contents = std::fs::read(file).ok()?;
+        parts.push(format!(
+            "--ignore-revs-file {:x}",
+            Sha256::digest(&contents)
+        ));
+    }
+    if parts.is_empty() {
+        return Some(String::new());
+    }
+    Some(format!("{:x}", Sha256::digest(parts.join("\n").as_bytes())))
+}
+
+/// Replace the lines `hunks` blame on `base` as a boundary with where `base_origins`
+/// says they came from. Returns None if history stopped at another boundary too, which
+/// the base's index can't resolve.
+fn resolve_through(
+    repo: &Repository,
+    hunks: Vec<BlameHunk>,
+    base: &str,
+    base_origins: &[BlameHunk],
+) -> Result<Option<Vec<BlameHunk>>, GitAiError> {
+    let mut resolved: Vec<BlameHunk> = Vec::with_capacity(hunks.len());
+    for hunk in hunks {
+        if !hunk.is_boundary {
+            resolved.push(hunk);
+            continue;
+        }
+        if hunk.commit_sha != base {
+            // Root commits are boundaries too, and are a line's real origin
+            if repo.find_commit(hunk.commit_sha.clone())?.parent_count()? == 0 {
+                resolved.push(hunk);
+                continue;
+            }
+            return Ok(None);
+        }
+
+        // Each line's place in the base, mapped to the base hunk that covers it
+        let mut previous: Option<usize> = None;
+        for offset in 0..=(hunk.range.1 - hunk.range.0) {
+            let line = hunk.range.0 + offset;
+            let base_line = hunk.orig_range.0 + offset;
+            let Some(index) = base_origins
+                .iter()
+                .position(|origin| origin.range.0 <= base_line && base_line <= origin.range.1)
+            else {
+                return Ok(None);
+            };
+            let origin = &base_origins[index];
+            let orig_line = origin.orig_range.0 + (base_line - origin.range.0);
+
+            if previous == Some(index)
+                && let Some(last) = resolved.last_mut()
+                && last.range.1 + 1 == line
+                && last.orig_range.1 + 1 == orig_line
+            {
+                last.range.1 = line;
+                last.orig_range.1 = orig_line;
+                continue;
+            }
+            let mut piece = origin.clone();
+            piece.range = (line, line);
+            piece.orig_range = (orig_line, orig_line);
+            resolved.push(piece);
+            previous = Some(index);
+        }
+    }
+    Ok(Some(resolved))
+}
+
+/// The parts of whole-file `hunks` within `line_range`, as `git blame -L` would report
+fn slice_hunks(
+    hunks: &[BlameHunk],
+    line_range: (u32, u32),
+    options: &GitAiBlameOptions,
+) -> Vec<BlameHunk> {
+    let (start, end) = line_range;
+    hunks
+        .iter()
+        .filter(|hunk| hunk.range.0 <= end && start <= hunk.range.1)
+        .map(|hunk| {
+            let mut hunk = hunk.clone();
+            let skipped = start.saturating_sub(hunk.range.0);
+            let dropped = hunk.range.1.saturating_sub(end);
+            hunk.range = (hunk.range.0 + skipped, hunk.range.1 - dropped);
+            hunk.orig_range = (hunk.orig_range.0 + skipped, hunk.orig_range.1 - dropped);
+            hunk.abbrev_sha = abbrev_sha(&hunk.commit_sha, options);
+            hunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(commit: &str, range: (u32, u32), orig_range: (u32, u32)) -> BlameHunk {
+        BlameHunk {
+            range,
+            orig_range,
+            commit_sha: commit.to_string(),
+            abbrev_sha: String::new(),
+            original_author: "Author".to_string(),
+            author_email: "author@example.com".to_string(),
+            author_time: 0,
+            author_tz: "+0000".to_string(),
+            ai_human_author: None,
+            committer: "Author".to_string(),
+            committer_email: "author@example.com".to_string(),
+            committer_time: 0,
+            committer_tz: "+0000".to_string(),
+            is_boundary: false,
+        }
+    }
+
+    #[test]
+    fn test_slice_hunks_clips_ranges() {
+        let hunks = vec![hunk("a", (1, 4), (10, 13)), hunk("b", (5, 6), (1, 2))];
+        let sliced = slice_hunks(&hunks, (3, 5), &GitAiBlameOptions::default());
+        assert_eq!(sliced.len(), 2);
+        assert_eq!((sliced[0].range, sliced[0].orig_range), ((3, 4), (12, 13)));
+        assert_eq!((sliced[1].range, sliced[1].orig_range), ((5, 5), (1, 1)));
+        assert_eq!(sliced[0].abbrev_sha, "a");
+    }
+
+    #[test]
+    fn test_variant_key_ignores_order_of_ignored_revs() {
+        let mut options = GitAiBlameOptions::default();
+        assert_eq!(variant_key(&options), Some(String::new()));
+
+        options.ignore_revs = vec!["b".to_string(), "a".to_string()];
+        let key = variant_key(&options);
+        options.ignore_revs = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(variant_key(&options), key);
+        options.ignore_whitespace = true;
+        assert_ne!(variant_key(&options), key);
+    }
+}
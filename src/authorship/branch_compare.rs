@@ -0,0 +1,206 @@
+//! Pre-merge view of a branch: the AI and human lines a squash merge of `head` onto
+//! `base` would be attributed with, computed with the same in-memory squash the CI
+//! rewrite uses, so reviewers see the numbers before the merge request exists.
+
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::authorship::range_authorship::{create_authorship_log_for_range, should_ignore_file};
+use crate::error::GitAiError;
+use crate::git::partial_clone::prefetch_blobs_for_range;
+use crate::git::refs::show_authorship_note;
+use crate::git::repository::{CommitRange, Repository};
+use crate::utils::debug_log;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LineCounts {
+    pub added_lines: u32,
+    pub ai_lines: u32,
+    pub human_lines: u32,
+    /// AI lines per `tool::model`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_agent: BTreeMap<String, u32>,
+}
+
+impl LineCounts {
+    /// Fraction of added lines attributed to AI (0.0 when nothing was added)
+    pub fn ai_share(&self) -> f64 {
+        if self.added_lines == 0 {
+            0.0
+        } else {
+            self.ai_lines as f64 / self.added_lines as f64
+        }
+    }
+
+    fn add(&mut self, other: &LineCounts) {
+        self.added_lines += other.added_lines;
+        self.ai_lines += other.ai_lines;
+        self.human_lines += other.human_lines;
+        for (agent, lines) in &other.by_agent {
+            *self.by_agent.entry(agent.clone()).or_default() += lines;
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BranchComparison {
+    pub base: String,
+    pub head: String,
+    pub merge_base: String,
+    pub commits: usize,
+    pub commits_without_notes: usize,
+    pub totals: LineCounts,
+    pub files: BTreeMap<String, LineCounts>,
+}
+
+/// Compare `head` against its merge base with `base`, like a merge request diff
+pub fn compare_branches(
+    repo: &Repository,
+    base: &str,
+    head: &str,
+    ignore_patterns: &[String],
+) -> Result<BranchComparison, GitAiError> {
+    let resolve = |rev: &str| {
+        repo.revparse_single(rev)
+            .map(|object| object.id())
+            .map_err(|_| GitAiError::Generic(format!("No commit found: {}", rev)))
+    };
+    let base_sha = resolve(base)?;
+    let head_sha = resolve(head)?;
+    let merge_base = repo.merge_base(head_sha.clone(), base_sha).map_err(|_| {
+        GitAiError::Generic(format!("{} and {} have no common history", base, head))
+    })?;
+
+    let mut comparison = BranchComparison {
+        base: base.to_string(),
+        head: head.to_string(),
+        merge_base: merge_base.clone(),
+        commits: 0,
+        commits_without_notes: 0,
+        totals: LineCounts::default(),
+        files: BTreeMap::new(),
+    };
+    if merge_base == head_sha {
+        return Ok(comparison);
+    }
+
+    let commits = CommitRange::new_infer_refname(repo, merge_base.clone(), head_sha.clone(), None)?
+        .all_commits();
+    comparison.commits = commits.len();
+    comparison.commits_without_notes = commits
+        .iter()
+        .filter(|sha| show_authorship_note(repo, sha).is_none())
+        .count();
+
+    if let Err(e) = prefetch_blobs_for_range(repo, &merge_base, &head_sha) {
+        debug_log(&format!("partial clone blob prefetch failed: {}", e));
+    }
+    let log =
+        create_authorship_log_for_range(repo, &merge_base, &head_sha, &commits, ignore_patterns)?;
+
+    for (path, added) in repo.diff_added_lines(&merge_base, &head_sha, None)? {
+        if added.is_empty() || should_ignore_file(&path, ignore_patterns) {
+            continue;
+        }
+        let counts = count_file_lines(&log, &path, &added);
+        comparison.totals.add(&counts);
+        comparison.files.insert(path, counts);
+    }
+    Ok(comparison)
+}
+
+/// Split a file's added lines into AI (per agent) and human, from the squashed log
+fn count_file_lines(log: &AuthorshipLog, path: &str, added: &[u32]) -> LineCounts {
+    let added: BTreeSet<u32> = added.iter().copied().collect();
+    let mut ai_lines = BTreeSet::new();
+    let mut by_agent = BTreeMap::new();
+
+    for attestation in log.attestations.iter().filter(|a| a.file_path == path) {
+        for entry in &attestation.entries {
+            let agent = log
+                .metadata
+                .prompts
+                .get(&entry.hash)
+                .map(|prompt| format!("{}::{}", prompt.agent_id.tool, prompt.agent_id.model))
+                .unwrap_or_else(|| "unknown".to_string());
+            for range in &entry.line_ranges {
+                let lines = match range {
+                    LineRange::Single(line) => *line..=*line,
+                    LineRange::Range(start, end) => *start..=*end,
+                };
+                for line in lines.filter(|line| added.contains(line)) {
+                    // A line is only counted once, for the first prompt claiming it
+                    if ai_lines.insert(line) {
+                        *by_agent.entry(agent.clone()).or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let ai = ai_lines.len() as u32;
+    LineCounts {
+        added_lines: added.len() as u32,
+        ai_lines: ai,
+        human_lines: added.len() as u32 - ai,
+        by_agent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::authorship_log::PromptRecord;
+    use crate::authorship::authorship_log_serialization::{AttestationEntry, FileAttestation};
+    use crate::authorship::working_log::AgentId;
+
+    fn prompt(tool: &str, model: &str) -> PromptRecord {
+        PromptRecord {
+            agent_id: AgentId {
+                tool: tool.to_string(),
+                id: "session".to_string(),
+                model: model.to_string(),
+            },
+            human_author: None,
+            messages: Vec::new(),
+            total_additions: 0,
+            total_deletions: 0,
+            accepted_lines: 0,
+            overriden_lines: 0,
+            messages_url: None,
+        }
+    }
+
+    #[test]
+    fn test_count_file_lines_only_counts_added_lines() {
+        let mut log = AuthorshipLog::new();
+        log.metadata
+            .prompts
+            .insert("aaaaaaa".to_string(), prompt("claude", "sonnet"));
+        log.metadata
+            .prompts
+            .insert("bbbbbbb".to_string(), prompt("cursor", "gpt"));
+        let mut attestation = FileAttestation::new("src/lib.rs".to_string());
+        attestation.add_entry(AttestationEntry::new(
+            "aaaaaaa".to_string(),
+            vec![LineRange::Range(1, 4)],
+        ));
+        attestation.add_entry(AttestationEntry::new(
+            "bbbbbbb".to_string(),
+            vec![LineRange::Single(4), LineRange::Single(9)],
+        ));
+        log.attestations.push(attestation);
+
+        // Line 1 predates the branch; 4 is claimed twice; 9 isn't in this file's diff
+        let counts = count_file_lines(&log, "src/lib.rs", &[2, 3, 4, 5, 6]);
+        assert_eq!(counts.added_lines, 5);
+        assert_eq!(counts.ai_lines, 3);
+        assert_eq!(counts.human_lines, 2);
+        assert_eq!(counts.by_agent.get("claude::sonnet"), Some(&3));
+        assert_eq!(counts.by_agent.get("cursor::gpt"), None);
+
+        let other = count_file_lines(&log, "src/main.rs", &[1]);
+        assert_eq!((other.ai_lines, other.human_lines), (0, 1));
+    }
+}
@@ -0,0 +1,110 @@
+use glob::{MatchOptions, Pattern};
+
+/// Whether a file is production code or test code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeKind {
+    Production,
+    Test,
+}
+
+impl CodeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodeKind::Production => "production",
+            CodeKind::Test => "test",
+        }
+    }
+}
+
+/// Classifies repository-relative paths as test or production code using the
+/// `test_path_patterns` globs (e.g. `**/tests/**`, `**/*_test.go`).
+#[derive(Debug, Clone, Default)]
+pub struct CodeKindClassifier {
+    test_patterns: Vec<Pattern>,
+}
+
+impl CodeKindClassifier {
+    /// Build a classifier from glob strings. Invalid globs are skipped with a warning.
+    pub fn new(patterns: &[String]) -> Self {
+        let test_patterns = patterns
+            .iter()
+            .filter_map(|p| match Pattern::new(p) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    eprintln!("Warning: ignoring invalid test path pattern '{}': {}", p, e);
+                    None
+                }
+            })
+            .collect();
+        CodeKindClassifier { test_patterns }
+    }
+
+    pub fn from_config(repo: &crate::git::repository::Repository) -> Self {
+        Self::new(crate::config::Config::for_repo(repo).test_path_patterns())
+    }
+
+    pub fn classify(&self, path: &str) -> CodeKind {
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        if self
+            .test_patterns
+            .iter()
+            .any(|pattern| pattern.matches_with(path, options))
+        {
+            CodeKind::Test
+        } else {
+            CodeKind::Production
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_TEST_PATH_PATTERNS;
+
+    fn default_classifier() -> CodeKindClassifier {
+        let patterns: Vec<String> = DEFAULT_TEST_PATH_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        CodeKindClassifier::new(&patterns)
+    }
+
+    #[test]
+    fn test_default_patterns_classify_common_layouts() {
+        let classifier = default_classifier();
+        for path in [
+            "tests/integration.rs",
+            "crates/core/tests/parse.rs",
+            "pkg/server/handler_test.go",
+            "app/test_models.py",
+            "spec/models/user_spec.rb",
+            "web/src/__tests__/App.tsx",
+            "web/src/Button.test.tsx",
+            "web/src/Button.spec.ts",
+            "src/test/java/com/acme/UserServiceTest.java",
+        ] {
+            assert_eq!(classifier.classify(path), CodeKind::Test, "{}", path);
+        }
+        for path in [
+            "src/main.rs",
+            "pkg/server/handler.go",
+            "app/models.py",
+            "src/testing_utils.rs",
+            "docs/latest/index.md",
+        ] {
+            assert_eq!(classifier.classify(path), CodeKind::Production, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_custom_patterns_replace_defaults() {
+        let classifier = CodeKindClassifier::new(&["qa/**".to_string(), "[invalid".to_string()]);
+        assert_eq!(classifier.classify("qa/smoke.sh"), CodeKind::Test);
+        assert_eq!(classifier.classify("tests/a.rs"), CodeKind::Production);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Default minimum AI share (percent of added lines) for a commit to count as fully-AI
+pub const DEFAULT_FULLY_AI_THRESHOLD: f64 = 90.0;
+
+/// Default minimum AI share (percent of added lines) for a commit to count as AI-assisted.
+/// At 0, any AI-attributed line makes a commit assisted.
+pub const DEFAULT_AI_ASSISTED_THRESHOLD: f64 = 0.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitClass {
+    FullyAi,
+    AiAssisted,
+    Human,
+}
+
+impl CommitClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitClass::FullyAi => "fully_ai",
+            CommitClass::AiAssisted => "ai_assisted",
+            CommitClass::Human => "human",
+        }
+    }
+}
+
+/// Percent thresholds separating fully-AI, AI-assisted and human commits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassThresholds {
+    pub fully_ai: f64,
+    pub ai_assisted: f64,
+}
+
+impl Default for ClassThresholds {
+    fn default() -> Self {
+        ClassThresholds {
+            fully_ai: DEFAULT_FULLY_AI_THRESHOLD,
+            ai_assisted: DEFAULT_AI_ASSISTED_THRESHOLD,
+        }
+    }
+}
+
+impl ClassThresholds {
+    pub fn from_config(repo: &crate::git::repository::Repository) -> Self {
+        let config = crate::config::Config::for_repo(repo);
+        ClassThresholds {
+            fully_ai: config.fully_ai_threshold(),
+            ai_assisted: config.ai_assisted_threshold(),
+        }
+    }
+
+    /// Classify a commit from its AI-attributed and total added line counts.
+    /// Commits without AI lines (including commits that add nothing) are human.
+    pub fn classify(&self, ai_lines: u32, added_lines: u32) -> CommitClass {
+        if ai_lines == 0 || added_lines == 0 {
+            return CommitClass::Human;
+        }
+        let percent = ai_lines.min(added_lines) as f64 * 100.0 / added_lines as f64;
+        if percent >= self.fully_ai {
+            CommitClass::FullyAi
+        } else if percent >= self.ai_assisted {
+            CommitClass::AiAssisted
+        } else {
+            CommitClass::Human
+        }
+    }
+}
+
+/// Count the lines a commit added that its authorship log attributes to AI.
+///
+/// `added_lines_by_file` maps file paths to the line numbers the commit added
+/// (as returned by `Repository::diff_added_lines`). Returns `(ai_lines, added_lines)`.
+pub fn count_ai_added_lines(
+    log: &AuthorshipLog,
+    added_lines_by_file: &HashMap<String, Vec<u32>>,
+) -> (u32, u32) {
+    let added_total: usize = added_lines_by_file.values().map(Vec::len).sum();
+
+    let mut ai_total = 0usize;
+    for attestation in &log.attestations {
+        let Some(added) = added_lines_by_file.get(&attestation.file_path) else {
+            continue;
+        };
+        let added: HashSet<u32> = added.iter().copied().collect();
+        let mut ai_lines = HashSet::new();
+        for entry in &attestation.entries {
+            for range in &entry.line_ranges {
+                let lines = match range {
+                    LineRange::Single(line) => *line..=*line,
+                    LineRange::Range(start, end) => *start..=*end,
+                };
+                ai_lines.extend(lines.filter(|line| added.contains(line)));
+            }
+        }
+        ai_total += ai_lines.len();
+    }
+
+    (ai_total as u32, added_total as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_with_default_thresholds() {
+        let thresholds = ClassThresholds::default();
+        assert_eq!(thresholds.classify(10, 10), CommitClass::FullyAi);
+        assert_eq!(thresholds.classify(9, 10), CommitClass::FullyAi);
+        assert_eq!(thresholds.classify(8, 10), CommitClass::AiAssisted);
+        assert_eq!(thresholds.classify(1, 100), CommitClass::AiAssisted);
+        assert_eq!(thresholds.classify(0, 100), CommitClass::Human);
+        assert_eq!(thresholds.classify(0, 0), CommitClass::Human);
+    }
+
+    #[test]
+    fn test_classify_with_custom_thresholds() {
+        let thresholds = ClassThresholds {
+            fully_ai: 100.0,
+            ai_assisted: 25.0,
+        };
+        assert_eq!(thresholds.classify(9, 10), CommitClass::AiAssisted);
+        assert_eq!(thresholds.classify(10, 10), CommitClass::FullyAi);
+        assert_eq!(thresholds.classify(2, 10), CommitClass::Human);
+    }
+}
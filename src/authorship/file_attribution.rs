@@ -0,0 +1,181 @@
+use crate::authorship::attribution_tracker::{
+    AttributionTracker, LineAttribution, attributions_to_line_attributions,
+};
+use crate::authorship::authorship_log::PromptRecord;
+use crate::authorship::virtual_attribution::VirtualAttributions;
+use crate::authorship::working_log::CheckpointKind;
+use crate::error::GitAiError;
+use crate::git::repository::Repository;
+use crate::model::{Attribution, FileAttribution};
+use std::collections::{BTreeMap, HashMap};
+
+/// Attribution for several files in one pass.
+///
+/// With `rev` set, files are read as of that revision and attributed from blame and
+/// authorship notes. Without it, the working tree is used: committed attribution at
+/// HEAD overlaid with uncommitted checkpoints.
+///
+/// Files that don't exist at `rev` (or in the working tree) are returned as errors in
+/// the matching position, so one bad path doesn't fail the whole batch.
+pub fn file_attributions(
+    repo: &Repository,
+    files: &[String],
+    rev: Option<&str>,
+) -> Result<Vec<Result<FileAttribution, GitAiError>>, GitAiError> {
+    let head = match rev {
+        Some(rev) => repo.revparse_single(&format!("{}^{{commit}}", rev))?.id(),
+        None => repo.head()?.target()?,
+    };
+
+    let (existing, missing): (Vec<&String>, Vec<&String>) =
+        files.iter().partition(|file| file_exists(repo, file, rev));
+    let existing: Vec<String> = existing.into_iter().cloned().collect();
+
+    let va = if existing.is_empty() {
+        None
+    } else if rev.is_some() {
+        Some(smol::block_on(VirtualAttributions::new_for_base_commit(
+            repo.clone(),
+            head.clone(),
+            &existing,
+            None,
+        ))?)
+    } else {
+        Some(smol::block_on(
+            VirtualAttributions::from_working_log_for_commit(
+                repo.clone(),
+                head.clone(),
+                &existing,
+                None,
+                None,
+            ),
+        )?)
+    };
+
+    Ok(files
+        .iter()
+        .map(|file| {
+            if missing.contains(&file) {
+                return Err(GitAiError::Generic(match rev {
+                    Some(rev) => format!("File '{}' not found at {}", file, rev),
+                    None => format!("File '{}' not found in the working tree", file),
+                }));
+            }
+            Ok(FileAttribution::new(
+                file.clone(),
+                head.clone(),
+                va.as_ref()
+                    .map(|va| ai_ranges(va, file))
+                    .unwrap_or_default(),
+            ))
+        })
+        .collect())
+}
+
+/// AI line attributions for the staged (index) version of `files`, keyed by file, plus
+/// the prompts they refer to.
+///
+/// Attribution is tracked against the working tree, so it is carried through whatever
+/// differs between the working tree and the index. Staged lines that aren't in the
+/// working tree (e.g. a file edited again after `git add`) count as human.
+#[allow(clippy::type_complexity)]
+pub fn staged_line_attributions(
+    repo: &Repository,
+    files: &[String],
+) -> Result<
+    (
+        HashMap<String, Vec<LineAttribution>>,
+        BTreeMap<String, PromptRecord>,
+    ),
+    GitAiError,
+> {
+    let Ok(head) = repo.head().and_then(|head| head.target()) else {
+        return Ok(Default::default());
+    };
+    if files.is_empty() {
+        return Ok(Default::default());
+    }
+
+    let va = smol::block_on(VirtualAttributions::from_working_log_for_commit(
+        repo.clone(),
+        head.clone(),
+        files,
+        None,
+        None,
+    ))?;
+    let staged = repo.get_staged_file_contents()?;
+    let tracker = AttributionTracker::new();
+    let human = CheckpointKind::Human.to_str();
+
+    let mut line_attributions = HashMap::new();
+    for file in files {
+        let Some(staged_content) = staged.get(file) else {
+            continue;
+        };
+        let Some(char_attrs) = va.get_char_attributions(file) else {
+            continue;
+        };
+        let tracked_content = match va.get_file_content(file) {
+            Some(content) => content.clone(),
+            None => String::from_utf8(repo.get_file_content(file, &head)?).unwrap_or_default(),
+        };
+
+        let staged_attrs = tracker.update_attributions(
+            &tracked_content,
+            staged_content,
+            char_attrs,
+            &human,
+            va.timestamp(),
+        )?;
+        let lines: Vec<LineAttribution> =
+            attributions_to_line_attributions(&staged_attrs, staged_content)
+                .into_iter()
+                .filter(|attr| attr.author_id != human)
+                .collect();
+        if !lines.is_empty() {
+            line_attributions.insert(file.clone(), lines);
+        }
+    }
+
+    let prompts = va
+        .prompts()
+        .iter()
+        .filter_map(|(id, by_commit)| Some((id.clone(), by_commit.values().next()?.clone())))
+        .collect();
+
+    Ok((line_attributions, prompts))
+}
+
+fn file_exists(repo: &Repository, file: &str, rev: Option<&str>) -> bool {
+    match rev {
+        Some(rev) => repo
+            .git(&["cat-file", "-e", &format!("{}:{}", rev, file)])
+            .is_ok(),
+        None => repo.workdir().is_ok_and(|dir| dir.join(file).is_file()),
+    }
+}
+
+fn ai_ranges(va: &VirtualAttributions, file: &str) -> Vec<Attribution> {
+    let human = CheckpointKind::Human.to_str();
+    let prompts = va.prompts();
+    va.get_line_attributions(file)
+        .map(|attrs| {
+            attrs
+                .iter()
+                .filter(|attr| attr.author_id != human)
+                .map(|attr| {
+                    let record = prompts
+                        .get(&attr.author_id)
+                        .and_then(|by_commit| by_commit.values().next());
+                    Attribution {
+                        start_line: attr.start_line,
+                        end_line: attr.end_line,
+                        prompt_id: attr.author_id.clone(),
+                        tool: record.map(|r| r.agent_id.tool.clone()),
+                        model: record.map(|r| r.agent_id.model.clone()),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,368 @@
+use crate::authorship::snapshot::{Snapshot, tree_blobs};
+use crate::authorship::working_log::CheckpointKind;
+use crate::commands::blame::GitAiBlameOptions;
+use crate::error::GitAiError;
+use crate::git::authorship_traversal::load_ai_touched_files_for_commits;
+use crate::git::repository::Repository;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File signature and format version of the heatmap cache
+pub const HEATMAP_MAGIC: &[u8; 8] = b"GAIHEAT1";
+
+/// One bit per line of a file at HEAD, set when the line is AI-authored.
+///
+/// Bit `n - 1` (least significant bit first within each byte) stores line `n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHeatmap {
+    /// Blob the bitmap was computed for, used to skip unchanged files on refresh
+    pub blob_oid: String,
+    pub line_count: u32,
+    pub bitmap: Vec<u8>,
+}
+
+impl FileHeatmap {
+    pub fn from_ai_lines(blob_oid: String, line_count: u32, ai_lines: &[u32]) -> Self {
+        let mut bitmap = vec![0u8; line_count.div_ceil(8) as usize];
+        for &line in ai_lines {
+            if line >= 1 && line <= line_count {
+                let bit = (line - 1) as usize;
+                bitmap[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        FileHeatmap {
+            blob_oid,
+            line_count,
+            bitmap,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_ai(&self, line: u32) -> bool {
+        if line == 0 || line > self.line_count {
+            return false;
+        }
+        let bit = (line - 1) as usize;
+        self.bitmap[bit / 8] & (1 << (bit % 8)) != 0
+    }
+
+    pub fn ai_line_count(&self) -> u32 {
+        self.bitmap.iter().map(|b| b.count_ones()).sum()
+    }
+}
+
+/// Line-level AI attribution for every AI-touched file at a commit, stored in a single
+/// compact cache file so editor plugins can read it without per-file queries. Files that
+/// aren't listed have no AI-authored lines.
+///
+/// Layout (integers are little-endian):
+///
+/// ```text
+/// magic        8 bytes  "GAIHEAT1"
+/// file_count   u32
+/// head_len     u16, then head commit SHA (ASCII)
+/// index        file_count entries, sorted by path:
+///                path_len u16, path (UTF-8), oid_len u8, blob OID (ASCII),
+///                line_count u32, bitmap_offset u32
+/// bitmaps      concatenated bitmaps of ceil(line_count / 8) bytes each;
+///              bitmap_offset is relative to the start of this section
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heatmap {
+    pub head: String,
+    pub files: BTreeMap<String, FileHeatmap>,
+}
+
+/// What a refresh did, for reporting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RefreshStats {
+    pub reused: usize,
+    pub computed: usize,
+}
+
+impl Heatmap {
+    /// Default cache location: `.git/ai/heatmap`
+    pub fn cache_path(repo: &Repository) -> PathBuf {
+        repo.path().join("ai").join("heatmap")
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(HEATMAP_MAGIC);
+        out.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.head.len() as u16).to_le_bytes());
+        out.extend_from_slice(self.head.as_bytes());
+
+        let mut offset = 0u32;
+        for (path, file) in &self.files {
+            out.extend_from_slice(&(path.len() as u16).to_le_bytes());
+            out.extend_from_slice(path.as_bytes());
+            out.push(file.blob_oid.len() as u8);
+            out.extend_from_slice(file.blob_oid.as_bytes());
+            out.extend_from_slice(&file.line_count.to_le_bytes());
+            out.extend_from_slice(&offset.to_le_bytes());
+            offset += file.bitmap.len() as u32;
+        }
+        for file in self.files.values() {
+            out.extend_from_slice(&file.bitmap);
+        }
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, GitAiError> {
+        let mut reader = ByteReader { data, pos: 0 };
+        if reader.take(HEATMAP_MAGIC.len())? != HEATMAP_MAGIC {
+            return Err(GitAiError::Generic(
+                "Not a git-ai heatmap file (bad magic)".to_string(),
+            ));
+        }
+        let file_count = reader.u32()?;
+        let head_len = reader.u16()? as usize;
+        let head = reader.string(head_len)?;
+
+        let mut index = Vec::with_capacity(file_count as usize);
+        for _ in 0..file_count {
+            let path_len = reader.u16()? as usize;
+            let path = reader.string(path_len)?;
+            let oid_len = reader.u8()? as usize;
+            let blob_oid = reader.string(oid_len)?;
+            let line_count = reader.u32()?;
+            let bitmap_offset = reader.u32()? as usize;
+            index.push((path, blob_oid, line_count, bitmap_offset));
+        }
+
+        let bitmaps = &data[reader.pos..];
+        let mut files = BTreeMap::new();
+        for (path, blob_oid, line_count, offset) in index {
+            let len = line_count.div_ceil(8) as usize;
+            let bitmap = bitmaps
+                .get(offset..offset + len)
+                .ok_or_else(truncated_error)?
+                .to_vec();
+            files.insert(
+                path,
+                FileHeatmap {
+                    blob_oid,
+                    line_count,
+                    bitmap,
+                },
+            );
+        }
+
+        Ok(Heatmap { head, files })
+    }
+
+    /// The AI-touched files of a snapshot, to seed a refresh without blaming them again
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let files = snapshot
+            .files
+            .iter()
+            .filter(|(_, file)| !file.ai.is_empty())
+            .map(|(path, file)| {
+                let heatmap = FileHeatmap::from_ai_lines(
+                    file.blob.clone(),
+                    file.lines.unwrap_or(0),
+                    &file.ai_lines(),
+                );
+                (path.clone(), heatmap)
+            })
+            .collect();
+        Heatmap {
+            head: snapshot.commit.clone(),
+            files,
+        }
+    }
+
+    /// Load a heatmap cache, returning `None` if it doesn't exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, GitAiError> {
+        match std::fs::read(path) {
+            Ok(data) => Self::from_bytes(&data).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the cache atomically so readers never observe a partial file.
+    pub fn write(&self, path: &Path) -> Result<(), GitAiError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, self.to_bytes())?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Build the heatmap for HEAD. Files whose blob is unchanged since `previous` reuse
+/// their bitmap; only new or modified AI-touched files are blamed again.
+///
+/// When `previous` was built for an ancestor of HEAD, only the notes of the commits
+/// since then are read: the files it lists plus the files those commits touched with
+/// AI are all that can have AI lines now. Otherwise (no cache, or history rewritten)
+/// every commit reachable from HEAD is walked.
+pub fn refresh_heatmap(
+    repo: &Repository,
+    previous: Option<&Heatmap>,
+) -> Result<(Heatmap, RefreshStats), GitAiError> {
+    let head = repo.git(&["rev-parse", "HEAD"])?.trim().to_string();
+    let blobs = tree_blobs(repo, "HEAD")?;
+
+    let since = previous.filter(|p| !p.head.is_empty() && repo.is_ancestor(&p.head, &head));
+    let range = match since {
+        Some(previous) => format!("{}..{}", previous.head, head),
+        None => head.clone(),
+    };
+    let commits: Vec<String> = repo
+        .git(&["rev-list", &range])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let mut ai_files = if commits.is_empty() {
+        Default::default()
+    } else {
+        smol::block_on(load_ai_touched_files_for_commits(repo, commits))?
+    };
+    if let Some(previous) = since {
+        ai_files.extend(previous.files.keys().cloned());
+    }
+
+    let mut stats = RefreshStats::default();
+    let mut files = BTreeMap::new();
+    for path in ai_files {
+        let Some(blob_oid) = blobs.get(&path) else {
+            continue;
+        };
+
+        if let Some(cached) = previous.and_then(|p| p.files.get(&path))
+            && &cached.blob_oid == blob_oid
+        {
+            files.insert(path, cached.clone());
+            stats.reused += 1;
+            continue;
+        }
+
+        if let Some(file) = compute_file_heatmap(repo, &head, &path, blob_oid)? {
+            files.insert(path, file);
+        }
+        stats.computed += 1;
+    }
+
+    Ok((Heatmap { head, files }, stats))
+}
+
+fn compute_file_heatmap(
+    repo: &Repository,
+    head: &str,
+    path: &str,
+    blob_oid: &str,
+) -> Result<Option<FileHeatmap>, GitAiError> {
+    let mut options = GitAiBlameOptions::default();
+    #[allow(clippy::field_reassign_with_default)]
+    {
+        options.newest_commit = Some(head.to_string());
+        options.no_output = true;
+        options.use_prompt_hashes_as_names = true;
+        options.return_human_authors_as_human = true;
+    }
+
+    // Empty and binary files can't be blamed line by line
+    let (line_authors, _) = match repo.blame(path, &options) {
+        Ok(result) => result,
+        Err(_) => return Ok(None),
+    };
+
+    let line_count = line_authors.keys().copied().max().unwrap_or(0);
+    let human = CheckpointKind::Human.to_str();
+    let ai_lines: Vec<u32> = line_authors
+        .iter()
+        .filter(|(_, author)| **author != human)
+        .map(|(line, _)| *line)
+        .collect();
+    if ai_lines.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(FileHeatmap::from_ai_lines(
+        blob_oid.to_string(),
+        line_count,
+        &ai_lines,
+    )))
+}
+
+fn truncated_error() -> GitAiError {
+    GitAiError::Generic("Heatmap file is truncated".to_string())
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], GitAiError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(truncated_error)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, GitAiError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, GitAiError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, GitAiError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String, GitAiError> {
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| GitAiError::Generic("Heatmap file contains invalid UTF-8".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_heatmap_bits() {
+        let file = FileHeatmap::from_ai_lines("abc".to_string(), 10, &[1, 8, 9, 42]);
+        assert_eq!(file.bitmap, vec![0b1000_0001, 0b0000_0001]);
+        assert!(file.is_ai(1));
+        assert!(!file.is_ai(2));
+        assert!(file.is_ai(9));
+        assert!(!file.is_ai(0));
+        assert!(!file.is_ai(11));
+        assert_eq!(file.ai_line_count(), 3);
+    }
+
+    #[test]
+    fn test_heatmap_roundtrip() {
+        let mut heatmap = Heatmap {
+            head: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            files: BTreeMap::new(),
+        };
+        heatmap.files.insert(
+            "src/lib.rs".to_string(),
+            FileHeatmap::from_ai_lines("aaa".to_string(), 20, &[3, 4, 5]),
+        );
+        heatmap.files.insert(
+            "src/main.rs".to_string(),
+            FileHeatmap::from_ai_lines("bbb".to_string(), 3, &[2]),
+        );
+
+        let bytes = heatmap.to_bytes();
+        assert_eq!(&bytes[..8], HEATMAP_MAGIC);
+        assert_eq!(Heatmap::from_bytes(&bytes).unwrap(), heatmap);
+
+        assert!(Heatmap::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Heatmap::from_bytes(b"NOTAHEATMAP").is_err());
+    }
+}
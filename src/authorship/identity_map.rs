@@ -0,0 +1,234 @@
+use crate::error::GitAiError;
+use crate::git::repository::Repository;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Default identity map location, relative to the repository root
+pub const IDENTITY_MAP_FILENAME: &str = ".git-ai-identities";
+
+/// Team label used for authors that aren't assigned to any team
+pub const UNASSIGNED_TEAM: &str = "(unassigned)";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+}
+
+impl Identity {
+    pub fn display(&self) -> String {
+        format!("{} <{}>", self.name, self.email)
+    }
+}
+
+/// Maps the many emails a person commits with to one canonical identity and team.
+///
+/// The file format is `.mailmap`-like, with `[Team]` headers assigning everyone below
+/// them to that team:
+///
+/// ```text
+/// # Lines starting with '#' are comments
+/// [Platform]
+/// Jane Doe <jane@corp.com> <jane.doe@gmail.com> <jdoe@old-corp.com>
+///
+/// [Payments]
+/// Sam Lee <sam@corp.com>
+/// ```
+///
+/// The first email on a line is the canonical one; any further emails are aliases.
+/// Emails are matched case-insensitively. A `#` starts a comment only at the start of a
+/// line or after whitespace, so it can appear inside emails and team names.
+#[derive(Debug, Clone, Default)]
+pub struct IdentityMap {
+    by_email: HashMap<String, Identity>,
+}
+
+impl IdentityMap {
+    pub fn parse(content: &str) -> Result<Self, GitAiError> {
+        let mut by_email = HashMap::new();
+        let mut team: Option<String> = None;
+
+        for (index, raw_line) in content.lines().enumerate() {
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| {
+                        GitAiError::Generic(format!(
+                            "Invalid team header on line {}: '{}'",
+                            index + 1,
+                            raw_line.trim()
+                        ))
+                    })?;
+                team = Some(name.to_string());
+                continue;
+            }
+
+            let (name, emails) = parse_identity_line(line).ok_or_else(|| {
+                GitAiError::Generic(format!(
+                    "Invalid identity on line {}: expected 'Name <email> [<alias>...]'",
+                    index + 1
+                ))
+            })?;
+
+            let identity = Identity {
+                name,
+                email: emails[0].clone(),
+                team: team.clone(),
+            };
+            for email in emails {
+                by_email.insert(email.to_lowercase(), identity.clone());
+            }
+        }
+
+        Ok(IdentityMap { by_email })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, GitAiError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            GitAiError::Generic(format!(
+                "Failed to read identity map {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::parse(&content)
+    }
+
+    /// Load `.git-ai-identities` from the repository root, if present.
+    pub fn load_for_repo(repo: &Repository) -> Result<Option<Self>, GitAiError> {
+        let Ok(workdir) = repo.workdir() else {
+            return Ok(None);
+        };
+        let path = workdir.join(IDENTITY_MAP_FILENAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+
+    /// Resolve a commit author to their canonical identity. Unknown authors are
+    /// returned as-is with no team.
+    pub fn resolve(&self, name: &str, email: &str) -> Identity {
+        self.by_email
+            .get(&email.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| Identity {
+                name: name.to_string(),
+                email: email.to_string(),
+                team: None,
+            })
+    }
+}
+
+/// The line up to its comment: a `#` at the start or preceded by whitespace.
+fn strip_comment(line: &str) -> &str {
+    let mut previous = None;
+    for (index, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..index];
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+/// Parse `Name <email> <alias>...` into the name and the list of emails.
+fn parse_identity_line(line: &str) -> Option<(String, Vec<String>)> {
+    let name_end = line.find('<')?;
+    let name = line[..name_end].trim().to_string();
+
+    let mut emails = Vec::new();
+    let mut rest = &line[name_end..];
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>')? + start;
+        let email = rest[start + 1..end].trim();
+        if !email.is_empty() {
+            emails.push(email.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+
+    if name.is_empty() || emails.is_empty() {
+        return None;
+    }
+    Some((name, emails))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# Identity map
+Contractor <contractor@agency.com>
+
+[Platform]
+Jane Doe <jane@corp.com> <jane.doe@gmail.com> <JDOE@old-corp.com>
+
+[Payments]  # billing and checkout
+Sam Lee <sam@corp.com>
+";
+
+    #[test]
+    fn test_parse_and_resolve_aliases() {
+        let map = IdentityMap::parse(SAMPLE).unwrap();
+
+        let jane = map.resolve("jdoe", "jdoe@old-corp.com");
+        assert_eq!(jane.name, "Jane Doe");
+        assert_eq!(jane.email, "jane@corp.com");
+        assert_eq!(jane.team.as_deref(), Some("Platform"));
+
+        assert_eq!(
+            map.resolve("Jane", "jane.doe@gmail.com"),
+            map.resolve("Jane Doe", "jane@corp.com")
+        );
+        assert_eq!(
+            map.resolve("Sam", "sam@corp.com").team.as_deref(),
+            Some("Payments")
+        );
+        assert_eq!(
+            map.resolve("Contractor", "contractor@agency.com").team,
+            None
+        );
+    }
+
+    #[test]
+    fn test_hash_inside_email_and_team_is_not_a_comment() {
+        let map = IdentityMap::parse(
+            "[C#]  # the .NET team\nDev <dev#ops@corp.com> # contractor\n  # indented comment\n",
+        )
+        .unwrap();
+        let dev = map.resolve("dev", "dev#ops@corp.com");
+        assert_eq!(dev.email, "dev#ops@corp.com");
+        assert_eq!(dev.team.as_deref(), Some("C#"));
+
+        assert_eq!(strip_comment("# all comment"), "");
+        assert_eq!(strip_comment("a#b"), "a#b");
+        assert_eq!(strip_comment("a\t#b"), "a\t");
+    }
+
+    #[test]
+    fn test_unknown_author_passes_through() {
+        let map = IdentityMap::parse(SAMPLE).unwrap();
+        let unknown = map.resolve("Alex", "alex@example.com");
+        assert_eq!(unknown.display(), "Alex <alex@example.com>");
+        assert_eq!(unknown.team, None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(IdentityMap::parse("[Unclosed\nA <a@b.c>").is_err());
+        assert!(IdentityMap::parse("No email here").is_err());
+        assert!(IdentityMap::parse("<only@email.com>").is_err());
+    }
+}
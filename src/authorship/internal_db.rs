@@ -5,12 +5,12 @@ use crate::error::GitAiError;
 use crate::utils::debug_log;
 use dirs;
 use rusqlite::{Connection, params};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{Mutex, OnceLock};
 
 /// Current schema version (must match MIGRATIONS.len())
-const SCHEMA_VERSION: usize = 2;
+const SCHEMA_VERSION: usize = 3;
 
 /// Database migrations - each migration upgrades the schema by one version
 /// Migration at index N upgrades from version N to version N+1
@@ -69,8 +69,23 @@ const MIGRATIONS: &[&str] = &[
     CREATE INDEX idx_cas_sync_queue_stale_processing
         ON cas_sync_queue(processing_started_at) WHERE status = 'processing';
     "#,
+    // Migration 2 -> 3: Add the blame line-origin index
+    r#"
+    CREATE TABLE blame_line_origins (
+        repo TEXT NOT NULL,
+        file_path TEXT NOT NULL,
+        variant TEXT NOT NULL,
+        commit_sha TEXT NOT NULL,
+        origins TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (repo, file_path, variant, commit_sha)
+    );
+    "#,
 ];
 
+/// Line-origin indexes kept per file; older ones are dropped as new commits are indexed
+const BLAME_INDEXES_PER_FILE: usize = 16;
+
 /// Global database singleton
 static INTERNAL_DB: OnceLock<Mutex<InternalDatabase>> = OnceLock::new();
 
@@ -1011,6 +1026,88 @@ impl InternalDatabase {
 
         Ok(())
     }
+
+    /// Commits of `repo` (its git directory) with a stored line-origin index for
+    /// `file_path`, for blames run with `variant`'s options
+    pub fn blame_index_commits(
+        &self,
+        repo: &str,
+        file_path: &str,
+        variant: &str,
+    ) -> Result<HashSet<String>, GitAiError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT commit_sha FROM blame_line_origins WHERE repo = ?1 AND file_path = ?2 AND variant = ?3",
+        )?;
+        let rows = stmt.query_map(params![repo, file_path, variant], |row| {
+            row.get::<_, String>(0)
+        })?;
+
+        let mut commits = HashSet::new();
+        for row in rows {
+            commits.insert(row?);
+        }
+        Ok(commits)
+    }
+
+    /// The serialized line origins of `file_path` at `commit_sha`, if indexed
+    pub fn get_blame_index(
+        &self,
+        repo: &str,
+        file_path: &str,
+        variant: &str,
+        commit_sha: &str,
+    ) -> Result<Option<String>, GitAiError> {
+        let result = self.conn.query_row(
+            "SELECT origins FROM blame_line_origins WHERE repo = ?1 AND file_path = ?2 AND variant = ?3 AND commit_sha = ?4",
+            params![repo, file_path, variant, commit_sha],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(origins) => Ok(Some(origins)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store the line origins of `file_path` at `commit_sha`, dropping the file's oldest
+    /// indexes beyond [`BLAME_INDEXES_PER_FILE`]
+    pub fn put_blame_index(
+        &mut self,
+        repo: &str,
+        file_path: &str,
+        variant: &str,
+        commit_sha: &str,
+        origins: &str,
+    ) -> Result<(), GitAiError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.conn.execute(
+            r#"
+            INSERT OR REPLACE INTO blame_line_origins (
+                repo, file_path, variant, commit_sha, origins, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![repo, file_path, variant, commit_sha, origins, now],
+        )?;
+        self.conn.execute(
+            r#"
+            DELETE FROM blame_line_origins
+            WHERE repo = ?1 AND file_path = ?2 AND variant = ?3
+              AND rowid NOT IN (
+                SELECT rowid FROM blame_line_origins
+                WHERE repo = ?1 AND file_path = ?2 AND variant = ?3
+                ORDER BY created_at DESC, rowid DESC
+                LIMIT ?4
+              )
+            "#,
+            params![repo, file_path, variant, BLAME_INDEXES_PER_FILE],
+        )?;
+
+        Ok(())
+    }
 }
 
 /// Calculate next retry timestamp based on attempt number
@@ -1098,7 +1195,7 @@ mod tests {
                 |row| row.get(0),
             )
             .unwrap();
-        assert_eq!(version, "2");
+        assert_eq!(version, "3");
     }
 
     #[test]
@@ -1642,6 +1739,33 @@ mod tests {
         assert_eq!(count, 0);
     }
 
+    #[test]
+    fn test_blame_index_keeps_newest_per_file() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        for i in 0..BLAME_INDEXES_PER_FILE + 2 {
+            db.put_blame_index("/repo/.git", "a.rs", "", &format!("c{}", i), "[]")
+                .unwrap();
+        }
+        db.put_blame_index("/repo/.git", "b.rs", "", "c0", "[1]")
+            .unwrap();
+
+        let commits = db.blame_index_commits("/repo/.git", "a.rs", "").unwrap();
+        assert_eq!(commits.len(), BLAME_INDEXES_PER_FILE);
+        assert!(!commits.contains("c0") && !commits.contains("c1"));
+        assert!(commits.contains(&format!("c{}", BLAME_INDEXES_PER_FILE + 1)));
+
+        assert_eq!(
+            db.get_blame_index("/repo/.git", "b.rs", "", "c0").unwrap(),
+            Some("[1]".to_string())
+        );
+        assert_eq!(
+            db.get_blame_index("/repo/.git", "b.rs", "-w", "c0")
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_exponential_backoff() {
         let now = 1000000i64;
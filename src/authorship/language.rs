@@ -0,0 +1,186 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Language label for files that can't be classified
+pub const UNKNOWN_LANGUAGE: &str = "Other";
+
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("go", "Go"),
+    ("py", "Python"),
+    ("pyi", "Python"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("mts", "TypeScript"),
+    ("cts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kts", "Kotlin"),
+    ("scala", "Scala"),
+    ("swift", "Swift"),
+    ("m", "Objective-C"),
+    ("mm", "Objective-C"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hpp", "C++"),
+    ("hh", "C++"),
+    ("cs", "C#"),
+    ("fs", "F#"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("pl", "Perl"),
+    ("lua", "Lua"),
+    ("dart", "Dart"),
+    ("ex", "Elixir"),
+    ("exs", "Elixir"),
+    ("erl", "Erlang"),
+    ("hs", "Haskell"),
+    ("ml", "OCaml"),
+    ("clj", "Clojure"),
+    ("zig", "Zig"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("fish", "Shell"),
+    ("ps1", "PowerShell"),
+    ("sql", "SQL"),
+    ("html", "HTML"),
+    ("htm", "HTML"),
+    ("css", "CSS"),
+    ("scss", "CSS"),
+    ("sass", "CSS"),
+    ("less", "CSS"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("md", "Markdown"),
+    ("mdx", "Markdown"),
+    ("json", "JSON"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("toml", "TOML"),
+    ("xml", "XML"),
+    ("proto", "Protobuf"),
+    ("tf", "Terraform"),
+];
+
+const FILENAMES: &[(&str, &str)] = &[
+    ("Dockerfile", "Dockerfile"),
+    ("Makefile", "Makefile"),
+    ("GNUmakefile", "Makefile"),
+    ("CMakeLists.txt", "CMake"),
+    ("Rakefile", "Ruby"),
+    ("Gemfile", "Ruby"),
+    ("Jenkinsfile", "Groovy"),
+];
+
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "Python"),
+    ("node", "JavaScript"),
+    ("deno", "TypeScript"),
+    ("bun", "JavaScript"),
+    ("ruby", "Ruby"),
+    ("perl", "Perl"),
+    ("php", "PHP"),
+    ("bash", "Shell"),
+    ("sh", "Shell"),
+    ("zsh", "Shell"),
+    ("fish", "Shell"),
+    ("lua", "Lua"),
+    ("pwsh", "PowerShell"),
+];
+
+/// Classify a file by its name or extension, falling back to its shebang line.
+pub fn detect_language(path: &str, first_line: Option<&str>) -> &'static str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+
+    if let Some((_, language)) = FILENAMES.iter().find(|(name, _)| *name == file_name) {
+        return language;
+    }
+
+    if let Some((_, extension)) = file_name.rsplit_once('.')
+        && !extension.is_empty()
+    {
+        let extension = extension.to_ascii_lowercase();
+        if let Some((_, language)) = EXTENSIONS.iter().find(|(ext, _)| *ext == extension) {
+            return language;
+        }
+    }
+
+    first_line
+        .and_then(language_from_shebang)
+        .unwrap_or(UNKNOWN_LANGUAGE)
+}
+
+/// Classify a repository file, reading its shebang from `root` only when the name
+/// alone isn't enough.
+pub fn detect_language_in(root: &Path, path: &str) -> &'static str {
+    let by_name = detect_language(path, None);
+    if by_name != UNKNOWN_LANGUAGE {
+        return by_name;
+    }
+
+    let first_line = std::fs::File::open(root.join(path)).ok().and_then(|file| {
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line).ok()?;
+        Some(line)
+    });
+    detect_language(path, first_line.as_deref())
+}
+
+/// `#!/usr/bin/env python3` → Python, `#!/bin/bash` → Shell
+fn language_from_shebang(line: &str) -> Option<&'static str> {
+    let command = line.strip_prefix("#!")?.trim();
+    let mut parts = command.split_whitespace();
+    let mut program = parts.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = parts.find(|arg| !arg.starts_with('-'))?;
+    }
+    // python3.12 -> python, nodejs -> node
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let program = program.strip_suffix("js").unwrap_or(program);
+
+    INTERPRETERS
+        .iter()
+        .find(|(name, _)| *name == program)
+        .map(|(_, language)| *language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language_by_extension_and_name() {
+        assert_eq!(detect_language("src/main.rs", None), "Rust");
+        assert_eq!(detect_language("web/App.TSX", None), "TypeScript");
+        assert_eq!(detect_language("docker/Dockerfile", None), "Dockerfile");
+        assert_eq!(detect_language("README", None), UNKNOWN_LANGUAGE);
+        assert_eq!(detect_language(".gitignore", None), UNKNOWN_LANGUAGE);
+    }
+
+    #[test]
+    fn test_detect_language_by_shebang() {
+        assert_eq!(
+            detect_language("bin/deploy", Some("#!/usr/bin/env python3.12\n")),
+            "Python"
+        );
+        assert_eq!(
+            detect_language("scripts/run", Some("#!/bin/bash -e")),
+            "Shell"
+        );
+        assert_eq!(
+            detect_language("tool", Some("#!/usr/bin/env -S nodejs --no-warnings")),
+            "JavaScript"
+        );
+        assert_eq!(detect_language("notes", Some("hello")), UNKNOWN_LANGUAGE);
+        // Extension wins over shebang
+        assert_eq!(detect_language("x.rb", Some("#!/bin/sh")), "Ruby");
+    }
+}
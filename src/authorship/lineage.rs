@@ -0,0 +1,352 @@
+//! The chain of events behind a hunk: every commit in `git log -L` history that wrote
+//! one of its lines, with the attribution that commit's note gives those lines, and,
+//! for AI lines carried over by a squash or rebase, the commits whose notes first
+//! recorded the agent session. Everything is reconstructed from notes, so commits
+//! without one show up as unattributed.
+
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::error::GitAiError;
+use crate::git::refs::{get_authorship, grep_ai_notes};
+use crate::git::repository::{Repository, exec_git};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineageEventKind {
+    /// A commit outside the hunk's history (typically on a since-merged branch) whose
+    /// note recorded an agent session that a later squash or rebase carried into it
+    AiCheckpoint,
+    /// Wrote lines, at least one of them attributed to an agent
+    AiEdit,
+    /// Wrote lines, all of them human
+    HumanEdit,
+    /// Carried AI lines over from several commits, or from one it doesn't replay
+    Squash,
+    /// Carried AI lines over from one commit with the same author date and message
+    Rebase,
+    /// Wrote lines but has no authorship note
+    Unattributed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineageAgent {
+    pub prompt: String,
+    pub tool: String,
+    pub model: String,
+    pub session: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub human_author: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageEvent {
+    pub kind: LineageEventKind,
+    pub commit: String,
+    pub summary: String,
+    pub author: String,
+    pub author_time: i64,
+    /// Path of the file in this commit
+    pub file: String,
+    /// Lines of the hunk this commit wrote, numbered as in this commit
+    pub lines: Vec<u32>,
+    pub ai_lines: u32,
+    pub human_lines: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<LineageAgent>,
+    /// For squashes and rebases, the commits the AI lines were carried over from
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rewritten_from: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HunkLineage {
+    pub file: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub rev: String,
+    /// Oldest first
+    pub events: Vec<LineageEvent>,
+}
+
+/// A commit in the hunk's `git log -L` history and the hunk lines it added
+#[derive(Debug, Clone, PartialEq)]
+struct HunkCommit {
+    sha: String,
+    path: String,
+    added_lines: Vec<u32>,
+}
+
+/// Reconstruct the lineage of lines `start_line..=end_line` of `file` as of `rev`
+pub fn hunk_lineage(
+    repo: &Repository,
+    file: &str,
+    start_line: u32,
+    end_line: u32,
+    rev: &str,
+) -> Result<HunkLineage, GitAiError> {
+    if start_line == 0 || end_line < start_line {
+        return Err(GitAiError::Generic(format!(
+            "Invalid line range {}-{}",
+            start_line, end_line
+        )));
+    }
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "log".to_string(),
+        "--no-color".to_string(),
+        "--format=%x00%H".to_string(),
+        format!("-L{},{}:{}", start_line, end_line, file),
+        rev.to_string(),
+    ]);
+    let output = exec_git(&args)?;
+    let history = parse_line_log(&String::from_utf8_lossy(&output.stdout));
+
+    let mut events = Vec::new();
+    let mut seen_origins = BTreeSet::new();
+    let mut foreign_prompts_cache = HashMap::new();
+    // Oldest first, so origins land before the rewrite that carried them over
+    for hunk_commit in history.iter().rev() {
+        let mut event = event_for_commit(repo, &hunk_commit.sha, &hunk_commit.path);
+        event.lines = hunk_commit.added_lines.clone();
+        let Some(log) = get_authorship(repo, &hunk_commit.sha) else {
+            event.kind = LineageEventKind::Unattributed;
+            events.push(event);
+            continue;
+        };
+
+        let mut agents: BTreeMap<String, LineageAgent> = BTreeMap::new();
+        for line in &hunk_commit.added_lines {
+            match log.get_line_attribution(
+                repo,
+                &hunk_commit.path,
+                *line,
+                &mut foreign_prompts_cache,
+            ) {
+                Some((_, Some(hash), Some(prompt))) => {
+                    event.ai_lines += 1;
+                    agents.entry(hash.clone()).or_insert_with(|| LineageAgent {
+                        prompt: hash,
+                        tool: prompt.agent_id.tool,
+                        model: prompt.agent_id.model,
+                        session: prompt.agent_id.id,
+                        human_author: prompt.human_author,
+                    });
+                }
+                _ => event.human_lines += 1,
+            }
+        }
+        event.agents = agents.into_values().collect();
+        event.kind = if event.ai_lines > 0 {
+            LineageEventKind::AiEdit
+        } else {
+            LineageEventKind::HumanEdit
+        };
+
+        let origins = rewrite_origins(repo, &hunk_commit.sha, &event.agents);
+        if !origins.is_empty() {
+            // Rebases and cherry-picks keep the author date and message; squashes don't
+            let replayed = origins.len() == 1 && {
+                let origin = event_for_commit(repo, &origins[0].0, "");
+                origin.author_time == event.author_time && origin.summary == event.summary
+            };
+            event.kind = if replayed {
+                LineageEventKind::Rebase
+            } else {
+                LineageEventKind::Squash
+            };
+            event.rewritten_from = origins.iter().map(|(sha, _)| sha.clone()).collect();
+            for (sha, origin_log) in origins {
+                if seen_origins.insert(sha.clone()) {
+                    events.push(origin_event(repo, &sha, &origin_log, &event.agents));
+                }
+            }
+        }
+        events.push(event);
+    }
+    events.sort_by_key(|event| event.author_time);
+
+    Ok(HunkLineage {
+        file: file.to_string(),
+        start_line,
+        end_line,
+        rev: rev.to_string(),
+        events,
+    })
+}
+
+/// Parse `git log -L --format=%x00%H` output into commits and the lines each added
+fn parse_line_log(output: &str) -> Vec<HunkCommit> {
+    let mut commits: Vec<HunkCommit> = Vec::new();
+    let mut new_line = 0u32;
+    for line in output.lines() {
+        if let Some(sha) = line.strip_prefix('\0') {
+            commits.push(HunkCommit {
+                sha: sha.trim().to_string(),
+                path: String::new(),
+                added_lines: Vec::new(),
+            });
+            continue;
+        }
+        let Some(commit) = commits.last_mut() else {
+            continue;
+        };
+        if let Some(path) = line.strip_prefix("+++ b/") {
+            commit.path = path.to_string();
+        } else if line.starts_with("+++ ") || line.starts_with("--- ") {
+            continue;
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            // `-a,b +c,d @@`: new-side lines start at c
+            new_line = header
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse().ok())
+                .unwrap_or(0);
+        } else if line.starts_with('+') {
+            commit.added_lines.push(new_line);
+            new_line += 1;
+        } else if line.starts_with(' ') {
+            new_line += 1;
+        }
+    }
+    commits
+}
+
+/// Commits outside `sha`'s ancestry whose notes recorded the same agent sessions: the
+/// branch commits a squash or rebase rewrote into `sha`
+fn rewrite_origins(
+    repo: &Repository,
+    sha: &str,
+    agents: &[LineageAgent],
+) -> Vec<(String, AuthorshipLog)> {
+    let mut origins: BTreeMap<String, AuthorshipLog> = BTreeMap::new();
+    for agent in agents {
+        let candidates = grep_ai_notes(repo, &format!("\"{}\"", agent.prompt)).unwrap_or_default();
+        for candidate in candidates {
+            if candidate == sha
+                || origins.contains_key(&candidate)
+                || repo.is_ancestor(&candidate, sha)
+                || repo.is_ancestor(sha, &candidate)
+            {
+                continue;
+            }
+            let Some(log) = get_authorship(repo, &candidate) else {
+                continue;
+            };
+            let records_session = log.attestations.iter().any(|attestation| {
+                attestation
+                    .entries
+                    .iter()
+                    .any(|entry| entry.hash == agent.prompt)
+            });
+            if records_session {
+                origins.insert(candidate, log);
+            }
+        }
+    }
+    let mut origins: Vec<(String, AuthorshipLog)> = origins.into_iter().collect();
+    origins.sort_by_key(|(sha, _)| event_for_commit(repo, sha, "").author_time);
+    origins
+}
+
+/// The checkpointed lines of `agents`' sessions in an origin commit's note
+fn origin_event(
+    repo: &Repository,
+    sha: &str,
+    log: &AuthorshipLog,
+    agents: &[LineageAgent],
+) -> LineageEvent {
+    let mut event = event_for_commit(repo, sha, "");
+    event.kind = LineageEventKind::AiCheckpoint;
+    let mut lines = BTreeSet::new();
+    let mut recorded = BTreeSet::new();
+    for attestation in &log.attestations {
+        for entry in &attestation.entries {
+            if !agents.iter().any(|agent| agent.prompt == entry.hash) {
+                continue;
+            }
+            if event.file.is_empty() {
+                event.file = attestation.file_path.clone();
+            }
+            recorded.insert(entry.hash.clone());
+            if attestation.file_path != event.file {
+                continue;
+            }
+            for range in &entry.line_ranges {
+                match range {
+                    LineRange::Single(line) => {
+                        lines.insert(*line);
+                    }
+                    LineRange::Range(start, end) => lines.extend(*start..=*end),
+                }
+            }
+        }
+    }
+    event.lines = lines.into_iter().collect();
+    event.ai_lines = event.lines.len() as u32;
+    event.agents = agents
+        .iter()
+        .filter(|agent| recorded.contains(&agent.prompt))
+        .cloned()
+        .collect();
+    event
+}
+
+/// An event with the commit's metadata filled in. Origin commits may have been garbage
+/// collected once their branch was deleted, leaving only the note.
+fn event_for_commit(repo: &Repository, sha: &str, file: &str) -> LineageEvent {
+    let commit = repo.find_commit(sha.to_string()).ok();
+    let author = commit.as_ref().and_then(|c| c.author().ok());
+    LineageEvent {
+        kind: LineageEventKind::HumanEdit,
+        commit: sha.to_string(),
+        summary: commit
+            .as_ref()
+            .and_then(|c| c.summary().ok())
+            .unwrap_or_default(),
+        author: author
+            .as_ref()
+            .map(|a| {
+                format!(
+                    "{} <{}>",
+                    a.name().unwrap_or_default(),
+                    a.email().unwrap_or_default()
+                )
+            })
+            .unwrap_or_default(),
+        author_time: author.as_ref().map(|a| a.when().seconds()).unwrap_or(0),
+        file: file.to_string(),
+        lines: Vec::new(),
+        ai_lines: 0,
+        human_lines: 0,
+        agents: Vec::new(),
+        rewritten_from: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_log_tracks_added_lines_and_renames() {
+        let output = "\0bbbb\n\ndiff --git a/old.rs b/new.rs\n--- a/old.rs\n+++ b/new.rs\n@@ -2,2 +2,3 @@\n-b\n+B\n c\n+d\n\0aaaa\n\ndiff --git a/old.rs b/old.rs\n--- /dev/null\n+++ b/old.rs\n@@ -0,0 +2,2 @@\n+b\n+c\n";
+        assert_eq!(
+            parse_line_log(output),
+            vec![
+                HunkCommit {
+                    sha: "bbbb".to_string(),
+                    path: "new.rs".to_string(),
+                    added_lines: vec![2, 4],
+                },
+                HunkCommit {
+                    sha: "aaaa".to_string(),
+                    path: "old.rs".to_string(),
+                    added_lines: vec![2, 3],
+                },
+            ]
+        );
+    }
+}
@@ -1,17 +1,35 @@
+pub mod attribution_inheritance;
 pub mod attribution_tracker;
 pub mod authorship_log;
 pub mod authorship_log_serialization;
+pub mod blame_index;
+pub mod branch_compare;
+pub mod code_kind;
+pub mod commit_class;
 pub mod diff_ai_accepted;
+pub mod file_attribution;
+pub mod heatmap;
+pub mod identity_map;
 pub mod imara_diff_utils;
 pub mod internal_db;
+pub mod language;
+pub mod lineage;
 pub mod move_detection;
+pub mod neutral_commits;
+pub mod note_delta;
+pub mod note_details;
+pub mod packages;
 pub mod post_commit;
 pub mod pre_commit;
 pub mod prompt_utils;
 pub mod range_authorship;
 pub mod rebase_authorship;
+pub mod report;
 pub mod secrets;
+pub mod security_tags;
+pub mod snapshot;
 pub mod stats;
 pub mod transcript;
 pub mod virtual_attribution;
+pub mod work_item;
 pub mod working_log;
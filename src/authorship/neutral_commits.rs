@@ -0,0 +1,134 @@
+//! Attribution-neutral commits: repo-wide formatter runs (`cargo fmt`, prettier) that
+//! touch thousands of lines without writing any of them. Blame looks through them to
+//! the commit that last really changed each line, and stats count them as neither AI
+//! nor human. Commits are neutral when listed in `attribution_neutral_commits`, or, with
+//! `neutral_whitespace_commits` on, when their diff only changes whitespace.
+
+use crate::config::Config;
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git};
+use std::collections::HashMap;
+
+/// Memoized neutrality checks against the config's rules
+pub struct NeutralCommits<'a> {
+    repo: &'a Repository,
+    listed: Vec<String>,
+    whitespace_only: bool,
+    cache: HashMap<String, bool>,
+}
+
+impl<'a> NeutralCommits<'a> {
+    pub fn from_config(repo: &'a Repository) -> Self {
+        let config = Config::for_repo(repo);
+        Self::new(
+            repo,
+            config.attribution_neutral_commits().to_vec(),
+            config.neutral_whitespace_commits(),
+        )
+    }
+
+    pub fn new(repo: &'a Repository, listed: Vec<String>, whitespace_only: bool) -> Self {
+        NeutralCommits {
+            repo,
+            listed: listed
+                .into_iter()
+                .map(|sha| sha.trim().to_lowercase())
+                .filter(|sha| !sha.is_empty())
+                .collect(),
+            whitespace_only,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Whether any commit can be neutral; callers skip the checks entirely when not
+    pub fn is_enabled(&self) -> bool {
+        self.whitespace_only || !self.listed.is_empty()
+    }
+
+    pub fn is_neutral(&mut self, sha: &str) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        if let Some(neutral) = self.cache.get(sha) {
+            return *neutral;
+        }
+        let neutral = self
+            .listed
+            .iter()
+            .any(|listed| sha.starts_with(listed.as_str()))
+            || (self.whitespace_only && is_whitespace_only_commit(self.repo, sha).unwrap_or(false));
+        self.cache.insert(sha.to_string(), neutral);
+        neutral
+    }
+}
+
+/// Whether every file `sha` modifies has the same content as in its parent once
+/// whitespace is removed. Re-wrapped lines count as whitespace changes; added, deleted
+/// and renamed files, merges and root commits don't.
+pub fn is_whitespace_only_commit(repo: &Repository, sha: &str) -> Result<bool, GitAiError> {
+    let commit = repo.find_commit(sha.to_string())?;
+    if commit.parent_count()? != 1 {
+        return Ok(false);
+    }
+    let parent = commit.parent(0)?.id();
+
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "diff-tree".to_string(),
+        "-r".to_string(),
+        "--no-renames".to_string(),
+        "--raw".to_string(),
+        "--no-abbrev".to_string(),
+        parent,
+        sha.to_string(),
+    ]);
+    let output = exec_git(&args)?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut changed = false;
+    for line in stdout.lines() {
+        // ":100644 100644 <old blob> <new blob> M\t<path>"
+        let Some(meta) = line.strip_prefix(':').and_then(|l| l.split('\t').next()) else {
+            continue;
+        };
+        let fields: Vec<&str> = meta.split_whitespace().collect();
+        if fields.len() < 5 || fields[4] != "M" {
+            return Ok(false);
+        }
+        let old = repo.find_blob(fields[2].to_string())?.content()?;
+        let new = repo.find_blob(fields[3].to_string())?.content()?;
+        if !same_ignoring_whitespace(&old, &new) {
+            return Ok(false);
+        }
+        changed = true;
+    }
+    Ok(changed)
+}
+
+fn same_ignoring_whitespace(a: &[u8], b: &[u8]) -> bool {
+    let non_whitespace = |bytes: &[u8]| -> Vec<u8> {
+        bytes
+            .iter()
+            .copied()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect()
+    };
+    non_whitespace(a) == non_whitespace(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_ignoring_whitespace() {
+        assert!(same_ignoring_whitespace(
+            b"fn main() { call(a, b); }\n",
+            b"fn main() {\n    call(a, b);\n}\n"
+        ));
+        assert!(!same_ignoring_whitespace(
+            b"call(a, b);\n",
+            b"call(a, b,);\n"
+        ));
+    }
+}
@@ -0,0 +1,283 @@
+//! Delta-encoded notes. With `note_delta_interval` (or `GIT_AI_NOTE_DELTA_INTERVAL`) set,
+//! a note leaves out the prompt records its commit's first parent's note already has:
+//! prompts that didn't change are listed by ID, and a prompt whose transcript only grew
+//! keeps just the new messages. Files whose line attributions match the parent's are
+//! left out the same way. Every `note_delta_interval`th note along a chain is written in
+//! full, so reading one never goes back further than that.
+//!
+//! A delta records the blob of the parent's note it was encoded against; if that note
+//! has since been rewritten, what the delta left out can't be recovered and resolving it
+//! fails. Notes read through [`crate::git::refs`] come back with the delta resolved, or
+//! as stored, with `delta` still set to mark what's missing, when it can't be.
+
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::error::GitAiError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What a delta-encoded note left out, and which note has it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteDelta {
+    /// Commit whose note this one is relative to
+    pub parent: String,
+    /// Blob of the parent's note on refs/notes/ai this one was encoded against
+    pub parent_note: String,
+    /// Deltas since the last full note, this one included
+    pub depth: u32,
+    /// Prompts left out because the parent's note has them as they are
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unchanged: Vec<String>,
+    /// Messages left off the start of a prompt's transcript because the parent's note has them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub messages_from: BTreeMap<String, usize>,
+    /// Files left out because the parent's note attributes them the same, with where
+    /// each goes among this note's files
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub unchanged_files: BTreeMap<String, usize>,
+}
+
+/// Leave out of `log` what `parent`'s note (resolved, stored as blob `parent_note`)
+/// already has, with `log` stored `depth` deltas from a full note. False when there's
+/// nothing to leave out.
+pub fn encode(
+    log: &mut AuthorshipLog,
+    parent_sha: &str,
+    parent_note: &str,
+    parent: &AuthorshipLog,
+    depth: u32,
+) -> bool {
+    let mut delta = NoteDelta {
+        parent: parent_sha.to_string(),
+        parent_note: parent_note.to_string(),
+        depth,
+        unchanged: Vec::new(),
+        messages_from: BTreeMap::new(),
+        unchanged_files: BTreeMap::new(),
+    };
+    for (index, file) in log.attestations.iter().enumerate() {
+        if parent.attestations.contains(file) {
+            delta.unchanged_files.insert(file.file_path.clone(), index);
+        }
+    }
+    log.attestations
+        .retain(|file| !delta.unchanged_files.contains_key(&file.file_path));
+    for (id, previous) in &parent.metadata.prompts {
+        let Some(record) = log.metadata.prompts.get_mut(id) else {
+            continue;
+        };
+        if record == previous {
+            log.metadata.prompts.remove(id);
+            delta.unchanged.push(id.clone());
+        } else if !previous.messages.is_empty() && record.messages.starts_with(&previous.messages) {
+            record.messages.drain(..previous.messages.len());
+            delta
+                .messages_from
+                .insert(id.clone(), previous.messages.len());
+        }
+    }
+    if delta.unchanged.is_empty()
+        && delta.messages_from.is_empty()
+        && delta.unchanged_files.is_empty()
+    {
+        return false;
+    }
+    log.metadata.delta = Some(Box::new(delta));
+    // Older readers would find prompts missing
+    log.metadata.require_current_reader();
+    true
+}
+
+/// Put back into `log` what its delta left out, from its parent's note (resolved), given
+/// with the blob it's stored as. Without the parent's note what was left out stays
+/// missing. Fails, leaving `log` as it was, if the parent's note isn't the one the delta
+/// was encoded against.
+pub fn resolve(
+    log: &mut AuthorshipLog,
+    parent: Option<(&str, &AuthorshipLog)>,
+) -> Result<(), GitAiError> {
+    let Some(delta) = log.metadata.delta.as_ref() else {
+        return Ok(());
+    };
+    if let Some((parent_note, _)) = parent
+        && parent_note != delta.parent_note
+    {
+        return Err(GitAiError::Generic(format!(
+            "the note of {} was rewritten after a delta against it was written (expected blob {}, found {})",
+            delta.parent, delta.parent_note, parent_note
+        )));
+    }
+    let Some(delta) = log.metadata.delta.take() else {
+        return Ok(());
+    };
+    let Some((_, parent)) = parent else {
+        return Ok(());
+    };
+    // Ascending positions, so each lands where it was
+    let mut unchanged_files: Vec<(usize, &String)> = delta
+        .unchanged_files
+        .iter()
+        .map(|(path, index)| (*index, path))
+        .collect();
+    unchanged_files.sort();
+    for (index, path) in unchanged_files {
+        if let Some(file) = parent.attestations.iter().find(|f| &f.file_path == path) {
+            log.attestations
+                .insert(index.min(log.attestations.len()), file.clone());
+        }
+    }
+    for id in &delta.unchanged {
+        if let Some(record) = parent.metadata.prompts.get(id) {
+            log.metadata.prompts.insert(id.clone(), record.clone());
+        }
+    }
+    for (id, from) in &delta.messages_from {
+        if let (Some(record), Some(previous)) = (
+            log.metadata.prompts.get_mut(id),
+            parent.metadata.prompts.get(id),
+        ) {
+            let mut messages = previous.messages[..(*from).min(previous.messages.len())].to_vec();
+            messages.append(&mut record.messages);
+            record.messages = messages;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `content` is a delta-encoded note
+pub fn is_delta(content: &str) -> bool {
+    // Cheap check first; most notes aren't deltas
+    content.contains("\"delta\"")
+        && AuthorshipLog::deserialize_from_string(content)
+            .is_ok_and(|log| log.metadata.delta.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::authorship_log::{LineRange, PromptRecord};
+    use crate::authorship::authorship_log_serialization::{AttestationEntry, FileAttestation};
+    use crate::authorship::transcript::Message;
+    use crate::authorship::working_log::AgentId;
+
+    fn prompt(id: &str, messages: &[&str]) -> PromptRecord {
+        PromptRecord {
+            agent_id: AgentId {
+                tool: "mock_ai".to_string(),
+                id: id.to_string(),
+                model: "unknown".to_string(),
+            },
+            human_author: None,
+            messages: messages
+                .iter()
+                .map(|text| Message::User {
+                    text: text.to_string(),
+                    timestamp: None,
+                })
+                .collect(),
+            total_additions: 1,
+            total_deletions: 0,
+            accepted_lines: 1,
+            overriden_lines: 0,
+            messages_url: None,
+        }
+    }
+
+    #[test]
+    fn test_encode_leaves_out_parent_prompts_and_resolve_restores_them() {
+        let mut parent = AuthorshipLog::new();
+        parent
+            .metadata
+            .prompts
+            .insert("same".to_string(), prompt("same", &["hi"]));
+        parent
+            .metadata
+            .prompts
+            .insert("grown".to_string(), prompt("grown", &["one"]));
+        let mut log = AuthorshipLog::new();
+        log.metadata
+            .prompts
+            .insert("same".to_string(), prompt("same", &["hi"]));
+        log.metadata
+            .prompts
+            .insert("grown".to_string(), prompt("grown", &["one", "two"]));
+        log.metadata
+            .prompts
+            .insert("new".to_string(), prompt("new", &["fresh"]));
+        let original = log.clone();
+
+        assert!(encode(&mut log, "abc123", "note-blob", &parent, 1));
+        let delta = log.metadata.delta.as_ref().unwrap();
+        assert_eq!(delta.parent, "abc123");
+        assert_eq!(delta.parent_note, "note-blob");
+        assert_eq!(delta.unchanged, vec!["same"]);
+        assert_eq!(delta.messages_from.get("grown"), Some(&1));
+        assert!(!log.metadata.prompts.contains_key("same"));
+        assert_eq!(log.metadata.prompts["grown"].messages.len(), 1);
+        let content = log.serialize_to_string().unwrap();
+        assert!(is_delta(&content));
+
+        let mut decoded = AuthorshipLog::deserialize_from_string(&content).unwrap();
+        resolve(&mut decoded, Some(("note-blob", &parent))).unwrap();
+        assert_eq!(decoded.metadata.prompts, original.metadata.prompts);
+        assert!(decoded.metadata.delta.is_none());
+
+        // Nothing in common with the parent
+        let mut unrelated = AuthorshipLog::new();
+        unrelated
+            .metadata
+            .prompts
+            .insert("other".to_string(), prompt("other", &[]));
+        assert!(!encode(&mut unrelated, "abc123", "note-blob", &parent, 1));
+        assert!(unrelated.metadata.delta.is_none());
+    }
+
+    fn file(path: &str, hash: &str, lines: LineRange) -> FileAttestation {
+        let mut file = FileAttestation::new(path.to_string());
+        file.add_entry(AttestationEntry::new(hash.to_string(), vec![lines]));
+        file
+    }
+
+    #[test]
+    fn test_encode_leaves_out_parent_attestations_and_resolve_restores_their_order() {
+        let mut parent = AuthorshipLog::new();
+        parent.attestations = vec![
+            file("a.rs", "h1", LineRange::Range(1, 10)),
+            file("b.rs", "h1", LineRange::Single(3)),
+        ];
+        let mut log = AuthorshipLog::new();
+        log.attestations = vec![
+            file("a.rs", "h1", LineRange::Range(1, 10)),
+            file("new.rs", "h2", LineRange::Single(1)),
+            file("b.rs", "h1", LineRange::Single(4)),
+        ];
+        let original = log.clone();
+
+        assert!(encode(&mut log, "abc123", "note-blob", &parent, 1));
+        let delta = log.metadata.delta.as_ref().unwrap();
+        assert_eq!(delta.unchanged_files.get("a.rs"), Some(&0));
+        assert_eq!(log.attestations.len(), 2);
+        assert_eq!(log.attestations[0].file_path, "new.rs");
+
+        let content = log.serialize_to_string().unwrap();
+        let mut decoded = AuthorshipLog::deserialize_from_string(&content).unwrap();
+        resolve(&mut decoded, Some(("note-blob", &parent))).unwrap();
+        assert_eq!(decoded.attestations, original.attestations);
+    }
+
+    #[test]
+    fn test_resolve_fails_when_the_parent_note_was_rewritten() {
+        let mut parent = AuthorshipLog::new();
+        parent.attestations = vec![file("a.rs", "h1", LineRange::Range(1, 10))];
+        let mut log = parent.clone();
+        log.attestations
+            .push(file("b.rs", "h1", LineRange::Single(1)));
+        assert!(encode(&mut log, "abc123", "note-blob", &parent, 1));
+
+        let mut rewritten = parent.clone();
+        rewritten.attestations[0] = file("a.rs", "h1", LineRange::Range(1, 2));
+        let mut unresolved = log.clone();
+        let error = resolve(&mut unresolved, Some(("other-blob", &rewritten))).unwrap_err();
+        assert!(error.to_string().contains("rewritten"), "{}", error);
+        assert_eq!(unresolved, log);
+    }
+}
@@ -0,0 +1,103 @@
+//! Size-tiered note storage. With `note_details_threshold` (or
+//! `GIT_AI_NOTE_DETAILS_THRESHOLD`) set, a note bigger than that many bytes is stored as
+//! a summary on `refs/notes/ai`, which every clone fetches, and in full on
+//! `refs/notes/ai-details`, which is only fetched when something needs the line maps.
+//!
+//! The summary keeps the metadata, so prompts, stats, classification and provenance read
+//! from it as before, and lists the attributed files in place of the attestations.
+//! Blame, show and anything else that reads a note through [`crate::git::refs`] gets the
+//! full note back.
+
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use serde::{Deserialize, Serialize};
+
+/// Where a summary's line maps went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteDetails {
+    /// Files the full note attests to
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Size of the full note in bytes
+    pub size: u64,
+}
+
+/// The summary to store in place of `content`, None when `content` isn't an authorship
+/// log or the summary wouldn't be smaller
+pub fn summarize(content: &str) -> Option<String> {
+    let log = AuthorshipLog::deserialize_from_string(content).ok()?;
+    if log.metadata.details.is_some() {
+        return None;
+    }
+    // A delta's files include those it left out for its parent's note to supply
+    let unchanged_files = log
+        .metadata
+        .delta
+        .iter()
+        .flat_map(|delta| delta.unchanged_files.keys().cloned())
+        .collect::<Vec<_>>();
+    let mut summary = AuthorshipLog {
+        attestations: Vec::new(),
+        metadata: log.metadata,
+    };
+    summary.metadata.details = Some(Box::new(NoteDetails {
+        files: log
+            .attestations
+            .into_iter()
+            .map(|attestation| attestation.file_path)
+            .chain(unchanged_files)
+            .collect(),
+        size: content.len() as u64,
+    }));
+    // Older readers would take the summary for a note without AI lines
+    summary.metadata.require_current_reader();
+    let summary = summary.serialize_to_string().ok()?;
+    (summary.len() < content.len()).then_some(summary)
+}
+
+/// Whether `content` is a summary whose full note is on the details ref
+pub fn is_summary(content: &str) -> bool {
+    // Cheap check first; most notes aren't summaries
+    content.contains("\"details\"")
+        && AuthorshipLog::deserialize_from_string(content)
+            .is_ok_and(|log| log.metadata.details.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::authorship_log::LineRange;
+    use crate::authorship::authorship_log_serialization::{AttestationEntry, FileAttestation};
+
+    #[test]
+    fn test_summarize_keeps_metadata_and_lists_files() {
+        let mut log = AuthorshipLog::new();
+        log.metadata.work_item = Some("ENG-42".to_string());
+        for path in ["src/a.rs", "src/b.rs"] {
+            let mut file = FileAttestation::new(path.to_string());
+            file.add_entry(AttestationEntry::new(
+                "abcdef1".to_string(),
+                (1..200).step_by(2).map(LineRange::Single).collect(),
+            ));
+            log.attestations.push(file);
+        }
+        let content = log.serialize_to_string().unwrap();
+
+        let summary = summarize(&content).expect("a smaller summary");
+        assert!(is_summary(&summary));
+        assert!(!is_summary(&content));
+        let parsed = AuthorshipLog::deserialize_from_string(&summary).unwrap();
+        assert!(parsed.attestations.is_empty());
+        assert_eq!(parsed.metadata.work_item.as_deref(), Some("ENG-42"));
+        let details = parsed.metadata.details.unwrap();
+        assert_eq!(details.files, vec!["src/a.rs", "src/b.rs"]);
+        assert_eq!(details.size, content.len() as u64);
+        assert_eq!(
+            parsed.metadata.min_reader_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+
+        // Already a summary, or not a note at all
+        assert_eq!(summarize(&summary), None);
+        assert_eq!(summarize("not a note"), None);
+    }
+}
@@ -0,0 +1,321 @@
+use std::path::Path;
+
+/// Package label for files that don't belong to any workspace member
+pub const ROOT_PACKAGE: &str = "(root)";
+
+/// Workspace members discovered from monorepo manifests in a repository root.
+///
+/// Supported manifests:
+/// - `Cargo.toml` (`[workspace] members`/`exclude`)
+/// - `pnpm-workspace.yaml` (`packages`)
+/// - `package.json` (`workspaces`, as an array or `{ "packages": [...] }`, used by npm/yarn)
+/// - `go.work` (`use` directives)
+///
+/// Members are identified by their directory relative to the repository root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspacePackages {
+    /// Member directories, longest first so nested packages win
+    dirs: Vec<String>,
+}
+
+impl WorkspacePackages {
+    pub fn detect(root: &Path) -> Self {
+        let mut includes = Vec::new();
+        let mut excludes = Vec::new();
+
+        if let Ok(content) = std::fs::read_to_string(root.join("Cargo.toml")) {
+            let (members, exclude) = parse_cargo_workspace(&content);
+            includes.extend(members);
+            excludes.extend(exclude);
+        }
+        if let Ok(content) = std::fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+            split_negations(parse_pnpm_workspace(&content), &mut includes, &mut excludes);
+        }
+        if let Ok(content) = std::fs::read_to_string(root.join("package.json")) {
+            split_negations(
+                parse_package_json_workspaces(&content),
+                &mut includes,
+                &mut excludes,
+            );
+        }
+        if let Ok(content) = std::fs::read_to_string(root.join("go.work")) {
+            includes.extend(parse_go_work(&content));
+        }
+
+        let excluded: Vec<String> = excludes
+            .iter()
+            .flat_map(|pattern| expand_member_pattern(root, pattern))
+            .collect();
+        let dirs: Vec<String> = includes
+            .iter()
+            .flat_map(|pattern| expand_member_pattern(root, pattern))
+            .filter(|dir| !excluded.contains(dir))
+            .collect();
+
+        Self::from_dirs(dirs)
+    }
+
+    pub fn from_dirs(dirs: Vec<String>) -> Self {
+        let mut dirs: Vec<String> = dirs
+            .into_iter()
+            .map(|d| normalize_dir(&d))
+            .filter(|d| !d.is_empty())
+            .collect();
+        dirs.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        dirs.dedup();
+        WorkspacePackages { dirs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+
+    /// The package a repository-relative file path belongs to, or [`ROOT_PACKAGE`].
+    pub fn package_for_path(&self, path: &str) -> &str {
+        self.dirs
+            .iter()
+            .find(|dir| {
+                path.strip_prefix(dir.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(String::as_str)
+            .unwrap_or(ROOT_PACKAGE)
+    }
+}
+
+fn normalize_dir(dir: &str) -> String {
+    let dir = dir.trim().replace('\\', "/");
+    let dir = dir.strip_prefix("./").unwrap_or(&dir);
+    dir.trim_end_matches('/').to_string()
+}
+
+fn split_negations(patterns: Vec<String>, includes: &mut Vec<String>, excludes: &mut Vec<String>) {
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) => excludes.push(negated.to_string()),
+            None => includes.push(pattern),
+        }
+    }
+}
+
+/// Expand a (possibly globbed) member pattern to existing directories relative to `root`.
+fn expand_member_pattern(root: &Path, pattern: &str) -> Vec<String> {
+    let pattern = normalize_dir(pattern);
+    if pattern.is_empty() || pattern == "." {
+        return Vec::new();
+    }
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![pattern];
+    }
+
+    let full_pattern = root.join(&pattern).to_string_lossy().to_string();
+    let Ok(paths) = glob::glob(&full_pattern) else {
+        return Vec::new();
+    };
+    paths
+        .filter_map(Result::ok)
+        .filter(|p| p.is_dir())
+        .filter_map(|p| {
+            p.strip_prefix(root)
+                .ok()
+                .map(|rel| normalize_dir(&rel.to_string_lossy()))
+        })
+        .collect()
+}
+
+/// Collect the quoted strings from a TOML array value that may span several lines.
+fn toml_string_array(section: &str, key: &str) -> Vec<String> {
+    let Some(start) = section.lines().position(|line| {
+        line.trim_start()
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    }) else {
+        return Vec::new();
+    };
+
+    let mut values = Vec::new();
+    for line in section.lines().skip(start) {
+        let line = line.split('#').next().unwrap_or("");
+        let mut in_quotes = None;
+        let mut current = String::new();
+        for c in line.chars() {
+            match (in_quotes, c) {
+                (None, '"' | '\'') => in_quotes = Some(c),
+                (Some(q), c) if c == q => {
+                    values.push(std::mem::take(&mut current));
+                    in_quotes = None;
+                }
+                (Some(_), c) => current.push(c),
+                _ => {}
+            }
+        }
+        if line.contains(']') {
+            break;
+        }
+    }
+    values
+}
+
+/// Parse `[workspace] members` and `exclude` from a Cargo.toml.
+fn parse_cargo_workspace(content: &str) -> (Vec<String>, Vec<String>) {
+    let Some(start) = content.lines().position(|l| l.trim() == "[workspace]") else {
+        return (Vec::new(), Vec::new());
+    };
+    let section: Vec<&str> = content
+        .lines()
+        .skip(start + 1)
+        .take_while(|l| !l.trim_start().starts_with('['))
+        .collect();
+    let section = section.join("\n");
+    (
+        toml_string_array(&section, "members"),
+        toml_string_array(&section, "exclude"),
+    )
+}
+
+/// Parse the `packages:` list from a pnpm-workspace.yaml.
+fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed.starts_with("packages:");
+            continue;
+        }
+        if in_packages && let Some(item) = trimmed.strip_prefix('-') {
+            let item = item.split(" #").next().unwrap_or("").trim();
+            let item = item.trim_matches(|c| c == '"' || c == '\'');
+            if !item.is_empty() {
+                packages.push(item.to_string());
+            }
+        }
+    }
+    packages
+}
+
+/// Parse `workspaces` from a package.json (npm/yarn).
+fn parse_package_json_workspaces(content: &str) -> Vec<String> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let workspaces = match json.get("workspaces") {
+        Some(serde_json::Value::Array(items)) => items,
+        Some(serde_json::Value::Object(obj)) => match obj.get("packages") {
+            Some(serde_json::Value::Array(items)) => items,
+            _ => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    workspaces
+        .iter()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Parse `use` directives (single-line and block form) from a go.work file.
+fn parse_go_work(content: &str) -> Vec<String> {
+    let mut dirs = Vec::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                dirs.push(line.trim_matches('"').to_string());
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("use")
+            && rest.starts_with([' ', '\t', '('])
+        {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() {
+                dirs.push(rest.trim_matches('"').to_string());
+            }
+        }
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_workspace() {
+        let content = r#"
+[package]
+name = "root"
+
+[workspace]
+members = [
+    "crates/*", # all crates
+    'tools/cli',
+]
+exclude = ["crates/experimental"]
+
+[dependencies]
+members = ["not-a-workspace"]
+"#;
+        let (members, exclude) = parse_cargo_workspace(content);
+        assert_eq!(members, vec!["crates/*", "tools/cli"]);
+        assert_eq!(exclude, vec!["crates/experimental"]);
+    }
+
+    #[test]
+    fn test_parse_pnpm_workspace() {
+        let content = "packages:\n  - 'packages/*'\n  - \"apps/web\" # app\n  - '!**/test/**'\ncatalog:\n  - ignored\n";
+        assert_eq!(
+            parse_pnpm_workspace(content),
+            vec!["packages/*", "apps/web", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn test_parse_package_json_workspaces() {
+        assert_eq!(
+            parse_package_json_workspaces(r#"{"workspaces": ["packages/*"]}"#),
+            vec!["packages/*"]
+        );
+        assert_eq!(
+            parse_package_json_workspaces(r#"{"workspaces": {"packages": ["libs/a"]}}"#),
+            vec!["libs/a"]
+        );
+        assert!(parse_package_json_workspaces(r#"{"name": "x"}"#).is_empty());
+    }
+
+    #[test]
+    fn test_parse_go_work() {
+        let content = "go 1.22\n\nuse (\n\t./api\n\t./worker // jobs\n)\nuse ./tools\n";
+        assert_eq!(parse_go_work(content), vec!["./api", "./worker", "./tools"]);
+    }
+
+    #[test]
+    fn test_package_for_path_prefers_nested_packages() {
+        let packages = WorkspacePackages::from_dirs(vec![
+            "./packages/ui".to_string(),
+            "packages/ui/icons/".to_string(),
+            "services/api".to_string(),
+        ]);
+        assert_eq!(
+            packages.package_for_path("packages/ui/src/button.tsx"),
+            "packages/ui"
+        );
+        assert_eq!(
+            packages.package_for_path("packages/ui/icons/star.svg"),
+            "packages/ui/icons"
+        );
+        assert_eq!(
+            packages.package_for_path("services/api-gateway/main.go"),
+            ROOT_PACKAGE
+        );
+        assert_eq!(packages.package_for_path("README.md"), ROOT_PACKAGE);
+    }
+}
@@ -1,9 +1,12 @@
 use crate::api::{ApiClient, ApiContext};
 use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::authorship::commit_class::{ClassThresholds, count_ai_added_lines};
 use crate::authorship::prompt_utils::{PromptUpdateResult, update_prompt_from_tool};
 use crate::authorship::secrets::{redact_secrets_from_prompts, strip_prompt_messages};
+use crate::authorship::security_tags::tag_security_findings;
 use crate::authorship::stats::{stats_for_commit_stats, write_stats_to_terminal};
 use crate::authorship::virtual_attribution::VirtualAttributions;
+use crate::authorship::work_item::resolve_work_item;
 use crate::authorship::working_log::{Checkpoint, CheckpointKind};
 use crate::config::{Config, PromptStorageMode};
 use crate::error::GitAiError;
@@ -21,6 +24,8 @@ const STATS_SKIP_MAX_ADDED_LINES: usize = 6000;
 /// Skip expensive stats for extremely wide commits touching many added-line files.
 const STATS_SKIP_MAX_FILES_WITH_ADDITIONS: usize = 200;
 
+const EMPTY_TREE_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 #[derive(Debug, Clone, Copy)]
 struct StatsCostEstimate {
     files_with_additions: usize,
@@ -43,6 +48,13 @@ pub fn post_commit(
     let repo_storage = &repo.storage;
     let working_log = repo_storage.working_log_for_base_commit(&parent_sha);
 
+    // `--allow-empty` and mode-only commits carry no lines, so none of the pending
+    // attribution belongs to them. Record an empty note and hand the working log over
+    // to the new HEAD untouched.
+    if !commit_changes_content(repo, &parent_sha, &commit_sha)? {
+        return write_empty_commit_note(repo, &parent_sha, &commit_sha);
+    }
+
     // Pull all working log entries from the parent commit
 
     let mut parent_working_log = working_log.read_all_checkpoints()?;
@@ -108,6 +120,20 @@ pub fn post_commit(
         )?;
 
     authorship_log.metadata.base_commit_sha = commit_sha.clone();
+    // Prefer the most recent work item recorded on a checkpoint, falling back to the branch
+    authorship_log.metadata.work_item = parent_working_log
+        .iter()
+        .rev()
+        .find_map(|checkpoint| checkpoint.work_item.clone())
+        .or_else(|| resolve_work_item(repo));
+
+    // Classify the commit in the note so queries don't need to recompute line stats
+    let added_lines_by_file = repo.diff_added_lines(&parent_sha, &commit_sha, None).ok();
+    authorship_log.metadata.classification = added_lines_by_file.as_ref().map(|added| {
+        let (ai_lines, added_lines) = count_ai_added_lines(&authorship_log, added);
+        ClassThresholds::from_config(repo).classify(ai_lines, added_lines)
+    });
+    tag_security_findings(repo, &commit_sha, &mut authorship_log);
 
     // Handle prompts based on effective prompt storage mode for this repository
     // The effective mode considers include/exclude lists and fallback settings
@@ -174,18 +200,17 @@ pub fn post_commit(
     // Compute stats once (needed for both metrics and terminal output), unless preflight
     // estimate predicts this would be too expensive for the commit hook path.
     let mut stats: Option<crate::authorship::stats::CommitStats> = None;
-    let skip_reason = estimate_stats_cost(repo, &parent_sha, &commit_sha)
-        .ok()
-        .and_then(|estimate| {
-            if should_skip_expensive_post_commit_stats(&estimate) {
-                Some(estimate)
-            } else {
-                None
-            }
-        });
+    let skip_reason = added_lines_by_file
+        .as_ref()
+        .map(estimate_stats_cost)
+        .filter(should_skip_expensive_post_commit_stats);
 
     if skip_reason.is_none() {
-        let computed = stats_for_commit_stats(repo, &commit_sha, &[])?;
+        let computed = stats_for_commit_stats(
+            repo,
+            &commit_sha,
+            crate::config::Config::for_repo(repo).ignore_patterns(),
+        )?;
         // Record metrics only when we have full stats.
         record_commit_metrics(
             repo,
@@ -232,19 +257,61 @@ pub fn post_commit(
     Ok((commit_sha.to_string(), authorship_log))
 }
 
-fn should_skip_expensive_post_commit_stats(estimate: &StatsCostEstimate) -> bool {
-    estimate.hunk_ranges >= STATS_SKIP_MAX_HUNKS
-        || estimate.added_lines >= STATS_SKIP_MAX_ADDED_LINES
-        || estimate.files_with_additions >= STATS_SKIP_MAX_FILES_WITH_ADDITIONS
+/// Whether any blob differs between the parent and the new commit. Mode-only changes and
+/// `--allow-empty` commits don't count.
+fn commit_changes_content(
+    repo: &Repository,
+    parent_sha: &str,
+    commit_sha: &str,
+) -> Result<bool, GitAiError> {
+    let from = if parent_sha == "initial" {
+        EMPTY_TREE_HASH
+    } else {
+        parent_sha
+    };
+    let output = repo.git(&["diff-tree", "-r", "--no-renames", from, commit_sha])?;
+
+    // Raw format: ":<old mode> <new mode> <old blob> <new blob> <status>\t<path>"
+    Ok(output.lines().any(|line| {
+        let fields: Vec<&str> = line
+            .split('\t')
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+        fields.len() < 4 || fields[2] != fields[3]
+    }))
 }
 
-fn estimate_stats_cost(
+fn write_empty_commit_note(
     repo: &Repository,
     parent_sha: &str,
     commit_sha: &str,
-) -> Result<StatsCostEstimate, GitAiError> {
-    let added_lines_by_file = repo.diff_added_lines(parent_sha, commit_sha, None)?;
+) -> Result<(String, AuthorshipLog), GitAiError> {
+    let mut authorship_log = AuthorshipLog::new();
+    authorship_log.metadata.base_commit_sha = commit_sha.to_string();
+
+    let authorship_json = authorship_log
+        .serialize_to_string()
+        .map_err(|_| GitAiError::Generic("Failed to serialize authorship log".to_string()))?;
+    notes_add(repo, commit_sha, &authorship_json)?;
+
+    repo.storage.rename_working_log(parent_sha, commit_sha)?;
+
+    debug_log(&format!(
+        "Commit {} has no content changes, wrote empty authorship note",
+        commit_sha
+    ));
+    Ok((commit_sha.to_string(), authorship_log))
+}
 
+fn should_skip_expensive_post_commit_stats(estimate: &StatsCostEstimate) -> bool {
+    estimate.hunk_ranges >= STATS_SKIP_MAX_HUNKS
+        || estimate.added_lines >= STATS_SKIP_MAX_ADDED_LINES
+        || estimate.files_with_additions >= STATS_SKIP_MAX_FILES_WITH_ADDITIONS
+}
+
+fn estimate_stats_cost(added_lines_by_file: &HashMap<String, Vec<u32>>) -> StatsCostEstimate {
     let files_with_additions = added_lines_by_file
         .values()
         .filter(|lines| !lines.is_empty())
@@ -253,19 +320,19 @@ fn estimate_stats_cost(
     let mut added_lines = 0usize;
     let mut hunk_ranges = 0usize;
 
-    for (_file, lines) in added_lines_by_file {
+    for lines in added_lines_by_file.values() {
         if lines.is_empty() {
             continue;
         }
         added_lines += lines.len();
-        hunk_ranges += count_line_ranges(&lines);
+        hunk_ranges += count_line_ranges(lines);
     }
 
-    Ok(StatsCostEstimate {
+    StatsCostEstimate {
         files_with_additions,
         added_lines,
         hunk_ranges,
-    })
+    }
 }
 
 fn count_line_ranges(lines: &[u32]) -> usize {
@@ -447,7 +514,8 @@ fn enqueue_prompt_messages_to_cas(
     }
 
     // Get API base URL for constructing messages_url
-    let api_base_url = Config::get().api_base_url();
+    let config = Config::get();
+    let api_base_url = config.api_base_url();
 
     for (_key, prompt) in prompts.iter_mut() {
         if !prompt.messages.is_empty() {
@@ -13,6 +13,7 @@ pub fn pre_commit(repo: &Repository, default_author: String) -> Result<(), GitAi
         true,
         None,
         true, // should skip if NO AI CHECKPOINTS
+        None,
     );
     result.map(|_| ())
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -7,6 +8,7 @@ use serde::Serialize;
 use crate::authorship::diff_ai_accepted::diff_ai_accepted_stats;
 use crate::authorship::stats::{CommitStats, stats_for_commit_stats, stats_from_authorship_log};
 use crate::error::GitAiError;
+use crate::git::partial_clone::prefetch_blobs_for_range;
 use crate::git::refs::{CommitAuthorship, get_commits_with_notes_from_list};
 use crate::git::repository::{CommitRange, Repository};
 use crate::utils::debug_log;
@@ -49,8 +51,8 @@ pub struct RangeAuthorshipStats {
 pub struct RangeAuthorshipStatsData {
     pub total_commits: usize,
     pub commits_with_authorship: usize,
-    pub authors_committing_authorship: HashSet<String>,
-    pub authors_not_committing_authorship: HashSet<String>,
+    pub authors_committing_authorship: BTreeSet<String>,
+    pub authors_not_committing_authorship: BTreeSet<String>,
     pub commits_without_authorship: Vec<String>,
     pub commits_without_authorship_with_authors: Vec<(String, String)>, // (sha, git_author)
 }
@@ -176,7 +178,7 @@ pub fn range_authorship(
 
 /// Create an in-memory authorship log for a commit range by treating it as a squash
 /// Similar to rewrite_authorship_after_squash_or_rebase but tailored for ranges
-fn create_authorship_log_for_range(
+pub fn create_authorship_log_for_range(
     repo: &Repository,
     start_sha: &str,
     end_sha: &str,
@@ -219,6 +221,13 @@ fn create_authorship_log_for_range(
                     ),
                     base_commit_sha: end_sha.to_string(),
                     prompts: std::collections::BTreeMap::new(),
+                    min_reader_version: None,
+                    work_item: None,
+                    classification: None,
+                    provenance: None,
+                    security_scan: None,
+                    details: None,
+                    delta: None,
                 },
             },
         );
@@ -416,6 +425,11 @@ fn calculate_range_stats_direct(
         return stats_for_commit_stats(repo, &end_sha, ignore_patterns);
     }
 
+    // Blobless clones would otherwise fetch each blob read below on its own
+    if let Err(e) = prefetch_blobs_for_range(repo, &start_sha, &end_sha) {
+        debug_log(&format!("partial clone blob prefetch failed: {}", e));
+    }
+
     // Step 1: Get git diff stats between start and end
     let (git_diff_added_lines, git_diff_deleted_lines) =
         get_git_diff_stats_for_range(repo, &start_sha, &end_sha, ignore_patterns)?;
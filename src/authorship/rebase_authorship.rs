@@ -4,6 +4,7 @@ use crate::error::GitAiError;
 use crate::git::authorship_traversal::{
     commits_have_authorship_notes, load_ai_touched_files_for_commits,
 };
+use crate::git::commit_graph::CommitGraph;
 use crate::git::refs::get_reference_as_authorship_log_v3;
 use crate::git::repository::{CommitRange, Repository};
 use crate::git::rewrite_log::RewriteLogEvent;
@@ -270,44 +271,60 @@ pub fn rewrite_authorship_after_squash_or_rebase(
         changed_files.len()
     ));
 
-    // Step 4: Create VirtualAttributions for both branches
-    // Use merge_base to limit blame range for performance
-    let repo_clone = repo.clone();
-    let merge_base_clone = merge_base.clone();
-    let source_va = smol::block_on(async {
-        VirtualAttributions::new_for_base_commit(
-            repo_clone,
-            source_head_sha.to_string(),
-            &changed_files,
-            merge_base_clone,
-        )
-        .await
-    })?;
-
-    let repo_clone = repo.clone();
-    let target_va = smol::block_on(async {
-        VirtualAttributions::new_for_base_commit(
-            repo_clone,
-            target_branch_head_sha.clone(),
-            &changed_files,
-            merge_base,
-        )
-        .await
-    })?;
-
-    // Step 4: Read committed files from merge commit (captures final state with conflict resolutions)
-    let committed_files = get_committed_files_content(repo, merge_commit_sha, &changed_files)?;
-
-    debug_log(&format!(
-        "Read {} committed files from merge commit",
-        committed_files.len()
-    ));
+    // Steps 4-6 run over batches of files: all of them at once normally, a few at a time
+    // in low-memory mode so only one batch's contents and attributions are held
+    let mut authorship_log = AuthorshipLog::new();
+    let batch_size = crate::memory::file_batch_size(changed_files.len());
+    for batch in changed_files.chunks(batch_size) {
+        // Step 4: Create VirtualAttributions for both branches
+        // Use merge_base to limit blame range for performance
+        let repo_clone = repo.clone();
+        let merge_base_clone = merge_base.clone();
+        let source_va = smol::block_on(async {
+            VirtualAttributions::new_for_base_commit(
+                repo_clone,
+                source_head_sha.to_string(),
+                batch,
+                merge_base_clone,
+            )
+            .await
+        })?;
+
+        let repo_clone = repo.clone();
+        let merge_base_clone = merge_base.clone();
+        let target_va = smol::block_on(async {
+            VirtualAttributions::new_for_base_commit(
+                repo_clone,
+                target_branch_head_sha.clone(),
+                batch,
+                merge_base_clone,
+            )
+            .await
+        })?;
+
+        // Step 4: Read committed files from merge commit (captures final state with conflict resolutions)
+        let committed_files = get_committed_files_content(repo, merge_commit_sha, batch)?;
 
-    // Step 5: Merge VirtualAttributions, favoring target branch (base)
-    let merged_va = merge_attributions_favoring_first(target_va, source_va, committed_files)?;
+        debug_log(&format!(
+            "Read {} committed files from merge commit",
+            committed_files.len()
+        ));
 
-    // Step 6: Convert to AuthorshipLog (everything is committed in CI merge)
-    let mut authorship_log = merged_va.to_authorship_log()?;
+        // Step 5: Merge VirtualAttributions, favoring target branch (base)
+        let merged_va = merge_attributions_favoring_first(target_va, source_va, committed_files)?;
+
+        // Step 6: Convert to AuthorshipLog (everything is committed in CI merge)
+        let batch_log = merged_va.to_authorship_log()?;
+        authorship_log.attestations.extend(batch_log.attestations);
+        for (prompt_id, record) in batch_log.metadata.prompts {
+            authorship_log
+                .metadata
+                .prompts
+                .entry(prompt_id)
+                .or_insert(record);
+        }
+    }
+    authorship_log.sort_attestations();
     authorship_log.metadata.base_commit_sha = merge_commit_sha.to_string();
 
     // Preserve accumulated totals from source commits (squash/rebase should not drop session totals).
@@ -503,35 +520,33 @@ pub fn rewrite_authorship_after_rebase_v2(
             new_content_for_changed_files.insert(file_path_str, new_content);
         }
 
+        // Whether each changed file is empty in this commit, so the contents themselves
+        // can be handed off instead of copied
+        let changed_file_is_empty: HashMap<String, bool> = new_content_for_changed_files
+            .iter()
+            .map(|(file, content)| (file.clone(), content.is_empty()))
+            .collect();
+
         // Only transform attributions for files that actually changed
         // For unchanged files, we'll preserve them as-is
         if !changed_files_in_commit.is_empty() {
             current_va = transform_attributions_to_final_state(
                 &current_va,
-                new_content_for_changed_files.clone(),
+                new_content_for_changed_files,
                 Some(&original_head_state_va),
             )?;
         }
 
-        // Build complete content state for authorship log (all tracked files)
-        let mut new_content_state = HashMap::new();
-        for file in current_va.files() {
-            if let Some(content) = current_va.get_file_content(&file) {
-                new_content_state.insert(file, content.clone());
-            }
-        }
-        // Update with any changed files
-        new_content_state.extend(new_content_for_changed_files);
-
         // Convert to AuthorshipLog, but filter to only files that exist in this commit
         let mut authorship_log = current_va.to_authorship_log()?;
 
         // Filter out attestations for files that don't exist in this commit (empty files)
         authorship_log.attestations.retain(|attestation| {
-            if let Some(content) = new_content_state.get(&attestation.file_path) {
-                !content.is_empty()
-            } else {
-                false
+            match changed_file_is_empty.get(&attestation.file_path) {
+                Some(is_empty) => !is_empty,
+                None => current_va
+                    .get_file_content(&attestation.file_path)
+                    .is_some_and(|content| !content.is_empty()),
             }
         });
 
@@ -845,6 +860,12 @@ pub fn rewrite_authorship_after_commit_amend(
         .map_err(|_| GitAiError::Generic("Failed to serialize authorship log".to_string()))?;
     crate::git::refs::notes_add(repo, amended_commit, &authorship_json)?;
 
+    // The amended note supersedes the original one. Drop it unless the original commit
+    // is still reachable (e.g. it was amended on one branch but also exists on another).
+    if !is_reachable_from_any_ref(repo, original_commit) {
+        crate::git::refs::notes_remove(repo, original_commit)?;
+    }
+
     // Save INITIAL file for uncommitted attributions
     if !initial_attributions.files.is_empty() {
         let new_working_log = repo.storage.working_log_for_base_commit(amended_commit);
@@ -859,6 +880,19 @@ pub fn rewrite_authorship_after_commit_amend(
     Ok(authorship_log)
 }
 
+fn is_reachable_from_any_ref(repo: &Repository, commit_sha: &str) -> bool {
+    // If the check itself fails, err on the side of keeping the note
+    repo.git(&[
+        "for-each-ref",
+        "--count=1",
+        "--format=%(refname)",
+        "--contains",
+        commit_sha,
+    ])
+    .map(|refs| !refs.trim().is_empty())
+    .unwrap_or(true)
+}
+
 pub fn walk_commits_to_base(
     repository: &Repository,
     head: &str,
@@ -872,12 +906,12 @@ pub fn walk_commits_to_base(
 
     let base_str = base.to_string();
     let head_commit = repository.find_commit(head.to_string())?;
+    // Parents come from the commit-graph where it has them, instead of a git call each
+    let commit_graph = CommitGraph::open(repository);
 
-    queue.push_back(head_commit);
-
-    while let Some(current) = queue.pop_front() {
-        let commit_id = current.id().to_string();
+    queue.push_back(head_commit.id());
 
+    while let Some(commit_id) = queue.pop_front() {
         // Skip if we've reached the base
         if commit_id == base_str {
             continue;
@@ -889,15 +923,19 @@ pub fn walk_commits_to_base(
         }
 
         visited.insert(commit_id.clone());
-        commits.push(commit_id);
 
         // Add all parents to the queue (handles merge commits)
-        let parent_count = current.parent_count().unwrap_or(0);
-        for i in 0..parent_count {
-            if let Ok(parent) = current.parent(i) {
-                queue.push_back(parent);
-            }
-        }
+        let parents = commit_graph
+            .as_ref()
+            .and_then(|graph| graph.parents(&commit_id))
+            .unwrap_or_else(|| {
+                repository
+                    .find_commit(commit_id.clone())
+                    .map(|commit| commit.parents().map(|parent| parent.id()).collect())
+                    .unwrap_or_default()
+            });
+        queue.extend(parents);
+        commits.push(commit_id);
     }
 
     Ok(commits)
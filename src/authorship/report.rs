@@ -0,0 +1,499 @@
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::authorship::commit_class::{ClassThresholds, CommitClass};
+use crate::authorship::identity_map::{IdentityMap, UNASSIGNED_TEAM};
+use crate::authorship::language::detect_language_in;
+use crate::authorship::neutral_commits::NeutralCommits;
+use crate::authorship::range_authorship::should_ignore_file;
+use crate::error::GitAiError;
+use crate::git::refs::get_authorship;
+use crate::git::repository::{Repository, exec_git};
+use chrono::{DateTime, Datelike};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Lines added to a single file by a single commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileContribution {
+    pub path: String,
+    pub added_lines: u32,
+    pub ai_lines: u32,
+}
+
+/// A commit's line contributions, as recorded by git and its authorship note
+#[derive(Debug, Clone)]
+pub struct CommitContribution {
+    pub sha: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date, seconds since the epoch
+    pub timestamp: i64,
+    pub has_note: bool,
+    pub files: Vec<FileContribution>,
+    /// Tags of the security findings on the commit's AI lines, once per finding
+    pub security_tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReportBucket {
+    pub commits: usize,
+    pub added_lines: u32,
+    pub ai_lines: u32,
+    pub human_lines: u32,
+}
+
+/// Time bucket used for per-period commit classification counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl ReportPeriod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "day" => Some(ReportPeriod::Day),
+            "week" => Some(ReportPeriod::Week),
+            "month" => Some(ReportPeriod::Month),
+            _ => None,
+        }
+    }
+
+    /// Label of the period containing `timestamp` (UTC): `2025-03-14`, `2025-W11`, `2025-03`
+    pub fn label(&self, timestamp: i64) -> String {
+        let Some(date) = DateTime::from_timestamp(timestamp, 0) else {
+            return "unknown".to_string();
+        };
+        match self {
+            ReportPeriod::Day => date.format("%Y-%m-%d").to_string(),
+            ReportPeriod::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            ReportPeriod::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+impl ReportBucket {
+    /// Fraction of added lines attributed to AI (0.0 when nothing was added)
+    pub fn ai_share(&self) -> f64 {
+        if self.added_lines == 0 {
+            0.0
+        } else {
+            self.ai_lines as f64 / self.added_lines as f64
+        }
+    }
+
+    fn add_file(&mut self, file: &FileContribution) {
+        self.added_lines += file.added_lines;
+        self.ai_lines += file.ai_lines;
+        self.human_lines += file.added_lines.saturating_sub(file.ai_lines);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub range: String,
+    pub commits_without_notes: usize,
+    pub totals: ReportBucket,
+    pub by_author: BTreeMap<String, ReportBucket>,
+    pub by_team: BTreeMap<String, ReportBucket>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_package: BTreeMap<String, ReportBucket>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_language: BTreeMap<String, ReportBucket>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_code_kind: BTreeMap<String, ReportBucket>,
+    /// Commit counts per classification (fully_ai / ai_assisted / human)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_classification: BTreeMap<String, usize>,
+    /// Commit counts per classification, per period
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub classification_over_time: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Security findings on AI lines per tag (CWE ID, rule category, ...)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_security_tag: BTreeMap<String, usize>,
+}
+
+/// Collect per-file contributions for the non-merge commits selected by `rev_args`
+/// (anything `git log` accepts, e.g. `["main..feature"]` or `["HEAD", "--since=..."]`).
+///
+/// Author names and emails have `.mailmap` applied. Attribution-neutral commits are left
+/// out.
+pub fn collect_contributions(
+    repo: &Repository,
+    rev_args: &[String],
+) -> Result<Vec<CommitContribution>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.push("log".to_string());
+    args.push("--no-merges".to_string());
+    args.push("--no-renames".to_string());
+    args.push("--numstat".to_string());
+    args.push("--format=%x00%H%x00%aN%x00%aE%x00%at".to_string());
+    args.extend(rev_args.iter().cloned());
+
+    let output = exec_git(&args)?;
+    let stdout = String::from_utf8(output.stdout)?;
+
+    let mut commits = parse_log_numstat(&stdout);
+    let mut neutral = NeutralCommits::from_config(repo);
+    commits.retain(|commit| !neutral.is_neutral(&commit.sha));
+    for commit in &mut commits {
+        if let Some(log) = get_authorship(repo, &commit.sha) {
+            commit.has_note = true;
+            apply_ai_lines(commit, &log);
+            commit.security_tags = log
+                .metadata
+                .security_scan
+                .iter()
+                .flat_map(|scan| &scan.findings)
+                .flat_map(|finding| finding.tags.iter().cloned())
+                .collect();
+        }
+    }
+    Ok(commits)
+}
+
+fn parse_log_numstat(stdout: &str) -> Vec<CommitContribution> {
+    let mut commits: Vec<CommitContribution> = Vec::new();
+
+    for line in stdout.lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            let fields: Vec<&str> = header.split('\0').collect();
+            if fields.len() >= 4 {
+                commits.push(CommitContribution {
+                    sha: fields[0].to_string(),
+                    author_name: fields[1].to_string(),
+                    author_email: fields[2].to_string(),
+                    timestamp: fields[3].parse().unwrap_or(0),
+                    has_note: false,
+                    files: Vec::new(),
+                    security_tags: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        // numstat: "added\tdeleted\tpath" ("-" for binary files)
+        let mut parts = line.splitn(3, '\t');
+        let (Some(added), Some(_deleted), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let Ok(added_lines) = added.parse::<u32>() else {
+            continue;
+        };
+        if let Some(commit) = commits.last_mut() {
+            commit.files.push(FileContribution {
+                path: path.to_string(),
+                added_lines,
+                ai_lines: 0,
+            });
+        }
+    }
+
+    commits
+}
+
+/// Count the AI-attributed lines per file from a commit's authorship note.
+fn apply_ai_lines(commit: &mut CommitContribution, log: &AuthorshipLog) {
+    for attestation in &log.attestations {
+        let Some(file) = commit
+            .files
+            .iter_mut()
+            .find(|f| f.path == attestation.file_path)
+        else {
+            continue;
+        };
+
+        let mut lines = BTreeSet::new();
+        for entry in &attestation.entries {
+            for range in &entry.line_ranges {
+                match range {
+                    LineRange::Single(line) => {
+                        lines.insert(*line);
+                    }
+                    LineRange::Range(start, end) => lines.extend(*start..=*end),
+                }
+            }
+        }
+        // Notes can describe lines carried over from earlier commits; never report more
+        // AI lines than the commit actually added
+        file.ai_lines = (lines.len() as u32).min(file.added_lines);
+    }
+}
+
+/// Aggregate contributions into totals and per-author/per-team buckets.
+pub fn build_report(
+    range: &str,
+    commits: &[CommitContribution],
+    identity_map: Option<&IdentityMap>,
+) -> Report {
+    let default_map = IdentityMap::default();
+    let identity_map = identity_map.unwrap_or(&default_map);
+
+    let mut report = Report {
+        range: range.to_string(),
+        commits_without_notes: 0,
+        totals: ReportBucket::default(),
+        by_author: BTreeMap::new(),
+        by_team: BTreeMap::new(),
+        by_package: BTreeMap::new(),
+        by_language: BTreeMap::new(),
+        by_code_kind: BTreeMap::new(),
+        by_classification: BTreeMap::new(),
+        classification_over_time: BTreeMap::new(),
+        by_security_tag: BTreeMap::new(),
+    };
+
+    for commit in commits {
+        if !commit.has_note {
+            report.commits_without_notes += 1;
+        }
+
+        let identity = identity_map.resolve(&commit.author_name, &commit.author_email);
+        let author = report.by_author.entry(identity.display()).or_default();
+        let team = report
+            .by_team
+            .entry(
+                identity
+                    .team
+                    .clone()
+                    .unwrap_or_else(|| UNASSIGNED_TEAM.to_string()),
+            )
+            .or_default();
+
+        report.totals.commits += 1;
+        author.commits += 1;
+        team.commits += 1;
+        for file in &commit.files {
+            report.totals.add_file(file);
+            author.add_file(file);
+            team.add_file(file);
+        }
+        for tag in &commit.security_tags {
+            *report.by_security_tag.entry(tag.clone()).or_default() += 1;
+        }
+    }
+
+    report
+}
+
+/// Group contributions by an arbitrary per-file key. A commit is counted once for
+/// every group it touches.
+pub fn group_files_by<F>(commits: &[CommitContribution], key: F) -> BTreeMap<String, ReportBucket>
+where
+    F: Fn(&FileContribution) -> String,
+{
+    let mut groups: BTreeMap<String, ReportBucket> = BTreeMap::new();
+    for commit in commits {
+        let mut touched = BTreeSet::new();
+        for file in &commit.files {
+            let group = key(file);
+            let bucket = groups.entry(group.clone()).or_default();
+            bucket.add_file(file);
+            if touched.insert(group) {
+                bucket.commits += 1;
+            }
+        }
+    }
+    groups
+}
+
+/// Added and AI lines per language of the commits selected by `rev_args`, leaving out
+/// files matching `ignore_patterns`, for `git-ai stats --by-language`.
+pub fn language_breakdown(
+    repo: &Repository,
+    rev_args: &[String],
+    ignore_patterns: &[String],
+) -> Result<BTreeMap<String, ReportBucket>, GitAiError> {
+    let mut commits = collect_contributions(repo, rev_args)?;
+    for commit in &mut commits {
+        commit
+            .files
+            .retain(|file| !should_ignore_file(&file.path, ignore_patterns));
+    }
+    let root = repo.workdir().unwrap_or_default();
+    Ok(group_files_by(&commits, |file| {
+        detect_language_in(&root, &file.path).to_string()
+    }))
+}
+
+/// Classify each commit by its AI line share, filling in the report's overall and
+/// per-period classification counts.
+pub fn classify_commits(
+    report: &mut Report,
+    commits: &[CommitContribution],
+    thresholds: &ClassThresholds,
+    period: ReportPeriod,
+) {
+    for commit in commits {
+        let class = commit_class(commit, thresholds);
+        *report
+            .by_classification
+            .entry(class.as_str().to_string())
+            .or_default() += 1;
+        *report
+            .classification_over_time
+            .entry(period.label(commit.timestamp))
+            .or_default()
+            .entry(class.as_str().to_string())
+            .or_default() += 1;
+    }
+}
+
+fn commit_class(commit: &CommitContribution, thresholds: &ClassThresholds) -> CommitClass {
+    let added: u32 = commit.files.iter().map(|f| f.added_lines).sum();
+    let ai: u32 = commit.files.iter().map(|f| f.ai_lines).sum();
+    thresholds.classify(ai, added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(
+        sha: &str,
+        name: &str,
+        email: &str,
+        files: &[(&str, u32, u32)],
+    ) -> CommitContribution {
+        CommitContribution {
+            sha: sha.to_string(),
+            author_name: name.to_string(),
+            author_email: email.to_string(),
+            timestamp: 0,
+            has_note: true,
+            files: files
+                .iter()
+                .map(|(path, added, ai)| FileContribution {
+                    path: path.to_string(),
+                    added_lines: *added,
+                    ai_lines: *ai,
+                })
+                .collect(),
+            security_tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_log_numstat() {
+        let stdout = "\0abc\0Jane\0jane@corp.com\x001700000000\n\n3\t1\tsrc/lib.rs\n-\t-\tlogo.png\n\0def\0Sam\0sam@corp.com\x001700000100\n\n10\t0\tREADME.md\n";
+        let commits = parse_log_numstat(stdout);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].sha, "abc");
+        assert_eq!(commits[0].author_email, "jane@corp.com");
+        assert_eq!(commits[0].timestamp, 1_700_000_000);
+        assert_eq!(commits[0].files.len(), 1);
+        assert_eq!(commits[0].files[0].added_lines, 3);
+        assert_eq!(commits[1].files[0].path, "README.md");
+    }
+
+    #[test]
+    fn test_build_report_merges_identities_into_teams() {
+        let map = IdentityMap::parse(
+            "[Platform]\nJane Doe <jane@corp.com> <jane@gmail.com>\n[Payments]\nSam <sam@corp.com>",
+        )
+        .unwrap();
+        let commits = vec![
+            commit("a", "Jane", "jane@corp.com", &[("src/a.rs", 10, 8)]),
+            commit("b", "jane", "jane@gmail.com", &[("src/b.rs", 10, 2)]),
+            commit("c", "Sam", "sam@corp.com", &[("src/c.rs", 5, 0)]),
+            commit("d", "Alex", "alex@example.com", &[("src/d.rs", 4, 4)]),
+        ];
+
+        let report = build_report("main..feature", &commits, Some(&map));
+
+        assert_eq!(report.totals.commits, 4);
+        assert_eq!(report.totals.added_lines, 29);
+        assert_eq!(report.totals.ai_lines, 14);
+        assert_eq!(report.totals.human_lines, 15);
+
+        let jane = &report.by_author["Jane Doe <jane@corp.com>"];
+        assert_eq!(jane.commits, 2);
+        assert_eq!(jane.ai_lines, 10);
+        assert!((jane.ai_share() - 0.5).abs() < f64::EPSILON);
+
+        assert_eq!(report.by_team["Platform"].commits, 2);
+        assert_eq!(report.by_team["Payments"].ai_lines, 0);
+        assert_eq!(report.by_team[UNASSIGNED_TEAM].ai_lines, 4);
+        assert!(report.by_security_tag.is_empty());
+    }
+
+    #[test]
+    fn test_build_report_counts_security_findings_per_tag() {
+        let mut flagged = commit("a", "Jane", "jane@corp.com", &[("src/a.rs", 10, 8)]);
+        flagged.security_tags = vec!["CWE-78".to_string(), "CWE-89".to_string()];
+        let mut also_flagged = commit("b", "Sam", "sam@corp.com", &[("src/b.rs", 4, 4)]);
+        also_flagged.security_tags = vec!["CWE-78".to_string()];
+
+        let report = build_report("HEAD", &[flagged, also_flagged], None);
+        assert_eq!(report.by_security_tag["CWE-78"], 2);
+        assert_eq!(report.by_security_tag["CWE-89"], 1);
+    }
+
+    #[test]
+    fn test_group_files_by_counts_commits_once_per_group() {
+        let commits = vec![
+            commit(
+                "a",
+                "Jane",
+                "jane@corp.com",
+                &[("crates/core/a.rs", 10, 5), ("crates/core/b.rs", 4, 4)],
+            ),
+            commit(
+                "b",
+                "Jane",
+                "jane@corp.com",
+                &[("crates/cli/main.rs", 6, 0)],
+            ),
+        ];
+
+        let groups = group_files_by(&commits, |file| {
+            file.path.split('/').take(2).collect::<Vec<_>>().join("/")
+        });
+
+        assert_eq!(groups["crates/core"].commits, 1);
+        assert_eq!(groups["crates/core"].added_lines, 14);
+        assert_eq!(groups["crates/core"].ai_lines, 9);
+        assert_eq!(groups["crates/cli"].commits, 1);
+        assert_eq!(groups["crates/cli"].human_lines, 6);
+    }
+
+    #[test]
+    fn test_classify_commits_over_time() {
+        let mut commits = vec![
+            commit("a", "Jane", "jane@corp.com", &[("src/a.rs", 10, 10)]),
+            commit("b", "Jane", "jane@corp.com", &[("src/b.rs", 10, 3)]),
+            commit("c", "Sam", "sam@corp.com", &[("src/c.rs", 5, 0)]),
+        ];
+        // 2024-01-31, 2024-02-01, 2024-02-02 (UTC)
+        commits[0].timestamp = 1_706_659_200;
+        commits[1].timestamp = 1_706_745_600;
+        commits[2].timestamp = 1_706_832_000;
+
+        let mut report = build_report("HEAD", &commits, None);
+        classify_commits(
+            &mut report,
+            &commits,
+            &ClassThresholds::default(),
+            ReportPeriod::Month,
+        );
+
+        assert_eq!(report.by_classification["fully_ai"], 1);
+        assert_eq!(report.by_classification["ai_assisted"], 1);
+        assert_eq!(report.by_classification["human"], 1);
+        assert_eq!(report.classification_over_time["2024-01"]["fully_ai"], 1);
+        assert_eq!(report.classification_over_time["2024-02"].len(), 2);
+    }
+
+    #[test]
+    fn test_report_period_labels() {
+        // 2024-12-30 is in ISO week 1 of 2025
+        assert_eq!(ReportPeriod::Day.label(1_735_560_000), "2024-12-30");
+        assert_eq!(ReportPeriod::Week.label(1_735_560_000), "2025-W01");
+        assert_eq!(ReportPeriod::Month.label(1_735_560_000), "2024-12");
+    }
+}
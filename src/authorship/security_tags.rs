@@ -0,0 +1,276 @@
+//! Tagging newly attributed AI hunks with findings from an external scanner
+//! (`security_scan_command` / `GIT_AI_SECURITY_SCAN_COMMAND`), such as a semgrep wrapper
+//! that reports CWE IDs. The command gets the hunks as JSON on stdin:
+//!
+//! `{"commit": "<sha>", "hunks": [{"path", "start_line", "end_line", "content"}]}`
+//!
+//! and prints a JSON array of findings:
+//!
+//! `[{"path", "start_line", "end_line", "rule", "tags": ["CWE-78", ...]}]`
+//!
+//! Findings outside the hunks are dropped, so a scan of whole files is fine. The rest are
+//! stored in the note's metadata, where reports count them per tag; a scan without
+//! findings is recorded too, so a clean commit can be told from an unscanned one.
+
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::config::Config;
+use crate::error::GitAiError;
+use crate::git::refs::{get_authorship, notes_add};
+use crate::git::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// A scanner finding on AI-authored lines of the commit the note is for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityFinding {
+    pub path: String,
+    pub start_line: u32,
+    /// Defaults to `start_line` for single-line findings
+    #[serde(default)]
+    pub end_line: u32,
+    /// Scanner rule that matched, e.g. a semgrep check ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// CWE IDs, OWASP categories or anything else to group findings by
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A scan of a commit's AI-authored lines
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SecurityScan {
+    #[serde(default)]
+    pub findings: Vec<SecurityFinding>,
+}
+
+/// A run of consecutive AI-authored lines, as sent to the scan command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ScanHunk {
+    path: String,
+    start_line: u32,
+    end_line: u32,
+    content: String,
+}
+
+/// Scan the AI hunks `log` records for `commit_sha` with the configured command and
+/// replace the note's findings with the result. Does nothing without a command; a
+/// command that fails is reported and leaves the findings as they were.
+pub fn tag_security_findings(repo: &Repository, commit_sha: &str, log: &mut AuthorshipLog) {
+    let config = Config::get();
+    let Some(command) = config.security_scan_command() else {
+        return;
+    };
+    match scan_ai_hunks(repo, commit_sha, log, command) {
+        Ok(findings) => log.metadata.security_scan = Some(Box::new(SecurityScan { findings })),
+        Err(e) => eprintln!("[git-ai] Warning: security scan failed: {}", e),
+    }
+}
+
+/// Re-tag the notes of `commits` after they were rewritten, since their hunks moved
+pub fn tag_rewritten_commits(repo: &Repository, commits: &[String]) -> Result<(), GitAiError> {
+    if Config::get().security_scan_command().is_none() {
+        return Ok(());
+    }
+    for commit in commits {
+        let Some(mut log) = get_authorship(repo, commit) else {
+            continue;
+        };
+        let before = log.metadata.security_scan.clone();
+        tag_security_findings(repo, commit, &mut log);
+        let Some(scan) = &log.metadata.security_scan else {
+            continue;
+        };
+        if log.metadata.security_scan == before {
+            continue;
+        }
+        println!(
+            "Tagged {} security finding(s) on {}",
+            scan.findings.len(),
+            commit
+        );
+        let content = log.serialize_to_string().map_err(|_| {
+            GitAiError::Generic(format!("Failed to serialize authorship for {}", commit))
+        })?;
+        notes_add(repo, commit, &content)?;
+    }
+    Ok(())
+}
+
+/// Run `command` over the AI hunks of `commit_sha` and return the findings on them
+pub fn scan_ai_hunks(
+    repo: &Repository,
+    commit_sha: &str,
+    log: &AuthorshipLog,
+    command: &str,
+) -> Result<Vec<SecurityFinding>, GitAiError> {
+    let hunks = ai_hunks(repo, commit_sha, log);
+    if hunks.is_empty() {
+        return Ok(Vec::new());
+    }
+    let input = serde_json::json!({ "commit": commit_sha, "hunks": hunks }).to_string();
+    let stdout = run_scan_command(repo, command, &input)?;
+    if stdout.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let findings: Vec<SecurityFinding> = serde_json::from_str(&stdout).map_err(|e| {
+        GitAiError::Generic(format!(
+            "security scan command printed invalid findings: {}",
+            e
+        ))
+    })?;
+    Ok(findings_in_hunks(findings, &hunks))
+}
+
+fn run_scan_command(repo: &Repository, command: &str, input: &str) -> Result<String, GitAiError> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Ok(workdir) = repo.workdir() {
+        shell.current_dir(workdir);
+    }
+    let mut child = shell
+        .spawn()
+        .map_err(|e| GitAiError::Generic(format!("could not run `{}`: {}", command, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A scanner that exits without reading everything closes the pipe; its exit
+        // status says whether that was a failure
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(GitAiError::Generic(format!(
+            "`{}` exited with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The consecutive AI-authored lines of each file `log` attests to, with their text at
+/// `commit_sha`. Files that can't be read there are left out.
+fn ai_hunks(repo: &Repository, commit_sha: &str, log: &AuthorshipLog) -> Vec<ScanHunk> {
+    let mut hunks = Vec::new();
+    for attestation in &log.attestations {
+        let mut lines = BTreeSet::new();
+        for entry in &attestation.entries {
+            for range in &entry.line_ranges {
+                match range {
+                    LineRange::Single(line) => {
+                        lines.insert(*line);
+                    }
+                    LineRange::Range(start, end) => lines.extend(*start..=*end),
+                }
+            }
+        }
+        if lines.is_empty() {
+            continue;
+        }
+        let Ok(content) = repo.get_file_content(&attestation.file_path, commit_sha) else {
+            continue;
+        };
+        let content = String::from_utf8_lossy(&content);
+        let file_lines: Vec<&str> = content.lines().collect();
+        for (start_line, end_line) in consecutive_runs(&lines) {
+            let Some(text) = (start_line as usize)
+                .checked_sub(1)
+                .and_then(|start| file_lines.get(start..end_line as usize))
+            else {
+                continue;
+            };
+            hunks.push(ScanHunk {
+                path: attestation.file_path.clone(),
+                start_line,
+                end_line,
+                content: text.join("\n"),
+            });
+        }
+    }
+    hunks
+}
+
+fn consecutive_runs(lines: &BTreeSet<u32>) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for &line in lines {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => runs.push((line, line)),
+        }
+    }
+    runs
+}
+
+/// Findings overlapping a hunk, with single-line findings' end lines filled in
+fn findings_in_hunks(findings: Vec<SecurityFinding>, hunks: &[ScanHunk]) -> Vec<SecurityFinding> {
+    findings
+        .into_iter()
+        .map(|mut finding| {
+            finding.end_line = finding.end_line.max(finding.start_line);
+            finding
+        })
+        .filter(|finding| {
+            hunks.iter().any(|hunk| {
+                hunk.path == finding.path
+                    && finding.start_line <= hunk.end_line
+                    && finding.end_line >= hunk.start_line
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_findings_outside_ai_hunks_are_dropped() {
+        let hunk = |path: &str, start_line, end_line| ScanHunk {
+            path: path.to_string(),
+            start_line,
+            end_line,
+            content: String::new(),
+        };
+        let hunks = vec![hunk("app.py", 3, 5), hunk("app.py", 9, 9)];
+        let finding = |path: &str, start_line, end_line| SecurityFinding {
+            path: path.to_string(),
+            start_line,
+            end_line,
+            rule: None,
+            tags: vec!["CWE-78".to_string()],
+        };
+        let kept = findings_in_hunks(
+            vec![
+                finding("app.py", 5, 0),
+                finding("app.py", 1, 2),
+                finding("app.py", 7, 12),
+                finding("other.py", 3, 3),
+            ],
+            &hunks,
+        );
+        assert_eq!(
+            kept,
+            vec![finding("app.py", 5, 5), finding("app.py", 7, 12)]
+        );
+    }
+
+    #[test]
+    fn test_consecutive_runs() {
+        let lines: BTreeSet<u32> = [1, 2, 3, 7, 9, 10].into_iter().collect();
+        assert_eq!(consecutive_runs(&lines), vec![(1, 3), (7, 7), (9, 10)]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Complete per-line attribution of a tree at one commit, in a single JSON file.
+//!
+//! Notes only describe what each commit added, and may be deltas against their parent's
+//! note or summaries with the line maps on the details ref, so answering "which lines of
+//! this tree are AI" means blaming every AI-touched file through history. A snapshot does
+//! that once for a revision so reports, exports and the editor heatmap cache
+//! (`git-ai heatmap --snapshot`) can read the result instead of redoing it.
+
+use crate::authorship::working_log::CheckpointKind;
+use crate::commands::blame::GitAiBlameOptions;
+use crate::error::GitAiError;
+use crate::git::authorship_traversal::load_ai_touched_files_for_commits;
+use crate::git::repository::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+const EMPTY_TREE_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub commit: String,
+    /// Every file in the commit's tree
+    pub files: BTreeMap<String, FileSnapshot>,
+    /// The prompts the AI ranges refer to, by hash
+    pub prompts: BTreeMap<String, SnapshotPrompt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub blob: String,
+    /// None for binary files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<u32>,
+    /// AI-authored lines; every other line is human
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ai: Vec<AiRange>,
+}
+
+/// Lines `start..=end` (1-based) written by one prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AiRange {
+    pub start: u32,
+    pub end: u32,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotPrompt {
+    pub tool: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub human_author: Option<String>,
+}
+
+impl FileSnapshot {
+    pub fn ai_line_count(&self) -> u32 {
+        self.ai
+            .iter()
+            .map(|range| range.end - range.start + 1)
+            .sum()
+    }
+
+    /// AI-authored line numbers, ascending
+    pub fn ai_lines(&self) -> Vec<u32> {
+        self.ai
+            .iter()
+            .flat_map(|range| range.start..=range.end)
+            .collect()
+    }
+}
+
+impl Snapshot {
+    /// Default location: `.git/ai/snapshots/<commit>.json`
+    pub fn default_path(repo: &Repository, commit: &str) -> PathBuf {
+        repo.path()
+            .join("ai")
+            .join("snapshots")
+            .join(format!("{}.json", commit))
+    }
+
+    pub fn ai_line_count(&self) -> u32 {
+        self.files.values().map(FileSnapshot::ai_line_count).sum()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, GitAiError> {
+        let snapshot: Snapshot = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(GitAiError::Generic(format!(
+                "Snapshot {} has version {}, newer than this git-ai reads ({})",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    /// Write the snapshot atomically so readers never observe a partial file.
+    pub fn write(&self, path: &Path) -> Result<(), GitAiError> {
+        if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Blame every AI-touched file in `rev`'s tree and record its AI lines
+pub fn build_snapshot(repo: &Repository, rev: &str) -> Result<Snapshot, GitAiError> {
+    let commit = repo
+        .git(&["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])?
+        .trim()
+        .to_string();
+    let blobs = tree_blobs(repo, &commit)?;
+    let mut line_counts = line_counts(repo, &commit)?;
+
+    let mut files: BTreeMap<String, FileSnapshot> = blobs
+        .into_iter()
+        .map(|(path, blob)| {
+            let lines = line_counts.remove(&path);
+            (
+                path,
+                FileSnapshot {
+                    blob,
+                    lines,
+                    ai: Vec::new(),
+                },
+            )
+        })
+        .collect();
+
+    let commits: Vec<String> = repo
+        .git(&["rev-list", &commit])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let ai_files = smol::block_on(load_ai_touched_files_for_commits(repo, commits))?;
+
+    let mut options = GitAiBlameOptions::default();
+    #[allow(clippy::field_reassign_with_default)]
+    {
+        options.newest_commit = Some(commit.clone());
+        options.no_output = true;
+        options.use_prompt_hashes_as_names = true;
+        options.return_human_authors_as_human = true;
+    }
+
+    let human = CheckpointKind::Human.to_str();
+    let mut prompts = BTreeMap::new();
+    for path in ai_files {
+        let Some(file) = files.get_mut(&path) else {
+            continue;
+        };
+        if file.lines.is_none_or(|lines| lines == 0) {
+            continue;
+        }
+        let Ok((line_authors, prompt_records)) = repo.blame(&path, &options) else {
+            continue;
+        };
+        let mut ai_lines: Vec<(u32, &String)> = line_authors
+            .iter()
+            .filter(|(_, author)| **author != human)
+            .map(|(line, author)| (*line, author))
+            .collect();
+        ai_lines.sort_unstable();
+        file.ai = ai_ranges(&ai_lines);
+        for range in &file.ai {
+            if let Some(record) = prompt_records.get(&range.prompt) {
+                prompts
+                    .entry(range.prompt.clone())
+                    .or_insert_with(|| SnapshotPrompt {
+                        tool: record.agent_id.tool.clone(),
+                        model: record.agent_id.model.clone(),
+                        human_author: record.human_author.clone(),
+                    });
+            }
+        }
+    }
+
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        commit,
+        files,
+        prompts,
+    })
+}
+
+/// Merge consecutive lines by the same prompt into ranges
+fn ai_ranges(lines: &[(u32, &String)]) -> Vec<AiRange> {
+    let mut ranges: Vec<AiRange> = Vec::new();
+    for &(line, prompt) in lines {
+        match ranges.last_mut() {
+            Some(last) if last.end + 1 == line && &last.prompt == prompt => last.end = line,
+            _ => ranges.push(AiRange {
+                start: line,
+                end: line,
+                prompt: prompt.clone(),
+            }),
+        }
+    }
+    ranges
+}
+
+/// Regular files in `commit`'s tree and their blob OIDs
+pub(crate) fn tree_blobs(
+    repo: &Repository,
+    commit: &str,
+) -> Result<HashMap<String, String>, GitAiError> {
+    let output = repo.git(&["ls-tree", "-r", "-z", commit])?;
+    let mut blobs = HashMap::new();
+    for entry in output.split('\0') {
+        // "<mode> <type> <oid>\t<path>"
+        let Some((meta, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut parts = meta.split(' ');
+        if let (Some(_mode), Some("blob"), Some(oid)) = (parts.next(), parts.next(), parts.next()) {
+            blobs.insert(path.to_string(), oid.to_string());
+        }
+    }
+    Ok(blobs)
+}
+
+/// Line count of each text file in `commit`'s tree, from one diff against the empty tree
+fn line_counts(repo: &Repository, commit: &str) -> Result<HashMap<String, u32>, GitAiError> {
+    let output = repo.git(&["diff", "--numstat", "-z", EMPTY_TREE_HASH, commit])?;
+    Ok(parse_numstat(&output))
+}
+
+fn parse_numstat(output: &str) -> HashMap<String, u32> {
+    output
+        .split('\0')
+        .filter_map(|entry| {
+            // "<added>\t<deleted>\t<path>"; binary files show "-" for both
+            let mut parts = entry.trim_start_matches('\n').splitn(3, '\t');
+            let added = parts.next()?.parse().ok()?;
+            let _deleted = parts.next()?;
+            Some((parts.next()?.to_string(), added))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ai_ranges_split_on_gaps_and_prompts() {
+        let a = "aaaa".to_string();
+        let b = "bbbb".to_string();
+        let ranges = ai_ranges(&[(1, &a), (2, &a), (3, &b), (5, &b), (6, &b)]);
+        assert_eq!(
+            ranges,
+            vec![
+                AiRange {
+                    start: 1,
+                    end: 2,
+                    prompt: a.clone(),
+                },
+                AiRange {
+                    start: 3,
+                    end: 3,
+                    prompt: b.clone(),
+                },
+                AiRange {
+                    start: 5,
+                    end: 6,
+                    prompt: b.clone(),
+                },
+            ]
+        );
+        let file = FileSnapshot {
+            blob: "0".repeat(40),
+            lines: Some(6),
+            ai: ranges,
+        };
+        assert_eq!(file.ai_line_count(), 5);
+        assert_eq!(file.ai_lines(), vec![1, 2, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_parse_numstat_skips_binary_files() {
+        let counts = parse_numstat("3\t0\tsrc/lib.rs\0-\t-\tlogo.png\0\n1\t0\tdir/a b.txt\0");
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["src/lib.rs"], 3);
+        assert_eq!(counts["dir/a b.txt"], 1);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::authorship::diff_ai_accepted::diff_ai_accepted_stats;
+use crate::authorship::neutral_commits::NeutralCommits;
+use crate::authorship::report::{ReportBucket, language_breakdown};
 use crate::authorship::transcript::Message;
+use crate::commands::report::print_section;
 use crate::error::GitAiError;
 use crate::git::refs::get_authorship;
 use crate::git::repository::Repository;
@@ -47,12 +50,26 @@ pub struct CommitStats {
     pub git_diff_added_lines: u32,
     #[serde(default)]
     pub tool_model_breakdown: BTreeMap<String, ToolModelHeadlineStats>,
+    /// A formatter run or other commit configured to count as neither AI nor human
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub attribution_neutral: bool,
+}
+
+/// Stats JSON with the `--by-language` breakdown, when requested, after the stats' own
+/// fields
+#[derive(Serialize)]
+pub struct WithLanguages<'a, T: Serialize> {
+    #[serde(flatten)]
+    pub stats: &'a T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_language: Option<&'a BTreeMap<String, ReportBucket>>,
 }
 
 pub fn stats_command(
     repo: &Repository,
     commit_sha: Option<&str>,
     json: bool,
+    by_language: bool,
     ignore_patterns: &[String],
 ) -> Result<(), GitAiError> {
     let (target, refname) = if let Some(sha) = commit_sha {
@@ -82,12 +99,27 @@ pub fn stats_command(
     ));
 
     let stats = stats_for_commit_stats(repo, &target, ignore_patterns)?;
+    let languages = if by_language {
+        Some(language_breakdown(
+            repo,
+            &[format!("{}^!", target)],
+            ignore_patterns,
+        )?)
+    } else {
+        None
+    };
 
     if json {
-        let json_str = serde_json::to_string(&stats)?;
+        let json_str = serde_json::to_string(&WithLanguages {
+            stats: &stats,
+            by_language: languages.as_ref(),
+        })?;
         println!("{}", json_str);
     } else {
         write_stats_to_terminal(&stats, true);
+        if let Some(languages) = languages {
+            print_section("By language", &languages);
+        }
     }
 
     Ok(())
@@ -99,6 +131,17 @@ pub fn write_stats_to_terminal(stats: &CommitStats, print: bool) -> String {
     // Set maximum bar width to 40 characters
     let bar_width: usize = 40;
 
+    // Attribution-neutral commits (e.g. formatter runs) have nothing to chart
+    if stats.attribution_neutral {
+        let message = format!("     \x1b[90m{:^40}\x1b[0m", "(attribution-neutral commit)");
+        output.push_str(&message);
+        output.push('\n');
+        if print {
+            println!("{}", message);
+        }
+        return output;
+    }
+
     // Handle deletion-only commits (no additions)
     if stats.git_diff_added_lines == 0 && stats.git_diff_deleted_lines > 0 {
         // Show gray bar for deletion-only commit
@@ -303,6 +346,12 @@ pub fn write_stats_to_markdown(stats: &CommitStats) -> String {
     // Set maximum bar width to 20 characters
     let bar_width: usize = 20;
 
+    if stats.attribution_neutral {
+        output.push_str("(attribution-neutral commit)");
+        output.push('\n');
+        return output;
+    }
+
     // Handle deletion-only commits (no additions)
     if stats.git_diff_added_lines == 0 && stats.git_diff_deleted_lines > 0 {
         output.push_str("(no additions)");
@@ -466,6 +515,7 @@ pub fn stats_from_authorship_log(
         tool_model_breakdown: BTreeMap::new(),
         git_diff_deleted_lines,
         git_diff_added_lines,
+        attribution_neutral: false,
     };
 
     // Process authorship log if present
@@ -540,6 +590,16 @@ pub fn stats_for_commit_stats(
     let (git_diff_added_lines, git_diff_deleted_lines) =
         get_git_diff_stats(repo, commit_sha, ignore_patterns)?;
 
+    // Formatter runs and the like: none of the lines count toward either side
+    if NeutralCommits::from_config(repo).is_neutral(commit_sha) {
+        return Ok(CommitStats {
+            git_diff_added_lines,
+            git_diff_deleted_lines,
+            attribution_neutral: true,
+            ..Default::default()
+        });
+    }
+
     // Step 2: get parent SHA for diff-based accepted counts
     let commit_obj = repo.revparse_single(commit_sha)?.peel_to_commit()?;
     let parent_sha = if commit_obj.parent_count()? == 0 {
@@ -703,6 +763,7 @@ mod tests {
             total_ai_additions: 100,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let mixed_output = write_stats_to_terminal(&stats, true);
@@ -720,6 +781,7 @@ mod tests {
             total_ai_additions: 100,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let ai_only_output = write_stats_to_terminal(&ai_stats, true);
@@ -737,6 +799,7 @@ mod tests {
             total_ai_additions: 0,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let human_only_output = write_stats_to_terminal(&human_stats, true);
@@ -754,6 +817,7 @@ mod tests {
             total_ai_additions: 100,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let minimal_human_output = write_stats_to_terminal(&minimal_human_stats, true);
@@ -771,6 +835,7 @@ mod tests {
             total_ai_additions: 0,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let deletion_only_output = write_stats_to_terminal(&deletion_only_stats, true);
@@ -791,6 +856,7 @@ mod tests {
             total_ai_additions: 100,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let mixed_output = write_stats_to_markdown(&stats);
@@ -808,6 +874,7 @@ mod tests {
             total_ai_additions: 100,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let ai_only_output = write_stats_to_markdown(&ai_stats);
@@ -825,6 +892,7 @@ mod tests {
             total_ai_additions: 0,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let human_only_output = write_stats_to_markdown(&human_stats);
@@ -842,6 +910,7 @@ mod tests {
             total_ai_additions: 100,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let minimal_human_output = write_stats_to_markdown(&minimal_human_stats);
@@ -859,6 +928,7 @@ mod tests {
             total_ai_additions: 0,
             total_ai_deletions: 0,
             tool_model_breakdown: BTreeMap::new(),
+            attribution_neutral: false,
         };
 
         let deletion_only_output = write_stats_to_markdown(&deletion_only_stats);
@@ -2,6 +2,7 @@ use crate::authorship::attribution_tracker::{
     Attribution, LineAttribution, line_attributions_to_attributions,
 };
 use crate::authorship::authorship_log::{LineRange, PromptRecord};
+use crate::authorship::imara_diff_utils::{DiffOp, capture_diff_slices};
 use crate::authorship::working_log::CheckpointKind;
 use crate::commands::blame::{GitAiBlameOptions, OLDEST_AI_BLAME_DATE};
 use crate::error::GitAiError;
@@ -104,7 +105,9 @@ impl VirtualAttributions {
     ) -> Result<Vec<(String, String, PromptRecord)>, GitAiError> {
         const MAX_CONCURRENT: usize = 30;
 
-        let semaphore = Arc::new(smol::lock::Semaphore::new(MAX_CONCURRENT));
+        let semaphore = Arc::new(smol::lock::Semaphore::new(crate::memory::concurrency(
+            MAX_CONCURRENT,
+        )));
         let mut tasks = Vec::new();
 
         for missing_id in missing_ids {
@@ -178,7 +181,9 @@ impl VirtualAttributions {
     async fn add_pathspecs_concurrent(&mut self, pathspecs: &[String]) -> Result<(), GitAiError> {
         const MAX_CONCURRENT: usize = 30;
 
-        let semaphore = Arc::new(smol::lock::Semaphore::new(MAX_CONCURRENT));
+        let semaphore = Arc::new(smol::lock::Semaphore::new(crate::memory::concurrency(
+            MAX_CONCURRENT,
+        )));
         let mut tasks = Vec::new();
 
         for pathspec in pathspecs {
@@ -594,6 +599,7 @@ impl VirtualAttributions {
             }
         }
 
+        authorship_log.sort_attestations();
         Ok(authorship_log)
     }
 }
@@ -634,26 +640,15 @@ fn collect_committed_hunks(
 }
 
 /// Helper function to collect unstaged line ranges (lines in working directory but not in commit)
-/// Returns (unstaged_hunks, pure_insertion_hunks)
-/// pure_insertion_hunks contains lines that were purely inserted (old_count=0), not modifications
-#[allow(clippy::type_complexity)]
 fn collect_unstaged_hunks(
     repo: &Repository,
     commit_sha: &str,
     pathspecs: Option<&HashSet<String>>,
-) -> Result<
-    (
-        HashMap<String, Vec<LineRange>>,
-        HashMap<String, Vec<LineRange>>,
-    ),
-    GitAiError,
-> {
+) -> Result<HashMap<String, Vec<LineRange>>, GitAiError> {
     let mut unstaged_hunks: HashMap<String, Vec<LineRange>> = HashMap::new();
-    let mut pure_insertion_hunks: HashMap<String, Vec<LineRange>> = HashMap::new();
 
-    // Use git diff to get added lines in working directory vs commit, with insertion tracking
-    let (added_lines, insertion_lines) =
-        repo.diff_workdir_added_lines_with_insertions(commit_sha, pathspecs)?;
+    // Use git diff to get added lines in working directory vs commit
+    let added_lines = repo.diff_workdir_added_lines(commit_sha, pathspecs)?;
 
     for (file_path, lines) in added_lines {
         if !lines.is_empty() {
@@ -661,12 +656,6 @@ fn collect_unstaged_hunks(
         }
     }
 
-    for (file_path, lines) in insertion_lines {
-        if !lines.is_empty() {
-            pure_insertion_hunks.insert(file_path, LineRange::compress_lines(&lines));
-        }
-    }
-
     // Check for untracked files in pathspecs that git diff didn't find
     // These are files that exist in the working directory but aren't tracked by git
     if let Some(paths) = pathspecs
@@ -693,17 +682,15 @@ fn collect_unstaged_hunks(
                     let line_count = content.lines().count() as u32;
                     if line_count > 0 {
                         // Create a range covering all lines (1-indexed)
-                        let range = vec![LineRange::Range(1, line_count)];
-                        unstaged_hunks.insert(pathspec.clone(), range.clone());
-                        // Untracked files are pure insertions (the entire file is new)
-                        pure_insertion_hunks.insert(pathspec.clone(), range);
+                        unstaged_hunks
+                            .insert(pathspec.clone(), vec![LineRange::Range(1, line_count)]);
                     }
                 }
             }
         }
     }
 
-    Ok((unstaged_hunks, pure_insertion_hunks))
+    Ok(unstaged_hunks)
 }
 
 impl VirtualAttributions {
@@ -747,59 +734,10 @@ impl VirtualAttributions {
         let mut initial_files: StdHashMap<String, Vec<LineAttribution>> = StdHashMap::new();
         let mut referenced_prompts: HashSet<String> = HashSet::new();
 
-        // Get committed hunks (in commit coordinates) and unstaged hunks (in working directory coordinates)
+        // Committed hunks are lines added by this commit (in commit coordinates). Unstaged
+        // hunks are lines in the working directory that aren't in the commit.
         let committed_hunks = collect_committed_hunks(repo, parent_sha, commit_sha, pathspecs)?;
-        let (mut unstaged_hunks, pure_insertion_hunks) =
-            collect_unstaged_hunks(repo, commit_sha, pathspecs)?;
-
-        // IMPORTANT: If a line appears in both committed_hunks and unstaged_hunks, it means:
-        // - The line was committed in this commit (in commit coordinates)
-        // - The line was then modified again in the working directory (in workdir coordinates)
-        // Since both use the same line numbering after the commit (workdir coordinates = commit coordinates
-        // for the committed state), we can directly compare line numbers.
-        // We should treat these lines as committed, not unstaged, because the attribution belongs
-        // to the commit even if there's a subsequent unstaged modification.
-        //
-        // HOWEVER: If a line is a PURE INSERTION (old_count=0), it means a new line was inserted
-        // at that position, pushing existing lines down. In this case, the line number overlap
-        // doesn't mean the same line - it's a different line at the same position!
-        // We should NOT filter out pure insertions even if they overlap with committed line numbers.
-        for (file_path, committed_ranges) in &committed_hunks {
-            if let Some(unstaged_ranges) = unstaged_hunks.get_mut(file_path) {
-                // Expand both to line numbers for comparison
-                let committed_lines: std::collections::HashSet<u32> =
-                    committed_ranges.iter().flat_map(|r| r.expand()).collect();
-
-                // Get pure insertion lines for this file (these should NOT be filtered out)
-                let pure_insertion_lines: std::collections::HashSet<u32> = pure_insertion_hunks
-                    .get(file_path)
-                    .map(|ranges| ranges.iter().flat_map(|r| r.expand()).collect())
-                    .unwrap_or_default();
-
-                // Filter out any unstaged lines that were also committed
-                // (these are lines that were committed, then modified again in workdir)
-                // BUT keep pure insertions even if they overlap with committed line numbers
-                let mut filtered_unstaged_lines: Vec<u32> = unstaged_ranges
-                    .iter()
-                    .flat_map(|r| r.expand())
-                    .filter(|line| {
-                        // Keep the line if it's NOT in committed, OR if it's a pure insertion
-                        !committed_lines.contains(line) || pure_insertion_lines.contains(line)
-                    })
-                    .collect();
-
-                if filtered_unstaged_lines.is_empty() {
-                    unstaged_ranges.clear();
-                } else {
-                    filtered_unstaged_lines.sort_unstable();
-                    filtered_unstaged_lines.dedup();
-                    *unstaged_ranges = LineRange::compress_lines(&filtered_unstaged_lines);
-                }
-            }
-        }
-
-        // Remove files with no unstaged hunks
-        unstaged_hunks.retain(|_, ranges| !ranges.is_empty());
+        let unstaged_hunks = collect_unstaged_hunks(repo, commit_sha, pathspecs)?;
 
         // Process each file
         for (file_path, (_, line_attrs)) in &self.attributions {
@@ -816,9 +754,24 @@ impl VirtualAttributions {
                 unstaged_lines.sort_unstable();
             }
 
-            // Split line attributions into committed and uncommitted
-            // VirtualAttributions has line numbers in working directory coordinates,
-            // so we need to convert to commit coordinates before comparing with committed hunks
+            // The commit contains exactly what was staged, which after `git add -p` can be
+            // any subset of the working directory's hunks. Rather than inferring commit line
+            // numbers from unstaged hunk offsets, diff the attributed content against the
+            // committed blob so every line maps to its exact position in the commit.
+            let attributed_content = match self.file_contents.get(file_path) {
+                Some(content) => content.clone(),
+                None => repo
+                    .workdir()
+                    .ok()
+                    .and_then(|dir| std::fs::read_to_string(dir.join(file_path)).ok())
+                    .unwrap_or_default(),
+            };
+            let committed_content = repo
+                .get_file_content(file_path, commit_sha)
+                .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+                .unwrap_or_default();
+            let commit_line_map = map_unchanged_lines(&attributed_content, &committed_content);
+
             let mut committed_lines_map: StdHashMap<String, Vec<u32>> = StdHashMap::new();
             let mut uncommitted_lines_map: StdHashMap<String, Vec<u32>> = StdHashMap::new();
 
@@ -828,42 +781,27 @@ impl VirtualAttributions {
             for line_attr in line_attrs {
                 // Check each line individually
                 for workdir_line_num in line_attr.start_line..=line_attr.end_line {
-                    // Check if this line is unstaged (in working directory but not in commit)
-                    let is_unstaged = unstaged_lines.binary_search(&workdir_line_num).is_ok();
-
-                    if is_unstaged {
-                        // Line is unstaged, mark as uncommitted
-                        uncommitted_lines_map
-                            .entry(line_attr.author_id.clone())
-                            .or_default()
-                            .push(workdir_line_num);
-                        referenced_prompts.insert(line_attr.author_id.clone());
-                    } else {
-                        // Convert working directory line number to commit line number
-                        // by subtracting the count of unstaged lines before this line
-                        let adjustment = unstaged_lines
-                            .iter()
-                            .filter(|&&l| l < workdir_line_num)
-                            .count() as u32;
-                        let commit_line_num = workdir_line_num - adjustment;
-
-                        // Check if this commit line number is in any committed hunk
-                        let is_committed = if let Some(hunks) = file_committed_hunks {
-                            hunks.iter().any(|hunk| hunk.contains(commit_line_num))
-                        } else {
-                            false
-                        };
-
+                    if let Some(&commit_line_num) = commit_line_map.get(&workdir_line_num) {
+                        // Line is in the commit. It's attributed to this commit only if the
+                        // commit added it; otherwise it already existed in the parent.
+                        let is_committed = file_committed_hunks
+                            .is_some_and(|hunks| hunks.iter().any(|h| h.contains(commit_line_num)));
                         if is_committed {
-                            // Line was committed in this commit (use commit coordinates)
                             committed_lines_map
                                 .entry(line_attr.author_id.clone())
                                 .or_default()
                                 .push(commit_line_num);
                         }
-                        // Note: Lines that are neither unstaged nor in committed_hunks are lines that
-                        // already existed in the parent commit. They are discarded (not added to uncommitted).
+                    } else if unstaged_lines.binary_search(&workdir_line_num).is_ok() {
+                        // Line is still pending in the working directory
+                        uncommitted_lines_map
+                            .entry(line_attr.author_id.clone())
+                            .or_default()
+                            .push(workdir_line_num);
+                        referenced_prompts.insert(line_attr.author_id.clone());
                     }
+                    // Lines that are neither in the commit nor in the working directory were
+                    // removed before committing and are discarded.
                 }
             }
 
@@ -995,6 +933,7 @@ impl VirtualAttributions {
             prompts: initial_prompts,
         };
 
+        authorship_log.sort_attestations();
         Ok((authorship_log, initial_attributions))
     }
 
@@ -1133,6 +1072,7 @@ impl VirtualAttributions {
             }
         }
 
+        authorship_log.sort_attestations();
         Ok(authorship_log)
     }
 
@@ -1740,6 +1680,31 @@ fn get_file_content_at_commit(
     }
 }
 
+/// Map 1-indexed line numbers in `from` to their line numbers in `to`, for every line
+/// the two versions share. Lines that only exist in `from` are absent from the map.
+fn map_unchanged_lines(from: &str, to: &str) -> HashMap<u32, u32> {
+    let from_lines: Vec<&str> = from.lines().collect();
+    let to_lines: Vec<&str> = to.lines().collect();
+
+    let mut mapping = HashMap::new();
+    for op in capture_diff_slices(&from_lines, &to_lines) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for offset in 0..len {
+                mapping.insert(
+                    (old_index + offset + 1) as u32,
+                    (new_index + offset + 1) as u32,
+                );
+            }
+        }
+    }
+    mapping
+}
+
 /// Check if a file exists in a commit's tree
 fn file_exists_in_commit(
     repo: &Repository,
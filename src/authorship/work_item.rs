@@ -0,0 +1,81 @@
+use crate::config::Config;
+use crate::git::repository::Repository;
+use regex::Regex;
+
+/// Environment variable for explicitly setting the work item on checkpoints
+/// (takes precedence over branch name parsing).
+pub const WORK_ITEM_ENV_VAR: &str = "GIT_AI_WORK_ITEM";
+
+/// Extract a work-item ID (Jira key, issue number, ...) from a branch name.
+///
+/// If the pattern has a capture group, the first group is used as the ID;
+/// otherwise the whole match is. An empty or invalid pattern never matches.
+pub fn work_item_from_branch(branch: &str, pattern: &str) -> Option<String> {
+    if pattern.is_empty() {
+        return None;
+    }
+    let regex = Regex::new(pattern).ok()?;
+    let captures = regex.captures(branch)?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(0))
+        .map(|m| m.as_str().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// Resolve the work item for the current state of `repo`: `GIT_AI_WORK_ITEM` if set,
+/// otherwise whatever the configured pattern extracts from the current branch name.
+pub fn resolve_work_item(repo: &Repository) -> Option<String> {
+    if let Ok(explicit) = std::env::var(WORK_ITEM_ENV_VAR) {
+        let explicit = explicit.trim();
+        if !explicit.is_empty() {
+            return Some(explicit.to_string());
+        }
+    }
+
+    let head = repo.head().ok()?;
+    let branch = head.name()?.strip_prefix("refs/heads/")?;
+    work_item_from_branch(branch, Config::for_repo(repo).work_item_pattern())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_WORK_ITEM_PATTERN;
+
+    #[test]
+    fn test_work_item_from_branch_default_pattern() {
+        assert_eq!(
+            work_item_from_branch("feature/PROJ-123-add-login", DEFAULT_WORK_ITEM_PATTERN),
+            Some("PROJ-123".to_string())
+        );
+        assert_eq!(
+            work_item_from_branch("ABC-7", DEFAULT_WORK_ITEM_PATTERN),
+            Some("ABC-7".to_string())
+        );
+        assert_eq!(
+            work_item_from_branch("main", DEFAULT_WORK_ITEM_PATTERN),
+            None
+        );
+        assert_eq!(
+            work_item_from_branch("fix/utf-8-handling", DEFAULT_WORK_ITEM_PATTERN),
+            None
+        );
+    }
+
+    #[test]
+    fn test_work_item_from_branch_capture_group() {
+        let github_issue = r"^(?:[^/]+/)?(\d+)-";
+        assert_eq!(
+            work_item_from_branch("fix/482-null-deref", github_issue),
+            Some("482".to_string())
+        );
+        assert_eq!(work_item_from_branch("feature/login", github_issue), None);
+    }
+
+    #[test]
+    fn test_work_item_from_branch_empty_or_invalid_pattern() {
+        assert_eq!(work_item_from_branch("PROJ-1", ""), None);
+        assert_eq!(work_item_from_branch("PROJ-1", "(unclosed"), None);
+    }
+}
@@ -100,6 +100,28 @@ pub struct CheckpointLineStats {
     pub deletions_sloc: u32,
 }
 
+/// Tokens an agent reported using
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// How much agent work went into a checkpoint, so reports can set it against how much of
+/// the code survives. Tool calls and tokens are the agent session's totals as of the
+/// checkpoint; the difference from the session's previous checkpoint is this edit's share.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct CheckpointEffort {
+    /// Files the checkpoint added or removed lines in
+    pub files_touched: u32,
+    pub tool_calls: u32,
+    /// None when the agent's transcript doesn't record usage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<TokenUsage>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Checkpoint {
     #[serde(default = "CheckpointKind::serde_default")]
@@ -115,9 +137,14 @@ pub struct Checkpoint {
     #[serde(default)]
     pub line_stats: CheckpointLineStats,
     #[serde(default)]
+    pub effort: CheckpointEffort,
+    #[serde(default)]
     pub api_version: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub git_ai_version: Option<String>,
+    /// Work-item ID (Jira key, issue number, ...) this checkpoint was made for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_item: Option<String>,
 }
 
 impl Checkpoint {
@@ -142,8 +169,10 @@ impl Checkpoint {
             agent_id: None,
             agent_metadata: None,
             line_stats: CheckpointLineStats::default(),
+            effort: CheckpointEffort::default(),
             api_version: CHECKPOINT_API_VERSION.to_string(),
             git_ai_version: Some(GIT_AI_VERSION.to_string()),
+            work_item: None,
         }
     }
 }
@@ -189,6 +218,23 @@ mod tests {
         assert!(deserialized.agent_id.is_none());
     }
 
+    #[test]
+    fn test_checkpoint_without_effort_deserializes() {
+        let checkpoint = Checkpoint::new(
+            CheckpointKind::AiAgent,
+            "".to_string(),
+            "claude".to_string(),
+            Vec::new(),
+        );
+        let mut json = serde_json::to_value(&checkpoint).unwrap();
+        assert!(json["effort"]["tokens"].is_null());
+        // Checkpoints written before effort was recorded
+        json.as_object_mut().unwrap().remove("effort");
+
+        let deserialized: Checkpoint = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.effort, CheckpointEffort::default());
+    }
+
     #[test]
     fn test_log_array_serialization() {
         let entry1 = WorkingLogEntry::new(
@@ -0,0 +1,150 @@
+//! Per-line annotations of the AI-authored lines `git-ai ci` rewrote authorship for
+//! (`ci_check_run` / `GIT_AI_CI_CHECK_RUN=1`), so reviewers see attribution inline next
+//! to the diff. Publishing them is up to each forge's module.
+
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::error::GitAiError;
+use crate::git::refs::get_authorship;
+use crate::git::repository::{Repository, exec_git};
+use std::collections::HashSet;
+
+/// An AI-authored line range in a file of the annotated commit
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineAnnotation {
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub message: String,
+}
+
+/// Annotations for the AI-authored lines the notes of `commits` record, in the files as
+/// of `target` (the last of them). A file a later commit changed again is skipped for
+/// the earlier commits, since their line numbers no longer match it.
+pub fn ai_line_annotations(
+    repo: &Repository,
+    commits: &[String],
+    target: &str,
+) -> Result<Vec<LineAnnotation>, GitAiError> {
+    let mut annotations = Vec::new();
+    for commit in commits {
+        let Some(log) = get_authorship(repo, commit) else {
+            continue;
+        };
+        let changed_since = if commit == target {
+            HashSet::new()
+        } else {
+            files_changed_between(repo, commit, target)?
+        };
+        annotations.extend(
+            annotations_from_log(&log)
+                .into_iter()
+                .filter(|annotation| !changed_since.contains(&annotation.path)),
+        );
+    }
+    annotations.sort();
+    Ok(annotations)
+}
+
+fn files_changed_between(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+) -> Result<HashSet<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "diff".to_string(),
+        "--name-only".to_string(),
+        "--no-renames".to_string(),
+        from.to_string(),
+        to.to_string(),
+    ]);
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+fn annotations_from_log(log: &AuthorshipLog) -> Vec<LineAnnotation> {
+    let mut annotations = Vec::new();
+    for attestation in &log.attestations {
+        for entry in &attestation.entries {
+            let message = match log.metadata.prompts.get(&entry.hash) {
+                Some(prompt) => format!(
+                    "Written by AI ({} {})",
+                    prompt.agent_id.tool, prompt.agent_id.model
+                ),
+                None => "Written by AI".to_string(),
+            };
+            for range in &entry.line_ranges {
+                let (start_line, end_line) = match range {
+                    LineRange::Single(line) => (*line, *line),
+                    LineRange::Range(start, end) => (*start, *end),
+                };
+                annotations.push(LineAnnotation {
+                    path: attestation.file_path.clone(),
+                    start_line,
+                    end_line,
+                    message: message.clone(),
+                });
+            }
+        }
+    }
+    annotations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::authorship_log::PromptRecord;
+    use crate::authorship::authorship_log_serialization::{AttestationEntry, FileAttestation};
+    use crate::authorship::working_log::AgentId;
+
+    #[test]
+    fn test_annotations_name_the_agent_per_range() {
+        let mut log = AuthorshipLog::new();
+        let mut file = FileAttestation::new("src/lib.rs".to_string());
+        file.entries.push(AttestationEntry::new(
+            "abc1234".to_string(),
+            vec![LineRange::Range(3, 5), LineRange::Single(9)],
+        ));
+        file.entries.push(AttestationEntry::new(
+            "missing".to_string(),
+            vec![LineRange::Single(12)],
+        ));
+        log.attestations.push(file);
+        log.metadata.prompts.insert(
+            "abc1234".to_string(),
+            PromptRecord {
+                agent_id: AgentId {
+                    tool: "claude".to_string(),
+                    id: "session".to_string(),
+                    model: "sonnet".to_string(),
+                },
+                human_author: None,
+                messages: Vec::new(),
+                total_additions: 0,
+                total_deletions: 0,
+                accepted_lines: 0,
+                overriden_lines: 0,
+                messages_url: None,
+            },
+        );
+
+        let annotation = |start_line, end_line, message: &str| LineAnnotation {
+            path: "src/lib.rs".to_string(),
+            start_line,
+            end_line,
+            message: message.to_string(),
+        };
+        assert_eq!(
+            annotations_from_log(&log),
+            vec![
+                annotation(3, 5, "Written by AI (claude sonnet)"),
+                annotation(9, 9, "Written by AI (claude sonnet)"),
+                annotation(12, 12, "Written by AI"),
+            ]
+        );
+    }
+}
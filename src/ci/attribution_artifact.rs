@@ -0,0 +1,218 @@
+//! `git-ai-attribution.json`, the machine-readable result of a `git-ai ci` run, so later
+//! stages of the pipeline (dashboards, compliance jobs) can use it without running git-ai
+//! again. It's written to the working directory, or to `GIT_AI_CI_ATTRIBUTION_ARTIFACT`,
+//! and lists each commit the run wrote a note for with the note's blob SHA and the lines
+//! the commit added per file, split into AI and human.
+
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::ci::ci_context::{CiContext, CiEvent, CiRunResult};
+use crate::ci::env;
+use crate::error::GitAiError;
+use crate::git::refs::get_authorship;
+use crate::git::repository::Repository;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming where to write the artifact
+pub const ATTRIBUTION_ARTIFACT_ENV_VAR: &str = "GIT_AI_CI_ATTRIBUTION_ARTIFACT";
+
+/// Where the artifact goes when the environment variable isn't set
+pub const DEFAULT_ATTRIBUTION_ARTIFACT: &str = "git-ai-attribution.json";
+
+const ATTRIBUTION_ARTIFACT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttributionArtifact {
+    pub version: u32,
+    /// What the run did: "rewritten", "already_exists", "skipped_simple_merge", ...
+    pub result: &'static str,
+    /// The merge commit, or the last pushed commit for a push
+    pub commit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_request: Option<u64>,
+    pub commits: Vec<CommitAttribution>,
+}
+
+/// A commit with a note and the lines it added
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitAttribution {
+    pub sha: String,
+    /// Blob SHA of the commit's note on refs/notes/ai
+    pub note_sha: Option<String>,
+    pub files: BTreeMap<String, FileLines>,
+}
+
+/// Line ranges the commit added to a file, as `[start, end]` pairs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileLines {
+    pub ai: Vec<[u32; 2]>,
+    pub human: Vec<[u32; 2]>,
+}
+
+/// Where to write the artifact
+pub fn artifact_path() -> PathBuf {
+    env(ATTRIBUTION_ARTIFACT_ENV_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_ATTRIBUTION_ARTIFACT))
+}
+
+impl AttributionArtifact {
+    /// The artifact for `result`, from the notes in `context`'s repository
+    pub fn collect(context: &CiContext, result: &CiRunResult) -> Result<Self, GitAiError> {
+        let (commit, merge_request) = match &context.event {
+            CiEvent::Merge {
+                merge_commit_sha,
+                merge_request,
+                ..
+            } => (merge_commit_sha.clone(), *merge_request),
+            CiEvent::Push { after_sha, .. } => (after_sha.clone(), None),
+        };
+        let (result_name, shas): (&'static str, Vec<String>) = match result {
+            CiRunResult::AuthorshipRewritten { commits, .. } => ("rewritten", commits.clone()),
+            CiRunResult::AlreadyExists { .. } => ("already_exists", vec![commit.clone()]),
+            CiRunResult::NotesWrittenToArtifact { commits, .. } => {
+                ("written_to_notes_artifact", commits.clone())
+            }
+            CiRunResult::DryRun { commits } => ("dry_run", commits.clone()),
+            CiRunResult::SkippedSimpleMerge => ("skipped_simple_merge", Vec::new()),
+            CiRunResult::SkippedFastForward => ("skipped_fast_forward", Vec::new()),
+            CiRunResult::NoAuthorshipAvailable => ("no_authorship_available", Vec::new()),
+            CiRunResult::PushChecked { .. } => ("push_checked", Vec::new()),
+        };
+        let commits = shas
+            .iter()
+            .map(|sha| commit_attribution(&context.repo, sha))
+            .collect::<Result<_, _>>()?;
+        Ok(AttributionArtifact {
+            version: ATTRIBUTION_ARTIFACT_VERSION,
+            result: result_name,
+            commit,
+            merge_request,
+            commits,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), GitAiError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn commit_attribution(repo: &Repository, sha: &str) -> Result<CommitAttribution, GitAiError> {
+    let note_sha = repo
+        .git(&["notes", "--ref=ai", "list", sha])
+        .ok()
+        .map(|out| out.trim().to_string())
+        .filter(|out| !out.is_empty());
+    let parent = format!("{}^", sha);
+    let added = if repo.git(&["rev-parse", "--verify", "-q", &parent]).is_ok() {
+        repo.diff_added_lines(&parent, sha, None)?
+    } else {
+        Default::default()
+    };
+    let files = match get_authorship(repo, sha) {
+        Some(log) => split_added_lines(&log, added),
+        None => split_added_lines(&AuthorshipLog::new(), added),
+    };
+    Ok(CommitAttribution {
+        sha: sha.to_string(),
+        note_sha,
+        files,
+    })
+}
+
+/// The added lines of each file, split by whether `log` attributes them to AI
+fn split_added_lines(
+    log: &AuthorshipLog,
+    added_lines_by_file: impl IntoIterator<Item = (String, Vec<u32>)>,
+) -> BTreeMap<String, FileLines> {
+    let mut files = BTreeMap::new();
+    for (path, mut added) in added_lines_by_file {
+        if added.is_empty() {
+            continue;
+        }
+        let ai: HashSet<u32> = log
+            .attestations
+            .iter()
+            .filter(|attestation| attestation.file_path == path)
+            .flat_map(|attestation| &attestation.entries)
+            .flat_map(|entry| &entry.line_ranges)
+            .flat_map(|range| match range {
+                LineRange::Single(line) => *line..=*line,
+                LineRange::Range(start, end) => *start..=*end,
+            })
+            .collect();
+        added.sort_unstable();
+        added.dedup();
+        let (ai_lines, human_lines): (Vec<u32>, Vec<u32>) =
+            added.into_iter().partition(|line| ai.contains(line));
+        files.insert(
+            path,
+            FileLines {
+                ai: pairs(&ai_lines),
+                human: pairs(&human_lines),
+            },
+        );
+    }
+    files
+}
+
+fn pairs(lines: &[u32]) -> Vec<[u32; 2]> {
+    LineRange::compress_lines(lines)
+        .into_iter()
+        .map(|range| match range {
+            LineRange::Single(line) => [line, line],
+            LineRange::Range(start, end) => [start, end],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::authorship_log_serialization::{AttestationEntry, FileAttestation};
+
+    #[test]
+    fn test_split_added_lines_into_ai_and_human_ranges() {
+        let mut log = AuthorshipLog::new();
+        let mut file = FileAttestation::new("src/lib.rs".to_string());
+        file.add_entry(AttestationEntry::new(
+            "abc1234".to_string(),
+            vec![LineRange::Range(2, 4), LineRange::Single(9)],
+        ));
+        log.attestations.push(file);
+
+        let files = split_added_lines(
+            &log,
+            [
+                ("src/lib.rs".to_string(), vec![1, 2, 3, 4, 5, 9]),
+                ("README.md".to_string(), vec![3]),
+                ("empty.txt".to_string(), vec![]),
+            ],
+        );
+        assert_eq!(
+            files,
+            BTreeMap::from([
+                (
+                    "README.md".to_string(),
+                    FileLines {
+                        ai: vec![],
+                        human: vec![[3, 3]],
+                    }
+                ),
+                (
+                    "src/lib.rs".to_string(),
+                    FileLines {
+                        ai: vec![[2, 4], [9, 9]],
+                        human: vec![[1, 1], [5, 5]],
+                    }
+                ),
+            ])
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Calling AWS JSON APIs (such as CodeCommit's) with Signature Version 4 signed requests.
+//!
+//! Credentials come from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and, for temporary
+//! credentials, `AWS_SESSION_TOKEN`; failing those, from the container credentials
+//! endpoint CodeBuild and ECS provide to the build's service role
+//! (`AWS_CONTAINER_CREDENTIALS_RELATIVE_URI` or `AWS_CONTAINER_CREDENTIALS_FULL_URI`).
+
+use crate::ci::env;
+use crate::ci::http;
+use crate::error::GitAiError;
+use crate::observability::timings::{self, Phase};
+use ring::hmac;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Host of the container credentials endpoint for `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI`
+const CONTAINER_CREDENTIALS_HOST: &str = "http://169.254.170.2";
+
+#[derive(Clone, PartialEq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("session_token", &self.session_token.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Credentials from the environment, else from the container credentials endpoint
+    pub fn resolve() -> Result<Self, GitAiError> {
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (env("AWS_ACCESS_KEY_ID"), env("AWS_SECRET_ACCESS_KEY"))
+        {
+            return Ok(Self {
+                access_key_id,
+                secret_access_key,
+                session_token: env("AWS_SESSION_TOKEN"),
+            });
+        }
+
+        let url = env("AWS_CONTAINER_CREDENTIALS_FULL_URI")
+            .or_else(|| {
+                env("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI")
+                    .map(|uri| format!("{}{}", CONTAINER_CREDENTIALS_HOST, uri))
+            })
+            .ok_or_else(|| {
+                GitAiError::Generic(
+                    "No AWS credentials: set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, or run with a CodeBuild service role".to_string(),
+                )
+            })?;
+        let mut request = http::get(&url).with_timeout(10);
+        if let Some(token) = env("AWS_CONTAINER_AUTHORIZATION_TOKEN") {
+            request = request.with_header("Authorization", token);
+        }
+        let response = http::send(request, "Container credentials request")?;
+        if response.status_code != 200 {
+            return Err(GitAiError::Generic(format!(
+                "Container credentials endpoint returned status {}",
+                response.status_code
+            )));
+        }
+        let credentials: ContainerCredentials =
+            serde_json::from_str(response.as_str().unwrap_or("")).map_err(|e| {
+                GitAiError::Generic(format!("Failed to parse container credentials: {}", e))
+            })?;
+        Ok(Self {
+            access_key_id: credentials.access_key_id,
+            secret_access_key: credentials.secret_access_key,
+            session_token: credentials.token,
+        })
+    }
+}
+
+/// Where and as whom to call one AWS service
+#[derive(Debug, Clone)]
+pub struct AwsClient {
+    pub endpoint: String,
+    pub region: String,
+    /// Signing name of the service, e.g. `codecommit`
+    pub service: String,
+    pub credentials: AwsCredentials,
+}
+
+impl AwsClient {
+    /// Call `target` (e.g. `CodeCommit_20150413.GetPullRequest`) on an AWS JSON 1.1 API
+    pub fn call(
+        &self,
+        target: &str,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, GitAiError> {
+        let body = request.to_string();
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = BTreeMap::from([
+            (
+                "content-type".to_string(),
+                "application/x-amz-json-1.1".to_string(),
+            ),
+            ("host".to_string(), host_header(&self.endpoint)?),
+            ("x-amz-date".to_string(), amz_date.clone()),
+            ("x-amz-target".to_string(), target.to_string()),
+        ]);
+        if let Some(token) = &self.credentials.session_token {
+            headers.insert("x-amz-security-token".to_string(), token.clone());
+        }
+        let authorization = sign(
+            "POST",
+            &self.endpoint,
+            &headers,
+            body.as_bytes(),
+            &self.credentials,
+            &self.region,
+            &self.service,
+            &amz_date,
+        )?;
+
+        let _timing = timings::phase(Phase::Api);
+        let mut request = http::post(&self.endpoint)
+            .with_header("Authorization", authorization)
+            .with_header(
+                "User-Agent",
+                format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+            )
+            .with_body(body);
+        // The HTTP client writes the Host header itself
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.with_header(name, value);
+        }
+        let response = http::send(request, &format!("AWS {} request", target))?;
+        let body = response.as_str().unwrap_or("").to_string();
+        if response.status_code != 200 {
+            return Err(GitAiError::Generic(format!(
+                "AWS {} returned status {}: {}",
+                target, response.status_code, body
+            )));
+        }
+        serde_json::from_str(&body).map_err(|e| {
+            GitAiError::Generic(format!("Failed to parse AWS {} response: {}", target, e))
+        })
+    }
+}
+
+/// The `Authorization` header for a request, signed with Signature Version 4. `headers`
+/// are lowercased names and must include `host` and `x-amz-date` (`amz_date`); all of
+/// them are signed.
+#[allow(clippy::too_many_arguments)]
+pub fn sign(
+    method: &str,
+    url: &str,
+    headers: &BTreeMap<String, String>,
+    payload: &[u8],
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+) -> Result<String, GitAiError> {
+    let parsed = url::Url::parse(url)
+        .map_err(|e| GitAiError::Generic(format!("Invalid AWS endpoint '{}': {}", url, e)))?;
+    let mut query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key), uri_encode(&value)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers.keys().cloned().collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{:x}",
+        method,
+        parsed.path(),
+        canonical_query,
+        canonical_headers,
+        signed_headers,
+        Sha256::digest(payload)
+    );
+
+    let date = amz_date.get(..8).unwrap_or(amz_date);
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+        amz_date,
+        scope,
+        Sha256::digest(canonical_request.as_bytes())
+    );
+
+    let mut key = format!("AWS4{}", credentials.secret_access_key).into_bytes();
+    for part in [date, region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature: String = hmac_sha256(&key, string_to_sign.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Ok(format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature
+    ))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, as SigV4 requires
+fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// `host[:port]`, as the HTTP client sends it
+fn host_header(url: &str) -> Result<String, GitAiError> {
+    let parsed = url::Url::parse(url)
+        .map_err(|e| GitAiError::Generic(format!("Invalid AWS endpoint '{}': {}", url, e)))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| GitAiError::Generic(format!("AWS endpoint '{}' has no host", url)))?;
+    Ok(match parsed.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+/// The region: AWS_REGION, else AWS_DEFAULT_REGION
+pub fn region_from_env() -> Option<String> {
+    env("AWS_REGION").or_else(|| env("AWS_DEFAULT_REGION"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_aws_documentation_example() {
+        // The IAM ListUsers example from the Signature Version 4 documentation
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let headers = BTreeMap::from([
+            (
+                "content-type".to_string(),
+                "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+            ),
+            ("host".to_string(), "iam.amazonaws.com".to_string()),
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        ]);
+        let authorization = sign(
+            "GET",
+            "https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08",
+            &headers,
+            b"",
+            &credentials,
+            "us-east-1",
+            "iam",
+            "20150830T123600Z",
+        )
+        .unwrap();
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn test_debug_does_not_contain_secret() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "very-secret".to_string(),
+            session_token: Some("session-secret".to_string()),
+        };
+        let debug = format!("{:?}", credentials);
+        assert!(debug.contains("AKIDEXAMPLE"));
+        assert!(!debug.contains("very-secret"));
+        assert!(!debug.contains("session-secret"));
+    }
+}
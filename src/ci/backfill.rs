@@ -0,0 +1,719 @@
+//! `git-ai ci backfill`: the sweep, run over many repositories unattended.
+//!
+//! Backfilling an organization asks the forge about every unattributed commit in hundreds
+//! of repositories, which runs into API rate limits and takes days. The backfill paces
+//! API requests against a budget per host, waits out rate-limited responses, and records
+//! its progress in a state file after every commit, so a restarted run resumes where the
+//! last one stopped instead of starting over, including a run stopped by the CI deadline.
+
+use crate::ci::credentials::{CiGitCredential, forge_git_args, git_args_for_dir};
+use crate::ci::deadline;
+use crate::ci::env;
+use crate::ci::gitlab::{gitlab_api_auth, gitlab_git_credential};
+use crate::ci::selftest::Provider;
+use crate::ci::sweep::{Lookup, SweepForge, describe, lookup_merged_request, process_request};
+use crate::error::GitAiError;
+use crate::git::refs::show_authorship_note;
+use crate::git::repository::{exec_git, find_repository};
+use crate::git::sync_authorship::fetch_authorship_notes;
+use crate::observability::timings::{self, Phase};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Budget for hosts without their own `--rate`. Well under GitHub's 5000 requests an
+/// hour for a token, and GitLab.com's per-user limits.
+pub const DEFAULT_RATE_BUDGET: RateBudget = RateBudget {
+    requests: 3000,
+    per: Duration::from_secs(3600),
+};
+
+/// Times a commit's lookup is retried after rate-limited responses before it's recorded
+/// as failed, for the next run to retry
+const MAX_RATE_LIMITED_ATTEMPTS: usize = 5;
+
+/// Commits between saves of the state file within a repository
+const SAVE_EVERY_COMMITS: usize = 50;
+
+/// A repository to backfill
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackfillRepo {
+    pub provider: Provider,
+    /// `owner/repo` or `group/project`
+    pub path: String,
+    /// Branch to backfill; the default branch when unset
+    pub branch: Option<String>,
+}
+
+impl BackfillRepo {
+    /// Key of the repository in the state file
+    pub fn key(&self) -> String {
+        let provider = match self.provider {
+            Provider::Github => "github",
+            Provider::Gitlab => "gitlab",
+        };
+        format!("{}:{}", provider, self.path)
+    }
+}
+
+/// Parse the repository list: one `github owner/repo` or `gitlab group/project` per line,
+/// optionally followed by the branch to backfill. Blank lines and `#` comments are ignored.
+pub fn parse_repo_list(text: &str) -> Result<Vec<BackfillRepo>, GitAiError> {
+    let mut repos = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let repo = match fields.as_slice() {
+            [provider, path, branch @ ..] if path.contains('/') && branch.len() <= 1 => {
+                Provider::parse(provider).map(|provider| BackfillRepo {
+                    provider,
+                    path: path.trim_matches('/').trim_end_matches(".git").to_string(),
+                    branch: branch.first().map(|branch| branch.to_string()),
+                })
+            }
+            _ => None,
+        };
+        match repo {
+            Some(repo) => repos.push(repo),
+            None => {
+                return Err(GitAiError::Generic(format!(
+                    "Line {}: expected `github <owner/repo> [branch]` or `gitlab <group/project> [branch]`, got '{}'",
+                    number + 1,
+                    line
+                )));
+            }
+        }
+    }
+    Ok(repos)
+}
+
+/// Requests allowed per period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateBudget {
+    pub requests: usize,
+    pub per: Duration,
+}
+
+impl RateBudget {
+    /// `5000/h`, `300/min`, `2/30s`
+    pub fn parse(s: &str) -> Result<Self, GitAiError> {
+        let invalid = || {
+            GitAiError::Generic(format!(
+                "Invalid rate '{}': expected <requests>/<period>, e.g. 5000/h or 300/min",
+                s
+            ))
+        };
+        let (requests, period) = s.split_once('/').ok_or_else(invalid)?;
+        let requests: usize = requests.trim().parse().map_err(|_| invalid())?;
+        let period = period.trim();
+        // A bare unit means one of it
+        let period = if period.starts_with(|c: char| c.is_ascii_digit()) {
+            period.to_string()
+        } else {
+            format!("1{}", period)
+        };
+        let per = humantime::parse_duration(&period).map_err(|_| invalid())?;
+        if requests == 0 || per.is_zero() {
+            return Err(invalid());
+        }
+        Ok(Self { requests, per })
+    }
+}
+
+/// Sliding-window request budgets, one per API host
+#[derive(Debug)]
+pub struct RateBudgets {
+    default: RateBudget,
+    hosts: HashMap<String, RateBudget>,
+    sent: HashMap<String, VecDeque<Instant>>,
+    /// Hosts that answered with a rate limit, and when to try them again
+    paused_until: HashMap<String, Instant>,
+}
+
+impl RateBudgets {
+    pub fn new(default: RateBudget) -> Self {
+        Self {
+            default,
+            hosts: HashMap::new(),
+            sent: HashMap::new(),
+            paused_until: HashMap::new(),
+        }
+    }
+
+    pub fn set(&mut self, host: &str, budget: RateBudget) {
+        self.hosts.insert(host.to_string(), budget);
+    }
+
+    /// How long until a request to `host` fits its budget
+    fn wait(&mut self, host: &str, now: Instant) -> Duration {
+        let budget = self.hosts.get(host).copied().unwrap_or(self.default);
+        let sent = self.sent.entry(host.to_string()).or_default();
+        while sent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= budget.per)
+        {
+            sent.pop_front();
+        }
+        let for_budget = if sent.len() < budget.requests {
+            Duration::ZERO
+        } else {
+            (sent[sent.len() - budget.requests] + budget.per).saturating_duration_since(now)
+        };
+        let for_pause = self
+            .paused_until
+            .get(host)
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or_default();
+        for_budget.max(for_pause)
+    }
+
+    fn record(&mut self, host: &str, now: Instant) {
+        self.sent
+            .entry(host.to_string())
+            .or_default()
+            .push_back(now);
+    }
+
+    fn pause(&mut self, host: &str, until: Instant) {
+        self.paused_until.insert(host.to_string(), until);
+    }
+
+    /// Block until a request to `host` fits its budget, then count it. Fails instead
+    /// when the wait would end after the CI deadline.
+    fn acquire(&mut self, host: &str) -> Result<(), GitAiError> {
+        loop {
+            let wait = self.wait(host, Instant::now());
+            if wait.is_zero() {
+                break;
+            }
+            if deadline::remaining().is_some_and(|left| wait >= left) {
+                return Err(deadline::exceeded(&format!(
+                    "waiting for the {} rate budget",
+                    host
+                )));
+            }
+            println!(
+                "[CI backfill] Waiting {}s for the {} rate budget",
+                wait.as_secs().max(1),
+                host
+            );
+            std::thread::sleep(wait);
+        }
+        self.record(host, Instant::now());
+        Ok(())
+    }
+}
+
+/// Progress of one repository, saved every [`SAVE_EVERY_COMMITS`] commits and when the
+/// run moves on or stops
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoProgress {
+    /// Every commit was settled; a resumed run skips the repository
+    #[serde(default)]
+    pub complete: bool,
+    /// The last commit, walking the first-parent history oldest first, that it and
+    /// every commit before it were dealt with. A resumed run starts after it, retrying
+    /// only those in `failed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_water_mark: Option<String>,
+    /// Requests processed, so other commits of a rebase-merged request count as covered
+    #[serde(default)]
+    pub requests: BTreeSet<u64>,
+    #[serde(default)]
+    pub processed: usize,
+    /// Commit -> the error its last attempt failed with. Retried on the next run.
+    #[serde(default)]
+    pub failed: BTreeMap<String, String>,
+    /// Why the repository itself couldn't be backfilled, e.g. a failed clone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Dry run: requests that would be processed
+    #[serde(skip)]
+    pub pending: usize,
+}
+
+/// The state file: progress per repository, by [`BackfillRepo::key`]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BackfillState {
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoProgress>,
+    /// Why this run stopped before the end, i.e. the CI deadline passed
+    #[serde(skip)]
+    pub stopped: Option<String>,
+}
+
+impl BackfillState {
+    /// Load the state file, or start afresh when there's none yet
+    pub fn load(path: &Path) -> Result<Self, GitAiError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                GitAiError::Generic(format!(
+                    "Failed to parse backfill state {}: {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the state file, replacing it atomically so a killed run can't corrupt it
+    pub fn save(&self, path: &Path) -> Result<(), GitAiError> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn failed(&self) -> usize {
+        self.repos
+            .values()
+            .filter(|progress| progress.error.is_some() || !progress.failed.is_empty())
+            .count()
+    }
+
+    /// Whether the run got through every repository without failures
+    pub fn succeeded(&self) -> bool {
+        self.failed() == 0 && self.stopped.is_none()
+    }
+
+    pub fn render(&self, repos: &[BackfillRepo]) -> String {
+        let mut out = format!("git-ai ci backfill: {} repositories\n", repos.len());
+        let (mut complete, mut failed) = (0, 0);
+        for repo in repos {
+            let key = repo.key();
+            let progress = self.repos.get(&key).cloned().unwrap_or_default();
+            let (status, detail) = if let Some(error) = &progress.error {
+                failed += 1;
+                ("FAIL", error.lines().next().unwrap_or("").to_string())
+            } else if !progress.failed.is_empty() {
+                failed += 1;
+                (
+                    "FAIL",
+                    format!(
+                        "{} processed, {} commit(s) failed",
+                        progress.processed,
+                        progress.failed.len()
+                    ),
+                )
+            } else if progress.complete {
+                complete += 1;
+                ("DONE", format!("{} processed", progress.processed))
+            } else if progress.pending > 0 {
+                (
+                    "TODO",
+                    format!("would process {} request(s)", progress.pending),
+                )
+            } else {
+                ("TODO", "not finished".to_string())
+            };
+            out.push_str(&format!("  {:<5} {}  {}\n", status, key, detail));
+        }
+        out.push_str(&format!(
+            "{} complete, {} failed, {} remaining\n",
+            complete,
+            failed,
+            repos.len() - complete - failed
+        ));
+        if let Some(reason) = &self.stopped {
+            out.push_str(&format!("Stopped early: {}; run again to resume\n", reason));
+        }
+        out
+    }
+}
+
+pub struct BackfillOptions {
+    /// Unix timestamp of the oldest commit to look at; all history when unset
+    pub since: Option<i64>,
+    pub state_path: PathBuf,
+    /// Where repositories are cloned, one at a time
+    pub work_dir: PathBuf,
+    pub budgets: RateBudgets,
+    /// Report what would be processed without rewriting, pushing or saving progress
+    pub dry_run: bool,
+    pub no_cleanup: bool,
+}
+
+/// Reach `repo` with the forge settings and tokens in the environment: GITHUB_API_URL,
+/// GITHUB_SERVER_URL and GITHUB_TOKEN for GitHub; CI_SERVER_URL, CI_API_V4_URL and
+/// GITLAB_TOKEN (or GITLAB_GROUP_TOKEN) for GitLab
+fn forge_for(repo: &BackfillRepo) -> Result<SweepForge, GitAiError> {
+    match repo.provider {
+        Provider::Github => {
+            let token = env("GITHUB_TOKEN").ok_or_else(|| {
+                GitAiError::Generic("GITHUB_TOKEN environment variable not set".to_string())
+            })?;
+            Ok(SweepForge::github(
+                &env("GITHUB_API_URL").unwrap_or_else(|| "https://api.github.com".to_string()),
+                &env("GITHUB_SERVER_URL").unwrap_or_else(|| "https://github.com".to_string()),
+                &repo.path,
+                &token,
+                CiGitCredential::from_env("x-access-token", "GITHUB_TOKEN"),
+            ))
+        }
+        Provider::Gitlab => {
+            let server_url =
+                env("CI_SERVER_URL").unwrap_or_else(|| "https://gitlab.com".to_string());
+            let api_url = env("CI_API_V4_URL").unwrap_or_else(|| format!("{}/api/v4", server_url));
+            Ok(SweepForge::gitlab(
+                &api_url,
+                &server_url,
+                &repo.path,
+                &repo.path,
+                gitlab_api_auth()?,
+                gitlab_git_credential(),
+            ))
+        }
+    }
+}
+
+/// Backfill every repository in `repos` that the state file doesn't record as complete,
+/// returning the state. A repository that fails is recorded and the run moves on.
+pub fn run_backfill(
+    repos: &[BackfillRepo],
+    options: &mut BackfillOptions,
+) -> Result<BackfillState, GitAiError> {
+    let mut state = BackfillState::load(&options.state_path)?;
+    std::fs::create_dir_all(&options.work_dir)?;
+
+    for (index, repo) in repos.iter().enumerate() {
+        let key = repo.key();
+        if state.repos.get(&key).is_some_and(|p| p.complete) {
+            println!("[CI backfill] {} was completed by an earlier run", key);
+            continue;
+        }
+        if let Err(e) = deadline::check(&format!("backfilling {}", key)) {
+            println!("[CI backfill] Stopping: {}", e);
+            state.stopped = Some(e.to_string());
+            break;
+        }
+        println!(
+            "[CI backfill] ({}/{}) Backfilling {}",
+            index + 1,
+            repos.len(),
+            key
+        );
+        let progress = state.repos.entry(key.clone()).or_default();
+        progress.error = None;
+        let result = forge_for(repo)
+            .and_then(|forge| backfill_repo(&forge, repo, &key, &mut state, options));
+        match result {
+            Ok(()) => {}
+            // Progress so far is saved; the next run picks the repository up from there
+            Err(e @ GitAiError::DeadlineExceeded { .. }) => {
+                println!("[CI backfill] Stopping in {}: {}", key, e);
+                state.stopped = Some(e.to_string());
+            }
+            Err(e) => {
+                println!("[CI backfill] {} failed: {}", key, e);
+                state.repos.entry(key).or_default().error = Some(e.to_string());
+            }
+        }
+        if !options.dry_run {
+            state.save(&options.state_path)?;
+        }
+        if state.stopped.is_some() {
+            break;
+        }
+    }
+    Ok(state)
+}
+
+fn backfill_repo(
+    forge: &SweepForge,
+    repo: &BackfillRepo,
+    key: &str,
+    state: &mut BackfillState,
+    options: &mut BackfillOptions,
+) -> Result<(), GitAiError> {
+    let clone_dir = options.work_dir.join(key.replace([':', '/'], "_"));
+    // Start from a fresh clone; a killed run may have left a partial one
+    if clone_dir.exists() {
+        std::fs::remove_dir_all(&clone_dir)?;
+    }
+    let clone_dir_str = clone_dir.to_string_lossy().to_string();
+    let mut clone_args = forge_git_args(forge.credential.as_ref());
+    clone_args.push("clone".to_string());
+    if let Some(branch) = &repo.branch {
+        clone_args.extend(["--branch".to_string(), branch.clone()]);
+    }
+    clone_args.extend([forge.clone_url.clone(), clone_dir_str.clone()]);
+    {
+        let _timing = timings::phase(Phase::Clone);
+        exec_git(&clone_args)?;
+    }
+
+    let result = backfill_clone(forge, key, &clone_dir_str, state, options);
+    if !options.no_cleanup {
+        let _ = std::fs::remove_dir_all(&clone_dir);
+    }
+    result
+}
+
+fn backfill_clone(
+    forge: &SweepForge,
+    key: &str,
+    clone_dir: &str,
+    state: &mut BackfillState,
+    options: &mut BackfillOptions,
+) -> Result<(), GitAiError> {
+    let repo_args = git_args_for_dir(clone_dir, forge.credential.as_ref());
+    let repo = find_repository(&repo_args)?;
+    fetch_authorship_notes(&repo, "origin")?;
+
+    let mut log_args = repo_args.clone();
+    log_args.extend([
+        "log".to_string(),
+        "--first-parent".to_string(),
+        "--reverse".to_string(),
+        "--format=%H".to_string(),
+    ]);
+    if let Some(since) = options.since {
+        let since = DateTime::<Utc>::from_timestamp(since, 0)
+            .unwrap_or_default()
+            .to_rfc3339();
+        log_args.push(format!("--since={}", since));
+    }
+    log_args.push("HEAD".to_string());
+    let output = exec_git(&log_args)?;
+    let commits: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let host = forge.api_host();
+    let progress = state.repos.entry(key.to_string()).or_default();
+    // When the mark is gone from the history, e.g. after a force push, start over
+    let resume_from = progress
+        .high_water_mark
+        .as_ref()
+        .and_then(|mark| commits.iter().position(|sha| sha == mark))
+        .map_or(0, |index| index + 1);
+    for (index, sha) in commits.iter().enumerate() {
+        let progress = state.repos.entry(key.to_string()).or_default();
+        if index < resume_from && !progress.failed.contains_key(sha) {
+            continue;
+        }
+        if show_authorship_note(&repo, sha).is_some() {
+            progress.failed.remove(sha);
+            if index >= resume_from {
+                progress.high_water_mark = Some(sha.clone());
+            }
+            continue;
+        }
+
+        let mut lookup = Err(GitAiError::Generic(format!(
+            "{} rate-limited {} lookups in a row",
+            host, MAX_RATE_LIMITED_ATTEMPTS
+        )));
+        for _ in 0..MAX_RATE_LIMITED_ATTEMPTS {
+            options.budgets.acquire(&host)?;
+            match lookup_merged_request(forge, sha) {
+                Ok(Lookup::RateLimited(wait)) => {
+                    println!(
+                        "[CI backfill] {} is rate limiting; pausing it for {}s",
+                        host,
+                        wait.as_secs()
+                    );
+                    options.budgets.pause(&host, Instant::now() + wait);
+                }
+                Ok(Lookup::Found(request)) => {
+                    lookup = Ok(request);
+                    break;
+                }
+                Err(e @ GitAiError::DeadlineExceeded { .. }) => return Err(e),
+                Err(e) => {
+                    lookup = Err(e);
+                    break;
+                }
+            }
+        }
+
+        let progress = state.repos.entry(key.to_string()).or_default();
+        match lookup {
+            Err(e) => {
+                progress.failed.insert(sha.clone(), e.to_string());
+            }
+            Ok(None) => {
+                progress.failed.remove(sha);
+            }
+            Ok(Some(request)) if progress.requests.contains(&request.number) => {
+                progress.failed.remove(sha);
+            }
+            Ok(Some(_)) if options.dry_run => progress.pending += 1,
+            Ok(Some(request)) => match process_request(&repo_args, &request) {
+                Ok(result) => {
+                    println!(
+                        "[CI backfill] {} request #{}: {}",
+                        key,
+                        request.number,
+                        describe(&result)
+                    );
+                    progress.requests.insert(request.number);
+                    progress.processed += 1;
+                    progress.failed.remove(sha);
+                }
+                Err(e @ GitAiError::DeadlineExceeded { .. }) => return Err(e),
+                Err(e) => {
+                    progress.failed.insert(sha.clone(), e.to_string());
+                }
+            },
+        }
+        if index >= resume_from {
+            progress.high_water_mark = Some(sha.clone());
+        }
+        // The caller saves the rest, including when the run stops mid-repository
+        if !options.dry_run && (index + 1) % SAVE_EVERY_COMMITS == 0 {
+            state.save(&options.state_path)?;
+        }
+    }
+
+    let progress = state.repos.entry(key.to_string()).or_default();
+    progress.complete = !options.dry_run && progress.failed.is_empty();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_list() {
+        let repos = parse_repo_list(
+            "# platform team\ngithub acme/api\n\ngitlab  group/sub/project.git develop  # monorepo\n",
+        )
+        .unwrap();
+        assert_eq!(
+            repos,
+            vec![
+                BackfillRepo {
+                    provider: Provider::Github,
+                    path: "acme/api".to_string(),
+                    branch: None,
+                },
+                BackfillRepo {
+                    provider: Provider::Gitlab,
+                    path: "group/sub/project".to_string(),
+                    branch: Some("develop".to_string()),
+                },
+            ]
+        );
+        assert_eq!(repos[1].key(), "gitlab:group/sub/project");
+        assert!(parse_repo_list("bitbucket acme/api").is_err());
+        assert!(parse_repo_list("github acme").is_err());
+        assert!(parse_repo_list("github acme/api main extra").is_err());
+    }
+
+    #[test]
+    fn test_parse_rate_budget() {
+        assert_eq!(
+            RateBudget::parse("5000/h").unwrap(),
+            RateBudget {
+                requests: 5000,
+                per: Duration::from_secs(3600)
+            }
+        );
+        assert_eq!(
+            RateBudget::parse("2/30s").unwrap().per,
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            RateBudget::parse("300/min").unwrap().per,
+            Duration::from_secs(60)
+        );
+        assert!(RateBudget::parse("0/h").is_err());
+        assert!(RateBudget::parse("lots").is_err());
+    }
+
+    #[test]
+    fn test_budgets_wait_for_the_window_per_host() {
+        let mut budgets = RateBudgets::new(RateBudget {
+            requests: 2,
+            per: Duration::from_secs(60),
+        });
+        budgets.set(
+            "slow.example.com",
+            RateBudget {
+                requests: 1,
+                per: Duration::from_secs(600),
+            },
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        budgets.record("api.github.com", at(0));
+        assert_eq!(budgets.wait("api.github.com", at(1)), Duration::ZERO);
+        budgets.record("api.github.com", at(10));
+        // Full until the first request leaves the window
+        assert_eq!(
+            budgets.wait("api.github.com", at(20)),
+            Duration::from_secs(40)
+        );
+        assert_eq!(budgets.wait("api.github.com", at(60)), Duration::ZERO);
+
+        // Other hosts have their own budgets
+        budgets.record("slow.example.com", at(0));
+        assert_eq!(
+            budgets.wait("slow.example.com", at(100)),
+            Duration::from_secs(500)
+        );
+        assert_eq!(budgets.wait("gitlab.com", at(100)), Duration::ZERO);
+
+        // A rate-limited host waits out its pause even with budget left
+        budgets.pause("gitlab.com", at(130));
+        assert_eq!(budgets.wait("gitlab.com", at(100)), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_state_round_trips_and_renders() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        assert!(BackfillState::load(&path).unwrap().repos.is_empty());
+
+        let mut state = BackfillState::default();
+        state.repos.insert(
+            "github:acme/api".to_string(),
+            RepoProgress {
+                complete: true,
+                high_water_mark: Some("abc".to_string()),
+                processed: 3,
+                ..Default::default()
+            },
+        );
+        state.repos.insert(
+            "gitlab:group/project".to_string(),
+            RepoProgress {
+                error: Some("clone failed\ndetails".to_string()),
+                ..Default::default()
+            },
+        );
+        state.save(&path).unwrap();
+        let mut loaded = BackfillState::load(&path).unwrap();
+        assert_eq!(
+            loaded.repos["github:acme/api"].high_water_mark.as_deref(),
+            Some("abc")
+        );
+
+        let repos =
+            parse_repo_list("github acme/api\ngitlab group/project\ngithub acme/web").unwrap();
+        let rendered = loaded.render(&repos);
+        assert!(rendered.contains("  DONE  github:acme/api  3 processed\n"));
+        assert!(rendered.contains("  FAIL  gitlab:group/project  clone failed\n"));
+        assert!(rendered.contains("  TODO  github:acme/web  not finished\n"));
+        assert!(rendered.ends_with("1 complete, 1 failed, 1 remaining\n"));
+
+        loaded.stopped = Some("CI deadline of 1h exceeded".to_string());
+        assert!(
+            loaded
+                .render(&repos)
+                .ends_with("Stopped early: CI deadline of 1h exceeded; run again to resume\n")
+        );
+    }
+}
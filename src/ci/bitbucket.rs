@@ -0,0 +1,407 @@
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::credentials::{CiGitCredential, forge_git_args, git_args_for_dir};
+use crate::ci::http;
+use crate::ci::{env, required_env};
+use crate::error::GitAiError;
+use crate::git::repository::exec_git;
+use crate::git::repository::find_repository;
+use crate::observability::timings::{self, Phase};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const BITBUCKET_PIPELINES_TEMPLATE_YAML: &str = include_str!("workflow_templates/bitbucket.yaml");
+
+const DEFAULT_BITBUCKET_API_URL: &str = "https://api.bitbucket.org/2.0";
+
+/// Pull request from the Bitbucket 2.0 API
+#[derive(Debug, Clone, Deserialize)]
+struct BitbucketPullRequest {
+    id: u64,
+    title: Option<String>,
+    state: String,
+    source: BitbucketPullRequestEndpoint,
+    destination: BitbucketPullRequestEndpoint,
+    merge_commit: Option<BitbucketCommitRef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BitbucketPullRequestEndpoint {
+    branch: BitbucketBranch,
+    commit: Option<BitbucketCommitRef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BitbucketBranch {
+    name: String,
+}
+
+/// Bitbucket abbreviates the hashes nested in pull requests to 12 characters
+#[derive(Debug, Clone, Deserialize)]
+struct BitbucketCommitRef {
+    hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketPage<T> {
+    values: Vec<T>,
+}
+
+impl BitbucketPullRequest {
+    fn merged_as(&self, commit_sha: &str) -> bool {
+        self.state == "MERGED"
+            && self
+                .merge_commit
+                .as_ref()
+                .is_some_and(|commit| is_abbreviation_of(&commit.hash, commit_sha))
+    }
+}
+
+/// Whether `short` abbreviates the full hash `sha`
+fn is_abbreviation_of(short: &str, sha: &str) -> bool {
+    short.len() >= 7 && sha.starts_with(short)
+}
+
+/// How to authenticate Bitbucket API requests
+pub struct BitbucketApiAuth {
+    /// Variable the token came from
+    pub env_var: &'static str,
+    /// Value of the `Authorization` header
+    pub header: String,
+}
+
+/// Authenticate with a repository, project or workspace access token
+/// (BITBUCKET_ACCESS_TOKEN, sent as a bearer token), or an app password
+/// (BITBUCKET_USERNAME and BITBUCKET_APP_PASSWORD, sent as basic auth). Pipelines
+/// provides no token of its own, so one of these has to be a repository variable.
+pub fn bitbucket_api_auth() -> Result<BitbucketApiAuth, GitAiError> {
+    if let Some(token) = env("BITBUCKET_ACCESS_TOKEN") {
+        return Ok(BitbucketApiAuth {
+            env_var: "BITBUCKET_ACCESS_TOKEN",
+            header: format!("Bearer {}", token),
+        });
+    }
+    if let (Some(username), Some(password)) =
+        (env("BITBUCKET_USERNAME"), env("BITBUCKET_APP_PASSWORD"))
+    {
+        return Ok(BitbucketApiAuth {
+            env_var: "BITBUCKET_APP_PASSWORD",
+            header: format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", username, password))
+            ),
+        });
+    }
+    Err(GitAiError::Generic(
+        "Neither BITBUCKET_ACCESS_TOKEN nor BITBUCKET_USERNAME and BITBUCKET_APP_PASSWORD is set"
+            .to_string(),
+    ))
+}
+
+/// Credential for git operations, passed through an ephemeral credential helper:
+/// - BITBUCKET_ACCESS_TOKEN, which Bitbucket expects with the `x-token-auth` username
+/// - BITBUCKET_USERNAME and BITBUCKET_APP_PASSWORD
+pub fn bitbucket_git_credential() -> Option<CiGitCredential> {
+    CiGitCredential::from_env("x-token-auth", "BITBUCKET_ACCESS_TOKEN")
+        .or_else(|| CiGitCredential::from_env_pair("BITBUCKET_USERNAME", "BITBUCKET_APP_PASSWORD"))
+}
+
+/// Clone URL for the repository. Pipelines sets BITBUCKET_GIT_HTTP_ORIGIN with an
+/// `http://` scheme for bitbucket.org, which only redirects, so it's upgraded to https.
+fn clone_url_from_origin(origin: &str) -> String {
+    let origin = origin.trim_end_matches('/');
+    let origin = match origin.strip_prefix("http://bitbucket.org/") {
+        Some(path) => format!("https://bitbucket.org/{}", path),
+        None => origin.to_string(),
+    };
+    format!("{}.git", origin)
+}
+
+fn api_get(endpoint: &str, auth: &BitbucketApiAuth) -> Result<String, GitAiError> {
+    println!("[Bitbucket Pipelines] Querying API: {}", endpoint);
+    let _timing = timings::phase(Phase::Api);
+    let request = http::get(endpoint)
+        .with_header("Authorization", &auth.header)
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        );
+    let response = http::send(request, "Bitbucket API request")?;
+    let body = response.as_str().unwrap_or("").to_string();
+    if response.status_code != 200 {
+        let mut message = format!(
+            "Bitbucket API returned status {}: {}",
+            response.status_code, body
+        );
+        if matches!(response.status_code, 401 | 403) {
+            message.push_str(&format!(
+                "\n{} needs Pull requests: Read and Repositories: Write permissions",
+                auth.env_var
+            ));
+        }
+        return Err(GitAiError::Generic(message));
+    }
+    Ok(body)
+}
+
+/// Find the merged pull request that produced BITBUCKET_COMMIT. In a pull request
+/// pipeline (BITBUCKET_PR_ID set) only that pull request is considered; otherwise the
+/// most recently updated merged pull requests are searched. Returns None if the commit
+/// didn't come from a merged pull request.
+pub fn get_bitbucket_ci_context() -> Result<Option<CiContext>, GitAiError> {
+    let api_url = env("BITBUCKET_API_URL").unwrap_or_else(|| DEFAULT_BITBUCKET_API_URL.to_string());
+    let commit_sha = required_env("BITBUCKET_COMMIT")?;
+    let workspace = required_env("BITBUCKET_WORKSPACE")?;
+    let repo_slug = required_env("BITBUCKET_REPO_SLUG")?;
+    let origin = required_env("BITBUCKET_GIT_HTTP_ORIGIN")?;
+    let pr_id = env("BITBUCKET_PR_ID");
+    let auth = bitbucket_api_auth()?;
+
+    println!("[Bitbucket Pipelines] Environment:");
+    println!("  Commit: {}", commit_sha);
+    println!("  Repository: {}/{}", workspace, repo_slug);
+    if let Some(pr_id) = &pr_id {
+        println!("  Pull request: #{}", pr_id);
+    }
+    println!("  Auth: {}", auth.env_var);
+
+    let repo_endpoint = format!("{}/repositories/{}/{}", api_url, workspace, repo_slug);
+    let pull_requests: Vec<BitbucketPullRequest> = match &pr_id {
+        Some(pr_id) => {
+            let body = api_get(&format!("{}/pullrequests/{}", repo_endpoint, pr_id), &auth)?;
+            vec![serde_json::from_str(&body).map_err(|e| {
+                GitAiError::Generic(format!("Failed to parse Bitbucket API response: {}", e))
+            })?]
+        }
+        None => {
+            let body = api_get(
+                &format!(
+                    "{}/pullrequests?state=MERGED&sort=-updated_on&pagelen=50",
+                    repo_endpoint
+                ),
+                &auth,
+            )?;
+            serde_json::from_str::<BitbucketPage<BitbucketPullRequest>>(&body)
+                .map_err(|e| {
+                    GitAiError::Generic(format!("Failed to parse Bitbucket API response: {}", e))
+                })?
+                .values
+        }
+    };
+
+    println!(
+        "[Bitbucket Pipelines] Found {} merged pull request(s) to check",
+        pull_requests.len()
+    );
+    for pr in &pull_requests {
+        println!(
+            "[Bitbucket Pipelines] PR #{}: \"{}\" ({})",
+            pr.id,
+            pr.title.as_deref().unwrap_or("(no title)"),
+            pr.state
+        );
+        println!("    source: {}", pr.source.branch.name);
+        println!("    destination: {}", pr.destination.branch.name);
+        println!(
+            "    merge_commit: {}",
+            pr.merge_commit
+                .as_ref()
+                .map(|c| c.hash.as_str())
+                .unwrap_or("(none)")
+        );
+    }
+
+    let Some(pr) = pull_requests
+        .into_iter()
+        .find(|pr| pr.merged_as(&commit_sha))
+    else {
+        println!("[Bitbucket Pipelines] No merged pull request produced this commit. Skipping...");
+        return Ok(None);
+    };
+    println!("[Bitbucket Pipelines] Found matching PR #{}", pr.id);
+
+    let head_short = pr
+        .source
+        .commit
+        .as_ref()
+        .map(|commit| commit.hash.clone())
+        .ok_or_else(|| {
+            GitAiError::Generic(format!("Pull request #{} has no source commit", pr.id))
+        })?;
+    // The pull request only names an abbreviated head; the commit endpoint has the full hash
+    let head_sha = serde_json::from_str::<BitbucketCommitRef>(&api_get(
+        &format!("{}/commit/{}", repo_endpoint, head_short),
+        &auth,
+    )?)
+    .map_err(|e| GitAiError::Generic(format!("Failed to parse Bitbucket API response: {}", e)))?
+    .hash;
+
+    let credential = bitbucket_git_credential();
+    if credential.is_none() {
+        println!("[Bitbucket Pipelines] Warning: no git credentials available, clone may fail");
+    }
+
+    let clone_dir = "git-ai-ci-clone".to_string();
+    let base_ref = pr.destination.branch.name.clone();
+    let head_ref = pr.source.branch.name.clone();
+    println!("[Bitbucket Pipelines] Cloning repository...");
+    let mut clone_args = forge_git_args(credential.as_ref());
+    clone_args.extend([
+        "clone".to_string(),
+        "--branch".to_string(),
+        base_ref.clone(),
+        clone_url_from_origin(&origin),
+        clone_dir.clone(),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Clone);
+        exec_git(&clone_args)?;
+    }
+    let repo_args = git_args_for_dir(&clone_dir, credential.as_ref());
+
+    // Bitbucket keeps no ref for a pull request's head, so fetch its source branch, or
+    // the head commit itself when the branch was closed on merge
+    let local_ref = format!("refs/bitbucket/pr/{}", pr.id);
+    let fetch = |refspec: String| {
+        let mut fetch_args = repo_args.clone();
+        fetch_args.extend(["fetch".to_string(), "origin".to_string(), refspec]);
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&fetch_args)
+    };
+    println!(
+        "[Bitbucket Pipelines] Fetching PR commits from {}...",
+        head_ref
+    );
+    if fetch(format!("refs/heads/{}:{}", head_ref, local_ref)).is_err() {
+        println!(
+            "[Bitbucket Pipelines] Branch {} is gone, fetching {} directly",
+            head_ref, head_sha
+        );
+        fetch(format!("{}:{}", head_sha, local_ref)).map_err(|e| {
+            GitAiError::Generic(format!(
+                "Could not fetch the head of pull request #{} ({}): {}\nIf the source branch is closed on merge, the commit may no longer be reachable",
+                pr.id, head_sha, e
+            ))
+        })?;
+    }
+
+    let repo = find_repository(&repo_args)?;
+
+    println!(
+        "[Bitbucket Pipelines] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        commit_sha, head_sha, head_ref, base_ref
+    );
+
+    Ok(Some(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: commit_sha,
+            head_ref,
+            head_sha,
+            base_ref,
+            base_sha: pr
+                .destination
+                .commit
+                .map(|commit| commit.hash)
+                .unwrap_or_default(),
+            merge_request: None,
+        },
+        temp_dir: PathBuf::from(clone_dir),
+    }))
+}
+
+/// Knobs for rendering the Bitbucket Pipelines template
+#[derive(Debug, Clone, Default)]
+pub struct BitbucketTemplateOptions {
+    /// Branch (or glob) the step runs on; `main` when unset
+    pub branch: Option<String>,
+}
+
+pub fn render_bitbucket_pipelines_yaml(
+    options: &BitbucketTemplateOptions,
+) -> Result<String, GitAiError> {
+    let branch = options.branch.as_deref().unwrap_or("main");
+    if branch.is_empty()
+        || branch.contains(['"', '\'', '\\'])
+        || branch.contains(char::is_whitespace)
+    {
+        return Err(GitAiError::Generic(format!(
+            "Invalid branch name '{}'",
+            branch
+        )));
+    }
+    Ok(BITBUCKET_PIPELINES_TEMPLATE_YAML.replace("{{BRANCH}}", branch))
+}
+
+/// Print the Bitbucket Pipelines YAML for users to copy into their bitbucket-pipelines.yml
+pub fn print_bitbucket_pipelines_yaml(
+    options: &BitbucketTemplateOptions,
+) -> Result<(), GitAiError> {
+    let yaml = render_bitbucket_pipelines_yaml(options)?;
+    println!("Add the following to your bitbucket-pipelines.yml:");
+    println!();
+    println!("{}", yaml);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pull_request(state: &str, merge_commit: Option<&str>) -> BitbucketPullRequest {
+        serde_json::from_value(serde_json::json!({
+            "id": 3,
+            "title": "Add feature",
+            "state": state,
+            "source": { "branch": { "name": "feature" }, "commit": { "hash": "aaaaaaaaaaaa" } },
+            "destination": { "branch": { "name": "main" }, "commit": { "hash": "bbbbbbbbbbbb" } },
+            "merge_commit": merge_commit.map(|hash| serde_json::json!({ "hash": hash })),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merged_as_matches_abbreviated_merge_commit() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        assert!(pull_request("MERGED", Some("0123456789ab")).merged_as(sha));
+        assert!(pull_request("MERGED", Some(sha)).merged_as(sha));
+        assert!(!pull_request("MERGED", Some("0123456789ac")).merged_as(sha));
+        assert!(!pull_request("MERGED", Some("0123")).merged_as(sha));
+        assert!(!pull_request("MERGED", None).merged_as(sha));
+        assert!(!pull_request("DECLINED", Some("0123456789ab")).merged_as(sha));
+    }
+
+    #[test]
+    fn test_clone_url_upgrades_bitbucket_org_to_https() {
+        assert_eq!(
+            clone_url_from_origin("http://bitbucket.org/team/repo"),
+            "https://bitbucket.org/team/repo.git"
+        );
+        assert_eq!(
+            clone_url_from_origin("http://127.0.0.1:8080/team/repo/"),
+            "http://127.0.0.1:8080/team/repo.git"
+        );
+    }
+
+    #[test]
+    fn test_template_renders_branch() {
+        let yaml = render_bitbucket_pipelines_yaml(&BitbucketTemplateOptions::default()).unwrap();
+        assert!(!yaml.contains("{{"));
+        assert!(yaml.contains("    'main':\n"));
+        assert!(yaml.contains("git-ai ci bitbucket run"));
+
+        let yaml = render_bitbucket_pipelines_yaml(&BitbucketTemplateOptions {
+            branch: Some("release/*".to_string()),
+        })
+        .unwrap();
+        assert!(yaml.contains("    'release/*':\n"));
+
+        assert!(
+            render_bitbucket_pipelines_yaml(&BitbucketTemplateOptions {
+                branch: Some("main' || x".to_string()),
+            })
+            .is_err()
+        );
+    }
+}
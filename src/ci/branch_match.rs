@@ -0,0 +1,107 @@
+//! Finding which branch a commit merged without a forge API to ask: for CI systems that
+//! only report the built commit, the branch is the one whose changes the commit
+//! reproduces, as a squash or a rebase.
+
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git, exec_git_stdin};
+
+/// A branch that may have been merged, and the revision to compare as its head
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeCandidate {
+    pub branch: String,
+    pub rev: String,
+}
+
+/// The first of `candidates` whose changes since it forked from the target are what
+/// `commit_sha` brought in: as one squashed commit, or as that many rebased ones. A
+/// candidate already at `commit_sha` was fast-forwarded. Candidates on `base_ref` itself,
+/// unknown revisions and heads already in the commit's history are skipped. Returns the
+/// branch name and head.
+pub fn find_merged_branch(
+    repo: &Repository,
+    commit_sha: &str,
+    base_ref: &str,
+    candidates: &[MergeCandidate],
+) -> Result<Option<(String, String)>, GitAiError> {
+    let mut squashed_patch_id: Option<Option<String>> = None;
+    for candidate in candidates {
+        if candidate.branch == base_ref {
+            continue;
+        }
+        let Ok(head_sha) = repo
+            .revparse_single(&candidate.rev)
+            .map(|object| object.id())
+        else {
+            continue;
+        };
+        if head_sha == commit_sha {
+            return Ok(Some((candidate.branch.clone(), head_sha)));
+        }
+        // Merged (or never diverged) branches are already in the commit's history
+        if repo.is_ancestor(&head_sha, commit_sha) {
+            continue;
+        }
+        let Ok(fork_point) = repo.merge_base(head_sha.clone(), commit_sha.to_string()) else {
+            continue;
+        };
+        let Some(branch_patch_id) = patch_id(repo, &fork_point, &head_sha) else {
+            continue;
+        };
+
+        let squashed = squashed_patch_id
+            .get_or_insert_with(|| patch_id(repo, &format!("{}^", commit_sha), commit_sha));
+        let branch_commits = count_commits(repo, &fork_point, &head_sha);
+        let rebased = || {
+            branch_commits > 1
+                && patch_id(
+                    repo,
+                    &format!("{}~{}", commit_sha, branch_commits),
+                    commit_sha,
+                )
+                .as_ref()
+                    == Some(&branch_patch_id)
+        };
+        if squashed.as_ref() == Some(&branch_patch_id) || rebased() {
+            return Ok(Some((candidate.branch.clone(), head_sha)));
+        }
+    }
+    Ok(None)
+}
+
+fn count_commits(repo: &Repository, from: &str, to: &str) -> usize {
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "rev-list".to_string(),
+        "--count".to_string(),
+        format!("{}..{}", from, to),
+    ]);
+    exec_git(&args)
+        .ok()
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Stable patch ID of the diff between two revisions, which ignores line numbers and
+/// whitespace so the same change matches after the target branch moved on. None for an
+/// empty diff or an unknown revision.
+pub fn patch_id(repo: &Repository, from: &str, to: &str) -> Option<String> {
+    let mut diff_args = repo.global_args_for_exec();
+    diff_args.extend([
+        "diff".to_string(),
+        "--no-color".to_string(),
+        "--no-ext-diff".to_string(),
+        from.to_string(),
+        to.to_string(),
+    ]);
+    let diff = exec_git(&diff_args).ok()?.stdout;
+    if diff.is_empty() {
+        return None;
+    }
+    let mut patch_id_args = repo.global_args_for_exec();
+    patch_id_args.extend(["patch-id".to_string(), "--stable".to_string()]);
+    let output = exec_git_stdin(&patch_id_args, &diff).ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+}
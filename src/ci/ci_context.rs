@@ -2,12 +2,34 @@ use crate::authorship::authorship_log_serialization::AuthorshipLog;
 use crate::authorship::rebase_authorship::{
     rewrite_authorship_after_rebase_v2, rewrite_authorship_after_squash_or_rebase,
 };
+use crate::authorship::security_tags::tag_rewritten_commits;
+use crate::ci::branch_match::patch_id;
+use crate::ci::github_app::refresh_app_token;
+use crate::ci::handoff::{NotesArtifact, artifact_path};
+use crate::ci::provenance::{
+    NoteSigner, detect_provenance, notes_tip, sign_notes_since, sign_notes_tip, stamp_provenance,
+};
 use crate::error::GitAiError;
-use crate::git::refs::{get_reference_as_authorship_log_v3, show_authorship_note};
-use crate::git::repository::{CommitRange, Repository};
-use crate::git::sync_authorship::fetch_authorship_notes;
+use crate::git::refs::{
+    get_authorship, get_reference_as_authorship_log_v3, notes_add, show_authorship_note,
+};
+use crate::git::repository::{CommitRange, Repository, exec_git};
+use crate::git::sync_authorship::{fetch_authorship_notes, push_authorship_notes_with};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Print the notes a run would write instead of pushing them (`--dry-run`)
+pub fn set_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
 
 #[derive(Debug)]
 pub enum CiEvent {
@@ -18,6 +40,17 @@ pub enum CiEvent {
         base_ref: String,
         #[allow(dead_code)]
         base_sha: String,
+        /// Number of the merge/pull request, when known
+        merge_request: Option<u64>,
+    },
+    /// A push straight to a branch, with no merge/pull request behind it. The pushed
+    /// commits' notes can only come from the pusher's machine, so this checks they made
+    /// it to the remote.
+    Push {
+        ref_name: String,
+        /// The branch tip before the push; None for a new branch
+        before_sha: Option<String>,
+        after_sha: String,
     },
 }
 
@@ -28,6 +61,8 @@ pub enum CiRunResult {
     AuthorshipRewritten {
         #[allow(dead_code)]
         authorship_log: AuthorshipLog,
+        /// Commits that got notes: the squash commit, or each rebased commit
+        commits: Vec<String>,
     },
     /// Skipped: merge commit has multiple parents (simple merge - authorship already present)
     SkippedSimpleMerge,
@@ -40,6 +75,17 @@ pub enum CiRunResult {
     },
     /// No AI authorship to track (pre-git-ai commits or human-only code)
     NoAuthorshipAvailable,
+    /// Rewrote the notes but wrote them to an artifact for another job to push, as
+    /// for a pull request from a fork
+    NotesWrittenToArtifact { commits: Vec<String>, path: PathBuf },
+    /// Rewrote the notes in the clone and printed them without pushing (`--dry-run`)
+    DryRun { commits: Vec<String> },
+    /// Checked the notes of a direct push
+    PushChecked {
+        commits: usize,
+        /// Pushed commits without an authorship note on the remote
+        missing: Vec<String>,
+    },
 }
 
 #[derive(Debug)]
@@ -68,8 +114,11 @@ impl CiContext {
                 head_sha,
                 base_ref,
                 base_sha: _,
+                merge_request: _,
             } => {
                 println!("Working repository is in {}", self.repo.path().display());
+                // Checked up front so a bad value fails before any rewriting
+                let signer = NoteSigner::from_env()?;
 
                 println!("Fetching authorship history");
                 // Ensure we have the full authorship history before checking for existing notes
@@ -152,20 +201,22 @@ impl CiContext {
                 );
 
                 // For multi-commit PRs, check if this is a rebase merge (multiple new commits)
-                // by walking back from merge_commit_sha
+                // by matching the commits behind merge_commit_sha to the PR's by patch ID
+                let mut rewritten_commits = vec![merge_commit_sha.clone()];
                 if original_commits.len() > 1 {
-                    // Try to find the new rebased commits
-                    // Walk back from merge_commit_sha the same number of commits as original
-                    let new_commits =
-                        self.get_rebased_commits(merge_commit_sha, original_commits.len());
-
-                    if new_commits.len() == original_commits.len() {
+                    if let Some(rebased) =
+                        self.match_rebased_commits(merge_commit_sha, &original_commits)
+                    {
+                        let new_commits: Vec<String> =
+                            rebased.iter().map(|(_, new)| new.clone()).collect();
                         println!(
                             "Detected rebase merge: {} original -> {} new commits",
                             original_commits.len(),
                             new_commits.len()
                         );
-                        // Rebase merge - use v2 which writes authorship to each rebased commit
+                        self.carry_over_rebased_notes(&rebased)?;
+                        // Rebase merge - v2 writes authorship to each rebased commit the
+                        // carry-over left without a note
                         rewrite_authorship_after_rebase_v2(
                             &self.repo,
                             head_sha,
@@ -173,6 +224,7 @@ impl CiContext {
                             &new_commits,
                             "", // human_author not used
                         )?;
+                        rewritten_commits = new_commits;
                     } else {
                         println!(
                             "Detected squash merge: {} original commits -> 1 merge commit",
@@ -205,10 +257,71 @@ impl CiContext {
                 // Check if authorship was created for THIS specific commit
                 match get_reference_as_authorship_log_v3(&self.repo, merge_commit_sha) {
                     Ok(authorship_log) => {
+                        let stamped_from = notes_tip(&self.repo);
+                        // Squashing and rebasing moved the hunks the findings were on
+                        tag_rewritten_commits(&self.repo, &rewritten_commits)?;
+
+                        if dry_run() {
+                            for commit in &rewritten_commits {
+                                println!("Would write the note of {}:", commit);
+                                println!(
+                                    "{}",
+                                    show_authorship_note(&self.repo, commit).unwrap_or_default()
+                                );
+                            }
+                            println!("Dry run, not pushing authorship.");
+                            return Ok(CiRunResult::DryRun {
+                                commits: rewritten_commits,
+                            });
+                        }
+
+                        if let Some(path) = artifact_path() {
+                            // A fork's token can't push; the ingesting job stamps and signs
+                            NotesArtifact::collect(&self.repo, &self.event, &rewritten_commits)?
+                                .write(&path)?;
+                            println!(
+                                "Wrote the notes of {} commit(s) to {} for an ingest job to push",
+                                rewritten_commits.len(),
+                                path.display()
+                            );
+                            return Ok(CiRunResult::NotesWrittenToArtifact {
+                                commits: rewritten_commits,
+                                path,
+                            });
+                        }
+
+                        let mut provenance = detect_provenance(|name| std::env::var(name).ok());
+                        provenance.signed_with = signer.as_ref().map(|s| s.name().to_string());
+                        println!(
+                            "Recording provenance: {} {}",
+                            provenance.ci,
+                            provenance
+                                .pipeline_url
+                                .as_deref()
+                                .unwrap_or("(no pipeline URL)")
+                        );
+                        stamp_provenance(&self.repo, &rewritten_commits, &provenance)?;
+                        if let Some(signer) = &signer {
+                            // One signed commit introduces the stamped notes, so a verifier
+                            // can tell them from notes merged in or edited later
+                            sign_notes_since(&self.repo, signer, stamped_from.as_deref())?;
+                        }
+
                         println!("Pushing authorship...");
-                        self.repo.push_authorship("origin")?;
+                        // The rewrite can outlast a GitHub App's hour-long token
+                        refresh_app_token()?;
+                        push_authorship_notes_with(&self.repo, "origin", |repo| match &signer {
+                            Some(signer) => {
+                                println!("Signing notes commit with {}", signer.name());
+                                sign_notes_tip(repo, signer)
+                            }
+                            None => Ok(()),
+                        })?;
                         println!("Pushed authorship. Done.");
-                        Ok(CiRunResult::AuthorshipRewritten { authorship_log })
+                        Ok(CiRunResult::AuthorshipRewritten {
+                            authorship_log,
+                            commits: rewritten_commits,
+                        })
                     }
                     Err(e) => {
                         if show_authorship_note(&self.repo, merge_commit_sha).is_some() {
@@ -221,9 +334,72 @@ impl CiContext {
                     }
                 }
             }
+            CiEvent::Push {
+                ref_name,
+                before_sha,
+                after_sha,
+            } => self.run_push(ref_name, before_sha.as_deref(), after_sha),
         }
     }
 
+    fn run_push(
+        &self,
+        ref_name: &str,
+        before_sha: Option<&str>,
+        after_sha: &str,
+    ) -> Result<CiRunResult, GitAiError> {
+        println!("Working repository is in {}", self.repo.path().display());
+        println!("Fetching authorship history");
+        fetch_authorship_notes(&self.repo, "origin")?;
+        println!("Fetched authorship history");
+
+        let commits = self.pushed_commits(before_sha, after_sha)?;
+        println!(
+            "Checking {} commit(s) pushed to {}",
+            commits.len(),
+            ref_name
+        );
+        let missing: Vec<String> = commits
+            .iter()
+            .filter(|sha| show_authorship_note(&self.repo, sha).is_none())
+            .cloned()
+            .collect();
+        for sha in &missing {
+            println!("{} has no authorship note on the remote", sha);
+        }
+        Ok(CiRunResult::PushChecked {
+            commits: commits.len(),
+            missing,
+        })
+    }
+
+    /// Commits a push added, oldest first. Without the old tip (a new branch, or a force
+    /// push that dropped it) only the new tip is checked.
+    fn pushed_commits(
+        &self,
+        before_sha: Option<&str>,
+        after_sha: &str,
+    ) -> Result<Vec<String>, GitAiError> {
+        let Some(before_sha) =
+            before_sha.filter(|sha| self.repo.find_commit(sha.to_string()).is_ok())
+        else {
+            return Ok(vec![after_sha.to_string()]);
+        };
+        let mut args = self.repo.global_args_for_exec();
+        args.extend([
+            "rev-list".to_string(),
+            "--reverse".to_string(),
+            after_sha.to_string(),
+            // Excluding rather than `before..after` also handles force pushes
+            format!("^{}", before_sha),
+        ]);
+        let output = exec_git(&args)?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect())
+    }
+
     pub fn teardown(&self) -> Result<(), GitAiError> {
         // Skip cleanup if temp_dir is empty (repository was provided externally)
         if self.temp_dir.as_os_str().is_empty() {
@@ -233,32 +409,112 @@ impl CiContext {
         Ok(())
     }
 
-    /// Get the rebased commits by walking back from merge_commit_sha.
-    /// For a rebase merge with N original commits, there should be N new commits
-    /// ending at merge_commit_sha.
-    fn get_rebased_commits(&self, merge_commit_sha: &str, expected_count: usize) -> Vec<String> {
-        let mut commits = Vec::new();
+    /// Pair the commits a rebase merge landed, oldest first, with the pull request
+    /// commits they replay, by patch ID. `original_commits` are newest first, as rev-list
+    /// lists them; walking back from `merge_commit_sha`, each commit must replay an older
+    /// original than the one after it. None unless more
+    /// than one matched or every unmatched original is empty, which tells a rebase from a
+    /// squash, whose one commit replays none of the originals by itself. Originals the
+    /// rebase dropped as already applied are skipped.
+    fn match_rebased_commits(
+        &self,
+        merge_commit_sha: &str,
+        original_commits: &[String],
+    ) -> Option<Vec<(String, String)>> {
+        let original_patch_ids: Vec<Option<String>> = original_commits
+            .iter()
+            .map(|commit| patch_id(&self.repo, &format!("{}^", commit), commit))
+            .collect();
+
+        let mut rebased = Vec::new();
+        let mut next_original = 0;
         let mut current_sha = merge_commit_sha.to_string();
+        while next_original < original_commits.len() {
+            let Some(new_patch_id) =
+                patch_id(&self.repo, &format!("{}^", current_sha), &current_sha)
+            else {
+                break;
+            };
+            let Some(offset) = original_patch_ids[next_original..]
+                .iter()
+                .position(|id| id.as_ref() == Some(&new_patch_id))
+            else {
+                break;
+            };
+            let index = next_original + offset;
+            rebased.push((original_commits[index].clone(), current_sha.clone()));
+            next_original = index + 1;
+
+            // A merge commit or the root ends the linear run the rebase landed
+            let Ok(commit) = self.repo.find_commit(current_sha.clone()) else {
+                break;
+            };
+            let parents: Vec<_> = commit.parents().collect();
+            if parents.len() != 1 {
+                break;
+            }
+            current_sha = parents[0].id().to_string();
+        }
 
-        for _ in 0..expected_count {
-            commits.push(current_sha.clone());
+        let matched: HashSet<&String> = rebased.iter().map(|(original, _)| original).collect();
+        let only_empty_unmatched = original_commits
+            .iter()
+            .zip(&original_patch_ids)
+            .all(|(commit, id)| matched.contains(commit) || id.is_none());
+        if rebased.is_empty() || (rebased.len() == 1 && !only_empty_unmatched) {
+            return None;
+        }
+        rebased.reverse();
+        Some(rebased)
+    }
 
-            // Get the parent of current commit
-            match self.repo.find_commit(current_sha.clone()) {
-                Ok(commit) => {
-                    let parents: Vec<_> = commit.parents().collect();
-                    if parents.len() != 1 {
-                        // Not a linear chain (merge commit or root), stop here
-                        break;
-                    }
-                    current_sha = parents[0].id().to_string();
-                }
-                Err(_) => break,
+    /// Copy each original commit's note to the commit that replays it when the files the
+    /// note attributes are the same in both, so its line numbers still hold. Rebased
+    /// commits whose files differ (the base changed them too) are left without a note.
+    fn carry_over_rebased_notes(&self, rebased: &[(String, String)]) -> Result<(), GitAiError> {
+        for (original, new) in rebased {
+            if show_authorship_note(&self.repo, new).is_some() {
+                continue;
             }
+            let Some(mut log) = get_authorship(&self.repo, original) else {
+                continue;
+            };
+            let paths: Vec<&str> = log
+                .attestations
+                .iter()
+                .map(|attestation| attestation.file_path.as_str())
+                .collect();
+            if !self.same_files(original, new, &paths) {
+                continue;
+            }
+            log.metadata.base_commit_sha = new.clone();
+            let content = log.serialize_to_string().map_err(|_| {
+                GitAiError::Generic(format!("Failed to serialize authorship for {}", new))
+            })?;
+            notes_add(&self.repo, new, &content)?;
+            println!("Carried over the authorship of {} to {}", original, new);
         }
+        Ok(())
+    }
 
-        // Reverse to get oldest-to-newest order (same as original_commits)
-        commits.reverse();
-        commits
+    /// Whether each of `paths` has the same contents in both commits
+    fn same_files(&self, from: &str, to: &str, paths: &[&str]) -> bool {
+        let tree = |sha: &str| {
+            self.repo
+                .find_commit(sha.to_string())
+                .and_then(|c| c.tree())
+        };
+        let (Ok(from_tree), Ok(to_tree)) = (tree(from), tree(to)) else {
+            return false;
+        };
+        paths.iter().all(|path| {
+            match (
+                from_tree.get_path(Path::new(path)),
+                to_tree.get_path(Path::new(path)),
+            ) {
+                (Ok(from_entry), Ok(to_entry)) => from_entry.id() == to_entry.id(),
+                _ => false,
+            }
+        })
     }
 }
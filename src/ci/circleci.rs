@@ -0,0 +1,383 @@
+use crate::ci::branch_match::{MergeCandidate, find_merged_branch};
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::credentials::git_args_for_dir;
+use crate::ci::env;
+use crate::ci::http;
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git, find_repository};
+use crate::observability::timings::{self, Phase};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const CIRCLECI_CONFIG_TEMPLATE_YAML: &str = include_str!("workflow_templates/circleci.yml");
+
+const DEFAULT_CIRCLECI_API_URL: &str = "https://circleci.com/api/v2";
+
+/// Pages of the project's pipelines to read when looking for the merged branch
+const MAX_PIPELINE_PAGES: usize = 5;
+
+/// Pipeline from the CircleCI v2 API
+#[derive(Debug, Clone, Deserialize)]
+struct CircleCiPipeline {
+    vcs: Option<CircleCiPipelineVcs>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CircleCiPipelineVcs {
+    branch: Option<String>,
+    revision: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CircleCiPage<T> {
+    items: Vec<T>,
+    next_page_token: Option<String>,
+}
+
+/// The number at the end of a CIRCLE_PULL_REQUEST URL, e.g.
+/// `https://github.com/org/repo/pull/12` or `https://bitbucket.org/team/repo/pull-requests/12`
+fn pull_request_number(url: &str) -> Option<u64> {
+    url.trim_end_matches('/').rsplit('/').next()?.parse().ok()
+}
+
+/// `gh/org/repo` or `bb/team/repo` from the checkout's repository URL. Projects set up
+/// through the CircleCI GitHub App have `circleci/<org id>/<project id>` slugs instead,
+/// which can only be configured.
+fn project_slug(repository_url: Option<&str>, username: &str, reponame: &str) -> String {
+    let vcs = if repository_url.is_some_and(|url| url.contains("bitbucket.org")) {
+        "bb"
+    } else {
+        "gh"
+    };
+    format!("{}/{}/{}", vcs, username, reponame)
+}
+
+/// CircleCI runs in the job's own checkout and only reports the built commit. When it
+/// merged a branch, the head is the merge commit's second parent, or else the branch
+/// whose changes the commit reproduces (a squash or rebase merge): the pull request in
+/// CIRCLE_PULL_REQUEST when CircleCI names one, or else a branch the project recently
+/// built, from the CircleCI v2 API's pipelines.
+///
+/// Reads CIRCLE_SHA1 and CIRCLE_BRANCH (the target branch), and for the API
+/// CIRCLECI_TOKEN, CIRCLECI_API_URL and CIRCLECI_PROJECT_SLUG (derived from
+/// CIRCLE_PROJECT_USERNAME and CIRCLE_PROJECT_REPONAME when unset). Returns None if no
+/// branch was merged as CIRCLE_SHA1.
+pub fn get_circleci_ci_context() -> Result<Option<CiContext>, GitAiError> {
+    let commit_sha = env("CIRCLE_SHA1")
+        .ok_or_else(|| GitAiError::Generic("CIRCLE_SHA1 environment variable not set".into()))?;
+    let base_ref = env("CIRCLE_BRANCH").ok_or_else(|| {
+        GitAiError::Generic(
+            "CIRCLE_BRANCH environment variable not set; tag pipelines don't merge anything"
+                .to_string(),
+        )
+    })?;
+    let pull_request = env("CIRCLE_PULL_REQUEST");
+
+    println!("[CircleCI] Environment:");
+    println!("  Commit: {}", commit_sha);
+    println!("  Branch: {}", base_ref);
+    if let Some(pull_request) = &pull_request {
+        println!("  Pull request: {}", pull_request);
+    }
+
+    // The runner's checkout, with the forge's TLS overrides for fetching and pushing
+    let repo = find_repository(&git_args_for_dir(".", None))?;
+    let commit = repo.find_commit(commit_sha.clone())?;
+    let base_sha = commit.parent(0).map(|p| p.id()).unwrap_or_default();
+
+    let merged = if commit.parent_count()? > 1 {
+        // A merge commit names the branch head itself
+        let head_sha = commit.parent(1)?.id();
+        Some((head_sha.clone(), head_sha))
+    } else {
+        let candidates = match pull_request.as_deref().and_then(pull_request_number) {
+            Some(number) => pull_request_candidate(&repo, number)?,
+            None => pipeline_candidates(&repo, &commit_sha, &base_ref)?,
+        };
+        let merged = find_merged_branch(&repo, &commit_sha, &base_ref, &candidates)?;
+        if let Some((branch, head_sha)) = &merged {
+            if *head_sha == commit_sha {
+                // A pull request build: the commit is the branch head, not merged yet
+                println!(
+                    "[CircleCI] {} is a branch head, not a merge. Skipping...",
+                    commit_sha
+                );
+                return Ok(None);
+            }
+            println!("[CircleCI] {} was merged as {}", branch, commit_sha);
+        }
+        merged
+    };
+
+    let Some((head_ref, head_sha)) = merged else {
+        println!("[CircleCI] No branch was merged as this commit. Skipping...");
+        return Ok(None);
+    };
+    println!(
+        "[CircleCI] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        commit_sha, head_sha, head_ref, base_ref
+    );
+
+    Ok(Some(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: commit_sha,
+            head_ref,
+            head_sha,
+            base_ref,
+            base_sha,
+            merge_request: None,
+        },
+        // The job's checkout belongs to CircleCI; nothing to clean up
+        temp_dir: PathBuf::new(),
+    }))
+}
+
+fn fetch(repo: &Repository, refspec: String) -> Result<(), GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["fetch".to_string(), "origin".to_string(), refspec]);
+    let _timing = timings::phase(Phase::Fetch);
+    exec_git(&args).map(|_| ())
+}
+
+/// The pull request's head, which GitHub keeps under `refs/pull/<n>/head` after the
+/// branch is deleted
+fn pull_request_candidate(
+    repo: &Repository,
+    number: u64,
+) -> Result<Vec<MergeCandidate>, GitAiError> {
+    let local_ref = format!("refs/circleci/pr/{}", number);
+    println!("[CircleCI] Fetching pull request #{}...", number);
+    fetch(repo, format!("+refs/pull/{}/head:{}", number, local_ref)).map_err(|e| {
+        GitAiError::Generic(format!(
+            "Could not fetch the head of pull request #{}: {}",
+            number, e
+        ))
+    })?;
+    Ok(vec![MergeCandidate {
+        branch: format!("pull/{}", number),
+        rev: local_ref,
+    }])
+}
+
+/// The latest revision CircleCI built on each other branch, fetched by hash so deleted
+/// branches still count. Without API access there are no candidates.
+fn pipeline_candidates(
+    repo: &Repository,
+    commit_sha: &str,
+    base_ref: &str,
+) -> Result<Vec<MergeCandidate>, GitAiError> {
+    let api_url = env("CIRCLECI_API_URL").unwrap_or_else(|| DEFAULT_CIRCLECI_API_URL.to_string());
+    let slug = match env("CIRCLECI_PROJECT_SLUG") {
+        Some(slug) => slug,
+        None => match (
+            env("CIRCLE_PROJECT_USERNAME"),
+            env("CIRCLE_PROJECT_REPONAME"),
+        ) {
+            (Some(username), Some(reponame)) => project_slug(
+                env("CIRCLE_REPOSITORY_URL").as_deref(),
+                &username,
+                &reponame,
+            ),
+            _ => {
+                return Err(GitAiError::Generic(
+                    "Set CIRCLECI_PROJECT_SLUG, or CIRCLE_PROJECT_USERNAME and CIRCLE_PROJECT_REPONAME"
+                        .to_string(),
+                ));
+            }
+        },
+    };
+    let token = env("CIRCLECI_TOKEN");
+    if token.is_none() {
+        println!("[CircleCI] Warning: CIRCLECI_TOKEN is not set, private projects will fail");
+    }
+
+    let mut pipelines = Vec::new();
+    let mut page_token: Option<String> = None;
+    for _ in 0..MAX_PIPELINE_PAGES {
+        let mut endpoint = format!("{}/project/{}/pipeline", api_url, slug);
+        if let Some(page_token) = &page_token {
+            endpoint.push_str(&format!("?page-token={}", page_token));
+        }
+        let page: CircleCiPage<CircleCiPipeline> =
+            serde_json::from_str(&api_get(&endpoint, token.as_deref())?).map_err(|e| {
+                GitAiError::Generic(format!("Failed to parse CircleCI API response: {}", e))
+            })?;
+        pipelines.extend(page.items);
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    let candidates = latest_branch_revisions(pipelines, commit_sha, base_ref);
+    println!(
+        "[CircleCI] Found {} recently built branch(es) to check",
+        candidates.len()
+    );
+    Ok(candidates
+        .into_iter()
+        .filter_map(|(branch, revision)| {
+            // rev-parse accepts any full hash, so ask for the commit itself
+            if repo
+                .revparse_single(&format!("{}^{{commit}}", revision))
+                .is_err()
+                && let Err(e) = fetch(repo, revision.clone())
+            {
+                println!(
+                    "[CircleCI] Warning: could not fetch {} ({}): {}",
+                    branch, revision, e
+                );
+                return None;
+            }
+            Some(MergeCandidate {
+                branch,
+                rev: revision,
+            })
+        })
+        .collect())
+}
+
+/// Each branch's newest pipeline revision (pipelines come newest first), leaving out
+/// the target branch and the commit being built
+fn latest_branch_revisions(
+    pipelines: Vec<CircleCiPipeline>,
+    commit_sha: &str,
+    base_ref: &str,
+) -> Vec<(String, String)> {
+    let mut latest: Vec<(String, String)> = Vec::new();
+    for vcs in pipelines.into_iter().filter_map(|pipeline| pipeline.vcs) {
+        let Some(branch) = vcs.branch else {
+            continue;
+        };
+        if branch == base_ref
+            || vcs.revision == commit_sha
+            || latest.iter().any(|(seen, _)| *seen == branch)
+        {
+            continue;
+        }
+        latest.push((branch, vcs.revision));
+    }
+    latest
+}
+
+fn api_get(endpoint: &str, token: Option<&str>) -> Result<String, GitAiError> {
+    println!("[CircleCI] Querying API: {}", endpoint);
+    let _timing = timings::phase(Phase::Api);
+    let mut request = http::get(endpoint).with_header(
+        "User-Agent",
+        format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+    );
+    if let Some(token) = token {
+        request = request.with_header("Circle-Token", token);
+    }
+    let response = http::send(request, "CircleCI API request")?;
+    let body = response.as_str().unwrap_or("").to_string();
+    if response.status_code != 200 {
+        let mut message = format!(
+            "CircleCI API returned status {}: {}",
+            response.status_code, body
+        );
+        if matches!(response.status_code, 401 | 403 | 404) {
+            message.push_str(
+                "\nCIRCLECI_TOKEN needs to be a personal API token with access to the project",
+            );
+        }
+        return Err(GitAiError::Generic(message));
+    }
+    Ok(body)
+}
+
+/// Knobs for rendering the CircleCI config template
+#[derive(Debug, Clone, Default)]
+pub struct CircleCiTemplateOptions {
+    /// Branch the job runs on; `main` when unset
+    pub branch: Option<String>,
+}
+
+pub fn render_circleci_config_yaml(
+    options: &CircleCiTemplateOptions,
+) -> Result<String, GitAiError> {
+    let branch = options.branch.as_deref().unwrap_or("main");
+    if branch.is_empty()
+        || branch.contains(['"', '\'', '\\'])
+        || branch.contains(char::is_whitespace)
+    {
+        return Err(GitAiError::Generic(format!(
+            "Invalid branch name '{}'",
+            branch
+        )));
+    }
+    Ok(CIRCLECI_CONFIG_TEMPLATE_YAML.replace("{{BRANCH}}", branch))
+}
+
+/// Print the CircleCI config for users to copy into their .circleci/config.yml
+pub fn print_circleci_config_yaml(options: &CircleCiTemplateOptions) -> Result<(), GitAiError> {
+    let yaml = render_circleci_config_yaml(options)?;
+    println!("Add the following to your .circleci/config.yml:");
+    println!();
+    println!("{}", yaml);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_request_number_and_project_slug() {
+        assert_eq!(
+            pull_request_number("https://github.com/org/repo/pull/12"),
+            Some(12)
+        );
+        assert_eq!(
+            pull_request_number("https://bitbucket.org/team/repo/pull-requests/7/"),
+            Some(7)
+        );
+        assert_eq!(pull_request_number("https://github.com/org/repo"), None);
+
+        assert_eq!(
+            project_slug(Some("git@github.com:org/repo.git"), "org", "repo"),
+            "gh/org/repo"
+        );
+        assert_eq!(
+            project_slug(Some("git@bitbucket.org:team/repo.git"), "team", "repo"),
+            "bb/team/repo"
+        );
+    }
+
+    #[test]
+    fn test_latest_branch_revisions_keeps_newest_per_branch() {
+        let pipelines: Vec<CircleCiPipeline> = serde_json::from_value(serde_json::json!([
+            { "vcs": { "branch": "feature", "revision": "f2" } },
+            { "vcs": { "branch": "main", "revision": "m1" } },
+            { "vcs": { "branch": "feature", "revision": "f1" } },
+            { "vcs": { "tag": "v1.0", "revision": "t1" } },
+            { "vcs": { "branch": "fix", "revision": "c0" } },
+            { "vcs": { "branch": "other", "revision": "o1" } },
+        ]))
+        .unwrap();
+        assert_eq!(
+            latest_branch_revisions(pipelines, "c0", "main"),
+            vec![
+                ("feature".to_string(), "f2".to_string()),
+                ("other".to_string(), "o1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_renders_branch() {
+        let yaml = render_circleci_config_yaml(&CircleCiTemplateOptions::default()).unwrap();
+        assert!(!yaml.contains("{{"));
+        assert!(yaml.contains("only: main"));
+        assert!(yaml.contains("git-ai ci circleci run"));
+
+        assert!(
+            render_circleci_config_yaml(&CircleCiTemplateOptions {
+                branch: Some("main' || x".to_string()),
+            })
+            .is_err()
+        );
+    }
+}
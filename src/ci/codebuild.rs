@@ -0,0 +1,397 @@
+use crate::ci::aws::{AwsClient, AwsCredentials, region_from_env};
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::credentials::{CiGitCredential, git_args_for_dir};
+use crate::ci::env;
+use crate::error::GitAiError;
+use crate::git::repository::{exec_git, find_repository};
+use crate::observability::timings::{self, Phase};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+const CODECOMMIT_TARGET_PREFIX: &str = "CodeCommit_20150413";
+
+/// Pages of closed pull requests (newest first) to search for the one merged as the
+/// commit, when CODECOMMIT_PULL_REQUEST_ID doesn't name it
+const MAX_PULL_REQUEST_PAGES: usize = 5;
+const PULL_REQUEST_PAGE_SIZE: usize = 20;
+
+/// Pull request from CodeCommit's GetPullRequest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeCommitPullRequest {
+    pull_request_id: String,
+    title: Option<String>,
+    pull_request_status: String,
+    #[serde(default)]
+    pull_request_targets: Vec<CodeCommitTarget>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeCommitTarget {
+    repository_name: String,
+    /// `refs/heads/<branch>`
+    source_reference: String,
+    destination_reference: String,
+    source_commit: Option<String>,
+    merge_metadata: Option<CodeCommitMergeMetadata>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeCommitMergeMetadata {
+    #[serde(default)]
+    is_merged: bool,
+    merge_commit_id: Option<String>,
+    /// `FAST_FORWARD_MERGE`, `SQUASH_MERGE` or `THREE_WAY_MERGE`
+    merge_option: Option<String>,
+}
+
+impl CodeCommitPullRequest {
+    /// The target of `repository` this pull request merged as `commit_sha`. CodeCommit
+    /// records the squash or fast-forward result as the merge commit too.
+    fn merged_target(&self, repository: &str, commit_sha: &str) -> Option<&CodeCommitTarget> {
+        self.pull_request_targets.iter().find(|target| {
+            target.repository_name == repository
+                && target.source_commit.is_some()
+                && target.merge_metadata.as_ref().is_some_and(|merge| {
+                    merge.is_merged && merge.merge_commit_id.as_deref() == Some(commit_sha)
+                })
+        })
+    }
+}
+
+/// `https://git-codecommit.us-east-1.amazonaws.com/v1/repos/my-repo` -> `my-repo`, and
+/// the region when the URL names one
+fn parse_repo_url(url: &str) -> Option<(String, Option<String>)> {
+    let (host, name) = url.split_once("/v1/repos/")?;
+    let name = name.trim_end_matches('/').trim_end_matches(".git");
+    if name.is_empty() || name.contains('/') {
+        return None;
+    }
+    let region = host
+        .split_once("git-codecommit.")
+        .and_then(|(_, rest)| rest.split_once('.'))
+        .map(|(region, _)| region.to_string());
+    Some((name.to_string(), region))
+}
+
+/// `arn:aws:codebuild:us-east-1:123456789012:build/project:id` -> `us-east-1`
+fn region_from_arn(arn: &str) -> Option<String> {
+    arn.split(':')
+        .nth(3)
+        .filter(|region| !region.is_empty())
+        .map(str::to_string)
+}
+
+fn codecommit_call(
+    client: &AwsClient,
+    action: &str,
+    request: serde_json::Value,
+) -> Result<serde_json::Value, GitAiError> {
+    println!("[CodeBuild] Calling CodeCommit {}: {}", action, request);
+    client.call(
+        &format!("{}.{}", CODECOMMIT_TARGET_PREFIX, action),
+        &request,
+    )
+}
+
+fn get_pull_request(
+    client: &AwsClient,
+    pull_request_id: &str,
+) -> Result<CodeCommitPullRequest, GitAiError> {
+    let response = codecommit_call(
+        client,
+        "GetPullRequest",
+        json!({ "pullRequestId": pull_request_id }),
+    )?;
+    serde_json::from_value(response.get("pullRequest").cloned().unwrap_or_default())
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse CodeCommit pull request: {}", e)))
+}
+
+/// The pull request of `repository` merged as `commit_sha`, searching the given id or
+/// else the most recently closed pull requests
+fn find_merged_pull_request(
+    client: &AwsClient,
+    repository: &str,
+    commit_sha: &str,
+    pull_request_id: Option<&str>,
+) -> Result<Option<CodeCommitPullRequest>, GitAiError> {
+    if let Some(id) = pull_request_id {
+        let pull_request = get_pull_request(client, id)?;
+        return Ok(pull_request
+            .merged_target(repository, commit_sha)
+            .is_some()
+            .then_some(pull_request));
+    }
+
+    let mut next_token: Option<String> = None;
+    for _ in 0..MAX_PULL_REQUEST_PAGES {
+        let mut request = json!({
+            "repositoryName": repository,
+            "pullRequestStatus": "CLOSED",
+            "maxResults": PULL_REQUEST_PAGE_SIZE,
+        });
+        if let Some(token) = &next_token {
+            request["nextToken"] = json!(token);
+        }
+        let response = codecommit_call(client, "ListPullRequests", request)?;
+        let ids: Vec<String> = response
+            .get("pullRequestIds")
+            .and_then(|ids| serde_json::from_value(ids.clone()).ok())
+            .unwrap_or_default();
+        println!("[CodeBuild] Found {} closed pull request(s)", ids.len());
+
+        for id in &ids {
+            let pull_request = get_pull_request(client, id)?;
+            println!(
+                "[CodeBuild] Pull request {}: \"{}\" ({})",
+                pull_request.pull_request_id,
+                pull_request.title.as_deref().unwrap_or("(no title)"),
+                pull_request.pull_request_status
+            );
+            if pull_request.merged_target(repository, commit_sha).is_some() {
+                return Ok(Some(pull_request));
+            }
+        }
+
+        next_token = response
+            .get("nextToken")
+            .and_then(|token| token.as_str())
+            .map(str::to_string);
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+/// Find the CodeCommit pull request merged as the commit CodeBuild is building, from
+/// CODEBUILD_RESOLVED_SOURCE_VERSION in the repository CODEBUILD_SOURCE_REPO_URL points
+/// at (or CODECOMMIT_REPOSITORY). Searches CODECOMMIT_PULL_REQUEST_ID when set (e.g. from
+/// the EventBridge event that started the build), else recently closed pull requests.
+///
+/// CodeCommit's API is called with the build's AWS credentials; git authenticates with
+/// CODECOMMIT_GIT_USERNAME and CODECOMMIT_GIT_PASSWORD (HTTPS Git credentials) when set,
+/// else through the AWS CLI's credential helper. Returns None if no merged pull request
+/// produced the commit.
+pub fn get_codebuild_ci_context() -> Result<Option<CiContext>, GitAiError> {
+    let commit_sha = env("CODEBUILD_RESOLVED_SOURCE_VERSION").ok_or_else(|| {
+        GitAiError::Generic(
+            "CODEBUILD_RESOLVED_SOURCE_VERSION environment variable not set".to_string(),
+        )
+    })?;
+    let repo_url = env("CODEBUILD_SOURCE_REPO_URL").ok_or_else(|| {
+        GitAiError::Generic("CODEBUILD_SOURCE_REPO_URL environment variable not set".to_string())
+    })?;
+    let parsed_url = parse_repo_url(&repo_url);
+    let repository = env("CODECOMMIT_REPOSITORY")
+        .or_else(|| parsed_url.as_ref().map(|(name, _)| name.clone()))
+        .ok_or_else(|| {
+            GitAiError::Generic(format!(
+                "{} is not a CodeCommit repository URL; set CODECOMMIT_REPOSITORY",
+                repo_url
+            ))
+        })?;
+    let region = region_from_env()
+        .or_else(|| parsed_url.and_then(|(_, region)| region))
+        .or_else(|| env("CODEBUILD_BUILD_ARN").and_then(|arn| region_from_arn(&arn)))
+        .ok_or_else(|| {
+            GitAiError::Generic("Could not determine the AWS region; set AWS_REGION".to_string())
+        })?;
+    let endpoint = env("AWS_ENDPOINT_URL_CODECOMMIT")
+        .or_else(|| env("AWS_ENDPOINT_URL"))
+        .map(|url| format!("{}/", url.trim_end_matches('/')))
+        .unwrap_or_else(|| format!("https://codecommit.{}.amazonaws.com/", region));
+    let client = AwsClient {
+        endpoint,
+        region,
+        service: "codecommit".to_string(),
+        credentials: AwsCredentials::resolve()?,
+    };
+
+    println!("[CodeBuild] Environment:");
+    println!("  Commit: {}", commit_sha);
+    println!("  Repository: {}", repository);
+    println!("  Region: {}", client.region);
+    println!("  CodeCommit API: {}", client.endpoint);
+
+    let Some(pull_request) = find_merged_pull_request(
+        &client,
+        &repository,
+        &commit_sha,
+        env("CODECOMMIT_PULL_REQUEST_ID").as_deref(),
+    )?
+    else {
+        println!("[CodeBuild] No merged pull request produced this commit. Skipping...");
+        return Ok(None);
+    };
+    let Some(target) = pull_request.merged_target(&repository, &commit_sha) else {
+        return Ok(None);
+    };
+    let head_sha = target.source_commit.clone().unwrap_or_default();
+    let head_ref = target
+        .source_reference
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&target.source_reference)
+        .to_string();
+    let base_ref = target
+        .destination_reference
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&target.destination_reference)
+        .to_string();
+    println!(
+        "[CodeBuild] Pull request {} was merged as {} ({})",
+        pull_request.pull_request_id,
+        commit_sha,
+        target
+            .merge_metadata
+            .as_ref()
+            .and_then(|merge| merge.merge_option.as_deref())
+            .unwrap_or("unknown merge option")
+    );
+
+    let credential =
+        CiGitCredential::from_env_pair("CODECOMMIT_GIT_USERNAME", "CODECOMMIT_GIT_PASSWORD");
+    let helper_args: Vec<String> = match &credential {
+        Some(credential) => credential.git_config_args(),
+        // The AWS CLI signs git's requests with the build's own credentials
+        None => vec![
+            "-c".to_string(),
+            "credential.helper=".to_string(),
+            "-c".to_string(),
+            "credential.helper=!aws codecommit credential-helper $@".to_string(),
+            "-c".to_string(),
+            "credential.UseHttpPath=true".to_string(),
+        ],
+    };
+
+    let clone_dir = "git-ai-ci-clone".to_string();
+    println!("[CodeBuild] Cloning repository...");
+    let mut clone_args = helper_args.clone();
+    clone_args.extend([
+        "clone".to_string(),
+        "--branch".to_string(),
+        base_ref.clone(),
+        repo_url.clone(),
+        clone_dir.clone(),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Clone);
+        exec_git(&clone_args)?;
+    }
+    let mut repo_args = git_args_for_dir(&clone_dir, None);
+    repo_args.extend(helper_args);
+
+    // CodeCommit has no pull request refs; fetch the source branch, or the source
+    // commit itself when the branch was deleted or moved on after the merge
+    let pull_request_ref = format!(
+        "refs/codecommit/pull-requests/{}",
+        pull_request.pull_request_id
+    );
+    println!("[CodeBuild] Fetching source branch {}...", head_ref);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        let mut fetch_args = repo_args.clone();
+        fetch_args.extend([
+            "fetch".to_string(),
+            "origin".to_string(),
+            format!("{}:{}", target.source_reference, pull_request_ref),
+        ]);
+        let has_head = exec_git(&fetch_args).is_ok() && {
+            let mut contains_args = repo_args.clone();
+            contains_args.extend([
+                "merge-base".to_string(),
+                "--is-ancestor".to_string(),
+                head_sha.clone(),
+                pull_request_ref.clone(),
+            ]);
+            exec_git(&contains_args).is_ok()
+        };
+        if !has_head {
+            println!(
+                "[CodeBuild] Source branch no longer contains {}, fetching it directly",
+                head_sha
+            );
+            let mut fetch_args = repo_args.clone();
+            fetch_args.extend([
+                "fetch".to_string(),
+                "origin".to_string(),
+                format!("{}:{}", head_sha, pull_request_ref),
+            ]);
+            exec_git(&fetch_args)?;
+        }
+    }
+
+    let repo = find_repository(&repo_args)?;
+    let base_sha = repo
+        .find_commit(commit_sha.clone())?
+        .parent(0)
+        .map(|parent| parent.id())
+        .unwrap_or_default();
+
+    println!(
+        "[CodeBuild] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        commit_sha, head_sha, head_ref, base_ref
+    );
+
+    Ok(Some(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: commit_sha,
+            head_ref,
+            head_sha,
+            base_ref,
+            base_sha,
+            merge_request: None,
+        },
+        temp_dir: PathBuf::from(clone_dir),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_url() {
+        assert_eq!(
+            parse_repo_url("https://git-codecommit.eu-west-1.amazonaws.com/v1/repos/my-repo"),
+            Some(("my-repo".to_string(), Some("eu-west-1".to_string())))
+        );
+        assert_eq!(
+            parse_repo_url("http://127.0.0.1:8080/v1/repos/app.git"),
+            Some(("app".to_string(), None))
+        );
+        assert_eq!(parse_repo_url("https://github.com/org/repo"), None);
+        assert_eq!(
+            region_from_arn("arn:aws:codebuild:us-east-2:123456789012:build/app:1234"),
+            Some("us-east-2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merged_target_matches_merge_commit_in_repository() {
+        let pull_request: CodeCommitPullRequest = serde_json::from_value(json!({
+            "pullRequestId": "7",
+            "title": "Add feature",
+            "pullRequestStatus": "CLOSED",
+            "pullRequestTargets": [{
+                "repositoryName": "app",
+                "sourceReference": "refs/heads/feature",
+                "destinationReference": "refs/heads/main",
+                "sourceCommit": "aaaa",
+                "mergeMetadata": {
+                    "isMerged": true,
+                    "mergeCommitId": "bbbb",
+                    "mergeOption": "SQUASH_MERGE"
+                }
+            }]
+        }))
+        .unwrap();
+        assert!(pull_request.merged_target("app", "bbbb").is_some());
+        assert!(pull_request.merged_target("app", "aaaa").is_none());
+        assert!(pull_request.merged_target("other", "bbbb").is_none());
+    }
+}
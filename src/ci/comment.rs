@@ -0,0 +1,127 @@
+//! The attribution summary `git-ai ci` comments on a merge/pull request after rewriting
+//! its authorship (`ci_comment` / `GIT_AI_CI_COMMENT=1`), so reviewers can see the AI
+//! share without running git-ai themselves. Posting it is up to each forge's module.
+
+use crate::authorship::report::{FileContribution, collect_contributions};
+use crate::error::GitAiError;
+use crate::git::repository::Repository;
+use std::collections::BTreeMap;
+
+/// Files listed before the rest are folded into a count
+const MAX_FILES: usize = 25;
+
+/// Markdown summarizing AI vs human lines per file across `commits`, None when they
+/// added no lines
+pub fn attribution_comment(
+    repo: &Repository,
+    commits: &[String],
+) -> Result<Option<String>, GitAiError> {
+    let mut rev_args = vec!["--no-walk".to_string()];
+    rev_args.extend(commits.iter().cloned());
+
+    // A rebase merge can touch a file in several commits
+    let mut by_path: BTreeMap<String, FileContribution> = BTreeMap::new();
+    for commit in collect_contributions(repo, &rev_args)? {
+        for file in commit.files {
+            let entry = by_path
+                .entry(file.path.clone())
+                .or_insert_with(|| FileContribution {
+                    path: file.path.clone(),
+                    added_lines: 0,
+                    ai_lines: 0,
+                });
+            entry.added_lines += file.added_lines;
+            entry.ai_lines += file.ai_lines;
+        }
+    }
+    Ok(render_comment(by_path.into_values().collect()))
+}
+
+fn render_comment(mut files: Vec<FileContribution>) -> Option<String> {
+    files.retain(|file| file.added_lines > 0);
+    if files.is_empty() {
+        return None;
+    }
+    // Biggest changes first, so the cut-off drops the least interesting files
+    files.sort_by(|a, b| {
+        b.added_lines
+            .cmp(&a.added_lines)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let added: u32 = files.iter().map(|file| file.added_lines).sum();
+    let ai: u32 = files.iter().map(|file| file.ai_lines).sum();
+    let mut body = String::from("### AI attribution\n\n");
+    body.push_str(&format!(
+        "**{}%** of the {} lines added were written by AI ({} AI, {} human).\n\n",
+        (ai as f64 / added as f64 * 100.0).round() as u32,
+        added,
+        ai,
+        added - ai
+    ));
+    body.push_str("| File | AI lines | Human lines |\n| --- | ---: | ---: |\n");
+    for file in files.iter().take(MAX_FILES) {
+        body.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            file.path.replace('|', "\\|"),
+            file.ai_lines,
+            file.added_lines - file.ai_lines
+        ));
+    }
+    if files.len() > MAX_FILES {
+        body.push_str(&format!(
+            "\n_…and {} more file(s)_\n",
+            files.len() - MAX_FILES
+        ));
+    }
+    body.push_str(
+        "\n<sub>Stats powered by [Git AI](https://github.com/git-ai-project/git-ai)</sub>\n",
+    );
+    Some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, added_lines: u32, ai_lines: u32) -> FileContribution {
+        FileContribution {
+            path: path.to_string(),
+            added_lines,
+            ai_lines,
+        }
+    }
+
+    #[test]
+    fn test_comment_lists_files_by_size() {
+        let comment = render_comment(vec![
+            file("small.rs", 2, 0),
+            file("deleted_only.rs", 0, 0),
+            file("a|b.rs", 8, 6),
+        ])
+        .unwrap();
+        assert!(
+            comment.contains("**60%** of the 10 lines added were written by AI (6 AI, 4 human).")
+        );
+        let big = comment.find("| `a\\|b.rs` | 6 | 2 |").unwrap();
+        let small = comment.find("| `small.rs` | 0 | 2 |").unwrap();
+        assert!(big < small);
+        assert!(!comment.contains("deleted_only.rs"));
+    }
+
+    #[test]
+    fn test_comment_folds_long_file_lists() {
+        let files = (0..30)
+            .map(|i| file(&format!("f{:02}.rs", i), 1, 1))
+            .collect();
+        let comment = render_comment(files).unwrap();
+        assert!(comment.contains("`f24.rs`"));
+        assert!(!comment.contains("`f25.rs`"));
+        assert!(comment.contains("_…and 5 more file(s)_"));
+    }
+
+    #[test]
+    fn test_no_comment_without_added_lines() {
+        assert_eq!(render_comment(vec![file("gone.rs", 0, 0)]), None);
+    }
+}
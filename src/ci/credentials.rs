@@ -0,0 +1,204 @@
+use crate::error::GitAiError;
+
+/// Credentials used by CI providers to authenticate git operations against the forge.
+///
+/// Tokens are never spliced into remote URLs (which would persist them in `.git/config`
+/// and expose them in process lists and logs). Instead, an ephemeral credential helper is
+/// passed with `-c` that reads the token from the environment variable it was provided in,
+/// so only the variable *name* ever appears in git's argv.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CiGitCredential {
+    username: CredentialUsername,
+    token_env_var: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CredentialUsername {
+    Literal(String),
+    /// Read from this environment variable, like the token
+    EnvVar(String),
+}
+
+impl CiGitCredential {
+    /// Create a credential that answers with `username` and the value of `token_env_var`.
+    pub fn new(username: &str, token_env_var: &str) -> Result<Self, GitAiError> {
+        if !is_safe(token_env_var, &[]) {
+            return Err(GitAiError::Generic(format!(
+                "Invalid credential environment variable name: '{}'",
+                token_env_var
+            )));
+        }
+        if !is_safe(username, &['-', '.']) {
+            return Err(GitAiError::Generic(format!(
+                "Invalid credential username: '{}'",
+                username
+            )));
+        }
+
+        Ok(CiGitCredential {
+            username: CredentialUsername::Literal(username.to_string()),
+            token_env_var: token_env_var.to_string(),
+        })
+    }
+
+    /// Create a credential that reads the username from `username_env_var` too, for
+    /// tokens with generated usernames such as GitLab deploy tokens. Both variables must
+    /// be set to non-empty values.
+    pub fn from_env_pair(username_env_var: &str, token_env_var: &str) -> Option<Self> {
+        let username = std::env::var(username_env_var).ok()?;
+        if username.trim().is_empty() || !is_safe(username_env_var, &[]) {
+            return None;
+        }
+        let token = std::env::var(token_env_var).ok()?;
+        if token.trim().is_empty() || !is_safe(token_env_var, &[]) {
+            return None;
+        }
+        Some(CiGitCredential {
+            username: CredentialUsername::EnvVar(username_env_var.to_string()),
+            token_env_var: token_env_var.to_string(),
+        })
+    }
+
+    /// Create a credential only if `token_env_var` is set to a non-empty value.
+    pub fn from_env(username: &str, token_env_var: &str) -> Option<Self> {
+        let token = std::env::var(token_env_var).ok()?;
+        if token.trim().is_empty() {
+            return None;
+        }
+        Self::new(username, token_env_var).ok()
+    }
+
+    /// The environment variable the token is read from
+    pub fn token_env_var(&self) -> &str {
+        &self.token_env_var
+    }
+
+    /// Global git args (`-c ...`) installing the ephemeral credential helper.
+    ///
+    /// The first `credential.helper=` resets any helpers configured on the runner so the
+    /// token we provide is the only one offered. The helper only answers `get` requests,
+    /// so git never asks it to store or erase anything.
+    pub fn git_config_args(&self) -> Vec<String> {
+        let username = match &self.username {
+            CredentialUsername::Literal(username) => username.clone(),
+            CredentialUsername::EnvVar(var) => format!("\"${{{}}}\"", var),
+        };
+        vec![
+            "-c".to_string(),
+            "credential.helper=".to_string(),
+            "-c".to_string(),
+            format!(
+                "credential.helper=!f() {{ test \"$1\" = get && echo username={} && echo \"password=${{{}}}\"; }}; f",
+                username, self.token_env_var
+            ),
+        ]
+    }
+}
+
+/// Whether `s` can be spliced into the credential helper script unquoted
+fn is_safe(s: &str, extra: &[char]) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || extra.contains(&c))
+}
+
+/// Global git args for talking to the forge: its TLS overrides and, if given, the
+/// credential helper
+pub fn forge_git_args(credential: Option<&CiGitCredential>) -> Vec<String> {
+    let mut args = crate::tls::git_config_args();
+    if let Some(credential) = credential {
+        args.extend(credential.git_config_args());
+    }
+    args
+}
+
+/// Build the global args for running git inside `dir` against the forge, with an
+/// optional credential.
+pub fn git_args_for_dir(dir: &str, credential: Option<&CiGitCredential>) -> Vec<String> {
+    let mut args = vec!["-C".to_string(), dir.to_string()];
+    args.extend(forge_git_args(credential));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_config_args_reference_env_var_not_token() {
+        let credential = CiGitCredential::new("oauth2", "GITLAB_TOKEN").unwrap();
+        let args = credential.git_config_args();
+
+        assert_eq!(args.len(), 4);
+        assert_eq!(args[0], "-c");
+        assert_eq!(args[1], "credential.helper=");
+        assert_eq!(args[2], "-c");
+        assert!(args[3].starts_with("credential.helper=!"));
+        assert!(args[3].contains("username=oauth2"));
+        assert!(args[3].contains("password=${GITLAB_TOKEN}"));
+    }
+
+    #[test]
+    fn test_username_from_env_is_not_inlined() {
+        let credential = CiGitCredential {
+            username: CredentialUsername::EnvVar("CI_DEPLOY_USER".to_string()),
+            token_env_var: "CI_DEPLOY_PASSWORD".to_string(),
+        };
+        let helper = &credential.git_config_args()[3];
+        assert!(helper.contains("echo username=\"${CI_DEPLOY_USER}\""));
+        assert!(helper.contains("password=${CI_DEPLOY_PASSWORD}"));
+        assert!(CiGitCredential::from_env_pair("CI DEPLOY USER", "CI_DEPLOY_PASSWORD").is_none());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_names() {
+        assert!(CiGitCredential::new("oauth2", "TOKEN; rm -rf /").is_err());
+        assert!(CiGitCredential::new("oauth2", "").is_err());
+        assert!(CiGitCredential::new("user name", "GITLAB_TOKEN").is_err());
+        assert!(CiGitCredential::new("x-access-token", "GITHUB_TOKEN").is_ok());
+        assert!(CiGitCredential::new("gitlab-ci-token", "CI_JOB_TOKEN").is_ok());
+    }
+
+    #[test]
+    fn test_git_args_for_dir() {
+        assert_eq!(
+            git_args_for_dir("clone", None),
+            vec!["-C".to_string(), "clone".to_string()]
+        );
+
+        let credential = CiGitCredential::new("x-access-token", "GITHUB_TOKEN").unwrap();
+        let args = git_args_for_dir("clone", Some(&credential));
+        assert_eq!(args.len(), 6);
+        assert_eq!(&args[..2], &["-C".to_string(), "clone".to_string()]);
+    }
+
+    #[test]
+    fn test_debug_does_not_contain_secret_values() {
+        let username = "deploy-user-8c1f2e";
+        let token = "glpat-secret-5d9a7b3e";
+        // SAFETY: the variable names are unique to this test
+        unsafe {
+            std::env::set_var("GIT_AI_TEST_CREDENTIAL_USER", username);
+            std::env::set_var("GIT_AI_TEST_CREDENTIAL_TOKEN", token);
+        }
+
+        let credential = CiGitCredential::from_env("oauth2", "GIT_AI_TEST_CREDENTIAL_TOKEN")
+            .expect("token is set");
+        let debug = format!("{:?}", credential);
+        assert!(debug.contains("GIT_AI_TEST_CREDENTIAL_TOKEN"));
+        assert!(debug.contains("oauth2"));
+        assert!(!debug.contains(token));
+
+        let credential = CiGitCredential::from_env_pair(
+            "GIT_AI_TEST_CREDENTIAL_USER",
+            "GIT_AI_TEST_CREDENTIAL_TOKEN",
+        )
+        .expect("both variables are set");
+        let debug = format!("{:?}", credential);
+        assert!(!debug.contains(username));
+        assert!(!debug.contains(token));
+        let args = credential.git_config_args().join(" ");
+        assert!(!args.contains(username));
+        assert!(!args.contains(token));
+    }
+}
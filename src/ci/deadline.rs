@@ -0,0 +1,81 @@
+//! Overall time budget for a CI command (`git-ai ci --deadline <duration> ...` or
+//! `GIT_AI_CI_DEADLINE`), so retries and slow forges can't run past the pipeline's own
+//! job timeout. API requests and remote git operations check it before they start and
+//! fail with [`GitAiError::DeadlineExceeded`] once it has passed; the sweep and the
+//! backfill stop there and report how far they got.
+
+use crate::error::GitAiError;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Environment variable setting the deadline when `--deadline` isn't passed
+pub const DEADLINE_ENV_VAR: &str = "GIT_AI_CI_DEADLINE";
+
+struct Deadline {
+    budget: Duration,
+    at: Instant,
+}
+
+static DEADLINE: OnceLock<Deadline> = OnceLock::new();
+
+/// Parse a budget like `20m`, `1h30m` or `90s`
+pub fn parse(value: &str) -> Result<Duration, GitAiError> {
+    humantime::parse_duration(value.trim())
+        .ok()
+        .filter(|budget| !budget.is_zero())
+        .ok_or_else(|| {
+            GitAiError::Generic(format!(
+                "Invalid deadline '{}': expected a duration like 20m or 1h30m",
+                value
+            ))
+        })
+}
+
+/// Start the clock on `budget`. Only the first call counts.
+pub fn start(budget: Duration) {
+    let _ = DEADLINE.set(Deadline {
+        budget,
+        at: Instant::now() + budget,
+    });
+}
+
+/// Time left before the deadline, None when there isn't one
+pub fn remaining() -> Option<Duration> {
+    DEADLINE
+        .get()
+        .map(|deadline| deadline.at.saturating_duration_since(Instant::now()))
+}
+
+/// Fail with [`GitAiError::DeadlineExceeded`] if the deadline has passed. `operation`
+/// says what was about to start, e.g. "run git fetch".
+pub fn check(operation: &str) -> Result<(), GitAiError> {
+    match remaining() {
+        Some(left) if left.is_zero() => Err(exceeded(operation)),
+        _ => Ok(()),
+    }
+}
+
+/// The error for running out of time before `operation`, including a wait that
+/// would end after the deadline
+pub fn exceeded(operation: &str) -> GitAiError {
+    GitAiError::DeadlineExceeded {
+        budget: DEADLINE
+            .get()
+            .map(|deadline| deadline.budget)
+            .unwrap_or_default(),
+        operation: operation.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deadline() {
+        assert_eq!(parse("20m").unwrap(), Duration::from_secs(1200));
+        assert_eq!(parse(" 1h30m ").unwrap(), Duration::from_secs(5400));
+        assert!(parse("0s").is_err());
+        assert!(parse("soon").is_err());
+    }
+}
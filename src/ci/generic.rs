@@ -0,0 +1,134 @@
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::credentials::git_args_for_dir;
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git, find_repository};
+use crate::observability::timings::{self, Phase};
+use std::path::PathBuf;
+
+/// Ref the merged branch's head is fetched into when the checkout doesn't have it
+const HEAD_FETCH_REF: &str = "refs/git-ai/generic/head";
+
+/// A merge as the CI system describes it, for systems git-ai has no provider for
+/// (TeamCity, Bamboo, Drone, ...)
+#[derive(Debug, Clone, Default)]
+pub struct GenericMerge {
+    pub merge_sha: String,
+    pub head_ref: String,
+    pub head_sha: String,
+    pub base_ref: String,
+    /// The base branch before the merge; the merge commit's first parent when unset
+    pub base_sha: Option<String>,
+}
+
+/// Build the context for `merge` in the job's checkout (the current directory), with no
+/// provider detection or forge API calls. Commits the checkout lacks are fetched from
+/// origin: the head branch, else the head commit itself when the branch is gone.
+pub fn get_generic_ci_context(merge: GenericMerge) -> Result<CiContext, GitAiError> {
+    for (flag, value) in [
+        ("--merge-sha", &merge.merge_sha),
+        ("--head-ref", &merge.head_ref),
+        ("--head-sha", &merge.head_sha),
+        ("--base-ref", &merge.base_ref),
+    ] {
+        if value.trim().is_empty() {
+            return Err(GitAiError::Generic(format!("{} is required", flag)));
+        }
+    }
+    let head_ref = merge
+        .head_ref
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&merge.head_ref)
+        .to_string();
+    let base_ref = merge
+        .base_ref
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&merge.base_ref)
+        .to_string();
+
+    println!("[Generic] Merge:");
+    println!("  Merge commit: {}", merge.merge_sha);
+    println!("  Head: {} ({})", head_ref, merge.head_sha);
+    println!("  Base: {}", base_ref);
+
+    // The runner's checkout, with the forge's TLS overrides for fetching and pushing
+    let repo = find_repository(&git_args_for_dir(".", None))?;
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        if !has_commit(&repo, &merge.merge_sha) {
+            println!("[Generic] Fetching merge commit {}...", merge.merge_sha);
+            fetch(&repo, &format!("+refs/heads/{}", base_ref))?;
+            if !has_commit(&repo, &merge.merge_sha) {
+                fetch(&repo, &merge.merge_sha)?;
+            }
+        }
+        if !has_commit(&repo, &merge.head_sha) {
+            println!("[Generic] Fetching head branch {}...", head_ref);
+            let fetched = fetch(
+                &repo,
+                &format!("+refs/heads/{}:{}", head_ref, HEAD_FETCH_REF),
+            )
+            .is_ok()
+                && has_commit(&repo, &merge.head_sha);
+            if !fetched {
+                println!(
+                    "[Generic] {} no longer contains {}, fetching it directly",
+                    head_ref, merge.head_sha
+                );
+                fetch(&repo, &format!("{}:{}", merge.head_sha, HEAD_FETCH_REF)).map_err(|e| {
+                    GitAiError::Generic(format!(
+                        "Head commit {} is not in the checkout and could not be fetched: {}",
+                        merge.head_sha, e
+                    ))
+                })?;
+            }
+        }
+    }
+
+    let base_sha = match merge.base_sha {
+        Some(base_sha) => base_sha,
+        None => repo
+            .find_commit(merge.merge_sha.clone())?
+            .parent(0)
+            .map(|parent| parent.id())
+            .unwrap_or_default(),
+    };
+
+    println!(
+        "[Generic] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        merge.merge_sha, merge.head_sha, head_ref, base_ref
+    );
+
+    Ok(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: merge.merge_sha,
+            head_ref,
+            head_sha: merge.head_sha,
+            base_ref,
+            base_sha,
+            merge_request: None,
+        },
+        // The job's checkout belongs to the CI system; nothing to clean up
+        temp_dir: PathBuf::new(),
+    })
+}
+
+fn has_commit(repo: &Repository, sha: &str) -> bool {
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "cat-file".to_string(),
+        "-e".to_string(),
+        format!("{}^{{commit}}", sha),
+    ]);
+    exec_git(&args).is_ok()
+}
+
+fn fetch(repo: &Repository, refspec: &str) -> Result<(), GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "fetch".to_string(),
+        "origin".to_string(),
+        refspec.to_string(),
+    ]);
+    exec_git(&args).map(|_| ())
+}
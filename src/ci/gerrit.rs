@@ -0,0 +1,393 @@
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::credentials::{CiGitCredential, forge_git_args, git_args_for_dir};
+use crate::ci::env;
+use crate::ci::http;
+use crate::error::GitAiError;
+use crate::git::repository::{exec_git, find_repository};
+use crate::observability::timings::{self, Phase};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Gerrit prefixes JSON responses with this line to defeat cross-site script inclusion
+const XSSI_PREFIX: &str = ")]}'";
+
+/// Patch set kinds Gerrit assigns when a patch set only rebases the previous one. When
+/// Gerrit rebases or cherry-picks a change on submit, the merged commit is one of these.
+const REBASE_KINDS: &[&str] = &[
+    "TRIVIAL_REBASE",
+    "MERGE_FIRST_PARENT_UPDATE",
+    "NO_CODE_CHANGE",
+    "NO_CHANGE",
+];
+
+/// Change from the Gerrit REST API, queried with `o=ALL_REVISIONS`
+#[derive(Debug, Clone, Deserialize)]
+struct GerritChange {
+    #[serde(rename = "_number")]
+    number: u64,
+    change_id: String,
+    branch: String,
+    subject: Option<String>,
+    status: String,
+    /// Commit sha -> patch set
+    #[serde(default)]
+    revisions: BTreeMap<String, GerritRevision>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GerritRevision {
+    #[serde(rename = "_number")]
+    number: u32,
+    #[serde(rename = "ref")]
+    ref_name: String,
+    kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GerritCommit {
+    #[serde(default)]
+    parents: Vec<GerritParent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GerritParent {
+    commit: String,
+}
+
+impl GerritChange {
+    /// The patch set whose code was reviewed, for a change submitted as `submitted_sha`:
+    /// that patch set itself, or if it only rebased earlier ones (as Gerrit's rebase and
+    /// cherry-pick submit strategies do), the last patch set that changed the code
+    fn reviewed_patch_set(&self, submitted_sha: &str) -> Option<(&str, &GerritRevision)> {
+        let mut patch_sets: Vec<(&str, &GerritRevision)> = self
+            .revisions
+            .iter()
+            .map(|(sha, revision)| (sha.as_str(), revision))
+            .collect();
+        patch_sets.sort_by_key(|(_, revision)| revision.number);
+
+        let submitted = patch_sets
+            .iter()
+            .position(|(sha, _)| *sha == submitted_sha)?;
+        let reviewed = patch_sets[..=submitted]
+            .iter()
+            .rposition(|(_, revision)| {
+                !revision
+                    .kind
+                    .as_deref()
+                    .is_some_and(|kind| REBASE_KINDS.contains(&kind))
+            })
+            .unwrap_or(0);
+        Some(patch_sets[reviewed])
+    }
+}
+
+/// The Gerrit server's base URL: GERRIT_URL, else GERRIT_CHANGE_URL (which the Jenkins
+/// Gerrit Trigger sets) without the change's path
+fn gerrit_server_url() -> Result<String, GitAiError> {
+    if let Some(url) = env("GERRIT_URL") {
+        return Ok(url.trim_end_matches('/').to_string());
+    }
+    env("GERRIT_CHANGE_URL")
+        .and_then(|url| server_url_from_change_url(&url))
+        .ok_or_else(|| {
+            GitAiError::Generic("Neither GERRIT_URL nor GERRIT_CHANGE_URL is set".to_string())
+        })
+}
+
+/// `https://review.example.com/c/project/+/1234` or `https://review.example.com/1234`
+/// -> `https://review.example.com`
+fn server_url_from_change_url(change_url: &str) -> Option<String> {
+    let change_url = change_url.trim_end_matches('/');
+    if let Some((server, _)) = change_url.split_once("/c/") {
+        return Some(server.to_string());
+    }
+    let (server, number) = change_url.rsplit_once('/')?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) && server.contains("://"))
+        .then(|| server.to_string())
+}
+
+/// Basic auth from GERRIT_USERNAME and GERRIT_HTTP_PASSWORD (the account's generated
+/// HTTP password), if both are set
+fn gerrit_auth_header() -> Option<String> {
+    let username = env("GERRIT_USERNAME")?;
+    let password = env("GERRIT_HTTP_PASSWORD")?;
+    Some(format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", username, password))
+    ))
+}
+
+/// GET a REST endpoint, authenticated under `/a/` when credentials are set
+fn api_get(server_url: &str, endpoint: &str, auth: Option<&str>) -> Result<String, GitAiError> {
+    let url = match auth {
+        Some(_) => format!("{}/a/{}", server_url, endpoint),
+        None => format!("{}/{}", server_url, endpoint),
+    };
+    println!("[Gerrit] Querying API: {}", url);
+    let _timing = timings::phase(Phase::Api);
+    let mut request = http::get(&url)
+        .with_header("Accept", "application/json")
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        );
+    if let Some(auth) = auth {
+        request = request.with_header("Authorization", auth);
+    }
+    let response = http::send(request, "Gerrit API request")?;
+    let body = response.as_str().unwrap_or("").to_string();
+    if response.status_code != 200 {
+        let mut message = format!(
+            "Gerrit API returned status {}: {}",
+            response.status_code, body
+        );
+        if matches!(response.status_code, 401 | 403) {
+            message.push_str(
+                "\nGERRIT_USERNAME needs read access to the project's changes and push access to refs/notes/*",
+            );
+        }
+        return Err(GitAiError::Generic(message));
+    }
+    Ok(body
+        .strip_prefix(XSSI_PREFIX)
+        .unwrap_or(&body)
+        .trim_start()
+        .to_string())
+}
+
+fn parse<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, GitAiError> {
+    serde_json::from_str(body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse Gerrit API response: {}", e)))
+}
+
+/// Changes in `project` with a patch set at `commit_sha`
+fn changes_with_patch_set(
+    server_url: &str,
+    project: &str,
+    commit_sha: &str,
+    auth: Option<&str>,
+) -> Result<Vec<GerritChange>, GitAiError> {
+    let body = api_get(
+        server_url,
+        &format!(
+            "changes/?q=commit:{}+project:{}&o=ALL_REVISIONS",
+            commit_sha,
+            project.replace('/', "%2F")
+        ),
+        auth,
+    )?;
+    parse(&body)
+}
+
+/// Find the submitted change that produced the commit Gerrit merged, and map its patch
+/// sets onto a merge: the head is the patch set that was reviewed, and the merged commit
+/// is either a later patch set Gerrit created by rebasing or cherry-picking it on submit,
+/// or a merge commit whose second parent is the reviewed patch set.
+///
+/// Reads GERRIT_PROJECT and the merged commit from GERRIT_NEWREV (set by the Jenkins
+/// Gerrit Trigger for change-merged events) or GIT_COMMIT. Returns None if no submitted
+/// change produced the commit.
+pub fn get_gerrit_ci_context() -> Result<Option<CiContext>, GitAiError> {
+    let server_url = gerrit_server_url()?;
+    let project = env("GERRIT_PROJECT")
+        .ok_or_else(|| GitAiError::Generic("GERRIT_PROJECT environment variable not set".into()))?;
+    let commit_sha = env("GERRIT_NEWREV")
+        .or_else(|| env("GIT_COMMIT"))
+        .ok_or_else(|| {
+            GitAiError::Generic("Neither GERRIT_NEWREV nor GIT_COMMIT is set".to_string())
+        })?;
+    let auth = gerrit_auth_header();
+
+    println!("[Gerrit] Environment:");
+    println!("  Commit: {}", commit_sha);
+    println!("  Project: {}", project);
+    println!("  Server: {}", server_url);
+    println!(
+        "  Auth: {}",
+        if auth.is_some() {
+            "GERRIT_USERNAME"
+        } else {
+            "anonymous"
+        }
+    );
+
+    let mut patch_set_sha = commit_sha.clone();
+    let mut changes = changes_with_patch_set(&server_url, &project, &commit_sha, auth.as_deref())?;
+    if changes.is_empty() {
+        // A merge commit created on submit isn't a patch set; its second parent is
+        let body = api_get(
+            &server_url,
+            &format!(
+                "projects/{}/commits/{}",
+                project.replace('/', "%2F"),
+                commit_sha
+            ),
+            auth.as_deref(),
+        )?;
+        let commit: GerritCommit = parse(&body)?;
+        if let [_, merged, ..] = commit.parents.as_slice() {
+            println!(
+                "[Gerrit] {} is a merge commit, looking up its second parent {}",
+                commit_sha, merged.commit
+            );
+            patch_set_sha = merged.commit.clone();
+            changes =
+                changes_with_patch_set(&server_url, &project, &patch_set_sha, auth.as_deref())?;
+        }
+    }
+
+    println!(
+        "[Gerrit] Found {} change(s) with a patch set at {}",
+        changes.len(),
+        patch_set_sha
+    );
+    for change in &changes {
+        println!(
+            "[Gerrit] Change {} ({}): \"{}\"",
+            change.number,
+            change.change_id,
+            change.subject.as_deref().unwrap_or("(no subject)")
+        );
+        println!("    branch: {}, status: {}", change.branch, change.status);
+    }
+
+    let Some((change, (head_sha, head_patch_set))) = changes
+        .iter()
+        .filter(|change| change.status == "MERGED")
+        .find_map(|change| Some((change, change.reviewed_patch_set(&patch_set_sha)?)))
+    else {
+        println!("[Gerrit] No submitted change produced this commit. Skipping...");
+        return Ok(None);
+    };
+    println!(
+        "[Gerrit] Change {} was submitted as {}; patch set {} was reviewed",
+        change.number, commit_sha, head_patch_set.number
+    );
+
+    let credential = CiGitCredential::from_env_pair("GERRIT_USERNAME", "GERRIT_HTTP_PASSWORD");
+    let clone_dir = "git-ai-ci-clone".to_string();
+    println!("[Gerrit] Cloning repository...");
+    let mut clone_args = forge_git_args(credential.as_ref());
+    clone_args.extend([
+        "clone".to_string(),
+        "--branch".to_string(),
+        change.branch.clone(),
+        format!("{}/{}.git", server_url, project),
+        clone_dir.clone(),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Clone);
+        exec_git(&clone_args)?;
+    }
+    let repo_args = git_args_for_dir(&clone_dir, credential.as_ref());
+
+    // Gerrit keeps every patch set under refs/changes/ after submission
+    println!(
+        "[Gerrit] Fetching patch set from {}...",
+        head_patch_set.ref_name
+    );
+    let mut fetch_args = repo_args.clone();
+    fetch_args.extend([
+        "fetch".to_string(),
+        "origin".to_string(),
+        format!(
+            "{}:refs/gerrit/changes/{}/{}",
+            head_patch_set.ref_name, change.number, head_patch_set.number
+        ),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&fetch_args)?;
+    }
+
+    let repo = find_repository(&repo_args)?;
+    let base_sha = repo
+        .find_commit(commit_sha.clone())?
+        .parent(0)
+        .map(|parent| parent.id())
+        .unwrap_or_default();
+
+    println!(
+        "[Gerrit] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        commit_sha, head_sha, head_patch_set.ref_name, change.branch
+    );
+
+    Ok(Some(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: commit_sha,
+            head_ref: head_patch_set.ref_name.clone(),
+            head_sha: head_sha.to_string(),
+            base_ref: change.branch.clone(),
+            base_sha,
+            merge_request: None,
+        },
+        temp_dir: PathBuf::from(clone_dir),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(patch_sets: &[(&str, &str)]) -> GerritChange {
+        let revisions: serde_json::Map<String, serde_json::Value> = patch_sets
+            .iter()
+            .enumerate()
+            .map(|(i, (sha, kind))| {
+                (
+                    sha.to_string(),
+                    serde_json::json!({
+                        "_number": i + 1,
+                        "ref": format!("refs/changes/34/1234/{}", i + 1),
+                        "kind": kind,
+                    }),
+                )
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "_number": 1234,
+            "change_id": "I0123456789abcdef0123456789abcdef01234567",
+            "project": "platform/app",
+            "branch": "main",
+            "subject": "Add feature",
+            "status": "MERGED",
+            "revisions": revisions,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reviewed_patch_set_skips_rebases_made_on_submit() {
+        let change = change(&[
+            ("aaaa", "REWORK"),
+            ("bbbb", "REWORK"),
+            ("cccc", "TRIVIAL_REBASE"),
+            ("dddd", "NO_CODE_CHANGE"),
+        ]);
+        let (sha, patch_set) = change.reviewed_patch_set("dddd").unwrap();
+        assert_eq!((sha, patch_set.number), ("bbbb", 2));
+        assert_eq!(patch_set.ref_name, "refs/changes/34/1234/2");
+
+        assert_eq!(change.reviewed_patch_set("bbbb").unwrap().0, "bbbb");
+        assert_eq!(change.reviewed_patch_set("aaaa").unwrap().0, "aaaa");
+        assert!(change.reviewed_patch_set("eeee").is_none());
+    }
+
+    #[test]
+    fn test_server_url_from_change_url() {
+        assert_eq!(
+            server_url_from_change_url("https://review.example.com/c/platform/app/+/1234"),
+            Some("https://review.example.com".to_string())
+        );
+        assert_eq!(
+            server_url_from_change_url("https://review.example.com/r/1234/"),
+            Some("https://review.example.com/r".to_string())
+        );
+        assert_eq!(server_url_from_change_url("1234"), None);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::credentials::{CiGitCredential, forge_git_args, git_args_for_dir};
+use crate::ci::http;
+use crate::ci::{env, required_env};
+use crate::error::GitAiError;
+use crate::git::repository::exec_git;
+use crate::git::repository::{find_repository, find_repository_in_path};
+use crate::observability::timings::{self, Phase};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GITEA_ACTIONS_TEMPLATE_YAML: &str = include_str!("workflow_templates/gitea.yaml");
+
+/// Recently updated closed pull requests to search for the one merged as the commit
+const PULL_REQUEST_LIMIT: usize = 50;
+
+/// Pull request from the Gitea (and Forgejo) v1 API
+#[derive(Debug, Clone, Deserialize)]
+struct GiteaPullRequest {
+    number: u64,
+    title: Option<String>,
+    #[serde(default)]
+    merged: bool,
+    merge_commit_sha: Option<String>,
+    head: GiteaPullRequestBranch,
+    base: GiteaPullRequestBranch,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GiteaPullRequestBranch {
+    #[serde(rename = "ref")]
+    ref_name: String,
+    sha: String,
+}
+
+impl GiteaPullRequest {
+    /// Gitea records the squash or rebase result as the merge commit too
+    fn merged_as(&self, commit_sha: &str) -> bool {
+        self.merged && self.merge_commit_sha.as_deref() == Some(commit_sha)
+    }
+}
+
+/// The API token: GITEA_TOKEN, or the job's automatic GITHUB_TOKEN, which Gitea and
+/// Forgejo Actions also provide. Returns the variable it was read from.
+fn gitea_token_env_var() -> Result<&'static str, GitAiError> {
+    ["GITEA_TOKEN", "GITHUB_TOKEN"]
+        .into_iter()
+        .find(|name| env(name).is_some())
+        .ok_or_else(|| {
+            GitAiError::Generic("Neither GITEA_TOKEN nor GITHUB_TOKEN is set".to_string())
+        })
+}
+
+fn api_get(endpoint: &str, token_env_var: &str) -> Result<String, GitAiError> {
+    println!("[Gitea Actions] Querying API: {}", endpoint);
+    let token = env(token_env_var).unwrap_or_default();
+    let _timing = timings::phase(Phase::Api);
+    let request = http::get(endpoint)
+        .with_header("Authorization", format!("token {}", token))
+        .with_header("Accept", "application/json")
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        );
+    let response = http::send(request, "Gitea API request")?;
+    let body = response.as_str().unwrap_or("").to_string();
+    if response.status_code != 200 {
+        let mut message = format!(
+            "Gitea API returned status {}: {}",
+            response.status_code, body
+        );
+        if matches!(response.status_code, 401 | 403) {
+            message.push_str(&format!(
+                "\n{} needs read access to pull requests and write access to the repository",
+                token_env_var
+            ));
+        }
+        return Err(GitAiError::Generic(message));
+    }
+    Ok(body)
+}
+
+/// Find the merged pull request that produced the pushed commit on a Gitea or Forgejo
+/// instance, from its most recently updated closed pull requests.
+///
+/// Gitea and Forgejo Actions set the GitHub-compatible GITHUB_SHA, GITHUB_REPOSITORY
+/// and GITHUB_SERVER_URL. The API defaults to `<server>/api/v1`; set GITEA_API_URL to
+/// override it. Returns None if the commit didn't come from a merged pull request.
+pub fn get_gitea_ci_context() -> Result<Option<CiContext>, GitAiError> {
+    let commit_sha = required_env("GITHUB_SHA")?;
+    let repository = required_env("GITHUB_REPOSITORY")?;
+    let server_url = required_env("GITHUB_SERVER_URL")?;
+    let server_url = server_url.trim_end_matches('/');
+    let api_url = env("GITEA_API_URL").unwrap_or_else(|| format!("{}/api/v1", server_url));
+    let api_url = api_url.trim_end_matches('/');
+    let token_env_var = gitea_token_env_var()?;
+
+    println!("[Gitea Actions] Environment:");
+    println!("  Commit: {}", commit_sha);
+    println!("  Repository: {}", repository);
+    println!("  Server: {}", server_url);
+    println!("  Auth: {}", token_env_var);
+
+    let body = api_get(
+        &format!(
+            "{}/repos/{}/pulls?state=closed&sort=recentupdate&limit={}",
+            api_url, repository, PULL_REQUEST_LIMIT
+        ),
+        token_env_var,
+    )?;
+    let pull_requests: Vec<GiteaPullRequest> = serde_json::from_str(&body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse Gitea API response: {}", e)))?;
+
+    println!(
+        "[Gitea Actions] Found {} closed pull request(s) to check",
+        pull_requests.len()
+    );
+    for pr in &pull_requests {
+        println!(
+            "[Gitea Actions] PR #{}: \"{}\"",
+            pr.number,
+            pr.title.as_deref().unwrap_or("(no title)")
+        );
+        println!("    head: {} ({})", pr.head.ref_name, pr.head.sha);
+        println!("    base: {}", pr.base.ref_name);
+        println!(
+            "    merged: {}, merge_commit_sha: {}",
+            pr.merged,
+            pr.merge_commit_sha.as_deref().unwrap_or("(none)")
+        );
+    }
+
+    let Some(pr) = pull_requests
+        .into_iter()
+        .find(|pr| pr.merged_as(&commit_sha))
+    else {
+        println!("[Gitea Actions] No merged pull request produced this commit. Skipping...");
+        return Ok(None);
+    };
+    println!("[Gitea Actions] Found matching PR #{}", pr.number);
+
+    // Gitea takes a token as the password for any username
+    let credential = CiGitCredential::from_env("oauth2", token_env_var);
+    let clone_dir = "git-ai-ci-clone".to_string();
+    println!("[Gitea Actions] Cloning repository...");
+    let mut clone_args = forge_git_args(credential.as_ref());
+    clone_args.extend([
+        "clone".to_string(),
+        "--branch".to_string(),
+        pr.base.ref_name.clone(),
+        format!("{}/{}.git", server_url, repository),
+        clone_dir.clone(),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Clone);
+        exec_git(&clone_args)?;
+    }
+    let repo_args = git_args_for_dir(&clone_dir, credential.as_ref());
+
+    // Like GitHub, Gitea keeps pull/{number}/head after the branch is deleted
+    println!(
+        "[Gitea Actions] Fetching PR commits from refs/pull/{}/head...",
+        pr.number
+    );
+    let mut fetch_args = repo_args.clone();
+    fetch_args.extend([
+        "fetch".to_string(),
+        "origin".to_string(),
+        format!("refs/pull/{}/head:refs/gitea/pr/{}", pr.number, pr.number),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&fetch_args)?;
+    }
+
+    let repo = find_repository(&repo_args)?;
+
+    println!(
+        "[Gitea Actions] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        commit_sha, pr.head.sha, pr.head.ref_name, pr.base.ref_name
+    );
+
+    Ok(Some(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: commit_sha,
+            head_ref: pr.head.ref_name,
+            head_sha: pr.head.sha,
+            base_ref: pr.base.ref_name,
+            base_sha: pr.base.sha,
+            merge_request: None,
+        },
+        temp_dir: PathBuf::from(clone_dir),
+    }))
+}
+
+/// Install or update the Gitea Actions workflow in the current repository, at
+/// .gitea/workflows/git-ai.yaml (which Forgejo reads too)
+pub fn install_gitea_ci_workflow() -> Result<PathBuf, GitAiError> {
+    let repo = find_repository_in_path(".")?;
+    install_gitea_ci_workflow_at(&repo.workdir()?)
+}
+
+/// Write the Gitea Actions workflow into the repository checked out at `workdir`
+pub fn install_gitea_ci_workflow_at(workdir: &Path) -> Result<PathBuf, GitAiError> {
+    let workflows_dir = workdir.join(".gitea").join("workflows");
+    fs::create_dir_all(&workflows_dir)
+        .map_err(|e| GitAiError::Generic(format!("Failed to create workflows dir: {}", e)))?;
+
+    let dest_path = workflows_dir.join("git-ai.yaml");
+    fs::write(&dest_path, GITEA_ACTIONS_TEMPLATE_YAML)
+        .map_err(|e| GitAiError::Generic(format!("Failed to write workflow file: {}", e)))?;
+
+    Ok(dest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pull_request(merged: bool, merge_commit_sha: Option<&str>) -> GiteaPullRequest {
+        serde_json::from_value(serde_json::json!({
+            "number": 4,
+            "title": "Add feature",
+            "state": "closed",
+            "merged": merged,
+            "merge_commit_sha": merge_commit_sha,
+            "head": { "ref": "feature", "sha": "aaaa", "repo_id": 1 },
+            "base": { "ref": "main", "sha": "bbbb", "repo_id": 1 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merged_as_requires_merged_pull_request() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        assert!(pull_request(true, Some(sha)).merged_as(sha));
+        assert!(!pull_request(true, Some("0123456789ab")).merged_as(sha));
+        assert!(!pull_request(true, None).merged_as(sha));
+        assert!(!pull_request(false, Some(sha)).merged_as(sha));
+    }
+
+    #[test]
+    fn test_install_writes_gitea_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = install_gitea_ci_workflow_at(dir.path()).unwrap();
+        assert_eq!(path, dir.path().join(".gitea/workflows/git-ai.yaml"));
+        let workflow = fs::read_to_string(path).unwrap();
+        assert!(workflow.contains("git-ai ci gitea run"));
+        assert!(workflow.contains("GITEA_TOKEN"));
+    }
+}
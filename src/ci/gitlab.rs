@@ -1,10 +1,23 @@
 use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::comment::attribution_comment;
+use crate::ci::credentials::{CiGitCredential, forge_git_args, git_args_for_dir};
+use crate::ci::gitlab_scopes::{
+    GitlabOperation, diagnose_api_denial, explain_git_error, required_token_scopes,
+};
+use crate::ci::handoff::NotesArtifact;
+use crate::ci::http;
+use crate::ci::oidc::{OIDC_TOKEN_ENV_VAR, exchange_gitlab_id_token};
+use crate::ci::sweep::{SweepForge, SweepReport, sweep_commits};
+use crate::ci::{env, required_env};
 use crate::error::GitAiError;
 use crate::git::repository::exec_git;
-use crate::git::repository::find_repository_in_path;
-use chrono::{Duration, Utc};
+use crate::git::repository::find_repository;
+use crate::git::sync_authorship::fetch_authorship_notes;
+use crate::observability::timings::{self, Phase};
+use chrono::{DateTime, Duration, Utc};
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 const GITLAB_CI_TEMPLATE_YAML: &str = include_str!("workflow_templates/gitlab.yaml");
 
@@ -13,134 +26,332 @@ const GITLAB_CI_TEMPLATE_YAML: &str = include_str!("workflow_templates/gitlab.ya
 struct GitLabMergeRequest {
     iid: u64,
     title: Option<String>,
+    /// `opened`, `closed`, `locked` or `merged`
+    #[serde(default)]
+    state: Option<String>,
     source_branch: String,
     target_branch: String,
     sha: String,
     merge_commit_sha: Option<String>,
     squash_commit_sha: Option<String>,
     squash: Option<bool>,
+    #[serde(default)]
+    updated_at: Option<String>,
 }
 
-/// Query GitLab API for recently merged MRs and find one matching the current commit SHA.
-/// Returns None if no matching MR is found (this is not an error - just means this commit
-/// wasn't from a merged MR).
-pub fn get_gitlab_ci_context() -> Result<Option<CiContext>, GitAiError> {
-    // Read required environment variables
-    let api_url = std::env::var("CI_API_V4_URL").map_err(|_| {
-        GitAiError::Generic("CI_API_V4_URL environment variable not set".to_string())
-    })?;
-    let project_id = std::env::var("CI_PROJECT_ID").map_err(|_| {
-        GitAiError::Generic("CI_PROJECT_ID environment variable not set".to_string())
-    })?;
-    let commit_sha = std::env::var("CI_COMMIT_SHA").map_err(|_| {
-        GitAiError::Generic("CI_COMMIT_SHA environment variable not set".to_string())
-    })?;
-    let server_url = std::env::var("CI_SERVER_URL").map_err(|_| {
-        GitAiError::Generic("CI_SERVER_URL environment variable not set".to_string())
-    })?;
-    let project_path = std::env::var("CI_PROJECT_PATH").map_err(|_| {
-        GitAiError::Generic("CI_PROJECT_PATH environment variable not set".to_string())
-    })?;
+/// A merge request's car on a merge train, from the merge trains API
+#[derive(Debug, Clone, Deserialize)]
+struct GitLabMergeTrainCar {
+    /// `idle`, `stale`, `fresh`, `merging`, `merged` or `skip_merged`
+    status: String,
+    #[serde(default)]
+    pipeline: Option<GitLabTrainPipeline>,
+}
 
-    println!("[GitLab CI] Environment:");
-    println!("  CI_COMMIT_SHA: {}", commit_sha);
-    println!("  CI_PROJECT_ID: {}", project_id);
-    println!("  CI_PROJECT_PATH: {}", project_path);
-
-    // Get auth token - prefer GITLAB_TOKEN (explicitly configured with proper permissions),
-    // fall back to CI_JOB_TOKEN (auto-provided but may lack API permissions)
-    let (auth_header_name, auth_token) = if let Ok(gitlab_token) = std::env::var("GITLAB_TOKEN") {
-        println!("  Auth: GITLAB_TOKEN");
-        ("PRIVATE-TOKEN", gitlab_token)
-    } else if let Ok(job_token) = std::env::var("CI_JOB_TOKEN") {
-        println!("  Auth: CI_JOB_TOKEN");
-        ("JOB-TOKEN", job_token)
-    } else {
-        return Err(GitAiError::Generic(
-            "Neither GITLAB_TOKEN nor CI_JOB_TOKEN environment variable is set".to_string(),
-        ));
-    };
+#[derive(Debug, Clone, Deserialize)]
+struct GitLabTrainPipeline {
+    /// The train ref's commit: the MR merged onto the cars ahead of it
+    sha: String,
+}
 
-    // Calculate cutoff time (10 minutes ago) with safety buffer
-    let cutoff = Utc::now() - Duration::minutes(15);
-    let cutoff_str = cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+/// MRs per page when searching recently merged ones, and how many pages to read before
+/// giving up. Busy monorepos can merge more than a page's worth in the search window.
+const MERGE_REQUEST_PAGE_SIZE: usize = 100;
+const MAX_MERGE_REQUEST_PAGES: usize = 20;
 
-    // Query GitLab API for recently merged MRs
-    let endpoint = format!(
-        "{}/projects/{}/merge_requests?state=merged&updated_after={}&order_by=updated_at&sort=desc&per_page=100",
-        api_url, project_id, cutoff_str
+/// Environment variable overriding how long before the pipeline was created to search
+/// for the merged MR, e.g. `1h` for instances whose pipelines queue for a long time
+pub const MERGE_WINDOW_ENV_VAR: &str = "GIT_AI_CI_MERGE_WINDOW";
+const DEFAULT_MERGE_WINDOW: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// Overrides for the project and commit a run works on. In a child or multi-project
+/// pipeline the job's own CI_PROJECT_ID and CI_COMMIT_SHA can describe the downstream
+/// project rather than the one whose merge should be rewritten.
+#[derive(Debug, Clone, Default)]
+pub struct GitlabRunOptions {
+    pub project_id: Option<String>,
+    pub project_path: Option<String>,
+    pub commit_sha: Option<String>,
+    /// `--clone <full|partial>`; GIT_AI_CI_CLONE when unset
+    pub clone: Option<String>,
+    /// `--in-place`: work in the job's own checkout instead of cloning
+    pub in_place: bool,
+    /// `--batch`: also process earlier merges since the last successful pipeline
+    pub batch: bool,
+}
+
+/// Environment variables choosing how the repository is cloned when `--clone` isn't
+/// passed, and how many commits a partial clone starts with
+pub const CLONE_ENV_VAR: &str = "GIT_AI_CI_CLONE";
+pub const CLONE_DEPTH_ENV_VAR: &str = "GIT_AI_CI_CLONE_DEPTH";
+const DEFAULT_CLONE_DEPTH: u32 = 50;
+/// Times a partial clone is deepened, twice as far each time, before the rest of the
+/// history is fetched
+const MAX_DEEPENS: u32 = 4;
+
+/// How a run gets the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CloneStrategy {
+    /// Full history of the target branch
+    #[default]
+    Full,
+    /// The last `depth` commits without file contents (`--depth --filter=blob:none`),
+    /// deepened until the MR's merge base is in. Git fetches the contents attribution
+    /// reads as it reads them, so monorepos don't pay for a full clone.
+    Partial { depth: u32 },
+    /// No clone: the runner's checkout in the working directory, with the refs the run
+    /// needs fetched into it. A shallow checkout (GIT_DEPTH) is deepened by `depth` like
+    /// a partial clone.
+    InPlace { depth: u32 },
+}
+
+impl CloneStrategy {
+    /// In place with `--in-place`, else `--clone` if passed, else GIT_AI_CI_CLONE,
+    /// defaulting to a full clone
+    pub fn resolve(
+        option: Option<&str>,
+        in_place: bool,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, GitAiError> {
+        let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+        let depth = || match env(CLONE_DEPTH_ENV_VAR) {
+            None => Ok(DEFAULT_CLONE_DEPTH),
+            Some(depth) => depth
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|depth| *depth > 0)
+                .ok_or_else(|| {
+                    GitAiError::Generic(format!(
+                        "Invalid {} '{}': expected a number of commits",
+                        CLONE_DEPTH_ENV_VAR, depth
+                    ))
+                }),
+        };
+        if in_place {
+            if option.is_some() {
+                return Err(GitAiError::Generic(
+                    "--in-place and --clone can't be combined".to_string(),
+                ));
+            }
+            return Ok(Self::InPlace { depth: depth()? });
+        }
+        let name = option.map(str::to_string).or_else(|| env(CLONE_ENV_VAR));
+        match name.as_deref().map(str::trim) {
+            None | Some("full") => Ok(Self::Full),
+            Some("partial") => Ok(Self::Partial { depth: depth()? }),
+            Some(other) => Err(GitAiError::Generic(format!(
+                "Unknown clone strategy '{}': expected full or partial",
+                other
+            ))),
+        }
+    }
+}
+
+/// Variables a trigger job forwards to describe the upstream pipeline, honored when the
+/// pipeline was started by another pipeline
+const UPSTREAM_PROJECT_ID_VAR: &str = "UPSTREAM_PROJECT_ID";
+const UPSTREAM_PROJECT_PATH_VAR: &str = "UPSTREAM_PROJECT_PATH";
+const UPSTREAM_COMMIT_SHA_VAR: &str = "UPSTREAM_COMMIT_SHA";
+
+/// The project and commit a run works on, and where each came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitlabTarget {
+    pub project_id: String,
+    /// None when the project was overridden by id alone and its path must be looked up
+    pub project_path: Option<String>,
+    pub commit_sha: String,
+    pub project_source: &'static str,
+    pub commit_source: &'static str,
+}
+
+/// Pick the project and commit: explicit options first, then the upstream pipeline's
+/// forwarded variables, then the job's own CI_* variables
+pub fn resolve_gitlab_target(
+    options: &GitlabRunOptions,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<GitlabTarget, GitAiError> {
+    let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+    let triggered_by_pipeline = matches!(
+        env("CI_PIPELINE_SOURCE").as_deref(),
+        Some("pipeline" | "parent_pipeline")
     );
+    let upstream = |name: &str| {
+        if triggered_by_pipeline {
+            env(name)
+        } else {
+            None
+        }
+    };
+    let required = |name: &str| {
+        env(name)
+            .ok_or_else(|| GitAiError::Generic(format!("{} environment variable not set", name)))
+    };
 
-    println!("[GitLab CI] Querying API: {}", endpoint);
+    let (project_id, project_path, project_source) = if let Some(id) = &options.project_id {
+        (id.clone(), options.project_path.clone(), "--project-id")
+    } else if let Some(id) = upstream(UPSTREAM_PROJECT_ID_VAR) {
+        (
+            id,
+            upstream(UPSTREAM_PROJECT_PATH_VAR),
+            UPSTREAM_PROJECT_ID_VAR,
+        )
+    } else {
+        let path = match &options.project_path {
+            Some(path) => path.clone(),
+            None => required("CI_PROJECT_PATH")?,
+        };
+        (required("CI_PROJECT_ID")?, Some(path), "CI_PROJECT_ID")
+    };
+
+    let (commit_sha, commit_source) = if let Some(sha) = &options.commit_sha {
+        (sha.clone(), "--commit-sha")
+    } else if let Some(sha) = upstream(UPSTREAM_COMMIT_SHA_VAR) {
+        (sha, UPSTREAM_COMMIT_SHA_VAR)
+    } else {
+        (required("CI_COMMIT_SHA")?, "CI_COMMIT_SHA")
+    };
+
+    Ok(GitlabTarget {
+        project_id,
+        project_path,
+        commit_sha,
+        project_source,
+        commit_source,
+    })
+}
 
-    let response = minreq::get(&endpoint)
-        .with_header(auth_header_name, &auth_token)
+/// Look up a project's path from its id, for overrides that only name the id
+pub fn fetch_project_path(
+    api_url: &str,
+    project_id: &str,
+    auth: &GitlabApiAuth,
+) -> Result<String, GitAiError> {
+    let endpoint = format!("{}/projects/{}", api_url, project_id);
+    let _timing = timings::phase(Phase::Api);
+    let request = http::get(&endpoint)
+        .with_header(auth.header, &auth.token)
         .with_header(
             "User-Agent",
             format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
-        )
-        .with_timeout(30)
-        .send()
-        .map_err(|e| GitAiError::Generic(format!("GitLab API request failed: {}", e)))?;
-
+        );
+    let response = http::send(request, "GitLab API request")?;
+    let body = response.as_str().unwrap_or("");
     if response.status_code != 200 {
         return Err(GitAiError::Generic(format!(
-            "GitLab API returned status {}: {}",
-            response.status_code,
-            response.as_str().unwrap_or("unknown error")
+            "Could not look up GitLab project {} (status {}): {}\nPass --project-path to skip the lookup",
+            project_id, response.status_code, body
         )));
     }
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|project| {
+            project
+                .get("path_with_namespace")
+                .and_then(|p| p.as_str())
+                .map(str::to_string)
+        })
+        .ok_or_else(|| {
+            GitAiError::Generic(format!(
+                "GitLab project {} has no path_with_namespace",
+                project_id
+            ))
+        })
+}
 
-    let merge_requests: Vec<GitLabMergeRequest> =
-        serde_json::from_str(response.as_str().unwrap_or("[]")).map_err(|e| {
-            GitAiError::Generic(format!("Failed to parse GitLab API response: {}", e))
-        })?;
+/// Query GitLab API for recently merged MRs and find one matching the current commit SHA.
+/// Returns None if no matching MR is found (this is not an error - just means this commit
+/// wasn't from a merged MR).
+pub fn get_gitlab_ci_context(options: &GitlabRunOptions) -> Result<Option<CiContext>, GitAiError> {
+    // Read required environment variables
+    let api_url = required_env("CI_API_V4_URL")?;
+    let server_url = required_env("CI_SERVER_URL")?;
+    let target = resolve_gitlab_target(options, env)?;
+    let strategy = CloneStrategy::resolve(options.clone.as_deref(), options.in_place, env)?;
 
+    exchange_gitlab_id_token()?;
+    let auth = gitlab_api_auth()?;
+    let project_id = target.project_id;
+    let mut commit_sha = target.commit_sha;
+    let project_path = match target.project_path {
+        Some(path) => path,
+        None => fetch_project_path(&api_url, &project_id, &auth)?,
+    };
+
+    println!("[GitLab CI] Environment:");
+    println!("  Commit: {} (from {})", commit_sha, target.commit_source);
     println!(
-        "[GitLab CI] Found {} recently merged MRs",
-        merge_requests.len()
+        "  Project ID: {} (from {})",
+        project_id, target.project_source
     );
+    println!("  Project path: {}", project_path);
+    println!("  Auth: {}", auth.env_var);
 
-    // Log details of each MR for debugging
-    for mr in &merge_requests {
-        println!(
-            "[GitLab CI] MR !{}: \"{}\"",
-            mr.iid,
-            mr.title.as_deref().unwrap_or("(no title)")
-        );
-        println!("    source_branch: {}", mr.source_branch);
-        println!("    target_branch: {}", mr.target_branch);
-        println!("    sha (head): {}", mr.sha);
-        println!(
-            "    merge_commit_sha: {}",
-            mr.merge_commit_sha.as_deref().unwrap_or("(none)")
-        );
-        println!(
-            "    squash_commit_sha: {}",
-            mr.squash_commit_sha.as_deref().unwrap_or("(none)")
-        );
-        println!("    squash: {:?}", mr.squash);
-
-        // Check which SHA matches
-        let merge_matches = mr.merge_commit_sha.as_ref() == Some(&commit_sha);
-        let squash_matches = mr.squash_commit_sha.as_ref() == Some(&commit_sha);
-        println!(
-            "    matches CI_COMMIT_SHA? merge_commit={}, squash_commit={}",
-            merge_matches, squash_matches
-        );
-    }
-
-    // Find MR where merge_commit_sha OR squash_commit_sha matches our commit
-    let matching_mr = merge_requests.into_iter().find(|mr| {
-        mr.merge_commit_sha.as_ref() == Some(&commit_sha)
-            || mr.squash_commit_sha.as_ref() == Some(&commit_sha)
-    });
+    // An MR pipeline names its merge request; otherwise search recently merged ones
+    let matching_mr = match merge_request_iid(env) {
+        // A train merges its own commit rather than CI_COMMIT_SHA, so ask the train
+        Some((iid, source)) if is_merge_train_pipeline(env) => {
+            println!(
+                "[GitLab CI] Merge train pipeline for merge request !{} (from {})",
+                iid, source
+            );
+            match find_merge_train_merge_request(&api_url, &project_id, iid, &auth)? {
+                MergeTrainCar::Merged(mr, merged_sha) => {
+                    println!(
+                        "[GitLab CI] Merge train merged MR !{} as {}",
+                        iid, merged_sha
+                    );
+                    commit_sha = merged_sha;
+                    Some(*mr)
+                }
+                MergeTrainCar::Pending(status) => {
+                    println!(
+                        "[GitLab CI] MR !{} is {} on its merge train, not merged; run again once the train merges it. Skipping...",
+                        iid, status
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+        Some((iid, source)) => {
+            println!("[GitLab CI] Merge request !{} (from {})", iid, source);
+            match find_named_merge_request(&api_url, &project_id, iid, &commit_sha, &auth)? {
+                NamedMergeRequest::Matches(mr) => Some(mr),
+                NamedMergeRequest::NotMerged(state) => {
+                    println!(
+                        "[GitLab CI] MR !{} is {}, not merged; nothing to rewrite yet. Skipping...",
+                        iid, state
+                    );
+                    return Ok(None);
+                }
+                NamedMergeRequest::OtherCommit => {
+                    println!(
+                        "[GitLab CI] MR !{} was not merged as this commit; searching recently merged MRs",
+                        iid
+                    );
+                    find_recent_merge_request(&api_url, &project_id, &commit_sha, &auth)?
+                }
+            }
+        }
+        None => find_recent_merge_request(&api_url, &project_id, &commit_sha, &auth)?,
+    };
 
     let mr = match matching_mr {
         Some(mr) => {
             println!("[GitLab CI] Found matching MR !{}", mr.iid);
             mr
         }
+        None if target.commit_source == "CI_COMMIT_SHA" && is_push_pipeline() => {
+            println!("[GitLab CI] No MR produced this commit; checking it as a direct push");
+            return gitlab_push_context(
+                &server_url,
+                &project_path,
+                &commit_sha,
+                auth.env_var,
+                strategy,
+            )
+            .map(Some);
+        }
         None => {
             println!("[GitLab CI] No recent MR found corresponding to this commit. Skipping...");
             return Ok(None);
@@ -148,14 +359,14 @@ pub fn get_gitlab_ci_context() -> Result<Option<CiContext>, GitAiError> {
     };
 
     // Determine which commit SHA to use as the "merge commit" for rewriting
-    // If this was a squash merge, CI_COMMIT_SHA might be the squash commit
+    // If this was a squash merge, the commit might be the squash commit
     // (which is what we want to rewrite authorship TO)
     let effective_merge_sha = if mr.squash_commit_sha.as_ref() == Some(&commit_sha) {
-        println!("[GitLab CI] CI_COMMIT_SHA matches squash_commit_sha - this is a squash merge");
+        println!("[GitLab CI] Commit matches squash_commit_sha - this is a squash merge");
         commit_sha.clone()
     } else {
         println!(
-            "[GitLab CI] CI_COMMIT_SHA matches merge_commit_sha - checking if this is a squash+merge"
+            "[GitLab CI] Commit matches merge_commit_sha - checking if this is a squash+merge"
         );
         // If squash was used but we matched on merge_commit_sha,
         // the actual squash commit is in squash_commit_sha
@@ -175,67 +386,15 @@ pub fn get_gitlab_ci_context() -> Result<Option<CiContext>, GitAiError> {
         effective_merge_sha
     );
 
-    // Found a matching MR - clone and fetch
-    let clone_dir = "git-ai-ci-clone".to_string();
-    let clone_url = format!("{}/{}.git", server_url, project_path);
-
-    // Build authenticated URLs:
-    // - clone_auth_url: Use CI_JOB_TOKEN for clone/fetch (read-only is fine)
-    // - push_auth_url: Use GITLAB_TOKEN for push (needs write_repository scope)
-    let scheme = if server_url.starts_with("https") {
-        "https"
-    } else {
-        "http"
-    };
-    let server_host = server_url
-        .trim_start_matches("https://")
-        .trim_start_matches("http://");
-
-    // Clone URL uses CI_JOB_TOKEN (available by default, read-only)
-    let clone_auth_url = if let Ok(job_token) = std::env::var("CI_JOB_TOKEN") {
-        println!("[GitLab CI] Using CI_JOB_TOKEN for clone/fetch");
-        clone_url.replace(
-            &server_url,
-            &format!("{}://gitlab-ci-token:{}@{}", scheme, job_token, server_host),
-        )
-    } else {
-        println!("[GitLab CI] Warning: CI_JOB_TOKEN not available, clone may fail");
-        clone_url.clone()
-    };
-
-    // Push URL uses GITLAB_TOKEN (needs write_repository scope)
-    let push_auth_url = if let Ok(gitlab_token) = std::env::var("GITLAB_TOKEN") {
-        println!("[GitLab CI] Using GITLAB_TOKEN for push (write_repository scope)");
-        clone_url.replace(
-            &server_url,
-            &format!("{}://oauth2:{}@{}", scheme, gitlab_token, server_host),
-        )
-    } else {
-        println!("[GitLab CI] Warning: GITLAB_TOKEN not set - push will likely fail");
-        println!("[GitLab CI] Create a Project Access Token with write_repository scope");
-        clone_auth_url.clone()
-    };
-
-    // Clone the repo using CI_JOB_TOKEN
-    println!("[GitLab CI] Cloning repository...");
-    exec_git(&[
-        "clone".to_string(),
-        "--branch".to_string(),
-        mr.target_branch.clone(),
-        clone_auth_url.clone(),
-        clone_dir.clone(),
-    ])?;
-
-    // Set origin URL to GITLAB_TOKEN URL for push
-    println!("[GitLab CI] Setting origin URL for push...");
-    exec_git(&[
-        "-C".to_string(),
-        clone_dir.clone(),
-        "remote".to_string(),
-        "set-url".to_string(),
-        "origin".to_string(),
-        push_auth_url,
-    ])?;
+    // Found a matching MR - clone (or use the job's checkout) and fetch
+    let workspace = prepare_workspace(
+        &server_url,
+        &project_path,
+        &mr.target_branch,
+        auth.env_var,
+        strategy,
+    )?;
+    let repo_args = &workspace.repo_args;
 
     // Fetch MR commits using GitLab's special MR refs
     // This is necessary because the MR branch may be deleted after merge
@@ -244,18 +403,33 @@ pub fn get_gitlab_ci_context() -> Result<Option<CiContext>, GitAiError> {
         "[GitLab CI] Fetching MR commits from refs/merge-requests/{}/head...",
         mr.iid
     );
-    exec_git(&[
-        "-C".to_string(),
-        clone_dir.clone(),
-        "fetch".to_string(),
-        clone_auth_url,
-        format!(
-            "refs/merge-requests/{}/head:refs/gitlab/mr/{}",
-            mr.iid, mr.iid
-        ),
-    ])?;
-
-    let repo = find_repository_in_path(&clone_dir)?;
+    // Forced, since a reused checkout can hold the ref from an earlier job
+    let mr_refspec = format!(
+        "+refs/merge-requests/{}/head:refs/gitlab/mr/{}",
+        mr.iid, mr.iid
+    );
+    let mut fetch_args = repo_args.clone();
+    fetch_args.push("fetch".to_string());
+    if let CloneStrategy::Partial { depth } = workspace.history {
+        fetch_args.push(format!("--depth={}", depth));
+    }
+    fetch_args.extend(["origin".to_string(), mr_refspec.clone()]);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&fetch_args).map_err(|e| explain_git_error(&workspace.git_token_source, e))?;
+    }
+    if let CloneStrategy::Partial { depth } = workspace.history {
+        // Attribution walks the MR's commits back to where it branched off
+        deepen_until(
+            repo_args,
+            &[mr.target_branch.clone(), mr_refspec],
+            depth,
+            &workspace.git_token_source,
+            || git_succeeds(repo_args, &["merge-base", &mr.sha, &mr.target_branch]),
+        )?;
+    }
+
+    let repo = find_repository(repo_args)?;
 
     println!(
         "[GitLab CI] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
@@ -270,14 +444,1354 @@ pub fn get_gitlab_ci_context() -> Result<Option<CiContext>, GitAiError> {
             head_sha: mr.sha.clone(),
             base_ref: mr.target_branch.clone(),
             base_sha: String::new(), // Not readily available from MR API, but not used in current impl
+            merge_request: Some(mr.iid),
         },
-        temp_dir: PathBuf::from(clone_dir),
+        temp_dir: workspace.temp_dir,
     }))
 }
 
+/// The merge request an MR pipeline runs for: CI_MERGE_REQUEST_IID, else the iid in
+/// CI_MERGE_REQUEST_REF_PATH (`refs/merge-requests/<iid>/head`), which merge result and
+/// merge train pipelines also set. Returns the iid and the variable it came from.
+fn merge_request_iid(env: impl Fn(&str) -> Option<String>) -> Option<(u64, &'static str)> {
+    if let Some(iid) = env("CI_MERGE_REQUEST_IID").and_then(|v| v.trim().parse().ok()) {
+        return Some((iid, "CI_MERGE_REQUEST_IID"));
+    }
+    env("CI_MERGE_REQUEST_REF_PATH")
+        .and_then(|path| {
+            path.strip_prefix("refs/merge-requests/")?
+                .split('/')
+                .next()?
+                .parse()
+                .ok()
+        })
+        .map(|iid| (iid, "CI_MERGE_REQUEST_REF_PATH"))
+}
+
+/// Whether the pipeline runs for a merge train car, whose commit is the train ref's
+/// rather than the one that lands on the target branch
+fn is_merge_train_pipeline(env: impl Fn(&str) -> Option<String>) -> bool {
+    env("CI_MERGE_REQUEST_EVENT_TYPE").is_some_and(|event| event.trim() == "merge_train")
+}
+
+/// Where a merge request stands on its merge train
+enum MergeTrainCar {
+    /// Merged, with the SHA that landed on the target branch
+    Merged(Box<GitLabMergeRequest>, String),
+    /// Still riding the train (or dropped from it), with the car's status
+    Pending(String),
+}
+
+impl GitLabMergeTrainCar {
+    /// The commit the train landed for `mr`: its squash or merge commit, else the train
+    /// ref's commit, which a fast-forward train moves the target branch to
+    fn merged_sha(&self, mr: &GitLabMergeRequest) -> Option<String> {
+        mr.squash_commit_sha
+            .clone()
+            .or_else(|| mr.merge_commit_sha.clone())
+            .or_else(|| self.pipeline.as_ref().map(|pipeline| pipeline.sha.clone()))
+    }
+}
+
+/// Look up the merge request's car on its merge train, and the MR itself once merged
+fn find_merge_train_merge_request(
+    api_url: &str,
+    project_id: &str,
+    iid: u64,
+    auth: &GitlabApiAuth,
+) -> Result<MergeTrainCar, GitAiError> {
+    let endpoint = format!(
+        "{}/projects/{}/merge_trains/merge_requests/{}",
+        api_url, project_id, iid
+    );
+    let body = gitlab_api_get(&endpoint, auth)?.body;
+    let car: GitLabMergeTrainCar = serde_json::from_str(&body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse GitLab API response: {}", e)))?;
+    if !matches!(car.status.as_str(), "merged" | "skip_merged") {
+        return Ok(MergeTrainCar::Pending(car.status));
+    }
+
+    let endpoint = format!("{}/projects/{}/merge_requests/{}", api_url, project_id, iid);
+    let body = gitlab_api_get(&endpoint, auth)?.body;
+    let mr: GitLabMergeRequest = serde_json::from_str(&body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse GitLab API response: {}", e)))?;
+    match car.merged_sha(&mr) {
+        Some(merged_sha) => Ok(MergeTrainCar::Merged(Box::new(mr), merged_sha)),
+        None => Err(GitAiError::Generic(format!(
+            "Merge train merged MR !{} but GitLab reports no merged commit for it",
+            iid
+        ))),
+    }
+}
+
+/// What a merge request named by the pipeline says about the commit
+enum NamedMergeRequest {
+    /// Merged as the commit (its merge or squash commit)
+    Matches(GitLabMergeRequest),
+    /// Still open (or closed): the pipeline ran before any merge, e.g. a merge result
+    /// pipeline, whose commit only previews the merge
+    NotMerged(String),
+    /// Merged, but as some other commit
+    OtherCommit,
+}
+
+impl GitLabMergeRequest {
+    fn merged_as(&self, commit_sha: &str) -> bool {
+        self.merge_commit_sha.as_deref() == Some(commit_sha)
+            || self.squash_commit_sha.as_deref() == Some(commit_sha)
+    }
+}
+
+/// A successful GitLab API response
+struct GitlabApiResponse {
+    body: String,
+    /// URL of the next page of a paginated list
+    next_page: Option<String>,
+}
+
+/// GET a GitLab API endpoint, explaining a denial in terms of the token's scopes
+fn gitlab_api_get(endpoint: &str, auth: &GitlabApiAuth) -> Result<GitlabApiResponse, GitAiError> {
+    println!("[GitLab CI] Querying API: {}", endpoint);
+
+    let _timing = timings::phase(Phase::Api);
+    let request = http::get(endpoint)
+        .with_header(auth.header, &auth.token)
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        );
+    let response = http::send(request, "GitLab API request")?;
+
+    if response.status_code != 200 {
+        let body = response.as_str().unwrap_or("unknown error");
+        let mut message = format!(
+            "GitLab API returned status {}: {}",
+            response.status_code, body
+        );
+        if let Some(diagnosis) = diagnose_api_denial(
+            GitlabOperation::ListMergeRequests,
+            auth.env_var,
+            response.status_code,
+            body,
+        ) {
+            message.push('\n');
+            message.push_str(&diagnosis);
+        }
+        return Err(GitAiError::Generic(message));
+    }
+    Ok(GitlabApiResponse {
+        body: response.as_str().unwrap_or("").to_string(),
+        next_page: next_page_url(endpoint, |name| {
+            response.headers.get(name).map(String::as_str)
+        }),
+    })
+}
+
+/// The next page of a list: the `Link` header's `rel="next"` URL, which keyset
+/// pagination only gives, else the offset pagination `X-Next-Page` number (empty on the
+/// last page) applied to `endpoint`
+fn next_page_url<'a>(endpoint: &str, headers: impl Fn(&str) -> Option<&'a str>) -> Option<String> {
+    let from_link = headers("link").and_then(|link| {
+        link.split(',').find_map(|part| {
+            let (url, params) = part.split_once(';')?;
+            params
+                .split(';')
+                .any(|param| param.trim().replace(' ', "") == "rel=\"next\"")
+                .then(|| {
+                    url.trim()
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_string()
+                })
+        })
+    });
+    from_link.or_else(|| {
+        let page = headers("x-next-page")?.trim();
+        if page.is_empty() {
+            return None;
+        }
+        let mut url = url::Url::parse(endpoint).ok()?;
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != "page")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("page", page);
+        Some(url.to_string())
+    })
+}
+
+/// Look up the merge request the pipeline names, rather than searching for it
+fn find_named_merge_request(
+    api_url: &str,
+    project_id: &str,
+    iid: u64,
+    commit_sha: &str,
+    auth: &GitlabApiAuth,
+) -> Result<NamedMergeRequest, GitAiError> {
+    let endpoint = format!("{}/projects/{}/merge_requests/{}", api_url, project_id, iid);
+    let body = gitlab_api_get(&endpoint, auth)?.body;
+    let mr: GitLabMergeRequest = serde_json::from_str(&body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse GitLab API response: {}", e)))?;
+    let state = mr.state.clone().unwrap_or_else(|| "unknown".to_string());
+    Ok(if mr.merged_as(commit_sha) {
+        println!("[GitLab CI] MR !{} was merged as this commit", mr.iid);
+        NamedMergeRequest::Matches(mr)
+    } else if state != "merged" {
+        NamedMergeRequest::NotMerged(state)
+    } else {
+        NamedMergeRequest::OtherCommit
+    })
+}
+
+/// The oldest update time an MR merged as the pipeline's commit can have: the merge
+/// window ([`MERGE_WINDOW_ENV_VAR`], 15 minutes by default) before CI_PIPELINE_CREATED_AT.
+/// Counting from when the pipeline was created rather than from `now` keeps a job that
+/// sat in a queue or waited for a runner from missing its MR.
+fn merge_request_cutoff(
+    env: impl Fn(&str) -> Option<String>,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, GitAiError> {
+    let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+    let window = match env(MERGE_WINDOW_ENV_VAR) {
+        Some(value) => humantime::parse_duration(value.trim()).map_err(|_| {
+            GitAiError::Generic(format!(
+                "Invalid {} '{}': expected a duration like 15m or 2h",
+                MERGE_WINDOW_ENV_VAR, value
+            ))
+        })?,
+        None => DEFAULT_MERGE_WINDOW,
+    };
+    let created_at = env("CI_PIPELINE_CREATED_AT").and_then(|value| {
+        match DateTime::parse_from_rfc3339(value.trim()) {
+            Ok(at) => Some(at.with_timezone(&Utc)),
+            Err(_) => {
+                println!(
+                    "[GitLab CI] Ignoring unparseable CI_PIPELINE_CREATED_AT '{}'",
+                    value
+                );
+                None
+            }
+        }
+    });
+    // A clock skewed ahead of the server's could put the creation time in the future
+    let anchor = created_at.map_or(now, |at| at.min(now));
+    let window = Duration::from_std(window).unwrap_or(Duration::MAX);
+    Ok(anchor
+        .checked_sub_signed(window)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC))
+}
+
+/// Search the MRs merged in the merge window for one merged as `commit_sha`. Can pick
+/// the wrong MR when two merge the same content, so only used when the pipeline doesn't
+/// name its MR.
+fn find_recent_merge_request(
+    api_url: &str,
+    project_id: &str,
+    commit_sha: &str,
+    auth: &GitlabApiAuth,
+) -> Result<Option<GitLabMergeRequest>, GitAiError> {
+    let cutoff = merge_request_cutoff(env, Utc::now())?;
+    let cutoff_str = cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    println!("[GitLab CI] Searching MRs merged since {}", cutoff_str);
+
+    // Query GitLab API for recently merged MRs, newest first, a page at a time until the
+    // MR turns up or the pages go past the cutoff
+    let mut endpoint = Some(format!(
+        "{}/projects/{}/merge_requests?state=merged&updated_after={}&order_by=updated_at&sort=desc&per_page={}",
+        api_url, project_id, cutoff_str, MERGE_REQUEST_PAGE_SIZE
+    ));
+    for page in 1..=MAX_MERGE_REQUEST_PAGES {
+        let Some(url) = endpoint.take() else {
+            break;
+        };
+        let response = gitlab_api_get(&url, auth)?;
+        let merge_requests: Vec<GitLabMergeRequest> = serde_json::from_str(&response.body)
+            .map_err(|e| {
+                GitAiError::Generic(format!("Failed to parse GitLab API response: {}", e))
+            })?;
+        println!(
+            "[GitLab CI] Found {} recently merged MRs on page {}",
+            merge_requests.len(),
+            page
+        );
+        log_merge_requests(&merge_requests, commit_sha);
+
+        let past_cutoff = merge_requests.last().is_some_and(|mr| {
+            mr.updated_at
+                .as_deref()
+                .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+                .is_some_and(|at| at < cutoff)
+        });
+        // Find MR where merge_commit_sha OR squash_commit_sha matches our commit
+        if let Some(mr) = merge_requests
+            .into_iter()
+            .find(|mr| mr.merged_as(commit_sha))
+        {
+            return Ok(Some(mr));
+        }
+        if past_cutoff {
+            break;
+        }
+        endpoint = response.next_page;
+    }
+    Ok(None)
+}
+
+/// Log details of each MR for debugging
+fn log_merge_requests(merge_requests: &[GitLabMergeRequest], commit_sha: &str) {
+    for mr in merge_requests {
+        println!(
+            "[GitLab CI] MR !{}: \"{}\"",
+            mr.iid,
+            mr.title.as_deref().unwrap_or("(no title)")
+        );
+        println!("    source_branch: {}", mr.source_branch);
+        println!("    target_branch: {}", mr.target_branch);
+        println!("    sha (head): {}", mr.sha);
+        println!(
+            "    merge_commit_sha: {}",
+            mr.merge_commit_sha.as_deref().unwrap_or("(none)")
+        );
+        println!(
+            "    squash_commit_sha: {}",
+            mr.squash_commit_sha.as_deref().unwrap_or("(none)")
+        );
+        println!("    squash: {:?}", mr.squash);
+
+        // Check which SHA matches
+        let merge_matches = mr.merge_commit_sha.as_deref() == Some(commit_sha);
+        let squash_matches = mr.squash_commit_sha.as_deref() == Some(commit_sha);
+        println!(
+            "    matches commit? merge_commit={}, squash_commit={}",
+            merge_matches, squash_matches
+        );
+    }
+}
+
+/// Most commits `--batch` looks back through
+const MAX_BATCH_COMMITS: usize = 50;
+
+/// With `--batch`, process the merges that landed on the target branch after the last
+/// successful pipeline's commit and before this run's, e.g. ones whose pipelines a merge
+/// train or a quick run of merges skipped. Each commit without an authorship note is
+/// traced to its MR and processed, oldest first, as `ci sweep` would. Falls back to
+/// CI_COMMIT_BEFORE_SHA when the pipelines can't be listed; None with neither.
+pub fn process_earlier_merges(
+    context: &CiContext,
+    options: &GitlabRunOptions,
+) -> Result<Option<SweepReport>, GitAiError> {
+    let CiEvent::Merge {
+        merge_commit_sha,
+        base_ref,
+        ..
+    } = &context.event
+    else {
+        return Ok(None);
+    };
+    let api_url = required_env("CI_API_V4_URL")?;
+    let server_url = required_env("CI_SERVER_URL")?;
+    let target = resolve_gitlab_target(options, env)?;
+    let auth = gitlab_api_auth()?;
+    let project_path = match target.project_path {
+        Some(path) => path,
+        None => fetch_project_path(&api_url, &target.project_id, &auth)?,
+    };
+
+    let last_run = last_successful_pipeline_sha(&api_url, &target.project_id, base_ref, &auth)
+        .unwrap_or_else(|e| {
+            println!(
+                "[GitLab CI] Could not list pipelines ({}); counting back to the previous push instead",
+                e.to_string().lines().next().unwrap_or("")
+            );
+            None
+        });
+    let Some(since) = last_run.or_else(|| commit_before_sha(env)) else {
+        println!(
+            "[GitLab CI] No earlier pipeline or push on {}; nothing else to process",
+            base_ref
+        );
+        return Ok(None);
+    };
+    println!(
+        "[GitLab CI] Looking for unprocessed merges on {} since {}",
+        base_ref, since
+    );
+
+    let repo_args = context.repo.global_args_for_exec();
+    fetch_authorship_notes(&context.repo, "origin")?;
+    let mut args = repo_args.clone();
+    args.extend([
+        "rev-list".to_string(),
+        "--first-parent".to_string(),
+        format!("--max-count={}", MAX_BATCH_COMMITS),
+        format!("{}^", merge_commit_sha),
+    ]);
+    if git_succeeds(
+        &repo_args,
+        &["cat-file", "-e", &format!("{}^{{commit}}", since)],
+    ) {
+        args.push(format!("^{}", since));
+    } else {
+        println!(
+            "[GitLab CI] {} isn't in the repository; looking back at most {} commits",
+            since, MAX_BATCH_COMMITS
+        );
+    }
+    let output = exec_git(&args)?;
+    let mut commits: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    commits.reverse();
+
+    let forge = SweepForge::gitlab(
+        &api_url,
+        &server_url,
+        &target.project_id,
+        &project_path,
+        auth,
+        gitlab_git_credential(),
+    );
+    sweep_commits(&repo_args, &forge, base_ref.clone(), &commits, false).map(Some)
+}
+
+/// The commit the last successful pipeline on `branch` ran for
+fn last_successful_pipeline_sha(
+    api_url: &str,
+    project_id: &str,
+    branch: &str,
+    auth: &GitlabApiAuth,
+) -> Result<Option<String>, GitAiError> {
+    let endpoint = format!(
+        "{}/projects/{}/pipelines?ref={}&status=success&order_by=id&sort=desc&per_page=1",
+        api_url,
+        project_id,
+        url::form_urlencoded::byte_serialize(branch.as_bytes()).collect::<String>()
+    );
+    println!("[GitLab CI] Querying API: {}", endpoint);
+    let _timing = timings::phase(Phase::Api);
+    let request = http::get(&endpoint)
+        .with_header(auth.header, &auth.token)
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        );
+    let response = http::send(request, "GitLab API request")?;
+    let body = response.as_str().unwrap_or("");
+    if response.status_code != 200 {
+        return Err(GitAiError::Generic(format!(
+            "GitLab API returned status {}: {}",
+            response.status_code, body
+        )));
+    }
+    let pipelines: Vec<serde_json::Value> = serde_json::from_str(body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse GitLab API response: {}", e)))?;
+    Ok(pipelines
+        .first()
+        .and_then(|pipeline| pipeline["sha"].as_str())
+        .map(str::to_string))
+}
+
+/// Comment the attribution summary of `commits` on the MR the context was built for.
+/// The token's scopes are checked first where GitLab reports them, so a token without
+/// `api` fails with the scope to add rather than a bare 403.
+pub fn post_merge_request_comment(
+    context: &CiContext,
+    commits: &[String],
+    options: &GitlabRunOptions,
+) -> Result<(), GitAiError> {
+    let CiEvent::Merge {
+        merge_request: Some(iid),
+        ..
+    } = &context.event
+    else {
+        return Ok(());
+    };
+    let Some(comment) = attribution_comment(&context.repo, commits)? else {
+        println!("[GitLab CI] No added lines to summarize on MR !{}", iid);
+        return Ok(());
+    };
+    let api_url = required_env("CI_API_V4_URL")?;
+    let target = resolve_gitlab_target(options, env)?;
+    let auth = gitlab_api_auth()?;
+
+    let operation = GitlabOperation::PostComment;
+    if auth.env_var == "CI_JOB_TOKEN" {
+        let diagnosis = diagnose_api_denial(operation, auth.env_var, 403, "")
+            .unwrap_or_else(|| operation.requirement());
+        return Err(GitAiError::Generic(diagnosis));
+    }
+    if let Some(scopes) = token_scopes(&api_url, &auth)
+        && !scopes
+            .iter()
+            .any(|scope| scope == operation.minimum_scope())
+    {
+        return Err(GitAiError::Generic(format!(
+            "{} has the {} scope(s) but needs {} to {}",
+            auth.env_var,
+            scopes.join(", "),
+            operation.minimum_scope(),
+            operation.description()
+        )));
+    }
+
+    let endpoint = format!(
+        "{}/projects/{}/merge_requests/{}/notes",
+        api_url, target.project_id, iid
+    );
+    println!("[GitLab CI] Commenting on MR !{}", iid);
+    let _timing = timings::phase(Phase::Api);
+    let request = http::post(&endpoint)
+        .with_header(auth.header, &auth.token)
+        .with_header("Content-Type", "application/json")
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        )
+        .with_body(serde_json::json!({ "body": comment }).to_string());
+    let response = http::send(request, "GitLab API request")?;
+    if response.status_code != 201 {
+        let body = response.as_str().unwrap_or("unknown error");
+        let mut message = format!(
+            "GitLab API returned status {}: {}",
+            response.status_code, body
+        );
+        if let Some(diagnosis) =
+            diagnose_api_denial(operation, auth.env_var, response.status_code, body)
+        {
+            message.push('\n');
+            message.push_str(&diagnosis);
+        }
+        return Err(GitAiError::Generic(message));
+    }
+    println!(
+        "[GitLab CI] Commented the attribution summary on MR !{}",
+        iid
+    );
+    Ok(())
+}
+
+/// The scopes of the API token, from its own token record. None when GitLab won't say,
+/// e.g. for an older version or a token that can't look itself up.
+fn token_scopes(api_url: &str, auth: &GitlabApiAuth) -> Option<Vec<String>> {
+    let endpoint = format!("{}/personal_access_tokens/self", api_url);
+    let _timing = timings::phase(Phase::Api);
+    let request = http::get(&endpoint)
+        .with_header(auth.header, &auth.token)
+        .with_header(
+            "User-Agent",
+            format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+        );
+    let response = http::send(request, "GitLab API request").ok()?;
+    if response.status_code != 200 {
+        return None;
+    }
+    let token: serde_json::Value = serde_json::from_str(response.as_str().ok()?).ok()?;
+    token["scopes"].as_array().map(|scopes| {
+        scopes
+            .iter()
+            .filter_map(|scope| scope.as_str().map(str::to_string))
+            .collect()
+    })
+}
+
+/// The branch tip before the push a pipeline runs for. GitLab sends all zeros for a new
+/// branch.
+fn commit_before_sha(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    env("CI_COMMIT_BEFORE_SHA").filter(|sha| !sha.is_empty() && !sha.chars().all(|c| c == '0'))
+}
+
+fn is_push_pipeline() -> bool {
+    env("CI_PIPELINE_SOURCE").as_deref() == Some("push")
+}
+
+/// Context for a push that no MR produced, such as a hotfix pushed straight to a
+/// protected branch
+fn gitlab_push_context(
+    server_url: &str,
+    project_path: &str,
+    commit_sha: &str,
+    api_token_source: &str,
+    strategy: CloneStrategy,
+) -> Result<CiContext, GitAiError> {
+    let branch = required_env("CI_COMMIT_BRANCH")?;
+    let before_sha = commit_before_sha(env);
+
+    let workspace = prepare_workspace(
+        server_url,
+        project_path,
+        &branch,
+        api_token_source,
+        strategy,
+    )?;
+    let repo_args = &workspace.repo_args;
+    if let (CloneStrategy::Partial { depth }, Some(before_sha)) = (workspace.history, &before_sha) {
+        // The check walks the pushed commits back to the old tip
+        deepen_until(
+            repo_args,
+            std::slice::from_ref(&branch),
+            depth,
+            &workspace.git_token_source,
+            || {
+                git_succeeds(
+                    repo_args,
+                    &["merge-base", "--is-ancestor", before_sha, commit_sha],
+                )
+            },
+        )?;
+    }
+    let repo = find_repository(repo_args)?;
+
+    println!(
+        "[GitLab CI] Created CiContext: push to {}, before={}, after={}",
+        branch,
+        before_sha.as_deref().unwrap_or("(new branch)"),
+        commit_sha
+    );
+
+    Ok(CiContext {
+        repo,
+        event: CiEvent::Push {
+            ref_name: branch,
+            before_sha,
+            after_sha: commit_sha.to_string(),
+        },
+        temp_dir: workspace.temp_dir,
+    })
+}
+
+/// Context for pushing the notes a fork's pipeline handed off in `artifact`, from a
+/// clone of this project's base branch
+pub fn gitlab_ingest_context(artifact: &NotesArtifact) -> Result<CiContext, GitAiError> {
+    let server_url = required_env("CI_SERVER_URL")?;
+    let project_path = required_env("CI_PROJECT_PATH")?;
+    exchange_gitlab_id_token()?;
+    let auth = gitlab_api_auth()?;
+    println!(
+        "[GitLab CI] Ingesting notes for {} on {} of {}",
+        artifact.merge_commit_sha, artifact.base_ref, project_path
+    );
+
+    let workspace = prepare_workspace(
+        &server_url,
+        &project_path,
+        &artifact.base_ref,
+        auth.env_var,
+        CloneStrategy::Full,
+    )?;
+    let repo = find_repository(&workspace.repo_args)?;
+    Ok(CiContext {
+        repo,
+        event: artifact.event(),
+        temp_dir: workspace.temp_dir,
+    })
+}
+
+/// The repository a run works in
+struct Workspace {
+    /// Git args for running in it, with the git credential
+    repo_args: Vec<String>,
+    /// Variable the git credential came from
+    git_token_source: String,
+    /// How much history it has: the strategy it was cloned with, or for the job's own
+    /// checkout Partial if the runner fetched it shallow and Full otherwise
+    history: CloneStrategy,
+    /// Directory to remove after the run; empty for the job's own checkout
+    temp_dir: PathBuf,
+}
+
+/// Clone `branch` of the project into `git-ai-ci-clone` with the git credential, or with
+/// [`CloneStrategy::InPlace`] fetch it into the checkout in the working directory
+fn prepare_workspace(
+    server_url: &str,
+    project_path: &str,
+    branch: &str,
+    api_token_source: &str,
+    strategy: CloneStrategy,
+) -> Result<Workspace, GitAiError> {
+    let clone_url = format!("{}/{}.git", server_url, project_path);
+
+    let credential = gitlab_git_credential();
+    let git_token_source = credential
+        .as_ref()
+        .map(CiGitCredential::token_env_var)
+        .unwrap_or(api_token_source)
+        .to_string();
+    match credential.as_ref().map(CiGitCredential::token_env_var) {
+        Some("CI_JOB_TOKEN") => {
+            println!("[GitLab CI] Using CI_JOB_TOKEN for clone/fetch");
+            println!("[GitLab CI] Warning: GITLAB_TOKEN not set - push will likely fail");
+            println!(
+                "[GitLab CI] Create a Project Access Token with the {} scopes",
+                required_token_scopes().join(" and ")
+            );
+        }
+        Some(token_env_var) => {
+            println!(
+                "[GitLab CI] Using {} for git operations (write_repository scope)",
+                token_env_var
+            );
+        }
+        None => println!("[GitLab CI] Warning: no git credentials available, clone may fail"),
+    }
+
+    let CloneStrategy::InPlace { depth } = strategy else {
+        let clone_dir = "git-ai-ci-clone";
+        clone_project(
+            &clone_url,
+            branch,
+            clone_dir,
+            credential.as_ref(),
+            &git_token_source,
+            strategy,
+        )?;
+        return Ok(Workspace {
+            repo_args: git_args_for_dir(clone_dir, credential.as_ref()),
+            git_token_source,
+            history: strategy,
+            temp_dir: PathBuf::from(clone_dir),
+        });
+    };
+
+    let mut repo_args = git_args_for_dir(".", credential.as_ref());
+    // The runner's origin URL embeds CI_JOB_TOKEN, which git sends instead of asking the
+    // credential helper and which can't push, so notes are pushed to the bare URL
+    repo_args.extend([
+        "-c".to_string(),
+        format!("remote.origin.pushurl={}", clone_url),
+    ]);
+    if !git_succeeds(&repo_args, &["rev-parse", "--is-inside-work-tree"]) {
+        return Err(GitAiError::Generic(
+            "--in-place needs the job's checkout in the working directory, but it isn't a git repository (GIT_STRATEGY=none?)"
+                .to_string(),
+        ));
+    }
+    let history = if is_shallow(&repo_args) {
+        println!("[GitLab CI] Using the job's shallow checkout instead of cloning");
+        CloneStrategy::Partial { depth }
+    } else {
+        println!("[GitLab CI] Using the job's checkout instead of cloning");
+        CloneStrategy::Full
+    };
+
+    // Runners check out a detached commit, so the target branch is fetched as a local
+    // branch, as a clone would have it
+    println!("[GitLab CI] Fetching {}...", branch);
+    let mut fetch_args = repo_args.clone();
+    fetch_args.push("fetch".to_string());
+    if let CloneStrategy::Partial { depth } = history {
+        fetch_args.push(format!("--depth={}", depth));
+    }
+    fetch_args.extend([
+        "origin".to_string(),
+        format!("+refs/heads/{}:refs/heads/{}", branch, branch),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&fetch_args).map_err(|e| explain_git_error(&git_token_source, e))?;
+    }
+
+    Ok(Workspace {
+        repo_args,
+        git_token_source,
+        history,
+        temp_dir: PathBuf::new(),
+    })
+}
+
+/// Clone `branch` from `clone_url` into `clone_dir` with the git credential
+fn clone_project(
+    clone_url: &str,
+    branch: &str,
+    clone_dir: &str,
+    credential: Option<&CiGitCredential>,
+    git_token_source: &str,
+    strategy: CloneStrategy,
+) -> Result<(), GitAiError> {
+    let mut clone_args = forge_git_args(credential);
+    clone_args.push("clone".to_string());
+    match strategy {
+        CloneStrategy::Full | CloneStrategy::InPlace { .. } => {
+            println!("[GitLab CI] Cloning repository...")
+        }
+        CloneStrategy::Partial { depth } => {
+            println!(
+                "[GitLab CI] Cloning the last {} commits without file contents...",
+                depth
+            );
+            clone_args.extend([
+                format!("--depth={}", depth),
+                "--filter=blob:none".to_string(),
+            ]);
+        }
+    }
+    clone_args.extend([
+        "--branch".to_string(),
+        branch.to_string(),
+        clone_url.to_string(),
+        clone_dir.to_string(),
+    ]);
+    let _timing = timings::phase(Phase::Clone);
+    exec_git(&clone_args)
+        .map(|_| ())
+        .map_err(|e| explain_git_error(git_token_source, e))
+}
+
+/// Deepen a partial clone until `ready` holds, fetching `refspecs` twice as far back each
+/// time, then the rest of their history if it still doesn't after [`MAX_DEEPENS`] tries
+fn deepen_until(
+    repo_args: &[String],
+    refspecs: &[String],
+    depth: u32,
+    git_token_source: &str,
+    ready: impl Fn() -> bool,
+) -> Result<(), GitAiError> {
+    let fetch = |option: String| {
+        let mut args = repo_args.to_vec();
+        args.extend(["fetch".to_string(), option, "origin".to_string()]);
+        args.extend(refspecs.iter().cloned());
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&args)
+            .map(|_| ())
+            .map_err(|e| explain_git_error(git_token_source, e))
+    };
+    let shallow = || is_shallow(repo_args);
+
+    let mut step = depth;
+    for _ in 0..MAX_DEEPENS {
+        if ready() || !shallow() {
+            return Ok(());
+        }
+        println!("[GitLab CI] Deepening the clone by {} commits...", step);
+        fetch(format!("--deepen={}", step))?;
+        step = step.saturating_mul(2);
+    }
+    if ready() || !shallow() {
+        return Ok(());
+    }
+    println!("[GitLab CI] Fetching the rest of the history...");
+    fetch("--unshallow".to_string())
+}
+
+/// Whether the clone or checkout has only part of its history
+fn is_shallow(repo_args: &[String]) -> bool {
+    let mut args = repo_args.to_vec();
+    args.extend([
+        "rev-parse".to_string(),
+        "--is-shallow-repository".to_string(),
+    ]);
+    exec_git(&args).is_ok_and(|output| output.stdout.starts_with(b"true"))
+}
+
+/// Whether git exits successfully in the clone
+fn git_succeeds(repo_args: &[String], args: &[&str]) -> bool {
+    let mut full = repo_args.to_vec();
+    full.extend(args.iter().map(|arg| arg.to_string()));
+    exec_git(&full).is_ok()
+}
+
+/// How to authenticate GitLab API requests
+pub struct GitlabApiAuth {
+    /// Variable the token came from
+    pub env_var: &'static str,
+    pub header: &'static str,
+    pub token: String,
+}
+
+/// Token variables the API accepts, in order of preference, with the header each is sent
+/// in. Personal, project and group access tokens all go in PRIVATE-TOKEN; deploy tokens
+/// can't call the API at all.
+const GITLAB_API_TOKENS: [(&str, &str); 4] = [
+    // Exchanged for the job's ID token, when an exchange service is configured
+    (OIDC_TOKEN_ENV_VAR, "PRIVATE-TOKEN"),
+    // Explicitly configured with proper permissions
+    ("GITLAB_TOKEN", "PRIVATE-TOKEN"),
+    // Group access token, for orgs that don't allow personal tokens in CI
+    ("GITLAB_GROUP_TOKEN", "PRIVATE-TOKEN"),
+    // Auto-provided but may lack API permissions
+    ("CI_JOB_TOKEN", "JOB-TOKEN"),
+];
+
+pub fn gitlab_api_auth() -> Result<GitlabApiAuth, GitAiError> {
+    GITLAB_API_TOKENS
+        .iter()
+        .find_map(|(env_var, header)| {
+            let token = env(env_var)?;
+            Some(GitlabApiAuth {
+                env_var,
+                header,
+                token,
+            })
+        })
+        .ok_or_else(|| {
+            GitAiError::Generic(
+                "None of GITLAB_TOKEN, GITLAB_GROUP_TOKEN or CI_JOB_TOKEN is set".to_string(),
+            )
+        })
+}
+
+/// Credential for git operations, passed through an ephemeral credential helper rather
+/// than embedded in the remote URL:
+/// - A token exchanged for the job's ID token (see [`crate::ci::oidc`])
+/// - GITLAB_TOKEN, then GITLAB_GROUP_TOKEN (need write_repository scope), since they can
+///   also push notes. GitLab ignores the username for access tokens.
+/// - A deploy token: GITLAB_DEPLOY_USER/GITLAB_DEPLOY_TOKEN, or the
+///   CI_DEPLOY_USER/CI_DEPLOY_PASSWORD GitLab injects for a deploy token named
+///   `gitlab-deploy-token`. Deploy tokens authenticate with their own username.
+/// - CI_JOB_TOKEN (available by default, read-only) as a fallback for clone/fetch
+pub fn gitlab_git_credential() -> Option<CiGitCredential> {
+    CiGitCredential::from_env("oauth2", OIDC_TOKEN_ENV_VAR)
+        .or_else(|| CiGitCredential::from_env("oauth2", "GITLAB_TOKEN"))
+        .or_else(|| CiGitCredential::from_env("oauth2", "GITLAB_GROUP_TOKEN"))
+        .or_else(|| CiGitCredential::from_env_pair("GITLAB_DEPLOY_USER", "GITLAB_DEPLOY_TOKEN"))
+        .or_else(|| CiGitCredential::from_env_pair("CI_DEPLOY_USER", "CI_DEPLOY_PASSWORD"))
+        .or_else(|| CiGitCredential::from_env("gitlab-ci-token", "CI_JOB_TOKEN"))
+}
+
+/// Path of the git-ai job file, relative to the repository root
+const GITLAB_CI_JOB_PATH: &str = ".gitlab/ci/git-ai.gitlab-ci.yml";
+
+/// Knobs for rendering the GitLab CI job template
+#[derive(Debug, Clone, Default)]
+pub struct GitlabTemplateOptions {
+    /// CI/CD variable holding the access token, when it isn't named `GITLAB_TOKEN`
+    pub token_var: Option<String>,
+    /// Branches the job runs on: exact names, or `/regex/` patterns. Empty means the
+    /// default branch only.
+    pub branches: Vec<String>,
+    /// Only run on protected refs, so protected variables are always available
+    pub protected_only: bool,
+}
+
+/// Render the GitLab CI job. A custom token variable is mapped onto `GITLAB_TOKEN` in the
+/// job's `variables:` so `git-ai ci gitlab run` reads it unchanged.
+pub fn render_gitlab_ci_yaml(options: &GitlabTemplateOptions) -> Result<String, GitAiError> {
+    let token_var = options.token_var.as_deref().unwrap_or("GITLAB_TOKEN");
+    if !is_valid_variable_name(token_var) {
+        return Err(GitAiError::Generic(format!(
+            "Invalid CI/CD variable name '{}': use letters, digits and underscores",
+            token_var
+        )));
+    }
+    let variables = if token_var == "GITLAB_TOKEN" {
+        String::new()
+    } else {
+        format!("  variables:\n    GITLAB_TOKEN: ${}\n", token_var)
+    };
+
+    let mut conditions: Vec<String> = if options.branches.is_empty() {
+        vec!["$CI_COMMIT_BRANCH == $CI_DEFAULT_BRANCH".to_string()]
+    } else {
+        options
+            .branches
+            .iter()
+            .map(|branch| branch_condition(branch))
+            .collect::<Result<_, _>>()?
+    };
+    for condition in &mut conditions {
+        condition.push_str(" && $CI_PIPELINE_SOURCE == \"push\"");
+        if options.protected_only {
+            condition.push_str(" && $CI_COMMIT_REF_PROTECTED == \"true\"");
+        }
+    }
+    let rules = conditions
+        .iter()
+        .map(|condition| {
+            format!(
+                "    - if: {}\n      when: always",
+                yaml_single_quoted(condition)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(GITLAB_CI_TEMPLATE_YAML
+        .replace("{{TOKEN_VAR}}", token_var)
+        .replace("{{VARIABLES}}", &variables)
+        .replace("{{RULES}}", &rules))
+}
+
+fn is_valid_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A single-quoted YAML scalar, so regex conditions containing `: `, ` #`, `[` or quotes
+/// reach GitLab verbatim instead of being read as YAML syntax.
+fn yaml_single_quoted(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// `rules:if` condition matching one `--branch` value
+fn branch_condition(branch: &str) -> Result<String, GitAiError> {
+    if branch.len() > 2 && branch.starts_with('/') && branch.ends_with('/') {
+        // Line breaks can't be carried in the single-line scalar
+        if branch.contains(char::is_control) {
+            return Err(GitAiError::Generic(format!(
+                "Invalid branch pattern '{}'",
+                branch.escape_debug()
+            )));
+        }
+        return Ok(format!("$CI_COMMIT_BRANCH =~ {}", branch));
+    }
+    // Git forbids quotes in ref names, so anything else is a typo we'd turn into broken YAML
+    if branch.is_empty()
+        || branch.contains(['"', '\'', '\\'])
+        || branch.contains(char::is_whitespace)
+    {
+        return Err(GitAiError::Generic(format!(
+            "Invalid branch name '{}'",
+            branch
+        )));
+    }
+    Ok(format!("$CI_COMMIT_BRANCH == \"{}\"", branch))
+}
+
+/// Write the git-ai job into the repository checked out at `workdir` and include it from
+/// `.gitlab-ci.yml`. An existing `.gitlab-ci.yml` is only created or left alone, never
+/// rewritten; the returned warning says when the include has to be added by hand.
+pub fn install_gitlab_ci_job_at(
+    workdir: &Path,
+    options: &GitlabTemplateOptions,
+) -> Result<(PathBuf, Option<String>), GitAiError> {
+    let yaml = render_gitlab_ci_yaml(options)?;
+    let job_path = workdir.join(GITLAB_CI_JOB_PATH);
+    if let Some(parent) = job_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| GitAiError::Generic(format!("Failed to create CI dir: {}", e)))?;
+    }
+    fs::write(&job_path, yaml)
+        .map_err(|e| GitAiError::Generic(format!("Failed to write CI job file: {}", e)))?;
+
+    let include = format!("include:\n  - local: '{}'\n", GITLAB_CI_JOB_PATH);
+    let pipeline_path = workdir.join(".gitlab-ci.yml");
+    let warning = match fs::read_to_string(&pipeline_path) {
+        Ok(pipeline) if pipeline.contains(GITLAB_CI_JOB_PATH) => None,
+        Ok(_) => Some(format!(
+            "add `- local: '{}'` to the include list in .gitlab-ci.yml",
+            GITLAB_CI_JOB_PATH
+        )),
+        Err(_) => {
+            fs::write(&pipeline_path, include).map_err(|e| {
+                GitAiError::Generic(format!("Failed to write .gitlab-ci.yml: {}", e))
+            })?;
+            None
+        }
+    };
+    Ok((job_path, warning))
+}
+
 /// Print the GitLab CI YAML snippet to stdout for users to copy into their .gitlab-ci.yml
-pub fn print_gitlab_ci_yaml() {
+pub fn print_gitlab_ci_yaml(options: &GitlabTemplateOptions) -> Result<(), GitAiError> {
+    let yaml = render_gitlab_ci_yaml(options)?;
     println!("Add the following to your .gitlab-ci.yml:");
     println!();
-    println!("{}", GITLAB_CI_TEMPLATE_YAML);
+    println!("{}", yaml);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_from<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    const JOB_ENV: [(&str, &str); 3] = [
+        ("CI_PROJECT_ID", "99"),
+        ("CI_PROJECT_PATH", "group/downstream"),
+        ("CI_COMMIT_SHA", "downstream-sha"),
+    ];
+
+    #[test]
+    fn test_target_defaults_to_job_variables() {
+        let target =
+            resolve_gitlab_target(&GitlabRunOptions::default(), env_from(&JOB_ENV)).unwrap();
+        assert_eq!(target.project_id, "99");
+        assert_eq!(target.project_path.as_deref(), Some("group/downstream"));
+        assert_eq!(target.commit_sha, "downstream-sha");
+        assert_eq!(target.commit_source, "CI_COMMIT_SHA");
+    }
+
+    #[test]
+    fn test_target_follows_upstream_pipeline_variables() {
+        let mut vars = JOB_ENV.to_vec();
+        vars.extend([
+            ("UPSTREAM_PROJECT_ID", "42"),
+            ("UPSTREAM_COMMIT_SHA", "upstream-sha"),
+        ]);
+
+        // Ignored unless another pipeline triggered this one
+        let target = resolve_gitlab_target(&GitlabRunOptions::default(), env_from(&vars)).unwrap();
+        assert_eq!(target.project_id, "99");
+
+        vars.push(("CI_PIPELINE_SOURCE", "pipeline"));
+        let target = resolve_gitlab_target(&GitlabRunOptions::default(), env_from(&vars)).unwrap();
+        assert_eq!(target.project_id, "42");
+        assert_eq!(target.project_path, None);
+        assert_eq!(target.commit_sha, "upstream-sha");
+        assert_eq!(target.project_source, "UPSTREAM_PROJECT_ID");
+    }
+
+    #[test]
+    fn test_target_options_override_everything() {
+        let mut vars = JOB_ENV.to_vec();
+        vars.extend([
+            ("CI_PIPELINE_SOURCE", "parent_pipeline"),
+            ("UPSTREAM_PROJECT_ID", "42"),
+            ("UPSTREAM_COMMIT_SHA", "upstream-sha"),
+        ]);
+        let options = GitlabRunOptions {
+            project_id: Some("7".to_string()),
+            project_path: Some("other/project".to_string()),
+            commit_sha: Some("flag-sha".to_string()),
+            ..Default::default()
+        };
+        let target = resolve_gitlab_target(&options, env_from(&vars)).unwrap();
+        assert_eq!(target.project_id, "7");
+        assert_eq!(target.project_path.as_deref(), Some("other/project"));
+        assert_eq!(target.commit_sha, "flag-sha");
+        assert_eq!(target.commit_source, "--commit-sha");
+
+        assert!(resolve_gitlab_target(&GitlabRunOptions::default(), env_from(&[])).is_err());
+    }
+
+    #[test]
+    fn test_merge_request_cutoff_counts_from_pipeline_creation() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let at = |cutoff: DateTime<Utc>| cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+        assert_eq!(
+            at(merge_request_cutoff(env_from(&[]), now).unwrap()),
+            "2026-03-01T11:45:00Z"
+        );
+        // Queued for an hour: the window ends when the pipeline was created, in UTC
+        let vars = [("CI_PIPELINE_CREATED_AT", "2026-03-01T12:00:00+01:00")];
+        assert_eq!(
+            at(merge_request_cutoff(env_from(&vars), now).unwrap()),
+            "2026-03-01T10:45:00Z"
+        );
+        let vars = [
+            ("CI_PIPELINE_CREATED_AT", "2026-03-01T11:00:00Z"),
+            (MERGE_WINDOW_ENV_VAR, "2h"),
+        ];
+        assert_eq!(
+            at(merge_request_cutoff(env_from(&vars), now).unwrap()),
+            "2026-03-01T09:00:00Z"
+        );
+        // A creation time ahead of the clock or unparseable counts from now
+        let vars = [("CI_PIPELINE_CREATED_AT", "2026-03-01T13:00:00Z")];
+        assert_eq!(
+            at(merge_request_cutoff(env_from(&vars), now).unwrap()),
+            "2026-03-01T11:45:00Z"
+        );
+        let vars = [("CI_PIPELINE_CREATED_AT", "yesterday")];
+        assert_eq!(
+            at(merge_request_cutoff(env_from(&vars), now).unwrap()),
+            "2026-03-01T11:45:00Z"
+        );
+        let vars = [(MERGE_WINDOW_ENV_VAR, "a while")];
+        assert!(merge_request_cutoff(env_from(&vars), now).is_err());
+    }
+
+    #[test]
+    fn test_clone_strategy_from_flag_or_env() {
+        assert_eq!(
+            CloneStrategy::resolve(None, false, env_from(&[])).unwrap(),
+            CloneStrategy::Full
+        );
+        assert_eq!(
+            CloneStrategy::resolve(None, false, env_from(&[(CLONE_ENV_VAR, "partial")])).unwrap(),
+            CloneStrategy::Partial {
+                depth: DEFAULT_CLONE_DEPTH
+            }
+        );
+        let vars = [(CLONE_ENV_VAR, "partial"), (CLONE_DEPTH_ENV_VAR, "200")];
+        assert_eq!(
+            CloneStrategy::resolve(None, false, env_from(&vars)).unwrap(),
+            CloneStrategy::Partial { depth: 200 }
+        );
+        // The flag wins over the environment
+        assert_eq!(
+            CloneStrategy::resolve(Some("full"), false, env_from(&vars)).unwrap(),
+            CloneStrategy::Full
+        );
+        assert!(CloneStrategy::resolve(Some("shallow"), false, env_from(&[])).is_err());
+        let vars = [(CLONE_ENV_VAR, "partial"), (CLONE_DEPTH_ENV_VAR, "0")];
+        assert!(CloneStrategy::resolve(None, false, env_from(&vars)).is_err());
+
+        // --in-place ignores GIT_AI_CI_CLONE but not the depth to deepen by
+        let vars = [(CLONE_ENV_VAR, "partial"), (CLONE_DEPTH_ENV_VAR, "20")];
+        assert_eq!(
+            CloneStrategy::resolve(None, true, env_from(&vars)).unwrap(),
+            CloneStrategy::InPlace { depth: 20 }
+        );
+        assert!(CloneStrategy::resolve(Some("full"), true, env_from(&[])).is_err());
+    }
+
+    #[test]
+    fn test_merge_request_iid_from_pipeline_variables() {
+        assert_eq!(merge_request_iid(env_from(&JOB_ENV)), None);
+        assert_eq!(
+            merge_request_iid(env_from(&[("CI_MERGE_REQUEST_IID", "12")])),
+            Some((12, "CI_MERGE_REQUEST_IID"))
+        );
+        assert_eq!(
+            merge_request_iid(env_from(&[(
+                "CI_MERGE_REQUEST_REF_PATH",
+                "refs/merge-requests/34/head"
+            )])),
+            Some((34, "CI_MERGE_REQUEST_REF_PATH"))
+        );
+        assert_eq!(
+            merge_request_iid(env_from(&[(
+                "CI_MERGE_REQUEST_REF_PATH",
+                "refs/heads/main"
+            )])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_train_pipeline_and_merged_sha() {
+        assert!(is_merge_train_pipeline(env_from(&[(
+            "CI_MERGE_REQUEST_EVENT_TYPE",
+            "merge_train"
+        )])));
+        assert!(!is_merge_train_pipeline(env_from(&[(
+            "CI_MERGE_REQUEST_EVENT_TYPE",
+            "merged_result"
+        )])));
+        assert!(!is_merge_train_pipeline(env_from(&[])));
+
+        let car: GitLabMergeTrainCar =
+            serde_json::from_str(r#"{"status": "merged", "pipeline": {"sha": "train-sha"}}"#)
+                .unwrap();
+        let mut mr: GitLabMergeRequest = serde_json::from_str(
+            r#"{"iid": 1, "source_branch": "f", "target_branch": "main", "sha": "head-sha",
+                "merge_commit_sha": "merge-sha", "squash_commit_sha": "squash-sha"}"#,
+        )
+        .unwrap();
+        assert_eq!(car.merged_sha(&mr).as_deref(), Some("squash-sha"));
+        mr.squash_commit_sha = None;
+        assert_eq!(car.merged_sha(&mr).as_deref(), Some("merge-sha"));
+        mr.merge_commit_sha = None;
+        assert_eq!(car.merged_sha(&mr).as_deref(), Some("train-sha"));
+    }
+
+    #[test]
+    fn test_next_page_url_follows_link_then_next_page_header() {
+        let endpoint =
+            "https://gitlab.example.com/api/v4/projects/1/merge_requests?state=merged&per_page=100";
+        let headers = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| *value)
+            }
+        };
+        assert_eq!(
+            next_page_url(endpoint, headers(&[("x-next-page", "2")])).as_deref(),
+            Some(
+                "https://gitlab.example.com/api/v4/projects/1/merge_requests?state=merged&per_page=100&page=2"
+            )
+        );
+        assert_eq!(
+            next_page_url(
+                &format!("{}&page=2", endpoint),
+                headers(&[("x-next-page", "3")])
+            )
+            .as_deref(),
+            Some(
+                "https://gitlab.example.com/api/v4/projects/1/merge_requests?state=merged&per_page=100&page=3"
+            )
+        );
+        assert_eq!(
+            next_page_url(
+                endpoint,
+                headers(&[
+                    (
+                        "link",
+                        "<https://gitlab.example.com/first>; rel=\"first\", <https://gitlab.example.com/next?cursor=abc>; rel=\"next\""
+                    ),
+                    ("x-next-page", "2")
+                ])
+            )
+            .as_deref(),
+            Some("https://gitlab.example.com/next?cursor=abc")
+        );
+        // The last page sends an empty X-Next-Page
+        assert_eq!(
+            next_page_url(endpoint, headers(&[("x-next-page", "")])),
+            None
+        );
+        assert_eq!(next_page_url(endpoint, headers(&[])), None);
+    }
+
+    #[test]
+    fn test_default_template_runs_on_default_branch_with_gitlab_token() {
+        let yaml = render_gitlab_ci_yaml(&GitlabTemplateOptions::default()).unwrap();
+        assert!(!yaml.contains("{{"));
+        assert!(!yaml.contains("variables:"));
+        assert!(yaml.contains("Key: GITLAB_TOKEN"));
+        assert!(yaml.contains(
+            "    - if: '$CI_COMMIT_BRANCH == $CI_DEFAULT_BRANCH && $CI_PIPELINE_SOURCE == \"push\"'\n      when: always\n  script:"
+        ));
+    }
+
+    #[test]
+    fn test_template_maps_custom_token_var_and_branch_rules() {
+        let yaml = render_gitlab_ci_yaml(&GitlabTemplateOptions {
+            token_var: Some("GIT_AI_BOT_TOKEN".to_string()),
+            branches: vec![
+                "main".to_string(),
+                "/^release-.*$/".to_string(),
+                "/^(hotfix|fix): [a-z']+ #\\d+$/".to_string(),
+            ],
+            protected_only: true,
+        })
+        .unwrap();
+        assert!(yaml.contains("  variables:\n    GITLAB_TOKEN: $GIT_AI_BOT_TOKEN\n  rules:"));
+        assert!(yaml.contains("Key: GIT_AI_BOT_TOKEN"));
+        assert!(yaml.contains(
+            "- if: '$CI_COMMIT_BRANCH == \"main\" && $CI_PIPELINE_SOURCE == \"push\" && $CI_COMMIT_REF_PROTECTED == \"true\"'"
+        ));
+        assert!(yaml.contains("- if: '$CI_COMMIT_BRANCH =~ /^release-.*$/ && $CI_PIPELINE_SOURCE"));
+        assert!(yaml.contains(
+            "- if: '$CI_COMMIT_BRANCH =~ /^(hotfix|fix): [a-z'']+ #\\d+$/ && $CI_PIPELINE_SOURCE"
+        ));
+        assert!(!yaml.contains("$CI_DEFAULT_BRANCH"));
+    }
+
+    #[test]
+    fn test_template_rejects_unsafe_values() {
+        let render = |token_var: &str, branch: &str| {
+            render_gitlab_ci_yaml(&GitlabTemplateOptions {
+                token_var: Some(token_var.to_string()),
+                branches: vec![branch.to_string()],
+                protected_only: false,
+            })
+        };
+        assert!(render("1TOKEN", "main").is_err());
+        assert!(render("MY-TOKEN", "main").is_err());
+        assert!(render("TOKEN", "main\" || true").is_err());
+        assert!(render("TOKEN", "").is_err());
+        assert!(render("TOKEN", "/^main\n$/").is_err());
+        assert!(render("TOKEN", "feature/x").is_ok());
+    }
 }
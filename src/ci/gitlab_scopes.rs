@@ -0,0 +1,347 @@
+//! The token scopes each GitLab operation needs, and turning GitLab's denials into the
+//! scope that's missing.
+//!
+//! GitLab answers a token that lacks a scope with a 403 and an `insufficient_scope` body
+//! for API calls, but with a bare "Access denied" or "not allowed to push" for git over
+//! HTTP, so both are mapped back to the operation that failed. The job template's setup
+//! notes are checked against [`GitlabOperation::ALL`] so they can't drift.
+
+use crate::error::GitAiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitlabOperation {
+    ListMergeRequests,
+    ReadCommit,
+    Clone,
+    FetchMergeRequestRef,
+    PushNotes,
+    /// Only with `ci_comment` on, so not part of [`GitlabOperation::ALL`]
+    PostComment,
+}
+
+impl GitlabOperation {
+    /// Operations every `ci gitlab run` needs
+    pub const ALL: [GitlabOperation; 5] = [
+        GitlabOperation::ListMergeRequests,
+        GitlabOperation::ReadCommit,
+        GitlabOperation::Clone,
+        GitlabOperation::FetchMergeRequestRef,
+        GitlabOperation::PushNotes,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            GitlabOperation::ListMergeRequests => "list merge requests",
+            GitlabOperation::ReadCommit => "read commits",
+            GitlabOperation::Clone => "clone the repository",
+            GitlabOperation::FetchMergeRequestRef => "fetch merge request refs",
+            GitlabOperation::PushNotes => "push authorship notes",
+            GitlabOperation::PostComment => "comment on merge requests",
+        }
+    }
+
+    /// The narrowest scope that allows the operation
+    pub fn minimum_scope(self) -> &'static str {
+        match self {
+            GitlabOperation::ListMergeRequests | GitlabOperation::ReadCommit => "read_api",
+            GitlabOperation::Clone | GitlabOperation::FetchMergeRequestRef => "read_repository",
+            GitlabOperation::PushNotes => "write_repository",
+            GitlabOperation::PostComment => "api",
+        }
+    }
+
+    /// The lowest project role that allows the operation
+    pub fn minimum_role(self) -> &'static str {
+        match self {
+            GitlabOperation::PushNotes => "Developer",
+            _ => "Reporter",
+        }
+    }
+
+    /// Whether CI_JOB_TOKEN can do this. It can read the repository, but on most GitLab
+    /// versions can't list merge requests and can never push.
+    pub fn allowed_for_job_token(self) -> bool {
+        matches!(
+            self,
+            GitlabOperation::Clone | GitlabOperation::FetchMergeRequestRef
+        )
+    }
+
+    /// One line saying what the token needs for this operation
+    pub fn requirement(self) -> String {
+        let requirement = format!(
+            "to {} the token needs the {} scope and at least the {} role",
+            self.description(),
+            self.minimum_scope(),
+            self.minimum_role()
+        );
+        if self.allowed_for_job_token() {
+            requirement
+        } else {
+            format!("{}; CI_JOB_TOKEN can't, so set GITLAB_TOKEN", requirement)
+        }
+    }
+}
+
+/// The scopes one token needs for a whole `ci gitlab run`. write_repository also grants
+/// read access, so read_repository is only listed without it.
+pub fn required_token_scopes() -> Vec<&'static str> {
+    let mut scopes: Vec<&'static str> = Vec::new();
+    for operation in GitlabOperation::ALL {
+        if !scopes.contains(&operation.minimum_scope()) {
+            scopes.push(operation.minimum_scope());
+        }
+    }
+    if scopes.contains(&"write_repository") {
+        scopes.retain(|scope| *scope != "read_repository");
+    }
+    scopes
+}
+
+/// Explain an API error response, given the variable the token came from
+pub fn diagnose_api_denial(
+    operation: GitlabOperation,
+    token_source: &str,
+    status: i32,
+    body: &str,
+) -> Option<String> {
+    let body: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    if body.get("error").and_then(|e| e.as_str()) == Some("insufficient_scope") {
+        let accepted = body
+            .get("scope")
+            .and_then(|s| s.as_str())
+            .map(|scopes| format!(" (GitLab accepts any of: {})", scopes))
+            .unwrap_or_default();
+        return Some(format!(
+            "{} is missing the {} scope needed to {}{}",
+            token_source,
+            operation.minimum_scope(),
+            operation.description(),
+            accepted
+        ));
+    }
+    match status {
+        401 | 403 if token_source == "CI_JOB_TOKEN" && !operation.allowed_for_job_token() => {
+            Some(format!(
+                "CI_JOB_TOKEN can't {}; set GITLAB_TOKEN to a token with the {} scope",
+                operation.description(),
+                operation.minimum_scope()
+            ))
+        }
+        401 => Some(format!(
+            "{} was rejected: it's expired, revoked or mistyped ({})",
+            token_source,
+            operation.requirement()
+        )),
+        403 => Some(format!(
+            "{} has the right scopes but its role can't {} ({})",
+            token_source,
+            operation.description(),
+            operation.requirement()
+        )),
+        // GitLab hides projects a token can't see behind a 404
+        404 => Some(format!(
+            "the project isn't visible to {} ({})",
+            token_source,
+            operation.requirement()
+        )),
+        _ => None,
+    }
+}
+
+/// Explain a failed git clone, fetch or push against GitLab
+pub fn diagnose_git_denial(
+    operation: GitlabOperation,
+    token_source: &str,
+    stderr: &str,
+) -> Option<String> {
+    let stderr = stderr.to_ascii_lowercase();
+    if stderr.contains("not allowed to push") || stderr.contains("not allowed to upload") {
+        return Some(if token_source == "CI_JOB_TOKEN" {
+            format!(
+                "CI_JOB_TOKEN can't {}; set GITLAB_TOKEN to a token with the {} scope",
+                operation.description(),
+                operation.minimum_scope()
+            )
+        } else if is_deploy_token(token_source) {
+            // Deploy tokens have no role, only scopes
+            format!(
+                "{} can't {}: the deploy token needs the {} scope",
+                token_source,
+                operation.description(),
+                operation.minimum_scope()
+            )
+        } else {
+            format!(
+                "{} can't {}: it needs the {} scope and at least the {} role",
+                token_source,
+                operation.description(),
+                operation.minimum_scope(),
+                operation.minimum_role()
+            )
+        });
+    }
+    if stderr.contains("insufficient_scope") || stderr.contains("insufficient scope") {
+        return Some(format!(
+            "{} is missing the {} scope needed to {}",
+            token_source,
+            operation.minimum_scope(),
+            operation.description()
+        ));
+    }
+    if stderr.contains("access denied")
+        || stderr.contains("authentication failed")
+        || stderr.contains("error: 401")
+        || stderr.contains("error: 403")
+    {
+        return Some(format!(
+            "{} was refused: it may be expired, or lack the {} scope needed to {}",
+            token_source,
+            operation.minimum_scope(),
+            operation.description()
+        ));
+    }
+    if stderr.contains("could not be found") || stderr.contains("error: 404") {
+        return Some(format!(
+            "the project isn't visible to {} ({})",
+            token_source,
+            operation.requirement()
+        ));
+    }
+    None
+}
+
+fn is_deploy_token(token_source: &str) -> bool {
+    matches!(token_source, "GITLAB_DEPLOY_TOKEN" | "CI_DEPLOY_PASSWORD")
+}
+
+/// Add the missing scope to a git error from a GitLab run, when it's a denial. The
+/// operation is read from the git subcommand that failed.
+pub fn explain_git_error(token_source: &str, error: GitAiError) -> GitAiError {
+    let GitAiError::GitCliError { args, stderr, .. } = &error else {
+        return error;
+    };
+    let operation = if args.iter().any(|arg| arg == "push") {
+        GitlabOperation::PushNotes
+    } else if args.iter().any(|arg| arg == "clone") {
+        GitlabOperation::Clone
+    } else if args.iter().any(|arg| arg == "fetch") {
+        GitlabOperation::FetchMergeRequestRef
+    } else {
+        return error;
+    };
+    match diagnose_git_denial(operation, token_source, stderr) {
+        Some(diagnosis) => GitAiError::Generic(format!("{}\n{}", error, diagnosis)),
+        None => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insufficient_scope_body_names_missing_scope() {
+        let body = r#"{"error":"insufficient_scope","error_description":"The request requires higher privileges than provided by the access token.","scope":"api read_api"}"#;
+        let diagnosis = diagnose_api_denial(
+            GitlabOperation::ListMergeRequests,
+            "GITLAB_TOKEN",
+            403,
+            body,
+        )
+        .unwrap();
+        assert_eq!(
+            diagnosis,
+            "GITLAB_TOKEN is missing the read_api scope needed to list merge requests (GitLab accepts any of: api read_api)"
+        );
+    }
+
+    #[test]
+    fn test_api_denials_by_status() {
+        let forbidden = r#"{"message":"403 Forbidden"}"#;
+        assert!(
+            diagnose_api_denial(
+                GitlabOperation::ListMergeRequests,
+                "CI_JOB_TOKEN",
+                403,
+                forbidden
+            )
+            .unwrap()
+            .starts_with("CI_JOB_TOKEN can't list merge requests; set GITLAB_TOKEN")
+        );
+        assert!(
+            diagnose_api_denial(GitlabOperation::ReadCommit, "GITLAB_TOKEN", 403, forbidden)
+                .unwrap()
+                .contains("its role can't read commits")
+        );
+        assert!(
+            diagnose_api_denial(GitlabOperation::ReadCommit, "GITLAB_TOKEN", 401, "")
+                .unwrap()
+                .contains("expired, revoked or mistyped")
+        );
+        assert!(
+            diagnose_api_denial(GitlabOperation::ReadCommit, "GITLAB_TOKEN", 404, "")
+                .unwrap()
+                .starts_with("the project isn't visible to GITLAB_TOKEN")
+        );
+        assert_eq!(
+            diagnose_api_denial(GitlabOperation::ReadCommit, "GITLAB_TOKEN", 500, ""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_git_denials() {
+        let push = "remote: You are not allowed to push code to this project.\nfatal: unable to access 'https://gitlab.com/g/p.git/': The requested URL returned error: 403";
+        assert_eq!(
+            diagnose_git_denial(GitlabOperation::PushNotes, "GITLAB_TOKEN", push).unwrap(),
+            "GITLAB_TOKEN can't push authorship notes: it needs the write_repository scope and at least the Developer role"
+        );
+        assert!(
+            diagnose_git_denial(GitlabOperation::PushNotes, "CI_JOB_TOKEN", push)
+                .unwrap()
+                .starts_with("CI_JOB_TOKEN can't push authorship notes")
+        );
+        assert_eq!(
+            diagnose_git_denial(GitlabOperation::PushNotes, "CI_DEPLOY_PASSWORD", push).unwrap(),
+            "CI_DEPLOY_PASSWORD can't push authorship notes: the deploy token needs the write_repository scope"
+        );
+        let clone = "remote: HTTP Basic: Access denied.\nfatal: Authentication failed for 'https://gitlab.com/g/p.git/'";
+        assert!(
+            diagnose_git_denial(GitlabOperation::Clone, "GITLAB_TOKEN", clone)
+                .unwrap()
+                .contains("lack the read_repository scope needed to clone the repository")
+        );
+        assert_eq!(
+            diagnose_git_denial(GitlabOperation::Clone, "GITLAB_TOKEN", "fatal: early EOF"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_explain_git_error_reads_operation_from_args() {
+        let error = GitAiError::GitCliError {
+            code: Some(128),
+            stderr: "remote: You are not allowed to push code to this project.".to_string(),
+            args: vec!["push".to_string(), "origin".to_string()],
+        };
+        let explained = explain_git_error("GITLAB_TOKEN", error).to_string();
+        assert!(explained.contains("not allowed to push"));
+        assert!(explained.ends_with("at least the Developer role"));
+
+        let unrelated = GitAiError::Generic("boom".to_string());
+        assert_eq!(
+            explain_git_error("GITLAB_TOKEN", unrelated).to_string(),
+            "Generic error: boom"
+        );
+    }
+
+    #[test]
+    fn test_job_template_documents_minimum_permissions() {
+        let template = include_str!("workflow_templates/gitlab.yaml");
+        let scopes = required_token_scopes();
+        assert_eq!(scopes, vec!["read_api", "write_repository"]);
+        assert!(template.contains(&format!("Scopes: {} (both required)", scopes.join(", "))));
+        assert!(template.contains("Role: Developer"));
+    }
+}
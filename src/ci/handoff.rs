@@ -0,0 +1,266 @@
+//! Two-phase runs for pull requests from forks, whose CI token can read the repository
+//! but not push to it. The fork's run rewrites the notes as usual but writes them to an
+//! artifact (`--notes-artifact <path>` or `GIT_AI_CI_NOTES_ARTIFACT`) instead of pushing;
+//! a job in the base repository with a token that can push (a `workflow_run` job on
+//! GitHub, a pipeline of the parent project on GitLab) downloads it and runs
+//! `git-ai ci <provider> ingest <path>`.
+//!
+//! The artifact comes from a run the fork controls, so ingesting checks it before
+//! pushing anything: the merge commit must be on the base branch, notes may only be for
+//! it and the commits a rebase merge landed right behind it, each must parse, and notes
+//! already on the remote are kept.
+
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::ci::ci_context::{CiContext, CiEvent, CiRunResult};
+use crate::ci::env;
+use crate::ci::github_app::refresh_app_token;
+use crate::ci::provenance::{
+    NoteSigner, detect_provenance, notes_tip, sign_notes_since, sign_notes_tip, stamp_provenance,
+};
+use crate::error::GitAiError;
+use crate::git::refs::{get_reference_as_authorship_log_v3, notes_add, show_authorship_note};
+use crate::git::repository::Repository;
+use crate::git::sync_authorship::{fetch_authorship_notes, push_authorship_notes_with};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable naming the artifact to write when `--notes-artifact` isn't passed
+pub const NOTES_ARTIFACT_ENV_VAR: &str = "GIT_AI_CI_NOTES_ARTIFACT";
+
+const NOTES_ARTIFACT_VERSION: u32 = 1;
+
+static NOTES_ARTIFACT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Rewritten notes handed from a fork's run to a job that can push them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotesArtifact {
+    pub version: u32,
+    pub merge_commit_sha: String,
+    pub head_ref: String,
+    pub head_sha: String,
+    pub base_ref: String,
+    pub base_sha: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_request: Option<u64>,
+    /// Note content by commit
+    pub notes: BTreeMap<String, String>,
+}
+
+/// Write notes to `path` instead of pushing them. Only the first call counts.
+pub fn set_artifact_path(path: PathBuf) {
+    let _ = NOTES_ARTIFACT.set(path);
+}
+
+/// Where to write the notes instead of pushing them, None to push as usual
+pub fn artifact_path() -> Option<PathBuf> {
+    NOTES_ARTIFACT
+        .get()
+        .cloned()
+        .or_else(|| env(NOTES_ARTIFACT_ENV_VAR).map(PathBuf::from))
+}
+
+impl NotesArtifact {
+    /// The notes of `commits` in `repo` for the merge `event` describes
+    pub fn collect(
+        repo: &Repository,
+        event: &CiEvent,
+        commits: &[String],
+    ) -> Result<Self, GitAiError> {
+        let CiEvent::Merge {
+            merge_commit_sha,
+            head_ref,
+            head_sha,
+            base_ref,
+            base_sha,
+            merge_request,
+        } = event
+        else {
+            return Err(GitAiError::Generic(
+                "Only a merge's notes can be written to an artifact".to_string(),
+            ));
+        };
+        let notes = commits
+            .iter()
+            .filter_map(|sha| show_authorship_note(repo, sha).map(|note| (sha.clone(), note)))
+            .collect();
+        Ok(NotesArtifact {
+            version: NOTES_ARTIFACT_VERSION,
+            merge_commit_sha: merge_commit_sha.clone(),
+            head_ref: head_ref.clone(),
+            head_sha: head_sha.clone(),
+            base_ref: base_ref.clone(),
+            base_sha: base_sha.clone(),
+            merge_request: *merge_request,
+            notes,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), GitAiError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self, GitAiError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            GitAiError::Generic(format!(
+                "Failed to read notes artifact {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let artifact: NotesArtifact = serde_json::from_str(&content).map_err(|e| {
+            GitAiError::Generic(format!("Invalid notes artifact {}: {}", path.display(), e))
+        })?;
+        if artifact.version != NOTES_ARTIFACT_VERSION {
+            return Err(GitAiError::Generic(format!(
+                "Notes artifact {} has version {}, but this git-ai reads version {}",
+                path.display(),
+                artifact.version,
+                NOTES_ARTIFACT_VERSION
+            )));
+        }
+        Ok(artifact)
+    }
+
+    /// The merge the notes were rewritten for
+    pub fn event(&self) -> CiEvent {
+        CiEvent::Merge {
+            merge_commit_sha: self.merge_commit_sha.clone(),
+            head_ref: self.head_ref.clone(),
+            head_sha: self.head_sha.clone(),
+            base_ref: self.base_ref.clone(),
+            base_sha: self.base_sha.clone(),
+            merge_request: self.merge_request,
+        }
+    }
+}
+
+/// Push the notes in `artifact` from `context`'s clone of the base repository, after
+/// checking them, recording this run's provenance and signing as `GIT_AI_CI_SIGN` asks
+pub fn ingest_notes_artifact(
+    context: &CiContext,
+    artifact: &NotesArtifact,
+) -> Result<CiRunResult, GitAiError> {
+    let repo = &context.repo;
+    println!("Working repository is in {}", repo.path().display());
+    let signer = NoteSigner::from_env()?;
+
+    println!("Fetching authorship history");
+    fetch_authorship_notes(repo, "origin")?;
+    println!("Fetched authorship history");
+
+    check_artifact_commits(repo, artifact)?;
+
+    let stamped_from = notes_tip(repo);
+    let mut written = Vec::new();
+    for (commit, note) in &artifact.notes {
+        if show_authorship_note(repo, commit).is_some() {
+            println!("{} already has authorship, keeping it", commit);
+            continue;
+        }
+        notes_add(repo, commit, note)?;
+        written.push(commit.clone());
+    }
+    let authorship_log = get_reference_as_authorship_log_v3(repo, &artifact.merge_commit_sha)?;
+    if written.is_empty() {
+        return Ok(CiRunResult::AlreadyExists { authorship_log });
+    }
+
+    let mut provenance = detect_provenance(|name| std::env::var(name).ok());
+    provenance.signed_with = signer.as_ref().map(|s| s.name().to_string());
+    stamp_provenance(repo, &written, &provenance)?;
+    if let Some(signer) = &signer {
+        sign_notes_since(repo, signer, stamped_from.as_deref())?;
+    }
+
+    println!("Pushing authorship for {} commit(s)...", written.len());
+    refresh_app_token()?;
+    push_authorship_notes_with(repo, "origin", |repo| match &signer {
+        Some(signer) => {
+            println!("Signing notes commit with {}", signer.name());
+            sign_notes_tip(repo, signer)
+        }
+        None => Ok(()),
+    })?;
+    println!("Pushed authorship. Done.");
+    Ok(CiRunResult::AuthorshipRewritten {
+        authorship_log,
+        commits: written,
+    })
+}
+
+/// Reject an artifact whose merge commit isn't on the base branch, or with notes for
+/// anything but it and the first-parent commits a rebase merge could have landed with
+/// it, or with notes that don't parse
+fn check_artifact_commits(repo: &Repository, artifact: &NotesArtifact) -> Result<(), GitAiError> {
+    let base = format!("refs/remotes/origin/{}", artifact.base_ref);
+    if !repo.is_ancestor(&artifact.merge_commit_sha, &base) {
+        return Err(GitAiError::Generic(format!(
+            "Notes artifact is for {}, which is not on {}",
+            artifact.merge_commit_sha, artifact.base_ref
+        )));
+    }
+    let landed: HashSet<String> = repo
+        .git(&[
+            "rev-list",
+            "--first-parent",
+            &format!("--max-count={}", artifact.notes.len().max(1)),
+            &artifact.merge_commit_sha,
+        ])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    for (commit, note) in &artifact.notes {
+        if !landed.contains(commit) {
+            return Err(GitAiError::Generic(format!(
+                "Notes artifact has a note for {}, which {} did not land",
+                commit, artifact.merge_commit_sha
+            )));
+        }
+        AuthorshipLog::deserialize_from_string(note).map_err(|e| {
+            GitAiError::Generic(format!(
+                "Notes artifact has an invalid note for {}: {}",
+                commit, e
+            ))
+        })?;
+    }
+    if !artifact.notes.contains_key(&artifact.merge_commit_sha) {
+        return Err(GitAiError::Generic(format!(
+            "Notes artifact has no note for its merge commit {}",
+            artifact.merge_commit_sha
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_artifact_round_trips_and_rejects_other_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out").join("notes.json");
+        let mut artifact = NotesArtifact {
+            version: NOTES_ARTIFACT_VERSION,
+            merge_commit_sha: "b".repeat(40),
+            head_ref: "feature".to_string(),
+            head_sha: "a".repeat(40),
+            base_ref: "main".to_string(),
+            base_sha: "c".repeat(40),
+            merge_request: Some(7),
+            notes: BTreeMap::from([("b".repeat(40), "note\n".to_string())]),
+        };
+        artifact.write(&path).unwrap();
+        assert_eq!(NotesArtifact::read(&path).unwrap(), artifact);
+
+        artifact.version = NOTES_ARTIFACT_VERSION + 1;
+        artifact.write(&path).unwrap();
+        assert!(NotesArtifact::read(&path).is_err());
+    }
+}
@@ -0,0 +1,901 @@
+//! Shared HTTP layer for provider API calls. Rate limits (429, or GitHub's 403 with no
+//! quota left) and server errors (5xx) are retried with exponential backoff, honoring
+//! `Retry-After`, so a transient forge hiccup doesn't fail the whole CI job, though never
+//! past the CI deadline. A POST or PATCH may have taken effect even when the response
+//! never came or was a 5xx, so those are only retried on a rate limit or when the
+//! connection failed before the request went out, never duplicating a comment or check
+//! run (see [`crate::ci::deadline`]). Nothing is sent while network
+//! access is off (see [`crate::network`]). Requests go out through an
+//! [`HttpBackend`]; HTTPS requests honor the TLS overrides for self-hosted forges (see
+//! [`crate::tls`]), and all of them the `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` settings.
+
+use crate::ci::deadline;
+use crate::error::GitAiError;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Environment variable overriding how many times a request is attempted in total
+pub const MAX_ATTEMPTS_ENV_VAR: &str = "GIT_AI_HTTP_MAX_ATTEMPTS";
+/// Environment variable choosing the [`HttpBackend`]: `minreq` (default), `ureq`, or
+/// `reqwest` in builds with the `reqwest` feature
+pub const BACKEND_ENV_VAR: &str = "GIT_AI_HTTP_BACKEND";
+/// Environment variable setting how many seconds to wait for each response, overriding
+/// any timeout the request sets itself
+pub const TIMEOUT_ENV_VAR: &str = "GIT_AI_HTTP_TIMEOUT";
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+/// Seconds to wait for a response when neither the request nor the environment says
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Wait before the first retry when the response doesn't say how long to wait
+const BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest single wait; a forge asking for longer fails the request instead
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// An API request, built like a `minreq::Request`
+#[derive(Debug, Clone)]
+pub struct Request {
+    method: &'static str,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    timeout_secs: Option<u64>,
+}
+
+pub fn get(url: impl Into<String>) -> Request {
+    Request::new("GET", url.into())
+}
+
+pub fn post(url: impl Into<String>) -> Request {
+    Request::new("POST", url.into())
+}
+
+pub fn patch(url: impl Into<String>) -> Request {
+    Request::new("PATCH", url.into())
+}
+
+impl Request {
+    /// Whether sending the request twice does no more than sending it once
+    fn is_idempotent(&self) -> bool {
+        self.method == "GET"
+    }
+
+    fn new(method: &'static str, url: String) -> Self {
+        Self {
+            method,
+            url,
+            headers: Vec::new(),
+            body: None,
+            timeout_secs: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.timeout_secs = Some(seconds);
+        self
+    }
+}
+
+/// A response of any status
+#[derive(Debug)]
+pub struct Response {
+    pub status_code: i32,
+    /// Header names are lowercased
+    pub headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn as_str(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+}
+
+/// Why a backend got no response
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendError {
+    pub message: String,
+    /// The connection failed, so the request never reached the server
+    pub before_sent: bool,
+}
+
+impl SendError {
+    /// A failure to connect, before any of the request was sent
+    pub fn connect(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            before_sent: true,
+        }
+    }
+}
+
+/// Any other failure, e.g. a timeout waiting for the response
+impl From<String> for SendError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            before_sent: false,
+        }
+    }
+}
+
+/// Sends a single request and returns the response whatever its status; [`send`]
+/// handles retries and rate limits on top
+pub trait HttpBackend: Send + Sync {
+    fn send(&self, request: &Request) -> Result<Response, SendError>;
+}
+
+/// The default backend, opening a connection per request
+pub struct MinreqBackend;
+
+impl HttpBackend for MinreqBackend {
+    fn send(&self, request: &Request) -> Result<Response, SendError> {
+        let method = match request.method {
+            "POST" => minreq::Method::Post,
+            "PATCH" => minreq::Method::Patch,
+            _ => minreq::Method::Get,
+        };
+        let mut sent = minreq::Request::new(method, request.url.as_str());
+        for (name, value) in &request.headers {
+            sent = sent.with_header(name, value);
+        }
+        if let Some(body) = &request.body {
+            sent = sent.with_body(body.clone());
+        }
+        if let Some(timeout) = request.timeout_secs {
+            sent = sent.with_timeout(timeout);
+        }
+        let response = sent.send().map_err(|e| {
+            use std::io::ErrorKind;
+            let before_sent = match &e {
+                minreq::Error::AddressNotFound | minreq::Error::RustlsCreateConnection(_) => true,
+                minreq::Error::IoError(io) => matches!(
+                    io.kind(),
+                    ErrorKind::ConnectionRefused
+                        | ErrorKind::AddrNotAvailable
+                        | ErrorKind::HostUnreachable
+                        | ErrorKind::NetworkUnreachable
+                ),
+                _ => false,
+            };
+            SendError {
+                message: e.to_string(),
+                before_sent,
+            }
+        })?;
+        Ok(Response {
+            status_code: response.status_code,
+            headers: response.headers.clone(),
+            body: response.into_bytes(),
+        })
+    }
+}
+
+/// A backend that keeps connections open across requests to the same host, for
+/// providers making many API calls, and can take a custom TLS configuration or proxy
+pub struct UreqBackend {
+    agent: ureq::Agent,
+}
+
+impl UreqBackend {
+    pub fn new() -> Self {
+        Self::from_builder(ureq::AgentBuilder::new())
+    }
+
+    /// A backend with the agent `builder` configures, e.g. with a TLS config or proxy
+    pub fn from_builder(builder: ureq::AgentBuilder) -> Self {
+        Self {
+            agent: builder.build(),
+        }
+    }
+}
+
+impl Default for UreqBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpBackend for UreqBackend {
+    fn send(&self, request: &Request) -> Result<Response, SendError> {
+        let mut sent = self.agent.request(request.method, &request.url);
+        for (name, value) in &request.headers {
+            sent = sent.set(name, value);
+        }
+        if let Some(timeout) = request.timeout_secs {
+            sent = sent.timeout(Duration::from_secs(timeout));
+        }
+        let result = match &request.body {
+            Some(body) => sent.send_bytes(body),
+            None => sent.call(),
+        };
+        // Error statuses are responses for the caller (and the retry loop) to read
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(e))
+                if matches!(
+                    e.kind(),
+                    ureq::ErrorKind::Dns
+                        | ureq::ErrorKind::ConnectionFailed
+                        | ureq::ErrorKind::ProxyConnect
+                ) =>
+            {
+                return Err(SendError::connect(e.to_string()));
+            }
+            Err(e) => return Err(e.to_string().into()),
+        };
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_string();
+                Some((name.to_ascii_lowercase(), value))
+            })
+            .collect();
+        let status_code = i32::from(response.status());
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|e| SendError::from(e.to_string()))?;
+        Ok(Response {
+            status_code,
+            headers,
+            body,
+        })
+    }
+}
+
+/// An async backend on `reqwest`, built with the `reqwest` feature: HTTP/2 and pooled
+/// connections for providers making many API calls. [`HttpBackend::send`] blocks on its
+/// own runtime; code already on a tokio runtime awaits [`ReqwestBackend::send_async`].
+#[cfg(feature = "reqwest")]
+pub struct ReqwestBackend {
+    client: reqwest::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestBackend {
+    pub fn new() -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .user_agent(format!("git-ai/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { client, runtime })
+    }
+
+    pub async fn send_async(&self, request: &Request) -> Result<Response, SendError> {
+        let method =
+            reqwest::Method::from_bytes(request.method.as_bytes()).map_err(|e| e.to_string())?;
+        let mut sent = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            sent = sent.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            sent = sent.body(body.clone());
+        }
+        if let Some(timeout) = request.timeout_secs {
+            sent = sent.timeout(Duration::from_secs(timeout));
+        }
+        let response = sent.send().await.map_err(|e| SendError {
+            message: e.to_string(),
+            before_sent: e.is_connect(),
+        })?;
+        let status_code = i32::from(response.status().as_u16());
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| SendError::from(e.to_string()))?
+            .to_vec();
+        Ok(Response {
+            status_code,
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpBackend for ReqwestBackend {
+    fn send(&self, request: &Request) -> Result<Response, SendError> {
+        self.runtime.block_on(self.send_async(request))
+    }
+}
+
+/// The backend for `request`: one honoring the TLS overrides for HTTPS and the proxy
+/// settings when they apply, else the one [`BACKEND_ENV_VAR`] names
+fn backend_for(request: &Request) -> Result<&'static dyn HttpBackend, GitAiError> {
+    static MINREQ: MinreqBackend = MinreqBackend;
+    static UREQ: OnceLock<UreqBackend> = OnceLock::new();
+    static TLS: OnceLock<Result<UreqBackend, String>> = OnceLock::new();
+    static PROXIED_HTTP: OnceLock<Result<UreqBackend, String>> = OnceLock::new();
+    static PROXIED_HTTPS: OnceLock<Result<UreqBackend, String>> = OnceLock::new();
+
+    let https = request.url.starts_with("https://");
+    let custom_tls = https && crate::tls::is_customized();
+    let proxy = proxy_for(&request.url, |name| std::env::var(name).ok());
+    if custom_tls || proxy.is_some() {
+        let cell = match (&proxy, https) {
+            (None, _) => &TLS,
+            (Some(_), false) => &PROXIED_HTTP,
+            (Some(_), true) => &PROXIED_HTTPS,
+        };
+        return cell
+            .get_or_init(|| custom_backend(custom_tls, proxy.as_deref()))
+            .as_ref()
+            .map(|backend| backend as &dyn HttpBackend)
+            .map_err(|e| GitAiError::Generic(e.clone()));
+    }
+    match std::env::var(BACKEND_ENV_VAR).as_deref() {
+        Err(_) | Ok("") | Ok("minreq") => Ok(&MINREQ),
+        Ok("ureq") => Ok(UREQ.get_or_init(UreqBackend::new)),
+        #[cfg(feature = "reqwest")]
+        Ok("reqwest") => {
+            static REQWEST: OnceLock<Result<ReqwestBackend, String>> = OnceLock::new();
+            REQWEST
+                .get_or_init(ReqwestBackend::new)
+                .as_ref()
+                .map(|backend| backend as &dyn HttpBackend)
+                .map_err(|e| GitAiError::Generic(e.clone()))
+        }
+        #[cfg(not(feature = "reqwest"))]
+        Ok("reqwest") => Err(GitAiError::Generic(format!(
+            "{}=reqwest needs git-ai built with the reqwest feature",
+            BACKEND_ENV_VAR
+        ))),
+        Ok(other) => Err(GitAiError::Generic(format!(
+            "Unknown {} '{}'; expected minreq, ureq or reqwest",
+            BACKEND_ENV_VAR, other
+        ))),
+    }
+}
+
+/// A pooled backend trusting the TLS overrides when `custom_tls`, going through `proxy`
+/// when set
+fn custom_backend(custom_tls: bool, proxy: Option<&str>) -> Result<UreqBackend, String> {
+    let mut builder = ureq::AgentBuilder::new();
+    if custom_tls {
+        let config = crate::tls::client_config().map_err(|e| e.to_string())?;
+        builder = builder.tls_config(std::sync::Arc::new(config));
+    }
+    if let Some(proxy) = proxy {
+        let proxy = ureq::Proxy::new(proxy).map_err(|e| format!("Invalid proxy: {}", e))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(UreqBackend::from_builder(builder))
+}
+
+/// The proxy to send a request for `url` through, read with `env` the way curl and git
+/// do: `https_proxy`/`HTTPS_PROXY` for HTTPS, `http_proxy`/`HTTP_PROXY` for plain
+/// HTTP, falling back to `all_proxy`/`ALL_PROXY`, unless `no_proxy`/`NO_PROXY` lists
+/// the host
+fn proxy_for(url: &str, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let var = |names: [&str; 2]| {
+        names
+            .into_iter()
+            .find_map(|name| env(name).filter(|value| !value.trim().is_empty()))
+    };
+    let proxy = match url.scheme() {
+        "https" => var(["https_proxy", "HTTPS_PROXY"]),
+        _ => var(["http_proxy", "HTTP_PROXY"]),
+    }
+    .or_else(|| var(["all_proxy", "ALL_PROXY"]))?;
+    let no_proxy = var(["no_proxy", "NO_PROXY"]).unwrap_or_default();
+    if bypasses_proxy(&no_proxy, url.host_str()?, url.port_or_known_default()) {
+        return None;
+    }
+    Some(proxy.trim().to_string())
+}
+
+/// Whether a `NO_PROXY` list exempts `host`: `*` exempts every host, `example.com`,
+/// `.example.com` and `*.example.com` the domain and its subdomains, and an entry with
+/// a port only that port
+fn bypasses_proxy(no_proxy: &str, host: &str, port: Option<u16>) -> bool {
+    let unbracket = |host: &str| {
+        host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase()
+    };
+    let host = unbracket(host);
+    no_proxy
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            // A bare IPv6 address has colons but no port
+            let (entry_host, entry_port) = match entry.rsplit_once(':') {
+                Some((name, entry_port)) if !name.contains(':') || name.ends_with(']') => {
+                    match entry_port.parse::<u16>() {
+                        Ok(entry_port) => (name, Some(entry_port)),
+                        Err(_) => return false,
+                    }
+                }
+                _ => (entry, None),
+            };
+            if entry_port.is_some() && entry_port != port {
+                return false;
+            }
+            let domain = unbracket(entry_host.trim_start_matches("*.").trim_start_matches('.'));
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+}
+
+/// Send `request` once, for callers that handle rate limits themselves
+pub fn send_once(request: &Request, what: &str) -> Result<Response, GitAiError> {
+    ensure_can_send(what)?;
+    let mut request = request.clone();
+    request.timeout_secs = Some(timeout_secs(request.timeout_secs));
+    backend_for(&request)?
+        .send(&request)
+        .map_err(|e| GitAiError::Generic(format!("{} failed: {}", what, e.message)))
+}
+
+/// Fail before a request goes out while network access is off or past the CI deadline
+fn ensure_can_send(what: &str) -> Result<(), GitAiError> {
+    crate::network::ensure_allowed(&format!("send the {}", what))?;
+    deadline::check(what)
+}
+
+/// How long to wait for a response: [`TIMEOUT_ENV_VAR`], which overrides the request's
+/// `own` timeout so one setting governs every request, else `own`, else 30s, cut short
+/// by the CI deadline
+fn timeout_secs(own: Option<u64>) -> u64 {
+    let configured = std::env::var(TIMEOUT_ENV_VAR)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0);
+    effective_timeout(own, configured, deadline::remaining())
+}
+
+fn effective_timeout(own: Option<u64>, configured: Option<u64>, left: Option<Duration>) -> u64 {
+    let timeout = configured.or(own).unwrap_or(DEFAULT_TIMEOUT_SECS);
+    match left {
+        // Whole seconds, rounded up so a request still gets a chance near the end
+        Some(left) => timeout.min(left.as_secs() + 1),
+        None => timeout,
+    }
+}
+
+fn max_attempts() -> u32 {
+    std::env::var(MAX_ATTEMPTS_ENV_VAR)
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .filter(|attempts| *attempts > 0)
+        .unwrap_or(DEFAULT_MAX_ATTEMPTS)
+}
+
+/// Send `request`, retrying rate limits, server errors and failed connections; a
+/// request that isn't idempotent, only rate limits and connections that failed before
+/// it was sent. `what` names the request in messages, e.g. "GitLab API request". Any other
+/// response, 4xx included, is returned for the caller to interpret.
+pub fn send(request: Request, what: &str) -> Result<Response, GitAiError> {
+    ensure_can_send(what)?;
+    send_with(backend_for(&request)?, request, what)
+}
+
+/// [`send`] through a particular backend
+pub fn send_with(
+    backend: &dyn HttpBackend,
+    request: Request,
+    what: &str,
+) -> Result<Response, GitAiError> {
+    let max_attempts = max_attempts();
+    let own_timeout = request.timeout_secs;
+    let mut request = request;
+    let mut attempt = 1;
+    loop {
+        ensure_can_send(what)?;
+        request.timeout_secs = Some(timeout_secs(own_timeout));
+        let (status, message, delay) = match backend.send(&request) {
+            Ok(response) => {
+                let delay = retry_delay(
+                    attempt,
+                    request.is_idempotent(),
+                    response.status_code,
+                    |name| response.headers.get(name).map(String::as_str),
+                    Utc::now().timestamp(),
+                );
+                let Some(delay) = delay else {
+                    return Ok(response);
+                };
+                let body = response.as_str().unwrap_or("").trim();
+                let message = format!("status {}: {}", response.status_code, truncate(body, 200));
+                (Some(response.status_code), message, delay)
+            }
+            Err(e) => {
+                if !e.before_sent && !request.is_idempotent() {
+                    return Err(GitAiError::HttpRequestFailed {
+                        what: what.to_string(),
+                        attempts: attempt,
+                        status: None,
+                        message: e.message,
+                    });
+                }
+                (None, e.message, backoff(attempt))
+            }
+        };
+
+        if attempt >= max_attempts || delay > MAX_DELAY {
+            return Err(GitAiError::HttpRequestFailed {
+                what: what.to_string(),
+                attempts: attempt,
+                status,
+                message,
+            });
+        }
+        if deadline::remaining().is_some_and(|left| delay >= left) {
+            return Err(deadline::exceeded(&format!("retrying {}", what)));
+        }
+        eprintln!(
+            "[git-ai] {} failed ({}); retrying in {}s (attempt {} of {})",
+            what,
+            status.map_or_else(|| "connection error".to_string(), |s| s.to_string()),
+            delay.as_secs(),
+            attempt + 1,
+            max_attempts
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// How long to wait before retrying a response with `status` on attempt `attempt`,
+/// or None if it shouldn't be retried. Only rate limits are retried for a request that
+/// isn't `idempotent`: a server error may come after it took effect.
+fn retry_delay<'a>(
+    attempt: u32,
+    idempotent: bool,
+    status: i32,
+    headers: impl Fn(&str) -> Option<&'a str> + Copy,
+    now: i64,
+) -> Option<Duration> {
+    if let Some(wait) = rate_limit_wait(status, headers, now) {
+        return Some(wait);
+    }
+    if idempotent && (500..600).contains(&status) {
+        return Some(retry_after(headers, now).unwrap_or_else(|| backoff(attempt)));
+    }
+    None
+}
+
+/// 1s, 2s, 4s, ... capped at [`MAX_DELAY`]
+fn backoff(attempt: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_DELAY)
+}
+
+/// `Retry-After` as delay-seconds or an HTTP date
+fn retry_after<'a>(headers: impl Fn(&str) -> Option<&'a str>, now: i64) -> Option<Duration> {
+    let value = headers("retry-after")?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(Duration::from_secs(
+        date.timestamp().saturating_sub(now).max(0) as u64,
+    ))
+}
+
+/// How long a rate-limited response says to wait: GitHub answers 403 or 429 with
+/// `x-ratelimit-remaining: 0` and an `x-ratelimit-reset` time, GitLab 429 with
+/// `ratelimit-reset`, and either may send `retry-after`
+pub(crate) fn rate_limit_wait<'a>(
+    status: i32,
+    headers: impl Fn(&str) -> Option<&'a str>,
+    now: i64,
+) -> Option<Duration> {
+    let header = |name: &str| headers(name).map(str::trim);
+    let exhausted = header("x-ratelimit-remaining") == Some("0");
+    if status != 429 && !(status == 403 && exhausted) {
+        return None;
+    }
+    let wait = retry_after(header, now).unwrap_or_else(|| {
+        let seconds = header("x-ratelimit-reset")
+            .or_else(|| header("ratelimit-reset"))
+            .and_then(|v| v.parse::<i64>().ok())
+            .map(|reset| reset.saturating_sub(now).max(1) as u64)
+            .unwrap_or(60);
+        Duration::from_secs(seconds)
+    });
+    Some(wait)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(
+        pairs: &'static [(&'static str, &'static str)],
+    ) -> impl Fn(&str) -> Option<&'static str> + Copy {
+        move |name: &str| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| *value)
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_wait_reads_retry_headers() {
+        assert_eq!(rate_limit_wait(200, headers(&[]), 0), None);
+        // A 403 with quota left is a permissions problem, not a rate limit
+        assert_eq!(
+            rate_limit_wait(403, headers(&[("x-ratelimit-remaining", "12")]), 0),
+            None
+        );
+        assert_eq!(
+            rate_limit_wait(
+                403,
+                headers(&[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1000")
+                ]),
+                940
+            ),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            rate_limit_wait(429, headers(&[("retry-after", "7")]), 0),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            rate_limit_wait(429, headers(&[("ratelimit-reset", "1005")]), 1000),
+            Some(Duration::from_secs(5))
+        );
+        // An HTTP date is a deadline, 10s after `now` here
+        assert_eq!(
+            rate_limit_wait(
+                429,
+                headers(&[("retry-after", "Sun, 06 Nov 1994 08:49:37 GMT")]),
+                784111767
+            ),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    /// Answers requests from a script, recording how many it saw
+    struct ScriptedBackend {
+        script: std::sync::Mutex<Vec<Result<(i32, &'static str), SendError>>>,
+        sent: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ScriptedBackend {
+        fn new(mut script: Vec<Result<(i32, &'static str), SendError>>) -> Self {
+            script.reverse();
+            Self {
+                script: std::sync::Mutex::new(script),
+                sent: Default::default(),
+            }
+        }
+    }
+
+    impl HttpBackend for ScriptedBackend {
+        fn send(&self, _request: &Request) -> Result<Response, SendError> {
+            self.sent.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let (status, body) = self.script.lock().unwrap().pop().unwrap()?;
+            Ok(Response {
+                status_code: status,
+                // Retry immediately
+                headers: HashMap::from([("retry-after".to_string(), "0".to_string())]),
+                body: body.as_bytes().to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_send_with_retries_until_success_or_out_of_attempts() {
+        let backend = ScriptedBackend::new(vec![Ok((503, "busy")), Ok((429, "")), Ok((200, "ok"))]);
+        let response = send_with(&backend, get("https://forge/api"), "API request").unwrap();
+        assert_eq!(response.as_str().unwrap(), "ok");
+
+        // Client errors are the caller's to interpret
+        let backend = ScriptedBackend::new(vec![Ok((404, "missing"))]);
+        let response = send_with(&backend, get("https://forge/api"), "API request").unwrap();
+        assert_eq!(response.status_code, 404);
+
+        let backend = ScriptedBackend::new(vec![
+            Ok((502, "bad gateway"));
+            DEFAULT_MAX_ATTEMPTS as usize
+        ]);
+        match send_with(&backend, get("https://forge/api"), "API request") {
+            Err(GitAiError::HttpRequestFailed {
+                attempts,
+                status,
+                message,
+                ..
+            }) => {
+                assert_eq!(attempts, DEFAULT_MAX_ATTEMPTS);
+                assert_eq!(status, Some(502));
+                assert_eq!(message, "status 502: bad gateway");
+            }
+            other => panic!(
+                "expected HttpRequestFailed, got {:?}",
+                other.map(|r| r.status_code)
+            ),
+        }
+        assert_eq!(
+            backend.sent.load(std::sync::atomic::Ordering::SeqCst),
+            DEFAULT_MAX_ATTEMPTS as usize
+        );
+    }
+
+    #[test]
+    fn test_send_with_retries_posts_only_when_they_cant_have_taken_effect() {
+        let post = || post("https://forge/api/comments");
+        let sent =
+            |backend: &ScriptedBackend| backend.sent.load(std::sync::atomic::Ordering::SeqCst);
+
+        // A server error or timeout may come after the comment was posted
+        let backend = ScriptedBackend::new(vec![Ok((503, "busy"))]);
+        let response = send_with(&backend, post(), "API request").unwrap();
+        assert_eq!(response.status_code, 503);
+        assert_eq!(sent(&backend), 1);
+
+        let backend = ScriptedBackend::new(vec![Err("timed out".to_string().into())]);
+        match send_with(&backend, post(), "API request") {
+            Err(GitAiError::HttpRequestFailed { attempts, .. }) => assert_eq!(attempts, 1),
+            other => panic!(
+                "expected HttpRequestFailed, got {:?}",
+                other.map(|r| r.status_code)
+            ),
+        }
+        assert_eq!(sent(&backend), 1);
+
+        // A rate limit or a refused connection means it wasn't
+        let backend = ScriptedBackend::new(vec![
+            Ok((429, "")),
+            Err(SendError::connect("connection refused")),
+            Ok((201, "created")),
+        ]);
+        let response = send_with(&backend, post(), "API request").unwrap();
+        assert_eq!(response.status_code, 201);
+        assert_eq!(sent(&backend), 3);
+    }
+
+    fn env(pairs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name: &str| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_proxy_for_picks_proxy_by_scheme() {
+        let vars = env(&[
+            ("HTTPS_PROXY", "http://proxy.corp:3128"),
+            ("http_proxy", "http://plain.corp:8080"),
+            ("NO_PROXY", "localhost,.internal.corp,10.0.0.5:8443"),
+        ]);
+        assert_eq!(
+            proxy_for("https://gitlab.com/api/v4/projects", &vars).as_deref(),
+            Some("http://proxy.corp:3128")
+        );
+        assert_eq!(
+            proxy_for("http://gitlab.example/api/v4", &vars).as_deref(),
+            Some("http://plain.corp:8080")
+        );
+        assert_eq!(proxy_for("https://git.internal.corp/api", &vars), None);
+        assert_eq!(proxy_for("http://localhost:8080/api", &vars), None);
+        assert_eq!(proxy_for("https://10.0.0.5:8443/api", &vars), None);
+        // The exemption is for another port
+        assert!(proxy_for("https://10.0.0.5/api", &vars).is_some());
+
+        let vars = env(&[("ALL_PROXY", "http://all.corp:3128")]);
+        assert_eq!(
+            proxy_for("https://api.github.com/repos", &vars).as_deref(),
+            Some("http://all.corp:3128")
+        );
+        assert_eq!(proxy_for("https://api.github.com/repos", env(&[])), None);
+    }
+
+    #[test]
+    fn test_bypasses_proxy_matches_domains_and_ports() {
+        assert!(bypasses_proxy("*", "gitlab.com", Some(443)));
+        assert!(bypasses_proxy("example.com", "example.com", Some(443)));
+        assert!(bypasses_proxy("example.com", "api.example.com", Some(443)));
+        assert!(bypasses_proxy(
+            "*.example.com",
+            "api.example.com",
+            Some(443)
+        ));
+        assert!(bypasses_proxy(
+            " .Example.com ",
+            "api.example.com",
+            Some(443)
+        ));
+        assert!(!bypasses_proxy("example.com", "badexample.com", Some(443)));
+        assert!(bypasses_proxy("::1", "[::1]", Some(80)));
+        assert!(bypasses_proxy("[::1]:8080", "[::1]", Some(8080)));
+        assert!(!bypasses_proxy("[::1]:8080", "[::1]", Some(80)));
+        assert!(!bypasses_proxy("", "gitlab.com", Some(443)));
+    }
+
+    #[test]
+    fn test_effective_timeout_is_cut_short_by_deadline() {
+        assert_eq!(effective_timeout(None, None, None), DEFAULT_TIMEOUT_SECS);
+        assert_eq!(effective_timeout(Some(10), None, None), 10);
+        assert_eq!(effective_timeout(None, Some(90), None), 90);
+        assert_eq!(
+            effective_timeout(Some(10), Some(5), Some(Duration::from_secs(600))),
+            5
+        );
+        assert_eq!(
+            effective_timeout(None, Some(90), Some(Duration::from_millis(4500))),
+            5
+        );
+        assert_eq!(
+            effective_timeout(None, None, Some(Duration::from_secs(600))),
+            DEFAULT_TIMEOUT_SECS
+        );
+    }
+
+    #[test]
+    fn test_configured_timeout_overrides_the_requests_own() {
+        // e.g. the 10s on container credential requests
+        assert_eq!(effective_timeout(Some(10), Some(90), None), 90);
+        assert_eq!(effective_timeout(Some(10), Some(2), None), 2);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_on_server_errors() {
+        assert_eq!(retry_delay(1, true, 200, headers(&[]), 0), None);
+        assert_eq!(retry_delay(1, true, 404, headers(&[]), 0), None);
+        assert_eq!(
+            retry_delay(1, true, 503, headers(&[]), 0),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            retry_delay(3, true, 502, headers(&[]), 0),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(retry_delay(30, true, 500, headers(&[]), 0), Some(MAX_DELAY));
+        assert_eq!(
+            retry_delay(1, true, 503, headers(&[("retry-after", "0")]), 0),
+            Some(Duration::ZERO)
+        );
+        // A POST may have gone through before the server failed
+        assert_eq!(retry_delay(1, false, 503, headers(&[]), 0), None);
+        assert_eq!(
+            retry_delay(1, false, 429, headers(&[("retry-after", "3")]), 0),
+            Some(Duration::from_secs(3))
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::ci::branch_match::{MergeCandidate, find_merged_branch};
+use crate::ci::ci_context::{CiContext, CiEvent};
+use crate::ci::env;
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git, find_repository_in_path};
+use crate::observability::timings::{self, Phase};
+use std::path::PathBuf;
+
+const JENKINSFILE_TEMPLATE: &str = include_str!("workflow_templates/jenkinsfile.groovy");
+
+/// Jenkins runs in the job's own checkout and has no forge API to ask which branch a
+/// commit merged, so the head is found locally: the second parent of a merge commit, or
+/// else the branch whose changes the commit reproduces (a squash or rebase merge).
+///
+/// Reads the build's GIT_COMMIT, and from multibranch pipelines CHANGE_ID,
+/// CHANGE_TARGET and CHANGE_BRANCH for change requests or BRANCH_NAME for branch builds
+/// (GIT_BRANCH without the multibranch plugin). Returns None if no branch in the
+/// checkout was merged as GIT_COMMIT.
+pub fn get_jenkins_ci_context() -> Result<Option<CiContext>, GitAiError> {
+    let commit_sha = env("GIT_COMMIT")
+        .ok_or_else(|| GitAiError::Generic("GIT_COMMIT environment variable not set".into()))?;
+    let change_id = env("CHANGE_ID");
+    let change_branch = env("CHANGE_BRANCH");
+    let base_ref = env("CHANGE_TARGET")
+        .or_else(|| env("BRANCH_NAME"))
+        .or_else(|| env("GIT_BRANCH").map(|branch| strip_remote(&branch).to_string()))
+        .ok_or_else(|| {
+            GitAiError::Generic(
+                "None of CHANGE_TARGET, BRANCH_NAME or GIT_BRANCH is set; can't tell which branch was merged into"
+                    .to_string(),
+            )
+        })?;
+
+    println!("[Jenkins] Environment:");
+    println!("  Commit: {}", commit_sha);
+    println!("  Target branch: {}", base_ref);
+    if let Some(change_id) = &change_id {
+        println!(
+            "  Change request: {} ({})",
+            change_id,
+            change_branch.as_deref().unwrap_or("unknown branch")
+        );
+    }
+
+    let repo = find_repository_in_path(".")?;
+    // Checkouts often only fetch the built branch; the merged branch's head is needed too.
+    // GitHub and GitLab keep pull/merge request heads after the branch is deleted.
+    println!("[Jenkins] Fetching branches...");
+    let mut fetch_args = repo.global_args_for_exec();
+    fetch_args.extend([
+        "fetch".to_string(),
+        "origin".to_string(),
+        "+refs/heads/*:refs/remotes/origin/*".to_string(),
+        "+refs/pull/*/head:refs/remotes/origin/PR-*".to_string(),
+        "+refs/merge-requests/*/head:refs/remotes/origin/MR-*".to_string(),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        if let Err(e) = exec_git(&fetch_args) {
+            println!(
+                "[Jenkins] Warning: could not fetch branches, using the checkout as is: {}",
+                e
+            );
+        }
+    }
+
+    let commit = repo.find_commit(commit_sha.clone())?;
+    let base_sha = commit.parent(0).map(|p| p.id()).unwrap_or_default();
+    let merged = if commit.parent_count()? > 1 {
+        // A merge commit names the branch head itself
+        let head_sha = commit.parent(1)?.id();
+        Some((
+            change_branch.clone().unwrap_or_else(|| head_sha.clone()),
+            head_sha,
+        ))
+    } else {
+        let candidates = match (&change_branch, &change_id) {
+            (Some(branch), _) => vec![format!("refs/remotes/origin/{}", branch)],
+            (None, Some(change_id)) => vec![
+                format!("refs/remotes/origin/PR-{}", change_id),
+                format!("refs/remotes/origin/MR-{}", change_id),
+            ],
+            (None, None) => remote_branches(&repo)?,
+        };
+        let candidates: Vec<MergeCandidate> = candidates
+            .into_iter()
+            .map(|rev| MergeCandidate {
+                branch: strip_remote(&rev).to_string(),
+                rev,
+            })
+            .collect();
+        let merged = find_merged_branch(&repo, &commit_sha, &base_ref, &candidates)?;
+        if let Some((branch, _)) = &merged {
+            println!("[Jenkins] {} was merged as {}", branch, commit_sha);
+        }
+        merged
+    };
+
+    let Some((head_ref, head_sha)) = merged else {
+        println!("[Jenkins] No branch in the checkout was merged as this commit. Skipping...");
+        return Ok(None);
+    };
+    println!(
+        "[Jenkins] Created CiContext: merge_commit_sha={}, head_sha={}, head_ref={}, base_ref={}",
+        commit_sha, head_sha, head_ref, base_ref
+    );
+
+    Ok(Some(CiContext {
+        repo,
+        event: CiEvent::Merge {
+            merge_commit_sha: commit_sha,
+            head_ref,
+            head_sha,
+            base_ref,
+            base_sha,
+            merge_request: None,
+        },
+        // The job's checkout belongs to Jenkins; nothing to clean up
+        temp_dir: PathBuf::new(),
+    }))
+}
+
+/// `origin/main` -> `main`
+fn strip_remote(branch: &str) -> &str {
+    let branch = branch.strip_prefix("refs/remotes/").unwrap_or(branch);
+    branch.strip_prefix("origin/").unwrap_or(branch)
+}
+
+/// Remote-tracking branches, most recently committed first
+fn remote_branches(repo: &Repository) -> Result<Vec<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "for-each-ref".to_string(),
+        "--sort=-committerdate".to_string(),
+        "--format=%(refname)".to_string(),
+        "refs/remotes/origin/".to_string(),
+    ]);
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|name| *name != "refs/remotes/origin/HEAD")
+        .map(str::to_string)
+        .collect())
+}
+
+/// Knobs for rendering the Jenkinsfile stage
+#[derive(Debug, Clone, Default)]
+pub struct JenkinsTemplateOptions {
+    /// Branch (or glob) the stage runs on; `main` when unset
+    pub branch: Option<String>,
+    /// Jenkins credentials that can push to the repository; `git-ai-push` when unset
+    pub credentials_id: Option<String>,
+}
+
+pub fn render_jenkinsfile(options: &JenkinsTemplateOptions) -> Result<String, GitAiError> {
+    let branch = options.branch.as_deref().unwrap_or("main");
+    let credentials_id = options.credentials_id.as_deref().unwrap_or("git-ai-push");
+    for (what, value) in [("branch name", branch), ("credentials ID", credentials_id)] {
+        if value.is_empty()
+            || value.contains(['"', '\'', '\\', '$'])
+            || value.contains(char::is_whitespace)
+        {
+            return Err(GitAiError::Generic(format!("Invalid {} '{}'", what, value)));
+        }
+    }
+    Ok(JENKINSFILE_TEMPLATE
+        .replace("{{BRANCH}}", branch)
+        .replace("{{CREDENTIALS_ID}}", credentials_id))
+}
+
+/// Print the Jenkinsfile stage for users to copy into their Jenkinsfile
+pub fn print_jenkinsfile(options: &JenkinsTemplateOptions) -> Result<(), GitAiError> {
+    let stage = render_jenkinsfile(options)?;
+    println!("Add the following stage to your Jenkinsfile:");
+    println!();
+    println!("{}", stage);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_remote() {
+        assert_eq!(strip_remote("origin/main"), "main");
+        assert_eq!(
+            strip_remote("refs/remotes/origin/release/1.0"),
+            "release/1.0"
+        );
+        assert_eq!(strip_remote("main"), "main");
+    }
+
+    #[test]
+    fn test_template_renders_branch_and_credentials() {
+        let stage = render_jenkinsfile(&JenkinsTemplateOptions::default()).unwrap();
+        assert!(!stage.contains("{{"));
+        assert!(stage.contains("branch 'main'"));
+        assert!(stage.contains("credentialsId: 'git-ai-push'"));
+        assert!(stage.contains("git-ai ci jenkins run"));
+
+        let stage = render_jenkinsfile(&JenkinsTemplateOptions {
+            branch: Some("release/*".to_string()),
+            credentials_id: Some("github-app".to_string()),
+        })
+        .unwrap();
+        assert!(stage.contains("branch 'release/*'"));
+        assert!(stage.contains("credentialsId: 'github-app'"));
+
+        assert!(
+            render_jenkinsfile(&JenkinsTemplateOptions {
+                branch: Some("main' || x".to_string()),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,1585 @@
+//! A local stand-in for GitLab, GitHub, Bitbucket, Gitea, Gerrit and CodeCommit, for exercising
+//! CI providers end to end.
+//!
+//! [`MockForge`] listens on a loopback port and serves:
+//! - the GitLab merge request and commit endpoints under `/api/v4` that `ci gitlab run`
+//!   and `ci selftest` query
+//! - the GitHub pull request and commit endpoints under `/api/v3` (the GitHub Enterprise
+//!   layout), and the installation token endpoints a GitHub App authenticates with
+//! - the Bitbucket 2.0 pull request and commit endpoints under `/api/bitbucket/2.0`,
+//!   serving the same pull requests as the GitHub endpoints
+//! - the Gitea v1 pull request list under `/api/v1`, serving the same pull requests as
+//!   the GitHub endpoints
+//! - the CircleCI v2 pipeline list under `/api/circleci/v2`, with a pipeline for each
+//!   pull request's head
+//! - the Gerrit change query and commit endpoints under `/gerrit`, authenticated under
+//!   `/gerrit/a/`, serving changes added with [`MockForge::add_gerrit_change`]
+//! - the CodeCommit `ListPullRequests` and `GetPullRequest` actions at `/codecommit/`,
+//!   which require a SigV4 `Authorization` header and serve the pull requests added
+//!   under a repository's name
+//! - an OIDC token exchange service and the GitHub Actions ID token endpoint, under `/oidc`
+//! - git smart HTTP for registered repositories at `/<path>.git`, through `git http-backend`,
+//!   so clones, fetches of merge request refs and note pushes hit a real repository
+//!
+//! [`MockForge::start_tls`] serves the same over HTTPS, like a self-hosted forge behind an
+//! internal CA. Every request is recorded so callers can check which endpoints were hit
+//! and with which credentials.
+
+use crate::config;
+use crate::error::GitAiError;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// A merge request as the GitLab API returns it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MockMergeRequest {
+    pub iid: u64,
+    pub title: Option<String>,
+    pub state: String,
+    pub source_branch: String,
+    pub target_branch: String,
+    /// Head of the source branch
+    pub sha: String,
+    pub merge_commit_sha: Option<String>,
+    pub squash_commit_sha: Option<String>,
+    pub squash: Option<bool>,
+}
+
+impl MockMergeRequest {
+    /// A merged MR whose result landed on the target branch as `merge_commit_sha`
+    pub fn merged(
+        iid: u64,
+        source_branch: &str,
+        target_branch: &str,
+        head_sha: &str,
+        merge_commit_sha: &str,
+    ) -> Self {
+        Self {
+            iid,
+            title: Some(format!("Merge {} into {}", source_branch, target_branch)),
+            state: "merged".to_string(),
+            source_branch: source_branch.to_string(),
+            target_branch: target_branch.to_string(),
+            sha: head_sha.to_string(),
+            merge_commit_sha: Some(merge_commit_sha.to_string()),
+            squash_commit_sha: None,
+            squash: Some(false),
+        }
+    }
+}
+
+/// A merge request's car on a merge train, rendered as the merge trains API returns it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockMergeTrainCar {
+    pub merge_request_iid: u64,
+    /// `fresh`, `merging`, `merged`, ...
+    pub status: String,
+    /// Commit of the train ref the car's pipeline ran on
+    pub pipeline_sha: String,
+}
+
+/// A GitLab pipeline, as the project pipelines API lists it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockPipeline {
+    pub id: u64,
+    /// Branch or tag it ran for
+    pub ref_name: String,
+    pub sha: String,
+    /// `success`, `failed`, `running`, ...
+    pub status: String,
+}
+
+/// A pull request, rendered in the GitHub API's nested shape when served
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockPullRequest {
+    pub number: u32,
+    pub title: String,
+    pub head_ref: String,
+    pub head_sha: String,
+    pub base_ref: String,
+    pub base_sha: String,
+    pub merged: bool,
+    pub merge_commit_sha: Option<String>,
+}
+
+/// A Gerrit change, rendered as the REST API returns it when queried with
+/// `o=ALL_REVISIONS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockGerritChange {
+    pub number: u64,
+    pub change_id: String,
+    pub project: String,
+    pub branch: String,
+    pub subject: String,
+    /// `MERGED`, `NEW` or `ABANDONED`
+    pub status: String,
+    /// Commit and kind (e.g. `REWORK`, `TRIVIAL_REBASE`) of each patch set, first to last
+    pub patch_sets: Vec<(String, String)>,
+}
+
+/// A request the forge received
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query string
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: BTreeMap<String, String>,
+    /// Request body, lossily decoded
+    pub body: String,
+}
+
+#[derive(Default)]
+struct ForgeState {
+    url: String,
+    /// Repository path (e.g. `group/project`) -> directory served for it
+    repos: BTreeMap<String, PathBuf>,
+    /// Numeric GitLab project id -> repository path
+    project_ids: BTreeMap<String, String>,
+    /// Project id or path -> merge requests
+    merge_requests: BTreeMap<String, Vec<MockMergeRequest>>,
+    /// Project id or path -> merge train cars
+    merge_train_cars: BTreeMap<String, Vec<MockMergeTrainCar>>,
+    /// Project id or path -> pipelines
+    pipelines: BTreeMap<String, Vec<MockPipeline>>,
+    /// `owner/repo` -> pull requests
+    pull_requests: BTreeMap<String, Vec<MockPullRequest>>,
+    gerrit_changes: Vec<MockGerritChange>,
+    required_token: Option<String>,
+    /// GitHub App id and the id of its installation on every repository
+    github_app: Option<(String, u64)>,
+    /// Installation tokens minted for the app, oldest first
+    installation_tokens: Vec<String>,
+    /// Bearer token the Actions ID token endpoint expects, when OIDC is enabled
+    oidc_request_token: Option<String>,
+    /// Forge tokens handed out for OIDC tokens, oldest first
+    exchanged_tokens: Vec<String>,
+    deny_push: bool,
+    /// Scopes GitLab reports for the API token, when set
+    token_scopes: Option<Vec<String>>,
+    /// Statuses (and `Retry-After` values) to answer the next API requests with
+    api_failures: VecDeque<(u16, Option<String>)>,
+    requests: Vec<RecordedRequest>,
+}
+
+pub struct MockForge {
+    addr: SocketAddr,
+    /// Serving HTTPS rather than plain HTTP
+    tls: bool,
+    state: Arc<Mutex<ForgeState>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockForge {
+    /// Start serving on an ephemeral loopback port
+    pub fn start() -> Result<Self, GitAiError> {
+        Self::start_with(None)
+    }
+
+    /// Start serving HTTPS on an ephemeral loopback port, with the PEM certificate chain
+    /// and private key at `cert` and `key` (issued for 127.0.0.1)
+    pub fn start_tls(cert: &Path, key: &Path) -> Result<Self, GitAiError> {
+        let invalid = |e: &dyn std::fmt::Display| GitAiError::Generic(format!("TLS setup: {}", e));
+        let certs = CertificateDer::pem_file_iter(cert)
+            .map_err(|e| invalid(&e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| invalid(&e))?;
+        let key = PrivateKeyDer::from_pem_file(key).map_err(|e| invalid(&e))?;
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| invalid(&e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid(&e))?;
+        Self::start_with(Some(Arc::new(config)))
+    }
+
+    fn start_with(tls: Option<Arc<rustls::ServerConfig>>) -> Result<Self, GitAiError> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let scheme = if tls.is_some() { "https" } else { "http" };
+        let state = Arc::new(Mutex::new(ForgeState {
+            url: format!("{}://{}", scheme, addr),
+            ..Default::default()
+        }));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let state = state.clone();
+                    let tls = tls.clone();
+                    std::thread::spawn(move || {
+                        let _ = match tls {
+                            Some(config) => rustls::ServerConnection::new(config)
+                                .map_err(std::io::Error::other)
+                                .and_then(|connection| {
+                                    handle_connection(
+                                        rustls::StreamOwned::new(connection, stream),
+                                        &state,
+                                    )
+                                }),
+                            None => handle_connection(stream, &state),
+                        };
+                    });
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            tls: scheme == "https",
+            state,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// Base URL, e.g. `http://127.0.0.1:41234`
+    pub fn url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{}://{}", scheme, self.addr)
+    }
+
+    pub fn gitlab_api_url(&self) -> String {
+        format!("{}/api/v4", self.url())
+    }
+
+    pub fn github_api_url(&self) -> String {
+        format!("{}/api/v3", self.url())
+    }
+
+    pub fn bitbucket_api_url(&self) -> String {
+        format!("{}/api/bitbucket/2.0", self.url())
+    }
+
+    pub fn gitea_api_url(&self) -> String {
+        format!("{}/api/v1", self.url())
+    }
+
+    pub fn circleci_api_url(&self) -> String {
+        format!("{}/api/circleci/v2", self.url())
+    }
+
+    /// Base URL of the Gerrit server. Register its projects' repositories with
+    /// [`MockForge::add_repo`] under `gerrit/<project>`.
+    pub fn gerrit_url(&self) -> String {
+        format!("{}/gerrit", self.url())
+    }
+
+    /// Endpoint of the CodeCommit API. Register repositories with [`MockForge::add_repo`]
+    /// under `v1/repos/<name>`, and their pull requests under `<name>`.
+    pub fn codecommit_url(&self) -> String {
+        format!("{}/codecommit", self.url())
+    }
+
+    /// Clone URL of a repository registered with [`MockForge::add_repo`]
+    pub fn repo_url(&self, path: &str) -> String {
+        format!("{}/{}.git", self.url(), path)
+    }
+
+    /// Serve the repository in `dir` at `<url>/<path>.git`. Pushes are accepted.
+    pub fn add_repo(&self, path: &str, dir: &Path) {
+        self.lock()
+            .repos
+            .insert(path.trim_matches('/').to_string(), dir.to_path_buf());
+    }
+
+    /// Let the GitLab API address the repository at `path` by its numeric `id`
+    pub fn add_project_id(&self, id: &str, path: &str) {
+        self.lock()
+            .project_ids
+            .insert(id.to_string(), path.trim_matches('/').to_string());
+    }
+
+    /// Add a merge request to a GitLab project, looked up by `project` as either its
+    /// numeric id or its path
+    pub fn add_merge_request(&self, project: &str, merge_request: MockMergeRequest) {
+        self.lock()
+            .merge_requests
+            .entry(project.to_string())
+            .or_default()
+            .push(merge_request);
+    }
+
+    /// Put a merge request of `project` on a merge train
+    pub fn add_merge_train_car(&self, project: &str, car: MockMergeTrainCar) {
+        self.lock()
+            .merge_train_cars
+            .entry(project.to_string())
+            .or_default()
+            .push(car);
+    }
+
+    /// Record a pipeline of `project`
+    pub fn add_pipeline(&self, project: &str, pipeline: MockPipeline) {
+        self.lock()
+            .pipelines
+            .entry(project.to_string())
+            .or_default()
+            .push(pipeline);
+    }
+
+    /// Add a pull request to the GitHub repository `owner/repo`, or the Bitbucket
+    /// repository `workspace/repo_slug`
+    pub fn add_pull_request(&self, repo: &str, pull_request: MockPullRequest) {
+        self.lock()
+            .pull_requests
+            .entry(repo.to_string())
+            .or_default()
+            .push(pull_request);
+    }
+
+    /// Add a change to the Gerrit server
+    pub fn add_gerrit_change(&self, change: MockGerritChange) {
+        self.lock().gerrit_changes.push(change);
+    }
+
+    /// Reject API requests that don't carry `token` as `PRIVATE-TOKEN`, `JOB-TOKEN`,
+    /// `Circle-Token`, an `Authorization` header or a basic auth password
+    pub fn require_token(&self, token: &str) {
+        self.lock().required_token = Some(token.to_string());
+    }
+
+    /// Register a GitHub App installed on every repository, so JWTs issued by `app_id`
+    /// can be exchanged for installation tokens. The JWT's issuer and expiry are checked
+    /// but not its signature. Minted tokens pass [`MockForge::require_token`].
+    pub fn add_github_app(&self, app_id: &str, installation_id: u64) {
+        self.lock().github_app = Some((app_id.to_string(), installation_id));
+    }
+
+    /// Installation tokens minted so far, oldest first
+    pub fn installation_tokens(&self) -> Vec<String> {
+        self.lock().installation_tokens.clone()
+    }
+
+    /// Serve an OIDC token exchange at [`MockForge::oidc_exchange_url`] and the Actions ID
+    /// token endpoint, which expects `request_token`. Identity tokens only need to decode
+    /// to claims with an issuer; they aren't verified. Exchanged tokens pass
+    /// [`MockForge::require_token`].
+    pub fn enable_oidc(&self, request_token: &str) {
+        self.lock().oidc_request_token = Some(request_token.to_string());
+    }
+
+    pub fn oidc_exchange_url(&self) -> String {
+        format!("{}/oidc/exchange", self.url())
+    }
+
+    /// Forge tokens exchanged for OIDC tokens so far, oldest first
+    pub fn exchanged_tokens(&self) -> Vec<String> {
+        self.lock().exchanged_tokens.clone()
+    }
+
+    /// The variables GitHub Actions sets for a job with `id-token: write`
+    pub fn github_oidc_env(&self, request_token: &str) -> Vec<(String, String)> {
+        vec![
+            (
+                "ACTIONS_ID_TOKEN_REQUEST_URL".to_string(),
+                format!("{}/oidc/github-id-token?api-version=2.0", self.url()),
+            ),
+            (
+                "ACTIONS_ID_TOKEN_REQUEST_TOKEN".to_string(),
+                request_token.to_string(),
+            ),
+        ]
+    }
+
+    /// Refuse git pushes with a 403, like a token without write access
+    pub fn deny_push(&self) {
+        self.lock().deny_push = true;
+    }
+
+    /// Report `scopes` for whichever token looks itself up at
+    /// `/personal_access_tokens/self`; without this the endpoint is a 404, like on GitLab
+    /// versions without it
+    pub fn set_token_scopes(&self, scopes: &[&str]) {
+        self.lock().token_scopes = Some(scopes.iter().map(|s| s.to_string()).collect());
+    }
+
+    /// Answer the next `count` API requests with `status`, like an overloaded or
+    /// rate-limiting forge, sending `retry_after` as the `Retry-After` header if set
+    pub fn fail_api_requests(&self, count: usize, status: u16, retry_after: Option<&str>) {
+        let mut state = self.lock();
+        for _ in 0..count {
+            state
+                .api_failures
+                .push_back((status, retry_after.map(str::to_string)));
+        }
+    }
+
+    /// Every request received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// The environment a GitLab CI job for `project_path` (with numeric `project_id`)
+    /// would see when building `commit_sha`
+    pub fn gitlab_ci_env(
+        &self,
+        project_id: &str,
+        project_path: &str,
+        commit_sha: &str,
+        job_token: &str,
+    ) -> Vec<(String, String)> {
+        [
+            ("GITLAB_CI", "true".to_string()),
+            ("CI_API_V4_URL", self.gitlab_api_url()),
+            ("CI_SERVER_URL", self.url()),
+            ("CI_PROJECT_ID", project_id.to_string()),
+            ("CI_PROJECT_PATH", project_path.to_string()),
+            ("CI_COMMIT_SHA", commit_sha.to_string()),
+            ("CI_JOB_TOKEN", job_token.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+
+    /// The environment a GitHub Actions job for `repo` (`owner/repo`) would see when
+    /// building `commit_sha`
+    pub fn github_actions_env(
+        &self,
+        repo: &str,
+        commit_sha: &str,
+        token: &str,
+    ) -> Vec<(String, String)> {
+        [
+            ("GITHUB_ACTIONS", "true".to_string()),
+            ("GITHUB_API_URL", self.github_api_url()),
+            ("GITHUB_SERVER_URL", self.url()),
+            ("GITHUB_REPOSITORY", repo.to_string()),
+            ("GITHUB_SHA", commit_sha.to_string()),
+            ("GITHUB_TOKEN", token.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+
+    /// The environment a Gitea Actions job for `repo` (`owner/repo`) would see when
+    /// building `commit_sha`
+    pub fn gitea_actions_env(
+        &self,
+        repo: &str,
+        commit_sha: &str,
+        token: &str,
+    ) -> Vec<(String, String)> {
+        [
+            ("GITEA_ACTIONS", "true".to_string()),
+            ("GITHUB_SERVER_URL", self.url()),
+            ("GITHUB_REPOSITORY", repo.to_string()),
+            ("GITHUB_SHA", commit_sha.to_string()),
+            ("GITEA_TOKEN", token.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+
+    /// The environment a Jenkins job triggered by Gerrit's change-merged event for
+    /// `project` would see, with `commit_sha` merged
+    pub fn gerrit_trigger_env(
+        &self,
+        project: &str,
+        commit_sha: &str,
+        username: &str,
+        http_password: &str,
+    ) -> Vec<(String, String)> {
+        [
+            ("GERRIT_EVENT_TYPE", "change-merged".to_string()),
+            ("GERRIT_URL", self.gerrit_url()),
+            ("GERRIT_PROJECT", project.to_string()),
+            ("GERRIT_NEWREV", commit_sha.to_string()),
+            ("GERRIT_USERNAME", username.to_string()),
+            ("GERRIT_HTTP_PASSWORD", http_password.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+
+    /// The environment a CodeBuild build of the CodeCommit repository `repository` would
+    /// see when building `commit_sha`, with `access_key_id` as both its AWS access key
+    /// and its HTTPS Git password
+    pub fn codebuild_env(
+        &self,
+        repository: &str,
+        commit_sha: &str,
+        access_key_id: &str,
+    ) -> Vec<(String, String)> {
+        [
+            ("CODEBUILD_BUILD_ID", format!("{}:build-1", repository)),
+            (
+                "CODEBUILD_SOURCE_REPO_URL",
+                format!("{}/v1/repos/{}.git", self.url(), repository),
+            ),
+            ("CODEBUILD_RESOLVED_SOURCE_VERSION", commit_sha.to_string()),
+            ("AWS_REGION", "us-east-1".to_string()),
+            ("AWS_ENDPOINT_URL_CODECOMMIT", self.codecommit_url()),
+            ("AWS_ACCESS_KEY_ID", access_key_id.to_string()),
+            (
+                "AWS_SECRET_ACCESS_KEY",
+                "mock-secret-access-key".to_string(),
+            ),
+            ("CODECOMMIT_GIT_USERNAME", "ci-bot".to_string()),
+            ("CODECOMMIT_GIT_PASSWORD", access_key_id.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+
+    /// The environment a Bitbucket Pipelines step for `repo` (`workspace/repo_slug`)
+    /// would see when building `commit_sha`
+    pub fn bitbucket_pipelines_env(
+        &self,
+        repo: &str,
+        commit_sha: &str,
+        token: &str,
+    ) -> Vec<(String, String)> {
+        let (workspace, repo_slug) = repo.split_once('/').unwrap_or((repo, ""));
+        [
+            ("CI", "true".to_string()),
+            ("BITBUCKET_API_URL", self.bitbucket_api_url()),
+            (
+                "BITBUCKET_GIT_HTTP_ORIGIN",
+                format!("{}/{}", self.url(), repo),
+            ),
+            ("BITBUCKET_WORKSPACE", workspace.to_string()),
+            ("BITBUCKET_REPO_SLUG", repo_slug.to_string()),
+            ("BITBUCKET_COMMIT", commit_sha.to_string()),
+            ("BITBUCKET_ACCESS_TOKEN", token.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+    }
+
+    /// The event payload GitHub Actions writes to `GITHUB_EVENT_PATH` for a
+    /// `pull_request` event on one of the forge's pull requests
+    pub fn github_event_payload(&self, repo: &str, number: u32) -> Option<serde_json::Value> {
+        let state = self.lock();
+        let pull_request = state
+            .pull_requests
+            .get(repo)?
+            .iter()
+            .find(|pr| pr.number == number)?;
+        Some(json!({
+            "action": "closed",
+            "number": number,
+            "pull_request": render_pull_request(&self.url(), repo, pull_request),
+        }))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ForgeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockForge {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: BTreeMap<String, String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn not_found() -> Self {
+        Self::json(404, &json!({ "message": "404 Not Found" }))
+    }
+}
+
+fn handle_connection(stream: impl Read + Write, state: &Mutex<ForgeState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let Some(request) = read_request(&mut reader)? else {
+        return Ok(());
+    };
+
+    let response = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.requests.push(RecordedRequest {
+            method: request.method.clone(),
+            path: if request.query.is_empty() {
+                request.path.clone()
+            } else {
+                format!("{}?{}", request.path, request.query)
+            },
+            headers: request.headers.clone(),
+            body: String::from_utf8_lossy(&request.body).into_owned(),
+        });
+        route(&request, &mut state)
+    };
+    let response = match response {
+        Route::Api(response) => response,
+        Route::Git(dir, path_info) => git_http_backend(&dir, &path_info, &request),
+    };
+
+    write_response(reader.get_mut(), &response)
+}
+
+fn read_request(reader: &mut impl BufRead) -> std::io::Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = BTreeMap::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            break;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let mut body = Vec::new();
+    if headers
+        .get("transfer-encoding")
+        .is_some_and(|value| value.eq_ignore_ascii_case("chunked"))
+    {
+        loop {
+            let mut size_line = String::new();
+            reader.read_line(&mut size_line)?;
+            let size_hex = size_line.trim().split(';').next().unwrap_or("");
+            let size = usize::from_str_radix(size_hex, 16).unwrap_or(0);
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk)?;
+            if size == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    } else if let Some(length) = headers
+        .get("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+    {
+        body.resize(length, 0);
+        reader.read_exact(&mut body)?;
+    }
+
+    Ok(Some(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body,
+    }))
+}
+
+fn write_response(stream: &mut impl Write, response: &Response) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        503 => "Service Unavailable",
+        _ => "Status",
+    }
+}
+
+enum Route {
+    Api(Response),
+    /// Repository directory and the path within it, for `git http-backend`
+    Git(PathBuf, String),
+}
+
+fn route(request: &Request, state: &mut ForgeState) -> Route {
+    if let Some(oidc_path) = request.path.strip_prefix("/oidc/")
+        && state.oidc_request_token.is_some()
+    {
+        return Route::Api(oidc_route(request, state, oidc_path));
+    }
+    if let Some(gerrit_path) = request.path.strip_prefix("/gerrit/")
+        && let Some(response) = gerrit_route(request, state, gerrit_path)
+    {
+        return Route::Api(response);
+    }
+    if request.path.trim_end_matches('/') == "/codecommit" && request.method == "POST" {
+        return Route::Api(codecommit_route(request, state));
+    }
+    if let Some(api_path) = request.path.strip_prefix("/api/") {
+        if let Some((status, retry_after)) = state.api_failures.pop_front() {
+            let mut response = Response::json(status, &json!({ "message": "Try again later" }));
+            if let Some(retry_after) = retry_after {
+                response
+                    .headers
+                    .push(("Retry-After".to_string(), retry_after));
+            }
+            return Route::Api(response);
+        }
+        let segments: Vec<&str> = api_path.split('/').collect();
+        if let Some(response) = github_app_route(request, state, &segments) {
+            return Route::Api(response);
+        }
+        if let Some(required) = &state.required_token
+            && !is_authorized(&request.headers, required)
+            && !state
+                .installation_tokens
+                .iter()
+                .chain(&state.exchanged_tokens)
+                .any(|token| is_authorized(&request.headers, token))
+        {
+            return Route::Api(Response::json(
+                401,
+                &json!({ "message": "401 Unauthorized" }),
+            ));
+        }
+        return Route::Api(match segments.as_slice() {
+            ["v4", "projects", project] => {
+                let path = resolve_project(state, project);
+                match state.project_ids.iter().find(|(_, p)| **p == path) {
+                    Some((id, path)) => Response::json(
+                        200,
+                        &json!({ "id": id.parse::<u64>().ok(), "path_with_namespace": path }),
+                    ),
+                    None => Response::not_found(),
+                }
+            }
+            ["v4", "projects", project, "merge_requests"] => {
+                let wanted_state = query_param(&request.query, "state");
+                let merge_requests: Vec<&MockMergeRequest> = project_merge_requests(state, project)
+                    .iter()
+                    .filter(|mr| wanted_state.as_deref().is_none_or(|s| s == mr.state))
+                    .collect();
+                gitlab_page(request, &merge_requests)
+            }
+            ["v4", "projects", project, "merge_requests", iid] => {
+                project_merge_requests(state, project)
+                    .iter()
+                    .find(|mr| mr.iid.to_string() == *iid)
+                    .map(|mr| Response::json(200, &json!(mr)))
+                    .unwrap_or_else(Response::not_found)
+            }
+            ["v4", "projects", _, "merge_requests", _, "notes"] if request.method == "POST" => {
+                Response::json(
+                    201,
+                    &json!({ "id": state.requests.len(), "body": body_field(request) }),
+                )
+            }
+            ["v4", "personal_access_tokens", "self"] => match &state.token_scopes {
+                Some(scopes) => Response::json(200, &json!({ "name": "git-ai", "scopes": scopes })),
+                None => Response::not_found(),
+            },
+            ["v4", "projects", project, "pipelines"] => {
+                let decoded = project.replace("%2F", "/").replace("%2f", "/");
+                let wanted_ref = query_param(&request.query, "ref");
+                let wanted_status = query_param(&request.query, "status");
+                let mut pipelines: Vec<&MockPipeline> = state
+                    .pipelines
+                    .get(&decoded)
+                    .or_else(|| state.pipelines.get(&resolve_project(state, project)))
+                    .into_iter()
+                    .flatten()
+                    .filter(|p| wanted_ref.as_deref().is_none_or(|r| r == p.ref_name))
+                    .filter(|p| wanted_status.as_deref().is_none_or(|s| s == p.status))
+                    .collect();
+                // Newest first, the API's default order
+                pipelines.sort_by_key(|p| std::cmp::Reverse(p.id));
+                let pipelines: Vec<serde_json::Value> = pipelines
+                    .into_iter()
+                    .map(|p| json!({ "id": p.id, "ref": p.ref_name, "sha": p.sha, "status": p.status }))
+                    .collect();
+                gitlab_page(request, &pipelines)
+            }
+            [
+                "v4",
+                "projects",
+                project,
+                "merge_trains",
+                "merge_requests",
+                iid,
+            ] => {
+                let decoded = project.replace("%2F", "/").replace("%2f", "/");
+                state
+                    .merge_train_cars
+                    .get(&decoded)
+                    .or_else(|| state.merge_train_cars.get(&resolve_project(state, project)))
+                    .into_iter()
+                    .flatten()
+                    .find(|car| car.merge_request_iid.to_string() == *iid)
+                    .map(|car| {
+                        let merge_request = project_merge_requests(state, project)
+                            .iter()
+                            .find(|mr| mr.iid == car.merge_request_iid);
+                        Response::json(
+                            200,
+                            &json!({
+                                "id": car.merge_request_iid,
+                                "merge_request": merge_request,
+                                "pipeline": { "sha": car.pipeline_sha },
+                                "target_branch": merge_request.map(|mr| &mr.target_branch),
+                                "status": car.status,
+                            }),
+                        )
+                    })
+                    .unwrap_or_else(Response::not_found)
+            }
+            [
+                "v4",
+                "projects",
+                project,
+                "repository",
+                "commits",
+                sha,
+                "merge_requests",
+            ] => {
+                let merge_requests: Vec<&MockMergeRequest> = project_merge_requests(state, project)
+                    .iter()
+                    .filter(|mr| {
+                        mr.sha == *sha
+                            || mr.merge_commit_sha.as_deref() == Some(sha)
+                            || mr.squash_commit_sha.as_deref() == Some(sha)
+                    })
+                    .collect();
+                Response::json(200, &json!(merge_requests))
+            }
+            ["v4", "projects", project, "repository", "commits", sha] => {
+                commit_response(state.repos.get(&resolve_project(state, project)), sha)
+            }
+            ["v3" | "v1", "repos", owner, repo, "pulls"] => {
+                let repo = format!("{}/{}", owner, repo);
+                let wanted_state = query_param(&request.query, "state");
+                let pull_requests: Vec<serde_json::Value> = state
+                    .pull_requests
+                    .get(&repo)
+                    .into_iter()
+                    .flatten()
+                    .filter(|pr| match wanted_state.as_deref() {
+                        Some("all") => true,
+                        Some("closed") => pr.merged,
+                        _ => !pr.merged,
+                    })
+                    .map(|pr| render_pull_request(&state.url, &repo, pr))
+                    .collect();
+                Response::json(200, &json!(pull_requests))
+            }
+            ["v3", "repos", owner, repo, "commits", sha] => {
+                commit_response(state.repos.get(&format!("{}/{}", owner, repo)), sha)
+            }
+            ["v3", "repos", owner, repo, "pulls", number] => {
+                let repo = format!("{}/{}", owner, repo);
+                state
+                    .pull_requests
+                    .get(&repo)
+                    .and_then(|prs| prs.iter().find(|pr| pr.number.to_string() == *number))
+                    .map(|pr| Response::json(200, &render_pull_request(&state.url, &repo, pr)))
+                    .unwrap_or_else(Response::not_found)
+            }
+            ["v3", "repos", _, _, "issues", _, "comments"] if request.method == "POST" => {
+                Response::json(
+                    201,
+                    &json!({ "id": state.requests.len(), "body": body_field(request) }),
+                )
+            }
+            ["v3", "repos", _, _, "check-runs"] if request.method == "POST" => {
+                Response::json(201, &json!({ "id": state.requests.len() }))
+            }
+            ["v3", "repos", _, _, "check-runs", id] if request.method == "PATCH" => {
+                Response::json(200, &json!({ "id": id.parse::<u64>().unwrap_or(0) }))
+            }
+            ["v3", "repos", owner, repo, "commits", sha, "pulls"] => {
+                let repo = format!("{}/{}", owner, repo);
+                let pull_requests: Vec<serde_json::Value> = state
+                    .pull_requests
+                    .get(&repo)
+                    .into_iter()
+                    .flatten()
+                    .filter(|pr| pr.head_sha == *sha || pr.merge_commit_sha.as_deref() == Some(sha))
+                    .map(|pr| render_pull_request(&state.url, &repo, pr))
+                    .collect();
+                Response::json(200, &json!(pull_requests))
+            }
+            [
+                "bitbucket",
+                "2.0",
+                "repositories",
+                workspace,
+                slug,
+                "pullrequests",
+            ] => {
+                let repo = format!("{}/{}", workspace, slug);
+                let wanted_state = query_param(&request.query, "state");
+                let pull_requests: Vec<serde_json::Value> = state
+                    .pull_requests
+                    .get(&repo)
+                    .into_iter()
+                    .flatten()
+                    .filter(|pr| match wanted_state.as_deref() {
+                        Some("MERGED") => pr.merged,
+                        _ => !pr.merged,
+                    })
+                    .map(render_bitbucket_pull_request)
+                    .collect();
+                Response::json(200, &json!({ "values": pull_requests }))
+            }
+            [
+                "bitbucket",
+                "2.0",
+                "repositories",
+                workspace,
+                slug,
+                "pullrequests",
+                id,
+            ] => state
+                .pull_requests
+                .get(&format!("{}/{}", workspace, slug))
+                .and_then(|prs| prs.iter().find(|pr| pr.number.to_string() == *id))
+                .map(|pr| Response::json(200, &render_bitbucket_pull_request(pr)))
+                .unwrap_or_else(Response::not_found),
+            [
+                "bitbucket",
+                "2.0",
+                "repositories",
+                workspace,
+                slug,
+                "commit",
+                hash,
+            ] => {
+                bitbucket_commit_response(state.repos.get(&format!("{}/{}", workspace, slug)), hash)
+            }
+            ["circleci", "v2", "project", _vcs, org, repo, "pipeline"] => {
+                // Newest first, like CircleCI
+                let pipelines: Vec<serde_json::Value> = state
+                    .pull_requests
+                    .get(&format!("{}/{}", org, repo))
+                    .into_iter()
+                    .flatten()
+                    .rev()
+                    .map(|pr| {
+                        json!({
+                            "id": format!("pipeline-{}", pr.number),
+                            "number": pr.number,
+                            "state": "created",
+                            "vcs": { "branch": pr.head_ref, "revision": pr.head_sha },
+                        })
+                    })
+                    .collect();
+                Response::json(200, &json!({ "items": pipelines, "next_page_token": null }))
+            }
+            _ => Response::not_found(),
+        });
+    }
+
+    for (repo_path, dir) in &state.repos {
+        if let Some(rest) = request.path.strip_prefix(&format!("/{}.git", repo_path))
+            && (rest.is_empty() || rest.starts_with('/'))
+        {
+            let is_push = rest.ends_with("/git-receive-pack")
+                || query_param(&request.query, "service").as_deref() == Some("git-receive-pack");
+            if state.deny_push && is_push {
+                return Route::Api(Response::json(403, &json!({ "message": "403 Forbidden" })));
+            }
+            return Route::Git(dir.clone(), rest.to_string());
+        }
+    }
+    Route::Api(Response::not_found())
+}
+
+/// The endpoints a GitHub App calls with its JWT, which bypass [`MockForge::require_token`]
+fn github_app_route(
+    request: &Request,
+    state: &mut ForgeState,
+    segments: &[&str],
+) -> Option<Response> {
+    let (app_id, installation_id) = state.github_app.clone()?;
+    let is_app_route = matches!(
+        segments,
+        ["v3", "repos", _, _, "installation"] | ["v3", "app", "installations", _, "access_tokens"]
+    );
+    if !is_app_route {
+        return None;
+    }
+    if !is_app_jwt(&request.headers, &app_id) {
+        return Some(Response::json(
+            401,
+            &json!({ "message": "A JSON web token could not be decoded" }),
+        ));
+    }
+    Some(match segments {
+        ["v3", "repos", _, _, "installation"] => {
+            Response::json(200, &json!({ "id": installation_id, "app_id": app_id }))
+        }
+        ["v3", "app", "installations", id, "access_tokens"]
+            if request.method == "POST" && *id == installation_id.to_string() =>
+        {
+            let token = format!("ghs_mock{}", state.installation_tokens.len() + 1);
+            state.installation_tokens.push(token.clone());
+            let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
+            Response::json(
+                201,
+                &json!({ "token": token, "expires_at": expires_at.to_rfc3339() }),
+            )
+        }
+        _ => Response::not_found(),
+    })
+}
+
+/// Gerrit's REST endpoints; None for other paths, such as its git repositories
+fn gerrit_route(request: &Request, state: &ForgeState, path: &str) -> Option<Response> {
+    let (authenticated, path) = match path.strip_prefix("a/") {
+        Some(path) => (true, path),
+        None => (false, path),
+    };
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    if !matches!(
+        segments.as_slice(),
+        ["changes"] | ["projects", _, "commits", _]
+    ) {
+        return None;
+    }
+    if let Some(required) = &state.required_token
+        && !(authenticated && is_authorized(&request.headers, required))
+    {
+        return Some(Response::json(401, &json!({ "message": "Unauthorized" })));
+    }
+
+    let body = match segments.as_slice() {
+        ["changes"] => {
+            let query = query_param(&request.query, "q").unwrap_or_default();
+            let mut commit = None;
+            let mut project = None;
+            for term in query.split('+') {
+                if let Some(sha) = term.strip_prefix("commit:") {
+                    commit = Some(sha.to_string());
+                } else if let Some(name) = term.strip_prefix("project:") {
+                    project = Some(name.replace("%2F", "/").replace("%2f", "/"));
+                }
+            }
+            let changes: Vec<serde_json::Value> = state
+                .gerrit_changes
+                .iter()
+                .filter(|change| project.as_ref().is_none_or(|p| *p == change.project))
+                .filter(|change| {
+                    commit
+                        .as_ref()
+                        .is_none_or(|sha| change.patch_sets.iter().any(|(s, _)| s == sha))
+                })
+                .map(render_gerrit_change)
+                .collect();
+            json!(changes)
+        }
+        ["projects", project, "commits", sha] => {
+            let project = project.replace("%2F", "/").replace("%2f", "/");
+            let Some(dir) = state.repos.get(&format!("gerrit/{}", project)) else {
+                return Some(Response::not_found());
+            };
+            let output = Command::new(config::Config::get().git_cmd())
+                .arg("-C")
+                .arg(dir)
+                .args(["rev-parse", &format!("{}^@", sha)])
+                .stderr(Stdio::null())
+                .output();
+            match output {
+                Ok(output) if output.status.success() => {
+                    let parents: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .map(|parent| json!({ "commit": parent }))
+                        .collect();
+                    json!({ "commit": sha, "parents": parents })
+                }
+                _ => return Some(Response::not_found()),
+            }
+        }
+        _ => return None,
+    };
+    // Gerrit guards its JSON against cross-site script inclusion
+    Some(Response {
+        status: 200,
+        headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+        body: format!(")]}}'\n{}", body).into_bytes(),
+    })
+}
+
+/// CodeCommit's JSON actions, dispatched on `X-Amz-Target`. Only checks the signature is
+/// present and, with [`MockForge::require_token`], made with that access key.
+fn codecommit_route(request: &Request, state: &ForgeState) -> Response {
+    let error = |status: u16, kind: &str| Response::json(status, &json!({ "__type": kind }));
+    let Some(credential) = request
+        .headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("AWS4-HMAC-SHA256 Credential="))
+        .filter(|_| request.headers.contains_key("x-amz-date"))
+    else {
+        return error(403, "MissingAuthenticationTokenException");
+    };
+    if let Some(required) = &state.required_token
+        && credential.split('/').next() != Some(required.as_str())
+    {
+        return error(403, "UnrecognizedClientException");
+    }
+    let Ok(body) = serde_json::from_slice::<serde_json::Value>(&request.body) else {
+        return error(400, "SerializationException");
+    };
+    let field = |name: &str| body.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+
+    match request.headers.get("x-amz-target").map(String::as_str) {
+        Some("CodeCommit_20150413.ListPullRequests") => {
+            // Newest first, like CodeCommit
+            let ids: Vec<String> = state
+                .pull_requests
+                .get(field("repositoryName"))
+                .into_iter()
+                .flatten()
+                .rev()
+                .filter(|pr| match field("pullRequestStatus") {
+                    "CLOSED" => pr.merged,
+                    "OPEN" => !pr.merged,
+                    _ => true,
+                })
+                .map(|pr| pr.number.to_string())
+                .collect();
+            let start: usize = field("nextToken").parse().unwrap_or(0);
+            let page_size = body
+                .get("maxResults")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(100) as usize;
+            let end = (start + page_size).min(ids.len());
+            Response::json(
+                200,
+                &json!({
+                    "pullRequestIds": ids.get(start..end).unwrap_or_default(),
+                    "nextToken": (end < ids.len()).then(|| end.to_string()),
+                }),
+            )
+        }
+        Some("CodeCommit_20150413.GetPullRequest") => state
+            .pull_requests
+            .iter()
+            .find_map(|(repository, prs)| {
+                let pr = prs
+                    .iter()
+                    .find(|pr| pr.number.to_string() == field("pullRequestId"))?;
+                Some(render_codecommit_pull_request(repository, pr))
+            })
+            .map(|pr| Response::json(200, &json!({ "pullRequest": pr })))
+            .unwrap_or_else(|| error(400, "PullRequestDoesNotExistException")),
+        _ => error(400, "InvalidActionException"),
+    }
+}
+
+fn render_codecommit_pull_request(repository: &str, pr: &MockPullRequest) -> serde_json::Value {
+    json!({
+        "pullRequestId": pr.number.to_string(),
+        "title": pr.title,
+        "pullRequestStatus": if pr.merged { "CLOSED" } else { "OPEN" },
+        "pullRequestTargets": [{
+            "repositoryName": repository,
+            "sourceReference": format!("refs/heads/{}", pr.head_ref),
+            "destinationReference": format!("refs/heads/{}", pr.base_ref),
+            "sourceCommit": pr.head_sha,
+            "destinationCommit": pr.base_sha,
+            "mergeMetadata": {
+                "isMerged": pr.merged,
+                "mergeCommitId": pr.merge_commit_sha,
+                "mergeOption": pr.merged.then_some("SQUASH_MERGE"),
+            },
+        }],
+    })
+}
+
+fn render_gerrit_change(change: &MockGerritChange) -> serde_json::Value {
+    let revisions: serde_json::Map<String, serde_json::Value> = change
+        .patch_sets
+        .iter()
+        .enumerate()
+        .map(|(i, (sha, kind))| {
+            (
+                sha.clone(),
+                json!({
+                    "_number": i + 1,
+                    "kind": kind,
+                    "ref": format!(
+                        "refs/changes/{:02}/{}/{}",
+                        change.number % 100,
+                        change.number,
+                        i + 1
+                    ),
+                }),
+            )
+        })
+        .collect();
+    json!({
+        "id": format!("{}~{}~{}", change.project.replace('/', "%2F"), change.branch, change.change_id),
+        "project": change.project,
+        "branch": change.branch,
+        "change_id": change.change_id,
+        "subject": change.subject,
+        "status": change.status,
+        "_number": change.number,
+        "current_revision": change.patch_sets.last().map(|(sha, _)| sha),
+        "revisions": revisions,
+    })
+}
+
+fn oidc_route(request: &Request, state: &mut ForgeState, path: &str) -> Response {
+    let bearer = request
+        .headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match path {
+        "github-id-token" => {
+            if bearer != state.oidc_request_token.as_deref() {
+                return Response::json(401, &json!({ "message": "Bad credentials" }));
+            }
+            let claims = json!({
+                "iss": "https://token.actions.githubusercontent.com",
+                "aud": query_param(&request.query, "audience"),
+                "exp": chrono::Utc::now().timestamp() + 300,
+            });
+            let jwt = format!(
+                "{}.{}.mock-signature",
+                URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#),
+                URL_SAFE_NO_PAD.encode(claims.to_string())
+            );
+            Response::json(200, &json!({ "value": jwt }))
+        }
+        "exchange" if request.method == "POST" => {
+            if bearer
+                .map(jwt_claims)
+                .and_then(|claims| claims.get("iss").cloned())
+                .is_none()
+            {
+                return Response::json(401, &json!({ "error": "invalid identity token" }));
+            }
+            let token = format!("oidc-exchanged{}", state.exchanged_tokens.len() + 1);
+            state.exchanged_tokens.push(token.clone());
+            Response::json(200, &json!({ "token": token }))
+        }
+        _ => Response::not_found(),
+    }
+}
+
+fn jwt_claims(jwt: &str) -> serde_json::Value {
+    jwt.split('.')
+        .nth(1)
+        .and_then(|claims| URL_SAFE_NO_PAD.decode(claims).ok())
+        .and_then(|claims| serde_json::from_slice::<serde_json::Value>(&claims).ok())
+        .unwrap_or_default()
+}
+
+/// Whether the request carries an unexpired `Bearer` JWT issued by `app_id`
+fn is_app_jwt(headers: &BTreeMap<String, String>, app_id: &str) -> bool {
+    let Some(jwt) = headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    let claims = jwt_claims(jwt);
+    let issuer = match &claims["iss"] {
+        serde_json::Value::String(iss) => iss.clone(),
+        other => other.to_string(),
+    };
+    issuer == app_id
+        && claims["exp"]
+            .as_i64()
+            .is_some_and(|exp| exp > chrono::Utc::now().timestamp())
+}
+
+fn is_authorized(headers: &BTreeMap<String, String>, token: &str) -> bool {
+    ["private-token", "job-token", "circle-token"]
+        .iter()
+        .any(|name| headers.get(*name).is_some_and(|value| value == token))
+        || headers.get("authorization").is_some_and(|value| {
+            value.split_once(' ').is_some_and(|(scheme, credential)| {
+                credential == token
+                    || (scheme == "Basic"
+                        && STANDARD
+                            .decode(credential)
+                            .ok()
+                            .and_then(|decoded| String::from_utf8(decoded).ok())
+                            .is_some_and(|decoded| {
+                                decoded
+                                    .split_once(':')
+                                    .is_some_and(|(_, password)| password == token)
+                            }))
+            })
+        })
+}
+
+/// GitLab accepts a project's numeric id or its URL-encoded path; resolve either to
+/// the path when the id is known
+fn resolve_project(state: &ForgeState, project: &str) -> String {
+    let project = project.replace("%2F", "/").replace("%2f", "/");
+    state.project_ids.get(&project).cloned().unwrap_or(project)
+}
+
+fn project_merge_requests<'a>(state: &'a ForgeState, project: &str) -> &'a [MockMergeRequest] {
+    let decoded = project.replace("%2F", "/").replace("%2f", "/");
+    state
+        .merge_requests
+        .get(&decoded)
+        .or_else(|| state.merge_requests.get(&resolve_project(state, project)))
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// A commit's API representation, if the repository has it
+fn commit_response(dir: Option<&PathBuf>, sha: &str) -> Response {
+    let Some(dir) = dir else {
+        return Response::not_found();
+    };
+    let exists = Command::new(config::Config::get().git_cmd())
+        .arg("-C")
+        .arg(dir)
+        .args(["cat-file", "-e", &format!("{}^{{commit}}", sha)])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if exists {
+        Response::json(200, &json!({ "id": sha, "sha": sha }))
+    } else {
+        Response::not_found()
+    }
+}
+
+/// One page of a GitLab list, with the offset pagination headers GitLab sends
+fn gitlab_page<T: Serialize>(request: &Request, items: &[T]) -> Response {
+    let per_page: usize = query_param(&request.query, "per_page")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20)
+        .max(1);
+    let page: usize = query_param(&request.query, "page")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1)
+        .max(1);
+    let start = ((page - 1) * per_page).min(items.len());
+    let end = (start + per_page).min(items.len());
+    let mut response = Response::json(200, &json!(&items[start..end]));
+    let next_page = if end < items.len() {
+        (page + 1).to_string()
+    } else {
+        String::new()
+    };
+    response.headers.extend([
+        ("X-Page".to_string(), page.to_string()),
+        ("X-Per-Page".to_string(), per_page.to_string()),
+        ("X-Next-Page".to_string(), next_page),
+        ("X-Total".to_string(), items.len().to_string()),
+    ]);
+    response
+}
+
+/// The `body` of a JSON request body, as comment endpoints take
+fn body_field(request: &Request) -> serde_json::Value {
+    serde_json::from_slice::<serde_json::Value>(&request.body)
+        .map(|value| value["body"].clone())
+        .unwrap_or_default()
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+fn render_pull_request(base_url: &str, repo: &str, pr: &MockPullRequest) -> serde_json::Value {
+    let repository = json!({
+        "full_name": repo,
+        "clone_url": format!("{}/{}.git", base_url, repo),
+    });
+    json!({
+        "number": pr.number,
+        "title": pr.title,
+        "state": if pr.merged { "closed" } else { "open" },
+        "merged": pr.merged,
+        "merged_at": pr.merged.then_some("2026-01-01T00:00:00Z"),
+        "merge_commit_sha": pr.merge_commit_sha,
+        "head": { "ref": pr.head_ref, "sha": pr.head_sha, "repo": repository },
+        "base": { "ref": pr.base_ref, "sha": pr.base_sha, "repo": repository },
+    })
+}
+
+/// A pull request in the Bitbucket API's shape, which abbreviates nested hashes
+fn render_bitbucket_pull_request(pr: &MockPullRequest) -> serde_json::Value {
+    let short = |sha: &str| json!({ "hash": &sha[..sha.len().min(12)] });
+    json!({
+        "id": pr.number,
+        "title": pr.title,
+        "state": if pr.merged { "MERGED" } else { "OPEN" },
+        "source": { "branch": { "name": pr.head_ref }, "commit": short(&pr.head_sha) },
+        "destination": { "branch": { "name": pr.base_ref }, "commit": short(&pr.base_sha) },
+        "merge_commit": pr.merge_commit_sha.as_deref().map(short),
+    })
+}
+
+/// A commit in the Bitbucket API's shape, with its full hash, if the repository has it
+fn bitbucket_commit_response(dir: Option<&PathBuf>, hash: &str) -> Response {
+    let Some(dir) = dir else {
+        return Response::not_found();
+    };
+    let output = Command::new(config::Config::get().git_cmd())
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", hash),
+        ])
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => Response::json(
+            200,
+            &json!({ "hash": String::from_utf8_lossy(&output.stdout).trim() }),
+        ),
+        _ => Response::not_found(),
+    }
+}
+
+/// Serve a git smart HTTP request from `dir` by running `git http-backend` as a CGI
+fn git_http_backend(dir: &Path, path_info: &str, request: &Request) -> Response {
+    let mut command = Command::new(config::Config::get().git_cmd());
+    command
+        .arg("http-backend")
+        .env("GIT_PROJECT_ROOT", dir)
+        .env("GIT_HTTP_EXPORT_ALL", "1")
+        // Serve partial clones, as GitLab and GitHub do
+        .env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", "uploadpack.allowFilter")
+        .env("GIT_CONFIG_VALUE_0", "true")
+        .env("PATH_INFO", path_info)
+        .env("QUERY_STRING", &request.query)
+        .env("REQUEST_METHOD", &request.method)
+        .env("CONTENT_LENGTH", request.body.len().to_string())
+        // http-backend only accepts pushes from an authenticated user
+        .env("REMOTE_USER", "git-ai-mock-forge")
+        .env("REMOTE_ADDR", "127.0.0.1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    for (header, variable) in [
+        ("content-type", "CONTENT_TYPE"),
+        ("content-encoding", "HTTP_CONTENT_ENCODING"),
+        ("git-protocol", "GIT_PROTOCOL"),
+    ] {
+        if let Some(value) = request.headers.get(header) {
+            command.env(variable, value);
+        }
+    }
+
+    let output = command.spawn().and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&request.body)?;
+        }
+        child.wait_with_output()
+    });
+    let Ok(output) = output else {
+        return Response::json(500, &json!({ "message": "git http-backend failed" }));
+    };
+    parse_cgi_output(&output.stdout)
+}
+
+fn parse_cgi_output(output: &[u8]) -> Response {
+    let (head, body) = match output.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(end) => (&output[..end], &output[end + 4..]),
+        None => match output.windows(2).position(|w| w == b"\n\n") {
+            Some(end) => (&output[..end], &output[end + 2..]),
+            None => (output, &[][..]),
+        },
+    };
+
+    let mut status = 200;
+    let mut headers = Vec::new();
+    for line in String::from_utf8_lossy(head).lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("status") {
+            status = value
+                .split_whitespace()
+                .next()
+                .and_then(|code| code.parse().ok())
+                .unwrap_or(500);
+        } else {
+            headers.push((name.trim().to_string(), value.to_string()));
+        }
+    }
+
+    Response {
+        status,
+        headers,
+        body: body.to_vec(),
+    }
+}
@@ -1,3 +1,44 @@
+pub mod annotations;
+pub mod attribution_artifact;
+pub mod aws;
+pub mod backfill;
+pub mod bitbucket;
+pub mod branch_match;
 pub mod ci_context;
+pub mod circleci;
+pub mod codebuild;
+pub mod comment;
+pub mod credentials;
+pub mod deadline;
+pub mod generic;
+pub mod gerrit;
+pub mod gitea;
 pub mod github;
+pub mod github_app;
 pub mod gitlab;
+pub mod gitlab_scopes;
+pub mod handoff;
+pub mod http;
+pub mod jenkins;
+// Helpers here are for tests built on the library; the binary doesn't use all of them
+#[allow(dead_code)]
+pub mod mock_forge;
+pub mod oidc;
+pub mod provenance;
+pub mod selftest;
+pub mod sweep;
+
+use crate::error::GitAiError;
+
+/// Read `name` from the environment, treating an empty or blank value as unset, the way
+/// CI systems leave variables they don't populate
+pub fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// [`env`], failing when the variable is unset or blank
+pub fn required_env(name: &str) -> Result<String, GitAiError> {
+    env(name).ok_or_else(|| GitAiError::Generic(format!("{} environment variable not set", name)))
+}
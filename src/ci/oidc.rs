@@ -0,0 +1,177 @@
+//! Exchanging the CI job's OIDC token for forge API credentials, so pipelines don't need
+//! a long-lived token in their variables.
+//!
+//! Neither forge accepts its own OIDC tokens on its API, so the exchange goes through a
+//! service the organization runs (set in `GIT_AI_OIDC_EXCHANGE_URL`): it verifies the
+//! job's identity token against the issuer's JWKS and answers with a short-lived forge
+//! token. The contract is a `POST` with the identity token as a `Bearer` credential and a
+//! JSON body naming the provider, answered with `{"token": "..."}` (`access_token` is
+//! accepted too, for OAuth-style services).
+//!
+//! The identity token comes from:
+//! - GitLab: an `id_tokens` entry named [`GITLAB_ID_TOKEN_VAR`] in the job definition
+//! - GitHub: the Actions token endpoint (`ACTIONS_ID_TOKEN_REQUEST_URL`), which needs
+//!   `permissions: id-token: write`
+//!
+//! The exchanged token is exported in [`OIDC_TOKEN_ENV_VAR`], which the providers try
+//! before any other token.
+
+use crate::ci::env;
+use crate::ci::http;
+use crate::error::GitAiError;
+use crate::observability::timings::{self, Phase};
+
+/// Environment variable the exchanged forge token is exported in
+pub const OIDC_TOKEN_ENV_VAR: &str = "GIT_AI_OIDC_TOKEN";
+
+/// Name of the GitLab ID token to declare under the job's `id_tokens`
+pub const GITLAB_ID_TOKEN_VAR: &str = "GIT_AI_ID_TOKEN";
+
+const EXCHANGE_URL_ENV_VAR: &str = "GIT_AI_OIDC_EXCHANGE_URL";
+const AUDIENCE_ENV_VAR: &str = "GIT_AI_OIDC_AUDIENCE";
+
+/// Exchange the GitLab job's ID token, if an exchange service is configured. Returns
+/// whether a token was exported.
+pub fn exchange_gitlab_id_token() -> Result<bool, GitAiError> {
+    let Some(exchange_url) = env(EXCHANGE_URL_ENV_VAR) else {
+        return Ok(false);
+    };
+    let id_token = env(GITLAB_ID_TOKEN_VAR).ok_or_else(|| {
+        GitAiError::Generic(format!(
+            "{} is set but the job has no {} ID token; add it under `id_tokens` in .gitlab-ci.yml",
+            EXCHANGE_URL_ENV_VAR, GITLAB_ID_TOKEN_VAR
+        ))
+    })?;
+    exchange(&exchange_url, "gitlab", &id_token)?;
+    Ok(true)
+}
+
+/// Request the GitHub Actions OIDC token and exchange it, if an exchange service is
+/// configured. Returns whether a token was exported.
+pub fn exchange_github_oidc_token() -> Result<bool, GitAiError> {
+    let Some(exchange_url) = env(EXCHANGE_URL_ENV_VAR) else {
+        return Ok(false);
+    };
+    let (Some(request_url), Some(request_token)) = (
+        env("ACTIONS_ID_TOKEN_REQUEST_URL"),
+        env("ACTIONS_ID_TOKEN_REQUEST_TOKEN"),
+    ) else {
+        return Err(GitAiError::Generic(format!(
+            "{} is set but the job can't request an OIDC token; grant the workflow `permissions: id-token: write`",
+            EXCHANGE_URL_ENV_VAR
+        )));
+    };
+
+    let url = github_id_token_url(&request_url, env(AUDIENCE_ENV_VAR).as_deref());
+    let body = send(
+        http::get(&url).with_header("Authorization", format!("Bearer {}", request_token)),
+        "GitHub OIDC token request",
+    )?;
+    let id_token = body
+        .get("value")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| GitAiError::Generic("GitHub returned no OIDC token".to_string()))?;
+    exchange(&exchange_url, "github", id_token)?;
+    Ok(true)
+}
+
+/// The Actions token endpoint already carries a query string; the audience is appended
+fn github_id_token_url(request_url: &str, audience: Option<&str>) -> String {
+    match audience {
+        Some(audience) => {
+            let separator = if request_url.contains('?') { '&' } else { '?' };
+            let encoded: String =
+                url::form_urlencoded::byte_serialize(audience.as_bytes()).collect();
+            format!("{}{}audience={}", request_url, separator, encoded)
+        }
+        None => request_url.to_string(),
+    }
+}
+
+fn exchange(exchange_url: &str, provider: &str, id_token: &str) -> Result<(), GitAiError> {
+    let request = http::post(exchange_url)
+        .with_header("Authorization", format!("Bearer {}", id_token))
+        .with_header("Content-Type", "application/json")
+        .with_body(serde_json::json!({ "provider": provider }).to_string());
+    let body = send(request, "OIDC token exchange")?;
+    let token = exchanged_token(&body).ok_or_else(|| {
+        GitAiError::Generic(format!(
+            "OIDC token exchange at {} returned no token",
+            exchange_url
+        ))
+    })?;
+
+    // Actions only masks secrets it knows about, and this one was just minted
+    if std::env::var_os("GITHUB_ACTIONS").is_some() {
+        println!("::add-mask::{}", token);
+    }
+    // SAFETY: set before any git subprocess or helper thread is started
+    unsafe {
+        std::env::set_var(OIDC_TOKEN_ENV_VAR, token);
+    }
+    println!(
+        "[{} CI] Exchanged the job's OIDC token for a forge token",
+        if provider == "gitlab" {
+            "GitLab"
+        } else {
+            "GitHub"
+        }
+    );
+    Ok(())
+}
+
+fn exchanged_token(body: &serde_json::Value) -> Option<&str> {
+    ["token", "access_token"]
+        .iter()
+        .find_map(|field| body.get(*field).and_then(|t| t.as_str()))
+        .filter(|token| !token.is_empty())
+}
+
+fn send(request: http::Request, what: &str) -> Result<serde_json::Value, GitAiError> {
+    let _timing = timings::phase(Phase::Api);
+    let request = request.with_header(
+        "User-Agent",
+        format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+    );
+    let response = http::send(request, what)?;
+    let body = response.as_str().unwrap_or("");
+    if !(200..300).contains(&response.status_code) {
+        return Err(GitAiError::Generic(format!(
+            "{} returned status {}: {}",
+            what, response.status_code, body
+        )));
+    }
+    serde_json::from_str(body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse {} response: {}", what, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_github_id_token_url_appends_audience() {
+        let base = "https://token.actions.githubusercontent.com/abc?api-version=2.0";
+        assert_eq!(github_id_token_url(base, None), base);
+        assert_eq!(
+            github_id_token_url(base, Some("https://sts.example.com")),
+            "https://token.actions.githubusercontent.com/abc?api-version=2.0&audience=https%3A%2F%2Fsts.example.com"
+        );
+        assert_eq!(
+            github_id_token_url("https://example.com/token", Some("git-ai")),
+            "https://example.com/token?audience=git-ai"
+        );
+    }
+
+    #[test]
+    fn test_exchanged_token_fields() {
+        assert_eq!(exchanged_token(&json!({ "token": "abc" })), Some("abc"));
+        assert_eq!(
+            exchanged_token(&json!({ "access_token": "def", "expires_in": 3600 })),
+            Some("def")
+        );
+        assert_eq!(exchanged_token(&json!({ "token": "" })), None);
+        assert_eq!(exchanged_token(&json!({ "error": "denied" })), None);
+    }
+}
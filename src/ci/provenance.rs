@@ -0,0 +1,478 @@
+use crate::authorship::authorship_log_serialization::NoteProvenance;
+use crate::config::ProvenanceIdentity;
+use crate::error::GitAiError;
+use crate::git::refs::{get_authorship, notes_add};
+use crate::git::repository::{Repository, exec_git, exec_git_stdin};
+
+const NOTES_REF: &str = "refs/notes/ai";
+
+/// Environment variable selecting how CI signs the notes commit it pushes
+pub const SIGN_ENV_VAR: &str = "GIT_AI_CI_SIGN";
+
+/// Environment variable overriding the pipeline URL recorded in rewritten notes
+pub const PIPELINE_URL_ENV_VAR: &str = "GIT_AI_CI_PIPELINE_URL";
+
+/// How the notes commit pushed from CI is signed, from `GIT_AI_CI_SIGN`:
+///
+/// - `ssh:<private key file>` signs with an SSH key the CI system holds
+/// - `gpg` or `gpg:<key id>` signs with a GPG key in the job's keyring
+/// - `sigstore` signs keylessly with gitsign, using the job's OIDC identity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteSigner {
+    Ssh(String),
+    Gpg(Option<String>),
+    Sigstore,
+}
+
+impl NoteSigner {
+    pub fn parse(value: &str) -> Result<Self, GitAiError> {
+        let value = value.trim();
+        let (kind, key) = match value.split_once(':') {
+            Some((kind, key)) => (kind, Some(key.trim()).filter(|key| !key.is_empty())),
+            None => (value, None),
+        };
+        match (kind, key) {
+            ("ssh", Some(key_file)) => Ok(NoteSigner::Ssh(key_file.to_string())),
+            ("ssh", None) => Err(GitAiError::Generic(format!(
+                "{}=ssh needs a key file, e.g. ssh:/path/to/key",
+                SIGN_ENV_VAR
+            ))),
+            ("gpg", key_id) => Ok(NoteSigner::Gpg(key_id.map(str::to_string))),
+            ("sigstore" | "gitsign", None) => Ok(NoteSigner::Sigstore),
+            _ => Err(GitAiError::Generic(format!(
+                "Unrecognized {} value '{}' (expected ssh:<key file>, gpg[:<key id>] or sigstore)",
+                SIGN_ENV_VAR, value
+            ))),
+        }
+    }
+
+    /// The signer `GIT_AI_CI_SIGN` asks for, None when it's unset
+    pub fn from_env() -> Result<Option<Self>, GitAiError> {
+        match std::env::var(SIGN_ENV_VAR) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(&value).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Name recorded in the note's provenance
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoteSigner::Ssh(_) => "ssh",
+            NoteSigner::Gpg(_) => "gpg",
+            NoteSigner::Sigstore => "sigstore",
+        }
+    }
+
+    /// `-c` options and the `-S` flag that make `git commit-tree` sign this way
+    fn commit_tree_args(&self) -> Vec<String> {
+        let config = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .flat_map(|(key, value)| ["-c".to_string(), format!("{}={}", key, value)])
+                .collect::<Vec<_>>()
+        };
+        let mut args = match self {
+            NoteSigner::Ssh(key_file) => config(&[
+                ("gpg.format", "ssh"),
+                ("user.signingkey", key_file.as_str()),
+            ]),
+            NoteSigner::Gpg(Some(key_id)) => {
+                config(&[("gpg.format", "openpgp"), ("user.signingkey", key_id)])
+            }
+            NoteSigner::Gpg(None) => config(&[("gpg.format", "openpgp")]),
+            NoteSigner::Sigstore => {
+                config(&[("gpg.format", "x509"), ("gpg.x509.program", "gitsign")])
+            }
+        };
+        args.push("commit-tree".to_string());
+        args.push("-S".to_string());
+        args
+    }
+}
+
+/// The CI run the current process belongs to, from the variables each CI system sets.
+/// `GIT_AI_CI_PIPELINE_URL` overrides the detected URL, and is the only source for
+/// systems git-ai doesn't recognize.
+pub fn detect_provenance(env: impl Fn(&str) -> Option<String>) -> NoteProvenance {
+    let env = |name: &str| env(name).filter(|v| !v.trim().is_empty());
+    let (ci, pipeline_url) = if env("GITEA_ACTIONS").is_some() {
+        ("gitea-actions", github_run_url(&env))
+    } else if env("GITHUB_ACTIONS").is_some() {
+        ("github-actions", github_run_url(&env))
+    } else if env("GITLAB_CI").is_some() {
+        ("gitlab-ci", env("CI_PIPELINE_URL"))
+    } else if let Some(build) = env("BITBUCKET_BUILD_NUMBER") {
+        (
+            "bitbucket-pipelines",
+            env("BITBUCKET_REPO_FULL_NAME")
+                .map(|repo| format!("https://bitbucket.org/{}/pipelines/results/{}", repo, build)),
+        )
+    } else if env("CIRCLECI").is_some() {
+        ("circleci", env("CIRCLE_BUILD_URL"))
+    } else if env("CODEBUILD_BUILD_ID").is_some() {
+        ("codebuild", env("CODEBUILD_BUILD_URL"))
+    } else if env("JENKINS_URL").is_some() {
+        ("jenkins", env("BUILD_URL"))
+    } else {
+        ("ci", None)
+    };
+    NoteProvenance {
+        ci: ci.to_string(),
+        pipeline_url: env(PIPELINE_URL_ENV_VAR).or(pipeline_url),
+        signed_with: None,
+    }
+}
+
+fn github_run_url(env: &impl Fn(&str) -> Option<String>) -> Option<String> {
+    Some(format!(
+        "{}/{}/actions/runs/{}",
+        env("GITHUB_SERVER_URL")?.trim_end_matches('/'),
+        env("GITHUB_REPOSITORY")?,
+        env("GITHUB_RUN_ID")?
+    ))
+}
+
+/// Record `provenance` in the notes of `commits`, skipping commits without one
+pub fn stamp_provenance(
+    repo: &Repository,
+    commits: &[String],
+    provenance: &NoteProvenance,
+) -> Result<(), GitAiError> {
+    for commit in commits {
+        let Some(mut log) = get_authorship(repo, commit) else {
+            continue;
+        };
+        log.metadata.provenance = Some(Box::new(provenance.clone()));
+        let content = log.serialize_to_string().map_err(|_| {
+            GitAiError::Generic(format!("Failed to serialize authorship for {}", commit))
+        })?;
+        notes_add(repo, commit, &content)?;
+    }
+    Ok(())
+}
+
+/// Current tip of the notes ref, None before the first note is written
+pub fn notes_tip(repo: &Repository) -> Option<String> {
+    repo.git(&["rev-parse", "--verify", "--quiet", NOTES_REF])
+        .ok()
+        .map(|tip| tip.trim().to_string())
+        .filter(|tip| !tip.is_empty())
+}
+
+/// Replace the notes ref's tip with a signed copy of it: same tree, parents and message
+pub fn sign_notes_tip(repo: &Repository, signer: &NoteSigner) -> Result<(), GitAiError> {
+    replace_notes_tip(repo, signer, None)
+}
+
+/// Fold the notes commits written since `base` (None when the ref didn't exist) into
+/// one signed commit, so the notes they wrote are introduced by a signed commit
+pub fn sign_notes_since(
+    repo: &Repository,
+    signer: &NoteSigner,
+    base: Option<&str>,
+) -> Result<(), GitAiError> {
+    replace_notes_tip(
+        repo,
+        signer,
+        Some(base.into_iter().map(str::to_string).collect()),
+    )
+}
+
+/// Point the notes ref at a signed commit of its tip's tree, with `parents` (the tip's
+/// own when None) and the tip's message
+fn replace_notes_tip(
+    repo: &Repository,
+    signer: &NoteSigner,
+    parents: Option<Vec<String>>,
+) -> Result<(), GitAiError> {
+    // gitsign gets its certificate from Fulcio and logs the signature to Rekor
+    if *signer == NoteSigner::Sigstore {
+        crate::network::ensure_allowed("sign notes with Sigstore")?;
+    }
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "log".to_string(),
+        "-1".to_string(),
+        "--format=%H%x00%T%x00%P%x00%B".to_string(),
+        NOTES_REF.to_string(),
+    ]);
+    let stdout = String::from_utf8(exec_git(&args)?.stdout)?;
+    let fields: Vec<&str> = stdout.splitn(4, '\0').collect();
+    let [tip, tree, tip_parents, message] = fields.as_slice() else {
+        return Err(GitAiError::Generic(format!(
+            "Could not read the tip of {}",
+            NOTES_REF
+        )));
+    };
+    let parents =
+        parents.unwrap_or_else(|| tip_parents.split_whitespace().map(str::to_string).collect());
+
+    let mut args = repo.global_args_for_exec();
+    args.extend(signer.commit_tree_args());
+    args.push(tree.to_string());
+    for parent in parents {
+        args.push("-p".to_string());
+        args.push(parent);
+    }
+    let output =
+        exec_git_stdin(&args, format!("{}\n", message.trim_end()).as_bytes()).map_err(|e| {
+            GitAiError::Generic(format!(
+                "Failed to sign the notes commit with {}: {}",
+                signer.name(),
+                e
+            ))
+        })?;
+    let signed = String::from_utf8(output.stdout)?.trim().to_string();
+
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "update-ref".to_string(),
+        "-m".to_string(),
+        "git-ai: sign notes".to_string(),
+        NOTES_REF.to_string(),
+        signed,
+        tip.to_string(),
+    ]);
+    exec_git(&args)?;
+    Ok(())
+}
+
+/// How a notes commit is signed, from the armor of its `gpgsig` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKind {
+    Ssh,
+    Gpg,
+    /// X.509, as gitsign signs with a Sigstore certificate
+    Sigstore,
+}
+
+impl SignatureKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignatureKind::Ssh => "ssh",
+            SignatureKind::Gpg => "gpg",
+            SignatureKind::Sigstore => "sigstore",
+        }
+    }
+
+    /// The signature kind of a raw commit object, None when it isn't signed
+    pub fn of_commit(raw_commit: &str) -> Option<Self> {
+        let headers = raw_commit.split("\n\n").next().unwrap_or_default();
+        let armor = headers
+            .lines()
+            .find(|line| line.starts_with("gpgsig ") || line.starts_with("gpgsig-sha256 "))?
+            .split_once(' ')?
+            .1
+            .trim();
+        Some(match armor {
+            "-----BEGIN SSH SIGNATURE-----" => SignatureKind::Ssh,
+            "-----BEGIN PGP SIGNATURE-----" => SignatureKind::Gpg,
+            _ => SignatureKind::Sigstore,
+        })
+    }
+}
+
+/// Paths a commit's note can have in the notes tree, which git fans out into
+/// directories as the number of notes grows
+fn note_paths(commit_sha: &str) -> Vec<String> {
+    let mut paths = vec![commit_sha.to_string()];
+    if commit_sha.len() > 4 {
+        paths.push(format!("{}/{}", &commit_sha[..2], &commit_sha[2..]));
+        paths.push(format!(
+            "{}/{}/{}",
+            &commit_sha[..2],
+            &commit_sha[2..4],
+            &commit_sha[4..]
+        ));
+    }
+    paths
+}
+
+/// The notes commit that wrote a commit's current note, None when it has no note. Notes
+/// merges that kept one side's note are skipped over, so this is the commit that
+/// introduced the note's content.
+pub fn note_introduced_by(
+    repo: &Repository,
+    commit_sha: &str,
+) -> Result<Option<String>, GitAiError> {
+    let mut args = vec!["log", "-1", "--format=%H", NOTES_REF, "--"];
+    let paths = note_paths(commit_sha);
+    args.extend(paths.iter().map(String::as_str));
+    let introduced_by = repo.git(&args)?.trim().to_string();
+    Ok(Some(introduced_by).filter(|sha| !sha.is_empty()))
+}
+
+/// Check a Sigstore-signed commit with gitsign, against any of the trusted `identities`
+/// (OIDC issuer and a regex the certificate's subject must match). Returns the
+/// identity that verified, or why none did.
+pub fn verify_sigstore_commit(
+    repo: &Repository,
+    commit_sha: &str,
+    identities: &[ProvenanceIdentity],
+) -> Result<ProvenanceIdentity, String> {
+    if identities.is_empty() {
+        return Err("no trusted identities configured".to_string());
+    }
+    // gitsign checks the signature against the Rekor transparency log
+    crate::network::ensure_allowed("verify a Sigstore signature").map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().map_err(|e| e.to_string())?;
+    let mut last_error = String::new();
+    for identity in identities {
+        let output = std::process::Command::new("gitsign")
+            .args(gitsign_verify_args(identity, commit_sha))
+            .current_dir(&workdir)
+            .output()
+            .map_err(|e| format!("could not run gitsign: {}", e))?;
+        if output.status.success() {
+            return Ok(identity.clone());
+        }
+        last_error = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("gitsign verify failed")
+            .trim()
+            .to_string();
+    }
+    Err(format!("no trusted identity matched ({})", last_error))
+}
+
+fn gitsign_verify_args(identity: &ProvenanceIdentity, commit_sha: &str) -> Vec<String> {
+    vec![
+        "verify".to_string(),
+        format!("--certificate-oidc-issuer={}", identity.issuer),
+        // Anchored so a subject pattern can't match part of an unrelated identity
+        format!("--certificate-identity-regexp=^(?:{})$", identity.subject),
+        commit_sha.to_string(),
+    ]
+}
+
+/// Check an SSH- or GPG-signed commit against the signers git itself trusts
+/// (`gpg.ssh.allowedSignersFile` or the GPG keyring)
+pub fn verify_git_signed_commit(repo: &Repository, commit_sha: &str) -> Result<(), String> {
+    repo.git(&["verify-commit", commit_sha])
+        .map(|_| ())
+        .map_err(|e| match e {
+            GitAiError::GitCliError { stderr, .. } => stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or("signature did not verify")
+                .trim()
+                .to_string(),
+            other => other.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_signer() {
+        assert_eq!(
+            NoteSigner::parse("ssh:/keys/ci").unwrap(),
+            NoteSigner::Ssh("/keys/ci".to_string())
+        );
+        assert_eq!(NoteSigner::parse("gpg").unwrap(), NoteSigner::Gpg(None));
+        assert_eq!(
+            NoteSigner::parse("gpg:ABCD1234").unwrap(),
+            NoteSigner::Gpg(Some("ABCD1234".to_string()))
+        );
+        assert_eq!(NoteSigner::parse("sigstore").unwrap(), NoteSigner::Sigstore);
+        assert!(NoteSigner::parse("ssh").is_err());
+        assert!(NoteSigner::parse("x509:foo").is_err());
+    }
+
+    #[test]
+    fn test_signature_kind_of_commit() {
+        let signed = |armor: &str| {
+            format!(
+                "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\nauthor CI <ci@example.com> 0 +0000\ncommitter CI <ci@example.com> 0 +0000\ngpgsig {}\n abc\n -----END-----\n\nNotes added by 'git notes add'\n",
+                armor
+            )
+        };
+        assert_eq!(
+            SignatureKind::of_commit(&signed("-----BEGIN SSH SIGNATURE-----")),
+            Some(SignatureKind::Ssh)
+        );
+        assert_eq!(
+            SignatureKind::of_commit(&signed("-----BEGIN PGP SIGNATURE-----")),
+            Some(SignatureKind::Gpg)
+        );
+        assert_eq!(
+            SignatureKind::of_commit(&signed("-----BEGIN SIGNED MESSAGE-----")),
+            Some(SignatureKind::Sigstore)
+        );
+        // A message that merely mentions a signature header isn't a signature
+        assert_eq!(
+            SignatureKind::of_commit(
+                "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\ngpgsig -----BEGIN SSH SIGNATURE-----\n"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_note_paths_and_gitsign_args() {
+        assert_eq!(
+            note_paths("abcdef01"),
+            vec!["abcdef01", "ab/cdef01", "ab/cd/ef01"]
+        );
+        let identity = ProvenanceIdentity {
+            issuer: "https://token.actions.githubusercontent.com".to_string(),
+            subject: "https://github.com/org/repo/.*".to_string(),
+        };
+        assert_eq!(
+            gitsign_verify_args(&identity, "abc"),
+            vec![
+                "verify",
+                "--certificate-oidc-issuer=https://token.actions.githubusercontent.com",
+                "--certificate-identity-regexp=^(?:https://github.com/org/repo/.*)$",
+                "abc",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_provenance() {
+        let detect = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            detect_provenance(|name| vars.get(name).cloned())
+        };
+
+        let github = detect(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_SERVER_URL", "https://github.com/"),
+            ("GITHUB_REPOSITORY", "org/repo"),
+            ("GITHUB_RUN_ID", "42"),
+        ]);
+        assert_eq!(github.ci, "github-actions");
+        assert_eq!(
+            github.pipeline_url.as_deref(),
+            Some("https://github.com/org/repo/actions/runs/42")
+        );
+
+        let gitlab = detect(&[
+            ("GITLAB_CI", "true"),
+            ("CI_PIPELINE_URL", "https://gitlab.com/g/p/-/pipelines/7"),
+        ]);
+        assert_eq!(gitlab.ci, "gitlab-ci");
+        assert_eq!(
+            gitlab.pipeline_url.as_deref(),
+            Some("https://gitlab.com/g/p/-/pipelines/7")
+        );
+
+        let unknown = detect(&[(PIPELINE_URL_ENV_VAR, "https://ci.example.com/build/3")]);
+        assert_eq!(unknown.ci, "ci");
+        assert_eq!(
+            unknown.pipeline_url.as_deref(),
+            Some("https://ci.example.com/build/3")
+        );
+        assert_eq!(detect(&[]).pipeline_url, None);
+    }
+}
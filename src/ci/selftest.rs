@@ -0,0 +1,418 @@
+//! `git-ai ci selftest`: check that the CI token can do everything `ci <provider> run`
+//! needs, without writing anything, and print a pass/fail matrix.
+//!
+//! The checks mirror a real run: list merged merge/pull requests, read the commit being
+//! built, fetch a merge request's head ref, and push notes (as a `--dry-run`, which still
+//! makes the forge authorize a push).
+
+use crate::ci::credentials::{CiGitCredential, git_args_for_dir};
+use crate::ci::github_app::{APP_TOKEN_ENV_VAR, authenticate_app_from_env};
+use crate::ci::gitlab::{
+    GitlabRunOptions, fetch_project_path, gitlab_api_auth, gitlab_git_credential,
+    resolve_gitlab_target,
+};
+use crate::ci::gitlab_scopes::GitlabOperation;
+use crate::ci::http;
+use crate::ci::oidc::{OIDC_TOKEN_ENV_VAR, exchange_github_oidc_token, exchange_gitlab_id_token};
+use crate::ci::required_env;
+use crate::error::GitAiError;
+use crate::git::repository::exec_git;
+use crate::observability::timings::{self, Phase};
+use std::path::PathBuf;
+
+/// Ref the push check targets. The push is a dry run, so it's never created.
+const SELFTEST_NOTES_REF: &str = "refs/notes/git-ai-selftest";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Gitlab,
+    Github,
+}
+
+impl Provider {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "gitlab" => Some(Provider::Gitlab),
+            "github" => Some(Provider::Github),
+            _ => None,
+        }
+    }
+
+    /// The provider whose CI job this is, from the variables each one sets
+    pub fn detect() -> Option<Self> {
+        if std::env::var_os("GITLAB_CI").is_some() {
+            Some(Provider::Gitlab)
+        } else if std::env::var_os("GITHUB_ACTIONS").is_some() {
+            Some(Provider::Github)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckOutcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: CheckOutcome,
+    /// What to fix when the check fails
+    pub hint: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SelftestReport {
+    /// e.g. "GitLab project group/project"
+    pub target: String,
+    /// Variable the token came from
+    pub token_source: String,
+    pub checks: Vec<Check>,
+}
+
+impl SelftestReport {
+    pub fn passed(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| matches!(check.outcome, CheckOutcome::Fail(_)))
+    }
+
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "git-ai ci selftest: {} (token: {})",
+                self.target, self.token_source
+            ),
+            String::new(),
+        ];
+        let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+        for check in &self.checks {
+            let (label, detail) = match &check.outcome {
+                CheckOutcome::Pass(detail) => {
+                    passed += 1;
+                    ("PASS", detail)
+                }
+                CheckOutcome::Fail(detail) => {
+                    failed += 1;
+                    ("FAIL", detail)
+                }
+                CheckOutcome::Skip(detail) => {
+                    skipped += 1;
+                    ("SKIP", detail)
+                }
+            };
+            lines.push(format!("  {}  {:<26} {}", label, check.name, detail));
+            if matches!(check.outcome, CheckOutcome::Fail(_)) {
+                lines.push(format!("        -> {}", check.hint));
+            }
+        }
+        lines.push(String::new());
+        lines.push(format!(
+            "{} passed, {} failed, {} skipped",
+            passed, failed, skipped
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Everything the checks need to know about one forge
+struct ForgeTarget {
+    target: String,
+    token_source: String,
+    api_headers: Vec<(&'static str, String)>,
+    list_url: String,
+    commit_url: String,
+    commit_sha: String,
+    clone_url: String,
+    credential: Option<CiGitCredential>,
+    /// Field holding a merge/pull request's number in list responses
+    number_field: &'static str,
+    /// Ref a merge/pull request's head is fetchable at
+    head_ref: fn(u64) -> String,
+    hints: [String; 4],
+}
+
+pub fn run_selftest(provider: Provider) -> Result<SelftestReport, GitAiError> {
+    let forge = match provider {
+        Provider::Gitlab => gitlab_target()?,
+        Provider::Github => github_target()?,
+    };
+
+    let mut checks = Vec::new();
+    let (list_outcome, latest) = check_list(&forge);
+    checks.push(Check {
+        name: "list merged requests",
+        outcome: list_outcome,
+        hint: forge.hints[0].clone(),
+    });
+    checks.push(Check {
+        name: "read commit",
+        outcome: check_commit(&forge),
+        hint: forge.hints[1].clone(),
+    });
+
+    let scratch = scratch_repo()?;
+    let scratch_dir = scratch.to_string_lossy().to_string();
+    checks.push(Check {
+        name: "fetch merge request ref",
+        outcome: match latest {
+            Some(number) => check_fetch(&forge, &scratch_dir, &(forge.head_ref)(number)),
+            None => CheckOutcome::Skip("no merged request to fetch".to_string()),
+        },
+        hint: forge.hints[2].clone(),
+    });
+    checks.push(Check {
+        name: "push notes (dry run)",
+        outcome: check_push(&forge, &scratch_dir),
+        hint: forge.hints[3].clone(),
+    });
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    Ok(SelftestReport {
+        target: forge.target,
+        token_source: forge.token_source,
+        checks,
+    })
+}
+
+fn gitlab_target() -> Result<ForgeTarget, GitAiError> {
+    let api_url = required_env("CI_API_V4_URL")?;
+    let server_url = required_env("CI_SERVER_URL")?;
+    // Checks the same project a run would, including an upstream pipeline's
+    let target = resolve_gitlab_target(&GitlabRunOptions::default(), |name| {
+        std::env::var(name).ok()
+    })?;
+    exchange_gitlab_id_token()?;
+    let auth = gitlab_api_auth()?;
+    let project_id = target.project_id;
+    let commit_sha = target.commit_sha;
+    let project_path = match target.project_path {
+        Some(path) => path,
+        None => fetch_project_path(&api_url, &project_id, &auth)?,
+    };
+
+    Ok(ForgeTarget {
+        target: format!("GitLab project {}", project_path),
+        token_source: auth.env_var.to_string(),
+        api_headers: vec![(auth.header, auth.token)],
+        list_url: format!(
+            "{}/projects/{}/merge_requests?state=merged&order_by=updated_at&sort=desc&per_page=1",
+            api_url, project_id
+        ),
+        commit_url: format!(
+            "{}/projects/{}/repository/commits/{}",
+            api_url, project_id, commit_sha
+        ),
+        commit_sha,
+        clone_url: format!("{}/{}.git", server_url, project_path),
+        credential: gitlab_git_credential(),
+        number_field: "iid",
+        head_ref: |iid| format!("refs/merge-requests/{}/head", iid),
+        hints: [
+            GitlabOperation::ListMergeRequests.requirement(),
+            GitlabOperation::ReadCommit.requirement(),
+            GitlabOperation::FetchMergeRequestRef.requirement(),
+            GitlabOperation::PushNotes.requirement(),
+        ],
+    })
+}
+
+fn github_target() -> Result<ForgeTarget, GitAiError> {
+    let repository = required_env("GITHUB_REPOSITORY")?;
+    let commit_sha = required_env("GITHUB_SHA")?;
+    let api_url =
+        std::env::var("GITHUB_API_URL").unwrap_or_else(|_| "https://api.github.com".to_string());
+    let server_url =
+        std::env::var("GITHUB_SERVER_URL").unwrap_or_else(|_| "https://github.com".to_string());
+
+    let exchanged = exchange_github_oidc_token()?;
+    let app_credential = if exchanged {
+        None
+    } else {
+        authenticate_app_from_env(&repository)?
+    };
+    let (token_source, token, credential, hints) = match (exchanged, app_credential) {
+        (true, _) => (
+            OIDC_TOKEN_ENV_VAR.to_string(),
+            required_env(OIDC_TOKEN_ENV_VAR)?,
+            CiGitCredential::from_env("x-access-token", OIDC_TOKEN_ENV_VAR),
+            [
+                "the exchanged token needs `pull-requests: read`",
+                "the exchanged token needs `contents: read`",
+                "the exchanged token needs `contents: read`",
+                "the exchanged token needs `contents: write`",
+            ],
+        ),
+        (false, Some(credential)) => (
+            format!("GitHub App {}", required_env("GITHUB_APP_ID")?),
+            required_env(APP_TOKEN_ENV_VAR)?,
+            Some(credential),
+            [
+                "give the GitHub App the `Pull requests: Read` permission",
+                "give the GitHub App the `Contents: Read` permission",
+                "give the GitHub App the `Contents: Read` permission",
+                "give the GitHub App the `Contents: Read and write` permission",
+            ],
+        ),
+        (false, None) => (
+            "GITHUB_TOKEN".to_string(),
+            required_env("GITHUB_TOKEN")?,
+            CiGitCredential::from_env("x-access-token", "GITHUB_TOKEN"),
+            [
+                "grant the workflow `permissions: pull-requests: read`",
+                "grant the workflow `permissions: contents: read`",
+                "grant the workflow `permissions: contents: read`",
+                "grant the workflow `permissions: contents: write`",
+            ],
+        ),
+    };
+
+    Ok(ForgeTarget {
+        target: format!("GitHub repository {}", repository),
+        token_source,
+        api_headers: vec![
+            ("Authorization", format!("Bearer {}", token)),
+            ("Accept", "application/vnd.github+json".to_string()),
+        ],
+        list_url: format!(
+            "{}/repos/{}/pulls?state=closed&sort=updated&direction=desc&per_page=1",
+            api_url, repository
+        ),
+        commit_url: format!("{}/repos/{}/commits/{}", api_url, repository, commit_sha),
+        commit_sha,
+        clone_url: format!("{}/{}.git", server_url, repository),
+        credential,
+        number_field: "number",
+        head_ref: |number| format!("pull/{}/head", number),
+        hints: hints.map(str::to_string),
+    })
+}
+
+/// GET an API endpoint, returning the parsed body of a 200 response
+fn api_get(forge: &ForgeTarget, url: &str) -> Result<serde_json::Value, String> {
+    let _timing = timings::phase(Phase::Api);
+    let mut request = http::get(url).with_header(
+        "User-Agent",
+        format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+    );
+    for (name, value) in &forge.api_headers {
+        request = request.with_header(*name, value);
+    }
+    let response = http::send_once(&request, "API request").map_err(|e| e.to_string())?;
+    let body: serde_json::Value =
+        serde_json::from_str(response.as_str().unwrap_or("null")).unwrap_or_default();
+    if response.status_code != 200 {
+        return Err(match body.get("message").and_then(|m| m.as_str()) {
+            Some(message) => format!("HTTP {}: {}", response.status_code, message),
+            None => format!("HTTP {}", response.status_code),
+        });
+    }
+    Ok(body)
+}
+
+fn check_list(forge: &ForgeTarget) -> (CheckOutcome, Option<u64>) {
+    match api_get(forge, &forge.list_url) {
+        Ok(body) => {
+            let latest = body
+                .as_array()
+                .and_then(|requests| requests.first())
+                .and_then(|request| request.get(forge.number_field))
+                .and_then(|number| number.as_u64());
+            let detail = match latest {
+                Some(number) => format!("latest #{}", number),
+                None => "none merged yet".to_string(),
+            };
+            (CheckOutcome::Pass(detail), latest)
+        }
+        Err(e) => (CheckOutcome::Fail(e), None),
+    }
+}
+
+fn check_commit(forge: &ForgeTarget) -> CheckOutcome {
+    match api_get(forge, &forge.commit_url) {
+        Ok(_) => CheckOutcome::Pass(forge.commit_sha.chars().take(8).collect()),
+        Err(e) => CheckOutcome::Fail(e),
+    }
+}
+
+fn check_fetch(forge: &ForgeTarget, scratch_dir: &str, head_ref: &str) -> CheckOutcome {
+    let mut args = git_args_for_dir(scratch_dir, forge.credential.as_ref());
+    args.extend([
+        "fetch".to_string(),
+        "--quiet".to_string(),
+        "--depth=1".to_string(),
+        forge.clone_url.clone(),
+        head_ref.to_string(),
+    ]);
+    let _timing = timings::phase(Phase::Fetch);
+    match exec_git(&args) {
+        Ok(_) => CheckOutcome::Pass(head_ref.to_string()),
+        Err(e) => CheckOutcome::Fail(git_failure(&e)),
+    }
+}
+
+fn check_push(forge: &ForgeTarget, scratch_dir: &str) -> CheckOutcome {
+    let mut args = git_args_for_dir(scratch_dir, forge.credential.as_ref());
+    args.extend([
+        "push".to_string(),
+        "--dry-run".to_string(),
+        "--quiet".to_string(),
+        forge.clone_url.clone(),
+        format!("HEAD:{}", SELFTEST_NOTES_REF),
+    ]);
+    let _timing = timings::phase(Phase::Push);
+    match exec_git(&args) {
+        Ok(_) => CheckOutcome::Pass("authorized".to_string()),
+        Err(e) => CheckOutcome::Fail(git_failure(&e)),
+    }
+}
+
+/// The line of git's stderr that says what went wrong
+fn git_failure(error: &GitAiError) -> String {
+    match error {
+        GitAiError::GitCliError { stderr, .. } => stderr
+            .lines()
+            .map(str::trim)
+            .rfind(|line| !line.is_empty())
+            .unwrap_or("git failed")
+            .to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// An empty repository with one commit, to fetch into and push from
+fn scratch_repo() -> Result<PathBuf, GitAiError> {
+    let dir = std::env::temp_dir().join(format!(
+        "git-ai-selftest-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    let dir_arg = dir.to_string_lossy().to_string();
+    exec_git(&["init".to_string(), "--quiet".to_string(), dir_arg.clone()])?;
+    let commit = exec_git(&[
+        "-C".to_string(),
+        dir_arg,
+        "-c".to_string(),
+        "user.name=git-ai".to_string(),
+        "-c".to_string(),
+        "user.email=git-ai@localhost".to_string(),
+        "commit".to_string(),
+        "--quiet".to_string(),
+        "--allow-empty".to_string(),
+        "-m".to_string(),
+        "git-ai selftest".to_string(),
+    ]);
+    if let Err(e) = commit {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(dir)
+}
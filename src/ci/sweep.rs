@@ -0,0 +1,596 @@
+//! `git-ai ci sweep`: a safety net for merges whose CI run was skipped or failed.
+//!
+//! A sweep clones the repository and walks the default branch's first-parent history back
+//! to `--since`. For every commit without an authorship note it asks the forge which
+//! merge/pull request produced it, then processes that request exactly as
+//! `ci <provider> run` would have. Run on a schedule, it catches up on anything missed.
+
+use crate::ci::ci_context::{CiContext, CiEvent, CiRunResult};
+use crate::ci::credentials::{CiGitCredential, forge_git_args, git_args_for_dir};
+use crate::ci::deadline;
+use crate::ci::github::github_credential;
+use crate::ci::gitlab::{
+    GitlabApiAuth, fetch_project_path, gitlab_api_auth, gitlab_git_credential,
+};
+use crate::ci::http::{self, rate_limit_wait};
+use crate::ci::oidc::exchange_gitlab_id_token;
+use crate::ci::selftest::Provider;
+use crate::ci::{env, required_env};
+use crate::error::GitAiError;
+use crate::git::refs::show_authorship_note;
+use crate::git::repository::{exec_git, find_repository};
+use crate::git::sync_authorship::fetch_authorship_notes;
+use crate::observability::timings::{self, Phase};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::time::Duration;
+
+const SWEEP_CLONE_DIR: &str = "git-ai-ci-sweep";
+
+#[derive(Debug, Clone)]
+pub struct SweepOptions {
+    /// Unix timestamp of the oldest commit to look at
+    pub since: i64,
+    /// Branch to sweep. Defaults to the repository's default branch.
+    pub branch: Option<String>,
+    /// Report what would be processed without rewriting or pushing anything
+    pub dry_run: bool,
+    pub no_cleanup: bool,
+}
+
+/// A merged merge/pull request, with what's needed to rewrite its authorship
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedRequest {
+    pub number: u64,
+    pub head_ref: String,
+    pub head_sha: String,
+    pub base_ref: String,
+    pub base_sha: String,
+    pub merge_commit_sha: String,
+    /// Ref the request's head can be fetched from after its branch is deleted
+    pub fetch_ref: String,
+}
+
+#[derive(Debug)]
+pub enum SweepOutcome {
+    /// The commit already has an authorship note
+    Attributed,
+    /// No merged request produced the commit, e.g. a direct push
+    NoRequest,
+    /// Another commit from the same (rebase-merged) request was already processed
+    Covered(u64),
+    /// Dry run: the request would be processed
+    WouldProcess(u64),
+    Processed(u64, Box<CiRunResult>),
+    Failed(String),
+    /// The sweep stopped at the CI deadline before dealing with the commit
+    Unchecked,
+}
+
+#[derive(Debug)]
+pub struct SweepReport {
+    pub branch: String,
+    pub commits: Vec<(String, SweepOutcome)>,
+    /// Why the sweep stopped before the end, i.e. the CI deadline passed
+    pub stopped: Option<String>,
+}
+
+impl SweepReport {
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, SweepOutcome::Failed(_)))
+    }
+
+    /// Whether every commit was dealt with
+    pub fn succeeded(&self) -> bool {
+        self.failed() == 0 && self.stopped.is_none()
+    }
+
+    fn count(&self, f: impl Fn(&SweepOutcome) -> bool) -> usize {
+        self.commits
+            .iter()
+            .filter(|(_, outcome)| f(outcome))
+            .count()
+    }
+
+    pub fn render(&self) -> String {
+        self.render_as("git-ai ci sweep")
+    }
+
+    /// [`SweepReport::render`] under another heading, for runs that sweep a few commits
+    /// on the way
+    pub fn render_as(&self, title: &str) -> String {
+        let mut out = format!(
+            "{}: {} commit(s) on {}\n",
+            title,
+            self.commits.len(),
+            self.branch
+        );
+        for (sha, outcome) in &self.commits {
+            let (status, detail) = match outcome {
+                SweepOutcome::Attributed => ("OK", "already attributed".to_string()),
+                SweepOutcome::NoRequest => ("SKIP", "not from a merged request".to_string()),
+                SweepOutcome::Covered(number) => ("OK", format!("covered by request #{}", number)),
+                SweepOutcome::WouldProcess(number) => {
+                    ("TODO", format!("would process request #{}", number))
+                }
+                SweepOutcome::Processed(number, result) => (
+                    "FIXED",
+                    format!("request #{}: {}", number, describe(result)),
+                ),
+                SweepOutcome::Failed(error) => {
+                    ("FAIL", error.lines().next().unwrap_or("").to_string())
+                }
+                SweepOutcome::Unchecked => ("TODO", "not done before the deadline".to_string()),
+            };
+            out.push_str(&format!(
+                "  {:<5} {}  {}\n",
+                status,
+                &sha[..sha.len().min(12)],
+                detail
+            ));
+        }
+        out.push_str(&format!(
+            "{} attributed, {} processed, {} to process, {} skipped, {} failed\n",
+            self.count(|o| matches!(o, SweepOutcome::Attributed | SweepOutcome::Covered(_))),
+            self.count(|o| matches!(o, SweepOutcome::Processed(..))),
+            self.count(|o| matches!(o, SweepOutcome::WouldProcess(_))),
+            self.count(|o| matches!(o, SweepOutcome::NoRequest)),
+            self.failed()
+        ));
+        if let Some(reason) = &self.stopped {
+            out.push_str(&format!(
+                "Stopped early: {}; {} commit(s) left for the next sweep\n",
+                reason,
+                self.count(|o| matches!(o, SweepOutcome::Unchecked))
+            ));
+        }
+        out
+    }
+}
+
+pub(crate) fn describe(result: &CiRunResult) -> &'static str {
+    match result {
+        CiRunResult::AuthorshipRewritten { .. } => "authorship rewritten",
+        CiRunResult::AlreadyExists { .. } => "authorship already exists",
+        CiRunResult::SkippedSimpleMerge => "simple merge, authorship preserved",
+        CiRunResult::SkippedFastForward => "fast-forward, nothing to rewrite",
+        CiRunResult::NoAuthorshipAvailable => "no AI authorship to track",
+        CiRunResult::NotesWrittenToArtifact { .. } => "notes written to an artifact",
+        CiRunResult::DryRun { .. } => "dry run, notes not pushed",
+        CiRunResult::PushChecked { .. } => "pushed commits checked",
+    }
+}
+
+/// How to reach the forge for one sweep
+pub(crate) struct SweepForge {
+    pub clone_url: String,
+    pub credential: Option<CiGitCredential>,
+    /// The default branch, when the CI environment names it
+    pub default_branch: Option<String>,
+    api_headers: Vec<(&'static str, String)>,
+    /// URL listing the requests a commit belongs to, with `{sha}` to fill in
+    commit_requests_url: String,
+    parse_requests: fn(&serde_json::Value, &str) -> Option<MergedRequest>,
+}
+
+impl SweepForge {
+    /// A GitLab project on the server at `server_url`. The API addresses it as `project`:
+    /// its numeric id, or its path.
+    pub fn gitlab(
+        api_url: &str,
+        server_url: &str,
+        project: &str,
+        project_path: &str,
+        auth: GitlabApiAuth,
+        credential: Option<CiGitCredential>,
+    ) -> Self {
+        SweepForge {
+            clone_url: format!("{}/{}.git", server_url, project_path),
+            credential,
+            default_branch: None,
+            api_headers: vec![(auth.header, auth.token)],
+            commit_requests_url: format!(
+                "{}/projects/{}/repository/commits/{{sha}}/merge_requests",
+                api_url,
+                project.replace('/', "%2F")
+            ),
+            parse_requests: parse_gitlab_merge_requests,
+        }
+    }
+
+    /// A GitHub repository (`owner/name`)
+    pub fn github(
+        api_url: &str,
+        server_url: &str,
+        repository: &str,
+        token: &str,
+        credential: Option<CiGitCredential>,
+    ) -> Self {
+        SweepForge {
+            clone_url: format!("{}/{}.git", server_url, repository),
+            credential,
+            default_branch: None,
+            api_headers: vec![
+                ("Authorization", format!("Bearer {}", token)),
+                ("Accept", "application/vnd.github+json".to_string()),
+            ],
+            commit_requests_url: format!("{}/repos/{}/commits/{{sha}}/pulls", api_url, repository),
+            parse_requests: parse_github_pull_requests,
+        }
+    }
+
+    /// Host the API requests go to, which rate limits apply to
+    pub fn api_host(&self) -> String {
+        url::Url::parse(&self.commit_requests_url)
+            .ok()
+            .and_then(|url| {
+                let host = url.host_str()?.to_string();
+                Some(match url.port() {
+                    Some(port) => format!("{}:{}", host, port),
+                    None => host,
+                })
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn gitlab_forge() -> Result<SweepForge, GitAiError> {
+    let api_url = required_env("CI_API_V4_URL")?;
+    let server_url = required_env("CI_SERVER_URL")?;
+    let project_id = required_env("CI_PROJECT_ID")?;
+    exchange_gitlab_id_token()?;
+    let auth = gitlab_api_auth()?;
+    let project_path = match env("CI_PROJECT_PATH") {
+        Some(path) => path,
+        None => fetch_project_path(&api_url, &project_id, &auth)?,
+    };
+
+    let mut forge = SweepForge::gitlab(
+        &api_url,
+        &server_url,
+        &project_id,
+        &project_path,
+        auth,
+        gitlab_git_credential(),
+    );
+    forge.default_branch = env("CI_DEFAULT_BRANCH");
+    Ok(forge)
+}
+
+fn github_forge() -> Result<SweepForge, GitAiError> {
+    let repository = required_env("GITHUB_REPOSITORY")?;
+    let api_url = env("GITHUB_API_URL").unwrap_or_else(|| "https://api.github.com".to_string());
+    let server_url = env("GITHUB_SERVER_URL").unwrap_or_else(|| "https://github.com".to_string());
+    let credential = github_credential(&repository)?;
+    let token = credential
+        .as_ref()
+        .and_then(|c| env(c.token_env_var()))
+        .ok_or_else(|| {
+            GitAiError::Generic("GITHUB_TOKEN environment variable not set".to_string())
+        })?;
+
+    Ok(SweepForge::github(
+        &api_url,
+        &server_url,
+        &repository,
+        &token,
+        credential,
+    ))
+}
+
+/// The merged MR among those GitLab lists for `sha`. Squash merges are rewritten onto
+/// the squash commit, like `ci gitlab run` does.
+fn parse_gitlab_merge_requests(body: &serde_json::Value, sha: &str) -> Option<MergedRequest> {
+    let mr = body
+        .as_array()?
+        .iter()
+        .find(|mr| mr["state"].as_str() == Some("merged"))?;
+    let str_field = |name: &str| mr[name].as_str().map(str::to_string);
+    let number = mr["iid"].as_u64()?;
+    Some(MergedRequest {
+        number,
+        head_ref: str_field("source_branch")?,
+        head_sha: str_field("sha")?,
+        base_ref: str_field("target_branch")?,
+        base_sha: mr["diff_refs"]["base_sha"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        merge_commit_sha: str_field("squash_commit_sha")
+            .or_else(|| str_field("merge_commit_sha"))
+            .unwrap_or_else(|| sha.to_string()),
+        fetch_ref: format!("refs/merge-requests/{}/head", number),
+    })
+}
+
+fn parse_github_pull_requests(body: &serde_json::Value, sha: &str) -> Option<MergedRequest> {
+    let pr = body
+        .as_array()?
+        .iter()
+        .find(|pr| !pr["merged_at"].is_null() || pr["merged"].as_bool() == Some(true))?;
+    let number = pr["number"].as_u64()?;
+    Some(MergedRequest {
+        number,
+        head_ref: pr["head"]["ref"].as_str()?.to_string(),
+        head_sha: pr["head"]["sha"].as_str()?.to_string(),
+        base_ref: pr["base"]["ref"].as_str()?.to_string(),
+        base_sha: pr["base"]["sha"].as_str().unwrap_or_default().to_string(),
+        merge_commit_sha: pr["merge_commit_sha"].as_str().unwrap_or(sha).to_string(),
+        fetch_ref: format!("pull/{}/head", number),
+    })
+}
+
+/// The answer to asking the forge which request produced a commit
+pub(crate) enum Lookup {
+    Found(Option<MergedRequest>),
+    /// The forge refused for now; try again after this long
+    RateLimited(Duration),
+}
+
+fn find_merged_request(forge: &SweepForge, sha: &str) -> Result<Option<MergedRequest>, GitAiError> {
+    match lookup_merged_request(forge, sha)? {
+        Lookup::Found(request) => Ok(request),
+        Lookup::RateLimited(wait) => Err(GitAiError::Generic(format!(
+            "API rate limit reached; retry in {}s",
+            wait.as_secs()
+        ))),
+    }
+}
+
+pub(crate) fn lookup_merged_request(forge: &SweepForge, sha: &str) -> Result<Lookup, GitAiError> {
+    let _timing = timings::phase(Phase::Api);
+    let url = forge.commit_requests_url.replace("{sha}", sha);
+    let mut request = http::get(&url).with_header(
+        "User-Agent",
+        format!("git-ai/{}", env!("CARGO_PKG_VERSION")),
+    );
+    for (name, value) in &forge.api_headers {
+        request = request.with_header(*name, value);
+    }
+    let response = http::send_once(&request, "API request")?;
+    if let Some(wait) = rate_limit_wait(
+        response.status_code,
+        |name| response.headers.get(name).map(String::as_str),
+        Utc::now().timestamp(),
+    ) {
+        return Ok(Lookup::RateLimited(wait));
+    }
+    let body = response.as_str().unwrap_or("");
+    if response.status_code != 200 {
+        return Err(GitAiError::Generic(format!(
+            "API returned status {} for {}: {}",
+            response.status_code, url, body
+        )));
+    }
+    let body: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| GitAiError::Generic(format!("Failed to parse API response: {}", e)))?;
+    Ok(Lookup::Found((forge.parse_requests)(&body, sha)))
+}
+
+fn git_stdout(args: Vec<String>) -> Result<String, GitAiError> {
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn run_sweep(provider: Provider, options: &SweepOptions) -> Result<SweepReport, GitAiError> {
+    let forge = match provider {
+        Provider::Gitlab => gitlab_forge()?,
+        Provider::Github => github_forge()?,
+    };
+
+    let mut clone_args = forge_git_args(forge.credential.as_ref());
+    clone_args.push("clone".to_string());
+    if let Some(branch) = options.branch.as_ref().or(forge.default_branch.as_ref()) {
+        clone_args.extend(["--branch".to_string(), branch.clone()]);
+    }
+    clone_args.extend([forge.clone_url.clone(), SWEEP_CLONE_DIR.to_string()]);
+    {
+        let _timing = timings::phase(Phase::Clone);
+        exec_git(&clone_args)?;
+    }
+
+    let result = sweep_clone(&forge, options);
+    if !options.no_cleanup {
+        let _ = std::fs::remove_dir_all(SWEEP_CLONE_DIR);
+    }
+    result
+}
+
+fn sweep_clone(forge: &SweepForge, options: &SweepOptions) -> Result<SweepReport, GitAiError> {
+    let repo_args = git_args_for_dir(SWEEP_CLONE_DIR, forge.credential.as_ref());
+    let repo = find_repository(&repo_args)?;
+    fetch_authorship_notes(&repo, "origin")?;
+
+    let git = |args: &[&str]| {
+        let mut full = repo_args.clone();
+        full.extend(args.iter().map(|arg| arg.to_string()));
+        git_stdout(full)
+    };
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+    let since = DateTime::<Utc>::from_timestamp(options.since, 0)
+        .unwrap_or_default()
+        .to_rfc3339();
+    let commits = git(&[
+        "log",
+        "--first-parent",
+        "--reverse",
+        "--format=%H",
+        &format!("--since={}", since),
+        "HEAD",
+    ])?;
+
+    let commits: Vec<String> = commits.lines().map(str::to_string).collect();
+    sweep_commits(&repo_args, forge, branch, &commits, options.dry_run)
+}
+
+/// Deal with `commits` of `branch`, oldest first: each one without an authorship note is
+/// traced to the request that produced it, which is processed unless `dry_run`
+pub(crate) fn sweep_commits(
+    repo_args: &[String],
+    forge: &SweepForge,
+    branch: String,
+    commits: &[String],
+    dry_run: bool,
+) -> Result<SweepReport, GitAiError> {
+    let repo = find_repository(repo_args)?;
+    let mut report = SweepReport {
+        branch,
+        commits: Vec::new(),
+        stopped: None,
+    };
+    let mut seen_requests = HashSet::new();
+    for sha in commits.iter().filter(|line| !line.is_empty()) {
+        if report.stopped.is_none()
+            && let Err(e) = deadline::check(&format!("checking commit {}", sha))
+        {
+            report.stopped = Some(e.to_string());
+        }
+        let outcome = if report.stopped.is_some() {
+            SweepOutcome::Unchecked
+        } else if show_authorship_note(&repo, sha).is_some() {
+            SweepOutcome::Attributed
+        } else {
+            match find_merged_request(forge, sha) {
+                Err(e @ GitAiError::DeadlineExceeded { .. }) => {
+                    report.stopped = Some(e.to_string());
+                    SweepOutcome::Unchecked
+                }
+                Err(e) => SweepOutcome::Failed(e.to_string()),
+                Ok(None) => SweepOutcome::NoRequest,
+                Ok(Some(request)) if !seen_requests.insert(request.number) => {
+                    SweepOutcome::Covered(request.number)
+                }
+                Ok(Some(request)) if dry_run => SweepOutcome::WouldProcess(request.number),
+                Ok(Some(request)) => match process_request(repo_args, &request) {
+                    Ok(result) => SweepOutcome::Processed(request.number, Box::new(result)),
+                    Err(e @ GitAiError::DeadlineExceeded { .. }) => {
+                        report.stopped = Some(e.to_string());
+                        SweepOutcome::Unchecked
+                    }
+                    Err(e) => SweepOutcome::Failed(e.to_string()),
+                },
+            }
+        };
+        report.commits.push((sha.to_string(), outcome));
+    }
+    Ok(report)
+}
+
+pub(crate) fn process_request(
+    repo_args: &[String],
+    request: &MergedRequest,
+) -> Result<CiRunResult, GitAiError> {
+    println!(
+        "[CI sweep] Processing request #{} ({} -> {})",
+        request.number, request.head_ref, request.base_ref
+    );
+    // Keep the head commits in a local ref, since the branch may be gone
+    let mut fetch_args = repo_args.to_vec();
+    fetch_args.extend([
+        "fetch".to_string(),
+        "origin".to_string(),
+        format!("{}:refs/git-ai/sweep/{}", request.fetch_ref, request.number),
+    ]);
+    {
+        let _timing = timings::phase(Phase::Fetch);
+        exec_git(&fetch_args)?;
+    }
+
+    let context = CiContext::with_repository(
+        find_repository(repo_args)?,
+        CiEvent::Merge {
+            merge_commit_sha: request.merge_commit_sha.clone(),
+            head_ref: request.head_ref.clone(),
+            head_sha: request.head_sha.clone(),
+            base_ref: request.base_ref.clone(),
+            base_sha: request.base_sha.clone(),
+            merge_request: Some(request.number),
+        },
+    );
+    context.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_gitlab_prefers_squash_commit() {
+        let body = json!([
+            { "iid": 3, "state": "closed", "source_branch": "old", "target_branch": "main", "sha": "a" },
+            {
+                "iid": 4, "state": "merged", "source_branch": "feature", "target_branch": "main",
+                "sha": "head", "merge_commit_sha": "merge", "squash_commit_sha": "squash",
+                "diff_refs": { "base_sha": "base" }
+            }
+        ]);
+        let request = parse_gitlab_merge_requests(&body, "squash").unwrap();
+        assert_eq!(request.number, 4);
+        assert_eq!(request.merge_commit_sha, "squash");
+        assert_eq!(request.base_sha, "base");
+        assert_eq!(request.fetch_ref, "refs/merge-requests/4/head");
+
+        assert_eq!(parse_gitlab_merge_requests(&json!([]), "x"), None);
+    }
+
+    #[test]
+    fn test_parse_github_needs_a_merged_pull_request() {
+        let pr = |merged_at: serde_json::Value| {
+            json!([{
+                "number": 9, "merged_at": merged_at, "merge_commit_sha": "merge",
+                "head": { "ref": "feature", "sha": "head" },
+                "base": { "ref": "main", "sha": "base" }
+            }])
+        };
+        assert_eq!(parse_github_pull_requests(&pr(json!(null)), "merge"), None);
+        let request =
+            parse_github_pull_requests(&pr(json!("2026-01-01T00:00:00Z")), "merge").unwrap();
+        assert_eq!(request.number, 9);
+        assert_eq!(request.head_sha, "head");
+        assert_eq!(request.fetch_ref, "pull/9/head");
+    }
+
+    #[test]
+    fn test_render_summarizes_outcomes() {
+        let report = SweepReport {
+            branch: "main".to_string(),
+            commits: vec![
+                ("a".repeat(40), SweepOutcome::Attributed),
+                ("b".repeat(40), SweepOutcome::WouldProcess(7)),
+                ("c".repeat(40), SweepOutcome::NoRequest),
+                (
+                    "d".repeat(40),
+                    SweepOutcome::Failed("boom\ndetails".to_string()),
+                ),
+            ],
+            stopped: None,
+        };
+        let rendered = report.render();
+        assert!(rendered.contains("  TODO  bbbbbbbbbbbb  would process request #7\n"));
+        assert!(rendered.contains("  FAIL  dddddddddddd  boom\n"));
+        assert!(
+            rendered.ends_with("1 attributed, 0 processed, 1 to process, 1 skipped, 1 failed\n")
+        );
+        assert_eq!(report.failed(), 1);
+    }
+
+    #[test]
+    fn test_render_reports_commits_left_at_deadline() {
+        let report = SweepReport {
+            branch: "main".to_string(),
+            commits: vec![
+                ("a".repeat(40), SweepOutcome::Attributed),
+                ("b".repeat(40), SweepOutcome::Unchecked),
+                ("c".repeat(40), SweepOutcome::Unchecked),
+            ],
+            stopped: Some("CI deadline of 10m exceeded".to_string()),
+        };
+        let rendered = report.render();
+        assert!(rendered.contains("  TODO  bbbbbbbbbbbb  not done before the deadline\n"));
+        assert!(rendered.ends_with(
+            "Stopped early: CI deadline of 10m exceeded; 2 commit(s) left for the next sweep\n"
+        ));
+        assert!(!report.succeeded());
+    }
+}
@@ -0,0 +1,31 @@
+// Git AI - Jenkins Pipeline Configuration
+// Add this stage to the stages block of your Jenkinsfile (multibranch or single-branch
+// pipeline)
+//
+// SETUP: git-ai pushes authorship notes back to the repository, so the job needs
+// credentials that can push.
+//
+// 1. Manage Jenkins > Credentials > Add Credentials
+//    - Kind: Username with password (a bot account and its access token)
+//    - ID: {{CREDENTIALS_ID}}
+// 2. Install the Git plugin (2.x or later) for the gitUsernamePassword binding below.
+//    For SSH remotes, wrap the step in sshagent(['<ssh credentials id>']) instead.
+//
+// No forge API is involved: git-ai finds the merged branch among the remote's branches
+// and GitHub/GitLab pull and merge request refs. On other hosts, the branch must still
+// exist when the stage runs, so don't delete branches on merge.
+
+stage('git-ai') {
+    when { branch '{{BRANCH}}' }
+    steps {
+        withCredentials([gitUsernamePassword(credentialsId: '{{CREDENTIALS_ID}}')]) {
+            sh '''
+                curl -fsSL https://usegitai.com/install.sh | bash
+                export PATH="$HOME/.git-ai/bin:$PATH"
+                git config user.name "jenkins[bot]"
+                git config user.email "jenkins[bot]@users.noreply.jenkins.io"
+                git-ai ci jenkins run
+            '''
+        }
+    }
+}
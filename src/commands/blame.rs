@@ -1,14 +1,17 @@
 use crate::authorship::authorship_log::PromptRecord;
 use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::authorship::blame_index::indexed_blame_hunks;
+use crate::authorship::neutral_commits::NeutralCommits;
 use crate::authorship::working_log::CheckpointKind;
 use crate::error::GitAiError;
 use crate::git::refs::get_reference_as_authorship_log_v3;
 use crate::git::repository::Repository;
 use crate::git::repository::{exec_git, exec_git_stdin};
+use crate::observability::timings::{self, Phase};
 #[cfg(windows)]
 use crate::utils::normalize_to_posix;
 use chrono::{DateTime, FixedOffset, TimeZone, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self, IsTerminal, Write};
@@ -22,7 +25,10 @@ pub static OLDEST_AI_BLAME_DATE: LazyLock<DateTime<FixedOffset>> = LazyLock::new
         .unwrap()
 });
 
-#[derive(Debug, Clone)]
+/// Blame reruns allowed for neutral commits uncovered behind other neutral commits
+const MAX_NEUTRAL_BLAME_PASSES: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlameHunk {
     /// Line range [start, end] (inclusive) - current line numbers in the file
     pub range: (u32, u32),
@@ -32,6 +38,7 @@ pub struct BlameHunk {
     pub commit_sha: String,
     /// Abbreviated commit SHA
     #[allow(dead_code)]
+    #[serde(skip)]
     pub abbrev_sha: String,
     /// Original author from Git blame
     pub original_author: String,
@@ -42,6 +49,7 @@ pub struct BlameHunk {
     /// Author timezone (e.g. "+0000")
     pub author_tz: String,
     /// AI human author name
+    #[serde(skip)]
     pub ai_human_author: Option<String>,
     /// Committer name
     pub committer: String,
@@ -258,7 +266,7 @@ impl Repository {
 
         // For JSON output, default to HEAD to exclude uncommitted changes
         // and use prompt hashes as names so we can correlate with prompt_records
-        let options = if options.json {
+        let mut options = if options.json {
             let mut opts = options.clone();
             if opts.newest_commit.is_none() {
                 opts.newest_commit = Some("HEAD".to_string());
@@ -340,11 +348,34 @@ impl Repository {
             }
         }
 
-        // Step 1: Get Git's native blame for all ranges
+        // Step 1: Get Git's native blame for all ranges, looking through attribution-neutral
+        // commits (formatter runs) to the commits that last really changed each line. Each
+        // pass can surface older neutral commits, so repeat a few times.
+        let mut neutral_commits = NeutralCommits::from_config(self);
         let mut all_blame_hunks = Vec::new();
-        for (start_line, end_line) in &line_ranges {
-            let hunks = self.blame_hunks(&relative_file_path, *start_line, *end_line, &options)?;
-            all_blame_hunks.extend(hunks);
+        for _ in 0..MAX_NEUTRAL_BLAME_PASSES {
+            all_blame_hunks.clear();
+            for (start_line, end_line) in &line_ranges {
+                let hunks =
+                    self.blame_hunks(&relative_file_path, *start_line, *end_line, &options)?;
+                all_blame_hunks.extend(hunks);
+            }
+            if !neutral_commits.is_enabled() {
+                break;
+            }
+            let mut newly_neutral: Vec<String> = Vec::new();
+            for hunk in &all_blame_hunks {
+                if !options.ignore_revs.contains(&hunk.commit_sha)
+                    && !newly_neutral.contains(&hunk.commit_sha)
+                    && neutral_commits.is_neutral(&hunk.commit_sha)
+                {
+                    newly_neutral.push(hunk.commit_sha.clone());
+                }
+            }
+            if newly_neutral.is_empty() {
+                break;
+            }
+            options.ignore_revs.extend(newly_neutral);
         }
 
         // Step 2: Overlay AI authorship information
@@ -402,6 +433,27 @@ impl Repository {
         start_line: u32,
         end_line: u32,
         options: &GitAiBlameOptions,
+    ) -> Result<Vec<BlameHunk>, GitAiError> {
+        let hunks = match indexed_blame_hunks(self, file_path, (start_line, end_line), options) {
+            Some(hunks) => hunks,
+            None => self.git_blame_hunks(file_path, Some((start_line, end_line)), None, options)?,
+        };
+
+        // Post-process hunks to populate ai_human_author from authorship logs
+        let hunks = self.populate_ai_human_authors(hunks, file_path, options)?;
+
+        Ok(hunks)
+    }
+
+    /// Run git blame on `line_range` of `file_path` (all of it when None) and parse its
+    /// hunks. With a `boundary` commit, history stops there: lines the boundary already
+    /// had are blamed on it and marked as boundary.
+    pub fn git_blame_hunks(
+        &self,
+        file_path: &str,
+        line_range: Option<(u32, u32)>,
+        boundary: Option<&str>,
+        options: &GitAiBlameOptions,
     ) -> Result<Vec<BlameHunk>, GitAiError> {
         // Build git blame --line-porcelain command
         let mut args = self.global_args_for_exec();
@@ -424,8 +476,10 @@ impl Repository {
         }
 
         // Limit to specified range
-        args.push("-L".to_string());
-        args.push(format!("{},{}", start_line, end_line));
+        if let Some((start_line, end_line)) = line_range {
+            args.push("-L".to_string());
+            args.push(format!("{},{}", start_line, end_line));
+        }
 
         // Add --since flag if oldest_date is specified
         // This controls the absolute lower bound of how far back to look
@@ -434,6 +488,10 @@ impl Repository {
             args.push(date.to_rfc3339());
         }
 
+        if let Some(boundary) = boundary {
+            args.push(format!("^{}", boundary));
+        }
+
         // Support newest_commit option (equivalent to libgit2's newest_commit)
         // This limits blame to only consider commits up to and including the specified commit
         // When oldest_commit is also set, we use a range: oldest_commit..newest_commit
@@ -465,6 +523,7 @@ impl Repository {
         args.push(file_path.to_string());
 
         // Execute git blame, using stdin if we have contents data
+        let _timing = timings::phase(Phase::Blame);
         let output = if let Some(ref data) = options.contents_data {
             exec_git_stdin(&args, data)?
         } else {
@@ -586,16 +645,7 @@ impl Repository {
                         orig_start
                     };
 
-                    let abbrev_len = if options.long_rev {
-                        40
-                    } else {
-                        options.abbrev.unwrap_or(7) as usize
-                    };
-                    let abbrev = if abbrev_len < prev_sha.len() {
-                        prev_sha[..abbrev_len].to_string()
-                    } else {
-                        prev_sha.clone()
-                    };
+                    let abbrev = abbrev_sha(&prev_sha, options);
 
                     hunks.push(BlameHunk {
                         range: (start, end),
@@ -655,16 +705,7 @@ impl Repository {
                 orig_start
             };
 
-            let abbrev_len = if options.long_rev {
-                40
-            } else {
-                options.abbrev.unwrap_or(7) as usize
-            };
-            let abbrev = if abbrev_len < prev_sha.len() {
-                prev_sha[..abbrev_len].to_string()
-            } else {
-                prev_sha.clone()
-            };
+            let abbrev = abbrev_sha(&prev_sha, options);
 
             hunks.push(BlameHunk {
                 range: (start, end),
@@ -684,9 +725,6 @@ impl Repository {
             });
         }
 
-        // Post-process hunks to populate ai_human_author from authorship logs
-        let hunks = self.populate_ai_human_authors(hunks, file_path, options)?;
-
         Ok(hunks)
     }
 
@@ -795,6 +833,20 @@ impl Repository {
     }
 }
 
+/// `sha` shortened as `--abbrev` or `-l` ask
+pub fn abbrev_sha(sha: &str, options: &GitAiBlameOptions) -> String {
+    let abbrev_len = if options.long_rev {
+        40
+    } else {
+        options.abbrev.unwrap_or(7) as usize
+    };
+    if abbrev_len < sha.len() {
+        sha[..abbrev_len].to_string()
+    } else {
+        sha.to_string()
+    }
+}
+
 #[allow(clippy::type_complexity)]
 fn overlay_ai_authorship(
     repo: &Repository,
@@ -898,10 +950,8 @@ fn overlay_ai_authorship(
     }
 
     // Collect all authorship logs we've seen (for JSON output to find other files)
-    let authorship_logs: Vec<AuthorshipLog> = commit_authorship_cache
-        .into_iter()
-        .filter_map(|(_, log)| log)
-        .collect();
+    let authorship_logs: Vec<AuthorshipLog> =
+        commit_authorship_cache.into_values().flatten().collect();
 
     // Convert HashSet to Vec and sort for deterministic output
     let prompt_commits_vec: HashMap<String, Vec<String>> = prompt_commits
@@ -925,7 +975,7 @@ fn overlay_ai_authorship(
 #[derive(Debug, Serialize)]
 struct JsonBlameOutput {
     lines: std::collections::BTreeMap<String, String>,
-    prompts: HashMap<String, PromptRecordWithOtherFiles>,
+    prompts: std::collections::BTreeMap<String, PromptRecordWithOtherFiles>,
 }
 
 /// Read model that patches PromptRecord with other_files and commits fields
@@ -1027,22 +1077,23 @@ fn output_json_format(
     let referenced_prompt_ids: std::collections::HashSet<&String> = lines_map.values().collect();
 
     // Create read models with other_files and commits populated
-    let filtered_prompts: HashMap<String, PromptRecordWithOtherFiles> = prompt_records
-        .iter()
-        .filter(|(k, _)| referenced_prompt_ids.contains(k))
-        .map(|(k, v)| {
-            let other_files = get_files_for_prompt_hash(k, authorship_logs, current_file);
-            let commits = prompt_commits.get(k).cloned().unwrap_or_default();
-            (
-                k.clone(),
-                PromptRecordWithOtherFiles {
-                    prompt_record: v.clone(),
-                    other_files,
-                    commits,
-                },
-            )
-        })
-        .collect();
+    let filtered_prompts: std::collections::BTreeMap<String, PromptRecordWithOtherFiles> =
+        prompt_records
+            .iter()
+            .filter(|(k, _)| referenced_prompt_ids.contains(k))
+            .map(|(k, v)| {
+                let other_files = get_files_for_prompt_hash(k, authorship_logs, current_file);
+                let commits = prompt_commits.get(k).cloned().unwrap_or_default();
+                (
+                    k.clone(),
+                    PromptRecordWithOtherFiles {
+                        prompt_record: v.clone(),
+                        other_files,
+                        commits,
+                    },
+                )
+            })
+            .collect();
 
     let output = JsonBlameOutput {
         lines: lines_map,
@@ -0,0 +1,428 @@
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::show_authorship_note;
+use crate::git::repository::{CommitRange, Repository};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// Commit message trailer marking a commit as intentionally having no authorship note
+/// (e.g. commits created by tools that bypass hooks, or vendored imports).
+pub const NO_ATTRIBUTION_TRAILER: &str = "Git-AI-Attribution";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletenessStatus {
+    /// Commit has an authorship note
+    Attributed,
+    /// Merge commit; attribution is carried by the commits it merges
+    Merge,
+    /// Commit is explicitly marked as not needing attribution
+    NotNeeded,
+    /// Commit has no authorship note and no marker
+    Missing,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitCompleteness {
+    pub sha: String,
+    pub summary: String,
+    pub status: CompletenessStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompletenessReport {
+    pub range: String,
+    pub total: usize,
+    pub missing: usize,
+    pub commits: Vec<CommitCompleteness>,
+}
+
+impl CompletenessReport {
+    pub fn is_complete(&self) -> bool {
+        self.missing == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StagedFileStatus {
+    /// A checkpoint recorded exactly the staged content
+    Attributed,
+    /// Checkpoints exist for the file, but it changed after the last one (or only part
+    /// of it is staged). Reported, but not a failure.
+    Modified,
+    /// No checkpoint has recorded this file since the last commit
+    Unattributed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StagedFileCheck {
+    pub file: String,
+    pub status: StagedFileStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StagedCheckReport {
+    pub base: String,
+    pub total: usize,
+    pub unattributed: usize,
+    pub files: Vec<StagedFileCheck>,
+}
+
+impl StagedCheckReport {
+    pub fn passed(&self) -> bool {
+        self.unattributed == 0
+    }
+}
+
+pub fn handle_check(args: &[String]) {
+    let mut range: Option<String> = None;
+    let mut staged = false;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--completeness" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --completeness requires a <base>..<head> range");
+                    std::process::exit(1);
+                }
+                range = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--staged" => {
+                staged = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            other => {
+                eprintln!("Error: unknown check argument: {}", other);
+                print_check_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if staged && range.is_some() {
+        eprintln!("Error: --staged and --completeness can't be combined");
+        std::process::exit(1);
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Without a range, check what is about to be committed. This is the entry point
+    // used by the pre-commit framework (see .pre-commit-hooks.yaml).
+    let Some(range) = range else {
+        run_staged_check(&repo, json_output);
+        return;
+    };
+
+    let report = match check_completeness(&repo, &range) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Failed to check attribution completeness: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize completeness report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_completeness_report(&report);
+    }
+
+    if !report.is_complete() {
+        std::process::exit(1);
+    }
+}
+
+fn print_check_help() {
+    eprintln!("Usage: git-ai check [--staged] [--json]");
+    eprintln!("       git-ai check --completeness <base>..<head> [--json]");
+    eprintln!();
+    eprintln!(
+        "  --staged                       Verify every staged file was recorded by a checkpoint (default)"
+    );
+    eprintln!(
+        "  --completeness <base>..<head>  Verify every commit in the range has an authorship note"
+    );
+    eprintln!("  --json                         Output in JSON format");
+    eprintln!();
+    eprintln!(
+        "Commits that intentionally have no note can be marked with a '{}: none' trailer.",
+        NO_ATTRIBUTION_TRAILER
+    );
+}
+
+/// Check that every commit in `base..head` has an authorship note, is a merge commit,
+/// or is explicitly marked as not needing attribution.
+pub fn check_completeness(repo: &Repository, spec: &str) -> Result<CompletenessReport, GitAiError> {
+    let (base, head) = match spec.split_once("..") {
+        Some((base, head)) if !base.is_empty() && !head.is_empty() => (base, head),
+        _ => {
+            return Err(GitAiError::Generic(
+                "Invalid commit range format. Expected <base>..<head>".to_string(),
+            ));
+        }
+    };
+
+    let range = CommitRange::new_infer_refname(repo, base.to_string(), head.to_string(), None)?;
+    // A range where base == head is empty for the purposes of a pre-merge check
+    let commit_shas = if range.start_oid == range.end_oid {
+        Vec::new()
+    } else {
+        range.all_commits()
+    };
+
+    let mut commits = Vec::with_capacity(commit_shas.len());
+    for sha in commit_shas {
+        let commit = repo.find_commit(sha.clone())?;
+        let summary = commit.summary().unwrap_or_default();
+
+        let status = if show_authorship_note(repo, &sha).is_some() {
+            CompletenessStatus::Attributed
+        } else if commit.parent_count()? > 1 {
+            CompletenessStatus::Merge
+        } else if has_no_attribution_marker(&commit.body().unwrap_or_default()) {
+            CompletenessStatus::NotNeeded
+        } else {
+            CompletenessStatus::Missing
+        };
+
+        commits.push(CommitCompleteness {
+            sha,
+            summary,
+            status,
+        });
+    }
+
+    let missing = commits
+        .iter()
+        .filter(|c| c.status == CompletenessStatus::Missing)
+        .count();
+
+    Ok(CompletenessReport {
+        range: spec.to_string(),
+        total: commits.len(),
+        missing,
+        commits,
+    })
+}
+
+/// Run the staged-files check, print the result and exit non-zero if it fails.
+/// Shared by `git-ai check` and `git-ai status --fail-on-unattributed`.
+pub fn run_staged_check(repo: &Repository, json_output: bool) {
+    let report = match check_staged(repo) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Failed to check staged attribution: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize staged check report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_staged_report(&report);
+    }
+
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
+
+/// Check that every staged file has been recorded by a checkpoint since the last
+/// commit. Only the index is examined, so this stays fast on large working trees.
+///
+/// A file with no checkpoint was edited outside any tracked flow (e.g. an agent whose
+/// hooks aren't installed), so its lines would silently be attributed to the human
+/// committer.
+pub fn check_staged(repo: &Repository) -> Result<StagedCheckReport, GitAiError> {
+    let base = repo
+        .head()
+        .and_then(|head| head.target())
+        .unwrap_or_else(|_| "initial".to_string());
+
+    // Deleted files add no lines, so there is nothing to attribute
+    let mut staged: Vec<String> = repo
+        .git(&["diff", "--cached", "--name-only", "-z", "--diff-filter=d"])?
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect();
+    staged.sort();
+
+    if staged.is_empty() {
+        return Ok(StagedCheckReport {
+            base,
+            total: 0,
+            unattributed: 0,
+            files: Vec::new(),
+        });
+    }
+
+    let working_log = repo.storage.working_log_for_base_commit(&base);
+    let mut recorded: HashMap<String, HashSet<String>> = HashMap::new();
+    for checkpoint in working_log.read_all_checkpoints()? {
+        for entry in checkpoint.entries {
+            recorded
+                .entry(entry.file)
+                .or_default()
+                .insert(entry.blob_sha);
+        }
+    }
+    // Attributions carried over from a previous partial commit count as a record too
+    for file in working_log.read_initial_attributions().files.into_keys() {
+        recorded.entry(file).or_default();
+    }
+
+    let mut files = Vec::with_capacity(staged.len());
+    for file in staged {
+        let status = match recorded.get(&file) {
+            None => StagedFileStatus::Unattributed,
+            Some(blob_shas) => {
+                // Binary content can't have been checkpointed, so it reads as modified
+                let staged_content = repo.git(&["show", &format!(":{}", file)]).ok();
+                if staged_content.is_some_and(|c| blob_shas.contains(&content_sha(&c))) {
+                    StagedFileStatus::Attributed
+                } else {
+                    StagedFileStatus::Modified
+                }
+            }
+        };
+        files.push(StagedFileCheck { file, status });
+    }
+
+    let unattributed = files
+        .iter()
+        .filter(|f| f.status == StagedFileStatus::Unattributed)
+        .count();
+
+    Ok(StagedCheckReport {
+        base,
+        total: files.len(),
+        unattributed,
+        files,
+    })
+}
+
+/// Same hash the working log uses for checkpoint blobs
+fn content_sha(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn print_staged_report(report: &StagedCheckReport) {
+    if report.total == 0 {
+        println!("No staged files to check");
+        return;
+    }
+
+    for file in &report.files {
+        let label = match file.status {
+            StagedFileStatus::Attributed => continue,
+            StagedFileStatus::Modified => "modified",
+            StagedFileStatus::Unattributed => "UNATTRIBUTED",
+        };
+        println!("{:<14} {}", label, file.file);
+    }
+
+    if report.passed() {
+        println!(
+            "All {} staged file(s) were recorded by a checkpoint",
+            report.total
+        );
+    } else {
+        println!(
+            "{} of {} staged file(s) have no checkpoint since the last commit",
+            report.unattributed, report.total
+        );
+        println!(
+            "Their lines will be attributed to you. If an AI agent edited them, check that its hooks are installed (git-ai install-hooks)"
+        );
+    }
+}
+
+/// Whether a commit message body contains a `Git-AI-Attribution: none` trailer.
+fn has_no_attribution_marker(body: &str) -> bool {
+    body.lines().any(|line| {
+        line.split_once(':').is_some_and(|(key, value)| {
+            key.trim().eq_ignore_ascii_case(NO_ATTRIBUTION_TRAILER)
+                && value.trim().eq_ignore_ascii_case("none")
+        })
+    })
+}
+
+fn print_completeness_report(report: &CompletenessReport) {
+    if report.total == 0 {
+        println!("No commits in {}", report.range);
+        return;
+    }
+
+    for commit in &report.commits {
+        let label = match commit.status {
+            CompletenessStatus::Attributed => continue,
+            CompletenessStatus::Merge => "merge",
+            CompletenessStatus::NotNeeded => "not needed",
+            CompletenessStatus::Missing => "MISSING",
+        };
+        let short_sha = &commit.sha[..commit.sha.len().min(7)];
+        println!("{:<10} {} {}", label, short_sha, commit.summary);
+    }
+
+    if report.is_complete() {
+        println!(
+            "All {} commit(s) in {} have complete attribution",
+            report.total, report.range
+        );
+    } else {
+        println!(
+            "{} of {} commit(s) in {} are missing authorship notes",
+            report.missing, report.total, report.range
+        );
+        println!(
+            "Add a '{}: none' trailer to commits that intentionally have no attribution",
+            NO_ATTRIBUTION_TRAILER
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_no_attribution_marker() {
+        assert!(has_no_attribution_marker("Git-AI-Attribution: none"));
+        assert!(has_no_attribution_marker(
+            "Some details\n\nSigned-off-by: A <a@b.c>\ngit-ai-attribution: None"
+        ));
+        assert!(!has_no_attribution_marker("Git-AI-Attribution: required"));
+        assert!(!has_no_attribution_marker("No trailers here"));
+        assert!(!has_no_attribution_marker(""));
+    }
+}
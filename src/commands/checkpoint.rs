@@ -1,9 +1,14 @@
+use crate::authorship::attribution_inheritance::{
+    InheritanceRules, inherit_attributions, preserve_whitespace_only_attributions,
+};
 use crate::authorship::attribution_tracker::{
-    Attribution, AttributionTracker, INITIAL_ATTRIBUTION_TS, LineAttribution,
+    Attribution, INITIAL_ATTRIBUTION_TS, LineAttribution, attribute_edit_with_granularity,
+    attributions_to_line_attributions,
 };
 use crate::authorship::authorship_log::PromptRecord;
 use crate::authorship::authorship_log_serialization::generate_short_hash;
 use crate::authorship::imara_diff_utils::{LineChangeTag, compute_line_changes};
+use crate::authorship::work_item::resolve_work_item;
 use crate::authorship::working_log::CheckpointKind;
 use crate::authorship::working_log::{Checkpoint, WorkingLogEntry};
 use crate::commands::blame::{GitAiBlameOptions, OLDEST_AI_BLAME_DATE};
@@ -104,6 +109,7 @@ pub fn run(
     quiet: bool,
     agent_run_result: Option<AgentRunResult>,
     is_pre_commit: bool,
+    inherit: Option<InheritanceRules>,
 ) -> Result<(usize, usize, usize), GitAiError> {
     let checkpoint_start = Instant::now();
     debug_log("[BENCHMARK] Starting checkpoint run");
@@ -117,6 +123,18 @@ pub fn run(
         Err(_) => "initial".to_string(),
     };
 
+    // Agents `.git-ai.toml` doesn't allow leave no checkpoint, so their edits count as human
+    if kind != CheckpointKind::Human
+        && let Some(result) = &agent_run_result
+        && !Config::for_repo(repo).is_agent_allowed(&result.agent_id.tool)
+    {
+        debug_log(&format!(
+            "skipping checkpoint from {}, which .git-ai.toml doesn't allow",
+            result.agent_id.tool
+        ));
+        return Ok((0, 0, 0));
+    }
+
     // Cannot run checkpoint on bare repositories
     if repo.workdir().is_err() {
         eprintln!("Cannot run checkpoint on bare repositories");
@@ -359,6 +377,7 @@ pub fn run(
         &checkpoints,
         agent_run_result.as_ref(),
         ts,
+        inherit,
     ))?;
     debug_log(&format!(
         "[BENCHMARK] get_checkpoint_entries generated {} entries, took {:?}",
@@ -378,6 +397,11 @@ pub fn run(
 
         // Aggregate line stats from in-memory stats (computed during entry creation)
         checkpoint.line_stats = compute_line_stats(&file_stats)?;
+        checkpoint.effort.files_touched = file_stats
+            .iter()
+            .filter(|stat| stat.additions > 0 || stat.deletions > 0)
+            .count() as u32;
+        checkpoint.work_item = resolve_work_item(repo);
 
         // Set transcript and agent_id if provided and not a human checkpoint
         if kind != CheckpointKind::Human
@@ -386,6 +410,18 @@ pub fn run(
             checkpoint.transcript = Some(agent_run.transcript.clone().unwrap_or_default());
             checkpoint.agent_id = Some(agent_run.agent_id.clone());
             checkpoint.agent_metadata = agent_run.agent_metadata.clone();
+            checkpoint.effort.tool_calls = agent_run
+                .transcript
+                .as_ref()
+                .map(|transcript| {
+                    transcript
+                        .messages()
+                        .iter()
+                        .filter(|message| message.is_tool_use())
+                        .count() as u32
+                })
+                .unwrap_or(0);
+            checkpoint.effort.tokens = agent_run.token_usage;
         }
         debug_log(&format!(
             "[BENCHMARK] Checkpoint creation took {:?}",
@@ -788,6 +824,7 @@ fn get_checkpoint_entry_for_file(
     head_tree_id: Arc<Option<String>>,
     initial_attributions: Arc<HashMap<String, Vec<LineAttribution>>>,
     ts: u128,
+    inherit: Option<InheritanceRules>,
 ) -> Result<Option<(WorkingLogEntry, FileLineStats)>, GitAiError> {
     let feature_flag_inter_commit_move = Config::get().get_feature_flags().inter_commit_move;
 
@@ -967,6 +1004,7 @@ fn get_checkpoint_entry_for_file(
     }
 
     let (entry, stats) = make_entry_for_file(
+        &Config::for_repo(&repo),
         &file_path,
         &file_content_hash,
         author_id.as_ref(),
@@ -974,6 +1012,7 @@ fn get_checkpoint_entry_for_file(
         &prev_attributions,
         &current_content,
         ts,
+        inherit.filter(|_| kind != CheckpointKind::Human),
     )?;
     debug_log(&format!(
         "[BENCHMARK] Processing file {} took {:?}",
@@ -993,6 +1032,7 @@ async fn get_checkpoint_entries(
     previous_checkpoints: &[Checkpoint],
     agent_run_result: Option<&AgentRunResult>,
     ts: u128,
+    inherit: Option<InheritanceRules>,
 ) -> Result<(Vec<WorkingLogEntry>, Vec<FileLineStats>), GitAiError> {
     let entries_fn_start = Instant::now();
 
@@ -1084,6 +1124,7 @@ async fn get_checkpoint_entries(
                     head_tree_id.clone(),
                     initial_attributions.clone(),
                     ts,
+                    inherit,
                 )
             })
             .await
@@ -1134,7 +1175,9 @@ async fn get_checkpoint_entries(
     Ok((entries, file_stats))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn make_entry_for_file(
+    config: &Config,
     file_path: &str,
     blob_sha: &str,
     author_id: &str,
@@ -1142,49 +1185,57 @@ fn make_entry_for_file(
     previous_attributions: &[Attribution],
     content: &str,
     ts: u128,
+    inherit: Option<InheritanceRules>,
 ) -> Result<(WorkingLogEntry, FileLineStats), GitAiError> {
-    let tracker = AttributionTracker::new();
-
-    let fill_start = Instant::now();
-    let filled_in_prev_attributions = tracker.attribute_unattributed_ranges(
+    let attribution_start = Instant::now();
+    let (mut new_attributions, mut line_attributions) = attribute_edit_with_granularity(
         previous_content,
         previous_attributions,
-        &CheckpointKind::Human.to_str(),
-        ts - 1,
-    );
-    debug_log(&format!(
-        "[BENCHMARK]   attribute_unattributed_ranges for {} took {:?}",
-        file_path,
-        fill_start.elapsed()
-    ));
-
-    let update_start = Instant::now();
-    let new_attributions = tracker.update_attributions(
-        previous_content,
         content,
-        &filled_in_prev_attributions,
         author_id,
         ts,
+        config.attribution_granularity_for(file_path),
     )?;
-    debug_log(&format!(
-        "[BENCHMARK]   update_attributions for {} took {:?}",
-        file_path,
-        update_start.elapsed()
-    ));
-
-    // TODO Consider discarding any "uncontentious" attributions for the human author. Any human attributions that do not share a line with any other author's attributions can be discarded.
-    // let filtered_attributions = crate::authorship::attribution_tracker::discard_uncontentious_attributions_for_author(&new_attributions, &CheckpointKind::Human.to_str());
-
-    let line_attr_start = Instant::now();
-    let line_attributions =
-        crate::authorship::attribution_tracker::attributions_to_line_attributions(
-            &new_attributions,
+    if let Some(rules) = inherit {
+        let (attributions, inherited) = inherit_attributions(
+            previous_content,
+            previous_attributions,
+            content,
+            new_attributions,
+            author_id,
+            &rules,
+            ts,
+        );
+        new_attributions = attributions;
+        if inherited > 0 {
+            debug_log(&format!(
+                "{} line(s) of {} kept their previous author",
+                inherited, file_path
+            ));
+            line_attributions = attributions_to_line_attributions(&new_attributions, content);
+        }
+    }
+    if config.preserve_whitespace_attribution() {
+        let (attributions, preserved) = preserve_whitespace_only_attributions(
+            previous_content,
+            previous_attributions,
             content,
+            new_attributions,
+            ts,
         );
+        new_attributions = attributions;
+        if preserved > 0 {
+            debug_log(&format!(
+                "{} reformatted line(s) of {} kept their previous author",
+                preserved, file_path
+            ));
+            line_attributions = attributions_to_line_attributions(&new_attributions, content);
+        }
+    }
     debug_log(&format!(
-        "[BENCHMARK]   attributions_to_line_attributions for {} took {:?}",
+        "[BENCHMARK]   attribute_edit for {} took {:?}",
         file_path,
-        line_attr_start.elapsed()
+        attribution_start.elapsed()
     ));
 
     // Compute line stats while we already have both contents in memory
@@ -1510,6 +1561,7 @@ mod tests {
             ]),
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         };
 
         // Run checkpoint - should not crash even with paths outside repo
@@ -1766,4 +1818,62 @@ mod tests {
             "Whitespace deletions ignored"
         );
     }
+
+    #[test]
+    fn test_checkpoint_records_agent_effort() {
+        use crate::authorship::transcript::{AiTranscript, Message};
+        use crate::authorship::working_log::TokenUsage;
+        use crate::commands::checkpoint_agent::agent_presets::AgentRunResult;
+
+        let (tmp_repo, mut lines_file, mut alphabet_file) =
+            TmpRepo::new_with_base_commit().unwrap();
+        let repo =
+            crate::git::repository::find_repository_in_path(tmp_repo.path().to_str().unwrap())
+                .expect("Repository should exist");
+        let base_commit = repo.head().unwrap().target().unwrap();
+        let working_log = repo.storage.working_log_for_base_commit(&base_commit);
+
+        lines_file.append("Agent line\n").unwrap();
+        alphabet_file.append("Another agent line\n").unwrap();
+
+        let tool_use = |name: &str| Message::ToolUse {
+            name: name.to_string(),
+            input: serde_json::json!({}),
+            timestamp: None,
+        };
+        let mut transcript = AiTranscript::new();
+        transcript.add_message(Message::user("Edit both files".to_string(), None));
+        transcript.add_message(tool_use("Edit"));
+        transcript.add_message(tool_use("Edit"));
+        let token_usage = Some(TokenUsage {
+            input_tokens: 1200,
+            output_tokens: 80,
+        });
+        tmp_repo
+            .trigger_checkpoint_with_agent_result(
+                "Claude",
+                Some(AgentRunResult {
+                    agent_id: AgentId {
+                        tool: "claude".to_string(),
+                        id: "session".to_string(),
+                        model: "model".to_string(),
+                    },
+                    agent_metadata: None,
+                    checkpoint_kind: CheckpointKind::AiAgent,
+                    transcript: Some(transcript),
+                    repo_working_dir: None,
+                    edited_filepaths: None,
+                    will_edit_filepaths: None,
+                    dirty_files: None,
+                    token_usage,
+                }),
+            )
+            .unwrap();
+
+        let checkpoints = working_log.read_all_checkpoints().unwrap();
+        let effort = &checkpoints.last().unwrap().effort;
+        assert_eq!(effort.files_touched, 2);
+        assert_eq!(effort.tool_calls, 2);
+        assert_eq!(effort.tokens, token_usage);
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{
     authorship::{
         transcript::{AiTranscript, Message},
-        working_log::{AgentId, CheckpointKind},
+        working_log::{AgentId, CheckpointKind, TokenUsage},
     },
     error::GitAiError,
     observability::log_error,
@@ -28,6 +28,9 @@ pub struct AgentRunResult {
     pub edited_filepaths: Option<Vec<String>>,
     pub will_edit_filepaths: Option<Vec<String>>,
     pub dirty_files: Option<HashMap<String, String>>,
+    /// Tokens the agent reports having used this session, for agents whose transcripts
+    /// record usage
+    pub token_usage: Option<TokenUsage>,
 }
 
 pub trait AgentCheckpointPreset {
@@ -92,6 +95,8 @@ impl AgentCheckpointPreset for ClaudePreset {
                 }
             };
 
+        let token_usage = ClaudePreset::token_usage_from_claude_code_jsonl(transcript_path);
+
         // The filename should be a UUID
         let agent_id = AgentId {
             tool: "claude".to_string(),
@@ -124,6 +129,7 @@ impl AgentCheckpointPreset for ClaudePreset {
                 edited_filepaths: None,
                 will_edit_filepaths: file_path_as_vec,
                 dirty_files: None,
+                token_usage: None,
             });
         }
 
@@ -137,11 +143,43 @@ impl AgentCheckpointPreset for ClaudePreset {
             edited_filepaths: file_path_as_vec,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage,
         })
     }
 }
 
 impl ClaudePreset {
+    /// Sum the usage Claude Code records on each assistant entry, None when the transcript
+    /// can't be read or records none
+    pub fn token_usage_from_claude_code_jsonl(transcript_path: &str) -> Option<TokenUsage> {
+        let jsonl_content = std::fs::read_to_string(transcript_path).ok()?;
+        let mut usage: Option<TokenUsage> = None;
+        for line in jsonl_content.lines() {
+            let Ok(raw_entry) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if raw_entry["type"].as_str() != Some("assistant") {
+                continue;
+            }
+            let entry_usage = &raw_entry["message"]["usage"];
+            if !entry_usage.is_object() {
+                continue;
+            }
+            let total = usage.get_or_insert_with(TokenUsage::default);
+            // Cached prompt tokens are still input the agent consumed
+            total.input_tokens += [
+                "input_tokens",
+                "cache_creation_input_tokens",
+                "cache_read_input_tokens",
+            ]
+            .iter()
+            .filter_map(|key| entry_usage[*key].as_u64())
+            .sum::<u64>();
+            total.output_tokens += entry_usage["output_tokens"].as_u64().unwrap_or(0);
+        }
+        usage
+    }
+
     /// Parse a Claude Code JSONL file into a transcript and extract model info
     pub fn transcript_and_model_from_claude_code_jsonl(
         transcript_path: &str,
@@ -331,6 +369,7 @@ impl AgentCheckpointPreset for GeminiPreset {
                 edited_filepaths: None,
                 will_edit_filepaths: file_path_as_vec,
                 dirty_files: None,
+                token_usage: None,
             });
         }
 
@@ -344,6 +383,7 @@ impl AgentCheckpointPreset for GeminiPreset {
             edited_filepaths: file_path_as_vec,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         })
     }
 }
@@ -539,6 +579,7 @@ impl AgentCheckpointPreset for ContinueCliPreset {
                 edited_filepaths: None,
                 will_edit_filepaths: file_path_as_vec,
                 dirty_files: None,
+                token_usage: None,
             });
         }
 
@@ -552,6 +593,7 @@ impl AgentCheckpointPreset for ContinueCliPreset {
             edited_filepaths: file_path_as_vec,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         })
     }
 }
@@ -762,6 +804,7 @@ impl AgentCheckpointPreset for CursorPreset {
                 edited_filepaths: None,
                 will_edit_filepaths: None,
                 dirty_files: None,
+                token_usage: None,
             });
         }
 
@@ -838,6 +881,7 @@ impl AgentCheckpointPreset for CursorPreset {
             edited_filepaths,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         })
     }
 }
@@ -1225,6 +1269,7 @@ impl AgentCheckpointPreset for GithubCopilotPreset {
                 edited_filepaths: None,
                 will_edit_filepaths: Some(will_edit_filepaths),
                 dirty_files,
+                token_usage: None,
             });
         }
 
@@ -1305,6 +1350,7 @@ impl AgentCheckpointPreset for GithubCopilotPreset {
             edited_filepaths: edited_filepaths.or(detected_edited_filepaths),
             will_edit_filepaths: None,
             dirty_files,
+            token_usage: None,
         })
     }
 }
@@ -1491,6 +1537,7 @@ impl AgentCheckpointPreset for DroidPreset {
                 edited_filepaths: None,
                 will_edit_filepaths: file_path_as_vec,
                 dirty_files: None,
+                token_usage: None,
             });
         }
 
@@ -1504,6 +1551,7 @@ impl AgentCheckpointPreset for DroidPreset {
             edited_filepaths: file_path_as_vec,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         })
     }
 }
@@ -2038,6 +2086,7 @@ impl AgentCheckpointPreset for AiTabPreset {
                 edited_filepaths: None,
                 will_edit_filepaths,
                 dirty_files,
+                token_usage: None,
             });
         }
 
@@ -2050,6 +2099,7 @@ impl AgentCheckpointPreset for AiTabPreset {
             edited_filepaths,
             will_edit_filepaths: None,
             dirty_files,
+            token_usage: None,
         })
     }
 }
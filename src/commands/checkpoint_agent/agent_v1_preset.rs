@@ -71,6 +71,7 @@ impl AgentCheckpointPreset for AgentV1Preset {
                 repo_working_dir: Some(repo_working_dir),
                 edited_filepaths: None,
                 dirty_files,
+                token_usage: None,
             }),
             AgentV1Input::AiAgent {
                 edited_filepaths,
@@ -93,6 +94,7 @@ impl AgentCheckpointPreset for AgentV1Preset {
                 edited_filepaths,
                 will_edit_filepaths: None,
                 dirty_files,
+                token_usage: None,
             }),
         }
     }
@@ -199,6 +199,7 @@ impl AgentCheckpointPreset for OpenCodePreset {
                 edited_filepaths: None,
                 will_edit_filepaths: file_path_as_vec,
                 dirty_files: None,
+                token_usage: None,
             });
         }
 
@@ -212,6 +213,7 @@ impl AgentCheckpointPreset for OpenCodePreset {
             edited_filepaths: file_path_as_vec,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         })
     }
 }
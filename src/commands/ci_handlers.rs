@@ -1,8 +1,111 @@
-use crate::ci::ci_context::{CiContext, CiEvent, CiRunResult};
-use crate::ci::github::{get_github_ci_context, install_github_ci_workflow};
-use crate::ci::gitlab::{get_gitlab_ci_context, print_gitlab_ci_yaml};
+use crate::ci::attribution_artifact::{
+    AttributionArtifact, artifact_path as attribution_artifact_path,
+};
+use crate::ci::backfill::{
+    BackfillOptions, DEFAULT_RATE_BUDGET, RateBudget, RateBudgets, parse_repo_list, run_backfill,
+};
+use crate::ci::bitbucket::{
+    BitbucketTemplateOptions, get_bitbucket_ci_context, print_bitbucket_pipelines_yaml,
+};
+use crate::ci::ci_context::{self, CiContext, CiEvent, CiRunResult};
+use crate::ci::circleci::{
+    CircleCiTemplateOptions, get_circleci_ci_context, print_circleci_config_yaml,
+};
+use crate::ci::codebuild::get_codebuild_ci_context;
+use crate::ci::credentials::CiGitCredential;
+use crate::ci::deadline;
+use crate::ci::generic::{GenericMerge, get_generic_ci_context};
+use crate::ci::gerrit::get_gerrit_ci_context;
+use crate::ci::gitea::{get_gitea_ci_context, install_gitea_ci_workflow};
+use crate::ci::github::{
+    get_github_ci_context, github_ingest_context, install_github_ci_workflow, post_check_run,
+    post_pull_request_comment, record_merge_group,
+};
+use crate::ci::gitlab::{
+    GitlabRunOptions, GitlabTemplateOptions, get_gitlab_ci_context, gitlab_git_credential,
+    gitlab_ingest_context, post_merge_request_comment, print_gitlab_ci_yaml,
+    process_earlier_merges,
+};
+use crate::ci::gitlab_scopes::explain_git_error;
+use crate::ci::handoff::{self, NotesArtifact, ingest_notes_artifact};
+use crate::ci::jenkins::{JenkinsTemplateOptions, get_jenkins_ci_context, print_jenkinsfile};
+use crate::ci::selftest::{Provider, run_selftest};
+use crate::ci::sweep::{SweepOptions, run_sweep};
+use crate::commands::sync_prompts::parse_since_arg;
+use crate::config::Config;
+use crate::error::GitAiError;
 use crate::git::repository::find_repository_in_path;
+use crate::memory;
 use crate::utils::debug_log;
+use std::path::{Path, PathBuf};
+
+/// `ci <provider> run`: build the provider's context with `get_context` (None is nothing
+/// to do), run it, report the result and tear the clone down unless `--no-cleanup` is in
+/// `args`. Exits 1 on any error.
+fn run_ci(
+    label: &str,
+    args: &[String],
+    get_context: impl FnOnce() -> Result<Option<CiContext>, GitAiError>,
+) -> ! {
+    run_ci_with(label, args, get_context, CiContext::run, |_, _| true)
+}
+
+/// [`run_ci`], running the context with `run` and following up on its result with
+/// `after_run` (comments, check runs) before the teardown; `after_run` returning false
+/// fails the job
+fn run_ci_with(
+    label: &str,
+    args: &[String],
+    get_context: impl FnOnce() -> Result<Option<CiContext>, GitAiError>,
+    run: impl FnOnce(&CiContext) -> Result<CiRunResult, GitAiError>,
+    after_run: impl FnOnce(&CiContext, &CiRunResult) -> bool,
+) -> ! {
+    let no_cleanup = args.iter().any(|a| a == "--no-cleanup");
+    let ci_context = match get_context() {
+        Ok(Some(ci_context)) => ci_context,
+        Ok(None) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("Failed to get {} context: {}", label, e);
+            std::process::exit(1);
+        }
+    };
+    debug_log(&format!("{} context: {:?}", label, ci_context));
+    let succeeded = match run(&ci_context) {
+        Ok(result) => {
+            debug_log(&format!("{} result: {:?}", label, result));
+            report_ci_result(&ci_context, &result, label);
+            after_run(&ci_context, &result)
+        }
+        Err(e) => {
+            eprintln!("Error running {} context: {}", label, e);
+            std::process::exit(1);
+        }
+    };
+    if !no_cleanup {
+        if let Err(e) = ci_context.teardown() {
+            eprintln!("Error tearing down {} context: {}", label, e);
+            std::process::exit(1);
+        }
+        debug_log(&format!("{} context teared down", label));
+    } else {
+        debug_log("Skipping teardown (--no-cleanup)");
+    }
+    std::process::exit(if succeeded { 0 } else { 1 });
+}
+
+/// Print a human-readable message for a CiRunResult and write the attribution artifact
+fn report_ci_result(context: &CiContext, result: &CiRunResult, prefix: &str) {
+    print_ci_result(result, prefix);
+    let path = attribution_artifact_path();
+    match AttributionArtifact::collect(context, result).and_then(|artifact| artifact.write(&path)) {
+        Ok(()) => println!("{}: wrote attribution to {}", prefix, path.display()),
+        Err(e) => eprintln!(
+            "Warning: could not write attribution to {}: {}",
+            path.display(),
+            e
+        ),
+    }
+}
 
 /// Print a human-readable message for a CiRunResult
 fn print_ci_result(result: &CiRunResult, prefix: &str) {
@@ -25,10 +128,41 @@ fn print_ci_result(result: &CiRunResult, prefix: &str) {
                 prefix
             );
         }
+        CiRunResult::NotesWrittenToArtifact { commits, path } => {
+            println!(
+                "{}: notes of {} commit(s) written to {} for an ingest job to push",
+                prefix,
+                commits.len(),
+                path.display()
+            );
+        }
+        CiRunResult::DryRun { commits } => {
+            println!(
+                "{}: dry run, notes of {} commit(s) not pushed",
+                prefix,
+                commits.len()
+            );
+        }
+        CiRunResult::PushChecked { commits, missing } if missing.is_empty() => {
+            println!(
+                "{}: all {} pushed commit(s) have authorship notes",
+                prefix, commits
+            );
+        }
+        CiRunResult::PushChecked { commits, missing } => {
+            println!(
+                "{}: {} of {} pushed commit(s) have no authorship note (pushed without git-ai?)",
+                prefix,
+                missing.len(),
+                commits
+            );
+        }
     }
 }
 
 pub fn handle_ci(args: &[String]) {
+    let args = apply_deadline_flag(args);
+    let args = args.as_slice();
     if args.is_empty() {
         print_ci_help_and_exit();
     }
@@ -40,9 +174,39 @@ pub fn handle_ci(args: &[String]) {
         "gitlab" => {
             handle_ci_gitlab(&args[1..]);
         }
+        "bitbucket" => {
+            handle_ci_bitbucket(&args[1..]);
+        }
+        "gitea" => {
+            handle_ci_gitea(&args[1..]);
+        }
+        "gerrit" => {
+            handle_ci_gerrit(&args[1..]);
+        }
+        "codebuild" => {
+            handle_ci_codebuild(&args[1..]);
+        }
+        "jenkins" => {
+            handle_ci_jenkins(&args[1..]);
+        }
+        "circleci" => {
+            handle_ci_circleci(&args[1..]);
+        }
+        "generic" => {
+            handle_ci_generic(&args[1..]);
+        }
         "local" => {
             handle_ci_local(&args[1..]);
         }
+        "selftest" => {
+            handle_ci_selftest(&args[1..]);
+        }
+        "sweep" => {
+            handle_ci_sweep(&args[1..]);
+        }
+        "backfill" => {
+            handle_ci_backfill(&args[1..]);
+        }
         _ => {
             eprintln!("Unknown ci subcommand: {}", args[0]);
             print_ci_help_and_exit();
@@ -57,41 +221,76 @@ fn handle_ci_github(args: &[String]) {
     // Subcommands: install | (default: run in CI context)
     match args[0].as_str() {
         "run" => {
-            let no_cleanup = args[1..].iter().any(|a| a == "--no-cleanup");
-            let ci_context = get_github_ci_context();
-            match ci_context {
-                Ok(Some(ci_context)) => {
-                    debug_log(&format!("GitHub CI context: {:?}", ci_context));
-                    match ci_context.run() {
-                        Ok(result) => {
-                            debug_log(&format!("GitHub CI result: {:?}", result));
-                            print_ci_result(&result, "GitHub CI");
-                        }
-                        Err(e) => {
-                            eprintln!("Error running GitHub CI context: {}", e);
-                            std::process::exit(1);
-                        }
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            apply_notes_artifact_flag(&args[1..]);
+            if std::env::var("GITHUB_EVENT_NAME").as_deref() == Ok("merge_group") {
+                if let Err(e) = record_merge_group() {
+                    eprintln!("Failed to record the merge queue commit: {}", e);
+                    std::process::exit(1);
+                }
+                std::process::exit(0);
+            }
+            run_ci_with(
+                "GitHub CI",
+                &args[1..],
+                || match get_github_ci_context()? {
+                    Some(ci_context) => Ok(Some(ci_context)),
+                    None => Err(GitAiError::Generic(
+                        "No GitHub CI context found".to_string(),
+                    )),
+                },
+                CiContext::run,
+                |ci_context, result| {
+                    if let CiRunResult::AuthorshipRewritten { commits, .. } = result
+                        && Config::get().ci_comment()
+                        && let Err(e) = post_pull_request_comment(ci_context, commits)
+                    {
+                        eprintln!("Warning: could not comment on the pull request: {}", e);
                     }
-                    if !no_cleanup {
-                        if let Err(e) = ci_context.teardown() {
-                            eprintln!("Error tearing down GitHub CI context: {}", e);
-                            std::process::exit(1);
-                        }
-                        debug_log("GitHub CI context teared down");
-                    } else {
-                        debug_log("Skipping teardown (--no-cleanup)");
+                    if let CiRunResult::AuthorshipRewritten { commits, .. } = result
+                        && Config::get().ci_check_run()
+                        && let Err(e) = post_check_run(ci_context, commits)
+                    {
+                        eprintln!("Warning: could not add the check run: {}", e);
                     }
-                    std::process::exit(0);
-                }
+                    true
+                },
+            );
+        }
+        "ingest" => {
+            let no_cleanup = args[1..].iter().any(|a| a == "--no-cleanup");
+            let artifact = read_notes_artifact_arg(&args[1..]);
+            let ci_context = match github_ingest_context(&artifact) {
+                Ok(ci_context) => ci_context,
                 Err(e) => {
                     eprintln!("Failed to get GitHub CI context: {}", e);
                     std::process::exit(1);
                 }
-                Ok(None) => {
-                    eprintln!("No GitHub CI context found");
+            };
+            debug_log(&format!("GitHub CI context: {:?}", ci_context));
+            match ingest_notes_artifact(&ci_context, &artifact) {
+                Ok(result) => {
+                    debug_log(&format!("GitHub CI result: {:?}", result));
+                    report_ci_result(&ci_context, &result, "GitHub CI");
+                    // The fork's run couldn't comment either
+                    if let CiRunResult::AuthorshipRewritten { commits, .. } = &result
+                        && Config::get().ci_comment()
+                        && let Err(e) = post_pull_request_comment(&ci_context, commits)
+                    {
+                        eprintln!("Warning: could not comment on the pull request: {}", e);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error ingesting notes artifact: {}", e);
                     std::process::exit(1);
                 }
             }
+            if !no_cleanup && let Err(e) = ci_context.teardown() {
+                eprintln!("Error tearing down GitHub CI context: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
         }
         "install" => match install_github_ci_workflow() {
             Ok(path) => {
@@ -117,44 +316,97 @@ fn handle_ci_gitlab(args: &[String]) {
     // Subcommands: install | run
     match args[0].as_str() {
         "run" => {
-            let no_cleanup = args[1..].iter().any(|a| a == "--no-cleanup");
-            let ci_context = get_gitlab_ci_context();
-            match ci_context {
-                Ok(Some(ci_context)) => {
-                    debug_log(&format!("GitLab CI context: {:?}", ci_context));
-                    match ci_context.run() {
-                        Ok(result) => {
-                            debug_log(&format!("GitLab CI result: {:?}", result));
-                            print_ci_result(&result, "GitLab CI");
-                        }
-                        Err(e) => {
-                            eprintln!("Error running GitLab CI context: {}", e);
-                            std::process::exit(1);
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            apply_notes_artifact_flag(&args[1..]);
+            let options = parse_gitlab_run_options(&args[1..]);
+            let batch_ok = std::cell::Cell::new(true);
+            run_ci_with(
+                "GitLab CI",
+                &args[1..],
+                || get_gitlab_ci_context(&options),
+                |ci_context| {
+                    // Earlier merges first, so notes land oldest first
+                    if options.batch {
+                        match process_earlier_merges(ci_context, &options) {
+                            Ok(Some(report)) => {
+                                print!("{}", report.render_as("GitLab CI earlier merges"));
+                                batch_ok.set(report.succeeded());
+                            }
+                            Ok(None) => {}
+                            Err(e) => {
+                                eprintln!("Error processing earlier merges: {}", e);
+                                batch_ok.set(false);
+                            }
                         }
                     }
-                    if !no_cleanup {
-                        if let Err(e) = ci_context.teardown() {
-                            eprintln!("Error tearing down GitLab CI context: {}", e);
-                            std::process::exit(1);
-                        }
-                        debug_log("GitLab CI context teared down");
-                    } else {
-                        debug_log("Skipping teardown (--no-cleanup)");
+                    ci_context.run().map_err(|e| {
+                        let token_source = gitlab_git_credential()
+                            .map(|c| CiGitCredential::token_env_var(&c).to_string())
+                            .unwrap_or_else(|| "the token".to_string());
+                        explain_git_error(&token_source, e)
+                    })
+                },
+                |ci_context, result| {
+                    if let CiRunResult::AuthorshipRewritten { commits, .. } = result
+                        && Config::get().ci_comment()
+                        && let Err(e) = post_merge_request_comment(ci_context, commits, &options)
+                    {
+                        eprintln!("Warning: could not comment on the merge request: {}", e);
                     }
-                    std::process::exit(0);
-                }
+                    batch_ok.get()
+                },
+            );
+        }
+        "ingest" => {
+            let no_cleanup = args[1..].iter().any(|a| a == "--no-cleanup");
+            let artifact = read_notes_artifact_arg(&args[1..]);
+            let ci_context = match gitlab_ingest_context(&artifact) {
+                Ok(ci_context) => ci_context,
                 Err(e) => {
                     eprintln!("Failed to get GitLab CI context: {}", e);
                     std::process::exit(1);
                 }
-                Ok(None) => {
-                    // No matching MR found - this is not an error, just nothing to do
-                    std::process::exit(0);
+            };
+            debug_log(&format!("GitLab CI context: {:?}", ci_context));
+            match ingest_notes_artifact(&ci_context, &artifact) {
+                Ok(result) => {
+                    debug_log(&format!("GitLab CI result: {:?}", result));
+                    report_ci_result(&ci_context, &result, "GitLab CI");
+                    if let CiRunResult::AuthorshipRewritten { commits, .. } = &result
+                        && Config::get().ci_comment()
+                        && let Err(e) = post_merge_request_comment(
+                            &ci_context,
+                            commits,
+                            &GitlabRunOptions::default(),
+                        )
+                    {
+                        eprintln!("Warning: could not comment on the merge request: {}", e);
+                    }
+                }
+                Err(e) => {
+                    let token_source = gitlab_git_credential()
+                        .map(|c| CiGitCredential::token_env_var(&c).to_string())
+                        .unwrap_or_else(|| "the token".to_string());
+                    eprintln!(
+                        "Error ingesting notes artifact: {}",
+                        explain_git_error(&token_source, e)
+                    );
+                    std::process::exit(1);
                 }
             }
+            if !no_cleanup && let Err(e) = ci_context.teardown() {
+                eprintln!("Error tearing down GitLab CI context: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
         }
         "install" => {
-            print_gitlab_ci_yaml();
+            let options = parse_gitlab_template_options(&args[1..]);
+            if let Err(e) = print_gitlab_ci_yaml(&options) {
+                eprintln!("Failed to render GitLab CI config: {}", e);
+                std::process::exit(1);
+            }
             std::process::exit(0);
         }
         other => {
@@ -164,6 +416,615 @@ fn handle_ci_gitlab(args: &[String]) {
     }
 }
 
+fn handle_ci_bitbucket(args: &[String]) {
+    if args.is_empty() {
+        print_ci_bitbucket_help_and_exit();
+    }
+    // Subcommands: install | run
+    match args[0].as_str() {
+        "run" => {
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            run_ci("Bitbucket Pipelines", &args[1..], get_bitbucket_ci_context);
+        }
+        "install" => {
+            let mut options = BitbucketTemplateOptions::default();
+            match &args[1..] {
+                [] => {}
+                [flag, branch] if flag == "--branch" => options.branch = Some(branch.clone()),
+                _ => print_ci_bitbucket_help_and_exit(),
+            }
+            if let Err(e) = print_bitbucket_pipelines_yaml(&options) {
+                eprintln!("Failed to render Bitbucket Pipelines config: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
+        other => {
+            eprintln!("Unknown ci bitbucket subcommand: {}", other);
+            print_ci_help_and_exit();
+        }
+    }
+}
+
+fn handle_ci_gitea(args: &[String]) {
+    if args.is_empty() {
+        print_ci_gitea_help_and_exit();
+    }
+    // Subcommands: install | run
+    match args[0].as_str() {
+        "run" => {
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            run_ci("Gitea Actions", &args[1..], get_gitea_ci_context);
+        }
+        "install" => match install_gitea_ci_workflow() {
+            Ok(path) => {
+                println!("Installed Gitea Actions workflow to {}", path.display());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("Failed to install Gitea Actions workflow: {}", e);
+                std::process::exit(1);
+            }
+        },
+        other => {
+            eprintln!("Unknown ci gitea subcommand: {}", other);
+            print_ci_help_and_exit();
+        }
+    }
+}
+
+fn handle_ci_gerrit(args: &[String]) {
+    if args.is_empty() {
+        print_ci_gerrit_help_and_exit();
+    }
+    // Subcommands: run
+    match args[0].as_str() {
+        "run" => {
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            run_ci("Gerrit", &args[1..], get_gerrit_ci_context);
+        }
+        other => {
+            eprintln!("Unknown ci gerrit subcommand: {}", other);
+            print_ci_help_and_exit();
+        }
+    }
+}
+
+fn handle_ci_codebuild(args: &[String]) {
+    if args.is_empty() {
+        print_ci_codebuild_help_and_exit();
+    }
+    // Subcommands: run
+    match args[0].as_str() {
+        "run" => {
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            run_ci("CodeBuild", &args[1..], get_codebuild_ci_context);
+        }
+        other => {
+            eprintln!("Unknown ci codebuild subcommand: {}", other);
+            print_ci_help_and_exit();
+        }
+    }
+}
+
+fn handle_ci_jenkins(args: &[String]) {
+    if args.is_empty() {
+        print_ci_jenkins_help_and_exit();
+    }
+    // Subcommands: install | run
+    match args[0].as_str() {
+        "run" => {
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            run_ci("Jenkins", &args[1..], get_jenkins_ci_context);
+        }
+        "install" => {
+            let mut options = JenkinsTemplateOptions::default();
+            let mut i = 1;
+            while i < args.len() {
+                match (args[i].as_str(), args.get(i + 1)) {
+                    ("--branch", Some(branch)) => options.branch = Some(branch.clone()),
+                    ("--credentials-id", Some(id)) => options.credentials_id = Some(id.clone()),
+                    _ => print_ci_jenkins_help_and_exit(),
+                }
+                i += 2;
+            }
+            if let Err(e) = print_jenkinsfile(&options) {
+                eprintln!("Failed to render Jenkinsfile stage: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
+        other => {
+            eprintln!("Unknown ci jenkins subcommand: {}", other);
+            print_ci_help_and_exit();
+        }
+    }
+}
+
+fn handle_ci_circleci(args: &[String]) {
+    if args.is_empty() {
+        print_ci_circleci_help_and_exit();
+    }
+    // Subcommands: install | run
+    match args[0].as_str() {
+        "run" => {
+            apply_max_memory_flag(&args[1..]);
+            apply_dry_run_flag(&args[1..]);
+            run_ci("CircleCI", &args[1..], get_circleci_ci_context);
+        }
+        "install" => {
+            let mut options = CircleCiTemplateOptions::default();
+            match &args[1..] {
+                [] => {}
+                [flag, branch] if flag == "--branch" => options.branch = Some(branch.clone()),
+                _ => print_ci_circleci_help_and_exit(),
+            }
+            if let Err(e) = print_circleci_config_yaml(&options) {
+                eprintln!("Failed to render CircleCI config: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
+        other => {
+            eprintln!("Unknown ci circleci subcommand: {}", other);
+            print_ci_help_and_exit();
+        }
+    }
+}
+
+fn handle_ci_sweep(args: &[String]) {
+    apply_max_memory_flag(args);
+    let mut provider = None;
+    let mut since = "7d".to_string();
+    let mut options = SweepOptions {
+        since: 0,
+        branch: None,
+        dry_run: false,
+        no_cleanup: false,
+    };
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--provider" | "--since" | "--branch" | "--max-memory" if i + 1 >= args.len() => {
+                eprintln!("Missing value for flag {}", args[i]);
+                std::process::exit(1);
+            }
+            "--provider" => {
+                let name = &args[i + 1];
+                match Provider::parse(name) {
+                    Some(p) => provider = Some(p),
+                    None => {
+                        eprintln!("Unknown provider '{}': expected github or gitlab", name);
+                        std::process::exit(1);
+                    }
+                }
+                i += 1;
+            }
+            "--since" => {
+                since = args[i + 1].clone();
+                i += 1;
+            }
+            "--branch" => {
+                options.branch = Some(args[i + 1].clone());
+                i += 1;
+            }
+            // Handled by apply_max_memory_flag
+            "--max-memory" => i += 1,
+            "--dry-run" => options.dry_run = true,
+            "--no-cleanup" => options.no_cleanup = true,
+            other => {
+                eprintln!("Unknown ci sweep argument: {}", other);
+                print_ci_help_and_exit();
+            }
+        }
+        i += 1;
+    }
+
+    options.since = match parse_since_arg(&since) {
+        Ok(timestamp) => timestamp,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let Some(provider) = provider.or_else(Provider::detect) else {
+        eprintln!("Could not detect the CI provider; pass --provider github or --provider gitlab");
+        std::process::exit(1);
+    };
+    match run_sweep(provider, &options) {
+        Ok(report) => {
+            println!("{}", report.render());
+            std::process::exit(if report.succeeded() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("Failed to run CI sweep: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn handle_ci_backfill(args: &[String]) {
+    apply_max_memory_flag(args);
+    let mut repos_path = None;
+    let mut since = None;
+    let mut options = BackfillOptions {
+        since: None,
+        state_path: "git-ai-backfill-state.json".into(),
+        work_dir: "git-ai-ci-backfill".into(),
+        budgets: RateBudgets::new(DEFAULT_RATE_BUDGET),
+        dry_run: false,
+        no_cleanup: false,
+    };
+    let mut host_rates = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let value = || {
+            args.get(i + 1).cloned().unwrap_or_else(|| {
+                eprintln!("Missing value for flag {}", args[i]);
+                std::process::exit(1);
+            })
+        };
+        match args[i].as_str() {
+            "--repos" => repos_path = Some(value()),
+            "--state" => options.state_path = value().into(),
+            "--work-dir" => options.work_dir = value().into(),
+            "--since" => since = Some(value()),
+            "--rate" => host_rates.push(value()),
+            "--default-rate" => match RateBudget::parse(&value()) {
+                Ok(budget) => options.budgets = RateBudgets::new(budget),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            },
+            // Handled by apply_max_memory_flag
+            "--max-memory" => {}
+            "--dry-run" => {
+                options.dry_run = true;
+                i += 1;
+                continue;
+            }
+            "--no-cleanup" => {
+                options.no_cleanup = true;
+                i += 1;
+                continue;
+            }
+            other => {
+                eprintln!("Unknown ci backfill argument: {}", other);
+                print_ci_help_and_exit();
+            }
+        }
+        i += 2;
+    }
+
+    for rate in &host_rates {
+        let budget = rate
+            .split_once('=')
+            .ok_or_else(|| {
+                format!(
+                    "Invalid --rate '{}': expected <host>=<requests>/<period>",
+                    rate
+                )
+            })
+            .and_then(|(host, budget)| {
+                RateBudget::parse(budget)
+                    .map(|budget| (host.to_string(), budget))
+                    .map_err(|e| e.to_string())
+            });
+        match budget {
+            Ok((host, budget)) => options.budgets.set(&host, budget),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(since) = since {
+        options.since = match parse_since_arg(&since) {
+            Ok(timestamp) => Some(timestamp),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+    }
+    let Some(repos_path) = repos_path else {
+        eprintln!("--repos <file> is required");
+        std::process::exit(1);
+    };
+    let repos = match std::fs::read_to_string(&repos_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_repo_list(&text).map_err(|e| e.to_string()))
+    {
+        Ok(repos) => repos,
+        Err(e) => {
+            eprintln!("Failed to read repository list {}: {}", repos_path, e);
+            std::process::exit(1);
+        }
+    };
+
+    match run_backfill(&repos, &mut options) {
+        Ok(state) => {
+            println!("{}", state.render(&repos));
+            std::process::exit(if state.succeeded() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("Failed to run CI backfill: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn handle_ci_selftest(args: &[String]) {
+    let mut provider = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--provider" => {
+                let Some(name) = args.get(i + 1) else {
+                    eprintln!("Missing value for flag --provider");
+                    std::process::exit(1);
+                };
+                match Provider::parse(name) {
+                    Some(p) => provider = Some(p),
+                    None => {
+                        eprintln!("Unknown provider '{}': expected github or gitlab", name);
+                        std::process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            other => {
+                eprintln!("Unknown ci selftest argument: {}", other);
+                print_ci_help_and_exit();
+            }
+        }
+    }
+
+    let Some(provider) = provider.or_else(Provider::detect) else {
+        eprintln!("Could not detect the CI provider; pass --provider github or --provider gitlab");
+        std::process::exit(1);
+    };
+    match run_selftest(provider) {
+        Ok(report) => {
+            println!("{}", report.render());
+            std::process::exit(if report.passed() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("Failed to run CI selftest: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Apply `--max-memory <size>` before anything reads the memory budget, and say when the
+/// run will be memory-bounded
+fn apply_max_memory_flag(args: &[String]) {
+    if let Some(i) = args.iter().position(|a| a == "--max-memory") {
+        let Some(value) = args.get(i + 1) else {
+            eprintln!("Missing value for flag --max-memory");
+            std::process::exit(1);
+        };
+        match memory::parse_memory_size(value) {
+            Ok(bytes) => memory::set_max_memory(bytes),
+            Err(e) => {
+                eprintln!("Error: --max-memory: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if memory::is_low_memory()
+        && let Some(limit) = memory::memory_limit()
+    {
+        println!(
+            "Low-memory mode: {} MiB available, processing files sequentially",
+            limit >> 20
+        );
+    }
+}
+
+/// Apply `--notes-artifact <path>`, so a fork's run writes its notes there instead of
+/// pushing them
+fn apply_dry_run_flag(args: &[String]) {
+    if args.iter().any(|a| a == "--dry-run") {
+        ci_context::set_dry_run();
+    }
+}
+
+fn apply_notes_artifact_flag(args: &[String]) {
+    if let Some(i) = args.iter().position(|a| a == "--notes-artifact") {
+        let Some(path) = args.get(i + 1) else {
+            eprintln!("Missing value for flag --notes-artifact");
+            std::process::exit(1);
+        };
+        handoff::set_artifact_path(PathBuf::from(path));
+    }
+}
+
+/// The notes artifact `ci <provider> ingest <path>` was given
+fn read_notes_artifact_arg(args: &[String]) -> NotesArtifact {
+    let Some(path) = args.iter().find(|a| !a.starts_with("--")) else {
+        eprintln!("Usage: git-ai ci <github|gitlab> ingest <artifact> [--no-cleanup]");
+        std::process::exit(1);
+    };
+    match NotesArtifact::read(Path::new(path)) {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Start the CI deadline from `--deadline <duration>`, wherever it's passed, else from
+/// GIT_AI_CI_DEADLINE, and return the arguments without the flag
+fn apply_deadline_flag(args: &[String]) -> Vec<String> {
+    let mut rest = Vec::new();
+    let mut value = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--deadline" {
+            let Some(budget) = iter.next() else {
+                eprintln!("Missing value for flag --deadline");
+                std::process::exit(1);
+            };
+            value = Some(budget.clone());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    let value = value.or_else(|| {
+        std::env::var(deadline::DEADLINE_ENV_VAR)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+    });
+    if let Some(value) = value {
+        match deadline::parse(&value) {
+            Ok(budget) => deadline::start(budget),
+            Err(e) => {
+                eprintln!("Error: --deadline: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    rest
+}
+
+fn parse_gitlab_run_options(args: &[String]) -> GitlabRunOptions {
+    let mut options = GitlabRunOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--project-id" | "--project-path" | "--commit-sha" | "--clone"
+                if i + 1 >= args.len() =>
+            {
+                eprintln!("Error: {} requires a value", args[i]);
+                print_ci_gitlab_help_and_exit();
+            }
+            "--project-id" => {
+                options.project_id = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--project-path" => {
+                options.project_path = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--commit-sha" => {
+                options.commit_sha = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--clone" => {
+                options.clone = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--in-place" => options.in_place = true,
+            "--batch" => options.batch = true,
+            // Handled by the caller
+            "--no-cleanup" | "--dry-run" => {}
+            "--max-memory" | "--notes-artifact" => i += 1,
+            other => {
+                eprintln!("Unknown option: {}", other);
+                print_ci_gitlab_help_and_exit();
+            }
+        }
+        i += 1;
+    }
+    options
+}
+
+fn parse_gitlab_template_options(args: &[String]) -> GitlabTemplateOptions {
+    let mut options = GitlabTemplateOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--token-var" | "--branch" if i + 1 >= args.len() => {
+                eprintln!("Error: {} requires a value", args[i]);
+                print_ci_gitlab_help_and_exit();
+            }
+            "--token-var" => {
+                options.token_var = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--branch" => {
+                options.branches.push(args[i + 1].clone());
+                i += 1;
+            }
+            "--protected-only" => options.protected_only = true,
+            other => {
+                eprintln!("Unknown option: {}", other);
+                print_ci_gitlab_help_and_exit();
+            }
+        }
+        i += 1;
+    }
+    options
+}
+
+fn handle_ci_generic(args: &[String]) {
+    if args.is_empty() {
+        print_ci_generic_help_and_exit();
+    }
+    apply_max_memory_flag(args);
+    apply_dry_run_flag(args);
+
+    let mut merge = GenericMerge::default();
+    let mut i = 0;
+    while i < args.len() {
+        let field = match args[i].as_str() {
+            "--merge-sha" => &mut merge.merge_sha,
+            "--head-ref" => &mut merge.head_ref,
+            "--head-sha" => &mut merge.head_sha,
+            "--base-ref" => &mut merge.base_ref,
+            "--base-sha" => merge.base_sha.get_or_insert_with(String::new),
+            "--max-memory" => {
+                i += 2;
+                continue;
+            }
+            "--dry-run" => {
+                i += 1;
+                continue;
+            }
+            "--help" | "-h" => print_ci_generic_help_and_exit(),
+            other => {
+                eprintln!("Unknown argument: {}", other);
+                print_ci_generic_help_and_exit();
+            }
+        };
+        let Some(value) = args.get(i + 1) else {
+            eprintln!("Missing value for flag {}", args[i]);
+            std::process::exit(1);
+        };
+        *field = value.clone();
+        i += 2;
+    }
+
+    let ci_context = match get_generic_ci_context(merge) {
+        Ok(ci_context) => ci_context,
+        Err(e) => {
+            eprintln!("Failed to build generic CI context: {}", e);
+            std::process::exit(1);
+        }
+    };
+    debug_log(&format!("Generic CI context: {:?}", ci_context));
+    match ci_context.run() {
+        Ok(result) => {
+            debug_log(&format!("Generic CI result: {:?}", result));
+            report_ci_result(&ci_context, &result, "Generic CI");
+        }
+        Err(e) => {
+            eprintln!("Error running generic CI context: {}", e);
+            std::process::exit(1);
+        }
+    }
+    // Runs in the job's checkout, so there's no clone to tear down
+    std::process::exit(0);
+}
+
 fn handle_ci_local(args: &[String]) {
     if args.is_empty() {
         print_ci_local_help_and_exit();
@@ -171,6 +1032,8 @@ fn handle_ci_local(args: &[String]) {
 
     let event = args[0].as_str();
     let event_args: &[String] = &args[1..];
+    apply_max_memory_flag(event_args);
+    apply_dry_run_flag(event_args);
 
     // Simple flag parser over remaining args: --key value
     let flag = |name: &str| -> Option<String> {
@@ -250,6 +1113,7 @@ fn handle_ci_local(args: &[String]) {
                     head_sha,
                     base_ref,
                     base_sha,
+                    merge_request: None,
                 },
                 // Not used for local runs; teardown not invoked
                 temp_dir: std::path::PathBuf::from("."),
@@ -259,7 +1123,41 @@ fn handle_ci_local(args: &[String]) {
             match ctx.run() {
                 Ok(result) => {
                     debug_log(&format!("Local CI result: {:?}", result));
-                    print_ci_result(&result, "Local CI (merge)");
+                    report_ci_result(&ctx, &result, "Local CI (merge)");
+                }
+                Err(e) => {
+                    eprintln!("Error running local CI: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            std::process::exit(0);
+        }
+        "push" => {
+            let Some(ref_name) = flag("--ref") else {
+                eprintln!("--ref is required (e.g., main)");
+                std::process::exit(1);
+            };
+            let Some(after_sha) = flag("--after-sha") else {
+                eprintln!("--after-sha is required");
+                std::process::exit(1);
+            };
+
+            let ctx = CiContext {
+                repo,
+                event: CiEvent::Push {
+                    ref_name,
+                    before_sha: flag("--before-sha"),
+                    after_sha,
+                },
+                // Not used for local runs; teardown not invoked
+                temp_dir: std::path::PathBuf::from("."),
+            };
+
+            debug_log(&format!("Local CI context: {:?}", ctx));
+            match ctx.run() {
+                Ok(result) => {
+                    debug_log(&format!("Local CI result: {:?}", result));
+                    report_ci_result(&ctx, &result, "Local CI (push)");
                 }
                 Err(e) => {
                     eprintln!("Error running local CI: {}", e);
@@ -282,17 +1180,126 @@ fn print_ci_help_and_exit() -> ! {
     eprintln!();
     eprintln!("Subcommands:");
     eprintln!("  github           GitHub CI");
-    eprintln!("    run [--no-cleanup]  Run GitHub CI in current repo");
+    eprintln!("    run [--no-cleanup] [--max-memory <size>]  Run GitHub CI in current repo");
+    eprintln!("    ingest <artifact>  Push the notes a fork's run wrote with --notes-artifact");
     eprintln!("    install        Install/update workflow in current repo");
     eprintln!("  gitlab           GitLab CI");
-    eprintln!("    run [--no-cleanup]  Run GitLab CI in current repo");
+    eprintln!("    run [--no-cleanup] [--max-memory <size>]  Run GitLab CI in current repo");
+    eprintln!("    ingest <artifact>  Push the notes a fork's run wrote with --notes-artifact");
     eprintln!("    install        Print YAML snippet to add to .gitlab-ci.yml");
+    eprintln!("  bitbucket        Bitbucket Pipelines");
+    eprintln!(
+        "    run [--no-cleanup] [--max-memory <size>]  Run Bitbucket Pipelines in current repo"
+    );
+    eprintln!("    install        Print YAML snippet to add to bitbucket-pipelines.yml");
+    eprintln!("  gitea            Gitea and Forgejo Actions");
+    eprintln!("    run [--no-cleanup] [--max-memory <size>]  Run Gitea Actions in current repo");
+    eprintln!("    install        Install/update workflow in current repo");
+    eprintln!("  gerrit           Gerrit (from a job triggered by change-merged events)");
+    eprintln!("    run [--no-cleanup] [--max-memory <size>]  Run for the submitted change");
+    eprintln!("  codebuild        AWS CodeBuild, for CodeCommit repositories");
+    eprintln!("    run [--no-cleanup] [--max-memory <size>]  Run for the merged pull request");
+    eprintln!("  jenkins          Jenkins");
+    eprintln!("    run [--max-memory <size>]  Run in the job's checkout");
+    eprintln!("    install        Print a stage to add to the Jenkinsfile");
+    eprintln!("  circleci         CircleCI");
+    eprintln!("    run [--max-memory <size>]  Run in the job's checkout");
+    eprintln!("    install        Print YAML snippet to add to .circleci/config.yml");
+    eprintln!("  selftest         Check the CI token can list and fetch merge requests, read");
+    eprintln!("                   commits and push notes, without writing anything");
+    eprintln!("    --provider <github|gitlab>  Provider to check (default: detected)");
+    eprintln!("  sweep            Find recent merges on the default branch that are missing");
+    eprintln!("                   rewritten attribution and process them (run on a schedule)");
+    eprintln!("    --since <when>               How far back to look (default: 7d)");
+    eprintln!("    --branch <name>              Branch to sweep (default: the default branch)");
+    eprintln!("    --provider <github|gitlab>   Forge to query (default: detected)");
+    eprintln!("    --dry-run                    Only report what would be processed");
+    eprintln!("    --no-cleanup                 Keep the clone afterwards");
+    eprintln!("  generic          Any other CI system, with the merge given as flags");
+    eprintln!(
+        "    --merge-sha <sha> --head-ref <ref> --head-sha <sha> --base-ref <ref> [--base-sha <sha>]"
+    );
+    eprintln!("  backfill         Sweep many repositories unattended, within API rate budgets,");
+    eprintln!("                   saving progress so a restarted run resumes");
+    eprintln!("    --repos <file>               `github owner/repo` or `gitlab group/project`");
+    eprintln!("                                 per line, optionally followed by a branch");
+    eprintln!(
+        "    --state <path>               Progress file (default: git-ai-backfill-state.json)"
+    );
+    eprintln!("    --since <when>               How far back to look (default: all history)");
+    eprintln!(
+        "    --rate <host>=<n>/<period>   Budget for an API host, e.g. api.github.com=4000/h"
+    );
+    eprintln!("    --default-rate <n>/<period>  Budget for other hosts (default: 3000/h)");
+    eprintln!("    --work-dir <dir>             Where to clone (default: git-ai-ci-backfill)");
+    eprintln!("    --dry-run                    Only report what would be processed");
+    eprintln!("    --no-cleanup                 Keep the clones afterwards");
     eprintln!("  local            Run CI locally by event name and flags");
     eprintln!("                   Usage: git-ai ci local <event> [flags]");
     eprintln!("                   Events:");
     eprintln!(
         "                     merge  --merge-commit-sha <sha> --base-ref <ref> --head-ref <ref> --head-sha <sha> --base-sha <sha>"
     );
+    eprintln!("                     push   --ref <ref> --after-sha <sha> [--before-sha <sha>]");
+    eprintln!();
+    eprintln!(
+        "  --max-memory <size>  Memory available to the run (e.g. 512M). Below 1G, files are"
+    );
+    eprintln!("                       processed sequentially and in batches. Defaults to");
+    eprintln!("                       $GIT_AI_MAX_MEMORY, then the cgroup memory limit.");
+    eprintln!("  --deadline <duration>  Time budget for the whole command (e.g. 20m), or set");
+    eprintln!("                         GIT_AI_CI_DEADLINE. Past it, no further API request or");
+    eprintln!("                         fetch/push starts; sweep and backfill report progress.");
+    eprintln!("  --dry-run            For a provider's run, local or generic: look up the merge");
+    eprintln!("                       and rewrite the notes in the clone, then print them");
+    eprintln!("                       instead of pushing.");
+    eprintln!();
+    eprintln!("Rewritten notes record the CI system and pipeline URL they came from.");
+    eprintln!("  GIT_AI_CI_PIPELINE_URL  Pipeline URL to record, where it isn't detected");
+    eprintln!("  GIT_AI_CI_SIGN          Sign the pushed notes commit: ssh:<key file>,");
+    eprintln!("                          gpg[:<key id>] or sigstore (keyless, via gitsign)");
+    eprintln!("  GIT_AI_SECURITY_SCAN_COMMAND  Rescan the rewritten AI hunks with this command");
+    eprintln!("                          and store its finding tags (security_scan_command)");
+    eprintln!("  GIT_AI_CI_NOTES_ARTIFACT  Write the rewritten notes to this file instead of");
+    eprintln!("                          pushing them (--notes-artifact), for a fork's token");
+    eprintln!();
+    eprintln!("Each run writes its result, with the AI and human lines each commit added per");
+    eprintln!("file and the SHAs of the notes it wrote, to git-ai-attribution.json.");
+    eprintln!("  GIT_AI_CI_ATTRIBUTION_ARTIFACT  Write it to this path instead");
+    eprintln!();
+    eprintln!("Forge API requests that hit a rate limit or a 5xx are retried with backoff.");
+    eprintln!("  GIT_AI_HTTP_MAX_ATTEMPTS  Attempts per request before giving up (default 4)");
+    eprintln!("  GIT_AI_HTTP_TIMEOUT       Seconds to wait for each response, for every request");
+    eprintln!("                            (default 30, or the request's own)");
+    eprintln!("  GIT_AI_HTTP_BACKEND       minreq (default), or ureq to reuse connections");
+    eprintln!("                            (or reqwest, for HTTP/2, in builds with that feature)");
+    eprintln!("  HTTPS_PROXY, HTTP_PROXY   Proxy to send them through, except to NO_PROXY hosts");
+    eprintln!();
+    eprintln!("Self-hosted forges with an internal CA (for API requests and git over HTTPS):");
+    eprintln!("  GIT_AI_CA_BUNDLE     PEM file of CA certificates to trust (config: ca_bundle)");
+    eprintln!("  GIT_AI_TLS_INSECURE  Set to 1 to skip certificate verification (tls_insecure)");
+    std::process::exit(1);
+}
+
+fn print_ci_generic_help_and_exit() -> ! {
+    eprintln!("git-ai ci generic - Rewrite authorship for a merge on any CI system");
+    eprintln!();
+    eprintln!(
+        "Usage: git-ai ci generic --merge-sha <sha> --head-ref <ref> --head-sha <sha> --base-ref <ref>"
+    );
+    eprintln!();
+    eprintln!("For CI systems without a git-ai provider (TeamCity, Bamboo, Drone, ...). Runs in");
+    eprintln!("the job's checkout, fetching the head from origin if the checkout lacks it, and");
+    eprintln!("pushes the rewritten notes to origin.");
+    eprintln!();
+    eprintln!("Flags:");
+    eprintln!("  --merge-sha <sha>    Commit the merge produced on the base branch");
+    eprintln!("  --head-ref <ref>     Branch that was merged");
+    eprintln!("  --head-sha <sha>     Tip of that branch when it was merged");
+    eprintln!("  --base-ref <ref>     Branch it was merged into");
+    eprintln!("  --base-sha <sha>     Base branch before the merge (default: the merge commit's");
+    eprintln!("                       first parent)");
+    eprintln!("  --max-memory <size>  Memory available to the run (e.g. 512M)");
     std::process::exit(1);
 }
 
@@ -305,6 +1312,11 @@ fn print_ci_local_help_and_exit() -> ! {
     eprintln!(
         "  merge  --merge-commit-sha <sha> --base-ref <ref> --head-ref <ref> --head-sha <sha> --base-sha <sha>"
     );
+    eprintln!("  push   --ref <ref> --after-sha <sha> [--before-sha <sha>]");
+    eprintln!("         Check the pushed commits have authorship notes on the remote");
+    eprintln!();
+    eprintln!("Flags:");
+    eprintln!("  --max-memory <size>  Memory available to the run (e.g. 512M)");
     std::process::exit(1);
 }
 
@@ -316,6 +1328,36 @@ fn print_ci_github_help_and_exit() -> ! {
     eprintln!("Subcommands:");
     eprintln!("  run [--no-cleanup]   Run GitHub CI in current repo");
     eprintln!("                       --no-cleanup  Skip teardown after run");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       Authenticates with GITHUB_TOKEN, or as a GitHub App when");
+    eprintln!("                       GITHUB_APP_ID and GITHUB_APP_PRIVATE_KEY (or");
+    eprintln!(
+        "                       GITHUB_APP_PRIVATE_KEY_PATH) are set. GITHUB_APP_INSTALLATION_ID"
+    );
+    eprintln!("                       is looked up from the repository if unset. With");
+    eprintln!("                       GIT_AI_OIDC_EXCHANGE_URL set, the job's OIDC token is");
+    eprintln!("                       exchanged for a token there first");
+    eprintln!("                       On a push event, checks the pushed commits have");
+    eprintln!("                       authorship notes instead of rewriting a merge");
+    eprintln!("                       On a merge_group event, records the pull request the");
+    eprintln!("                       merge queue's commit was built for; the push that lands");
+    eprintln!("                       the commit on the base branch rewrites it from that");
+    eprintln!("                       pull request");
+    eprintln!("                       With GIT_AI_CI_COMMENT=1 (or ci_comment), comments the");
+    eprintln!("                       AI vs human lines per file on the pull request (needs");
+    eprintln!("                       `pull-requests: write`)");
+    eprintln!("                       With GIT_AI_CI_CHECK_RUN=1 (or ci_check_run), adds a");
+    eprintln!("                       check run to the merge commit annotating its AI-authored");
+    eprintln!("                       lines (needs `checks: write`)");
+    eprintln!("                       --notes-artifact <path>  Write the notes to <path>");
+    eprintln!("                                     instead of pushing them, for pull requests");
+    eprintln!("                                     from forks, whose token can't push");
+    eprintln!("  ingest <artifact>    Push the notes in an artifact from a fork's run, from a");
+    eprintln!("                       workflow_run job in the base repository. Only notes for");
+    eprintln!("                       the merge commit on its base branch and the commits it");
+    eprintln!("                       landed are pushed, and existing notes are kept");
+    eprintln!("                       --no-cleanup  Skip teardown after run");
     eprintln!("  install              Install/update workflow in current repo");
     std::process::exit(1);
 }
@@ -328,6 +1370,173 @@ fn print_ci_gitlab_help_and_exit() -> ! {
     eprintln!("Subcommands:");
     eprintln!("  run [--no-cleanup]   Run GitLab CI in current repo");
     eprintln!("                       --no-cleanup  Skip teardown after run");
+    eprintln!("                       --project-id <id>     Project whose merge to rewrite");
+    eprintln!("                       --project-path <path> Its path (looked up if omitted)");
+    eprintln!("                       --commit-sha <sha>    Merge commit to rewrite");
+    eprintln!("                                     (defaults: UPSTREAM_PROJECT_ID,");
+    eprintln!("                                     UPSTREAM_PROJECT_PATH and UPSTREAM_COMMIT_SHA");
+    eprintln!("                                     in pipelines triggered by another pipeline,");
+    eprintln!("                                     else CI_PROJECT_ID, CI_PROJECT_PATH and");
+    eprintln!("                                     CI_COMMIT_SHA)");
+    eprintln!("                       --clone <full|partial>  How to clone (default: full, or");
+    eprintln!("                                     GIT_AI_CI_CLONE). partial fetches the last");
+    eprintln!("                                     GIT_AI_CI_CLONE_DEPTH (50) commits without");
+    eprintln!("                                     file contents, deepening to the MR's merge");
+    eprintln!("                                     base, for large repositories");
+    eprintln!("                       --in-place  Use the job's checkout instead of cloning,");
+    eprintln!("                                     fetching the target branch and MR into it");
+    eprintln!("                                     (deepened like partial when shallow)");
+    eprintln!("                       --batch     Also process merges since the last");
+    eprintln!("                                     successful pipeline (or the previous");
+    eprintln!("                                     push) that have no authorship yet");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       Tokens, first set wins: GITLAB_TOKEN, GITLAB_GROUP_TOKEN,");
+    eprintln!("                       then CI_JOB_TOKEN. A deploy token (GITLAB_DEPLOY_USER and");
+    eprintln!(
+        "                       GITLAB_DEPLOY_TOKEN, or CI_DEPLOY_USER and CI_DEPLOY_PASSWORD)"
+    );
+    eprintln!("                       is used for git ahead of CI_JOB_TOKEN. With");
+    eprintln!("                       GIT_AI_OIDC_EXCHANGE_URL set, the GIT_AI_ID_TOKEN ID token");
+    eprintln!("                       is exchanged for a token there, used before all of these");
+    eprintln!("                       Without an MR pipeline, searches MRs merged within");
+    eprintln!("                       GIT_AI_CI_MERGE_WINDOW (default 15m) before the pipeline");
+    eprintln!("                       was created (CI_PIPELINE_CREATED_AT)");
+    eprintln!("                       In a push pipeline whose commit no MR produced, checks");
+    eprintln!("                       the pushed commits have authorship notes instead");
+    eprintln!("                       With GIT_AI_CI_COMMENT=1 (or ci_comment), comments the");
+    eprintln!("                       AI vs human lines per file on the MR (needs a token with");
+    eprintln!("                       the api scope)");
+    eprintln!("                       --notes-artifact <path>  Write the notes to <path>");
+    eprintln!("                                     instead of pushing them, for MRs from forks,");
+    eprintln!("                                     whose job token can't push");
+    eprintln!("  ingest <artifact>    Push the notes in an artifact from a fork's pipeline,");
+    eprintln!("                       from a job in the parent project (CI_PROJECT_PATH). Only");
+    eprintln!("                       notes for the merge commit on its target branch and the");
+    eprintln!("                       commits it landed are pushed, and existing notes are kept");
+    eprintln!("                       --no-cleanup  Skip teardown after run");
     eprintln!("  install              Print YAML snippet to add to .gitlab-ci.yml");
+    eprintln!("                       --token-var <name>  CI/CD variable holding the token");
+    eprintln!("                                           (default: GITLAB_TOKEN)");
+    eprintln!("                       --branch <name|/regex/>  Run on this branch (repeatable;");
+    eprintln!("                                           default: the default branch)");
+    eprintln!("                       --protected-only    Only run on protected branches");
+    std::process::exit(1);
+}
+
+fn print_ci_jenkins_help_and_exit() -> ! {
+    eprintln!("git-ai ci jenkins - Jenkins utilities");
+    eprintln!();
+    eprintln!("Usage: git-ai ci jenkins <subcommand> [args...]");
+    eprintln!();
+    eprintln!("Subcommands:");
+    eprintln!("  run                  Rewrite authorship for the branch merged as GIT_COMMIT,");
+    eprintln!("                       working in the job's checkout without a forge API");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       The target branch is CHANGE_TARGET, BRANCH_NAME or");
+    eprintln!("                       GIT_BRANCH. The merged branch is CHANGE_BRANCH (or");
+    eprintln!("                       PR-<CHANGE_ID>) when set, else whichever remote branch");
+    eprintln!("                       GIT_COMMIT squashed or rebased");
+    eprintln!("  install              Print a stage to add to the Jenkinsfile");
+    eprintln!("                       --branch <name|glob>      Branch to run on (default: main)");
+    eprintln!("                       --credentials-id <id>     Jenkins credentials that can push");
+    eprintln!("                                                 (default: git-ai-push)");
+    std::process::exit(1);
+}
+
+fn print_ci_circleci_help_and_exit() -> ! {
+    eprintln!("git-ai ci circleci - CircleCI utilities");
+    eprintln!();
+    eprintln!("Usage: git-ai ci circleci <subcommand> [args...]");
+    eprintln!();
+    eprintln!("Subcommands:");
+    eprintln!("  run                  Rewrite authorship for the branch merged as CIRCLE_SHA1");
+    eprintln!("                       into CIRCLE_BRANCH, working in the job's checkout");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       The merged branch is the pull request in");
+    eprintln!("                       CIRCLE_PULL_REQUEST when set, else whichever recently");
+    eprintln!("                       built branch CIRCLE_SHA1 squashed or rebased, from the");
+    eprintln!("                       CircleCI API (CIRCLECI_TOKEN; CIRCLECI_PROJECT_SLUG for");
+    eprintln!("                       GitHub App projects; CIRCLECI_API_URL for server installs)");
+    eprintln!("  install              Print YAML snippet to add to .circleci/config.yml");
+    eprintln!("                       --branch <name>  Branch to run on (default: main)");
+    std::process::exit(1);
+}
+
+fn print_ci_gitea_help_and_exit() -> ! {
+    eprintln!("git-ai ci gitea - Gitea and Forgejo Actions utilities");
+    eprintln!();
+    eprintln!("Usage: git-ai ci gitea <subcommand> [args...]");
+    eprintln!();
+    eprintln!("Subcommands:");
+    eprintln!("  run [--no-cleanup]   Rewrite authorship for the merged pull request that");
+    eprintln!("                       produced GITHUB_SHA");
+    eprintln!("                       --no-cleanup  Skip teardown after run");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       Authenticates with GITEA_TOKEN, else GITHUB_TOKEN. The");
+    eprintln!("                       API is <GITHUB_SERVER_URL>/api/v1 unless GITEA_API_URL");
+    eprintln!("                       is set");
+    eprintln!("  install              Install/update workflow in current repo");
+    std::process::exit(1);
+}
+
+fn print_ci_gerrit_help_and_exit() -> ! {
+    eprintln!("git-ai ci gerrit - Gerrit utilities");
+    eprintln!();
+    eprintln!("Usage: git-ai ci gerrit <subcommand> [args...]");
+    eprintln!();
+    eprintln!("Subcommands:");
+    eprintln!("  run [--no-cleanup]   Rewrite authorship for the submitted change that produced");
+    eprintln!("                       GERRIT_NEWREV (else GIT_COMMIT) in GERRIT_PROJECT");
+    eprintln!("                       --no-cleanup  Skip teardown after run");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       The server is GERRIT_URL, else taken from");
+    eprintln!("                       GERRIT_CHANGE_URL. Authenticates with GERRIT_USERNAME and");
+    eprintln!("                       GERRIT_HTTP_PASSWORD. When Gerrit rebased or");
+    eprintln!("                       cherry-picked the change on submit, authorship comes from");
+    eprintln!("                       the last patch set that changed the code");
+    std::process::exit(1);
+}
+
+fn print_ci_codebuild_help_and_exit() -> ! {
+    eprintln!("git-ai ci codebuild - AWS CodeBuild utilities");
+    eprintln!();
+    eprintln!("Usage: git-ai ci codebuild <subcommand> [args...]");
+    eprintln!();
+    eprintln!("Subcommands:");
+    eprintln!("  run [--no-cleanup]   Rewrite authorship for the CodeCommit pull request merged");
+    eprintln!("                       as CODEBUILD_RESOLVED_SOURCE_VERSION");
+    eprintln!("                       --no-cleanup  Skip teardown after run");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       Searches CODECOMMIT_PULL_REQUEST_ID if set, else recently");
+    eprintln!("                       closed pull requests. Calls CodeCommit with the build's");
+    eprintln!("                       service role (or AWS_ACCESS_KEY_ID); the role needs");
+    eprintln!("                       codecommit:ListPullRequests, GetPullRequest, GitPull and");
+    eprintln!("                       GitPush. Git uses CODECOMMIT_GIT_USERNAME and");
+    eprintln!("                       CODECOMMIT_GIT_PASSWORD if set, else the AWS CLI's");
+    eprintln!("                       credential helper");
+    std::process::exit(1);
+}
+
+fn print_ci_bitbucket_help_and_exit() -> ! {
+    eprintln!("git-ai ci bitbucket - Bitbucket Pipelines utilities");
+    eprintln!();
+    eprintln!("Usage: git-ai ci bitbucket <subcommand> [args...]");
+    eprintln!();
+    eprintln!("Subcommands:");
+    eprintln!("  run [--no-cleanup]   Rewrite authorship for the merged pull request that");
+    eprintln!("                       produced BITBUCKET_COMMIT");
+    eprintln!("                       --no-cleanup  Skip teardown after run");
+    eprintln!("                       --max-memory <size>  Memory available to the run");
+    eprintln!("                                     (e.g. 512M; default: cgroup limit)");
+    eprintln!("                       Authenticates with BITBUCKET_ACCESS_TOKEN, or an app");
+    eprintln!("                       password in BITBUCKET_USERNAME and BITBUCKET_APP_PASSWORD");
+    eprintln!("  install              Print YAML snippet to add to bitbucket-pipelines.yml");
+    eprintln!("                       --branch <name|glob>  Branch to run on (default: main)");
     std::process::exit(1);
 }
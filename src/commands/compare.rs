@@ -0,0 +1,161 @@
+use crate::authorship::branch_compare::{BranchComparison, LineCounts, compare_branches};
+use crate::config::Config;
+use crate::git::find_repository;
+
+pub fn handle_compare(args: &[String]) {
+    let mut revs: Vec<String> = Vec::new();
+    let mut ignore_patterns: Vec<String> = Vec::new();
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--json" => json_output = true,
+            "--ignore" => {
+                let Some(pattern) = args.get(i + 1) else {
+                    eprintln!("Error: --ignore requires a value");
+                    std::process::exit(1);
+                };
+                ignore_patterns.push(pattern.clone());
+                i += 1;
+            }
+            arg if arg.starts_with("--") => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                print_compare_help();
+                std::process::exit(1);
+            }
+            arg => revs.push(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let Some((base, head)) = parse_revs(&revs) else {
+        print_compare_help();
+        std::process::exit(1);
+    };
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // Plus the repository's `.git-ai.toml` ignore list
+    ignore_patterns.extend(Config::for_repo(&repo).ignore_patterns().iter().cloned());
+
+    let comparison = match compare_branches(&repo, &base, &head, &ignore_patterns) {
+        Ok(comparison) => comparison,
+        Err(e) => {
+            eprintln!("Compare failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&comparison) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize comparison: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_comparison(&comparison);
+    }
+}
+
+/// `base..head`, `base...head` (both compare from the merge base), or `base [head]`
+/// with head defaulting to HEAD
+fn parse_revs(revs: &[String]) -> Option<(String, String)> {
+    let non_empty = |rev: &str| {
+        if rev.is_empty() {
+            "HEAD".to_string()
+        } else {
+            rev.to_string()
+        }
+    };
+    match revs {
+        [range] if range.contains("..") => {
+            let (base, head) = range.split_once("...").or_else(|| range.split_once(".."))?;
+            Some((non_empty(base), non_empty(head)))
+        }
+        [base] => Some((base.clone(), "HEAD".to_string())),
+        [base, head] => Some((base.clone(), head.clone())),
+        _ => None,
+    }
+}
+
+fn print_compare_help() {
+    eprintln!("Usage: git-ai compare <base>..<head> [--ignore <pattern>]... [--json]");
+    eprintln!("       git-ai compare <base> [<head>]");
+}
+
+fn print_comparison(comparison: &BranchComparison) {
+    println!(
+        "{}..{}: {} commit(s) since merge base {}",
+        comparison.base,
+        comparison.head,
+        comparison.commits,
+        &comparison.merge_base[..comparison.merge_base.len().min(8)]
+    );
+    if comparison.commits_without_notes > 0 {
+        println!(
+            "warning: {} commit(s) have no authorship note; their lines count as human",
+            comparison.commits_without_notes
+        );
+    }
+    println!();
+
+    println!(
+        "{:<60} {:>8} {:>8} {:>8} {:>6}",
+        "file", "added", "ai", "human", "ai%"
+    );
+    if comparison.files.is_empty() {
+        println!("  (no added lines)");
+    }
+    for (path, counts) in &comparison.files {
+        print_row(path, counts);
+    }
+    print_row("total", &comparison.totals);
+
+    if !comparison.totals.by_agent.is_empty() {
+        println!();
+        println!("{:<60} {:>8}", "agent", "ai");
+        for (agent, lines) in &comparison.totals.by_agent {
+            println!("{:<60} {:>8}", agent, lines);
+        }
+    }
+}
+
+fn print_row(name: &str, counts: &LineCounts) {
+    println!(
+        "{:<60} {:>8} {:>8} {:>8} {:>5.1}%",
+        name,
+        counts.added_lines,
+        counts.ai_lines,
+        counts.human_lines,
+        counts.ai_share() * 100.0
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revs(args: &[&str]) -> Option<(String, String)> {
+        parse_revs(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_revs() {
+        let pair = |base: &str, head: &str| Some((base.to_string(), head.to_string()));
+        assert_eq!(revs(&["main..feature"]), pair("main", "feature"));
+        assert_eq!(revs(&["main...feature"]), pair("main", "feature"));
+        assert_eq!(revs(&["main.."]), pair("main", "HEAD"));
+        assert_eq!(revs(&["main"]), pair("main", "HEAD"));
+        assert_eq!(revs(&["main", "feature"]), pair("main", "feature"));
+        assert_eq!(revs(&[]), None);
+        assert_eq!(revs(&["a", "b", "c"]), None);
+    }
+}
@@ -1,5 +1,9 @@
 use dirs;
 use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::authorship::attribution_tracker::AttributionGranularity;
+use crate::config::ProvenanceIdentity;
 
 use crate::git::repository::find_repository_in_path;
 
@@ -110,6 +114,55 @@ fn print_config_help() {
     eprintln!("  include_prompts_in_repositories  Repos to include for prompt storage (array)");
     eprintln!("  default_prompt_storage       Fallback storage mode for non-included repos");
     eprintln!("  quiet                        Suppress chart output after commits (bool)");
+    eprintln!("  work_item_pattern            Regex for work-item IDs in branch names");
+    eprintln!("  test_path_patterns           Globs classifying files as test code (array)");
+    eprintln!("  fully_ai_threshold           Min AI % of added lines for a fully-AI commit");
+    eprintln!("  ai_assisted_threshold        Min AI % of added lines for an AI-assisted commit");
+    eprintln!("  attribution_neutral_commits  Commits blame and stats skip over, e.g. formatter");
+    eprintln!("                               runs (array of SHAs)");
+    eprintln!("  neutral_whitespace_commits   Treat whitespace-only commits as neutral (bool)");
+    eprintln!("  preserve_whitespace_attribution  Lines an edit only reindents or re-wraps keep");
+    eprintln!("                               their previous author (bool)");
+    eprintln!("  attribution_granularity      Units edits are attributed in, per file extension");
+    eprintln!(
+        "                               (object, e.g. {{\"md\": \"word\", \"yaml\": \"line\"}};"
+    );
+    eprintln!("                               line, word, token (default) or statement)");
+    eprintln!("  trusted_provenance_identities  CI identities whose Sigstore signatures vouch");
+    eprintln!("                               for notes (array of {{\"issuer\", \"subject\"}};");
+    eprintln!("                               subject is a regex)");
+    eprintln!("  no_network                   Fail any HTTP request or remote git operation");
+    eprintln!("                               instead of reaching the network (bool)");
+    eprintln!("  ca_bundle                    PEM file of extra CA certificates to trust for");
+    eprintln!("                               forge HTTPS (or set GIT_AI_CA_BUNDLE)");
+    eprintln!("  tls_insecure                 Skip forge HTTPS certificate verification (bool,");
+    eprintln!("                               or set GIT_AI_TLS_INSECURE=1)");
+    eprintln!("  ci_comment                   Comment the attribution summary on merge/pull");
+    eprintln!("                               requests CI rewrites (bool, or set");
+    eprintln!("                               GIT_AI_CI_COMMENT=1)");
+    eprintln!("  ci_check_run                 Add a GitHub check run annotating AI-authored");
+    eprintln!("                               lines of merge commits CI rewrites (bool, or set");
+    eprintln!("                               GIT_AI_CI_CHECK_RUN=1)");
+    eprintln!("  security_scan_command        Command that scans new AI hunks (JSON on stdin)");
+    eprintln!("                               and prints findings to tag them with (or set");
+    eprintln!("                               GIT_AI_SECURITY_SCAN_COMMAND)");
+    eprintln!("  note_details_threshold       Notes over this many bytes keep a summary on");
+    eprintln!("                               refs/notes/ai and their line maps on");
+    eprintln!("                               refs/notes/ai-details, fetched when needed (or set");
+    eprintln!("                               GIT_AI_NOTE_DETAILS_THRESHOLD)");
+    eprintln!("  note_delta_interval          Write every Nth note in full and the rest as");
+    eprintln!("                               deltas against their parent's (or set");
+    eprintln!("                               GIT_AI_NOTE_DELTA_INTERVAL)");
+    eprintln!();
+    eprintln!("Repository Config (.git-ai.toml at the repository root):");
+    eprintln!("  ignore                       Globs left out of stats and compare (array)");
+    eprintln!("  [agents] allow               Agents whose checkpoints are recorded (array)");
+    eprintln!("  [policy]                     work_item_pattern, test_path_patterns,");
+    eprintln!("                               fully_ai_threshold, ai_assisted_threshold,");
+    eprintln!("                               attribution_neutral_commits,");
+    eprintln!("                               neutral_whitespace_commits,");
+    eprintln!("                               preserve_whitespace_attribution and");
+    eprintln!("                               attribution_granularity, overriding this file");
     eprintln!();
     eprintln!("Repository Patterns:");
     eprintln!("  For exclude/allow/exclude_prompts_in_repositories, you can provide:");
@@ -208,6 +261,14 @@ pub fn handle_config(args: &[String]) {
     }
 }
 
+/// The config in effect here, with the current repository's `.git-ai.toml` if in one
+fn runtime_config() -> std::sync::Arc<crate::config::Config> {
+    match crate::git::find_repository(&Vec::<String>::new()) {
+        Ok(repo) => crate::config::Config::for_repo(&repo),
+        Err(_) => crate::config::Config::get(),
+    }
+}
+
 fn show_all_config() -> Result<(), String> {
     let file_config = crate::config::load_file_config_public()?;
 
@@ -215,7 +276,7 @@ fn show_all_config() -> Result<(), String> {
     let mut effective_config = serde_json::Map::new();
 
     // Get the actual runtime config
-    let runtime_config = crate::config::Config::get();
+    let runtime_config = runtime_config();
 
     // Add fields with their effective values
     effective_config.insert(
@@ -303,6 +364,88 @@ fn show_all_config() -> Result<(), String> {
     }
 
     effective_config.insert("quiet".to_string(), Value::Bool(runtime_config.is_quiet()));
+    effective_config.insert(
+        "work_item_pattern".to_string(),
+        Value::String(runtime_config.work_item_pattern().to_string()),
+    );
+    effective_config.insert(
+        "test_path_patterns".to_string(),
+        serde_json::to_value(runtime_config.test_path_patterns()).unwrap_or(Value::Array(vec![])),
+    );
+    effective_config.insert(
+        "fully_ai_threshold".to_string(),
+        serde_json::json!(runtime_config.fully_ai_threshold()),
+    );
+    effective_config.insert(
+        "ai_assisted_threshold".to_string(),
+        serde_json::json!(runtime_config.ai_assisted_threshold()),
+    );
+    effective_config.insert(
+        "attribution_neutral_commits".to_string(),
+        serde_json::to_value(runtime_config.attribution_neutral_commits())
+            .unwrap_or(Value::Array(vec![])),
+    );
+    effective_config.insert(
+        "neutral_whitespace_commits".to_string(),
+        Value::Bool(runtime_config.neutral_whitespace_commits()),
+    );
+    effective_config.insert(
+        "preserve_whitespace_attribution".to_string(),
+        Value::Bool(runtime_config.preserve_whitespace_attribution()),
+    );
+    effective_config.insert(
+        "attribution_granularity".to_string(),
+        attribution_granularity_value(&runtime_config),
+    );
+    effective_config.insert(
+        "trusted_provenance_identities".to_string(),
+        serde_json::to_value(runtime_config.trusted_provenance_identities())
+            .unwrap_or(Value::Array(vec![])),
+    );
+    effective_config.insert(
+        "no_network".to_string(),
+        Value::Bool(runtime_config.no_network()),
+    );
+    effective_config.insert(
+        "ca_bundle".to_string(),
+        runtime_config
+            .ca_bundle()
+            .map(|path| Value::String(path.to_string()))
+            .unwrap_or(Value::Null),
+    );
+    effective_config.insert(
+        "tls_insecure".to_string(),
+        Value::Bool(runtime_config.tls_insecure()),
+    );
+    effective_config.insert(
+        "ci_comment".to_string(),
+        Value::Bool(runtime_config.ci_comment()),
+    );
+    effective_config.insert(
+        "ci_check_run".to_string(),
+        Value::Bool(runtime_config.ci_check_run()),
+    );
+    effective_config.insert(
+        "security_scan_command".to_string(),
+        runtime_config
+            .security_scan_command()
+            .map(|command| Value::String(command.to_string()))
+            .unwrap_or(Value::Null),
+    );
+    effective_config.insert(
+        "note_details_threshold".to_string(),
+        runtime_config
+            .note_details_threshold()
+            .map(Value::from)
+            .unwrap_or(Value::Null),
+    );
+    effective_config.insert(
+        "note_delta_interval".to_string(),
+        runtime_config
+            .note_delta_interval()
+            .map(Value::from)
+            .unwrap_or(Value::Null),
+    );
 
     // Feature flags - show effective flags with defaults applied
     let flags_value = serde_json::to_value(runtime_config.get_feature_flags())
@@ -324,7 +467,7 @@ fn show_all_config() -> Result<(), String> {
 
 fn get_config_value(key: &str) -> Result<(), String> {
     let file_config = crate::config::load_file_config_public()?;
-    let runtime_config = crate::config::Config::get();
+    let runtime_config = runtime_config();
 
     let key_path = parse_key_path(key);
 
@@ -392,6 +535,46 @@ fn get_config_value(key: &str) -> Result<(), String> {
                 }
             }
             "quiet" => Value::Bool(runtime_config.is_quiet()),
+            "work_item_pattern" => Value::String(runtime_config.work_item_pattern().to_string()),
+            "test_path_patterns" => serde_json::to_value(runtime_config.test_path_patterns())
+                .unwrap_or(Value::Array(vec![])),
+            "fully_ai_threshold" => serde_json::json!(runtime_config.fully_ai_threshold()),
+            "ai_assisted_threshold" => serde_json::json!(runtime_config.ai_assisted_threshold()),
+            "attribution_neutral_commits" => {
+                serde_json::to_value(runtime_config.attribution_neutral_commits())
+                    .unwrap_or(Value::Array(vec![]))
+            }
+            "neutral_whitespace_commits" => {
+                Value::Bool(runtime_config.neutral_whitespace_commits())
+            }
+            "preserve_whitespace_attribution" => {
+                Value::Bool(runtime_config.preserve_whitespace_attribution())
+            }
+            "attribution_granularity" => attribution_granularity_value(&runtime_config),
+            "trusted_provenance_identities" => {
+                serde_json::to_value(runtime_config.trusted_provenance_identities())
+                    .unwrap_or(Value::Array(vec![]))
+            }
+            "no_network" => Value::Bool(runtime_config.no_network()),
+            "ca_bundle" => runtime_config
+                .ca_bundle()
+                .map(|path| Value::String(path.to_string()))
+                .unwrap_or(Value::Null),
+            "tls_insecure" => Value::Bool(runtime_config.tls_insecure()),
+            "ci_comment" => Value::Bool(runtime_config.ci_comment()),
+            "ci_check_run" => Value::Bool(runtime_config.ci_check_run()),
+            "security_scan_command" => runtime_config
+                .security_scan_command()
+                .map(|command| Value::String(command.to_string()))
+                .unwrap_or(Value::Null),
+            "note_details_threshold" => runtime_config
+                .note_details_threshold()
+                .map(Value::from)
+                .unwrap_or(Value::Null),
+            "note_delta_interval" => runtime_config
+                .note_delta_interval()
+                .map(Value::from)
+                .unwrap_or(Value::Null),
             _ => return Err(format!("Unknown config key: {}", key)),
         };
 
@@ -553,6 +736,161 @@ fn set_config_value(key: &str, value: &str, add_mode: bool) -> Result<(), String
                 crate::config::save_file_config(&file_config)?;
                 eprintln!("[quiet]: {}", bool_value);
             }
+            "work_item_pattern" => {
+                if let Err(e) = regex::Regex::new(value) {
+                    return Err(format!("Invalid work_item_pattern '{}': {}", value, e));
+                }
+                file_config.work_item_pattern = Some(value.to_string());
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[work_item_pattern]: {}", value);
+            }
+            "test_path_patterns" => {
+                let added =
+                    set_string_array_field(&mut file_config.test_path_patterns, value, add_mode)?;
+                for pattern in &added {
+                    glob::Pattern::new(pattern)
+                        .map_err(|e| format!("Invalid glob pattern '{}': {}", pattern, e))?;
+                }
+                crate::config::save_file_config(&file_config)?;
+                log_array_changes(&added, add_mode);
+            }
+            "fully_ai_threshold" => {
+                file_config.fully_ai_threshold = Some(parse_percent(value)?);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[fully_ai_threshold]: {}", value);
+            }
+            "ai_assisted_threshold" => {
+                file_config.ai_assisted_threshold = Some(parse_percent(value)?);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[ai_assisted_threshold]: {}", value);
+            }
+            "attribution_neutral_commits" => {
+                let added = set_string_array_field(
+                    &mut file_config.attribution_neutral_commits,
+                    value,
+                    add_mode,
+                )?;
+                crate::config::save_file_config(&file_config)?;
+                log_array_changes(&added, add_mode);
+            }
+            "neutral_whitespace_commits" => {
+                let bool_value = parse_bool(value)?;
+                file_config.neutral_whitespace_commits = Some(bool_value);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[neutral_whitespace_commits]: {}", bool_value);
+            }
+            "preserve_whitespace_attribution" => {
+                let bool_value = parse_bool(value)?;
+                file_config.preserve_whitespace_attribution = Some(bool_value);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[preserve_whitespace_attribution]: {}", bool_value);
+            }
+            "no_network" => {
+                let bool_value = parse_bool(value)?;
+                file_config.no_network = Some(bool_value);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[no_network]: {}", bool_value);
+            }
+            "ca_bundle" => {
+                if !std::path::Path::new(value).is_file() {
+                    return Err(format!("CA bundle not found: {}", value));
+                }
+                file_config.ca_bundle = Some(value.to_string());
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[ca_bundle]: {}", value);
+            }
+            "tls_insecure" => {
+                let bool_value = parse_bool(value)?;
+                file_config.tls_insecure = Some(bool_value);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[tls_insecure]: {}", bool_value);
+            }
+            "ci_comment" => {
+                let bool_value = parse_bool(value)?;
+                file_config.ci_comment = Some(bool_value);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[ci_comment]: {}", bool_value);
+            }
+            "ci_check_run" => {
+                let bool_value = parse_bool(value)?;
+                file_config.ci_check_run = Some(bool_value);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[ci_check_run]: {}", bool_value);
+            }
+            "security_scan_command" => {
+                if value.trim().is_empty() {
+                    return Err("security_scan_command can't be empty".to_string());
+                }
+                file_config.security_scan_command = Some(value.to_string());
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[security_scan_command]: {}", value);
+            }
+            "note_details_threshold" => {
+                let threshold: u64 = value.trim().parse().map_err(|_| {
+                    "note_details_threshold must be a size in bytes, e.g. 16384".to_string()
+                })?;
+                file_config.note_details_threshold = Some(threshold);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[note_details_threshold]: {}", threshold);
+            }
+            "note_delta_interval" => {
+                let interval: u32 = value.trim().parse().map_err(|_| {
+                    "note_delta_interval must be a number of notes, e.g. 16".to_string()
+                })?;
+                file_config.note_delta_interval = Some(interval);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[note_delta_interval]: {}", interval);
+            }
+            "attribution_granularity" => {
+                if add_mode {
+                    return Err("Cannot use --add with attribution_granularity".to_string());
+                }
+                let granularity: BTreeMap<String, String> = serde_json::from_str(value)
+                    .map_err(|e| {
+                        format!(
+                            "attribution_granularity must be a JSON object of extension to granularity: {}",
+                            e
+                        )
+                    })?;
+                for value in granularity.values() {
+                    value.parse::<AttributionGranularity>()?;
+                }
+                file_config.attribution_granularity = Some(granularity);
+                crate::config::save_file_config(&file_config)?;
+                eprintln!("[attribution_granularity]: {}", value);
+            }
+            "trusted_provenance_identities" => {
+                let parsed: Vec<ProvenanceIdentity> = match serde_json::from_str(value) {
+                    Ok(identities) => identities,
+                    Err(_) => vec![serde_json::from_str(value).map_err(|e| {
+                        format!(
+                            "trusted_provenance_identities must be a JSON object (or array of objects) with \"issuer\" and \"subject\": {}",
+                            e
+                        )
+                    })?],
+                };
+                for identity in &parsed {
+                    regex::Regex::new(&identity.subject).map_err(|e| {
+                        format!("Invalid subject pattern '{}': {}", identity.subject, e)
+                    })?;
+                }
+                let identities = file_config
+                    .trusted_provenance_identities
+                    .get_or_insert_with(Vec::new);
+                if !add_mode {
+                    identities.clear();
+                }
+                for identity in parsed {
+                    if !identities.contains(&identity) {
+                        eprintln!(
+                            "+ [trusted_provenance_identities]: {} {}",
+                            identity.issuer, identity.subject
+                        );
+                        identities.push(identity);
+                    }
+                }
+                crate::config::save_file_config(&file_config)?;
+            }
             _ => return Err(format!("Unknown config key: {}", key)),
         }
 
@@ -724,6 +1062,131 @@ fn unset_config_value(key: &str) -> Result<(), String> {
                     eprintln!("- [quiet]: {}", v);
                 }
             }
+            "work_item_pattern" => {
+                let old_value = file_config.work_item_pattern.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [work_item_pattern]: {}", v);
+                }
+            }
+            "test_path_patterns" => {
+                let old_values = file_config.test_path_patterns.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(items) = old_values {
+                    log_array_removals(&items);
+                }
+            }
+            "fully_ai_threshold" => {
+                let old_value = file_config.fully_ai_threshold.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [fully_ai_threshold]: {}", v);
+                }
+            }
+            "ai_assisted_threshold" => {
+                let old_value = file_config.ai_assisted_threshold.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [ai_assisted_threshold]: {}", v);
+                }
+            }
+            "attribution_neutral_commits" => {
+                let old_values = file_config.attribution_neutral_commits.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(items) = old_values {
+                    log_array_removals(&items);
+                }
+            }
+            "neutral_whitespace_commits" => {
+                let old_value = file_config.neutral_whitespace_commits.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [neutral_whitespace_commits]: {}", v);
+                }
+            }
+            "preserve_whitespace_attribution" => {
+                let old_value = file_config.preserve_whitespace_attribution.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [preserve_whitespace_attribution]: {}", v);
+                }
+            }
+            "no_network" => {
+                let old_value = file_config.no_network.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [no_network]: {}", v);
+                }
+            }
+            "ca_bundle" => {
+                let old_value = file_config.ca_bundle.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [ca_bundle]: {}", v);
+                }
+            }
+            "tls_insecure" => {
+                let old_value = file_config.tls_insecure.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [tls_insecure]: {}", v);
+                }
+            }
+            "ci_comment" => {
+                let old_value = file_config.ci_comment.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [ci_comment]: {}", v);
+                }
+            }
+            "ci_check_run" => {
+                let old_value = file_config.ci_check_run.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [ci_check_run]: {}", v);
+                }
+            }
+            "security_scan_command" => {
+                let old_value = file_config.security_scan_command.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [security_scan_command]: {}", v);
+                }
+            }
+            "note_details_threshold" => {
+                let old_value = file_config.note_details_threshold.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [note_details_threshold]: {}", v);
+                }
+            }
+            "note_delta_interval" => {
+                let old_value = file_config.note_delta_interval.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!("- [note_delta_interval]: {}", v);
+                }
+            }
+            "attribution_granularity" => {
+                let old_value = file_config.attribution_granularity.take();
+                crate::config::save_file_config(&file_config)?;
+                if let Some(v) = old_value {
+                    eprintln!(
+                        "- [attribution_granularity]: {}",
+                        serde_json::to_string(&v).unwrap_or_default()
+                    );
+                }
+            }
+            "trusted_provenance_identities" => {
+                let old_values = file_config.trusted_provenance_identities.take();
+                crate::config::save_file_config(&file_config)?;
+                for identity in old_values.unwrap_or_default() {
+                    eprintln!(
+                        "- [trusted_provenance_identities]: {} {}",
+                        identity.issuer, identity.subject
+                    );
+                }
+            }
             _ => return Err(format!("Unknown config key: {}", key)),
         }
 
@@ -860,6 +1323,28 @@ fn resolve_repository_value(value: &str) -> Result<Vec<String>, String> {
     }
 }
 
+/// Set or extend a plain string array field (no repository resolution)
+fn set_string_array_field(
+    field: &mut Option<Vec<String>>,
+    value: &str,
+    add_mode: bool,
+) -> Result<Vec<String>, String> {
+    let values: Vec<String> = if value.starts_with('[') {
+        serde_json::from_str(value).map_err(|e| format!("Invalid JSON array of strings: {}", e))?
+    } else {
+        vec![value.to_string()]
+    };
+
+    if add_mode {
+        let mut arr = field.take().unwrap_or_default();
+        arr.extend(values.iter().cloned());
+        *field = Some(arr);
+    } else {
+        *field = Some(values.clone());
+    }
+    Ok(values)
+}
+
 /// Log array changes with + prefix for add mode, or just list items for set mode
 fn log_array_changes(items: &[String], add_mode: bool) {
     #[allow(clippy::if_same_then_else)]
@@ -892,6 +1377,16 @@ fn parse_bool(value: &str) -> Result<bool, String> {
     }
 }
 
+fn parse_percent(value: &str) -> Result<f64, String> {
+    match value.trim().trim_end_matches('%').parse::<f64>() {
+        Ok(v) if (0.0..=100.0).contains(&v) => Ok(v),
+        _ => Err(format!(
+            "Invalid percentage: '{}'. Expected a number between 0 and 100",
+            value
+        )),
+    }
+}
+
 fn parse_value(value: &str) -> Result<Value, String> {
     // Try to parse as JSON first
     if let Ok(json_value) = serde_json::from_str::<Value>(value) {
@@ -902,6 +1397,22 @@ fn parse_value(value: &str) -> Result<Value, String> {
     Ok(Value::String(value.to_string()))
 }
 
+/// `attribution_granularity` as a JSON object of extension to granularity name
+fn attribution_granularity_value(config: &crate::config::Config) -> Value {
+    Value::Object(
+        config
+            .attribution_granularity()
+            .iter()
+            .map(|(extension, granularity)| {
+                (
+                    extension.clone(),
+                    Value::String(granularity.as_str().to_string()),
+                )
+            })
+            .collect(),
+    )
+}
+
 /// Mask an API key for display (show first 4 and last 4 chars if long enough)
 fn mask_api_key(key: &str) -> String {
     if key.len() > 8 {
@@ -0,0 +1,652 @@
+//! `git-ai daemon`: one background process per user that keeps the editor heatmap cache
+//! (`.git/ai/heatmap`) of every watched repository current. An editor plugin starts it
+//! with `git-ai daemon start` and registers its repository with `git-ai daemon watch`;
+//! after each commit the daemon refreshes that repository's heatmap incrementally,
+//! blaming only the AI-touched files whose blob changed.
+//!
+//! Refreshes run `git-ai heatmap` in the repository, so each one reads that repository's
+//! current `.git-ai.toml` and `~/.git-ai/config.json`. When `.git-ai.toml` changes the
+//! heatmap is rebuilt in full, since its policy settings change which lines count as AI.
+//!
+//! Clients talk to it over a Unix socket (`~/.git-ai/internal/daemon.sock`, or
+//! `GIT_AI_DAEMON_SOCKET`), one newline-delimited JSON request and response per
+//! connection:
+//!
+//! ```text
+//! -> {"method": "watch", "params": {"path": "/home/me/project"}}
+//! <- {"result": {"repository": "/home/me/project", "head": "..."}}
+//! -> {"method": "bogus"}
+//! <- {"error": {"code": "unknown_method", "message": "..."}}
+//! ```
+//!
+//! Methods: `watch`, `unwatch`, `ping` (version, watched repositories and their cache
+//! sizes, as reported by `git-ai daemon status`) and `stop`.
+
+use crate::authorship::heatmap::{Heatmap, RefreshStats};
+use crate::commands::editor_host::COMMIT_NOTE_GRACE;
+use crate::config::REPO_CONFIG_FILE_NAME;
+use crate::error::GitAiError;
+use crate::git::refs::show_authorship_note;
+use crate::git::repository::{Repository, find_repository_in_path};
+use crate::mdm::utils::home_dir;
+use crate::utils::debug_log;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Environment variable overriding where the daemon listens
+pub const DAEMON_SOCKET_ENV_VAR: &str = "GIT_AI_DAEMON_SOCKET";
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+
+/// How long a client waits for the daemon to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long `start` waits for a new daemon to accept connections
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct ProtocolError {
+    code: &'static str,
+    message: String,
+}
+
+/// State shared by the connection threads and the refresh loop
+struct DaemonState {
+    repos: Mutex<BTreeMap<PathBuf, WatchedRepo>>,
+    poll_interval: Duration,
+    socket: PathBuf,
+    started: Instant,
+    /// When the refresh loop last woke up
+    last_poll: Mutex<Instant>,
+    /// Set by `stop`; the accept loop exits at its next connection
+    stopping: AtomicBool,
+}
+
+#[derive(Clone)]
+struct WatchedRepo {
+    repo: Repository,
+    /// HEAD the heatmap was last refreshed for
+    heatmap_head: Option<String>,
+    /// When HEAD was first seen without its commit's note yet
+    head_moved_at: Option<Instant>,
+    /// `.git-ai.toml` mtime the heatmap was last refreshed with
+    config_mtime: Option<SystemTime>,
+    last_refresh: Option<RefreshStats>,
+    last_error: Option<String>,
+}
+
+/// Where the daemon listens
+pub fn socket_path() -> PathBuf {
+    std::env::var(DAEMON_SOCKET_ENV_VAR)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            home_dir()
+                .join(".git-ai")
+                .join("internal")
+                .join("daemon.sock")
+        })
+}
+
+/// Send one request to the running daemon and return its result
+pub fn daemon_request(method: &str, params: Value) -> Result<Value, GitAiError> {
+    let socket = socket_path();
+    let mut stream = UnixStream::connect(&socket).map_err(|e| {
+        GitAiError::Generic(format!(
+            "git-ai daemon is not running ({}: {})",
+            socket.display(),
+            e
+        ))
+    })?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let request = Request {
+        method: method.to_string(),
+        params,
+    };
+    writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).map_err(|e| {
+        GitAiError::Generic(format!("git-ai daemon did not answer {}: {}", method, e))
+    })?;
+    let mut response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response.get("error") {
+        return Err(GitAiError::Generic(
+            error["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string(),
+        ));
+    }
+    Ok(response["result"].take())
+}
+
+fn is_running(socket: &Path) -> bool {
+    UnixStream::connect(socket).is_ok()
+}
+
+pub fn handle_daemon(args: &[String]) {
+    let Some(subcommand) = args.first() else {
+        print_usage();
+        std::process::exit(1);
+    };
+    let rest = &args[1..];
+    match subcommand.as_str() {
+        "run" => {
+            if let Err(e) = run(poll_interval_arg(rest)) {
+                eprintln!("git-ai daemon: {}", e);
+                std::process::exit(1);
+            }
+        }
+        "start" => start(rest),
+        "status" => print_status(rest),
+        "stop" => match daemon_request("stop", Value::Null) {
+            Ok(_) => println!("Stopped git-ai daemon"),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        "watch" | "unwatch" => {
+            let path = repository_arg(rest);
+            match daemon_request(subcommand, json!({ "path": path })) {
+                Ok(_) if subcommand == "watch" => println!("Watching {}", path.display()),
+                Ok(_) => println!("Stopped watching {}", path.display()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "--help" | "-h" | "help" => print_usage(),
+        other => {
+            eprintln!("Error: Unknown daemon subcommand: {}", other);
+            print_usage();
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_usage() {
+    eprintln!("Usage: git-ai daemon <subcommand>");
+    eprintln!("  start [--poll-interval-ms <ms>]  Start the daemon in the background");
+    eprintln!("  run [--poll-interval-ms <ms>]    Run the daemon in the foreground");
+    eprintln!("  watch [<path>]                   Keep a repository's heatmap cache current");
+    eprintln!("  unwatch [<path>]                 Stop watching a repository");
+    eprintln!(
+        "  status [--json]                  Report the daemon's version, repositories and caches"
+    );
+    eprintln!("  stop                             Stop the daemon");
+}
+
+fn poll_interval_arg(args: &[String]) -> Duration {
+    let mut poll_interval = Duration::from_millis(DEFAULT_POLL_INTERVAL_MS);
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--poll-interval-ms" => {
+                let Some(ms) = args.get(i + 1).and_then(|v| v.parse::<u64>().ok()) else {
+                    eprintln!("Error: --poll-interval-ms requires a number of milliseconds");
+                    std::process::exit(1);
+                };
+                poll_interval = Duration::from_millis(ms.max(50));
+                i += 2;
+            }
+            arg => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                print_usage();
+                std::process::exit(1);
+            }
+        }
+    }
+    poll_interval
+}
+
+/// The working directory of the repository at `args[0]`, or of the current one
+fn repository_arg(args: &[String]) -> PathBuf {
+    if args.len() > 1 {
+        print_usage();
+        std::process::exit(1);
+    }
+    let path = args.first().map(String::as_str).unwrap_or(".");
+    let workdir = find_repository_in_path(path).and_then(|repo| repo.workdir());
+    match workdir.and_then(|dir| Ok(dir.canonicalize()?)) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Failed to find repository at {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Start `git-ai daemon run` detached from the terminal and wait for it to listen. Its
+/// output is appended to `daemon.log` next to the socket.
+fn start(args: &[String]) {
+    let poll_interval = poll_interval_arg(args);
+    let socket = socket_path();
+    if is_running(&socket) {
+        println!("git-ai daemon is already running");
+        return;
+    }
+
+    let spawned = crate::utils::current_git_ai_exe().and_then(|exe| {
+        let dir = socket.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join("daemon.log"))?;
+        Ok(Command::new(exe)
+            .args(["daemon", "run", "--poll-interval-ms"])
+            .arg(poll_interval.as_millis().to_string())
+            .env_remove("GIT_AI")
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(log.try_clone()?))
+            .stderr(Stdio::from(log))
+            // Its own process group, so closing the terminal doesn't stop it
+            .process_group(0)
+            .spawn()?)
+    });
+    let child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to start git-ai daemon: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let started = Instant::now();
+    while !is_running(&socket) {
+        if started.elapsed() > START_TIMEOUT {
+            eprintln!(
+                "git-ai daemon (pid {}) did not start listening on {}",
+                child.id(),
+                socket.display()
+            );
+            std::process::exit(1);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    println!("Started git-ai daemon (pid {})", child.id());
+}
+
+/// Serve requests and refresh watched repositories until asked to stop
+fn run(poll_interval: Duration) -> Result<(), GitAiError> {
+    let socket = socket_path();
+    if is_running(&socket) {
+        return Err(GitAiError::Generic(format!(
+            "already running on {}",
+            socket.display()
+        )));
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    let _ = std::fs::remove_file(&socket);
+    if let Some(dir) = socket.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Created owner-only from the start rather than restricted after bind, so there's no
+    // window in which another user can connect. No other threads are running yet to be
+    // affected by the process-wide umask.
+    let previous_umask = unsafe { libc::umask(0o177) };
+    let bound = UnixListener::bind(&socket);
+    unsafe { libc::umask(previous_umask) };
+    let listener = bound?;
+    println!(
+        "git-ai daemon {} listening on {} (pid {})",
+        env!("CARGO_PKG_VERSION"),
+        socket.display(),
+        std::process::id()
+    );
+
+    let state = Arc::new(DaemonState {
+        repos: Mutex::new(BTreeMap::new()),
+        poll_interval,
+        socket,
+        started: Instant::now(),
+        last_poll: Mutex::new(Instant::now()),
+        stopping: AtomicBool::new(false),
+    });
+    {
+        let state = state.clone();
+        std::thread::spawn(move || refresh_loop(&state));
+    }
+
+    for stream in listener.incoming() {
+        if state.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let state = state.clone();
+        std::thread::spawn(move || serve_connection(stream, &state));
+    }
+    let _ = std::fs::remove_file(&state.socket);
+    Ok(())
+}
+
+fn serve_connection(stream: UnixStream, state: &DaemonState) {
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let mut line = String::new();
+    let mut reader = BufReader::new(&stream);
+    if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+        return;
+    }
+
+    let (response, stop) = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => {
+            let response = match handle_request(state, &request) {
+                Ok(result) => json!({ "result": result }),
+                Err(error) => json!({ "error": error }),
+            };
+            (response, request.method == "stop")
+        }
+        Err(e) => {
+            let error = ProtocolError {
+                code: "invalid_request",
+                message: format!("Invalid JSON request: {}", e),
+            };
+            (json!({ "error": error }), false)
+        }
+    };
+    let _ = writeln!(&stream, "{}", response);
+
+    if stop {
+        // Wake the accept loop so it sees the flag, removes the socket and returns
+        state.stopping.store(true, Ordering::SeqCst);
+        let _ = UnixStream::connect(&state.socket);
+    }
+}
+
+fn handle_request(state: &DaemonState, request: &Request) -> Result<Value, ProtocolError> {
+    match request.method.as_str() {
+        "watch" => {
+            let repo = repository_param(&request.params)?;
+            let workdir = repo_workdir(&repo);
+            let head = repo.head().and_then(|h| h.target()).ok();
+            state
+                .repos
+                .lock()
+                .unwrap()
+                .entry(workdir.clone())
+                .or_insert_with(|| WatchedRepo {
+                    config_mtime: repo_config_mtime(&workdir),
+                    repo,
+                    heatmap_head: None,
+                    head_moved_at: None,
+                    last_refresh: None,
+                    last_error: None,
+                });
+            Ok(json!({ "repository": workdir, "head": head }))
+        }
+        "unwatch" => {
+            let repo = repository_param(&request.params)?;
+            let removed = state
+                .repos
+                .lock()
+                .unwrap()
+                .remove(&repo_workdir(&repo))
+                .is_some();
+            Ok(json!({ "unwatched": removed }))
+        }
+        "ping" => Ok(ping(state)),
+        "stop" => Ok(Value::Null),
+        other => Err(ProtocolError {
+            code: "unknown_method",
+            message: format!("Unknown method '{}'", other),
+        }),
+    }
+}
+
+fn repository_param(params: &Value) -> Result<Repository, ProtocolError> {
+    let path = params
+        .get("path")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ProtocolError {
+            code: "invalid_params",
+            message: "Missing string parameter 'path'".to_string(),
+        })?;
+    find_repository_in_path(path).map_err(|e| ProtocolError {
+        code: "not_a_repository",
+        message: format!("{}: {}", path, e),
+    })
+}
+
+fn repo_workdir(repo: &Repository) -> PathBuf {
+    let dir = repo.workdir().unwrap_or_else(|_| repo.path().to_path_buf());
+    dir.canonicalize().unwrap_or(dir)
+}
+
+/// Daemon version and uptime, and every watched repository with its heatmap cache
+fn ping(state: &DaemonState) -> Value {
+    let watched: Vec<(PathBuf, WatchedRepo)> = state
+        .repos
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, watched)| (path.clone(), watched.clone()))
+        .collect();
+    let repositories: Vec<Value> = watched
+        .iter()
+        .map(|(path, watched)| {
+            let cache = Heatmap::cache_path(&watched.repo);
+            let heatmap_bytes = std::fs::metadata(&cache).map(|m| m.len()).unwrap_or(0);
+            let heatmap_files = Heatmap::load(&cache)
+                .ok()
+                .flatten()
+                .map(|heatmap| heatmap.files.len())
+                .unwrap_or(0);
+            json!({
+                "path": path,
+                "head": watched.repo.head().and_then(|h| h.target()).ok(),
+                "heatmap_head": watched.heatmap_head,
+                "cache": {
+                    "heatmap_bytes": heatmap_bytes,
+                    "heatmap_files": heatmap_files,
+                },
+                "last_refresh": watched.last_refresh.map(|stats| json!({
+                    "reused": stats.reused,
+                    "computed": stats.computed,
+                })),
+                "last_error": watched.last_error,
+            })
+        })
+        .collect();
+
+    json!({
+        "git_ai_version": env!("CARGO_PKG_VERSION"),
+        "pid": std::process::id(),
+        "uptime_ms": state.started.elapsed().as_millis() as u64,
+        "poll_interval_ms": state.poll_interval.as_millis() as u64,
+        "last_poll_ms_ago": state.last_poll.lock().unwrap().elapsed().as_millis() as u64,
+        "repositories": repositories,
+    })
+}
+
+/// `status`: ping the daemon and print what it reports. Exits 1 when it isn't running
+/// or doesn't answer.
+fn print_status(args: &[String]) {
+    let json_output = match args {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        _ => {
+            print_usage();
+            std::process::exit(1);
+        }
+    };
+    let ping = match daemon_request("ping", Value::Null) {
+        Ok(ping) => ping,
+        Err(e) => {
+            if json_output {
+                println!("{}", json!({ "running": false, "error": e.to_string() }));
+            } else {
+                eprintln!("{}", e);
+            }
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        let mut status = ping;
+        status["running"] = json!(true);
+        println!("{}", status);
+        return;
+    }
+    println!(
+        "git-ai daemon {} (pid {}), up {}s, polling every {}ms",
+        ping["git_ai_version"].as_str().unwrap_or("unknown"),
+        ping["pid"],
+        ping["uptime_ms"].as_u64().unwrap_or(0) / 1000,
+        ping["poll_interval_ms"]
+    );
+    let repositories = ping["repositories"].as_array().cloned().unwrap_or_default();
+    if repositories.is_empty() {
+        println!("No repositories watched");
+    }
+    for repo in &repositories {
+        let short = |value: &Value| {
+            value
+                .as_str()
+                .map(|sha| sha.chars().take(8).collect::<String>())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        println!(
+            "  {}  head {}  heatmap {} ({} files, {} bytes)",
+            repo["path"].as_str().unwrap_or_default(),
+            short(&repo["head"]),
+            short(&repo["heatmap_head"]),
+            repo["cache"]["heatmap_files"],
+            repo["cache"]["heatmap_bytes"]
+        );
+        if let Some(error) = repo["last_error"].as_str() {
+            println!("    last refresh failed: {}", error);
+        }
+    }
+}
+
+fn refresh_loop(state: &DaemonState) {
+    loop {
+        std::thread::sleep(state.poll_interval);
+        *state.last_poll.lock().unwrap() = Instant::now();
+        // Refresh from a copy so requests aren't blocked while files are blamed
+        let watched: Vec<(PathBuf, WatchedRepo)> = state
+            .repos
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, watched)| (path.clone(), watched.clone()))
+            .collect();
+        for (path, mut watched) in watched {
+            if !refresh_repo(&mut watched) {
+                continue;
+            }
+            // Unless it was unwatched meanwhile
+            if let Some(entry) = state.repos.lock().unwrap().get_mut(&path) {
+                *entry = watched;
+            }
+        }
+    }
+}
+
+/// Refresh the repository's heatmap if HEAD moved or its `.git-ai.toml` changed since
+/// the last refresh. Returns whether anything about the repository changed.
+fn refresh_repo(watched: &mut WatchedRepo) -> bool {
+    let Ok(head) = watched.repo.head().and_then(|h| h.target()) else {
+        return false;
+    };
+    let workdir = repo_workdir(&watched.repo);
+    let config_mtime = repo_config_mtime(&workdir);
+    let config_changed = config_mtime != watched.config_mtime;
+    if watched.heatmap_head.as_deref() == Some(head.as_str()) && !config_changed {
+        return false;
+    }
+    // HEAD moves before the post-commit hook writes the note
+    if !config_changed
+        && show_authorship_note(&watched.repo, &head).is_none()
+        && watched
+            .head_moved_at
+            .get_or_insert_with(Instant::now)
+            .elapsed()
+            < COMMIT_NOTE_GRACE
+    {
+        return true;
+    }
+    watched.head_moved_at = None;
+
+    match run_heatmap(&workdir, config_changed) {
+        Ok(stats) => {
+            debug_log(&format!(
+                "daemon refreshed heatmap of {} at {} ({} reused, {} recomputed)",
+                workdir.display(),
+                head,
+                stats.reused,
+                stats.computed
+            ));
+            watched.last_refresh = Some(stats);
+            watched.last_error = None;
+        }
+        Err(e) => {
+            eprintln!(
+                "git-ai daemon: failed to refresh heatmap of {}: {}",
+                workdir.display(),
+                e
+            );
+            watched.last_error = Some(e.to_string());
+        }
+    }
+    // A failed refresh isn't retried until HEAD or the config changes again
+    watched.heatmap_head = Some(head);
+    watched.config_mtime = config_mtime;
+    true
+}
+
+fn repo_config_mtime(workdir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(workdir.join(REPO_CONFIG_FILE_NAME))
+        .ok()?
+        .modified()
+        .ok()
+}
+
+/// Run `git-ai heatmap --json` in `workdir`, rebuilding from scratch when `full`
+fn run_heatmap(workdir: &Path, full: bool) -> Result<RefreshStats, GitAiError> {
+    let mut command = Command::new(crate::utils::current_git_ai_exe()?);
+    command
+        .args(["heatmap", "--json"])
+        .current_dir(workdir)
+        .env_remove("GIT_AI")
+        .stdin(Stdio::null());
+    if full {
+        command.arg("--full");
+    }
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(GitAiError::Generic(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let summary: Value = serde_json::from_slice(&output.stdout)?;
+    let count = |key: &str| summary[key].as_u64().unwrap_or(0) as usize;
+    Ok(RefreshStats {
+        reused: count("reused"),
+        computed: count("computed"),
+    })
+}
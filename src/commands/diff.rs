@@ -1,4 +1,5 @@
 use crate::authorship::authorship_log::{LineRange, PromptRecord};
+use crate::authorship::file_attribution::staged_line_attributions;
 use crate::commands::blame::GitAiBlameOptions;
 use crate::error::GitAiError;
 use crate::git::repository::{Repository, exec_git};
@@ -10,10 +11,13 @@ use std::io::IsTerminal;
 // Data Structures
 // ============================================================================
 
+const EMPTY_TREE_SHA: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 #[derive(Debug)]
 pub enum DiffSpec {
     SingleCommit(String),      // SHA
     TwoCommit(String, String), // start..end
+    Staged,                    // HEAD..index
 }
 
 pub enum DiffFormat {
@@ -84,6 +88,7 @@ pub fn handle_diff(repo: &Repository, args: &[String]) -> Result<(), GitAiError>
         eprintln!("Error: diff requires a commit or commit range argument");
         eprintln!("Usage: git-ai diff <commit>");
         eprintln!("       git-ai diff <commit1>..<commit2>");
+        eprintln!("       git-ai diff --staged");
         std::process::exit(1);
     }
 
@@ -107,6 +112,13 @@ pub fn parse_diff_args(args: &[String]) -> Result<(DiffSpec, DiffFormat), GitAiE
         DiffFormat::GitCompatibleTerminal
     };
 
+    if args
+        .iter()
+        .any(|arg| arg == "--staged" || arg == "--cached")
+    {
+        return Ok((DiffSpec::Staged, format));
+    }
+
     // Check for commit range (start..end)
     if arg.contains("..") {
         let parts: Vec<&str> = arg.split("..").collect();
@@ -149,10 +161,11 @@ pub fn execute_diff(
             let from = resolve_parent(repo, &to)?;
             (from, to)
         }
+        DiffSpec::Staged => return execute_staged_diff(repo, format),
     };
 
     // Step 1: Get diff hunks with line numbers
-    let hunks = get_diff_with_line_numbers(repo, &from_commit, &to_commit)?;
+    let hunks = get_diff_with_line_numbers(repo, &from_commit, Some(&to_commit))?;
 
     // Step 2: Overlay AI attributions
     let attributions = overlay_diff_attributions(repo, &from_commit, &to_commit, &hunks)?;
@@ -165,13 +178,121 @@ pub fn execute_diff(
                 .map_err(|e| GitAiError::Generic(format!("Failed to serialize JSON: {}", e)))?
         }
         DiffFormat::GitCompatibleTerminal => {
-            format_annotated_diff(repo, &from_commit, &to_commit, &attributions)?
+            format_annotated_diff(repo, &from_commit, Some(&to_commit), &attributions)?
         }
     };
 
     Ok(output)
 }
 
+/// Diff HEAD against the index. Staged lines can't be blamed yet, so they are
+/// attributed from the working log instead.
+fn execute_staged_diff(repo: &Repository, format: DiffFormat) -> Result<String, GitAiError> {
+    let from_commit = resolve_commit(repo, "HEAD").unwrap_or_else(|_| EMPTY_TREE_SHA.to_string());
+
+    let hunks = get_diff_with_line_numbers(repo, &from_commit, None)?;
+
+    let mut files: Vec<String> = hunks.iter().map(|h| h.file_path.clone()).collect();
+    files.sort();
+    files.dedup();
+    let (line_attributions, prompts) = staged_line_attributions(repo, &files)?;
+
+    match format {
+        DiffFormat::Json => {
+            let mut file_diffs = get_diff_split_by_file(repo, &from_commit, None)?;
+            let mut diff_json = DiffJson {
+                files: BTreeMap::new(),
+                prompts: BTreeMap::new(),
+            };
+            for file_path in files {
+                let mut annotations: BTreeMap<String, Vec<LineRange>> = BTreeMap::new();
+                for attr in line_attributions.get(&file_path).into_iter().flatten() {
+                    let added: Vec<u32> = hunks
+                        .iter()
+                        .filter(|h| h.file_path == file_path)
+                        .flat_map(|h| h.added_lines.iter().copied())
+                        .filter(|line| (attr.start_line..=attr.end_line).contains(line))
+                        .collect();
+                    if added.is_empty() {
+                        continue;
+                    }
+                    if let Some(record) = prompts.get(&attr.author_id) {
+                        diff_json
+                            .prompts
+                            .insert(attr.author_id.clone(), record.clone());
+                    }
+                    annotations
+                        .entry(attr.author_id.clone())
+                        .or_default()
+                        .extend(LineRange::compress_lines(&added));
+                }
+
+                let base_content = match repo.get_file_content(&file_path, &from_commit) {
+                    Ok(bytes) => String::from_utf8(bytes).unwrap_or_default(),
+                    Err(_) => String::new(),
+                };
+                diff_json.files.insert(
+                    file_path.clone(),
+                    FileDiffJson {
+                        annotations,
+                        diff: file_diffs.remove(&file_path).unwrap_or_default(),
+                        base_content,
+                    },
+                );
+            }
+            serde_json::to_string(&diff_json)
+                .map_err(|e| GitAiError::Generic(format!("Failed to serialize JSON: {}", e)))
+        }
+        DiffFormat::GitCompatibleTerminal => {
+            let user_name = match repo.config_get_str("user.name") {
+                Ok(Some(name)) if !name.trim().is_empty() => name,
+                _ => "unknown".to_string(),
+            };
+
+            let mut attributions = HashMap::new();
+            for hunk in &hunks {
+                let file_attrs = line_attributions.get(&hunk.file_path);
+                for &line in &hunk.added_lines {
+                    let ai_tool = file_attrs
+                        .and_then(|attrs| {
+                            attrs
+                                .iter()
+                                .find(|a| a.start_line <= line && line <= a.end_line)
+                        })
+                        .map(|attr| {
+                            prompts
+                                .get(&attr.author_id)
+                                .map(|p| p.agent_id.tool.clone())
+                                .unwrap_or_else(|| attr.author_id.clone())
+                        });
+                    let attribution = match ai_tool {
+                        Some(tool) => Attribution::Ai(tool),
+                        None => Attribution::Human(user_name.clone()),
+                    };
+                    attributions.insert(
+                        DiffLineKey {
+                            file: hunk.file_path.clone(),
+                            line,
+                            side: LineSide::New,
+                        },
+                        attribution,
+                    );
+                }
+            }
+            format_annotated_diff(repo, &from_commit, None, &attributions)
+        }
+    }
+}
+
+/// Revision arguments for `git diff`: `from` against `to`, or against the index
+/// when `to` is `None`
+fn diff_revision_args(from: &str, to: Option<&str>) -> Vec<String> {
+    match to {
+        Some(to) => vec![from.to_string(), to.to_string()],
+        None => vec!["--cached".to_string(), from.to_string()],
+    }
+}
+
 // ============================================================================
 // Commit Resolution
 // ============================================================================
@@ -216,14 +337,14 @@ fn resolve_parent(repo: &Repository, commit: &str) -> Result<String, GitAiError>
 
             if sha.is_empty() {
                 // No parent, this is initial commit - use empty tree
-                Ok("4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string())
+                Ok(EMPTY_TREE_SHA.to_string())
             } else {
                 Ok(sha)
             }
         }
         Err(_) => {
             // No parent, this is initial commit - use empty tree hash
-            Ok("4b825dc642cb6eb9a060e54bf8d69288fbee4904".to_string())
+            Ok(EMPTY_TREE_SHA.to_string())
         }
     }
 }
@@ -235,14 +356,13 @@ fn resolve_parent(repo: &Repository, commit: &str) -> Result<String, GitAiError>
 pub fn get_diff_with_line_numbers(
     repo: &Repository,
     from: &str,
-    to: &str,
+    to: Option<&str>,
 ) -> Result<Vec<DiffHunk>, GitAiError> {
     let mut args = repo.global_args_for_exec();
     args.push("diff".to_string());
     args.push("-U0".to_string()); // No context lines, just changes
     args.push("--no-color".to_string());
-    args.push(from.to_string());
-    args.push(to.to_string());
+    args.extend(diff_revision_args(from, to));
 
     let output = exec_git(&args)?;
     let diff_text = String::from_utf8(output.stdout)
@@ -491,7 +611,7 @@ fn build_diff_json(
     let mut all_prompts: BTreeMap<String, PromptRecord> = BTreeMap::new();
 
     // Get the full diff output and split by file
-    let file_diffs = get_diff_split_by_file(repo, from_commit, to_commit)?;
+    let file_diffs = get_diff_split_by_file(repo, from_commit, Some(to_commit))?;
 
     // Get unique files from hunks
     let mut unique_files: Vec<String> = hunks.iter().map(|h| h.file_path.clone()).collect();
@@ -538,13 +658,12 @@ fn build_diff_json(
 fn get_diff_split_by_file(
     repo: &Repository,
     from_commit: &str,
-    to_commit: &str,
+    to_commit: Option<&str>,
 ) -> Result<HashMap<String, String>, GitAiError> {
     let mut args = repo.global_args_for_exec();
     args.push("diff".to_string());
     args.push("--no-color".to_string());
-    args.push(from_commit.to_string());
-    args.push(to_commit.to_string());
+    args.extend(diff_revision_args(from_commit, to_commit));
 
     let output = exec_git(&args)?;
     let diff_text = String::from_utf8(output.stdout)
@@ -692,15 +811,14 @@ fn collect_file_annotations(
 pub fn format_annotated_diff(
     repo: &Repository,
     from_commit: &str,
-    to_commit: &str,
+    to_commit: Option<&str>,
     attributions: &HashMap<DiffLineKey, Attribution>,
 ) -> Result<String, GitAiError> {
     // Execute git diff with normal context
     let mut args = repo.global_args_for_exec();
     args.push("diff".to_string());
     args.push("--no-color".to_string());
-    args.push(from_commit.to_string());
-    args.push(to_commit.to_string());
+    args.extend(diff_revision_args(from_commit, to_commit));
 
     let output = exec_git(&args)?;
     let diff_text = String::from_utf8(output.stdout)
@@ -953,7 +1071,7 @@ pub fn get_diff_json_filtered(
     let from_commit = resolve_parent(repo, &to_commit)?;
 
     // Get diff hunks with line numbers
-    let hunks = get_diff_with_line_numbers(repo, &from_commit, &to_commit)?;
+    let hunks = get_diff_with_line_numbers(repo, &from_commit, Some(&to_commit))?;
 
     // Get attributions for overlay (not used directly, but needed for build_diff_json)
     let attributions = overlay_diff_attributions(repo, &from_commit, &to_commit, &hunks)?;
@@ -0,0 +1,235 @@
+use crate::authorship::authorship_log_serialization::{
+    AUTHORSHIP_CAPABILITIES, AUTHORSHIP_LOG_VERSION, GIT_AI_VERSION, IncompatibleNoteError,
+    check_note_compatibility,
+};
+use crate::config::Config;
+use crate::git::authorship_traversal::read_all_notes;
+use crate::git::find_repository;
+use crate::git::repository::Repository;
+use crate::git::sync_authorship::{ensure_notes_refspecs, missing_notes_refspecs};
+use serde::Serialize;
+
+/// At most this many incompatible notes are listed individually
+const MAX_LISTED_NOTES: usize = 10;
+
+#[derive(Serialize)]
+struct DoctorReport {
+    git_ai_version: &'static str,
+    authorship_format: &'static str,
+    capabilities: &'static [&'static str],
+    git_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<NotesReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remotes: Option<Vec<RemoteReport>>,
+}
+
+#[derive(Serialize)]
+struct NotesReport {
+    total: usize,
+    incompatible: usize,
+    incompatible_notes: Vec<IncompatibleNote>,
+}
+
+/// Notes refspecs a remote lacks, so plain `git fetch`/`git push` skip authorship notes
+#[derive(Serialize)]
+struct RemoteReport {
+    name: String,
+    missing_refspecs: Vec<String>,
+    fixed: bool,
+}
+
+#[derive(Serialize)]
+struct IncompatibleNote {
+    commit: String,
+    reason: String,
+}
+
+/// Handle `git-ai doctor [--json] [--fix]`: report what this binary supports, check that
+/// it can read every authorship note in the current repository, and check that each
+/// remote syncs notes. `--fix` adds any missing notes refspecs.
+pub fn handle_doctor(args: &[String]) {
+    let mut json_output = false;
+    let mut fix = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json_output = true,
+            "--fix" => fix = true,
+            _ => {
+                eprintln!("Error: unknown option '{}'", arg);
+                eprintln!("Usage: git-ai doctor [--json] [--fix]");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let repo = find_repository(&Vec::<String>::new()).ok();
+    let notes = match repo.as_ref().map(check_notes) {
+        Some(Ok(notes)) => Some(notes),
+        Some(Err(e)) => {
+            eprintln!("Failed to read authorship notes: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let remotes = match repo.as_ref().map(|repo| check_remotes(repo, fix)) {
+        Some(Ok(remotes)) => Some(remotes),
+        Some(Err(e)) => {
+            eprintln!("Failed to configure notes refspecs: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let report = DoctorReport {
+        git_ai_version: GIT_AI_VERSION,
+        authorship_format: AUTHORSHIP_LOG_VERSION,
+        capabilities: AUTHORSHIP_CAPABILITIES,
+        git_version: git_version(),
+        notes,
+        remotes,
+    };
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_report(&report);
+    }
+
+    if report.notes.as_ref().is_some_and(|n| n.incompatible > 0) {
+        std::process::exit(1);
+    }
+}
+
+fn check_notes(repo: &Repository) -> Result<NotesReport, crate::error::GitAiError> {
+    let notes = read_all_notes(repo)?;
+    let mut incompatible: Vec<(String, IncompatibleNoteError)> = notes
+        .iter()
+        .filter_map(|(commit, content)| {
+            // Metadata is the JSON after the `---` divider
+            let metadata: Vec<&str> = content
+                .lines()
+                .skip_while(|line| *line != "---")
+                .skip(1)
+                .collect();
+            let metadata = serde_json::from_str(&metadata.join("\n")).ok()?;
+            check_note_compatibility(&metadata)
+                .err()
+                .map(|e| (commit.clone(), e))
+        })
+        .collect();
+    incompatible.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(NotesReport {
+        total: notes.len(),
+        incompatible: incompatible.len(),
+        incompatible_notes: incompatible
+            .into_iter()
+            .take(MAX_LISTED_NOTES)
+            .map(|(commit, e)| IncompatibleNote {
+                commit,
+                reason: e.to_string(),
+            })
+            .collect(),
+    })
+}
+
+fn check_remotes(
+    repo: &Repository,
+    fix: bool,
+) -> Result<Vec<RemoteReport>, crate::error::GitAiError> {
+    let mut reports = Vec::new();
+    for name in repo.remotes()?.into_iter().filter(|name| !name.is_empty()) {
+        let missing = if fix {
+            ensure_notes_refspecs(repo, &name)?
+        } else {
+            missing_notes_refspecs(repo, &name)
+        };
+        reports.push(RemoteReport {
+            fixed: fix && !missing.is_empty(),
+            missing_refspecs: missing
+                .into_iter()
+                .map(|(direction, refspec)| format!("{} {}", direction, refspec))
+                .collect(),
+            name,
+        });
+    }
+    Ok(reports)
+}
+
+fn git_version() -> Option<String> {
+    let output = std::process::Command::new(Config::get().git_cmd())
+        .arg("--version")
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    Some(
+        version
+            .trim()
+            .trim_start_matches("git version ")
+            .to_string(),
+    )
+}
+
+fn print_report(report: &DoctorReport) {
+    println!("git-ai version:     {}", report.git_ai_version);
+    println!("authorship format:  {}", report.authorship_format);
+    println!("capabilities:       {}", report.capabilities.join(", "));
+    println!(
+        "git version:        {}",
+        report.git_version.as_deref().unwrap_or("not found")
+    );
+
+    let Some(notes) = &report.notes else {
+        println!("(not in a git repository; skipping notes checks)");
+        return;
+    };
+    print_remotes(report.remotes.as_deref().unwrap_or_default());
+    if notes.incompatible == 0 {
+        println!("authorship notes:   {} readable", notes.total);
+        return;
+    }
+    println!(
+        "authorship notes:   {} of {} need a newer git-ai",
+        notes.incompatible, notes.total
+    );
+    for note in &notes.incompatible_notes {
+        println!("  {}: {}", note.commit, note.reason);
+    }
+    if notes.incompatible > notes.incompatible_notes.len() {
+        println!(
+            "  ... and {} more",
+            notes.incompatible - notes.incompatible_notes.len()
+        );
+    }
+}
+
+fn print_remotes(remotes: &[RemoteReport]) {
+    for remote in remotes {
+        if remote.missing_refspecs.is_empty() {
+            println!(
+                "remote {}:{}notes refspecs configured",
+                remote.name,
+                pad(&remote.name)
+            );
+        } else if remote.fixed {
+            println!(
+                "remote {}:{}added {}",
+                remote.name,
+                pad(&remote.name),
+                remote.missing_refspecs.join(", ")
+            );
+        } else {
+            println!(
+                "remote {}:{}missing {} (run `git-ai doctor --fix`)",
+                remote.name,
+                pad(&remote.name),
+                remote.missing_refspecs.join(", ")
+            );
+        }
+    }
+}
+
+/// Spaces that line a `remote <name>:` label up with the other report columns
+fn pad(name: &str) -> String {
+    " ".repeat(12usize.saturating_sub(name.len()).max(1))
+}
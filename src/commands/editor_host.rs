@@ -0,0 +1,480 @@
+//! `git-ai editor-host`: a long-running process that editor extensions talk to over
+//! stdio, so they never have to parse human-oriented CLI output.
+//!
+//! Messages are newline-delimited JSON objects in both directions.
+//!
+//! Requests carry an `id` that the response echoes:
+//!
+//! ```text
+//! -> {"id": 1, "method": "initialize", "params": {"protocol_version": 1}}
+//! <- {"id": 1, "result": {"protocol_version": 1, "git_ai_version": "...", "capabilities": [...]}}
+//! -> {"id": 2, "method": "subscribe", "params": {"file": "src/main.rs"}}
+//! <- {"id": 2, "result": {"schema_version": "model/1.0.0", "file": "src/main.rs", "head": "...", "ranges": [...]}}
+//! <- {"id": 3, "error": {"code": "unknown_method", "message": "..."}}
+//! ```
+//!
+//! Methods: `initialize`, `attribution` (one-off query), `subscribe`, `unsubscribe`,
+//! `health` and `shutdown`. Once subscribed, the host pushes a notification (no `id`)
+//! whenever a checkpoint or commit may have changed a file's attribution:
+//!
+//! ```text
+//! <- {"method": "attribution_changed", "params": {"reason": "commit", "attribution": {...}}}
+//! ```
+//!
+//! `reason` is `commit`, `checkpoint`, or `config` when the user's git-ai config changed;
+//! the host reloads the config in place, so it never needs restarting to pick up edits.
+//!
+//! Every poll, the host also writes a heartbeat to `.git/ai/editor-host/<pid>.json`.
+//! `git-ai editor-host --status` reads those files, so tooling that doesn't own the
+//! host's stdio can still tell a wedged or crashed host from a healthy one.
+
+use crate::authorship::authorship_log_serialization::GIT_AI_VERSION;
+use crate::authorship::file_attribution::file_attributions;
+use crate::config::Config;
+use crate::git::find_repository;
+use crate::git::refs::show_authorship_note;
+use crate::git::repository::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Bumped on incompatible changes to the message format
+pub const EDITOR_HOST_PROTOCOL_VERSION: u32 = 1;
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
+
+/// How long the watcher waits after HEAD moves for the post-commit hook to write the new
+/// commit's authorship note, so the commit notification carries its attribution
+pub(crate) const COMMIT_NOTE_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct ProtocolError {
+    code: &'static str,
+    message: String,
+}
+
+/// State shared by the request loop and the watcher thread
+struct HostState {
+    subscriptions: Mutex<BTreeSet<String>>,
+    poll_interval: Duration,
+    started: Instant,
+    started_at: i64,
+    /// Last time the watcher thread completed a poll
+    last_poll: Mutex<Instant>,
+}
+
+impl HostState {
+    fn watcher_is_stale(&self) -> bool {
+        self.last_poll.lock().unwrap().elapsed() > stale_after(self.poll_interval)
+    }
+}
+
+/// Heartbeat file contents, rewritten by the watcher thread on every poll
+#[derive(Debug, Serialize, Deserialize)]
+struct Heartbeat {
+    pid: u32,
+    git_ai_version: String,
+    protocol_version: u32,
+    poll_interval_ms: u64,
+    started_at: i64,
+    heartbeat_at: i64,
+    subscriptions: usize,
+}
+
+/// A host whose watcher hasn't polled for this long is considered wedged
+fn stale_after(poll_interval: Duration) -> Duration {
+    (poll_interval * 10).max(Duration::from_secs(10))
+}
+
+fn heartbeat_dir(repo: &Repository) -> PathBuf {
+    repo.path().join("ai").join("editor-host")
+}
+
+/// Serializes writes so responses and notifications never interleave mid-line
+#[derive(Clone)]
+struct Output(Arc<Mutex<std::io::Stdout>>);
+
+impl Output {
+    fn send(&self, message: &Value) {
+        let mut stdout = self.0.lock().unwrap();
+        let _ = writeln!(stdout, "{}", message);
+        let _ = stdout.flush();
+    }
+}
+
+pub fn handle_editor_host(args: &[String]) {
+    let mut poll_interval = Duration::from_millis(DEFAULT_POLL_INTERVAL_MS);
+    let mut status = false;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--poll-interval-ms" => {
+                let Some(ms) = args.get(i + 1).and_then(|v| v.parse::<u64>().ok()) else {
+                    eprintln!("Error: --poll-interval-ms requires a number of milliseconds");
+                    std::process::exit(1);
+                };
+                poll_interval = Duration::from_millis(ms.max(50));
+                i += 2;
+            }
+            "--status" => {
+                status = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            arg => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                eprintln!("Usage: git-ai editor-host [--poll-interval-ms <ms>]");
+                eprintln!("       git-ai editor-host --status [--json]");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if status {
+        print_status(&repo, json_output);
+        return;
+    }
+
+    let output = Output(Arc::new(Mutex::new(std::io::stdout())));
+    let state = Arc::new(HostState {
+        subscriptions: Mutex::new(BTreeSet::new()),
+        poll_interval,
+        started: Instant::now(),
+        started_at: chrono::Utc::now().timestamp(),
+        last_poll: Mutex::new(Instant::now()),
+    });
+    write_heartbeat(&repo, &state);
+
+    {
+        let repo = repo.clone();
+        let output = output.clone();
+        let state = state.clone();
+        std::thread::spawn(move || watch_for_changes(repo, output, state));
+    }
+
+    let stdin = std::io::stdin();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                output.send(&error_response(
+                    Value::Null,
+                    "invalid_request",
+                    format!("Invalid JSON request: {}", e),
+                ));
+                continue;
+            }
+        };
+
+        let id = request.id.clone();
+        let shutdown = request.method == "shutdown";
+        let response = match handle_request(&repo, &state, &request) {
+            Ok(result) => json!({ "id": id, "result": result }),
+            Err(error) => json!({ "id": id, "error": error }),
+        };
+        output.send(&response);
+
+        if shutdown {
+            break;
+        }
+    }
+
+    let _ = std::fs::remove_file(heartbeat_dir(&repo).join(format!("{}.json", std::process::id())));
+}
+
+fn handle_request(
+    repo: &Repository,
+    state: &HostState,
+    request: &Request,
+) -> Result<Value, ProtocolError> {
+    let subscriptions = &state.subscriptions;
+    match request.method.as_str() {
+        "initialize" => {
+            let client_version = request
+                .params
+                .get("protocol_version")
+                .and_then(Value::as_u64)
+                .unwrap_or(EDITOR_HOST_PROTOCOL_VERSION as u64);
+            if client_version != EDITOR_HOST_PROTOCOL_VERSION as u64 {
+                return Err(ProtocolError {
+                    code: "unsupported_protocol_version",
+                    message: format!(
+                        "Client speaks protocol version {}, this git-ai speaks version {}",
+                        client_version, EDITOR_HOST_PROTOCOL_VERSION
+                    ),
+                });
+            }
+            Ok(json!({
+                "protocol_version": EDITOR_HOST_PROTOCOL_VERSION,
+                "git_ai_version": GIT_AI_VERSION,
+                "capabilities": ["attribution", "subscribe", "attribution_changed", "health"],
+            }))
+        }
+        "attribution" => {
+            let file = file_param(&request.params)?;
+            attribution_value(repo, &file)
+        }
+        "subscribe" => {
+            let file = file_param(&request.params)?;
+            let result = attribution_value(repo, &file)?;
+            subscriptions.lock().unwrap().insert(file);
+            Ok(result)
+        }
+        "unsubscribe" => {
+            let file = file_param(&request.params)?;
+            let removed = subscriptions.lock().unwrap().remove(&file);
+            Ok(json!({ "unsubscribed": removed }))
+        }
+        "health" => Ok(health_value(repo, state)),
+        "shutdown" => Ok(Value::Null),
+        other => Err(ProtocolError {
+            code: "unknown_method",
+            message: format!("Unknown method '{}'", other),
+        }),
+    }
+}
+
+fn file_param(params: &Value) -> Result<String, ProtocolError> {
+    params
+        .get("file")
+        .and_then(Value::as_str)
+        .map(|file| file.trim_start_matches("./").to_string())
+        .ok_or_else(|| ProtocolError {
+            code: "invalid_params",
+            message: "Missing string parameter 'file' (repository-relative path)".to_string(),
+        })
+}
+
+fn attribution_value(repo: &Repository, file: &str) -> Result<Value, ProtocolError> {
+    let attribution = file_attributions(repo, &[file.to_string()], None)
+        .and_then(|mut results| results.remove(0))
+        .map_err(|e| ProtocolError {
+            code: "attribution_failed",
+            message: e.to_string(),
+        })?;
+    serde_json::to_value(attribution).map_err(|e| ProtocolError {
+        code: "attribution_failed",
+        message: e.to_string(),
+    })
+}
+
+/// Liveness and resource summary. `status` is `degraded` when the watcher thread has
+/// stopped polling, i.e. change notifications are no longer being delivered.
+fn health_value(repo: &Repository, state: &HostState) -> Value {
+    let head = repo.head().and_then(|h| h.target()).ok();
+    let working_log_bytes: u64 = head
+        .as_ref()
+        .map(|head| {
+            let log = repo.storage.working_log_for_base_commit(head);
+            [log.dir.join("checkpoints.jsonl"), log.initial_file.clone()]
+                .iter()
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0);
+    let heatmap_bytes = std::fs::metadata(repo.path().join("ai").join("heatmap"))
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    json!({
+        "status": if state.watcher_is_stale() { "degraded" } else { "ok" },
+        "git_ai_version": GIT_AI_VERSION,
+        "protocol_version": EDITOR_HOST_PROTOCOL_VERSION,
+        "pid": std::process::id(),
+        "uptime_ms": state.started.elapsed().as_millis() as u64,
+        "repository": repo.workdir().ok(),
+        "head": head,
+        "subscriptions": state.subscriptions.lock().unwrap().len(),
+        "watcher": {
+            "poll_interval_ms": state.poll_interval.as_millis() as u64,
+            "last_poll_ms_ago": state.last_poll.lock().unwrap().elapsed().as_millis() as u64,
+        },
+        "cache": {
+            "working_log_bytes": working_log_bytes,
+            "heatmap_bytes": heatmap_bytes,
+        },
+    })
+}
+
+fn write_heartbeat(repo: &Repository, state: &HostState) {
+    let heartbeat = Heartbeat {
+        pid: std::process::id(),
+        git_ai_version: GIT_AI_VERSION.to_string(),
+        protocol_version: EDITOR_HOST_PROTOCOL_VERSION,
+        poll_interval_ms: state.poll_interval.as_millis() as u64,
+        started_at: state.started_at,
+        heartbeat_at: chrono::Utc::now().timestamp(),
+        subscriptions: state.subscriptions.lock().unwrap().len(),
+    };
+    let dir = heartbeat_dir(repo);
+    let written = std::fs::create_dir_all(&dir).and_then(|_| {
+        let json = serde_json::to_string(&heartbeat).map_err(std::io::Error::other)?;
+        std::fs::write(dir.join(format!("{}.json", heartbeat.pid)), json)
+    });
+    if let Err(e) = written {
+        crate::utils::debug_log(&format!("failed to write editor-host heartbeat: {}", e));
+    }
+}
+
+/// `--status`: report every host that has a heartbeat file. Exits 1 when no host is
+/// running or any host has stopped heartbeating.
+fn print_status(repo: &Repository, json_output: bool) {
+    let now = chrono::Utc::now().timestamp();
+    let mut hosts: Vec<(Heartbeat, bool)> = std::fs::read_dir(heartbeat_dir(repo))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path()).ok())
+        .filter_map(|content| serde_json::from_str::<Heartbeat>(&content).ok())
+        .map(|heartbeat| {
+            let stale_secs =
+                stale_after(Duration::from_millis(heartbeat.poll_interval_ms)).as_secs() as i64;
+            let stale = now - heartbeat.heartbeat_at > stale_secs;
+            (heartbeat, stale)
+        })
+        .collect();
+    hosts.sort_by_key(|(heartbeat, _)| heartbeat.pid);
+
+    if json_output {
+        let entries: Vec<Value> = hosts
+            .iter()
+            .map(|(heartbeat, stale)| {
+                let mut entry = serde_json::to_value(heartbeat).unwrap_or(Value::Null);
+                entry["status"] = json!(if *stale { "stale" } else { "running" });
+                entry
+            })
+            .collect();
+        println!("{}", json!({ "hosts": entries }));
+    } else if hosts.is_empty() {
+        println!("No editor-host running");
+    } else {
+        for (heartbeat, stale) in &hosts {
+            println!(
+                "pid {}  {}  version {}  subscriptions {}  last heartbeat {}s ago",
+                heartbeat.pid,
+                if *stale { "stale" } else { "running" },
+                heartbeat.git_ai_version,
+                heartbeat.subscriptions,
+                now - heartbeat.heartbeat_at
+            );
+        }
+    }
+
+    if hosts.is_empty() || hosts.iter().any(|(_, stale)| *stale) {
+        std::process::exit(1);
+    }
+}
+
+/// What the watcher compares between polls: HEAD, and the working log files that
+/// checkpoints append to
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RepoState {
+    head: Option<String>,
+    working_log: Vec<Option<(SystemTime, u64)>>,
+}
+
+fn repo_state(repo: &Repository) -> RepoState {
+    let head = repo.head().and_then(|h| h.target()).ok();
+    let working_log = match &head {
+        Some(head) => {
+            let log = repo.storage.working_log_for_base_commit(head);
+            [log.dir.join("checkpoints.jsonl"), log.initial_file.clone()]
+                .iter()
+                .map(|path| {
+                    std::fs::metadata(path)
+                        .ok()
+                        .and_then(|m| Some((m.modified().ok()?, m.len())))
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    RepoState { head, working_log }
+}
+
+fn watch_for_changes(repo: Repository, output: Output, host: Arc<HostState>) {
+    let mut last_state = repo_state(&repo);
+    let mut head_moved_at: Option<Instant> = None;
+    loop {
+        std::thread::sleep(host.poll_interval);
+        *host.last_poll.lock().unwrap() = Instant::now();
+        write_heartbeat(&repo, &host);
+
+        // Pick up edits to ~/.git-ai/config.json and .git-ai.toml without restarting the host
+        let config_reloaded = Config::reload_if_changed() | Config::reload_repo_if_changed(&repo);
+        let state = repo_state(&repo);
+        if state == last_state && !config_reloaded {
+            continue;
+        }
+        // HEAD moves before the post-commit hook writes the note
+        if state.head != last_state.head
+            && let Some(head) = &state.head
+            && show_authorship_note(&repo, head).is_none()
+            && head_moved_at.get_or_insert_with(Instant::now).elapsed() < COMMIT_NOTE_GRACE
+        {
+            // The reload is only reported once, so don't hold it back for the note
+            if config_reloaded {
+                notify_subscribers(&repo, &output, &host, "config");
+            }
+            continue;
+        }
+        head_moved_at = None;
+        let reason = if state.head != last_state.head {
+            "commit"
+        } else if state != last_state {
+            "checkpoint"
+        } else {
+            "config"
+        };
+        last_state = state;
+        notify_subscribers(&repo, &output, &host, reason);
+    }
+}
+
+fn notify_subscribers(repo: &Repository, output: &Output, host: &HostState, reason: &str) {
+    let files: Vec<String> = host.subscriptions.lock().unwrap().iter().cloned().collect();
+    for file in files {
+        let params = match attribution_value(repo, &file) {
+            Ok(attribution) => json!({ "reason": reason, "attribution": attribution }),
+            Err(error) => json!({ "reason": reason, "file": file, "error": error }),
+        };
+        output.send(&json!({ "method": "attribution_changed", "params": params }));
+    }
+}
+
+fn error_response(id: Value, code: &'static str, message: String) -> Value {
+    json!({ "id": id, "error": ProtocolError { code, message } })
+}
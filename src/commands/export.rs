@@ -0,0 +1,306 @@
+use crate::authorship::report::collect_contributions;
+use crate::commands::sync_prompts::parse_since_arg;
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::get_authorship;
+use crate::git::repository::{Repository, exec_git};
+use crate::model::{CommitEvent, CommitSummary, ExportEvent, HunkEvent, MODEL_SCHEMA_VERSION};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+
+pub fn handle_export(args: &[String]) {
+    let mut range: Option<String> = None;
+    let mut since: Option<String> = None;
+    let mut until: Option<String> = None;
+    let mut output_path: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--format" => match args.get(i + 1).map(String::as_str) {
+                Some("jsonl") => i += 2,
+                Some(other) => {
+                    eprintln!(
+                        "Error: unsupported export format '{}' (expected jsonl)",
+                        other
+                    );
+                    std::process::exit(1);
+                }
+                None => {
+                    eprintln!("Error: --format requires a value");
+                    std::process::exit(1);
+                }
+            },
+            "--since" | "--until" | "--output" | "-o" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("Error: {} requires a value", args[i]);
+                    std::process::exit(1);
+                };
+                match args[i].as_str() {
+                    "--since" => since = Some(value.clone()),
+                    "--until" => until = Some(value.clone()),
+                    _ => output_path = Some(value.clone()),
+                }
+                i += 2;
+            }
+            "--help" | "-h" => {
+                print_export_help();
+                std::process::exit(0);
+            }
+            arg if arg.starts_with("--") => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                print_export_help();
+                std::process::exit(1);
+            }
+            _ => {
+                if range.is_some() {
+                    eprintln!("Error: export accepts at most one revision or range");
+                    std::process::exit(1);
+                }
+                range = Some(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    let mut rev_args = vec![range.unwrap_or_else(|| "HEAD".to_string())];
+    for (flag, value) in [("--since", since), ("--until", until)] {
+        let Some(value) = value else { continue };
+        match parse_since_arg(&value) {
+            Ok(timestamp) => rev_args.push(format!("{}={}", flag, timestamp)),
+            Err(e) => {
+                eprintln!("Error parsing {}: {}", flag, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match &output_path {
+        Some(path) => std::fs::File::create(path)
+            .map_err(GitAiError::from)
+            .and_then(|file| write_events(&repo, &rev_args, std::io::BufWriter::new(file))),
+        None => write_events(&repo, &rev_args, std::io::stdout().lock()),
+    };
+    match result {
+        Ok(count) => {
+            if let Some(path) = output_path {
+                eprintln!("Exported {} event(s) to {}", count, path);
+            }
+        }
+        Err(e) => {
+            eprintln!("Export failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_export_help() {
+    eprintln!(
+        "Usage: git-ai export [<rev|range>] [--format jsonl] [--since <time>] [--until <time>] [--output <path>]"
+    );
+    eprintln!();
+    eprintln!("Writes one JSON event per line: a \"commit\" event for each non-merge commit, and");
+    eprintln!("a \"hunk\" event for each run of AI-authored lines it records, for loading into a");
+    eprintln!("data warehouse. Events carry a deterministic event_id for deduplication.");
+    eprintln!();
+    eprintln!("  --format jsonl    Output format (the only one, and the default)");
+    eprintln!("  --since <time>    Only commits after this time (e.g. 7d, 2026-01-01, RFC 3339)");
+    eprintln!("  --until <time>    Only commits before this time");
+    eprintln!("  --output <path>   Write to a file instead of stdout");
+}
+
+/// Commit details the contribution log doesn't carry
+struct CommitDetails {
+    parents: Vec<String>,
+    committer_name: String,
+    committer_email: String,
+    committed_at: i64,
+    subject: String,
+}
+
+/// Write the events for the commits `rev_args` selects, returning how many were written
+pub fn write_events(
+    repo: &Repository,
+    rev_args: &[String],
+    mut out: impl Write,
+) -> Result<usize, GitAiError> {
+    let events = export_events(repo, rev_args)?;
+    for event in &events {
+        serde_json::to_writer(&mut out, event)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(events.len())
+}
+
+/// Events for the non-merge commits `rev_args` selects (anything `git log` accepts),
+/// newest first, each commit followed by its hunks
+pub fn export_events(
+    repo: &Repository,
+    rev_args: &[String],
+) -> Result<Vec<ExportEvent>, GitAiError> {
+    let contributions = collect_contributions(repo, rev_args)?;
+    let details = commit_details(repo, rev_args)?;
+    let repo_url = repo
+        .get_default_remote()
+        .ok()
+        .flatten()
+        .and_then(|remote| {
+            repo.remotes_with_urls()
+                .ok()?
+                .into_iter()
+                .find(|(name, _)| *name == remote)
+        })
+        .and_then(|(_, url)| crate::repo_url::normalize_repo_url(&url).ok());
+
+    let mut events = Vec::new();
+    for contribution in contributions {
+        let details = details.get(&contribution.sha);
+        let committed_at = rfc3339(
+            details
+                .map(|d| d.committed_at)
+                .unwrap_or(contribution.timestamp),
+        );
+        let added_lines: u32 = contribution.files.iter().map(|f| f.added_lines).sum();
+        let ai_lines: u32 = contribution.files.iter().map(|f| f.ai_lines).sum();
+        let log = contribution
+            .has_note
+            .then(|| get_authorship(repo, &contribution.sha))
+            .flatten();
+
+        events.push(ExportEvent::Commit(CommitEvent {
+            schema_version: MODEL_SCHEMA_VERSION.to_string(),
+            event_id: event_id(&["commit", &contribution.sha]),
+            repo: repo_url.clone(),
+            commit: contribution.sha.clone(),
+            parents: details.map(|d| d.parents.clone()).unwrap_or_default(),
+            author_name: contribution.author_name.clone(),
+            author_email: contribution.author_email.clone(),
+            authored_at: rfc3339(contribution.timestamp),
+            committer_name: details
+                .map(|d| d.committer_name.clone())
+                .unwrap_or_default(),
+            committer_email: details
+                .map(|d| d.committer_email.clone())
+                .unwrap_or_default(),
+            committed_at: committed_at.clone(),
+            subject: details.map(|d| d.subject.clone()).unwrap_or_default(),
+            has_authorship: contribution.has_note,
+            added_lines,
+            ai_lines,
+            human_lines: added_lines.saturating_sub(ai_lines),
+            git_ai_version: log
+                .as_ref()
+                .and_then(|log| log.metadata.git_ai_version.clone()),
+            work_item: log.as_ref().and_then(|log| log.metadata.work_item.clone()),
+            classification: log
+                .as_ref()
+                .and_then(|log| log.metadata.classification)
+                .map(|class| class.as_str().to_string()),
+        }));
+
+        let Some(log) = log else { continue };
+        let summary = CommitSummary::from_authorship_log(&contribution.sha, &log);
+        for file in &summary.files {
+            for range in &file.ranges {
+                let prompt = log.metadata.prompts.get(&range.prompt_id);
+                events.push(ExportEvent::Hunk(HunkEvent {
+                    schema_version: MODEL_SCHEMA_VERSION.to_string(),
+                    event_id: event_id(&[
+                        "hunk",
+                        &contribution.sha,
+                        &file.file,
+                        &range.start_line.to_string(),
+                        &range.end_line.to_string(),
+                    ]),
+                    repo: repo_url.clone(),
+                    commit: contribution.sha.clone(),
+                    author_email: contribution.author_email.clone(),
+                    committed_at: committed_at.clone(),
+                    file: file.file.clone(),
+                    start_line: range.start_line,
+                    end_line: range.end_line,
+                    line_count: range.end_line - range.start_line + 1,
+                    prompt_id: range.prompt_id.clone(),
+                    tool: range.tool.clone(),
+                    model: range.model.clone(),
+                    session_id: prompt.map(|p| p.agent_id.id.clone()),
+                    human_author: prompt.and_then(|p| p.human_author.clone()),
+                }));
+            }
+        }
+    }
+    Ok(events)
+}
+
+fn commit_details(
+    repo: &Repository,
+    rev_args: &[String],
+) -> Result<HashMap<String, CommitDetails>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.push("log".to_string());
+    args.push("--no-merges".to_string());
+    args.push("--format=%H%x00%P%x00%cN%x00%cE%x00%ct%x00%s".to_string());
+    args.extend(rev_args.iter().cloned());
+    let output = exec_git(&args)?;
+    let stdout = String::from_utf8(output.stdout)?;
+
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(6, '\0').collect();
+            let [sha, parents, name, email, time, subject] = fields.as_slice() else {
+                return None;
+            };
+            Some((
+                sha.to_string(),
+                CommitDetails {
+                    parents: parents.split_whitespace().map(str::to_string).collect(),
+                    committer_name: name.to_string(),
+                    committer_email: email.to_string(),
+                    committed_at: time.parse().unwrap_or(0),
+                    subject: subject.to_string(),
+                },
+            ))
+        })
+        .collect())
+}
+
+fn rfc3339(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Stable across exports, so loads of overlapping windows can be deduplicated
+fn event_id(parts: &[&str]) -> String {
+    format!("{:x}", Sha256::digest(parts.join("\0").as_bytes()))[..32].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_id_is_stable_and_distinguishes_parts() {
+        assert_eq!(event_id(&["commit", "abc"]), event_id(&["commit", "abc"]));
+        assert_ne!(event_id(&["commit", "abc"]), event_id(&["commit", "abd"]));
+        assert_ne!(event_id(&["a", "bc"]), event_id(&["ab", "c"]));
+        assert_eq!(event_id(&["commit", "abc"]).len(), 32);
+    }
+
+    #[test]
+    fn test_rfc3339_is_utc() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(1_767_225_600), "2026-01-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,347 @@
+use crate::authorship::rebase_authorship::rewrite_authorship_after_cherry_pick;
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::notes_add;
+use crate::git::repository::{Repository, exec_git, exec_git_stdin};
+use crate::git::sync_authorship::{NotesSyncOp, run_notes_sync};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// At most this many unrecoverable commits are listed individually
+const MAX_LISTED_UNKNOWN: usize = 20;
+
+#[derive(Serialize)]
+struct FetchMissingReport {
+    range: String,
+    commits: usize,
+    missing: usize,
+    restored: Vec<Recovered>,
+    reconstructed: Vec<Recovered>,
+    unknown: Vec<UnknownCommit>,
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct Recovered {
+    commit: String,
+    /// Notes ref the note was copied from, or the commit with the same patch-id
+    source: String,
+}
+
+#[derive(Serialize)]
+struct UnknownCommit {
+    commit: String,
+    subject: String,
+}
+
+/// Handle `git-ai fetch-missing [<range>] [--remote <name>] [--offline] [--dry-run] [--json]`:
+/// find commits in a range without authorship notes and recover what can be recovered.
+///
+/// Notes are fetched first. Then each commit without a note gets one copied from a
+/// remote's tracking notes ref that still has it, or rebuilt from an annotated commit
+/// with the same patch-id (a cherry-pick or rebase whose hooks didn't run). Whatever is
+/// left is reported as unknown.
+pub fn handle_fetch_missing(args: &[String]) {
+    let mut range = None;
+    let mut remote = None;
+    let mut offline = false;
+    let mut dry_run = false;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--remote" => {
+                let Some(name) = args.get(i + 1) else {
+                    eprintln!("Error: --remote requires a remote name");
+                    print_usage();
+                    std::process::exit(1);
+                };
+                remote = Some(name.clone());
+                i += 1;
+            }
+            "--offline" => offline = true,
+            "--dry-run" => dry_run = true,
+            "--json" => json_output = true,
+            "--help" | "-h" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            arg if arg.starts_with('-') => {
+                eprintln!("Error: unknown option '{}'", arg);
+                print_usage();
+                std::process::exit(1);
+            }
+            arg if range.is_none() => range = Some(arg.to_string()),
+            arg => {
+                eprintln!("Error: unexpected argument '{}'", arg);
+                print_usage();
+                std::process::exit(1);
+            }
+        }
+        i += 1;
+    }
+    let range = range.unwrap_or_else(|| "HEAD".to_string());
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if !offline {
+        let remote = remote
+            .clone()
+            .or_else(|| repo.get_default_remote().ok().flatten());
+        if let Some(remote) = remote
+            && let Err(e) = run_notes_sync(&repo, NotesSyncOp::Fetch, &remote)
+        {
+            eprintln!(
+                "warning: failed to fetch authorship notes from {}: {}",
+                remote, e
+            );
+        }
+    }
+
+    match fetch_missing(&repo, &range, dry_run) {
+        Ok(report) if json_output => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+        Ok(report) => print_report(&report),
+        Err(e) => {
+            eprintln!("fetch-missing failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn fetch_missing(
+    repo: &Repository,
+    range: &str,
+    dry_run: bool,
+) -> Result<FetchMissingReport, GitAiError> {
+    let commits = commits_in_range(repo, range)?;
+    let annotated = annotated_commits(repo, "refs/notes/ai")?;
+    let mut missing: Vec<String> = commits
+        .iter()
+        .filter(|commit| !annotated.contains(*commit))
+        .cloned()
+        .collect();
+    let missing_count = missing.len();
+
+    // 1. Notes another clone pushed that were fetched but never merged into ours
+    let mut restored = Vec::new();
+    for tracking_ref in tracking_notes_refs(repo)? {
+        let tracked = annotated_commits(repo, &tracking_ref)?;
+        let mut still_missing = Vec::new();
+        for commit in missing {
+            if !tracked.contains(&commit) {
+                still_missing.push(commit);
+                continue;
+            }
+            if !dry_run {
+                let note = show_note(repo, &tracking_ref, &commit)?;
+                notes_add(repo, &commit, &note)?;
+            }
+            restored.push(Recovered {
+                commit,
+                source: tracking_ref.clone(),
+            });
+        }
+        missing = still_missing;
+    }
+
+    // 2. Commits with the same change as an annotated commit
+    let mut reconstructed = Vec::new();
+    if !missing.is_empty() {
+        let mut candidates: Vec<String> = annotated_commits(repo, "refs/notes/ai")?
+            .into_iter()
+            .collect();
+        candidates.sort();
+        let candidates_by_patch_id: HashMap<String, String> = patch_ids(repo, &candidates)?
+            .into_iter()
+            .map(|(commit, patch_id)| (patch_id, commit))
+            .collect();
+        let missing_patch_ids = patch_ids(repo, &missing)?;
+
+        let mut still_missing = Vec::new();
+        for commit in missing {
+            let twin = missing_patch_ids
+                .get(&commit)
+                .and_then(|patch_id| candidates_by_patch_id.get(patch_id));
+            let Some(twin) = twin else {
+                still_missing.push(commit);
+                continue;
+            };
+            if !dry_run {
+                rewrite_authorship_after_cherry_pick(
+                    repo,
+                    std::slice::from_ref(twin),
+                    std::slice::from_ref(&commit),
+                    "",
+                )?;
+            }
+            reconstructed.push(Recovered {
+                commit,
+                source: twin.clone(),
+            });
+        }
+        missing = still_missing;
+    }
+
+    let unknown = missing
+        .into_iter()
+        .map(|commit| UnknownCommit {
+            subject: commit_subject(repo, &commit),
+            commit,
+        })
+        .collect();
+
+    Ok(FetchMissingReport {
+        range: range.to_string(),
+        commits: commits.len(),
+        missing: missing_count,
+        restored,
+        reconstructed,
+        unknown,
+        dry_run,
+    })
+}
+
+/// Non-merge commits in `range` (a revision or `a..b`), newest first. Merge commits
+/// carry no authorship of their own.
+fn commits_in_range(repo: &Repository, range: &str) -> Result<Vec<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["rev-list", "--no-merges", range].map(str::to_string));
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Commits with a note in `notes_ref`
+fn annotated_commits(repo: &Repository, notes_ref: &str) -> Result<HashSet<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["notes", &format!("--ref={}", notes_ref), "list"].map(str::to_string));
+    let output = match exec_git(&args) {
+        Ok(output) => output,
+        // No notes ref yet
+        Err(GitAiError::GitCliError { .. }) => return Ok(HashSet::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect())
+}
+
+fn tracking_notes_refs(repo: &Repository) -> Result<Vec<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(
+        [
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/notes/ai-remote/",
+        ]
+        .map(str::to_string),
+    );
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+fn show_note(repo: &Repository, notes_ref: &str, commit: &str) -> Result<String, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["notes", &format!("--ref={}", notes_ref), "show", commit].map(str::to_string));
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Stable patch-id of each commit's change, keyed by commit. Commits without a diff
+/// (e.g. empty commits) have no patch-id.
+fn patch_ids(repo: &Repository, commits: &[String]) -> Result<HashMap<String, String>, GitAiError> {
+    if commits.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut diff_args = repo.global_args_for_exec();
+    diff_args.extend(["diff-tree", "--stdin", "-p", "--no-color", "--root"].map(str::to_string));
+    let diffs = exec_git_stdin(&diff_args, format!("{}\n", commits.join("\n")).as_bytes())?;
+
+    let mut patch_id_args = repo.global_args_for_exec();
+    patch_id_args.extend(["patch-id", "--stable"].map(str::to_string));
+    let output = exec_git_stdin(&patch_id_args, &diffs.stdout)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (patch_id, commit) = line.split_once(' ')?;
+            Some((commit.to_string(), patch_id.to_string()))
+        })
+        .collect())
+}
+
+fn commit_subject(repo: &Repository, commit: &str) -> String {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["log", "-1", "--format=%s", commit].map(str::to_string));
+    exec_git(&args)
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+fn print_report(report: &FetchMissingReport) {
+    let verb = |done: &'static str, planned: &'static str| {
+        if report.dry_run { planned } else { done }
+    };
+    println!(
+        "{} of {} commits in {} have no authorship note",
+        report.missing, report.commits, report.range
+    );
+    if report.missing == 0 {
+        return;
+    }
+    println!(
+        "  {} {} from remote notes",
+        report.restored.len(),
+        verb("restored", "can be restored")
+    );
+    for recovered in &report.restored {
+        println!("    {} <- {}", short(&recovered.commit), recovered.source);
+    }
+    println!(
+        "  {} {} from commits with the same patch",
+        report.reconstructed.len(),
+        verb("reconstructed", "can be reconstructed")
+    );
+    for recovered in &report.reconstructed {
+        println!(
+            "    {} <- {}",
+            short(&recovered.commit),
+            short(&recovered.source)
+        );
+    }
+    println!("  {} unknown", report.unknown.len());
+    for unknown in report.unknown.iter().take(MAX_LISTED_UNKNOWN) {
+        println!("    {} {}", short(&unknown.commit), unknown.subject);
+    }
+    if report.unknown.len() > MAX_LISTED_UNKNOWN {
+        println!(
+            "    ... and {} more",
+            report.unknown.len() - MAX_LISTED_UNKNOWN
+        );
+    }
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(8)]
+}
+
+fn print_usage() {
+    eprintln!(
+        "Usage: git-ai fetch-missing [<range>] [--remote <name>] [--offline] [--dry-run] [--json]"
+    );
+}
@@ -86,7 +86,7 @@ pub fn handle_flush_cas(_args: &[String]) {
             cas_objects.push(CasObject {
                 content,
                 hash: record.hash.clone(),
-                metadata: record.metadata.clone(),
+                metadata: record.metadata.clone().into_iter().collect(),
             });
             record_map.insert(record.hash.clone(), record.clone());
         }
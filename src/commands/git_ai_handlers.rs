@@ -1,6 +1,8 @@
+use crate::authorship::attribution_inheritance::InheritanceRules;
 use crate::authorship::internal_db::InternalDatabase;
 use crate::authorship::range_authorship;
-use crate::authorship::stats::stats_command;
+use crate::authorship::report::language_breakdown;
+use crate::authorship::stats::{WithLanguages, stats_command};
 use crate::authorship::working_log::{AgentId, CheckpointKind};
 use crate::commands;
 use crate::commands::checkpoint_agent::agent_presets::{
@@ -9,10 +11,15 @@ use crate::commands::checkpoint_agent::agent_presets::{
 };
 use crate::commands::checkpoint_agent::agent_v1_preset::AgentV1Preset;
 use crate::commands::checkpoint_agent::opencode_preset::OpenCodePreset;
+use crate::commands::report::print_section;
 use crate::config;
+use crate::error::GitAiError;
 use crate::git::find_repository;
 use crate::git::find_repository_in_path;
-use crate::git::repository::{CommitRange, group_files_by_repository};
+use crate::git::repository::{CommitRange, Repository, group_files_by_repository};
+use crate::git::shallow;
+use crate::network;
+use crate::observability::timings;
 use crate::observability::wrapper_performance_targets::log_performance_for_checkpoint;
 use crate::observability::{self, log_message};
 use crate::utils::is_interactive_terminal;
@@ -22,6 +29,19 @@ use std::io::Read;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn handle_git_ai(args: &[String]) {
+    if timings::requested(args) {
+        timings::enable();
+    }
+    if network::requested(args) {
+        network::disable();
+    }
+    let args: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--timings" && *arg != "--no-network")
+        .cloned()
+        .collect();
+    let args = args.as_slice();
+
     if args.is_empty() {
         print_help();
         return;
@@ -33,6 +53,7 @@ pub fn handle_git_ai(args: &[String]) {
     let config = config::Config::get();
 
     let allowed_repository = config.is_allowed_repository(&repository_option);
+    let command_start = std::time::Instant::now();
 
     // Start DB warmup early for commands that need database access
     match args[0].as_str() {
@@ -69,9 +90,60 @@ pub fn handle_git_ai(args: &[String]) {
         "status" => {
             commands::status::handle_status(&args[1..]);
         }
+        "report" => {
+            commands::report::handle_report(&args[1..]);
+        }
+        "top" => {
+            commands::top::handle_top(&args[1..]);
+        }
+        "compare" => {
+            commands::compare::handle_compare(&args[1..]);
+        }
+        "heatmap" => {
+            commands::heatmap::handle_heatmap(&args[1..]);
+        }
+        "snapshot" => {
+            commands::snapshot::handle_snapshot(&args[1..]);
+        }
+        "editor-host" => {
+            commands::editor_host::handle_editor_host(&args[1..]);
+        }
+        "daemon" => {
+            #[cfg(unix)]
+            commands::daemon::handle_daemon(&args[1..]);
+            #[cfg(not(unix))]
+            {
+                eprintln!("git-ai daemon is only supported on Unix");
+                std::process::exit(1);
+            }
+        }
+        "query" => {
+            commands::query::handle_query(&args[1..]);
+        }
         "show" => {
             commands::show::handle_show(&args[1..]);
         }
+        "check" => {
+            commands::check::handle_check(&args[1..]);
+        }
+        "verify" => {
+            commands::verify::handle_verify(&args[1..]);
+        }
+        "simulate-agent" => {
+            commands::simulate_agent::handle_simulate_agent(&args[1..]);
+        }
+        "introduced-by" => {
+            commands::introduced_by::handle_introduced_by(&args[1..]);
+        }
+        "lineage" => {
+            commands::lineage::handle_lineage(&args[1..]);
+        }
+        "grep" => {
+            commands::grep::handle_grep(&args[1..]);
+        }
+        "session" => {
+            commands::session::handle_session(&args[1..]);
+        }
         "checkpoint" => {
             if !allowed_repository {
                 eprintln!(
@@ -98,6 +170,9 @@ pub fn handle_git_ai(args: &[String]) {
             println!("{}", config.git_cmd());
             std::process::exit(0);
         }
+        "init" => {
+            commands::init::handle_init(&args[1..]);
+        }
         "install-hooks" | "install" => match commands::install_hooks::run(&args[1..]) {
             Ok(statuses) => {
                 if let Ok(statuses_value) = serde_json::to_value(&statuses) {
@@ -135,6 +210,24 @@ pub fn handle_git_ai(args: &[String]) {
         "flush-cas" => {
             commands::flush_cas::handle_flush_cas(&args[1..]);
         }
+        "sync" => {
+            commands::sync::handle_sync(&args[1..]);
+        }
+        "fetch-missing" => {
+            commands::fetch_missing::handle_fetch_missing(&args[1..]);
+        }
+        "export" => {
+            commands::export::handle_export(&args[1..]);
+        }
+        "import" => {
+            commands::import::handle_import(&args[1..]);
+        }
+        "doctor" => {
+            commands::doctor::handle_doctor(&args[1..]);
+        }
+        "telemetry" => {
+            commands::telemetry::handle_telemetry(&args[1..]);
+        }
         "flush-metrics-db" => {
             commands::flush_metrics_db::handle_flush_metrics_db(&args[1..]);
         }
@@ -171,12 +264,34 @@ pub fn handle_git_ai(args: &[String]) {
             std::process::exit(1);
         }
     }
+
+    observability::usage::record_command(
+        "git-ai",
+        &args[0],
+        command_start.elapsed(),
+        repository_option.as_ref(),
+    );
+
+    // Elsewhere the report is printed on exit
+    #[cfg(not(unix))]
+    if let Some(report) = timings::report() {
+        eprintln!("{}", report);
+    }
 }
 
 fn print_help() {
     eprintln!("git-ai - git proxy with AI authorship tracking");
     eprintln!();
-    eprintln!("Usage: git-ai <command> [args...]");
+    eprintln!("Usage: git-ai [--timings] [--no-network] <command> [args...]");
+    eprintln!();
+    eprintln!(
+        "  --timings          Print per-phase durations (clone, API, diff, note write, push)"
+    );
+    eprintln!("                     when the command exits (or set GIT_AI_TIMINGS=1)");
+    eprintln!(
+        "  --no-network       Fail any HTTP request or remote git operation instead of making it"
+    );
+    eprintln!("                     (or set GIT_AI_NO_NETWORK=1, or the no_network config)");
     eprintln!();
     eprintln!("Commands:");
     eprintln!("  checkpoint         Checkpoint working changes and attribute author");
@@ -186,16 +301,112 @@ fn print_help() {
     );
     eprintln!("    --show-working-log          Display current working log");
     eprintln!("    --reset                     Reset working log");
+    eprintln!("    --staged                    Attribute only staged changes, read from the index");
+    eprintln!(
+        "    --inherit-attribution[=<pct>]  Lines the agent only slightly changed (e.g. a rename)"
+    );
+    eprintln!("                                keep their previous author when at least <pct>% of");
+    eprintln!("                                tokens match (default: 70)");
+    eprintln!(
+        "    --inherit-from=<human|any>  Whose lines can be kept: human only (default) or any"
+    );
     eprintln!("    mock_ai [pathspecs...]      Test preset accepting optional file pathspecs");
     eprintln!("  blame <file>       Git blame with AI authorship overlay");
+    eprintln!("  introduced-by <file>:<line>  Show the commit, attribution, agent and session");
+    eprintln!("                     that introduced a line (accepts stack trace frames)");
+    eprintln!("    --rev <rev>           Look up the line as of a revision (default: HEAD)");
+    eprintln!("    --json                Output in JSON format");
+    eprintln!("  lineage <file>:<start>[-<end>]  Show the events that produced a hunk: AI");
+    eprintln!("                     checkpoints, human edits, rebases and squash merges");
+    eprintln!("    --rev <rev>           Look up the hunk as of a revision (default: HEAD)");
+    eprintln!("    --json                Output in JSON format");
+    eprintln!("  grep <pattern>     git grep, with each matching line's attribution and agent");
+    eprintln!("    --ai / --human        Only AI-authored / human-authored lines");
+    eprintln!("    --rev <rev>           Search a revision instead of the working tree");
+    eprintln!("    --json                Output in JSON format");
+    eprintln!("                          Other options and pathspecs are passed to git grep");
+    eprintln!("  session show <id>  Replay the edits an agent session made, per commit and");
+    eprintln!("                     uncommitted checkpoint, oldest first");
+    eprintln!("    --rev <rev>           Only commits reachable from a revision, no checkpoints");
+    eprintln!("    --json                Output in JSON format");
     eprintln!("  diff <commit|range>  Show diff with AI authorship annotations");
     eprintln!("    <commit>              Diff from commit's parent to commit");
     eprintln!("    <commit1>..<commit2>  Diff between two commits");
+    eprintln!("    --staged              Diff HEAD against the index, attributed from checkpoints");
     eprintln!("  stats [commit]     Show AI authorship statistics for a commit");
     eprintln!("    --json                 Output in JSON format");
+    eprintln!("    --deepen               In a shallow clone, fetch history the range needs");
+    eprintln!("    --by-language          Break down by programming language");
+    eprintln!("  report [rev|range] Summarize AI authorship by author and team");
+    eprintln!("    --since <time>        Only include commits after this time (default: 30d)");
+    eprintln!("    --identity-map <path> Identity/team mapping file (default: .git-ai-identities)");
+    eprintln!("    --by-package          Break down by monorepo workspace package");
+    eprintln!("    --by-language         Break down by programming language");
+    eprintln!(
+        "    --split-tests         Split production vs test code (config: test_path_patterns)"
+    );
+    eprintln!("    --classify            Count fully-AI / AI-assisted / human commits");
+    eprintln!("    --period <p>          Classification period: day, week (default), month");
+    eprintln!("    --json                Output in JSON format");
+    eprintln!(
+        "  top                List files with the most AI-authored lines and highest AI share"
+    );
+    eprintln!("    --since <time>        Only include commits after this time");
+    eprintln!("    -n, --limit <n>       Number of entries per list (default: 10)");
+    eprintln!(
+        "    --min-lines <n>       Minimum added lines for the AI share ranking (default: 10)"
+    );
+    eprintln!("    --dirs                Rank top-level directories instead of files");
+    eprintln!("    --depth <n>           Directory depth to rank (implies --dirs)");
+    eprintln!("    --json                Output in JSON format");
+    eprintln!("  compare <base>..<head>  Per-file AI/human added lines a merge of head would have");
+    eprintln!("    --ignore <pattern>    Leave out matching files (repeatable)");
+    eprintln!("    --json                Output in JSON format");
+    eprintln!("  heatmap            Export a per-line AI attribution cache for editor plugins");
+    eprintln!("    --output <path>       Cache file (default: .git/ai/heatmap)");
+    eprintln!("    --snapshot <path>     Reuse the AI lines of a snapshot instead of the cache");
+    eprintln!("    --full                Rebuild instead of reusing unchanged files");
+    eprintln!("    --json                Output a JSON summary");
+    eprintln!("  snapshot [<rev>]   Write the per-line attribution of the whole tree at <rev>");
+    eprintln!("                     (default: HEAD) to one JSON file");
+    eprintln!("    --output <path>       Snapshot file (default: .git/ai/snapshots/<commit>.json)");
+    eprintln!("    --json                Output a JSON summary");
+    eprintln!(
+        "  editor-host        Serve attribution to editor extensions over stdio (JSON lines)"
+    );
+    eprintln!(
+        "    --poll-interval-ms <ms>  How often to check for checkpoints/commits (default: 500)"
+    );
+    eprintln!("    --status [--json]        Report running hosts and whether any is wedged");
+    eprintln!("  daemon <subcommand> Keep watched repositories' heatmap caches current (Unix)");
+    eprintln!("    start|run [--poll-interval-ms <ms>]  Start in the background or foreground");
+    eprintln!("    watch|unwatch [<path>]               Add or remove a repository");
+    eprintln!(
+        "    status [--json]                      Report version, repositories and cache sizes"
+    );
+    eprintln!("    stop                                 Stop the daemon");
+    eprintln!("  query <file>       Print a file's AI line ranges as JSON");
+    eprintln!("    --rev <rev>           Query a revision instead of the working tree");
+    eprintln!("    --batch               Read many {{file, rev}} queries as JSON from stdin");
     eprintln!("  status             Show uncommitted AI authorship status (debug)");
     eprintln!("    --json                 Output in JSON format");
+    eprintln!("    --fail-on-unattributed Only check staged files; fail if any has no checkpoint");
     eprintln!("  show <rev|range>   Display authorship logs for a revision or range");
+    eprintln!("    --json                Output a commit summary (an array for ranges)");
+    eprintln!("  check              Verify authorship data before committing or merging");
+    eprintln!("    --staged               Fail if a staged file has no checkpoint (default)");
+    eprintln!("    --completeness <base>..<head>  Fail if any commit in the range lacks a note");
+    eprintln!("    --json                 Output in JSON format");
+    eprintln!("  verify --provenance [<commit>|<base>..<head>]  Fail if a note wasn't written");
+    eprintln!("                     and signed by trusted CI (Sigstore via gitsign, SSH or GPG)");
+    eprintln!("    --issuer <url> --subject <regex>  Trust this Sigstore identity too");
+    eprintln!("    --json                 Output in JSON format");
+    eprintln!(
+        "  simulate-agent <script.json|->  Replay scripted AI and human edits in a scratch repo"
+    );
+    eprintln!("    --keep                 Keep the scratch repository");
+    eprintln!("    --dir <path>           Create the scratch repository here (implies --keep)");
+    eprintln!("    --json                 Output the report as JSON");
     eprintln!("  show-prompt <id>   Display a prompt record by its ID");
     eprintln!("    --commit <rev>        Look in a specific commit only");
     eprintln!(
@@ -203,6 +414,25 @@ fn print_help() {
     );
     eprintln!("  share <id>         Share a prompt by creating a bundle");
     eprintln!("    --title <title>       Custom title for the bundle (default: auto-generated)");
+    eprintln!("  sync <fetch|push> [remote...]  Fetch or push authorship notes");
+    eprintln!(
+        "                     (hooks defer here after hook_network_budget_ms, default: 2000)"
+    );
+    eprintln!("  export [rev|range] Write commit and AI hunk events as JSONL for data warehouses");
+    eprintln!("    --since <time>        Only commits after this time (e.g. 7d, 2026-01-01)");
+    eprintln!("    --until <time>        Only commits before this time");
+    eprintln!("    --output <path>       Write to a file instead of stdout");
+    eprintln!("  import <file|->    Write notes from another tool's per-line attribution records");
+    eprintln!("    --format <csv|jsonl>  Input format (default: from the file extension)");
+    eprintln!("    --overwrite           Replace notes on commits that already have authorship");
+    eprintln!("    --dry-run             Validate without writing notes");
+    eprintln!("  fetch-missing [range]  Recover authorship notes missing from commits in range");
+    eprintln!("    --remote <name>        Remote to fetch notes from (default: default remote)");
+    eprintln!("    --offline              Don't fetch notes first");
+    eprintln!("    --dry-run              Report what could be recovered without writing notes");
+    eprintln!("    --json                 Output in JSON format");
+    eprintln!("    --all                 Sync with every configured remote, in parallel");
+    eprintln!("    --flush               Retry operations queued while the remote was unreachable");
     eprintln!("  sync-prompts       Update prompts in database to latest versions");
     eprintln!("    --since <time>        Only sync prompts updated after this time");
     eprintln!(
@@ -215,10 +445,24 @@ fn print_help() {
     eprintln!("    set <key> <value>     Set a config value (arrays: single value = [value])");
     eprintln!("    --add <key> <value>   Add to array or upsert into object");
     eprintln!("    unset <key>           Remove config value (reverts to default)");
+    eprintln!("  telemetry <on|off|status>  Manage opt-in anonymous usage telemetry");
+    eprintln!("  init               Set up git-ai for this repository");
+    eprintln!("                       Adds a notes fetch refspec to each remote");
+    eprintln!("    --remote <name>        Also sync authorship notes with this remote");
+    eprintln!("    --skip-hooks           Don't install coding agent hooks");
+    eprintln!("    --dry-run              Show what would be done without changing anything");
+    eprintln!(
+        "    --org --repos <file>   Set up every repository (path or clone URL) listed in file"
+    );
+    eprintln!("      --ci <kind>            CI template: auto (default), github, gitlab, none");
+    eprintln!("      --clone-dir <dir>      Where to clone repositories given as URLs");
+    eprintln!("      --json                 Output per-repository results as JSON");
     eprintln!("  install-hooks      Install git hooks for AI authorship tracking");
     eprintln!("  uninstall-hooks    Remove git-ai hooks from all detected tools");
     eprintln!("  ci                 Continuous integration utilities");
     eprintln!("    github                 GitHub CI helpers");
+    eprintln!("    gitlab                 GitLab CI helpers");
+    eprintln!("    selftest               Check the CI token's permissions");
     eprintln!("  squash-authorship  Generate authorship log for squashed commits");
     eprintln!(
         "    <base_branch> <new_sha> <old_sha>  Required: base branch, new commit SHA, old commit SHA"
@@ -239,6 +483,9 @@ fn print_help() {
     eprintln!("  login              Authenticate with Git AI");
     eprintln!("  logout             Clear stored credentials");
     eprintln!("  version, -v, --version     Print the git-ai version");
+    eprintln!("  doctor             Show supported note capabilities and check notes are readable");
+    eprintln!("    --json                 Output in JSON format");
+    eprintln!("    --fix                  Add missing notes fetch refspecs to remotes");
     eprintln!("  help, -h, --help           Show this help message");
     eprintln!();
     std::process::exit(0);
@@ -253,7 +500,9 @@ fn handle_checkpoint(args: &[String]) {
     // Parse checkpoint-specific arguments
     let mut show_working_log = false;
     let mut reset = false;
+    let mut staged = false;
     let mut hook_input = None;
+    let mut inherit: Option<InheritanceRules> = None;
 
     let mut i = 0;
     while i < args.len() {
@@ -266,6 +515,31 @@ fn handle_checkpoint(args: &[String]) {
                 reset = true;
                 i += 1;
             }
+            "--staged" => {
+                staged = true;
+                i += 1;
+            }
+            "--inherit-attribution" => {
+                inherit.get_or_insert_with(InheritanceRules::default);
+                i += 1;
+            }
+            arg if arg.starts_with("--inherit-attribution=")
+                || arg.starts_with("--inherit-from=") =>
+            {
+                let (flag, value) = arg.split_once('=').unwrap();
+                let rules = inherit.get_or_insert_with(InheritanceRules::default);
+                let parsed = if flag == "--inherit-from" {
+                    InheritanceRules::parse_from(value).map(|from| rules.from = from)
+                } else {
+                    InheritanceRules::parse_min_similarity(value)
+                        .map(|similarity| rules.min_similarity = similarity)
+                };
+                if let Err(e) = parsed {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(0);
+                }
+                i += 1;
+            }
             "--hook-input" => {
                 if i + 1 < args.len() {
                     hook_input = Some(args[i + 1].clone());
@@ -482,6 +756,7 @@ fn handle_checkpoint(args: &[String]) {
                     edited_filepaths,
                     will_edit_filepaths: None,
                     dirty_files: None,
+                    token_usage: None,
                 });
             }
             _ => {}
@@ -598,7 +873,7 @@ fn handle_checkpoint(args: &[String]) {
                 };
 
                 // Create a modified agent_run_result with only this repo's files
-                let repo_agent_result = agent_run_result.as_ref().map(|r| {
+                let mut repo_agent_result = agent_run_result.as_ref().map(|r| {
                     let mut modified = r.clone();
                     modified.repo_working_dir = Some(repo_workdir.to_string_lossy().to_string());
                     if r.checkpoint_kind == CheckpointKind::Human {
@@ -611,6 +886,18 @@ fn handle_checkpoint(args: &[String]) {
                     modified
                 });
 
+                if staged
+                    && let Some(result) = repo_agent_result.as_mut()
+                    && let Err(e) = scope_checkpoint_to_staged(&repo, result)
+                {
+                    eprintln!(
+                        "  Failed to read staged files for {}: {}",
+                        repo_workdir.display(),
+                        e
+                    );
+                    continue;
+                }
+
                 let checkpoint_result = commands::checkpoint::run(
                     &repo,
                     &default_user_name,
@@ -620,6 +907,7 @@ fn handle_checkpoint(args: &[String]) {
                     false,
                     repo_agent_result,
                     false,
+                    inherit,
                 );
 
                 match checkpoint_result {
@@ -710,9 +998,18 @@ fn handle_checkpoint(args: &[String]) {
             edited_filepaths: None,
             repo_working_dir: Some(effective_working_dir),
             dirty_files: None,
+            token_usage: None,
         });
     }
 
+    if staged
+        && let Some(result) = agent_run_result.as_mut()
+        && let Err(e) = scope_checkpoint_to_staged(&repo, result)
+    {
+        eprintln!("Failed to read staged files: {}", e);
+        std::process::exit(0);
+    }
+
     // Get the current user name from git config
     let default_user_name = match repo.config_get_str("user.name") {
         Ok(Some(name)) if !name.trim().is_empty() => name,
@@ -733,6 +1030,7 @@ fn handle_checkpoint(args: &[String]) {
         false,
         agent_run_result,
         false,
+        inherit,
     );
     match checkpoint_result {
         Ok((_, files_edited, _)) => {
@@ -760,6 +1058,53 @@ fn handle_checkpoint(args: &[String]) {
     }
 }
 
+/// `checkpoint --staged`: limit the checkpoint to staged files and read their content
+/// from the index, so an agent that stages its own changes is credited with exactly the
+/// staged hunks. Anything else in the working tree is left for the next checkpoint.
+fn scope_checkpoint_to_staged(
+    repo: &Repository,
+    result: &mut AgentRunResult,
+) -> Result<(), GitAiError> {
+    let staged = repo.get_staged_file_contents()?;
+    let workdir = repo.workdir()?;
+
+    let is_human = result.checkpoint_kind == CheckpointKind::Human;
+    let requested = if is_human {
+        &result.will_edit_filepaths
+    } else {
+        &result.edited_filepaths
+    };
+    let mut files: Vec<String> = match requested {
+        Some(paths) => paths
+            .iter()
+            .map(|path| {
+                let path = std::path::Path::new(path);
+                path.strip_prefix(&workdir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .filter(|path| staged.contains_key(path))
+            .collect(),
+        None => staged.keys().cloned().collect(),
+    };
+    files.sort();
+    files.dedup();
+
+    result.dirty_files = Some(
+        files
+            .iter()
+            .map(|file| (file.clone(), staged[file].clone()))
+            .collect(),
+    );
+    if is_human {
+        result.will_edit_filepaths = Some(files);
+    } else {
+        result.edited_filepaths = Some(files);
+    }
+    Ok(())
+}
+
 fn handle_ai_blame(args: &[String]) {
     if args.is_empty() {
         eprintln!("Error: blame requires a file argument");
@@ -868,8 +1213,10 @@ fn handle_stats(args: &[String]) {
     };
     // Parse stats-specific arguments
     let mut json_output = false;
+    let mut deepen = false;
+    let mut by_language = false;
     let mut commit_sha = None;
-    let mut commit_range: Option<CommitRange> = None;
+    let mut range_bounds: Option<(String, String)> = None;
     let mut ignore_patterns: Vec<String> = Vec::new();
 
     let mut i = 0;
@@ -879,6 +1226,14 @@ fn handle_stats(args: &[String]) {
                 json_output = true;
                 i += 1;
             }
+            "--deepen" => {
+                deepen = true;
+                i += 1;
+            }
+            "--by-language" => {
+                by_language = true;
+                i += 1;
+            }
             "--ignore" => {
                 // Collect all arguments after --ignore until we hit another flag or commit SHA
                 // This supports shell glob expansion: `--ignore *.lock` expands to `--ignore Cargo.lock package.lock`
@@ -908,27 +1263,13 @@ fn handle_stats(args: &[String]) {
             }
             _ => {
                 // First non-flag argument is treated as commit SHA or range
-                if commit_sha.is_none() {
+                if commit_sha.is_none() && range_bounds.is_none() {
                     let arg = &args[i];
                     // Check if this is a commit range (contains "..")
                     if arg.contains("..") {
                         let parts: Vec<&str> = arg.split("..").collect();
                         if parts.len() == 2 {
-                            match CommitRange::new_infer_refname(
-                                &repo,
-                                parts[0].to_string(),
-                                parts[1].to_string(),
-                                // @todo this is probably fine, but we might want to give users an option to override from this command.
-                                None,
-                            ) {
-                                Ok(range) => {
-                                    commit_range = Some(range);
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to create commit range: {}", e);
-                                    std::process::exit(1);
-                                }
-                            }
+                            range_bounds = Some((parts[0].to_string(), parts[1].to_string()));
                         } else {
                             eprintln!("Invalid commit range format. Expected: <commit>..<commit>");
                             std::process::exit(1);
@@ -945,15 +1286,54 @@ fn handle_stats(args: &[String]) {
         }
     }
 
-    // Handle commit range if detected
-    if let Some(range) = commit_range {
+    // Plus the repository's `.git-ai.toml` ignore list
+    ignore_patterns.extend(
+        config::Config::for_repo(&repo)
+            .ignore_patterns()
+            .iter()
+            .cloned(),
+    );
+
+    if let Some((start, end)) = &range_bounds {
+        let start = resolve_shallow_range_start(&repo, start, end, deepen);
+        let languages = if by_language {
+            match language_breakdown(&repo, &[format!("{}..{}", start, end)], &ignore_patterns) {
+                Ok(languages) => Some(languages),
+                Err(e) => {
+                    eprintln!("Language breakdown failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        } else {
+            None
+        };
+        let range = match CommitRange::new_infer_refname(
+            &repo,
+            start,
+            end.clone(),
+            // @todo this is probably fine, but we might want to give users an option to override from this command.
+            None,
+        ) {
+            Ok(range) => range,
+            Err(e) => {
+                eprintln!("Failed to create commit range: {}", e);
+                std::process::exit(1);
+            }
+        };
         match range_authorship::range_authorship(range, false, &ignore_patterns) {
             Ok(stats) => {
                 if json_output {
-                    let json_str = serde_json::to_string(&stats).unwrap();
+                    let json_str = serde_json::to_string(&WithLanguages {
+                        stats: &stats,
+                        by_language: languages.as_ref(),
+                    })
+                    .unwrap();
                     println!("{}", json_str);
                 } else {
                     range_authorship::print_range_authorship_stats(&stats);
+                    if let Some(languages) = languages {
+                        print_section("By language", &languages);
+                    }
                 }
             }
             Err(e) => {
@@ -964,7 +1344,14 @@ fn handle_stats(args: &[String]) {
         return;
     }
 
-    if let Err(e) = stats_command(&repo, commit_sha.as_deref(), json_output, &ignore_patterns) {
+    warn_if_shallow_boundary_commit(&repo, commit_sha.as_deref().unwrap_or("HEAD"), deepen);
+    if let Err(e) = stats_command(
+        &repo,
+        commit_sha.as_deref(),
+        json_output,
+        by_language,
+        &ignore_patterns,
+    ) {
         match e {
             crate::error::GitAiError::Generic(msg) if msg.starts_with("No commit found:") => {
                 eprintln!("{}", msg);
@@ -977,6 +1364,82 @@ fn handle_stats(args: &[String]) {
     }
 }
 
+/// In a shallow clone a range can start past the available history. Fetch enough of it
+/// with `--deepen`, otherwise start from the shallow boundary and say so.
+fn resolve_shallow_range_start(repo: &Repository, start: &str, end: &str, deepen: bool) -> String {
+    // rev-parse accepts any full hash, so also check the commit is actually present
+    let resolves = |rev: &str| {
+        repo.revparse_single(rev)
+            .and_then(|object| repo.find_commit(object.id()))
+            .is_ok()
+    };
+    if !shallow::is_shallow(repo) || resolves(start) || !resolves(end) {
+        return start.to_string();
+    }
+
+    if deepen {
+        let remote = repo
+            .get_default_remote()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "origin".to_string());
+        match shallow::deepen_until(repo, &remote, || resolves(start)) {
+            Ok(true) => return start.to_string(),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to deepen history: {}", e),
+        }
+    }
+
+    let Some(boundary) = shallow::boundary_ancestor_of(repo, end) else {
+        return start.to_string();
+    };
+    eprintln!(
+        "warning: {} is not available in this shallow clone; showing stats for {}..{} instead.",
+        start,
+        &boundary[..boundary.len().min(8)],
+        end
+    );
+    if !deepen {
+        eprintln!("Re-run with --deepen to fetch the missing history.");
+    }
+    boundary
+}
+
+/// A shallow boundary commit has no parent locally, so its stats would count the whole
+/// tree as added. Deepen by one commit when asked, otherwise warn.
+fn warn_if_shallow_boundary_commit(repo: &Repository, rev: &str, deepen: bool) {
+    if !shallow::is_shallow(repo) {
+        return;
+    }
+    let Ok(commit) = repo.revparse_single(rev) else {
+        return;
+    };
+    let commit = commit.id();
+    let at_boundary = || shallow::shallow_boundary(repo).contains(&commit);
+    if !at_boundary() {
+        return;
+    }
+    if deepen {
+        let remote = repo
+            .get_default_remote()
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "origin".to_string());
+        match shallow::deepen_until(repo, &remote, || !at_boundary()) {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to deepen history: {}", e),
+        }
+    }
+    eprintln!(
+        "warning: {} is at the shallow clone boundary and its parent isn't available, so every line in it counts as added.",
+        &commit[..commit.len().min(8)]
+    );
+    if !deepen {
+        eprintln!("Re-run with --deepen to fetch its parent.");
+    }
+}
+
 fn get_all_files_for_mock_ai(working_dir: &str) -> Vec<String> {
     // Find the git repository
     let repo = match find_repository_in_path(working_dir) {
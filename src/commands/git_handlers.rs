@@ -16,6 +16,7 @@ use crate::config;
 use crate::git::cli_parser::{ParsedGitInvocation, parse_git_cli_args};
 use crate::git::find_repository;
 use crate::git::repository::Repository;
+use crate::git::sync_authorship::{self, NotesSyncHandle};
 use crate::observability;
 
 use crate::observability::wrapper_performance_targets::log_performance_target_if_violated;
@@ -90,9 +91,9 @@ pub struct CommandHooksContext {
     pub pre_commit_hook_result: Option<bool>,
     pub rebase_original_head: Option<String>,
     pub _rebase_onto: Option<String>,
-    pub fetch_authorship_handle: Option<std::thread::JoinHandle<()>>,
+    pub fetch_authorship_handle: Option<NotesSyncHandle>,
     pub stash_sha: Option<String>,
-    pub push_authorship_handle: Option<std::thread::JoinHandle<()>>,
+    pub push_authorship_handle: Option<NotesSyncHandle>,
     /// VirtualAttributions captured before a pull --rebase --autostash operation.
     /// Used to preserve uncommitted AI attributions that git's internal stash would lose.
     pub stashed_va: Option<VirtualAttributions>,
@@ -179,6 +180,12 @@ pub fn handle_git(args: &[String]) {
             git_duration,
             post_command_duration,
         );
+        observability::usage::record_command(
+            "git",
+            parsed_args.command.as_deref().unwrap_or("unknown"),
+            pre_command_duration + git_duration + post_command_duration,
+            Some(repository),
+        );
 
         exit_status
     } else {
@@ -319,6 +326,11 @@ fn run_pre_command_hooks(
     repository: &mut Repository,
 ) {
     let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        #[cfg(any(test, feature = "test-support"))]
+        if std::env::var("GIT_AI_TEST_PANIC_IN_HOOK").is_ok() {
+            panic!("GIT_AI_TEST_PANIC_IN_HOOK set");
+        }
+
         // Pre-command hooks
         match parsed_args.command.as_deref() {
             Some("commit") => {
@@ -465,6 +477,9 @@ fn run_post_command_hooks(
             }
             _ => {}
         }
+
+        // Retry notes operations that were queued while the remote was unreachable
+        sync_authorship::maybe_flush_pending_sync(repository);
     }));
 
     if let Err(panic_payload) = result {
@@ -0,0 +1,262 @@
+use crate::commands::blame::GitAiBlameOptions;
+use crate::commands::introduced_by::LineAttribution;
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::repository::{Repository, exec_git};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize)]
+pub struct GrepMatch {
+    pub file: String,
+    pub line: u32,
+    pub text: String,
+    pub attribution: LineAttribution,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// The human author, for human lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+pub fn handle_grep(args: &[String]) {
+    let mut rev: Option<String> = None;
+    let mut only: Option<LineAttribution> = None;
+    let mut json_output = false;
+    let mut grep_args: Vec<String> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--rev" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --rev requires a revision");
+                    std::process::exit(1);
+                }
+                rev = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--ai" => {
+                only = Some(LineAttribution::Ai);
+                i += 1;
+            }
+            "--human" => {
+                only = Some(LineAttribution::Human);
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            // Everything after `--` is a pathspec, even our own flag names
+            "--" => {
+                grep_args.extend(args[i..].iter().cloned());
+                break;
+            }
+            _ => {
+                grep_args.push(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    if grep_args
+        .iter()
+        .all(|arg| arg == "--" || arg.starts_with('-'))
+    {
+        eprintln!(
+            "Usage: git-ai grep [--ai|--human] [--rev <rev>] [--json] [<git grep options>] <pattern> [-- <pathspec>...]"
+        );
+        std::process::exit(1);
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut matches = match grep_with_attribution(&repo, &grep_args, rev.as_deref()) {
+        Ok(matches) => matches,
+        Err(e) => {
+            eprintln!("Failed to search: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(only) = only {
+        matches.retain(|m| m.attribution == only);
+    }
+
+    if json_output {
+        match serde_json::to_string(&matches) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize result: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        for m in &matches {
+            println!("{}:{}: [{}] {}", m.file, m.line, label(m), m.text);
+        }
+    }
+    // Like git grep, exit 1 when nothing matched
+    if matches.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Run `git grep` with `grep_args` over the working tree, or over `rev`'s tree, and
+/// attribute each matching line by blaming it
+pub fn grep_with_attribution(
+    repo: &Repository,
+    grep_args: &[String],
+    rev: Option<&str>,
+) -> Result<Vec<GrepMatch>, GitAiError> {
+    let by_file = git_grep(grep_args, rev)?;
+
+    let mut matches = Vec::new();
+    for (file, lines) in by_file {
+        // One blame of the whole file beats one per matching line
+        let options = GitAiBlameOptions {
+            newest_commit: rev.map(str::to_string),
+            use_prompt_hashes_as_names: true,
+            mark_unknown: true,
+            no_output: true,
+            ..Default::default()
+        };
+        // A file that was never committed has nothing to blame; its lines stay unknown
+        let (line_authors, prompts) = repo.blame(&file, &options).unwrap_or_default();
+        for (line, text) in lines {
+            let author = line_authors.get(&line);
+            let mut grep_match = GrepMatch {
+                file: file.clone(),
+                line,
+                text,
+                attribution: LineAttribution::Unknown,
+                agent: None,
+                model: None,
+                session: None,
+                author: None,
+            };
+            match author.map(|author| (author, prompts.get(author))) {
+                Some((_, Some(prompt))) => {
+                    grep_match.attribution = LineAttribution::Ai;
+                    grep_match.agent = Some(prompt.agent_id.tool.clone());
+                    grep_match.model = Some(prompt.agent_id.model.clone());
+                    grep_match.session = Some(prompt.agent_id.id.clone());
+                }
+                // Not committed yet, or from a commit without an authorship note
+                Some((author, None)) if author == "Unknown" => {}
+                Some((author, None)) => {
+                    grep_match.attribution = LineAttribution::Human;
+                    grep_match.author = Some(author.clone());
+                }
+                None => {}
+            }
+            matches.push(grep_match);
+        }
+    }
+    Ok(matches)
+}
+
+/// Matching lines per repository-relative path, from `git grep` run in the current
+/// directory so pathspecs resolve the way the user typed them
+fn git_grep(
+    grep_args: &[String],
+    rev: Option<&str>,
+) -> Result<BTreeMap<String, BTreeMap<u32, String>>, GitAiError> {
+    let mut args = vec![
+        "grep".to_string(),
+        "--line-number".to_string(),
+        "--null".to_string(),
+        "--full-name".to_string(),
+        "--no-color".to_string(),
+        "-I".to_string(),
+    ];
+    // The tree goes after the pattern but before any pathspecs
+    let pathspec_start = grep_args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(grep_args.len());
+    args.extend(grep_args[..pathspec_start].iter().cloned());
+    if let Some(rev) = rev {
+        args.push(rev.to_string());
+    }
+    args.extend(grep_args[pathspec_start..].iter().cloned());
+
+    let output = match exec_git(&args) {
+        Ok(output) => output,
+        // No matches
+        Err(GitAiError::GitCliError { code: Some(1), .. }) => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(parse_grep_output(
+        &String::from_utf8_lossy(&output.stdout),
+        rev,
+    ))
+}
+
+/// Parse `path\0line\0text` lines; with a tree the path is prefixed by `<rev>:`
+fn parse_grep_output(stdout: &str, rev: Option<&str>) -> BTreeMap<String, BTreeMap<u32, String>> {
+    let mut by_file: BTreeMap<String, BTreeMap<u32, String>> = BTreeMap::new();
+    for line in stdout.lines() {
+        let mut fields = line.splitn(3, '\0');
+        let (Some(path), Some(number), Some(text)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Ok(number) = number.parse::<u32>() else {
+            continue;
+        };
+        let path = rev
+            .and_then(|rev| path.strip_prefix(rev)?.strip_prefix(':'))
+            .unwrap_or(path);
+        by_file
+            .entry(path.to_string())
+            .or_default()
+            .insert(number, text.to_string());
+    }
+    by_file
+}
+
+fn label(m: &GrepMatch) -> String {
+    match m.attribution {
+        LineAttribution::Ai => match (&m.agent, &m.model) {
+            (Some(agent), Some(model)) if !model.is_empty() => format!("ai {} {}", agent, model),
+            (Some(agent), _) => format!("ai {}", agent),
+            _ => "ai".to_string(),
+        },
+        LineAttribution::Human => match &m.author {
+            Some(author) => format!("human {}", author),
+            None => "human".to_string(),
+        },
+        LineAttribution::Unknown => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grep_output() {
+        let stdout = "src/a.rs\x0012\x00    exec(cmd);\nsrc/a.rs\x003\x00let a: u8 = 1;\nHEAD~1:b.py\x007\x00os.system(x)\nbinary\n";
+        let parsed = parse_grep_output(stdout, Some("HEAD~1"));
+        assert_eq!(
+            parsed["src/a.rs"].iter().collect::<Vec<_>>(),
+            vec![
+                (&3, &"let a: u8 = 1;".to_string()),
+                (&12, &"    exec(cmd);".to_string())
+            ]
+        );
+        assert_eq!(parsed["b.py"][&7], "os.system(x)");
+        assert_eq!(parsed.len(), 2);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::authorship::heatmap::{Heatmap, refresh_heatmap};
+use crate::authorship::snapshot::Snapshot;
+use crate::git::find_repository;
+use std::path::PathBuf;
+
+pub fn handle_heatmap(args: &[String]) {
+    let mut output: Option<PathBuf> = None;
+    let mut snapshot: Option<PathBuf> = None;
+    let mut full = false;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--output" | "-o" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --output requires a path");
+                    std::process::exit(1);
+                }
+                output = Some(PathBuf::from(&args[i + 1]));
+                i += 2;
+            }
+            "--snapshot" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --snapshot requires a path");
+                    std::process::exit(1);
+                }
+                snapshot = Some(PathBuf::from(&args[i + 1]));
+                i += 2;
+            }
+            "--full" => {
+                full = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            arg => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                eprintln!(
+                    "Usage: git-ai heatmap [--output <path>] [--snapshot <path>] [--full] [--json]"
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let path = output.unwrap_or_else(|| Heatmap::cache_path(&repo));
+
+    // Reuse bitmaps from a snapshot, or from the existing cache unless a full rebuild was
+    // requested. An unreadable cache (e.g. from an older format) is simply rebuilt.
+    let previous = if let Some(snapshot) = snapshot {
+        match Snapshot::load(&snapshot) {
+            Ok(snapshot) => Some(Heatmap::from_snapshot(&snapshot)),
+            Err(e) => {
+                eprintln!("Failed to read snapshot {}: {}", snapshot.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else if full {
+        None
+    } else {
+        Heatmap::load(&path).ok().flatten()
+    };
+
+    let (heatmap, stats) = match refresh_heatmap(&repo, previous.as_ref()) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Failed to build heatmap: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = heatmap.write(&path) {
+        eprintln!("Failed to write heatmap to {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+
+    let ai_lines: u32 = heatmap.files.values().map(|f| f.ai_line_count()).sum();
+
+    if json_output {
+        let summary = serde_json::json!({
+            "path": path.to_string_lossy(),
+            "head": heatmap.head,
+            "files": heatmap.files.len(),
+            "ai_lines": ai_lines,
+            "reused": stats.reused,
+            "computed": stats.computed,
+        });
+        println!("{}", summary);
+    } else {
+        println!(
+            "Wrote heatmap for {} AI-touched file(s), {} AI line(s) at {} to {} ({} reused, {} recomputed)",
+            heatmap.files.len(),
+            ai_lines,
+            &heatmap.head[..heatmap.head.len().min(7)],
+            path.display(),
+            stats.reused,
+            stats.computed
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::git::cli_parser::{ParsedGitInvocation, extract_clone_target_directory};
 use crate::git::repository::find_repository_in_path;
-use crate::git::sync_authorship::fetch_authorship_notes;
+use crate::git::sync_authorship::{
+    NotesSyncHandle, NotesSyncOp, ensure_remote_refspec, notes_fetch_refspec,
+};
 use crate::utils::debug_log;
 
 pub fn post_clone_hook(parsed_args: &ParsedGitInvocation, exit_status: std::process::ExitStatus) {
@@ -38,12 +40,43 @@ pub fn post_clone_hook(parsed_args: &ParsedGitInvocation, exit_status: std::proc
         }
     };
 
-    // Fetch authorship notes from origin
-    if let Err(e) = fetch_authorship_notes(&repository, "origin") {
-        debug_log(&format!("authorship fetch from origin failed: {}", e));
-        println!(", failed.");
-    } else {
-        debug_log("successfully fetched authorship notes from origin");
-        println!(", done.");
+    // A fresh clone has exactly one remote, which `-o/--origin` may have renamed
+    let remote_name = repository
+        .remotes()
+        .ok()
+        .and_then(|remotes| remotes.into_iter().find(|name| !name.is_empty()))
+        .unwrap_or_else(|| "origin".to_string());
+
+    // The default fetch refspec never includes notes, and `--single-branch`/`--no-tags`
+    // clones narrow it further. Persist a notes refspec so later fetches keep them current.
+    match ensure_remote_refspec(
+        &repository,
+        &remote_name,
+        "fetch",
+        &notes_fetch_refspec(&remote_name),
+    ) {
+        Ok(_) => debug_log(&format!(
+            "configured notes fetch refspec for remote '{}'",
+            remote_name
+        )),
+        Err(e) => debug_log(&format!(
+            "failed to configure notes fetch refspec for remote '{}': {}",
+            remote_name, e
+        )),
+    }
+
+    // Fetch authorship notes now, leaving the fetch to finish in the background if it
+    // outlasts the hook network budget
+    let handle = NotesSyncHandle::spawn(&repository, NotesSyncOp::Fetch, vec![remote_name]);
+    match handle.wait_or_defer(&repository) {
+        Some(Ok(())) => {
+            debug_log("successfully fetched authorship notes from origin");
+            println!(", done.");
+        }
+        Some(Err(e)) => {
+            debug_log(&format!("authorship fetch from origin failed: {}", e));
+            println!(", failed.");
+        }
+        None => println!(", continuing in background."),
     }
 }
@@ -68,8 +68,18 @@ pub fn commit_post_command_hook(
         return;
     }
 
+    // HEAD didn't move, so nothing was committed
+    if new_sha == original_commit {
+        return;
+    }
+
     let commit_author = get_commit_default_author(repository, &parsed_args.command_args);
-    if parsed_args.has_command_flag("--amend") {
+    let is_amend = parsed_args.has_command_flag("--amend")
+        || original_commit
+            .as_deref()
+            .zip(new_sha.as_deref())
+            .is_some_and(|(orig, new)| is_amended_commit(repository, orig, new));
+    if is_amend {
         if let (Some(orig), Some(sha)) = (original_commit.clone(), new_sha.clone()) {
             repository.handle_rewrite_log_event(
                 RewriteLogEvent::commit_amend(orig, sha),
@@ -98,6 +108,21 @@ pub fn commit_post_command_hook(
     crate::observability::spawn_background_flush();
 }
 
+/// Whether `new_sha` replaced `original` instead of building on top of it: both have the
+/// same parents. Catches amends that never show up as a literal `--amend` argument, such
+/// as abbreviated options (`--amen`).
+fn is_amended_commit(repo: &Repository, original: &str, new_sha: &str) -> bool {
+    let parents_of = |sha: &str| {
+        repo.git(&["show", "-s", "--format=%P", sha])
+            .ok()
+            .map(|parents| parents.trim().to_string())
+    };
+    match (parents_of(original), parents_of(new_sha)) {
+        (Some(original_parents), Some(new_parents)) => original_parents == new_parents,
+        _ => false,
+    }
+}
+
 pub fn get_commit_default_author(repo: &Repository, args: &[String]) -> String {
     // According to git commit manual, --author flag overrides all other author information
     if let Some(author_spec) = extract_author_from_args(args)
@@ -4,15 +4,18 @@ use crate::commands::hooks::commit_hooks::get_commit_default_author;
 use crate::commands::hooks::rebase_hooks::build_rebase_commit_mappings;
 use crate::commands::upgrade;
 use crate::git::cli_parser::{ParsedGitInvocation, is_dry_run};
-use crate::git::repository::{Repository, exec_git, find_repository};
+use crate::git::repository::{Repository, exec_git};
 use crate::git::rewrite_log::RewriteLogEvent;
-use crate::git::sync_authorship::{fetch_authorship_notes, fetch_remote_from_args};
+use crate::git::sync_authorship::{
+    NotesSyncHandle, NotesSyncOp, fetch_remotes_from_args, has_notes_fetch_refspec,
+    merge_fetched_notes,
+};
 use crate::utils::debug_log;
 
 pub fn fetch_pull_pre_command_hook(
     parsed_args: &ParsedGitInvocation,
     repository: &Repository,
-) -> Option<std::thread::JoinHandle<()>> {
+) -> Option<NotesSyncHandle> {
     upgrade::maybe_schedule_background_update_check();
 
     // Early return for dry-run
@@ -22,33 +25,47 @@ pub fn fetch_pull_pre_command_hook(
 
     crate::observability::spawn_background_flush();
 
-    // Extract the remote name
-    let remote = match fetch_remote_from_args(repository, parsed_args) {
-        Ok(remote) => remote,
+    // Extract the remote names (several for --all / --multiple)
+    let remotes = match fetch_remotes_from_args(repository, parsed_args) {
+        Ok(remotes) => remotes,
         Err(_) => {
             debug_log("failed to extract remote for authorship fetch; skipping");
             return None;
         }
     };
 
-    // Clone what we need for the background thread
-    let global_args = repository.global_args_for_exec();
+    // With a notes fetch refspec configured (see `git-ai init`), the fetch itself brings
+    // the remote's notes and the post-command hook merges them. A second fetch of the
+    // same tracking ref would only race it.
+    let remotes: Vec<String> = remotes
+        .into_iter()
+        .filter(|remote| !has_notes_fetch_refspec(repository, remote))
+        .collect();
+    if remotes.is_empty() {
+        return None;
+    }
 
-    // Spawn background thread to fetch authorship notes in parallel with main fetch
-    Some(std::thread::spawn(move || {
-        debug_log(&format!(
-            "started fetching authorship notes from remote: {}",
-            remote
-        ));
-        // Recreate repository in the background thread
-        if let Ok(repo) = find_repository(&global_args) {
-            if let Err(e) = fetch_authorship_notes(&repo, &remote) {
-                debug_log(&format!("authorship fetch failed: {}", e));
-            }
-        } else {
-            debug_log("failed to open repository for authorship fetch");
+    // Fetch authorship notes in a child process, in parallel with the main fetch
+    Some(NotesSyncHandle::spawn(
+        repository,
+        NotesSyncOp::Fetch,
+        remotes,
+    ))
+}
+
+/// Merge the notes the fetch itself brought from remotes with a notes fetch refspec
+fn merge_notes_fetched_by_refspec(parsed_args: &ParsedGitInvocation, repository: &Repository) {
+    if is_dry_run(&parsed_args.command_args) {
+        return;
+    }
+    let Ok(remotes) = fetch_remotes_from_args(repository, parsed_args) else {
+        return;
+    };
+    for remote in remotes {
+        if has_notes_fetch_refspec(repository, &remote) {
+            merge_fetched_notes(repository, &remote);
         }
-    }))
+    }
 }
 
 /// Pre-command hook for git pull.
@@ -116,17 +133,18 @@ pub fn pull_pre_command_hook(
 }
 
 pub fn fetch_pull_post_command_hook(
-    _repository: &Repository,
-    _parsed_args: &ParsedGitInvocation,
+    repository: &Repository,
+    parsed_args: &ParsedGitInvocation,
     _exit_status: std::process::ExitStatus,
     command_hooks_context: &mut CommandHooksContext,
 ) {
-    // Always wait for the authorship fetch thread to complete if it was started,
-    // regardless of whether the main fetch/pull succeeded or failed.
-    // This ensures proper cleanup of the background thread.
+    // Always wait for the authorship fetch if it was started, regardless of whether
+    // the main fetch/pull succeeded or failed. Past the hook network budget the fetch
+    // is left to finish in the background instead.
     if let Some(handle) = command_hooks_context.fetch_authorship_handle.take() {
-        let _ = handle.join();
+        let _ = handle.wait_or_defer(repository);
     }
+    merge_notes_fetched_by_refspec(parsed_args, repository);
 }
 
 /// Post-command hook for git pull.
@@ -139,10 +157,11 @@ pub fn pull_post_command_hook(
     exit_status: std::process::ExitStatus,
     command_hooks_context: &mut CommandHooksContext,
 ) {
-    // Wait for authorship fetch thread
+    // Wait for the authorship fetch (or leave it running past the hook network budget)
     if let Some(handle) = command_hooks_context.fetch_authorship_handle.take() {
-        let _ = handle.join();
+        let _ = handle.wait_or_defer(repository);
     }
+    merge_notes_fetched_by_refspec(parsed_args, repository);
 
     if !exit_status.success() {
         debug_log("Pull failed, skipping post-pull authorship restoration");
@@ -1,14 +1,14 @@
 use crate::commands::git_handlers::CommandHooksContext;
 use crate::commands::upgrade;
 use crate::git::cli_parser::{ParsedGitInvocation, is_dry_run};
-use crate::git::repository::{Repository, find_repository};
-use crate::git::sync_authorship::push_authorship_notes;
+use crate::git::repository::Repository;
+use crate::git::sync_authorship::{NotesSyncHandle, NotesSyncOp, warn_if_notes_push_blocked};
 use crate::utils::debug_log;
 
 pub fn push_pre_command_hook(
     parsed_args: &ParsedGitInvocation,
     repository: &Repository,
-) -> Option<std::thread::JoinHandle<()>> {
+) -> Option<NotesSyncHandle> {
     upgrade::maybe_schedule_background_update_check();
 
     // Early returns for cases where we shouldn't push authorship notes
@@ -48,31 +48,29 @@ pub fn push_pre_command_hook(
         .or_else(|| repository.get_default_remote().ok().flatten());
 
     if let Some(remote) = remote {
-        debug_log(&format!(
-            "started pushing authorship notes to remote: {}",
-            remote
-        ));
-        // Clone what we need for the background thread
-        let global_args = repository.global_args_for_exec();
-
         crate::observability::spawn_background_flush();
 
+        // The host refused notes before; pushing again would only fail the same way
+        if repository.storage.blocked_notes_push(&remote).is_some() {
+            debug_log(&format!(
+                "notes pushes to '{}' are blocked by the remote; skipping",
+                remote
+            ));
+            warn_if_notes_push_blocked(repository, &remote);
+            return None;
+        }
+
         // Spawn CAS flush if prompt_storage is "default" (CAS upload mode)
         if crate::config::Config::get().prompt_storage() == "default" {
             crate::commands::flush_cas::spawn_background_cas_flush();
         }
 
-        // Spawn background thread to push authorship notes in parallel with main push
-        Some(std::thread::spawn(move || {
-            // Recreate repository in the background thread
-            if let Ok(repo) = find_repository(&global_args) {
-                if let Err(e) = push_authorship_notes(&repo, &remote) {
-                    debug_log(&format!("authorship push failed: {}", e));
-                }
-            } else {
-                debug_log("failed to open repository for authorship push");
-            }
-        }))
+        // Push authorship notes in a child process, in parallel with the main push
+        Some(NotesSyncHandle::spawn(
+            repository,
+            NotesSyncOp::Push,
+            vec![remote],
+        ))
     } else {
         // No remotes configured; skip silently
         debug_log("no remotes found for authorship push; skipping");
@@ -81,16 +79,19 @@ pub fn push_pre_command_hook(
 }
 
 pub fn push_post_command_hook(
-    _repository: &Repository,
+    repository: &Repository,
     _parsed_args: &ParsedGitInvocation,
     _exit_status: std::process::ExitStatus,
     command_hooks_context: &mut CommandHooksContext,
 ) {
-    // Always wait for the authorship push thread to complete if it was started,
-    // regardless of whether the main push succeeded or failed.
-    // This ensures proper cleanup of the background thread.
+    // Always wait for the authorship push if it was started, regardless of whether
+    // the main push succeeded or failed. Past the hook network budget the push is
+    // left to finish in the background instead.
     if let Some(handle) = command_hooks_context.push_authorship_handle.take() {
-        let _ = handle.join();
+        let _ = handle.wait_or_defer(repository);
+    }
+    for remote in repository.remotes().unwrap_or_default() {
+        warn_if_notes_push_blocked(repository, &remote);
     }
 }
 
@@ -38,6 +38,7 @@ pub fn pre_stash_hook(
             true,
             None,
             true, // same optimizations as pre_commit.rs
+            None,
         ) {
             Ok(result) => result,
             Err(e) => {
@@ -0,0 +1,592 @@
+use crate::authorship::authorship_log::{LineRange, PromptRecord};
+use crate::authorship::authorship_log_serialization::{
+    AttestationEntry, AuthorshipLog, generate_short_hash,
+};
+use crate::authorship::working_log::AgentId;
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::{notes_add, show_authorship_note};
+use crate::git::repository::Repository;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// Tool values that mark a record as human-written
+const HUMAN_TOOLS: &[&str] = &["", "human", "none"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Jsonl,
+}
+
+/// One record from another tool: who wrote some lines of a file as of a revision
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRecord {
+    /// Row (CSV, after the header) or line (JSONL) the record came from, from 1
+    pub row: usize,
+    pub revision: String,
+    pub path: String,
+    /// Inclusive line ranges; empty means the whole file
+    pub lines: Vec<(u32, u32)>,
+    /// AI tool that wrote the lines; None for a human
+    pub tool: Option<String>,
+    pub model: Option<String>,
+    pub session: Option<String>,
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedCommit {
+    pub commit: String,
+    pub records: usize,
+    pub ai_lines: u32,
+    /// Skipped because the commit already has authorship
+    pub skipped: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub records: usize,
+    pub commits: Vec<ImportedCommit>,
+    pub dry_run: bool,
+}
+
+pub fn handle_import(args: &[String]) {
+    let mut format: Option<ImportFormat> = None;
+    let mut input: Option<String> = None;
+    let mut overwrite = false;
+    let mut dry_run = false;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--format" => {
+                format = match args.get(i + 1).map(String::as_str) {
+                    Some("csv") => Some(ImportFormat::Csv),
+                    Some("jsonl") => Some(ImportFormat::Jsonl),
+                    _ => {
+                        eprintln!("Error: --format requires csv or jsonl");
+                        std::process::exit(1);
+                    }
+                };
+                i += 2;
+            }
+            "--overwrite" => {
+                overwrite = true;
+                i += 1;
+            }
+            "--dry-run" => {
+                dry_run = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            "--help" | "-h" => {
+                print_import_help();
+                std::process::exit(0);
+            }
+            other if input.is_none() && (other == "-" || !other.starts_with('-')) => {
+                input = Some(other.to_string());
+                i += 1;
+            }
+            other => {
+                eprintln!("Error: unknown import argument: {}", other);
+                print_import_help();
+                std::process::exit(1);
+            }
+        }
+    }
+    let Some(input) = input else {
+        print_import_help();
+        std::process::exit(1);
+    };
+    // Default to the file's extension
+    let Some(format) = format.or_else(|| {
+        if input.ends_with(".csv") {
+            Some(ImportFormat::Csv)
+        } else if input.ends_with(".jsonl") || input.ends_with(".ndjson") {
+            Some(ImportFormat::Jsonl)
+        } else {
+            None
+        }
+    }) else {
+        eprintln!(
+            "Error: --format csv|jsonl is required when reading from {}",
+            input
+        );
+        std::process::exit(1);
+    };
+
+    let contents = if input == "-" {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map(|_| contents)
+    } else {
+        std::fs::read_to_string(&input)
+    };
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", input, e);
+            std::process::exit(1);
+        }
+    };
+
+    let records = match parse_records(&contents, format) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", input, e);
+            std::process::exit(1);
+        }
+    };
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let report = match import_records(&repo, &records, overwrite, dry_run) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize import report: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    for commit in &report.commits {
+        if commit.skipped {
+            println!(
+                "  {} skipped: already has authorship (use --overwrite to replace it)",
+                &commit.commit[..commit.commit.len().min(8)]
+            );
+        } else {
+            println!(
+                "  {} {} record(s), {} AI line(s)",
+                &commit.commit[..commit.commit.len().min(8)],
+                commit.records,
+                commit.ai_lines
+            );
+        }
+    }
+    let written = report.commits.iter().filter(|c| !c.skipped).count();
+    println!(
+        "{} {} record(s) onto {} commit(s){}",
+        if dry_run { "Would import" } else { "Imported" },
+        report.records,
+        written,
+        if dry_run { " (dry run)" } else { "" }
+    );
+}
+
+fn print_import_help() {
+    eprintln!(
+        "Usage: git-ai import [--format csv|jsonl] <file|-> [--overwrite] [--dry-run] [--json]"
+    );
+    eprintln!();
+    eprintln!("Writes authorship notes from another tool's records of who wrote which lines.");
+    eprintln!("Each record names a revision, a path, and the lines (as of that revision):");
+    eprintln!("  revision (or commit)  Commit the lines were recorded at");
+    eprintln!("  path (or file)        File, relative to the repository root");
+    eprintln!("  lines                 e.g. \"3-7,12\"; or start_line and end_line; empty for");
+    eprintln!("                        the whole file");
+    eprintln!("  tool                  AI tool that wrote them; empty or \"human\" for a person");
+    eprintln!("  model, session, author  Optional details for AI records");
+    eprintln!("CSV needs a header row naming the columns; JSONL has one object per line.");
+    eprintln!();
+    eprintln!("  --format <csv|jsonl>  Input format (default: from the file extension)");
+    eprintln!("  --overwrite           Replace notes on commits that already have authorship");
+    eprintln!("  --dry-run             Validate and report without writing notes");
+    eprintln!("  --json                Output in JSON format");
+}
+
+/// Parse records, failing on the first malformed one
+pub fn parse_records(
+    contents: &str,
+    format: ImportFormat,
+) -> Result<Vec<ImportRecord>, GitAiError> {
+    let mut records = Vec::new();
+    match format {
+        ImportFormat::Csv => {
+            let mut rows = parse_csv(contents).into_iter();
+            let header: Vec<String> = rows
+                .next()
+                .ok_or_else(|| GitAiError::Generic("empty CSV, expected a header".to_string()))?
+                .iter()
+                .map(|name| name.trim().to_lowercase())
+                .collect();
+            for (index, row) in rows.enumerate() {
+                if row.iter().all(|field| field.trim().is_empty()) {
+                    continue;
+                }
+                let fields: BTreeMap<&str, &str> = header
+                    .iter()
+                    .map(String::as_str)
+                    .zip(row.iter().map(String::as_str))
+                    .collect();
+                records.push(record_from_fields(index + 1, |name| {
+                    fields.get(name).map(|v| v.to_string())
+                })?);
+            }
+        }
+        ImportFormat::Jsonl => {
+            for (index, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(line)
+                    .map_err(|e| GitAiError::Generic(format!("line {}: {}", index + 1, e)))?;
+                records.push(record_from_fields(index + 1, |name| {
+                    object.get(name).and_then(|value| match value {
+                        serde_json::Value::String(s) => Some(s.clone()),
+                        serde_json::Value::Number(n) => Some(n.to_string()),
+                        _ => None,
+                    })
+                })?);
+            }
+        }
+    }
+    Ok(records)
+}
+
+fn record_from_fields(
+    row: usize,
+    field: impl Fn(&str) -> Option<String>,
+) -> Result<ImportRecord, GitAiError> {
+    let get = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| field(name))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let invalid = |message: String| GitAiError::Generic(format!("record {}: {}", row, message));
+
+    let revision = get(&["revision", "commit", "sha"])
+        .ok_or_else(|| invalid("missing revision".to_string()))?;
+    let path = get(&["path", "file"]).ok_or_else(|| invalid("missing path".to_string()))?;
+    let lines = match (get(&["lines", "line"]), get(&["start_line"])) {
+        (Some(spec), _) => parse_line_ranges(&spec).map_err(invalid)?,
+        (None, Some(start)) => {
+            let end = get(&["end_line"]).unwrap_or_else(|| start.clone());
+            parse_line_ranges(&format!("{}-{}", start, end)).map_err(invalid)?
+        }
+        (None, None) => Vec::new(),
+    };
+    let tool =
+        get(&["tool", "agent"]).filter(|tool| !HUMAN_TOOLS.contains(&tool.to_lowercase().as_str()));
+
+    Ok(ImportRecord {
+        row,
+        revision,
+        path,
+        lines,
+        tool,
+        model: get(&["model"]),
+        session: get(&["session", "session_id"]),
+        author: get(&["author", "human_author"]),
+    })
+}
+
+/// `"3-7,12"` -> [(3, 7), (12, 12)]
+fn parse_line_ranges(spec: &str) -> Result<Vec<(u32, u32)>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            match (start.trim().parse::<u32>(), end.trim().parse::<u32>()) {
+                (Ok(start), Ok(end)) if start >= 1 && start <= end => Ok((start, end)),
+                _ => Err(format!("invalid line range '{}'", part)),
+            }
+        })
+        .collect()
+}
+
+/// RFC 4180 CSV: comma-separated fields, optionally double-quoted with `""` escaping a
+/// quote; quoted fields may span lines
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Write a note for each commit the records name, attributing their AI lines to a prompt
+/// per tool, model and session. Every record is validated before anything is written.
+pub fn import_records(
+    repo: &Repository,
+    records: &[ImportRecord],
+    overwrite: bool,
+    dry_run: bool,
+) -> Result<ImportReport, GitAiError> {
+    // Commit -> its records, in input order
+    let mut by_commit: BTreeMap<String, Vec<&ImportRecord>> = BTreeMap::new();
+    let mut resolved: BTreeMap<&str, String> = BTreeMap::new();
+    for record in records {
+        let commit = match resolved.get(record.revision.as_str()) {
+            Some(commit) => commit.clone(),
+            None => {
+                let commit = repo
+                    .revparse_single(&format!("{}^{{commit}}", record.revision))
+                    .map_err(|_| {
+                        GitAiError::Generic(format!(
+                            "record {}: unknown revision '{}'",
+                            record.row, record.revision
+                        ))
+                    })?
+                    .id();
+                resolved.insert(&record.revision, commit.clone());
+                commit
+            }
+        };
+        by_commit.entry(commit).or_default().push(record);
+    }
+
+    let mut logs = Vec::new();
+    for (commit, records) in &by_commit {
+        let skipped = !overwrite && show_authorship_note(repo, commit).is_some();
+        let log = build_authorship_log(repo, commit, records)?;
+        logs.push((commit, records.len(), log, skipped));
+    }
+
+    let mut commits = Vec::new();
+    for (commit, record_count, log, skipped) in logs {
+        let ai_lines = log
+            .attestations
+            .iter()
+            .flat_map(|file| &file.entries)
+            .flat_map(|entry| &entry.line_ranges)
+            .map(|range| range.expand().len() as u32)
+            .sum();
+        if !skipped && !dry_run {
+            let note = log.serialize_to_string().map_err(|_| {
+                GitAiError::Generic("Failed to serialize authorship log".to_string())
+            })?;
+            notes_add(repo, commit, &note)?;
+        }
+        commits.push(ImportedCommit {
+            commit: commit.clone(),
+            records: record_count,
+            ai_lines,
+            skipped,
+        });
+    }
+
+    Ok(ImportReport {
+        records: records.len(),
+        commits,
+        dry_run,
+    })
+}
+
+fn build_authorship_log(
+    repo: &Repository,
+    commit: &str,
+    records: &[&ImportRecord],
+) -> Result<AuthorshipLog, GitAiError> {
+    let mut log = AuthorshipLog::new();
+    log.metadata.base_commit_sha = commit.to_string();
+
+    // Path -> line -> prompt hash; later records override earlier ones, so a human
+    // record can carve lines out of an AI one
+    let mut files: BTreeMap<&str, BTreeMap<u32, Option<String>>> = BTreeMap::new();
+    let mut line_counts: BTreeMap<&str, u32> = BTreeMap::new();
+    for record in records {
+        let line_count = match line_counts.get(record.path.as_str()) {
+            Some(count) => *count,
+            None => {
+                let content = repo.get_file_content(&record.path, commit).map_err(|_| {
+                    GitAiError::Generic(format!(
+                        "record {}: {} does not exist at {}",
+                        record.row, record.path, record.revision
+                    ))
+                })?;
+                let count = String::from_utf8_lossy(&content).lines().count() as u32;
+                line_counts.insert(&record.path, count);
+                count
+            }
+        };
+        let ranges = if record.lines.is_empty() {
+            vec![(1, line_count)]
+        } else {
+            record.lines.clone()
+        };
+        if let Some((_, end)) = ranges.iter().find(|(_, end)| *end > line_count) {
+            return Err(GitAiError::Generic(format!(
+                "record {}: line {} is past the end of {} ({} lines at {})",
+                record.row, end, record.path, line_count, record.revision
+            )));
+        }
+
+        let hash = match &record.tool {
+            Some(tool) => {
+                let model = record.model.as_deref().unwrap_or("unknown");
+                let session = record
+                    .session
+                    .clone()
+                    .unwrap_or_else(|| format!("import:{}", model));
+                let hash = generate_short_hash(&session, tool);
+                let prompt =
+                    log.metadata
+                        .prompts
+                        .entry(hash.clone())
+                        .or_insert_with(|| PromptRecord {
+                            agent_id: AgentId {
+                                tool: tool.clone(),
+                                id: session,
+                                model: model.to_string(),
+                            },
+                            human_author: record.author.clone(),
+                            messages: Vec::new(),
+                            total_additions: 0,
+                            total_deletions: 0,
+                            accepted_lines: 0,
+                            overriden_lines: 0,
+                            messages_url: None,
+                        });
+                if prompt.human_author.is_none() {
+                    prompt.human_author = record.author.clone();
+                }
+                Some(hash)
+            }
+            None => None,
+        };
+        let lines = files.entry(&record.path).or_default();
+        for (start, end) in ranges {
+            for line in start..=end {
+                lines.insert(line, hash.clone());
+            }
+        }
+    }
+
+    for (path, lines) in files {
+        let mut by_prompt: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for (line, hash) in lines {
+            if let Some(hash) = hash {
+                by_prompt.entry(hash).or_default().push(line);
+            }
+        }
+        if by_prompt.is_empty() {
+            continue;
+        }
+        let file = log.get_or_create_file(path);
+        for (hash, lines) in by_prompt {
+            file.add_entry(AttestationEntry::new(
+                hash,
+                LineRange::compress_lines(&lines),
+            ));
+        }
+    }
+
+    let counts: BTreeMap<String, u32> = log
+        .attestations
+        .iter()
+        .flat_map(|file| &file.entries)
+        .fold(BTreeMap::new(), |mut counts, entry| {
+            *counts.entry(entry.hash.clone()).or_default() += entry
+                .line_ranges
+                .iter()
+                .map(|range| range.expand().len() as u32)
+                .sum::<u32>();
+            counts
+        });
+    // Drop prompts whose lines later records all took over
+    log.metadata
+        .prompts
+        .retain(|hash, _| counts.contains_key(hash));
+    for (hash, prompt) in log.metadata.prompts.iter_mut() {
+        let lines = counts.get(hash).copied().unwrap_or(0);
+        prompt.total_additions = lines;
+        prompt.accepted_lines = lines;
+    }
+
+    log.sort_attestations();
+    Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_records_with_quotes_and_column_aliases() {
+        let csv = "commit,file,lines,tool,model,author\r\n\
+                   abc123,src/lib.rs,\"1-3,7\",cursor,gpt-4o,\"Doe, Jane\"\r\n\
+                   abc123,\"src/with \"\"quotes\"\".rs\",,human,,\r\n\
+                   \r\n";
+        let records = parse_records(csv, ImportFormat::Csv).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].revision, "abc123");
+        assert_eq!(records[0].lines, vec![(1, 3), (7, 7)]);
+        assert_eq!(records[0].tool.as_deref(), Some("cursor"));
+        assert_eq!(records[0].author.as_deref(), Some("Doe, Jane"));
+        assert_eq!(records[1].path, "src/with \"quotes\".rs");
+        assert!(records[1].lines.is_empty());
+        assert_eq!(records[1].tool, None);
+    }
+
+    #[test]
+    fn test_parse_jsonl_records() {
+        let jsonl = r#"{"revision": "HEAD", "path": "a.py", "start_line": 2, "end_line": 4, "tool": "copilot"}
+
+{"revision": "HEAD~1", "path": "b.py", "line": "5"}"#;
+        let records = parse_records(jsonl, ImportFormat::Jsonl).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].lines, vec![(2, 4)]);
+        assert_eq!(records[0].row, 1);
+        assert_eq!(records[1].lines, vec![(5, 5)]);
+        assert_eq!(records[1].row, 3);
+    }
+
+    #[test]
+    fn test_parse_records_rejects_bad_input() {
+        let missing_path = "revision,lines\nabc,1-2\n";
+        assert!(parse_records(missing_path, ImportFormat::Csv).is_err());
+        let reversed = "revision,path,lines\nabc,a.rs,5-2\n";
+        let error = parse_records(reversed, ImportFormat::Csv).unwrap_err();
+        assert!(error.to_string().contains("record 1"));
+        assert!(parse_records("not json\n", ImportFormat::Jsonl).is_err());
+    }
+}
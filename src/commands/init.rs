@@ -0,0 +1,551 @@
+use crate::ci::github::install_github_ci_workflow_at;
+use crate::ci::gitlab::{GitlabTemplateOptions, install_gitlab_ci_job_at};
+use crate::commands::install_hooks;
+use crate::config::REPO_CONFIG_FILE_NAME;
+use crate::git::find_repository;
+use crate::git::refs::ref_exists;
+use crate::git::repository::{Repository, exec_git, find_repository_in_path};
+use crate::git::sync_authorship::{
+    NotesSyncOp, ensure_notes_refspecs, missing_notes_refspecs, run_notes_sync,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Which CI template `init --org` installs in each repository
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CiKind {
+    /// Pick from the repository's remote URLs
+    Auto,
+    Github,
+    Gitlab,
+    None,
+}
+
+impl CiKind {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(CiKind::Auto),
+            "github" => Some(CiKind::Github),
+            "gitlab" => Some(CiKind::Gitlab),
+            "none" => Some(CiKind::None),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RepoOutcome {
+    repo: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    ok: bool,
+    actions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Handle `git-ai init`: one-shot setup for a repository. Installs agent hooks, writes a
+/// starter `.git-ai.toml` when there is none, optionally syncs authorship notes with a remote,
+/// and prints next steps.
+pub fn handle_init(args: &[String]) {
+    let mut remote: Option<String> = None;
+    let mut skip_hooks = false;
+    let mut dry_run = false;
+    let mut org = false;
+    let mut repos_file: Option<String> = None;
+    let mut ci = CiKind::Auto;
+    let mut clone_dir: Option<PathBuf> = None;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--remote" => {
+                let Some(name) = args.get(i + 1) else {
+                    eprintln!("Error: --remote requires a remote name");
+                    print_usage();
+                    std::process::exit(1);
+                };
+                remote = Some(name.clone());
+                i += 2;
+            }
+            "--skip-hooks" => {
+                skip_hooks = true;
+                i += 1;
+            }
+            "--dry-run" => {
+                dry_run = true;
+                i += 1;
+            }
+            "--org" => {
+                org = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            "--repos" | "--ci" | "--clone-dir" => {
+                let flag = args[i].as_str();
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("Error: {} requires a value", flag);
+                    print_usage();
+                    std::process::exit(1);
+                };
+                match flag {
+                    "--repos" => repos_file = Some(value.clone()),
+                    "--clone-dir" => clone_dir = Some(PathBuf::from(value)),
+                    _ => {
+                        ci = CiKind::parse(value).unwrap_or_else(|| {
+                            eprintln!("Error: --ci must be one of auto, github, gitlab, none");
+                            std::process::exit(1);
+                        })
+                    }
+                }
+                i += 2;
+            }
+            "--help" | "-h" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            arg => {
+                eprintln!("Error: unknown option '{}'", arg);
+                print_usage();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if org {
+        let Some(repos_file) = repos_file else {
+            eprintln!("Error: --org requires --repos <file>");
+            print_usage();
+            std::process::exit(1);
+        };
+        let options = OrgOptions {
+            ci,
+            remote,
+            clone_dir,
+            dry_run,
+        };
+        handle_init_org(&repos_file, &options, json_output);
+        return;
+    }
+    if repos_file.is_some() || clone_dir.is_some() || ci != CiKind::Auto {
+        eprintln!("Error: --repos, --ci and --clone-dir are only valid with --org");
+        print_usage();
+        std::process::exit(1);
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(_) => {
+            eprintln!("Error: git-ai init must be run inside a git repository");
+            std::process::exit(1);
+        }
+    };
+
+    let mut failed = false;
+
+    let installed_agents = if skip_hooks {
+        println!("Agent hooks:  skipped (--skip-hooks)");
+        Vec::new()
+    } else {
+        let install_args = if dry_run {
+            vec!["--dry-run".to_string()]
+        } else {
+            Vec::new()
+        };
+        match install_hooks::run(&install_args) {
+            Ok(statuses) => {
+                let mut agents: Vec<String> = statuses
+                    .into_iter()
+                    .filter(|(_, status)| status == "installed" || status == "already_installed")
+                    .map(|(agent, _)| agent)
+                    .collect();
+                agents.sort();
+                agents
+            }
+            Err(e) => {
+                eprintln!("Agent hooks:  failed: {}", e);
+                failed = true;
+                Vec::new()
+            }
+        }
+    };
+    println!();
+
+    let starter = repo
+        .workdir()
+        .map_err(|e| e.to_string())
+        .and_then(|workdir| write_starter_repo_config(&workdir, dry_run));
+    match starter {
+        Ok(Some(path)) if dry_run => println!("Config:       would write {}", path),
+        Ok(Some(path)) => println!("Config:       wrote starter config to {}", path),
+        Ok(None) => println!("Config:       existing {} kept", REPO_CONFIG_FILE_NAME),
+        Err(e) => {
+            eprintln!("Config:       failed: {}", e);
+            failed = true;
+        }
+    }
+
+    match configure_notes_refspecs(&repo, dry_run) {
+        Ok(message) => println!("Refspecs:     {}", message),
+        Err(e) => {
+            eprintln!("Refspecs:     failed: {}", e);
+            failed = true;
+        }
+    }
+
+    if let Some(remote) = &remote {
+        match sync_notes_with_remote(&repo, remote, dry_run) {
+            Ok(message) => println!("Notes:        {}", message),
+            Err(e) => {
+                eprintln!("Notes:        failed to sync with {}: {}", remote, e);
+                failed = true;
+            }
+        }
+    }
+
+    print_next_steps(&installed_agents);
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Settings written by `init`, all commented out so they document the format without
+/// changing behavior until a team opts in
+const STARTER_REPO_CONFIG: &str = r#"# git-ai settings for this repository. Commit this file so every clone, CI job and
+# editor shares them; they override each user's ~/.git-ai/config.json and are reloaded
+# without restarting editor-host or the daemon.
+
+# Files left out of stats and compare totals
+# ignore = ["*.lock", "vendor/**"]
+
+# [agents]
+# Only record checkpoints from these agents (every agent when unset)
+# allow = ["claude", "cursor", "codex"]
+
+# [policy]
+# work_item_pattern = "[A-Z]+-[0-9]+"
+# test_path_patterns = ["tests/**", "**/*_test.go"]
+# fully_ai_threshold = 90
+# ai_assisted_threshold = 10
+# attribution_neutral_commits = []
+# neutral_whitespace_commits = false
+# preserve_whitespace_attribution = false
+# attribution_granularity = { md = "line" }
+"#;
+
+/// Write a commented starter `.git-ai.toml` at the repository root unless one exists.
+/// Returns the path when a file was (or would be) written.
+fn write_starter_repo_config(workdir: &Path, dry_run: bool) -> Result<Option<String>, String> {
+    let path = workdir.join(REPO_CONFIG_FILE_NAME);
+    if path.exists() {
+        return Ok(None);
+    }
+    if !dry_run {
+        std::fs::write(&path, STARTER_REPO_CONFIG)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(Some(path.display().to_string()))
+}
+
+/// Add a notes fetch refspec to every remote so a plain `git fetch` brings authorship
+/// notes along too, e.g. from IDEs that bypass the git-ai wrapper. Notes are still pushed
+/// by the wrapper, since any `remote.<name>.push` entry would change what `git push` sends.
+fn configure_notes_refspecs(repo: &Repository, dry_run: bool) -> Result<String, String> {
+    let remotes: Vec<String> = repo
+        .remotes()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .collect();
+    if remotes.is_empty() {
+        return Ok("no remotes to configure".to_string());
+    }
+
+    let mut changed = Vec::new();
+    for remote in &remotes {
+        let missing = if dry_run {
+            missing_notes_refspecs(repo, remote)
+        } else {
+            ensure_notes_refspecs(repo, remote).map_err(|e| e.to_string())?
+        };
+        if !missing.is_empty() {
+            changed.push(remote.as_str());
+        }
+    }
+    Ok(match (changed.is_empty(), dry_run) {
+        (true, _) => "notes refspecs already configured".to_string(),
+        (false, true) => format!("would add notes refspecs to {}", changed.join(", ")),
+        (false, false) => format!("added notes refspecs to {}", changed.join(", ")),
+    })
+}
+
+/// Pull any notes the remote already has, then publish ours so `refs/notes/ai` exists
+/// on the remote for teammates and CI.
+fn sync_notes_with_remote(
+    repo: &Repository,
+    remote: &str,
+    dry_run: bool,
+) -> Result<String, String> {
+    if !repo
+        .remotes()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .any(|name| name == remote)
+    {
+        return Err(format!("no remote named '{}'", remote));
+    }
+    if dry_run {
+        return Ok(format!(
+            "would fetch and push refs/notes/ai with {}",
+            remote
+        ));
+    }
+
+    run_notes_sync(repo, NotesSyncOp::Fetch, remote).map_err(|e| e.to_string())?;
+    if !ref_exists(repo, "refs/notes/ai") {
+        return Ok(format!(
+            "no authorship notes yet; they'll be pushed to {} with your next git push",
+            remote
+        ));
+    }
+    run_notes_sync(repo, NotesSyncOp::Push, remote).map_err(|e| e.to_string())?;
+    Ok(format!("refs/notes/ai is in sync with {}", remote))
+}
+
+struct OrgOptions {
+    ci: CiKind,
+    remote: Option<String>,
+    clone_dir: Option<PathBuf>,
+    dry_run: bool,
+}
+
+/// `init --org`: apply the same setup to every repository listed in `repos_file` (one
+/// local path or clone URL per line, `#` comments allowed) and report each outcome.
+/// Agent hooks are per machine, so org mode leaves them to `git-ai install-hooks`.
+fn handle_init_org(repos_file: &str, options: &OrgOptions, json_output: bool) {
+    let entries: Vec<String> = match std::fs::read_to_string(repos_file) {
+        Ok(content) => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        Err(e) => {
+            eprintln!("Error: failed to read {}: {}", repos_file, e);
+            std::process::exit(1);
+        }
+    };
+
+    let outcomes: Vec<RepoOutcome> = entries
+        .iter()
+        .map(|entry| {
+            let mut outcome = RepoOutcome {
+                repo: entry.clone(),
+                path: None,
+                ok: true,
+                actions: Vec::new(),
+                error: None,
+            };
+            if let Err(e) = init_org_repo(entry, options, &mut outcome) {
+                outcome.ok = false;
+                outcome.error = Some(e);
+            }
+            if !json_output {
+                match &outcome.error {
+                    None => println!("ok      {}: {}", entry, outcome.actions.join("; ")),
+                    Some(e) => println!("failed  {}: {}", entry, e),
+                }
+            }
+            outcome
+        })
+        .collect();
+
+    let failed = outcomes.iter().filter(|o| !o.ok).count();
+    if json_output {
+        let report = serde_json::json!({
+            "repos": outcomes,
+            "succeeded": outcomes.len() - failed,
+            "failed": failed,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!();
+        println!(
+            "{} of {} repositories set up, {} failed",
+            outcomes.len() - failed,
+            outcomes.len(),
+            failed
+        );
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn init_org_repo(
+    entry: &str,
+    options: &OrgOptions,
+    outcome: &mut RepoOutcome,
+) -> Result<(), String> {
+    let path = resolve_org_repo(entry, options, outcome)?;
+    outcome.path = Some(path.clone());
+    let repo = find_repository_in_path(&path.to_string_lossy())
+        .map_err(|_| format!("{} is not a git repository", path.display()))?;
+    let workdir = repo.workdir().map_err(|e| e.to_string())?;
+
+    let ci = match options.ci {
+        CiKind::Auto => detect_ci(&repo),
+        ci => ci,
+    };
+    match ci {
+        CiKind::Github if options.dry_run => outcome
+            .actions
+            .push("would install GitHub workflow".to_string()),
+        CiKind::Github => {
+            install_github_ci_workflow_at(&workdir).map_err(|e| e.to_string())?;
+            outcome
+                .actions
+                .push("installed GitHub workflow".to_string());
+        }
+        CiKind::Gitlab if options.dry_run => outcome
+            .actions
+            .push("would install GitLab CI job".to_string()),
+        CiKind::Gitlab => {
+            let (_, warning) =
+                install_gitlab_ci_job_at(&workdir, &GitlabTemplateOptions::default())
+                    .map_err(|e| e.to_string())?;
+            outcome.actions.push("installed GitLab CI job".to_string());
+            if let Some(warning) = warning {
+                outcome.actions.push(warning);
+            }
+        }
+        CiKind::Auto | CiKind::None => outcome.actions.push("no CI template".to_string()),
+    }
+
+    let starter = match write_starter_repo_config(&workdir, options.dry_run)? {
+        Some(_) if options.dry_run => format!("would write {}", REPO_CONFIG_FILE_NAME),
+        Some(_) => format!("wrote {}", REPO_CONFIG_FILE_NAME),
+        None => format!("existing {} kept", REPO_CONFIG_FILE_NAME),
+    };
+    outcome.actions.push(starter);
+
+    outcome
+        .actions
+        .push(configure_notes_refspecs(&repo, options.dry_run)?);
+    if let Some(remote) = &options.remote {
+        outcome
+            .actions
+            .push(sync_notes_with_remote(&repo, remote, options.dry_run)?);
+    }
+    Ok(())
+}
+
+/// Local paths are used as-is; clone URLs are cloned into `--clone-dir` (or reused if a
+/// previous run already cloned them)
+fn resolve_org_repo(
+    entry: &str,
+    options: &OrgOptions,
+    outcome: &mut RepoOutcome,
+) -> Result<PathBuf, String> {
+    let is_url = entry.contains("://") || (entry.contains('@') && entry.contains(':'));
+    if !is_url {
+        return Ok(PathBuf::from(entry));
+    }
+
+    let clone_dir = options
+        .clone_dir
+        .as_deref()
+        .ok_or_else(|| "clone URLs require --clone-dir".to_string())?;
+    let name = entry
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(entry)
+        .trim_end_matches(".git");
+    let target = clone_dir.join(name);
+    if target.exists() || options.dry_run {
+        return Ok(target);
+    }
+
+    std::fs::create_dir_all(clone_dir).map_err(|e| e.to_string())?;
+    exec_git(&[
+        "clone".to_string(),
+        entry.to_string(),
+        target.to_string_lossy().to_string(),
+    ])
+    .map_err(|e| format!("clone failed: {}", e))?;
+    outcome
+        .actions
+        .push(format!("cloned into {}", target.display()));
+    Ok(target)
+}
+
+fn detect_ci(repo: &Repository) -> CiKind {
+    let urls: Vec<String> = repo
+        .remotes_with_urls()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, url)| url.to_lowercase())
+        .collect();
+    if urls.iter().any(|url| url.contains("github")) {
+        CiKind::Github
+    } else if urls.iter().any(|url| url.contains("gitlab")) {
+        CiKind::Gitlab
+    } else {
+        CiKind::None
+    }
+}
+
+fn print_next_steps(installed_agents: &[String]) {
+    println!();
+    println!("Next steps:");
+    if installed_agents.is_empty() {
+        println!("  1. Install a supported coding agent, then run `git-ai install-hooks`");
+    } else {
+        println!(
+            "  1. Restart your coding agents so they load the git-ai hooks ({})",
+            installed_agents.join(", ")
+        );
+    }
+    println!("  2. Make a change with your agent and commit it as usual");
+    println!("  3. Run `git-ai stats` or `git-ai blame <file>` to see AI attribution");
+    println!("  4. `git push` shares authorship notes (refs/notes/ai) with your remote");
+}
+
+fn print_usage() {
+    eprintln!("Usage: git-ai init [--remote <name>] [--skip-hooks] [--dry-run]");
+    eprintln!(
+        "       git-ai init --org --repos <file> [--ci auto|github|gitlab|none] [--clone-dir <dir>] [--remote <name>] [--dry-run] [--json]"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RepoFileConfig;
+
+    #[test]
+    fn test_starter_repo_config_parses_once_uncommented() {
+        let uncommented: String = STARTER_REPO_CONFIG
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.starts_with('[') || line.contains(" = "))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let config: RepoFileConfig = toml::from_str(&uncommented).unwrap();
+        assert!(config.ignore.is_some());
+        assert!(config.agents.unwrap().allow.is_some());
+        let policy = config.policy.unwrap();
+        assert_eq!(policy.fully_ai_threshold, Some(90.0));
+        assert!(policy.attribution_granularity.is_some());
+
+        let defaults: RepoFileConfig = toml::from_str(STARTER_REPO_CONFIG).unwrap();
+        assert!(defaults.ignore.is_none() && defaults.policy.is_none());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::commands::blame::GitAiBlameOptions;
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::get_reference_as_authorship_log_v3;
+use crate::git::repository::Repository;
+use crate::utils::normalize_to_posix;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+// `path/to/file.rs:42` or `path/to/file.rs:42:7` (Rust, Go, JS, compiler output). The
+// path may itself contain colons (`C:\src\app.js`, `file:///...`); it ends at the first
+// `:<digits>`, so the line is the number after the last colon of the path.
+static COLON_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([^\s()\x22'<>]+?):(\d+)(?::\d+)?").unwrap());
+// `File "path/to/file.py", line 42` (Python tracebacks)
+static PYTHON_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"File "([^"]+)", line (\d+)"#).unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineAttribution {
+    Ai,
+    Human,
+    /// The introducing commit has no authorship note
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntroducedBy {
+    pub file: String,
+    pub line: u32,
+    pub commit: String,
+    pub summary: String,
+    pub author: String,
+    pub author_time: i64,
+    pub attribution: LineAttribution,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub human_author: Option<String>,
+}
+
+pub fn handle_introduced_by(args: &[String]) {
+    let mut location: Option<String> = None;
+    let mut rev: Option<String> = None;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--rev" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --rev requires a revision");
+                    std::process::exit(1);
+                }
+                rev = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            _ => {
+                if location.is_some() {
+                    eprintln!("Error: introduced-by accepts exactly one location");
+                    std::process::exit(1);
+                }
+                location = Some(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    let Some(location) = location else {
+        eprintln!("Usage: git-ai introduced-by <file>:<line> [--rev <rev>] [--json]");
+        eprintln!("  <location> may also be a stack trace frame, e.g. 'at run (src/app.js:42:7)'");
+        std::process::exit(1);
+    };
+
+    let Some((file, line)) = parse_location(&location) else {
+        eprintln!(
+            "Error: could not find a <file>:<line> location in '{}'",
+            location
+        );
+        std::process::exit(1);
+    };
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match introduced_by(&repo, &file, line, rev.as_deref()) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Failed to look up {}:{}: {}", file, line, e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize result: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_introduced_by(&result);
+    }
+}
+
+/// Extract a file and line from `file:line`, `file:line:col`, or a stack trace frame.
+/// When several locations are present, the last one wins since it is usually the
+/// innermost path in a frame like `at fn (/abs/path/file.js:10:5)`.
+pub fn parse_location(input: &str) -> Option<(String, u32)> {
+    let trimmed = input.trim();
+
+    if let Some(caps) = PYTHON_LOCATION.captures_iter(trimmed).last() {
+        let line = caps[2].parse().ok()?;
+        return Some((caps[1].to_string(), line));
+    }
+
+    let caps = COLON_LOCATION.captures_iter(trimmed).last()?;
+    let line: u32 = caps[2].parse().ok()?;
+    if line == 0 {
+        return None;
+    }
+    let mut file = caps[1].strip_prefix("file://").unwrap_or(&caps[1]);
+    // `file:///C:/src/app.js` names `C:/src/app.js`
+    if let Some(rest) = file.strip_prefix('/')
+        && rest.as_bytes().get(1) == Some(&b':')
+        && rest.as_bytes()[0].is_ascii_alphabetic()
+    {
+        file = rest;
+    }
+    Some((file.to_string(), line))
+}
+
+/// Normalize a user- or stack-trace-supplied path to be relative to the repository root.
+pub(crate) fn repo_relative_path(repo: &Repository, file: &str) -> Result<String, GitAiError> {
+    let workdir = repo.canonical_workdir();
+    let path = Path::new(file);
+
+    let candidate = if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        std::env::current_dir()
+            .ok()
+            .map(|cwd| cwd.join(path))
+            .filter(|p| p.exists())
+    };
+
+    if let Some(candidate) = candidate
+        && let Ok(canonical) = candidate.canonicalize()
+        && let Ok(relative) = canonical.strip_prefix(workdir)
+    {
+        return Ok(normalize_to_posix(&relative.to_string_lossy()));
+    }
+
+    if path.is_absolute() {
+        return Err(GitAiError::Generic(format!(
+            "File '{}' is not within repository root '{}'",
+            file,
+            workdir.display()
+        )));
+    }
+
+    let normalized = normalize_to_posix(file);
+    Ok(normalized
+        .strip_prefix("./")
+        .unwrap_or(&normalized)
+        .to_string())
+}
+
+/// Find the commit that introduced `line` of `file` (as of `rev`, default HEAD) and the
+/// attribution recorded for it in that commit's authorship note.
+pub fn introduced_by(
+    repo: &Repository,
+    file: &str,
+    line: u32,
+    rev: Option<&str>,
+) -> Result<IntroducedBy, GitAiError> {
+    let file_path = repo_relative_path(repo, file)?;
+
+    let options = GitAiBlameOptions {
+        newest_commit: Some(rev.unwrap_or("HEAD").to_string()),
+        ..Default::default()
+    };
+    let hunks = repo.blame_hunks(&file_path, line, line, &options)?;
+    let hunk = hunks.into_iter().next().ok_or_else(|| {
+        GitAiError::Generic(format!("No blame information for {}:{}", file_path, line))
+    })?;
+
+    let summary = repo
+        .find_commit(hunk.commit_sha.clone())
+        .and_then(|c| c.summary())
+        .unwrap_or_default();
+
+    let mut result = IntroducedBy {
+        file: file_path.clone(),
+        line,
+        commit: hunk.commit_sha.clone(),
+        summary,
+        author: format!("{} <{}>", hunk.original_author, hunk.author_email),
+        author_time: hunk.author_time,
+        attribution: LineAttribution::Unknown,
+        agent: None,
+        model: None,
+        session: None,
+        human_author: None,
+    };
+
+    let Ok(authorship_log) = get_reference_as_authorship_log_v3(repo, &hunk.commit_sha) else {
+        return Ok(result);
+    };
+
+    // The note records line numbers as they were in the introducing commit
+    let orig_line = hunk.orig_range.0;
+    let mut foreign_prompts_cache = HashMap::new();
+    match authorship_log.get_line_attribution(
+        repo,
+        &file_path,
+        orig_line,
+        &mut foreign_prompts_cache,
+    ) {
+        Some((_, _, Some(prompt))) => {
+            result.attribution = LineAttribution::Ai;
+            result.agent = Some(prompt.agent_id.tool);
+            result.model = Some(prompt.agent_id.model);
+            result.session = Some(prompt.agent_id.id);
+            result.human_author = prompt.human_author;
+        }
+        Some((author, _, None)) => {
+            result.attribution = LineAttribution::Human;
+            result.human_author = Some(author.username);
+        }
+        None => {
+            result.attribution = LineAttribution::Human;
+        }
+    }
+
+    Ok(result)
+}
+
+fn print_introduced_by(result: &IntroducedBy) {
+    let short_sha = &result.commit[..result.commit.len().min(7)];
+    let date = chrono::DateTime::from_timestamp(result.author_time, 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+
+    println!("{}:{}", result.file, result.line);
+    println!("  commit       {} {}", short_sha, result.summary);
+    println!("  author       {} {}", result.author, date);
+    match result.attribution {
+        LineAttribution::Ai => {
+            println!("  attribution  ai");
+            if let Some(agent) = &result.agent {
+                match &result.model {
+                    Some(model) if !model.is_empty() => {
+                        println!("  agent        {} ({})", agent, model)
+                    }
+                    _ => println!("  agent        {}", agent),
+                }
+            }
+            if let Some(session) = &result.session {
+                println!("  session      {}", session);
+            }
+            if let Some(human) = &result.human_author {
+                println!("  prompted by  {}", human);
+            }
+        }
+        LineAttribution::Human => println!("  attribution  human"),
+        LineAttribution::Unknown => {
+            println!("  attribution  unknown (no authorship note for this commit)")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            parse_location("src/main.rs:42"),
+            Some(("src/main.rs".to_string(), 42))
+        );
+        assert_eq!(
+            parse_location("src/main.rs:42:7"),
+            Some(("src/main.rs".to_string(), 42))
+        );
+        assert_eq!(
+            parse_location("    at run (/home/me/app/src/app.js:10:5)"),
+            Some(("/home/me/app/src/app.js".to_string(), 10))
+        );
+        assert_eq!(
+            parse_location("thread 'main' panicked at src/lib.rs:8:9:"),
+            Some(("src/lib.rs".to_string(), 8))
+        );
+        assert_eq!(
+            parse_location(r#"  File "pkg/mod.py", line 12, in handler"#),
+            Some(("pkg/mod.py".to_string(), 12))
+        );
+        assert_eq!(
+            parse_location(r"   at Run (C:\src\app\index.js:10:5)"),
+            Some((r"C:\src\app\index.js".to_string(), 10))
+        );
+        assert_eq!(
+            parse_location("at file:///home/me/app/src/app.mjs:3:1"),
+            Some(("/home/me/app/src/app.mjs".to_string(), 3))
+        );
+        assert_eq!(
+            parse_location("at file:///C:/src/app.mjs:3:1"),
+            Some(("C:/src/app.mjs".to_string(), 3))
+        );
+        assert_eq!(parse_location("src/main.rs"), None);
+        assert_eq!(parse_location("src/main.rs:0"), None);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::authorship::lineage::{HunkLineage, LineageEventKind, hunk_lineage};
+use crate::commands::introduced_by::repo_relative_path;
+use crate::git::find_repository;
+
+pub fn handle_lineage(args: &[String]) {
+    let mut location: Option<String> = None;
+    let mut rev: Option<String> = None;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--rev" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --rev requires a revision");
+                    std::process::exit(1);
+                }
+                rev = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            _ => {
+                if location.is_some() {
+                    eprintln!("Error: lineage accepts exactly one hunk");
+                    std::process::exit(1);
+                }
+                location = Some(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    let Some(location) = location else {
+        eprintln!("Usage: git-ai lineage <file>:<start>[-<end>] [--rev <rev>] [--json]");
+        std::process::exit(1);
+    };
+
+    let Some((file, start_line, end_line)) = parse_hunk(&location) else {
+        eprintln!(
+            "Error: expected <file>:<start> or <file>:<start>-<end>, got '{}'",
+            location
+        );
+        std::process::exit(1);
+    };
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = repo_relative_path(&repo, &file).and_then(|file| {
+        hunk_lineage(
+            &repo,
+            &file,
+            start_line,
+            end_line,
+            rev.as_deref().unwrap_or("HEAD"),
+        )
+    });
+    let lineage = match result {
+        Ok(lineage) => lineage,
+        Err(e) => {
+            eprintln!("Failed to trace {}: {}", location, e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&lineage) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize result: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_lineage(&lineage);
+    }
+}
+
+/// Parse `file:start`, `file:start-end` or `file:start,end`
+fn parse_hunk(input: &str) -> Option<(String, u32, u32)> {
+    let (file, range) = input.trim().rsplit_once(':')?;
+    let (start, end) = match range.split_once(['-', ',']) {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let line = range.parse().ok()?;
+            (line, line)
+        }
+    };
+    if file.is_empty() || start == 0 || end < start {
+        return None;
+    }
+    Some((file.to_string(), start, end))
+}
+
+fn print_lineage(lineage: &HunkLineage) {
+    println!(
+        "{}:{}-{} as of {}",
+        lineage.file, lineage.start_line, lineage.end_line, lineage.rev
+    );
+    if lineage.events.is_empty() {
+        println!("  no history for these lines");
+        return;
+    }
+    for event in &lineage.events {
+        let short_sha = &event.commit[..event.commit.len().min(7)];
+        let date = chrono::DateTime::from_timestamp(event.author_time, 0)
+            .filter(|_| event.author_time > 0)
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let kind = match event.kind {
+            LineageEventKind::AiCheckpoint => "ai checkpoint",
+            LineageEventKind::AiEdit => "ai edit",
+            LineageEventKind::HumanEdit => "human edit",
+            LineageEventKind::Squash => "squash",
+            LineageEventKind::Rebase => "rebase",
+            LineageEventKind::Unattributed => "unattributed",
+        };
+        println!();
+        println!("  {:<13} {} {} {}", kind, short_sha, date, event.summary);
+        if !event.author.is_empty() {
+            println!("    author       {}", event.author);
+        } else if event.kind == LineageEventKind::AiCheckpoint {
+            println!("    commit is gone, only its authorship note remains");
+        }
+        println!(
+            "    lines        {} in {} ({} ai, {} human)",
+            event.lines.len(),
+            event.file,
+            event.ai_lines,
+            event.human_lines
+        );
+        for agent in &event.agents {
+            let model = if agent.model.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", agent.model)
+            };
+            println!(
+                "    agent        {}{} session {}",
+                agent.tool, model, agent.session
+            );
+        }
+        if !event.rewritten_from.is_empty() {
+            let sources: Vec<&str> = event
+                .rewritten_from
+                .iter()
+                .map(|sha| &sha[..sha.len().min(7)])
+                .collect();
+            println!("    rewrites     {}", sources.join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunk() {
+        assert_eq!(
+            parse_hunk("src/main.rs:10-14"),
+            Some(("src/main.rs".to_string(), 10, 14))
+        );
+        assert_eq!(
+            parse_hunk("src/main.rs:10,14"),
+            Some(("src/main.rs".to_string(), 10, 14))
+        );
+        assert_eq!(
+            parse_hunk("src/main.rs:7"),
+            Some(("src/main.rs".to_string(), 7, 7))
+        );
+        assert_eq!(parse_hunk("src/main.rs:14-10"), None);
+        assert_eq!(parse_hunk("src/main.rs"), None);
+        assert_eq!(parse_hunk("src/main.rs:0"), None);
+    }
+}
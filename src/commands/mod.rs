@@ -1,27 +1,50 @@
 pub mod blame;
+pub mod check;
 pub mod checkpoint;
 pub mod checkpoint_agent;
 pub mod ci_handlers;
+pub mod compare;
 pub mod config;
+#[cfg(unix)]
+pub mod daemon;
 pub mod diff;
+pub mod doctor;
+pub mod editor_host;
 pub mod exchange_nonce;
+pub mod export;
+pub mod fetch_missing;
 pub mod flush_cas;
 pub mod flush_logs;
 pub mod flush_metrics_db;
 pub mod git_ai_handlers;
 pub mod git_handlers;
+pub mod grep;
+pub mod heatmap;
 pub mod hooks;
+pub mod import;
+pub mod init;
 pub mod install_hooks;
+pub mod introduced_by;
+pub mod lineage;
 pub mod login;
 pub mod logout;
 pub mod personal_dashboard;
 pub mod prompt_picker;
 pub mod prompts_db;
+pub mod query;
+pub mod report;
+pub mod session;
 pub mod share;
 pub mod share_tui;
 pub mod show;
 pub mod show_prompt;
+pub mod simulate_agent;
+pub mod snapshot;
 pub mod squash_authorship;
 pub mod status;
+pub mod sync;
 pub mod sync_prompts;
+pub mod telemetry;
+pub mod top;
 pub mod upgrade;
+pub mod verify;
@@ -4,9 +4,11 @@
 //! Designed for Claude Code skills and other terminal-based analysis tools.
 
 use crate::authorship::internal_db::InternalDatabase;
+use crate::authorship::note_delta::is_delta;
 use crate::authorship::transcript::AiTranscript;
 use crate::error::GitAiError;
 use crate::git::find_repository_in_path;
+use crate::git::refs::show_authorship_note;
 use crate::git::repository::{Repository, exec_git, exec_git_stdin};
 use chrono::{Local, TimeZone};
 use rusqlite::{Connection, params};
@@ -936,12 +938,19 @@ fn get_commits_with_notes_since(repo: &Repository, since_timestamp: i64) -> Vec<
     let blob_shas: Vec<String> = filtered.iter().map(|(blob, _)| blob.clone()).collect();
     let contents = batch_read_blobs(&global_args, &blob_shas);
 
-    // Step 5: Pair commit SHAs with note contents
+    // Step 5: Pair commit SHAs with note contents, filling in what deltas left out
     filtered
         .into_iter()
         .zip(contents)
         .filter(|(_, content)| content.contains('{')) // Only include notes with JSON
-        .map(|((_, commit_sha), content)| (commit_sha, content))
+        .map(|((_, commit_sha), content)| {
+            let content = if is_delta(&content) {
+                show_authorship_note(repo, &commit_sha).unwrap_or(content)
+            } else {
+                content
+            };
+            (commit_sha, content)
+        })
         .collect()
 }
 
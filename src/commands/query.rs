@@ -0,0 +1,183 @@
+use crate::authorship::file_attribution::file_attributions;
+use crate::git::find_repository;
+use crate::git::repository::Repository;
+use crate::model::FileAttribution;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// One file/revision lookup. Without `rev`, the working tree is queried.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Query {
+    pub file: String,
+    #[serde(default)]
+    pub rev: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryResult {
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<FileAttribution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchOutput {
+    results: Vec<QueryResult>,
+}
+
+/// Batch input is either a bare array of queries or `{"queries": [...]}`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BatchInput {
+    List(Vec<Query>),
+    Wrapped { queries: Vec<Query> },
+}
+
+pub fn handle_query(args: &[String]) {
+    let mut batch = false;
+    let mut file: Option<String> = None;
+    let mut rev: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--batch" => {
+                batch = true;
+                i += 1;
+            }
+            "--rev" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --rev requires a revision");
+                    std::process::exit(1);
+                }
+                rev = Some(args[i + 1].clone());
+                i += 2;
+            }
+            arg if arg.starts_with("--") => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                print_query_help();
+                std::process::exit(1);
+            }
+            _ => {
+                if file.is_some() {
+                    eprintln!("Error: query accepts one file; use --batch for more");
+                    std::process::exit(1);
+                }
+                file = Some(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    let queries = if batch {
+        if file.is_some() || rev.is_some() {
+            eprintln!("Error: --batch reads queries from stdin and takes no file or --rev");
+            std::process::exit(1);
+        }
+        let mut input = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+            eprintln!("Failed to read queries from stdin: {}", e);
+            std::process::exit(1);
+        }
+        match serde_json::from_str::<BatchInput>(&input) {
+            Ok(BatchInput::List(queries)) | Ok(BatchInput::Wrapped { queries }) => queries,
+            Err(e) => {
+                eprintln!("Invalid batch input: {}", e);
+                print_query_help();
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let Some(file) = file else {
+            print_query_help();
+            std::process::exit(1);
+        };
+        vec![Query { file, rev }]
+    };
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let results = run_queries(&repo, &queries);
+
+    let json = if batch {
+        serde_json::to_string(&BatchOutput { results })
+    } else {
+        serde_json::to_string(&results[0])
+    };
+    match json {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("Failed to serialize results: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_query_help() {
+    eprintln!("Usage: git-ai query <file> [--rev <rev>]");
+    eprintln!("       git-ai query --batch < queries.json");
+    eprintln!("  queries.json: [{{\"file\": \"src/main.rs\", \"rev\": \"HEAD\"}}, ...]");
+    eprintln!("  Omit \"rev\" to query the working tree, including uncommitted AI edits");
+}
+
+/// Answer queries in input order. Queries against the same revision share one blame
+/// pass, which is the point of batching.
+pub fn run_queries(repo: &Repository, queries: &[Query]) -> Vec<QueryResult> {
+    let mut by_rev: BTreeMap<Option<String>, Vec<usize>> = BTreeMap::new();
+    for (index, query) in queries.iter().enumerate() {
+        by_rev.entry(query.rev.clone()).or_default().push(index);
+    }
+
+    let mut results: Vec<Option<QueryResult>> = queries.iter().map(|_| None).collect();
+    for (rev, indices) in by_rev {
+        let files: Vec<String> = indices
+            .iter()
+            .map(|&i| normalize_file(&queries[i].file))
+            .collect();
+
+        match file_attributions(repo, &files, rev.as_deref()) {
+            Ok(attributions) => {
+                for (&index, attribution) in indices.iter().zip(attributions) {
+                    let (attribution, error) = match attribution {
+                        Ok(attribution) => (Some(attribution), None),
+                        Err(e) => (None, Some(e.to_string())),
+                    };
+                    results[index] = Some(QueryResult {
+                        file: queries[index].file.clone(),
+                        rev: rev.clone(),
+                        attribution,
+                        error,
+                    });
+                }
+            }
+            Err(e) => {
+                for &index in &indices {
+                    results[index] = Some(QueryResult {
+                        file: queries[index].file.clone(),
+                        rev: rev.clone(),
+                        attribution: None,
+                        error: Some(e.to_string()),
+                    });
+                }
+            }
+        }
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+fn normalize_file(file: &str) -> String {
+    let file = file.replace('\\', "/");
+    file.strip_prefix("./").unwrap_or(&file).to_string()
+}
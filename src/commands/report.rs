@@ -0,0 +1,300 @@
+use crate::authorship::code_kind::CodeKindClassifier;
+use crate::authorship::commit_class::ClassThresholds;
+use crate::authorship::identity_map::IdentityMap;
+use crate::authorship::language::detect_language_in;
+use crate::authorship::packages::WorkspacePackages;
+use crate::authorship::report::{
+    Report, ReportBucket, ReportPeriod, build_report, classify_commits, collect_contributions,
+    group_files_by,
+};
+use crate::commands::sync_prompts::parse_since_arg;
+use crate::git::find_repository;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Window used when neither a range nor `--since` is given
+const DEFAULT_SINCE: &str = "30d";
+
+pub fn handle_report(args: &[String]) {
+    let mut range: Option<String> = None;
+    let mut since: Option<String> = None;
+    let mut identity_map_path: Option<PathBuf> = None;
+    let mut by_package = false;
+    let mut by_language = false;
+    let mut split_tests = false;
+    let mut classify = false;
+    let mut period = ReportPeriod::Week;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--since" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --since requires a value");
+                    std::process::exit(1);
+                }
+                since = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--identity-map" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --identity-map requires a path");
+                    std::process::exit(1);
+                }
+                identity_map_path = Some(PathBuf::from(&args[i + 1]));
+                i += 2;
+            }
+            "--by-package" => {
+                by_package = true;
+                i += 1;
+            }
+            "--by-language" => {
+                by_language = true;
+                i += 1;
+            }
+            "--split-tests" => {
+                split_tests = true;
+                i += 1;
+            }
+            "--classify" => {
+                classify = true;
+                i += 1;
+            }
+            "--period" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --period requires a value (day, week or month)");
+                    std::process::exit(1);
+                }
+                period = match ReportPeriod::parse(&args[i + 1]) {
+                    Some(period) => period,
+                    None => {
+                        eprintln!(
+                            "Error: Invalid --period '{}': expected day, week or month",
+                            args[i + 1]
+                        );
+                        std::process::exit(1);
+                    }
+                };
+                classify = true;
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            arg if arg.starts_with("--") => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                print_report_help();
+                std::process::exit(1);
+            }
+            _ => {
+                if range.is_some() {
+                    eprintln!("Error: report accepts at most one revision or range");
+                    std::process::exit(1);
+                }
+                range = Some(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let identity_map = match identity_map_path {
+        Some(path) => IdentityMap::load(&path).map(Some),
+        None => IdentityMap::load_for_repo(&repo),
+    };
+    let identity_map = match identity_map {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Failed to load identity map: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Without an explicit range, report on recent history of HEAD
+    let since = match (&range, since) {
+        (None, None) => Some(DEFAULT_SINCE.to_string()),
+        (_, since) => since,
+    };
+    let mut rev_args = vec![range.clone().unwrap_or_else(|| "HEAD".to_string())];
+    let mut label = rev_args[0].clone();
+    if let Some(since) = since {
+        match parse_since_arg(&since) {
+            Ok(timestamp) => {
+                rev_args.push(format!("--since={}", timestamp));
+                label = format!("{} (since {})", label, since);
+            }
+            Err(e) => {
+                eprintln!("Error parsing --since: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let commits = match collect_contributions(&repo, &rev_args) {
+        Ok(commits) => commits,
+        Err(e) => {
+            eprintln!("Failed to collect commits: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut report = build_report(&label, &commits, identity_map.as_ref());
+
+    let workdir = repo.workdir().ok();
+
+    if by_package {
+        let packages = workdir
+            .as_deref()
+            .map(WorkspacePackages::detect)
+            .unwrap_or_default();
+        if packages.is_empty() {
+            eprintln!(
+                "No workspace manifests found (Cargo.toml, pnpm-workspace.yaml, package.json, go.work)"
+            );
+        }
+        report.by_package = group_files_by(&commits, |file| {
+            packages.package_for_path(&file.path).to_string()
+        });
+    }
+
+    if by_language {
+        let root = workdir.unwrap_or_default();
+        report.by_language = group_files_by(&commits, |file| {
+            detect_language_in(&root, &file.path).to_string()
+        });
+    }
+
+    if split_tests {
+        let classifier = CodeKindClassifier::from_config(&repo);
+        report.by_code_kind = group_files_by(&commits, |file| {
+            classifier.classify(&file.path).as_str().to_string()
+        });
+    }
+
+    if classify {
+        classify_commits(
+            &mut report,
+            &commits,
+            &ClassThresholds::from_config(&repo),
+            period,
+        );
+    }
+
+    if json_output {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_report(&report);
+    }
+}
+
+fn print_report_help() {
+    eprintln!(
+        "Usage: git-ai report [<rev|range>] [--since <time>] [--identity-map <path>] [--by-package] [--by-language] [--split-tests] [--classify] [--period <day|week|month>] [--json]"
+    );
+}
+
+fn print_report(report: &Report) {
+    println!("AI authorship report for {}", report.range);
+    println!();
+    print_header("");
+    print_row("Total", &report.totals);
+
+    print_section("By team", &report.by_team);
+    print_section("By author", &report.by_author);
+    print_section("By package", &report.by_package);
+    print_section("By language", &report.by_language);
+    print_section("Production vs test", &report.by_code_kind);
+    print_classification(report);
+
+    if !report.by_security_tag.is_empty() {
+        println!();
+        println!("{:<40} {:>8}", "Security findings on AI lines", "findings");
+        for (tag, count) in &report.by_security_tag {
+            println!("{:<40} {:>8}", tag, count);
+        }
+    }
+    if report.commits_without_notes > 0 {
+        println!();
+        println!(
+            "{} commit(s) have no authorship note and are counted as human",
+            report.commits_without_notes
+        );
+    }
+}
+
+fn print_classification(report: &Report) {
+    if report.by_classification.is_empty() {
+        return;
+    }
+    let classes = ["fully_ai", "ai_assisted", "human"];
+    let count = |counts: &BTreeMap<String, usize>, class: &str| -> usize {
+        counts.get(class).copied().unwrap_or(0)
+    };
+
+    println!();
+    println!(
+        "{:<40} {:>12} {:>12} {:>12}",
+        "Commits by classification", classes[0], classes[1], classes[2]
+    );
+    println!(
+        "{:<40} {:>12} {:>12} {:>12}",
+        "Total",
+        count(&report.by_classification, classes[0]),
+        count(&report.by_classification, classes[1]),
+        count(&report.by_classification, classes[2])
+    );
+    for (period, counts) in &report.classification_over_time {
+        println!(
+            "{:<40} {:>12} {:>12} {:>12}",
+            period,
+            count(counts, classes[0]),
+            count(counts, classes[1]),
+            count(counts, classes[2])
+        );
+    }
+}
+
+pub(crate) fn print_section(title: &str, buckets: &BTreeMap<String, ReportBucket>) {
+    if buckets.is_empty() {
+        return;
+    }
+    println!();
+    print_header(title);
+    for (name, bucket) in buckets {
+        print_row(name, bucket);
+    }
+}
+
+fn print_header(title: &str) {
+    println!(
+        "{:<40} {:>8} {:>8} {:>8} {:>8} {:>6}",
+        title, "commits", "added", "ai", "human", "ai%"
+    );
+}
+
+fn print_row(name: &str, bucket: &ReportBucket) {
+    println!(
+        "{:<40} {:>8} {:>8} {:>8} {:>8} {:>5.1}%",
+        name,
+        bucket.commits,
+        bucket.added_lines,
+        bucket.ai_lines,
+        bucket.human_lines,
+        bucket.ai_share() * 100.0
+    );
+}
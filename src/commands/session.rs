@@ -0,0 +1,452 @@
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::authorship::imara_diff_utils::{LineChangeTag, compute_line_changes};
+use crate::authorship::working_log::{Checkpoint, CheckpointKind};
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::{get_authorship, grep_ai_notes};
+use crate::git::repository::{Repository, exec_git};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Serialize)]
+pub struct SessionReplay {
+    pub session: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Oldest first
+    pub edits: Vec<SessionEdit>,
+}
+
+/// What the session changed in one commit, or in one checkpoint not committed yet
+#[derive(Debug, Serialize)]
+pub struct SessionEdit {
+    /// None for a checkpoint in the working log
+    pub commit: Option<String>,
+    pub summary: String,
+    pub timestamp: i64,
+    pub files: Vec<SessionFileEdit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionFileEdit {
+    pub file: String,
+    pub added: Vec<SessionLine>,
+    /// Only known for checkpoints, which keep the file's previous version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub removed: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionLine {
+    pub line: u32,
+    pub text: String,
+}
+
+pub fn handle_session(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("show") => handle_session_show(&args[1..]),
+        Some(other) => {
+            eprintln!("Unknown session subcommand: {}", other);
+            print_session_usage_and_exit();
+        }
+        None => print_session_usage_and_exit(),
+    }
+}
+
+fn print_session_usage_and_exit() -> ! {
+    eprintln!("Usage: git-ai session show <id> [--rev <rev>] [--json]");
+    std::process::exit(1);
+}
+
+fn handle_session_show(args: &[String]) {
+    let mut session: Option<String> = None;
+    let mut rev: Option<String> = None;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--rev" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --rev requires a revision");
+                    std::process::exit(1);
+                }
+                rev = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            _ => {
+                if session.is_some() {
+                    eprintln!("Error: session show accepts exactly one session id");
+                    std::process::exit(1);
+                }
+                session = Some(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    let Some(session) = session else {
+        print_session_usage_and_exit();
+    };
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let replay = match replay_session(&repo, &session, rev.as_deref()) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Failed to replay session {}: {}", session, e);
+            std::process::exit(1);
+        }
+    };
+    if replay.edits.is_empty() {
+        eprintln!("No edits found for session {}", session);
+        std::process::exit(1);
+    }
+
+    if json_output {
+        match serde_json::to_string(&replay) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize result: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_replay(&replay);
+    }
+}
+
+/// The edits session `session` made: the lines its authorship notes attribute to it in
+/// each commit reachable from `rev` (default HEAD), then, when `rev` isn't given, what its
+/// checkpoints in the working log changed since.
+pub fn replay_session(
+    repo: &Repository,
+    session: &str,
+    rev: Option<&str>,
+) -> Result<SessionReplay, GitAiError> {
+    let tip = repo.revparse_single(rev.unwrap_or("HEAD"))?.id();
+    let mut replay = SessionReplay {
+        session: session.to_string(),
+        agent: None,
+        model: None,
+        edits: Vec::new(),
+    };
+
+    // The id is recorded with its prompt in each note; rewritten commits that are no
+    // longer reachable (e.g. the branch commits of a squash merge) are left out
+    let commits: Vec<String> = grep_ai_notes(repo, &format!("\"{}\"", session))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|commit| repo.is_ancestor(commit, &tip))
+        .collect();
+    let details = commit_details(repo, &commits)?;
+    for commit in commits.iter().rev() {
+        let Some(log) = get_authorship(repo, commit) else {
+            continue;
+        };
+        let Some((summary, timestamp)) = details.get(commit) else {
+            continue;
+        };
+        let prompts: Vec<_> = log
+            .metadata
+            .prompts
+            .iter()
+            .filter(|(_, prompt)| prompt.agent_id.id == session)
+            .collect();
+        if let Some((_, prompt)) = prompts.first()
+            && replay.agent.is_none()
+        {
+            replay.agent = Some(prompt.agent_id.tool.clone());
+            replay.model = Some(prompt.agent_id.model.clone());
+        }
+        let hashes: Vec<&str> = prompts.iter().map(|(hash, _)| hash.as_str()).collect();
+        let files = committed_file_edits(repo, commit, &log, &hashes)?;
+        if !files.is_empty() {
+            replay.edits.push(SessionEdit {
+                commit: Some(commit.clone()),
+                summary: summary.clone(),
+                timestamp: *timestamp,
+                files,
+            });
+        }
+    }
+
+    if rev.is_none() {
+        let working_log = repo.storage.working_log_for_base_commit(&tip);
+        let checkpoints = working_log.read_all_checkpoints()?;
+        let versions = |blob_sha: &str| working_log.get_file_version(blob_sha).ok();
+        let base = |file: &str| {
+            repo.get_file_content(file, &tip)
+                .ok()
+                .and_then(|content| String::from_utf8(content).ok())
+        };
+        let edits = replay_checkpoints(&checkpoints, session, versions, base);
+        if !edits.is_empty()
+            && replay.agent.is_none()
+            && let Some(agent_id) = checkpoints
+                .iter()
+                .filter_map(|checkpoint| checkpoint.agent_id.as_ref())
+                .find(|agent_id| agent_id.id == session)
+        {
+            replay.agent = Some(agent_id.tool.clone());
+            replay.model = Some(agent_id.model.clone());
+        }
+        replay.edits.extend(edits);
+    }
+    Ok(replay)
+}
+
+/// Subject and author time of each commit
+fn commit_details(
+    repo: &Repository,
+    commits: &[String],
+) -> Result<HashMap<String, (String, i64)>, GitAiError> {
+    if commits.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut args = repo.global_args_for_exec();
+    args.extend([
+        "log".to_string(),
+        "--no-walk".to_string(),
+        "--format=%H%x00%at%x00%s".to_string(),
+    ]);
+    args.extend(commits.iter().cloned());
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            let sha = fields.next()?.to_string();
+            let timestamp = fields.next()?.parse().ok()?;
+            Some((
+                sha,
+                (fields.next().unwrap_or_default().to_string(), timestamp),
+            ))
+        })
+        .collect())
+}
+
+/// The lines of each file `log` attributes to the prompts `hashes`, with their text at
+/// `commit`
+fn committed_file_edits(
+    repo: &Repository,
+    commit: &str,
+    log: &AuthorshipLog,
+    hashes: &[&str],
+) -> Result<Vec<SessionFileEdit>, GitAiError> {
+    let mut files = Vec::new();
+    for attestation in &log.attestations {
+        let lines: BTreeSet<u32> = attestation
+            .entries
+            .iter()
+            .filter(|entry| hashes.contains(&entry.hash.as_str()))
+            .flat_map(|entry| entry.line_ranges.iter().flat_map(LineRange::expand))
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let content = repo
+            .get_file_content(&attestation.file_path, commit)
+            .map(|content| String::from_utf8_lossy(&content).into_owned())
+            .unwrap_or_default();
+        let text: Vec<&str> = content.lines().collect();
+        files.push(SessionFileEdit {
+            file: attestation.file_path.clone(),
+            added: lines
+                .into_iter()
+                .map(|line| SessionLine {
+                    line,
+                    text: text
+                        .get(line as usize - 1)
+                        .map(|text| text.to_string())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            removed: None,
+        });
+    }
+    Ok(files)
+}
+
+/// What each of the session's checkpoints changed, diffing every file against its version
+/// in the checkpoint before (whoever made it), or in the base commit
+fn replay_checkpoints(
+    checkpoints: &[Checkpoint],
+    session: &str,
+    versions: impl Fn(&str) -> Option<String>,
+    base: impl Fn(&str) -> Option<String>,
+) -> Vec<SessionEdit> {
+    let mut latest: HashMap<&str, String> = HashMap::new();
+    let mut edits = Vec::new();
+    for checkpoint in checkpoints {
+        let ours = checkpoint.kind != CheckpointKind::Human
+            && checkpoint
+                .agent_id
+                .as_ref()
+                .is_some_and(|agent_id| agent_id.id == session);
+        let mut files = Vec::new();
+        for entry in &checkpoint.entries {
+            // Deleted files have no version
+            let current = versions(&entry.blob_sha).unwrap_or_default();
+            let previous = latest
+                .remove(entry.file.as_str())
+                .or_else(|| base(&entry.file))
+                .unwrap_or_default();
+            if ours {
+                let edit = file_edit(&entry.file, &previous, &current);
+                if !edit.added.is_empty() || edit.removed != Some(0) {
+                    files.push(edit);
+                }
+            }
+            latest.insert(entry.file.as_str(), current);
+        }
+        if !files.is_empty() {
+            edits.push(SessionEdit {
+                commit: None,
+                summary: "uncommitted checkpoint".to_string(),
+                timestamp: checkpoint.timestamp as i64,
+                files,
+            });
+        }
+    }
+    edits
+}
+
+fn file_edit(file: &str, previous: &str, current: &str) -> SessionFileEdit {
+    let mut added = Vec::new();
+    let mut removed = 0;
+    let mut line = 0;
+    for change in compute_line_changes(previous, current) {
+        match change.tag() {
+            LineChangeTag::Equal => line += 1,
+            LineChangeTag::Insert => {
+                line += 1;
+                added.push(SessionLine {
+                    line,
+                    text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                });
+            }
+            LineChangeTag::Delete => removed += 1,
+        }
+    }
+    SessionFileEdit {
+        file: file.to_string(),
+        added,
+        removed: Some(removed),
+    }
+}
+
+fn print_replay(replay: &SessionReplay) {
+    match (&replay.agent, &replay.model) {
+        (Some(agent), Some(model)) if !model.is_empty() => {
+            println!("session {} ({} {})", replay.session, agent, model)
+        }
+        (Some(agent), _) => println!("session {} ({})", replay.session, agent),
+        _ => println!("session {}", replay.session),
+    }
+    for edit in &replay.edits {
+        let date = chrono::DateTime::from_timestamp(edit.timestamp, 0)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!();
+        match &edit.commit {
+            Some(commit) => println!(
+                "  commit {} {} {}",
+                &commit[..commit.len().min(7)],
+                date,
+                edit.summary
+            ),
+            None => println!("  {} {}", edit.summary, date),
+        }
+        for file in &edit.files {
+            match file.removed {
+                Some(removed) => {
+                    println!("    {} +{} -{}", file.file, file.added.len(), removed)
+                }
+                None => println!("    {} +{}", file.file, file.added.len()),
+            }
+            for line in &file.added {
+                println!("      {:>5} + {}", line.line, line.text);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorship::working_log::{AgentId, WorkingLogEntry};
+
+    fn checkpoint(kind: CheckpointKind, session: &str, files: &[(&str, &str)]) -> Checkpoint {
+        let mut checkpoint = Checkpoint::new(
+            kind,
+            String::new(),
+            "author".to_string(),
+            files
+                .iter()
+                .map(|(file, blob)| {
+                    WorkingLogEntry::new(file.to_string(), blob.to_string(), Vec::new(), Vec::new())
+                })
+                .collect(),
+        );
+        checkpoint.agent_id = Some(AgentId {
+            tool: "claude".to_string(),
+            id: session.to_string(),
+            model: "sonnet".to_string(),
+        });
+        checkpoint
+    }
+
+    #[test]
+    fn test_replay_checkpoints_diffs_against_previous_version() {
+        let checkpoints = vec![
+            checkpoint(CheckpointKind::AiAgent, "s1", &[("a.rs", "v1")]),
+            // Another session's edit is the baseline for the next one, not part of it
+            checkpoint(CheckpointKind::AiAgent, "s2", &[("a.rs", "v2")]),
+            checkpoint(CheckpointKind::AiAgent, "s1", &[("a.rs", "v3")]),
+        ];
+        let versions = |blob: &str| {
+            Some(
+                match blob {
+                    "v1" => "base\none\n",
+                    "v2" => "base\none\ntwo\n",
+                    _ => "base\nthree\ntwo\n",
+                }
+                .to_string(),
+            )
+        };
+        let base = |_: &str| Some("base\n".to_string());
+
+        let edits = replay_checkpoints(&checkpoints, "s1", versions, base);
+        assert_eq!(edits.len(), 2);
+
+        let first = &edits[0].files[0];
+        assert_eq!(first.file, "a.rs");
+        assert_eq!(first.removed, Some(0));
+        assert_eq!(first.added.len(), 1);
+        assert_eq!(
+            (first.added[0].line, first.added[0].text.as_str()),
+            (2, "one")
+        );
+
+        let second = &edits[1].files[0];
+        assert_eq!(second.removed, Some(1));
+        assert_eq!(
+            (second.added[0].line, second.added[0].text.as_str()),
+            (2, "three")
+        );
+    }
+}
@@ -4,7 +4,7 @@ use crate::authorship::prompt_utils::find_prompt_with_db_fallback;
 use crate::authorship::secrets::redact_secrets_from_prompts;
 use crate::commands::diff::{DiffOptions, get_diff_json_filtered};
 use crate::git::find_repository;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
 
 /// Handle the `share` command
 ///
@@ -126,7 +126,7 @@ pub fn create_bundle(
 ) -> Result<crate::api::CreateBundleResponse, crate::error::GitAiError> {
     use crate::authorship::internal_db::InternalDatabase;
 
-    let mut prompts = HashMap::new();
+    let mut prompts = BTreeMap::new();
     prompts.insert(prompt_id.clone(), prompt_record.clone());
 
     // Get commit_sha from the database
@@ -155,12 +155,10 @@ pub fn create_bundle(
     let prompt_ids: Vec<String> = prompts.keys().cloned().collect();
 
     // Redact secrets from all prompts before uploading
-    let mut prompts_btree: BTreeMap<String, _> = prompts.into_iter().collect();
-    redact_secrets_from_prompts(&mut prompts_btree);
-    let prompts: HashMap<String, _> = prompts_btree.into_iter().collect();
+    redact_secrets_from_prompts(&mut prompts);
 
     // Get diff files if requested
-    let files: HashMap<String, ApiFileRecord> = if include_diffs {
+    let files: BTreeMap<String, ApiFileRecord> = if include_diffs {
         if let Some(ref sha) = commit_sha {
             // Try to get the repository
             if let Ok(repo) = find_repository(&Vec::<String>::new()) {
@@ -181,16 +179,16 @@ pub fn create_bundle(
                             .map(|(path, file_diff)| (path.clone(), ApiFileRecord::from(file_diff)))
                             .collect()
                     }
-                    Err(_) => HashMap::new(), // Diff failed, proceed without files
+                    Err(_) => BTreeMap::new(), // Diff failed, proceed without files
                 }
             } else {
-                HashMap::new() // No repo, proceed without files
+                BTreeMap::new() // No repo, proceed without files
             }
         } else {
-            HashMap::new() // No commit SHA, proceed without files
+            BTreeMap::new() // No commit SHA, proceed without files
         }
     } else {
-        HashMap::new()
+        BTreeMap::new()
     };
 
     // Create bundle with prompts and optional files
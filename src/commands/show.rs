@@ -2,16 +2,20 @@ use crate::error::GitAiError;
 use crate::git::find_repository;
 use crate::git::refs::{CommitAuthorship, get_commits_with_notes_from_list};
 use crate::git::repository::{CommitRange, Repository};
+use crate::model::CommitSummary;
 
 const NO_AUTHORSHIP_DATA_MESSAGE: &str = "No authorship data found for this revision";
 
 pub fn handle_show(args: &[String]) {
-    if args.is_empty() {
+    let json = args.iter().any(|arg| arg == "--json");
+    let specs: Vec<&String> = args.iter().filter(|arg| *arg != "--json").collect();
+
+    if specs.is_empty() {
         eprintln!("Error: show requires a revision or range");
         std::process::exit(1);
     }
 
-    if args.len() > 1 {
+    if specs.len() > 1 {
         eprintln!("Error: show accepts exactly one revision or range");
         std::process::exit(1);
     }
@@ -24,7 +28,12 @@ pub fn handle_show(args: &[String]) {
         }
     };
 
-    if let Err(e) = show_authorship(&repo, &args[0]) {
+    let result = if json {
+        show_authorship_json(&repo, specs[0])
+    } else {
+        show_authorship(&repo, specs[0])
+    };
+    if let Err(e) = result {
         eprintln!("Failed to show authorship: {}", e);
         std::process::exit(1);
     }
@@ -71,6 +80,34 @@ fn show_authorship(repo: &Repository, spec: &str) -> Result<(), GitAiError> {
     Ok(())
 }
 
+/// One [`CommitSummary`] for a revision, or an array of them for a range
+fn show_authorship_json(repo: &Repository, spec: &str) -> Result<(), GitAiError> {
+    let commits = resolve_commits(repo, spec)?;
+    let summaries: Vec<CommitSummary> = get_commits_with_notes_from_list(repo, &commits)?
+        .iter()
+        .map(|entry| match entry {
+            CommitAuthorship::Log {
+                sha,
+                authorship_log,
+                ..
+            } => CommitSummary::from_authorship_log(sha, authorship_log),
+            CommitAuthorship::NoLog { sha, .. } => CommitSummary::without_authorship(sha),
+        })
+        .collect();
+
+    let json = if spec.contains("..") {
+        serde_json::to_string_pretty(&summaries)
+    } else {
+        match summaries.first() {
+            Some(summary) => serde_json::to_string_pretty(summary),
+            None => serde_json::to_string_pretty(&summaries),
+        }
+    }
+    .map_err(GitAiError::JsonError)?;
+    println!("{}", json);
+    Ok(())
+}
+
 fn resolve_commits(repo: &Repository, spec: &str) -> Result<Vec<String>, GitAiError> {
     if let Some((start, end)) = spec.split_once("..") {
         if start.is_empty() || end.is_empty() {
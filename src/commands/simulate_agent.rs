@@ -0,0 +1,623 @@
+use crate::authorship::post_commit::post_commit;
+use crate::authorship::stats::stats_for_commit_stats;
+use crate::authorship::transcript::AiTranscript;
+use crate::authorship::working_log::{AgentId, CheckpointKind};
+use crate::commands::blame::GitAiBlameOptions;
+use crate::commands::checkpoint::run as checkpoint;
+use crate::commands::checkpoint_agent::agent_presets::AgentRunResult;
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git, find_repository_in_path};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Name and email the scratch repository commits with, and the human author of its
+/// checkpoints unless a step names another
+const SIMULATED_HUMAN: &str = "Simulated Human";
+const SIMULATED_EMAIL: &str = "simulated@git-ai.local";
+
+/// A scripted session: AI and human edits, checkpointed as they happen, with commits and
+/// expectations about the attribution that results
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationScript {
+    pub steps: Vec<SimulationStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum SimulationStep {
+    /// Edit files as an AI agent, then checkpoint them as its work
+    Ai(AiEdit),
+    /// Edit files by hand, then checkpoint them as the human's work
+    Human(HumanEdit),
+    /// Stage everything and commit it with this message
+    Commit(String),
+    /// Check the attribution so far
+    Expect(Expectation),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AiEdit {
+    #[serde(default = "default_tool")]
+    pub tool: String,
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// Edits in the same session share a prompt
+    #[serde(default = "default_session")]
+    pub session: String,
+    pub files: BTreeMap<String, FileContent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HumanEdit {
+    pub author: Option<String>,
+    pub files: BTreeMap<String, FileContent>,
+}
+
+/// A file's new contents, as a string or a list of lines; null deletes the file
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum FileContent {
+    Text(String),
+    Lines(Vec<String>),
+    Deleted,
+}
+
+/// What attribution should look like. Line expectations blame the working tree, so the
+/// file must have been committed; commit expectations read HEAD's stats.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    pub file: Option<String>,
+    /// Lines (e.g. `"2-4,7"`) that are exactly the AI-attributed ones
+    pub ai_lines: Option<String>,
+    /// Lines that are exactly the human-attributed ones
+    pub human_lines: Option<String>,
+    pub ai_additions: Option<u32>,
+    pub human_additions: Option<u32>,
+}
+
+fn default_tool() -> String {
+    "simulated-agent".to_string()
+}
+
+fn default_model() -> String {
+    "simulated-model".to_string()
+}
+
+fn default_session() -> String {
+    "simulated-session".to_string()
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepReport {
+    pub step: usize,
+    pub description: String,
+    /// Set for expectations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passed: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulationReport {
+    pub repo: String,
+    pub steps: Vec<StepReport>,
+    pub failed_expectations: usize,
+}
+
+pub fn handle_simulate_agent(args: &[String]) {
+    let mut script_path: Option<String> = None;
+    let mut dir: Option<PathBuf> = None;
+    let mut keep = false;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--dir" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("Error: --dir requires a path");
+                    std::process::exit(1);
+                };
+                dir = Some(PathBuf::from(value));
+                keep = true;
+                i += 2;
+            }
+            "--keep" => {
+                keep = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            "--help" | "-h" => print_simulate_agent_help_and_exit(),
+            other if script_path.is_none() && (other == "-" || !other.starts_with('-')) => {
+                script_path = Some(other.to_string());
+                i += 1;
+            }
+            other => {
+                eprintln!("Error: unknown simulate-agent argument: {}", other);
+                print_simulate_agent_help_and_exit();
+            }
+        }
+    }
+    let Some(script_path) = script_path else {
+        print_simulate_agent_help_and_exit();
+    };
+
+    let script = match read_script(&script_path) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Failed to read simulation script: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let dir = dir.unwrap_or_else(|| {
+        std::env::temp_dir().join(format!("git-ai-simulation-{}", uuid::Uuid::new_v4()))
+    });
+    let result = run_simulation(&script, &dir);
+    if !keep {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Simulation failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize simulation report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_report(&report, keep);
+    }
+
+    if report.failed_expectations > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn print_simulate_agent_help_and_exit() -> ! {
+    eprintln!("Usage: git-ai simulate-agent <script.json|-> [--keep] [--dir <path>] [--json]");
+    eprintln!();
+    eprintln!("Replays scripted AI and human edits in a scratch repository, checkpointing and");
+    eprintln!("committing them the way hooks would, with your git-ai config, then checks the");
+    eprintln!("attribution against the script's expectations.");
+    eprintln!();
+    eprintln!("  --keep        Keep the scratch repository and print its path");
+    eprintln!("  --dir <path>  Create the scratch repository here (implies --keep)");
+    eprintln!("  --json        Output the report as JSON");
+    eprintln!();
+    eprintln!("Script:");
+    eprintln!("  {{\"steps\": [");
+    eprintln!("    {{\"human\": {{\"files\": {{\"app.py\": [\"import os\"]}}}}}},");
+    eprintln!("    {{\"commit\": \"Initial commit\"}},");
+    eprintln!(
+        "    {{\"ai\": {{\"tool\": \"claude\", \"model\": \"sonnet\", \"files\": {{\"app.py\": [\"import os\", \"print(os.getcwd())\"]}}}}}},"
+    );
+    eprintln!("    {{\"commit\": \"Print the working directory\"}},");
+    eprintln!(
+        "    {{\"expect\": {{\"file\": \"app.py\", \"ai_lines\": \"2\", \"human_lines\": \"1\"}}}},"
+    );
+    eprintln!("    {{\"expect\": {{\"ai_additions\": 1, \"human_additions\": 0}}}}");
+    eprintln!("  ]}}");
+    eprintln!();
+    eprintln!("File contents are a string or a list of lines; null deletes the file.");
+    eprintln!("Exits with 1 if any expectation fails.");
+    std::process::exit(1);
+}
+
+fn read_script(path: &str) -> Result<SimulationScript, GitAiError> {
+    let contents = if path == "-" {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        contents
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    serde_json::from_str(&contents)
+        .map_err(|e| GitAiError::Generic(format!("Invalid simulation script: {}", e)))
+}
+
+/// Replay `script` in a new repository at `dir`, which must not exist or be empty
+pub fn run_simulation(
+    script: &SimulationScript,
+    dir: &Path,
+) -> Result<SimulationReport, GitAiError> {
+    if dir.exists() && std::fs::read_dir(dir)?.next().is_some() {
+        return Err(GitAiError::Generic(format!(
+            "{} is not empty; the simulation needs a fresh directory",
+            dir.display()
+        )));
+    }
+    std::fs::create_dir_all(dir)?;
+    let dir_str = dir.to_string_lossy().to_string();
+    exec_git(&[
+        "init".to_string(),
+        "--quiet".to_string(),
+        "--initial-branch=main".to_string(),
+        dir_str.clone(),
+    ])?;
+    let repo = find_repository_in_path(&dir_str)?;
+    for (key, value) in [
+        ("user.name", SIMULATED_HUMAN),
+        ("user.email", SIMULATED_EMAIL),
+    ] {
+        git(&repo, &["config", key, value])?;
+    }
+
+    let mut steps = Vec::new();
+    let mut failed_expectations = 0;
+    for (index, step) in script.steps.iter().enumerate() {
+        let number = index + 1;
+        let report = run_step(&repo, dir, step, number)
+            .map_err(|e| GitAiError::Generic(format!("step {}: {}", number, e)))?;
+        if report.passed == Some(false) {
+            failed_expectations += 1;
+        }
+        steps.push(report);
+    }
+
+    Ok(SimulationReport {
+        repo: dir_str,
+        steps,
+        failed_expectations,
+    })
+}
+
+fn run_step(
+    repo: &Repository,
+    dir: &Path,
+    step: &SimulationStep,
+    number: usize,
+) -> Result<StepReport, GitAiError> {
+    let report = |description: String| StepReport {
+        step: number,
+        description,
+        passed: None,
+        failures: Vec::new(),
+    };
+    match step {
+        SimulationStep::Ai(edit) => {
+            let paths = write_files(dir, &edit.files)?;
+            let agent_run_result = AgentRunResult {
+                agent_id: AgentId {
+                    tool: edit.tool.clone(),
+                    id: edit.session.clone(),
+                    model: edit.model.clone(),
+                },
+                agent_metadata: None,
+                checkpoint_kind: CheckpointKind::AiAgent,
+                transcript: Some(AiTranscript { messages: vec![] }),
+                repo_working_dir: None,
+                edited_filepaths: Some(paths.clone()),
+                will_edit_filepaths: None,
+                dirty_files: None,
+                token_usage: None,
+            };
+            checkpoint(
+                repo,
+                &edit.tool,
+                CheckpointKind::AiAgent,
+                false, // show_working_log
+                false, // reset
+                true,  // quiet
+                Some(agent_run_result),
+                false,
+                None, // inherit
+            )?;
+            Ok(report(format!(
+                "{} ({}) edited {}",
+                edit.tool,
+                edit.model,
+                paths.join(", ")
+            )))
+        }
+        SimulationStep::Human(edit) => {
+            let paths = write_files(dir, &edit.files)?;
+            let author = edit.author.as_deref().unwrap_or(SIMULATED_HUMAN);
+            checkpoint(
+                repo,
+                author,
+                CheckpointKind::Human,
+                false, // show_working_log
+                false, // reset
+                true,  // quiet
+                None,  // agent_run_result
+                false,
+                None, // inherit
+            )?;
+            Ok(report(format!("{} edited {}", author, paths.join(", "))))
+        }
+        SimulationStep::Commit(message) => {
+            let parent = git(repo, &["rev-parse", "--verify", "--quiet", "HEAD"])
+                .ok()
+                .filter(|sha| !sha.is_empty());
+            git(repo, &["add", "-A"])?;
+            git(repo, &["commit", "--quiet", "--no-verify", "-m", message])?;
+            let commit_sha = git(repo, &["rev-parse", "HEAD"])?;
+            post_commit(
+                repo,
+                parent,
+                commit_sha.clone(),
+                SIMULATED_HUMAN.to_string(),
+                true,
+            )?;
+            Ok(report(format!(
+                "committed {} \"{}\"",
+                &commit_sha[..commit_sha.len().min(7)],
+                message
+            )))
+        }
+        SimulationStep::Expect(expectation) => {
+            let failures = check_expectation(repo, expectation)?;
+            let mut step = report(describe_expectation(expectation));
+            step.passed = Some(failures.is_empty());
+            step.failures = failures;
+            Ok(step)
+        }
+    }
+}
+
+/// Write (or delete) the files, returning their paths
+fn write_files(
+    dir: &Path,
+    files: &BTreeMap<String, FileContent>,
+) -> Result<Vec<String>, GitAiError> {
+    for (path, content) in files {
+        let relative = Path::new(path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            return Err(GitAiError::Generic(format!(
+                "{} is outside the scratch repository",
+                path
+            )));
+        }
+        let full_path = dir.join(relative);
+        match content {
+            FileContent::Deleted => {
+                if full_path.exists() {
+                    std::fs::remove_file(&full_path)?;
+                }
+            }
+            FileContent::Text(text) => {
+                if let Some(parent) = full_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&full_path, text)?;
+            }
+            FileContent::Lines(lines) => {
+                if let Some(parent) = full_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let mut text = lines.join("\n");
+                if !lines.is_empty() {
+                    text.push('\n');
+                }
+                std::fs::write(&full_path, text)?;
+            }
+        }
+    }
+    Ok(files.keys().cloned().collect())
+}
+
+fn check_expectation(
+    repo: &Repository,
+    expectation: &Expectation,
+) -> Result<Vec<String>, GitAiError> {
+    let mut failures = Vec::new();
+
+    if expectation.ai_lines.is_some() || expectation.human_lines.is_some() {
+        let file = expectation.file.as_deref().ok_or_else(|| {
+            GitAiError::Generic("ai_lines and human_lines need a file".to_string())
+        })?;
+        let options = GitAiBlameOptions {
+            use_prompt_hashes_as_names: true,
+            no_output: true,
+            ..Default::default()
+        };
+        let (line_authors, prompts) = repo.blame(file, &options)?;
+        let (ai, human): (BTreeSet<u32>, BTreeSet<u32>) = {
+            let mut ai = BTreeSet::new();
+            let mut human = BTreeSet::new();
+            for (line, author) in line_authors {
+                if prompts.contains_key(&author) {
+                    ai.insert(line);
+                } else {
+                    human.insert(line);
+                }
+            }
+            (ai, human)
+        };
+        for (what, spec, actual) in [
+            ("AI", &expectation.ai_lines, &ai),
+            ("human", &expectation.human_lines, &human),
+        ] {
+            let Some(spec) = spec else { continue };
+            let expected = parse_lines(spec)?;
+            if expected != *actual {
+                failures.push(format!(
+                    "{}: expected {} lines {}, got {}",
+                    file,
+                    what,
+                    format_lines(&expected),
+                    format_lines(actual)
+                ));
+            }
+        }
+    }
+
+    if expectation.ai_additions.is_some() || expectation.human_additions.is_some() {
+        let head = git(repo, &["rev-parse", "HEAD"])?;
+        let stats = stats_for_commit_stats(repo, &head, &[])?;
+        for (what, expected, actual) in [
+            ("AI", expectation.ai_additions, stats.ai_additions),
+            ("human", expectation.human_additions, stats.human_additions),
+        ] {
+            if let Some(expected) = expected
+                && expected != actual
+            {
+                failures.push(format!(
+                    "HEAD: expected {} {} additions, got {}",
+                    expected, what, actual
+                ));
+            }
+        }
+    }
+
+    Ok(failures)
+}
+
+fn describe_expectation(expectation: &Expectation) -> String {
+    let mut parts = Vec::new();
+    if let Some(lines) = &expectation.ai_lines {
+        parts.push(format!("AI lines {}", lines));
+    }
+    if let Some(lines) = &expectation.human_lines {
+        parts.push(format!("human lines {}", lines));
+    }
+    if let Some(count) = expectation.ai_additions {
+        parts.push(format!("{} AI additions", count));
+    }
+    if let Some(count) = expectation.human_additions {
+        parts.push(format!("{} human additions", count));
+    }
+    match &expectation.file {
+        Some(file) => format!("expect {}: {}", file, parts.join(", ")),
+        None => format!("expect HEAD: {}", parts.join(", ")),
+    }
+}
+
+/// `"2-4,7"` -> {2, 3, 4, 7}; `""` is no lines
+fn parse_lines(spec: &str) -> Result<BTreeSet<u32>, GitAiError> {
+    let invalid = || GitAiError::Generic(format!("Invalid line list '{}'", spec));
+    let mut lines = BTreeSet::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let start: u32 = start.trim().parse().map_err(|_| invalid())?;
+        let end: u32 = end.trim().parse().map_err(|_| invalid())?;
+        if start == 0 || end < start {
+            return Err(invalid());
+        }
+        lines.extend(start..=end);
+    }
+    Ok(lines)
+}
+
+/// {2, 3, 4, 7} -> `"2-4,7"`, or `"none"`
+fn format_lines(lines: &BTreeSet<u32>) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn git(repo: &Repository, args: &[&str]) -> Result<String, GitAiError> {
+    let mut full_args = repo.global_args_for_exec();
+    full_args.extend(args.iter().map(|arg| arg.to_string()));
+    let output = exec_git(&full_args)?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn print_report(report: &SimulationReport, keep: bool) {
+    for step in &report.steps {
+        let status = match step.passed {
+            Some(true) => "ok   ",
+            Some(false) => "FAIL ",
+            None => "",
+        };
+        println!("[{}] {}{}", step.step, status, step.description);
+        for failure in &step.failures {
+            println!("      {}", failure);
+        }
+    }
+    let expectations = report
+        .steps
+        .iter()
+        .filter(|step| step.passed.is_some())
+        .count();
+    println!();
+    println!(
+        "{} of {} expectation(s) passed",
+        expectations - report.failed_expectations,
+        expectations
+    );
+    if keep {
+        println!("Scratch repository: {}", report.repo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_lines() {
+        let lines = parse_lines("2-4, 7,9-9").unwrap();
+        assert_eq!(
+            lines.iter().copied().collect::<Vec<_>>(),
+            vec![2, 3, 4, 7, 9]
+        );
+        assert_eq!(format_lines(&lines), "2-4,7,9");
+        assert_eq!(format_lines(&parse_lines("").unwrap()), "none");
+        assert!(parse_lines("4-2").is_err());
+        assert!(parse_lines("0").is_err());
+        assert!(parse_lines("a").is_err());
+    }
+
+    #[test]
+    fn test_script_parses_steps_and_file_contents() {
+        let script: SimulationScript = serde_json::from_str(
+            r#"{"steps": [
+                {"human": {"files": {"a.txt": "one\n", "b.txt": null}}},
+                {"ai": {"tool": "claude", "files": {"a.txt": ["one", "two"]}}},
+                {"commit": "Add two"},
+                {"expect": {"file": "a.txt", "ai_lines": "2"}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(script.steps.len(), 4);
+        let SimulationStep::Ai(edit) = &script.steps[1] else {
+            panic!("expected an AI step");
+        };
+        assert_eq!(edit.model, "simulated-model");
+        assert!(matches!(edit.files["a.txt"], FileContent::Lines(_)));
+
+        assert!(serde_json::from_str::<SimulationScript>(r#"{"steps": [{"push": {}}]}"#).is_err());
+    }
+}
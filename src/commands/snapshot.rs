@@ -0,0 +1,87 @@
+use crate::authorship::snapshot::{Snapshot, build_snapshot};
+use crate::git::find_repository;
+use std::path::PathBuf;
+
+pub fn handle_snapshot(args: &[String]) {
+    let mut rev: Option<String> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--output" | "-o" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --output requires a path");
+                    std::process::exit(1);
+                }
+                output = Some(PathBuf::from(&args[i + 1]));
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            arg if arg.starts_with('-') || rev.is_some() => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                eprintln!("Usage: git-ai snapshot [<rev>] [--output <path>] [--json]");
+                std::process::exit(1);
+            }
+            arg => {
+                rev = Some(arg.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let rev = rev.unwrap_or_else(|| "HEAD".to_string());
+    let snapshot = match build_snapshot(&repo, &rev) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!("Failed to build snapshot of {}: {}", rev, e);
+            std::process::exit(1);
+        }
+    };
+
+    let path = output.unwrap_or_else(|| Snapshot::default_path(&repo, &snapshot.commit));
+    if let Err(e) = snapshot.write(&path) {
+        eprintln!("Failed to write snapshot to {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+
+    let ai_files = snapshot
+        .files
+        .values()
+        .filter(|file| !file.ai.is_empty())
+        .count();
+    let ai_lines = snapshot.ai_line_count();
+
+    if json_output {
+        let summary = serde_json::json!({
+            "path": path.to_string_lossy(),
+            "commit": snapshot.commit,
+            "files": snapshot.files.len(),
+            "ai_files": ai_files,
+            "ai_lines": ai_lines,
+            "prompts": snapshot.prompts.len(),
+        });
+        println!("{}", summary);
+    } else {
+        println!(
+            "Wrote snapshot of {} file(s), {} with {} AI line(s), at {} to {}",
+            snapshot.files.len(),
+            ai_files,
+            ai_lines,
+            &snapshot.commit[..snapshot.commit.len().min(7)],
+            path.display()
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::authorship::stats::{CommitStats, write_stats_to_terminal};
 use crate::authorship::virtual_attribution::VirtualAttributions;
-use crate::authorship::working_log::CheckpointKind;
+use crate::authorship::working_log::{CheckpointEffort, CheckpointKind};
+use crate::commands::check::run_staged_check;
 use crate::commands::checkpoint;
 use crate::error::GitAiError;
 use crate::git::find_repository;
@@ -18,6 +19,7 @@ struct CheckpointInfo {
     deletions: u32,
     tool_model: String,
     is_human: bool,
+    effort: CheckpointEffort,
 }
 
 #[derive(Serialize)]
@@ -28,15 +30,31 @@ struct StatusOutput {
 
 pub fn handle_status(args: &[String]) {
     let mut json_output = false;
+    let mut fail_on_unattributed = false;
 
     let mut i = 0;
     while i < args.len() {
-        if args[i].as_str() == "--json" {
-            json_output = true;
+        match args[i].as_str() {
+            "--json" => json_output = true,
+            "--fail-on-unattributed" => fail_on_unattributed = true,
+            _ => {}
         }
         i += 1;
     }
 
+    // Hook mode: only look at staged files, and don't run the usual human checkpoint
+    // first, since it would record (and so hide) exactly the edits being checked for.
+    if fail_on_unattributed {
+        match find_repository(&[]) {
+            Ok(repo) => run_staged_check(&repo, json_output),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     if let Err(e) = run_status(json_output) {
         eprintln!("Error: {}", e);
         std::process::exit(1);
@@ -60,6 +78,7 @@ fn run_status(json: bool) -> Result<(), GitAiError> {
         true,
         None,
         false,
+        None,
     );
 
     let head = repo.head()?;
@@ -114,6 +133,7 @@ fn run_status(json: bool) -> Result<(), GitAiError> {
             deletions,
             tool_model,
             is_human,
+            effort: checkpoint.effort.clone(),
         });
     }
 
@@ -0,0 +1,143 @@
+use crate::git::find_repository;
+use crate::git::repository::Repository;
+use crate::git::sync_authorship::{NotesSyncOp, run_notes_sync_all};
+
+/// Handle `git-ai sync <fetch|push> [<remote>...]`: fetch or push authorship notes.
+/// `--all` syncs with every configured remote; remotes are synced in parallel.
+/// `git-ai sync --flush` retries everything queued in `.git/ai/pending-sync`.
+///
+/// Hooks run their notes network work through this command, leaving it to finish in the
+/// background when it outlasts the hook budget. Its output is appended to
+/// `.git/ai/logs/sync.log`, so every line is timestamped.
+pub fn handle_sync(args: &[String]) {
+    let mut op = None;
+    let mut remotes: Vec<String> = Vec::new();
+    let mut all = false;
+    let mut flush = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--help" || arg == "-h" {
+            print_usage();
+            std::process::exit(0);
+        } else if arg == "--flush" {
+            flush = true;
+        } else if arg == "--all" {
+            all = true;
+        } else if arg.starts_with('-') {
+            eprintln!("Error: unknown option '{}'", arg);
+            print_usage();
+            std::process::exit(1);
+        } else if op.is_none() {
+            op = match NotesSyncOp::parse(arg) {
+                Some(op) => Some(op),
+                None => {
+                    eprintln!("Error: unknown sync operation '{}'", arg);
+                    print_usage();
+                    std::process::exit(1);
+                }
+            };
+        } else {
+            remotes.push(arg.to_string());
+        }
+        i += 1;
+    }
+
+    if flush && (op.is_some() || all) {
+        eprintln!("Error: --flush can't be combined with an operation");
+        print_usage();
+        std::process::exit(1);
+    }
+    if all && !remotes.is_empty() {
+        eprintln!("Error: --all can't be combined with remote names");
+        print_usage();
+        std::process::exit(1);
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if flush {
+        flush_pending_sync(&repo);
+        return;
+    }
+
+    let Some(op) = op else {
+        print_usage();
+        std::process::exit(1);
+    };
+
+    if all {
+        remotes = repo
+            .remotes()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| !name.is_empty())
+            .collect();
+    } else if remotes.is_empty()
+        && let Ok(Some(remote)) = repo.get_default_remote()
+    {
+        remotes.push(remote);
+    }
+    if remotes.is_empty() {
+        eprintln!("Error: no remote given and no default remote configured");
+        std::process::exit(1);
+    }
+
+    if sync_and_report(&repo, op, &remotes) > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn flush_pending_sync(repo: &Repository) {
+    let entries = repo.storage.read_pending_sync();
+    if entries.is_empty() {
+        println!("No pending notes sync operations");
+        return;
+    }
+
+    // Fetch before push so queued pushes go out on top of the latest remote notes
+    let mut failed = 0;
+    for op in [NotesSyncOp::Fetch, NotesSyncOp::Push] {
+        let remotes: Vec<String> = entries
+            .iter()
+            .filter(|entry| entry.op == op)
+            .map(|entry| entry.remote.clone())
+            .collect();
+        if !remotes.is_empty() {
+            failed += sync_and_report(repo, op, &remotes);
+        }
+    }
+    if failed > 0 {
+        eprintln!("{} notes sync operation(s) still pending", failed);
+        std::process::exit(1);
+    }
+}
+
+/// Sync with every remote in parallel and print one line per remote. Returns the number
+/// of remotes that failed.
+fn sync_and_report(repo: &Repository, op: NotesSyncOp, remotes: &[String]) -> usize {
+    let mut failed = 0;
+    for (remote, result) in run_notes_sync_all(repo, op, remotes) {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        match result {
+            Ok(()) => println!("[{}] {} {}: ok", timestamp, op.as_str(), remote),
+            Err(e) => {
+                eprintln!("[{}] {} {}: failed: {}", timestamp, op.as_str(), remote, e);
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+fn print_usage() {
+    eprintln!("Usage: git-ai sync <fetch|push> [<remote>...] [--all]");
+    eprintln!("       git-ai sync --flush");
+}
@@ -60,7 +60,7 @@ pub fn handle_sync_prompts(args: &[String]) {
     }
 }
 
-fn parse_since_arg(since_str: &str) -> Result<i64, GitAiError> {
+pub fn parse_since_arg(since_str: &str) -> Result<i64, GitAiError> {
     // Try parsing as relative duration first (1d, 2h, 1w)
     if let Ok(duration) = humantime::parse_duration(since_str) {
         let now = SystemTime::now()
@@ -0,0 +1,55 @@
+use crate::config::{self, Config};
+use crate::observability::usage;
+
+/// Handle `git-ai telemetry <on|off|status>`: manage opt-in anonymous usage telemetry.
+pub fn handle_telemetry(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("on") => set_usage_telemetry(true),
+        Some("off") => {
+            set_usage_telemetry(false);
+            // Nothing collected before opting out is ever sent
+            usage::clear_spool();
+        }
+        Some("status") | None => print_status(),
+        Some("--help") | Some("-h") => print_usage(),
+        Some(other) => {
+            eprintln!("Error: unknown telemetry command '{}'", other);
+            print_usage();
+            std::process::exit(1);
+        }
+    }
+}
+
+fn set_usage_telemetry(enabled: bool) {
+    let result = config::load_file_config_public().and_then(|mut file_config| {
+        file_config.usage_telemetry = Some(enabled);
+        config::save_file_config(&file_config)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to update config: {}", e);
+        std::process::exit(1);
+    }
+    println!(
+        "Usage telemetry {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+}
+
+fn print_status() {
+    let enabled = Config::get().usage_telemetry_enabled();
+    println!(
+        "Usage telemetry: {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    println!("Spooled events: {}", usage::read_spool().len());
+    if let Some(path) = usage::spool_path() {
+        println!("Spool: {}", path.display());
+    }
+    println!();
+    println!("When enabled, git-ai records the command name, its duration, and a coarse");
+    println!("repository size bucket. It never records paths, remotes, arguments, or content.");
+}
+
+fn print_usage() {
+    eprintln!("Usage: git-ai telemetry <on|off|status>");
+}
@@ -0,0 +1,267 @@
+use crate::authorship::report::{ReportBucket, collect_contributions, group_files_by};
+use crate::commands::sync_prompts::parse_since_arg;
+use crate::git::find_repository;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const DEFAULT_LIMIT: usize = 10;
+
+/// Paths with fewer added lines than this are left out of the AI-share ranking, so a
+/// one-line file that happens to be AI-written doesn't top the list
+const DEFAULT_MIN_LINES: u32 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+struct Hotspot {
+    path: String,
+    commits: usize,
+    added_lines: u32,
+    ai_lines: u32,
+    ai_share: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct TopResult {
+    by_ai_lines: Vec<Hotspot>,
+    by_ai_share: Vec<Hotspot>,
+}
+
+pub fn handle_top(args: &[String]) {
+    let mut since: Option<String> = None;
+    let mut limit = DEFAULT_LIMIT;
+    let mut min_lines = DEFAULT_MIN_LINES;
+    let mut dir_depth: Option<usize> = None;
+    let mut json_output = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--since" => {
+                since = Some(require_value(args, i, "--since").to_string());
+                i += 2;
+            }
+            "-n" | "--limit" => {
+                limit = parse_number(require_value(args, i, "--limit"), "--limit");
+                i += 2;
+            }
+            "--min-lines" => {
+                min_lines = parse_number(require_value(args, i, "--min-lines"), "--min-lines");
+                i += 2;
+            }
+            "--dirs" => {
+                dir_depth.get_or_insert(1);
+                i += 1;
+            }
+            "--depth" => {
+                dir_depth = Some(parse_number(require_value(args, i, "--depth"), "--depth"));
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            arg => {
+                eprintln!("Error: Unknown argument: {}", arg);
+                print_top_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut rev_args = vec!["HEAD".to_string()];
+    if let Some(since) = since {
+        match parse_since_arg(&since) {
+            Ok(timestamp) => rev_args.push(format!("--since={}", timestamp)),
+            Err(e) => {
+                eprintln!("Error parsing --since: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let commits = match collect_contributions(&repo, &rev_args) {
+        Ok(commits) => commits,
+        Err(e) => {
+            eprintln!("Failed to collect commits: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Only rank paths that still exist; deleted files don't need review attention
+    let workdir = repo.workdir().ok();
+    let mut groups = group_files_by(&commits, |file| match dir_depth {
+        Some(depth) => directory_at_depth(&file.path, depth),
+        None => file.path.clone(),
+    });
+    if let Some(workdir) = workdir.as_deref() {
+        groups.retain(|path, _| path_exists(workdir, path));
+    }
+
+    let result = rank_hotspots(groups, limit, min_lines);
+
+    if json_output {
+        match serde_json::to_string(&result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize hotspots: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_hotspots("Most AI-authored lines", &result.by_ai_lines);
+        println!();
+        print_hotspots(
+            &format!("Highest AI share (at least {} added lines)", min_lines),
+            &result.by_ai_share,
+        );
+    }
+}
+
+fn require_value<'a>(args: &'a [String], i: usize, flag: &str) -> &'a str {
+    match args.get(i + 1) {
+        Some(value) => value,
+        None => {
+            eprintln!("Error: {} requires a value", flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> T {
+    match value.parse() {
+        Ok(n) => n,
+        Err(_) => {
+            eprintln!(
+                "Error: {} expects a non-negative number, got '{}'",
+                flag, value
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_top_help() {
+    eprintln!(
+        "Usage: git-ai top [--since <time>] [-n <limit>] [--min-lines <n>] [--dirs] [--depth <n>] [--json]"
+    );
+}
+
+/// `src/api/handlers/user.rs` at depth 2 -> `src/api`; root-level files map to `.`
+fn directory_at_depth(path: &str, depth: usize) -> String {
+    let components: Vec<&str> = path.split('/').collect();
+    let dirs = &components[..components.len() - 1];
+    if dirs.is_empty() {
+        return ".".to_string();
+    }
+    dirs[..depth.clamp(1, dirs.len())].join("/")
+}
+
+fn path_exists(workdir: &Path, path: &str) -> bool {
+    path == "." || workdir.join(path).exists()
+}
+
+fn rank_hotspots(
+    groups: BTreeMap<String, ReportBucket>,
+    limit: usize,
+    min_lines: u32,
+) -> TopResult {
+    let mut hotspots: Vec<Hotspot> = groups
+        .into_iter()
+        .filter(|(_, bucket)| bucket.ai_lines > 0)
+        .map(|(path, bucket)| Hotspot {
+            ai_share: bucket.ai_share(),
+            path,
+            commits: bucket.commits,
+            added_lines: bucket.added_lines,
+            ai_lines: bucket.ai_lines,
+        })
+        .collect();
+
+    let mut by_ai_share: Vec<Hotspot> = hotspots
+        .iter()
+        .filter(|h| h.added_lines >= min_lines)
+        .cloned()
+        .collect();
+    by_ai_share.sort_by(|a, b| {
+        b.ai_share
+            .total_cmp(&a.ai_share)
+            .then(b.ai_lines.cmp(&a.ai_lines))
+            .then(a.path.cmp(&b.path))
+    });
+    by_ai_share.truncate(limit);
+
+    hotspots.sort_by(|a, b| b.ai_lines.cmp(&a.ai_lines).then(a.path.cmp(&b.path)));
+    hotspots.truncate(limit);
+
+    TopResult {
+        by_ai_lines: hotspots,
+        by_ai_share,
+    }
+}
+
+fn print_hotspots(title: &str, hotspots: &[Hotspot]) {
+    println!(
+        "{:<60} {:>8} {:>8} {:>8} {:>6}",
+        title, "commits", "added", "ai", "ai%"
+    );
+    if hotspots.is_empty() {
+        println!("  (none)");
+    }
+    for hotspot in hotspots {
+        println!(
+            "{:<60} {:>8} {:>8} {:>8} {:>5.1}%",
+            hotspot.path,
+            hotspot.commits,
+            hotspot.added_lines,
+            hotspot.ai_lines,
+            hotspot.ai_share * 100.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(added_lines: u32, ai_lines: u32) -> ReportBucket {
+        ReportBucket {
+            commits: 1,
+            added_lines,
+            ai_lines,
+            human_lines: added_lines - ai_lines,
+        }
+    }
+
+    #[test]
+    fn test_directory_at_depth() {
+        assert_eq!(directory_at_depth("src/api/handlers/user.rs", 1), "src");
+        assert_eq!(directory_at_depth("src/api/handlers/user.rs", 2), "src/api");
+        assert_eq!(directory_at_depth("src/lib.rs", 3), "src");
+        assert_eq!(directory_at_depth("README.md", 1), ".");
+    }
+
+    #[test]
+    fn test_rank_hotspots() {
+        let groups = BTreeMap::from([
+            ("big.rs".to_string(), bucket(200, 100)),
+            ("tiny.rs".to_string(), bucket(2, 2)),
+            ("generated.rs".to_string(), bucket(40, 38)),
+            ("human.rs".to_string(), bucket(50, 0)),
+        ]);
+
+        let result = rank_hotspots(groups, 2, 10);
+
+        let by_lines: Vec<&str> = result.by_ai_lines.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(by_lines, vec!["big.rs", "generated.rs"]);
+        let by_share: Vec<&str> = result.by_ai_share.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(by_share, vec!["generated.rs", "big.rs"]);
+    }
+}
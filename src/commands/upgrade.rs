@@ -681,7 +681,7 @@ fn print_cached_notice(cache: &UpdateCache) {
 
 pub fn maybe_schedule_background_update_check() {
     let config = config::Config::get();
-    if config.version_checks_disabled() {
+    if config.version_checks_disabled() || crate::network::is_disabled() {
         return;
     }
 
@@ -725,7 +725,7 @@ fn spawn_background_upgrade_process() -> bool {
     }
 }
 
-fn is_newer_version(latest: &str, current: &str) -> bool {
+pub fn is_newer_version(latest: &str, current: &str) -> bool {
     let parse_version =
         |v: &str| -> Vec<u32> { v.split('.').filter_map(|s| s.parse::<u32>().ok()).collect() };
 
@@ -0,0 +1,327 @@
+use crate::authorship::authorship_log_serialization::NoteProvenance;
+use crate::ci::provenance::{
+    SignatureKind, note_introduced_by, verify_git_signed_commit, verify_sigstore_commit,
+};
+use crate::config::{Config, ProvenanceIdentity};
+use crate::error::GitAiError;
+use crate::git::find_repository;
+use crate::git::refs::{get_authorship, show_authorship_note};
+use crate::git::repository::{CommitRange, Repository};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceStatus {
+    /// Note was written by CI, in a notes commit signed by a trusted identity
+    Trusted,
+    /// Note doesn't record the CI run that wrote it
+    NoProvenance,
+    /// The notes commit that wrote the note isn't signed
+    Unsigned,
+    /// The notes commit is signed, but not by a trusted identity
+    Untrusted,
+    /// Commit has no authorship note, so there is no attribution to vouch for
+    NoNote,
+}
+
+impl ProvenanceStatus {
+    fn passes(&self) -> bool {
+        matches!(self, ProvenanceStatus::Trusted | ProvenanceStatus::NoNote)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitProvenance {
+    pub sha: String,
+    pub summary: String,
+    pub status: ProvenanceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<NoteProvenance>,
+    /// The notes commit that wrote the commit's current note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes_commit: Option<String>,
+    /// How that notes commit is signed ("ssh", "gpg" or "sigstore")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<&'static str>,
+    /// The identity that verified, or why verification failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProvenanceReport {
+    pub spec: String,
+    pub total: usize,
+    pub failed: usize,
+    pub commits: Vec<CommitProvenance>,
+}
+
+impl ProvenanceReport {
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+pub fn handle_verify(args: &[String]) {
+    let mut provenance = false;
+    let mut json_output = false;
+    let mut issuer: Option<String> = None;
+    let mut subject: Option<String> = None;
+    let mut spec: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--provenance" => {
+                provenance = true;
+                i += 1;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            "--issuer" | "--subject" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: {} requires a value", args[i]);
+                    std::process::exit(1);
+                }
+                if args[i] == "--issuer" {
+                    issuer = Some(args[i + 1].clone());
+                } else {
+                    subject = Some(args[i + 1].clone());
+                }
+                i += 2;
+            }
+            other if other.starts_with('-') => {
+                eprintln!("Error: unknown verify argument: {}", other);
+                print_verify_help();
+                std::process::exit(1);
+            }
+            other => {
+                if spec.is_some() {
+                    eprintln!("Error: verify takes a single <commit> or <base>..<head>");
+                    std::process::exit(1);
+                }
+                spec = Some(other.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    if !provenance {
+        print_verify_help();
+        std::process::exit(1);
+    }
+
+    let mut identities = Config::get().trusted_provenance_identities().to_vec();
+    match (issuer, subject) {
+        (Some(issuer), Some(subject)) => identities.push(ProvenanceIdentity { issuer, subject }),
+        (None, None) => {}
+        _ => {
+            eprintln!("Error: --issuer and --subject must be given together");
+            std::process::exit(1);
+        }
+    }
+
+    let repo = match find_repository(&Vec::<String>::new()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("Failed to find repository: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let spec = spec.unwrap_or_else(|| "HEAD".to_string());
+    let report = match verify_provenance(&repo, &spec, &identities) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Failed to verify provenance: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if json_output {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize provenance report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print_provenance_report(&report);
+    }
+
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
+
+fn print_verify_help() {
+    eprintln!("Usage: git-ai verify --provenance [<commit>|<base>..<head>] [--json]");
+    eprintln!("                     [--issuer <url> --subject <regex>]");
+    eprintln!();
+    eprintln!(
+        "  --provenance       Verify notes were written by CI and signed by a trusted identity"
+    );
+    eprintln!("  --issuer <url>     OIDC issuer of a trusted Sigstore identity, with --subject");
+    eprintln!("  --subject <regex>  Certificate subject the identity must match in full");
+    eprintln!("  --json             Output in JSON format");
+    eprintln!();
+    eprintln!("Sigstore signatures are checked with gitsign against --issuer/--subject and the");
+    eprintln!("trusted_provenance_identities config. SSH and GPG signatures are checked against");
+    eprintln!("the signers git trusts (gpg.ssh.allowedSignersFile or the GPG keyring).");
+}
+
+/// Check that the note on each commit in `spec` (a commit or `<base>..<head>`) records
+/// the CI run that wrote it, and was written by a notes commit signed by a trusted
+/// identity. Commits without a note pass: there is no attribution to vouch for.
+pub fn verify_provenance(
+    repo: &Repository,
+    spec: &str,
+    identities: &[ProvenanceIdentity],
+) -> Result<ProvenanceReport, GitAiError> {
+    let commit_shas = match spec.split_once("..") {
+        Some((base, head)) if !base.is_empty() && !head.is_empty() => {
+            let range =
+                CommitRange::new_infer_refname(repo, base.to_string(), head.to_string(), None)?;
+            if range.start_oid == range.end_oid {
+                Vec::new()
+            } else {
+                range.all_commits()
+            }
+        }
+        Some(_) => {
+            return Err(GitAiError::Generic(
+                "Invalid commit range format. Expected <base>..<head>".to_string(),
+            ));
+        }
+        None => vec![repo.revparse_single(spec)?.id()],
+    };
+
+    // Notes commits often carry the notes of many commits; verify each one once
+    let mut signatures: HashMap<String, (Option<SignatureKind>, Result<String, String>)> =
+        HashMap::new();
+    let mut commits = Vec::with_capacity(commit_shas.len());
+    for sha in commit_shas {
+        let summary = repo.find_commit(sha.clone())?.summary().unwrap_or_default();
+        let mut commit = CommitProvenance {
+            sha,
+            summary,
+            status: ProvenanceStatus::NoNote,
+            provenance: None,
+            notes_commit: None,
+            signature: None,
+            detail: None,
+        };
+        if show_authorship_note(repo, &commit.sha).is_none() {
+            commits.push(commit);
+            continue;
+        }
+        commit.provenance = get_authorship(repo, &commit.sha)
+            .and_then(|log| log.metadata.provenance)
+            .map(|provenance| *provenance);
+        if commit.provenance.is_none() {
+            commit.status = ProvenanceStatus::NoProvenance;
+            commits.push(commit);
+            continue;
+        }
+
+        let Some(notes_commit) = note_introduced_by(repo, &commit.sha)? else {
+            commit.status = ProvenanceStatus::Unsigned;
+            commits.push(commit);
+            continue;
+        };
+        let (kind, verified) = signatures
+            .entry(notes_commit.clone())
+            .or_insert_with(|| verify_notes_commit(repo, &notes_commit, identities));
+        commit.notes_commit = Some(notes_commit);
+        commit.signature = kind.map(|kind| kind.name());
+        match (kind, verified) {
+            (None, _) => commit.status = ProvenanceStatus::Unsigned,
+            (Some(_), Ok(identity)) => {
+                commit.status = ProvenanceStatus::Trusted;
+                commit.detail = Some(identity.clone());
+            }
+            (Some(_), Err(reason)) => {
+                commit.status = ProvenanceStatus::Untrusted;
+                commit.detail = Some(reason.clone());
+            }
+        }
+        commits.push(commit);
+    }
+
+    let failed = commits.iter().filter(|c| !c.status.passes()).count();
+    Ok(ProvenanceReport {
+        spec: spec.to_string(),
+        total: commits.len(),
+        failed,
+        commits,
+    })
+}
+
+/// A notes commit's signature kind, and who it verified as or why it didn't
+fn verify_notes_commit(
+    repo: &Repository,
+    notes_commit: &str,
+    identities: &[ProvenanceIdentity],
+) -> (Option<SignatureKind>, Result<String, String>) {
+    let kind = repo
+        .git(&["cat-file", "commit", notes_commit])
+        .ok()
+        .and_then(|raw| SignatureKind::of_commit(&raw));
+    let verified = match kind {
+        None => Err("not signed".to_string()),
+        Some(SignatureKind::Sigstore) => verify_sigstore_commit(repo, notes_commit, identities)
+            .map(|identity| format!("{} {}", identity.issuer, identity.subject)),
+        Some(SignatureKind::Ssh | SignatureKind::Gpg) => {
+            verify_git_signed_commit(repo, notes_commit).map(|_| "trusted by git".to_string())
+        }
+    };
+    (kind, verified)
+}
+
+fn print_provenance_report(report: &ProvenanceReport) {
+    if report.total == 0 {
+        println!("No commits in {}", report.spec);
+        return;
+    }
+
+    for commit in &report.commits {
+        let label = match commit.status {
+            ProvenanceStatus::Trusted | ProvenanceStatus::NoNote => continue,
+            ProvenanceStatus::NoProvenance => "NO PROVENANCE",
+            ProvenanceStatus::Unsigned => "UNSIGNED",
+            ProvenanceStatus::Untrusted => "UNTRUSTED",
+        };
+        let short_sha = &commit.sha[..commit.sha.len().min(7)];
+        match &commit.detail {
+            Some(detail) => println!(
+                "{:<13} {} {} ({})",
+                label, short_sha, commit.summary, detail
+            ),
+            None => println!("{:<13} {} {}", label, short_sha, commit.summary),
+        }
+    }
+
+    let noted = report
+        .commits
+        .iter()
+        .filter(|c| c.status != ProvenanceStatus::NoNote)
+        .count();
+    if report.passed() {
+        println!(
+            "All {} note(s) in {} were written and signed by trusted automation",
+            noted, report.spec
+        );
+    } else {
+        println!(
+            "{} of {} note(s) in {} lack trusted provenance",
+            report.failed, noted, report.spec
+        );
+        println!(
+            "Trusted notes are rewritten by git-ai ci with GIT_AI_CI_SIGN set; check the signing identity is in trusted_provenance_identities"
+        );
+    }
+}
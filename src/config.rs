@@ -1,22 +1,46 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
 use glob::Pattern;
 use serde::{Deserialize, Serialize};
 
+use crate::authorship::attribution_tracker::AttributionGranularity;
+use crate::authorship::commit_class::{DEFAULT_AI_ASSISTED_THRESHOLD, DEFAULT_FULLY_AI_THRESHOLD};
 use crate::feature_flags::FeatureFlags;
 use crate::git::repository::Repository;
 use crate::mdm::utils::home_dir;
 
-#[cfg(any(test, feature = "test-support"))]
-use std::sync::RwLock;
-
 /// Default API base URL for comparison
 pub const DEFAULT_API_BASE_URL: &str = "https://usegitai.com";
 
+/// Default time git hooks wait on notes network operations before deferring them
+pub const DEFAULT_HOOK_NETWORK_BUDGET_MS: u64 = 2000;
+
+/// Default pattern for extracting work-item IDs (e.g. Jira keys like `PROJ-123`) from branch names
+pub const DEFAULT_WORK_ITEM_PATTERN: &str = r"\b[A-Z][A-Z0-9]{1,9}-[0-9]+\b";
+
+/// Repository-level config file, at the root of the working tree
+pub const REPO_CONFIG_FILE_NAME: &str = ".git-ai.toml";
+
+/// Default globs classifying files as test code in reports
+pub const DEFAULT_TEST_PATH_PATTERNS: &[&str] = &[
+    "**/test/**",
+    "**/tests/**",
+    "**/__tests__/**",
+    "**/spec/**",
+    "**/*_test.*",
+    "**/test_*.py",
+    "**/*.test.*",
+    "**/*.spec.*",
+    "**/*Test.java",
+    "**/*Tests.cs",
+];
+
 /// Prompt storage mode enum for type-safe handling
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PromptStorageMode {
@@ -57,6 +81,14 @@ impl std::fmt::Display for PromptStorageMode {
     }
 }
 
+/// A CI identity whose Sigstore signatures vouch for notes: the certificate's OIDC issuer
+/// and a regex its subject (e.g. a workflow identity) must match in full
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceIdentity {
+    pub issuer: String,
+    pub subject: String,
+}
+
 pub struct Config {
     git_path: String,
     exclude_prompts_in_repositories: Vec<Pattern>,
@@ -74,6 +106,27 @@ pub struct Config {
     default_prompt_storage: Option<String>,
     api_key: Option<String>,
     quiet: bool,
+    work_item_pattern: String,
+    test_path_patterns: Vec<String>,
+    fully_ai_threshold: f64,
+    ai_assisted_threshold: f64,
+    hook_network_budget: Duration,
+    usage_telemetry: bool,
+    attribution_neutral_commits: Vec<String>,
+    neutral_whitespace_commits: bool,
+    preserve_whitespace_attribution: bool,
+    attribution_granularity: BTreeMap<String, AttributionGranularity>,
+    trusted_provenance_identities: Vec<ProvenanceIdentity>,
+    no_network: bool,
+    ca_bundle: Option<String>,
+    tls_insecure: bool,
+    ci_comment: bool,
+    ci_check_run: bool,
+    security_scan_command: Option<String>,
+    note_details_threshold: Option<u64>,
+    note_delta_interval: Option<u32>,
+    ignore_patterns: Vec<String>,
+    allowed_agents: Vec<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -140,9 +193,111 @@ pub struct FileConfig {
     pub api_key: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub quiet: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_item_pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_path_patterns: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fully_ai_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_assisted_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_network_budget_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_telemetry: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution_neutral_commits: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub neutral_whitespace_commits: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preserve_whitespace_attribution: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution_granularity: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_provenance_identities: Option<Vec<ProvenanceIdentity>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_network: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_insecure: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_comment: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_check_run: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_scan_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_details_threshold: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_delta_interval: Option<u32>,
 }
 
-static CONFIG: OnceLock<Config> = OnceLock::new();
+/// `.git-ai.toml`: settings committed with a repository so everyone working on it
+/// shares them. They override `~/.git-ai/config.json`; environment variables still
+/// override both.
+///
+/// ```toml
+/// ignore = ["*.lock", "vendor/**"]
+///
+/// [agents]
+/// allow = ["claude", "cursor"]
+///
+/// [policy]
+/// fully_ai_threshold = 90
+/// attribution_neutral_commits = ["4f1c2d9"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct RepoFileConfig {
+    /// Files left out of stats and compare totals
+    #[serde(default)]
+    pub ignore: Option<Vec<String>>,
+    #[serde(default)]
+    pub agents: Option<RepoAgentsConfig>,
+    #[serde(default)]
+    pub policy: Option<RepoPolicyConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RepoAgentsConfig {
+    /// Agents (by tool name) whose checkpoints are recorded; every agent when unset
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+}
+
+/// The attribution and classification settings of [`FileConfig`] a repository can pin
+#[derive(Debug, Default, Deserialize)]
+pub struct RepoPolicyConfig {
+    #[serde(default)]
+    pub work_item_pattern: Option<String>,
+    #[serde(default)]
+    pub test_path_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    pub fully_ai_threshold: Option<f64>,
+    #[serde(default)]
+    pub ai_assisted_threshold: Option<f64>,
+    #[serde(default)]
+    pub attribution_neutral_commits: Option<Vec<String>>,
+    #[serde(default)]
+    pub neutral_whitespace_commits: Option<bool>,
+    #[serde(default)]
+    pub preserve_whitespace_attribution: Option<bool>,
+    #[serde(default)]
+    pub attribution_granularity: Option<BTreeMap<String, String>>,
+}
+
+/// Swapped out whole by `Config::reload_if_changed`, so callers holding the previous
+/// config keep a consistent one until they drop it
+static CONFIG: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+/// Mtime of `~/.git-ai/config.json` as of the last (re)load, `None` when it didn't exist
+static CONFIG_FILE_MTIME: Mutex<Option<Option<SystemTime>>> = Mutex::new(None);
+
+/// A repository's config with the mtime of its `.git-ai.toml` when it was loaded
+type LoadedRepoConfig = (Option<SystemTime>, Arc<Config>);
+
+/// Configs of the repositories `Config::for_repo` was asked about, by working tree
+static REPO_CONFIGS: Mutex<BTreeMap<PathBuf, LoadedRepoConfig>> = Mutex::new(BTreeMap::new());
 
 #[cfg(any(test, feature = "test-support"))]
 static TEST_FEATURE_FLAGS_OVERRIDE: RwLock<Option<FeatureFlags>> = RwLock::new(None);
@@ -162,6 +317,20 @@ pub struct ConfigPatch {
     pub disable_auto_updates: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub prompt_storage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_network_budget_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution_neutral_commits: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub neutral_whitespace_commits: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preserve_whitespace_attribution: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution_granularity: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_details_threshold: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_delta_interval: Option<u32>,
 }
 
 impl Config {
@@ -169,12 +338,55 @@ impl Config {
     /// Safe to call multiple times; subsequent calls are no-ops.
     #[allow(dead_code)]
     pub fn init() {
-        let _ = CONFIG.get_or_init(build_config);
+        let _ = Config::get();
     }
 
     /// Access the global configuration. Lazily initializes if not already initialized.
-    pub fn get() -> &'static Config {
-        CONFIG.get_or_init(build_config)
+    /// Settings a repository's `.git-ai.toml` can pin come from [`Config::for_repo`].
+    pub fn get() -> Arc<Config> {
+        if let Some(config) = CONFIG.read().unwrap().as_ref() {
+            return Arc::clone(config);
+        }
+        let mut config = CONFIG.write().unwrap();
+        Arc::clone(config.get_or_insert_with(|| {
+            *CONFIG_FILE_MTIME.lock().unwrap() = Some(config_file_mtime());
+            Arc::new(build_config(None))
+        }))
+    }
+
+    /// The configuration for `repo`: the global one with the `.git-ai.toml` at the root
+    /// of its working tree on top. Cached per repository and rebuilt when the file changes.
+    pub fn for_repo(repo: &Repository) -> Arc<Config> {
+        load_repo_config(repo).0
+    }
+
+    /// Rebuild the global configuration if `~/.git-ai/config.json` changed since it was
+    /// last loaded. Long-running processes call this periodically; short-lived commands
+    /// never need to. A file that no longer parses is reported and ignored, keeping the
+    /// previous config rather than silently falling back to defaults mid-edit. Returns
+    /// whether a new config was installed.
+    pub fn reload_if_changed() -> bool {
+        let _ = Config::get();
+        let mtime = config_file_mtime();
+        let mut loaded_mtime = CONFIG_FILE_MTIME.lock().unwrap();
+        if *loaded_mtime == Some(mtime) {
+            return false;
+        }
+        *loaded_mtime = Some(mtime);
+
+        if let Err(e) = load_file_config_public() {
+            eprintln!("Warning: keeping previous git-ai config: {}", e);
+            return false;
+        }
+        *CONFIG.write().unwrap() = Some(Arc::new(build_config(None)));
+        // Repositories' configs build on the global one
+        REPO_CONFIGS.lock().unwrap().clear();
+        true
+    }
+
+    /// Like [`Config::reload_if_changed`], for `repo`'s `.git-ai.toml`
+    pub fn reload_repo_if_changed(repo: &Repository) -> bool {
+        load_repo_config(repo).1
     }
 
     /// Returns the command to invoke git.
@@ -376,6 +588,134 @@ impl Config {
         self.quiet
     }
 
+    /// Returns the regex used to extract work-item IDs from branch names.
+    /// An empty pattern disables branch name parsing.
+    pub fn work_item_pattern(&self) -> &str {
+        &self.work_item_pattern
+    }
+
+    /// Returns the globs used to classify files as test code (vs production code)
+    pub fn test_path_patterns(&self) -> &[String] {
+        &self.test_path_patterns
+    }
+
+    /// Minimum percent of AI-attributed added lines for a commit to be classified fully-AI
+    pub fn fully_ai_threshold(&self) -> f64 {
+        self.fully_ai_threshold
+    }
+
+    /// Minimum percent of AI-attributed added lines for a commit to be classified AI-assisted
+    pub fn ai_assisted_threshold(&self) -> f64 {
+        self.ai_assisted_threshold
+    }
+
+    /// How long git hooks wait on notes fetch/push before handing it to a background process
+    pub fn hook_network_budget(&self) -> Duration {
+        self.hook_network_budget
+    }
+
+    /// Commits (full or abbreviated SHAs) that neither author nor take authorship of lines
+    pub fn attribution_neutral_commits(&self) -> &[String] {
+        &self.attribution_neutral_commits
+    }
+
+    /// Whether commits that only change whitespace are attribution-neutral
+    pub fn neutral_whitespace_commits(&self) -> bool {
+        self.neutral_whitespace_commits
+    }
+
+    /// Whether lines an edit only reindents or re-wraps keep their previous author
+    pub fn preserve_whitespace_attribution(&self) -> bool {
+        self.preserve_whitespace_attribution
+    }
+
+    /// Attribution granularity configured per file extension
+    pub fn attribution_granularity(&self) -> &BTreeMap<String, AttributionGranularity> {
+        &self.attribution_granularity
+    }
+
+    /// Granularity to attribute edits to `path` in, by its extension; token-level when
+    /// the extension isn't configured
+    pub fn attribution_granularity_for(&self, path: &str) -> AttributionGranularity {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.attribution_granularity.get(&ext.to_lowercase()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// CI identities `git-ai verify --provenance` accepts Sigstore signatures from
+    pub fn trusted_provenance_identities(&self) -> &[ProvenanceIdentity] {
+        &self.trusted_provenance_identities
+    }
+
+    /// Whether every HTTP request and remote git operation should fail instead of
+    /// reaching the network
+    pub fn no_network(&self) -> bool {
+        self.no_network
+    }
+
+    /// PEM file of extra CA certificates to trust for forge HTTPS, e.g. an internal CA
+    pub fn ca_bundle(&self) -> Option<&str> {
+        self.ca_bundle.as_deref()
+    }
+
+    /// Whether forge HTTPS certificates should be accepted without verification
+    pub fn tls_insecure(&self) -> bool {
+        self.tls_insecure
+    }
+
+    /// Whether `git-ai ci` should comment the attribution summary on the merge request
+    /// it rewrote
+    pub fn ci_comment(&self) -> bool {
+        self.ci_comment
+    }
+
+    /// Whether `git-ai ci github` should add a check run annotating the AI-authored lines
+    /// of the merge commit it rewrote
+    pub fn ci_check_run(&self) -> bool {
+        self.ci_check_run
+    }
+
+    /// Shell command that scans newly attributed AI hunks and returns finding tags to
+    /// store in the note (semgrep, a custom script, ...)
+    pub fn security_scan_command(&self) -> Option<&str> {
+        self.security_scan_command.as_deref()
+    }
+
+    /// Size in bytes above which a note is stored as a summary on `refs/notes/ai`, with
+    /// its line maps on `refs/notes/ai-details`; None keeps every note whole
+    pub fn note_details_threshold(&self) -> Option<u64> {
+        self.note_details_threshold
+    }
+
+    /// Every how many notes along a first-parent chain one is written in full; the rest
+    /// leave out what their parent's note already has. None writes every note in full.
+    pub fn note_delta_interval(&self) -> Option<u32> {
+        self.note_delta_interval
+    }
+
+    /// Globs of files `.git-ai.toml` leaves out of stats and compare totals
+    pub fn ignore_patterns(&self) -> &[String] {
+        &self.ignore_patterns
+    }
+
+    /// Whether checkpoints from `tool` are recorded: `.git-ai.toml` can limit a
+    /// repository to some agents, and edits by the others count as human
+    pub fn is_agent_allowed(&self, tool: &str) -> bool {
+        self.allowed_agents.is_empty()
+            || self
+                .allowed_agents
+                .iter()
+                .any(|agent| agent.eq_ignore_ascii_case(tool))
+    }
+
+    /// Whether the user opted in to anonymous usage telemetry (`git-ai telemetry on`)
+    pub fn usage_telemetry_enabled(&self) -> bool {
+        self.usage_telemetry
+    }
+
     /// Override feature flags for testing purposes.
     /// Only available when the `test-support` feature is enabled or in test mode.
     /// Must be `pub` to work with integration tests in the `tests/` directory.
@@ -419,8 +759,42 @@ impl Config {
     }
 }
 
-fn build_config() -> Config {
+/// `repo`'s cached config, rebuilt first if its `.git-ai.toml` changed, and whether it
+/// was rebuilt. A file that no longer parses keeps the previous config, as on reload.
+fn load_repo_config(repo: &Repository) -> (Arc<Config>, bool) {
+    let Ok(workdir) = repo.workdir() else {
+        return (Config::get(), false);
+    };
+    let mtime = repo_config_mtime(&workdir);
+    let mut configs = REPO_CONFIGS.lock().unwrap();
+    let loaded = configs.get_mut(&workdir);
+    let reload = loaded.is_some();
+    if let Some((loaded_mtime, config)) = loaded {
+        if *loaded_mtime == mtime {
+            return (Arc::clone(config), false);
+        }
+        *loaded_mtime = mtime;
+        if let Err(e) = load_repo_file_config(&workdir) {
+            eprintln!("Warning: keeping previous git-ai config: {}", e);
+            return (Arc::clone(config), false);
+        }
+    }
+    let config = Arc::new(build_config(Some(&workdir)));
+    configs.insert(workdir, (mtime, Arc::clone(&config)));
+    (config, reload)
+}
+
+/// Build the config from `~/.git-ai/config.json` and the environment, with the
+/// `.git-ai.toml` of the working tree at `workdir` on top if given
+fn build_config(workdir: Option<&Path>) -> Config {
     let file_cfg = load_file_config();
+    let repo_cfg = workdir.and_then(|workdir| {
+        load_repo_file_config(workdir).unwrap_or_else(|e| {
+            eprintln!("Warning: {}", e);
+            None
+        })
+    });
+    let policy = repo_cfg.as_ref().and_then(|c| c.policy.as_ref());
     let exclude_prompts_in_repositories = file_cfg
         .as_ref()
         .and_then(|c| c.exclude_prompts_in_repositories.clone())
@@ -573,6 +947,159 @@ fn build_config() -> Config {
     // Get quiet setting (defaults to false)
     let quiet = file_cfg.as_ref().and_then(|c| c.quiet).unwrap_or(false);
 
+    // Get work item pattern (defaults to Jira-style keys, empty disables)
+    let work_item_pattern = policy
+        .and_then(|p| p.work_item_pattern.clone())
+        .or_else(|| file_cfg.as_ref().and_then(|c| c.work_item_pattern.clone()))
+        .and_then(|pattern| {
+            if regex::Regex::new(&pattern).is_ok() {
+                Some(pattern)
+            } else {
+                eprintln!(
+                    "Warning: Invalid work_item_pattern '{}', using default",
+                    pattern
+                );
+                None
+            }
+        })
+        .unwrap_or_else(|| DEFAULT_WORK_ITEM_PATTERN.to_string());
+
+    // Get test path classifiers (defaults to common test directory/file conventions)
+    let test_path_patterns = policy
+        .and_then(|p| p.test_path_patterns.clone())
+        .or_else(|| file_cfg.as_ref().and_then(|c| c.test_path_patterns.clone()))
+        .unwrap_or_else(default_test_path_patterns);
+
+    // Get commit classification thresholds (percentages, 0-100)
+    let fully_ai_threshold = parse_percent_threshold(
+        "fully_ai_threshold",
+        policy
+            .and_then(|p| p.fully_ai_threshold)
+            .or_else(|| file_cfg.as_ref().and_then(|c| c.fully_ai_threshold)),
+        DEFAULT_FULLY_AI_THRESHOLD,
+    );
+    let ai_assisted_threshold = parse_percent_threshold(
+        "ai_assisted_threshold",
+        policy
+            .and_then(|p| p.ai_assisted_threshold)
+            .or_else(|| file_cfg.as_ref().and_then(|c| c.ai_assisted_threshold)),
+        DEFAULT_AI_ASSISTED_THRESHOLD,
+    );
+
+    // Get hook network budget (defaults to 2 seconds)
+    let hook_network_budget = Duration::from_millis(
+        file_cfg
+            .as_ref()
+            .and_then(|c| c.hook_network_budget_ms)
+            .unwrap_or(DEFAULT_HOOK_NETWORK_BUDGET_MS),
+    );
+
+    // Usage telemetry is strictly opt-in
+    let usage_telemetry = file_cfg
+        .as_ref()
+        .and_then(|c| c.usage_telemetry)
+        .unwrap_or(false);
+
+    // Formatting-only commits to skip over when attributing lines
+    let attribution_neutral_commits = policy
+        .and_then(|p| p.attribution_neutral_commits.clone())
+        .or_else(|| {
+            file_cfg
+                .as_ref()
+                .and_then(|c| c.attribution_neutral_commits.clone())
+        })
+        .unwrap_or_default();
+    let neutral_whitespace_commits = policy
+        .and_then(|p| p.neutral_whitespace_commits)
+        .or_else(|| file_cfg.as_ref().and_then(|c| c.neutral_whitespace_commits))
+        .unwrap_or(false);
+    let preserve_whitespace_attribution = policy
+        .and_then(|p| p.preserve_whitespace_attribution)
+        .or_else(|| {
+            file_cfg
+                .as_ref()
+                .and_then(|c| c.preserve_whitespace_attribution)
+        })
+        .unwrap_or(false);
+    let attribution_granularity = policy
+        .and_then(|p| p.attribution_granularity.as_ref())
+        .or_else(|| {
+            file_cfg
+                .as_ref()
+                .and_then(|c| c.attribution_granularity.as_ref())
+        })
+        .map(parse_attribution_granularity)
+        .unwrap_or_default();
+    let trusted_provenance_identities = file_cfg
+        .as_ref()
+        .and_then(|c| c.trusted_provenance_identities.clone())
+        .unwrap_or_default();
+    let no_network = file_cfg
+        .as_ref()
+        .and_then(|c| c.no_network)
+        .unwrap_or(false);
+
+    // TLS overrides for self-hosted forges (env vars take precedence)
+    let ca_bundle = env::var("GIT_AI_CA_BUNDLE")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .or_else(|| {
+            file_cfg
+                .as_ref()
+                .and_then(|c| c.ca_bundle.clone())
+                .filter(|s| !s.is_empty())
+        });
+    let tls_insecure = match env::var("GIT_AI_TLS_INSECURE") {
+        Ok(value) if !value.is_empty() => value != "0" && value != "false",
+        _ => file_cfg
+            .as_ref()
+            .and_then(|c| c.tls_insecure)
+            .unwrap_or(false),
+    };
+    // Usually turned on per pipeline, so the env var takes precedence
+    let ci_comment = match env::var("GIT_AI_CI_COMMENT") {
+        Ok(value) if !value.is_empty() => value != "0" && value != "false",
+        _ => file_cfg
+            .as_ref()
+            .and_then(|c| c.ci_comment)
+            .unwrap_or(false),
+    };
+    let ci_check_run = match env::var("GIT_AI_CI_CHECK_RUN") {
+        Ok(value) if !value.is_empty() => value != "0" && value != "false",
+        _ => file_cfg
+            .as_ref()
+            .and_then(|c| c.ci_check_run)
+            .unwrap_or(false),
+    };
+    let security_scan_command = env::var("GIT_AI_SECURITY_SCAN_COMMAND")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| {
+            file_cfg
+                .as_ref()
+                .and_then(|c| c.security_scan_command.clone())
+                .filter(|s| !s.trim().is_empty())
+        });
+    let note_details_threshold = match env::var("GIT_AI_NOTE_DETAILS_THRESHOLD") {
+        Ok(value) if !value.trim().is_empty() => value.trim().parse().ok(),
+        _ => file_cfg.as_ref().and_then(|c| c.note_details_threshold),
+    };
+    let note_delta_interval = match env::var("GIT_AI_NOTE_DELTA_INTERVAL") {
+        Ok(value) if !value.trim().is_empty() => value.trim().parse().ok(),
+        _ => file_cfg.as_ref().and_then(|c| c.note_delta_interval),
+    };
+
+    // Repository-only settings
+    let ignore_patterns = repo_cfg
+        .as_ref()
+        .and_then(|c| c.ignore.clone())
+        .unwrap_or_default();
+    let allowed_agents = repo_cfg
+        .as_ref()
+        .and_then(|c| c.agents.as_ref())
+        .and_then(|a| a.allow.clone())
+        .unwrap_or_default();
+
     #[cfg(any(test, feature = "test-support"))]
     {
         let mut config = Config {
@@ -592,6 +1119,27 @@ fn build_config() -> Config {
             default_prompt_storage,
             api_key,
             quiet,
+            work_item_pattern,
+            test_path_patterns,
+            fully_ai_threshold,
+            ai_assisted_threshold,
+            hook_network_budget,
+            usage_telemetry,
+            attribution_neutral_commits,
+            neutral_whitespace_commits,
+            preserve_whitespace_attribution,
+            attribution_granularity,
+            trusted_provenance_identities,
+            no_network,
+            ca_bundle,
+            tls_insecure,
+            ci_comment,
+            ci_check_run,
+            security_scan_command,
+            note_details_threshold,
+            note_delta_interval,
+            ignore_patterns,
+            allowed_agents,
         };
         apply_test_config_patch(&mut config);
         config
@@ -615,9 +1163,74 @@ fn build_config() -> Config {
         default_prompt_storage,
         api_key,
         quiet,
+        work_item_pattern,
+        test_path_patterns,
+        fully_ai_threshold,
+        ai_assisted_threshold,
+        hook_network_budget,
+        usage_telemetry,
+        attribution_neutral_commits,
+        neutral_whitespace_commits,
+        preserve_whitespace_attribution,
+        attribution_granularity,
+        trusted_provenance_identities,
+        no_network,
+        ca_bundle,
+        tls_insecure,
+        ci_comment,
+        ci_check_run,
+        security_scan_command,
+        note_details_threshold,
+        note_delta_interval,
+        ignore_patterns,
+        allowed_agents,
     }
 }
 
+fn parse_percent_threshold(key: &str, value: Option<f64>, default: f64) -> f64 {
+    match value {
+        Some(v) if (0.0..=100.0).contains(&v) => v,
+        Some(v) => {
+            eprintln!(
+                "Warning: {} must be between 0 and 100 (got {}), using default",
+                key, v
+            );
+            default
+        }
+        None => default,
+    }
+}
+
+fn default_test_path_patterns() -> Vec<String> {
+    DEFAULT_TEST_PATH_PATTERNS
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// Extension -> granularity, from keys like `md`, `.md` or `*.md`; invalid
+/// granularities are warned about and skipped
+fn parse_attribution_granularity(
+    raw: &BTreeMap<String, String>,
+) -> BTreeMap<String, AttributionGranularity> {
+    let mut granularity = BTreeMap::new();
+    for (extension, value) in raw {
+        let extension = extension.trim();
+        let extension = extension
+            .strip_prefix("*.")
+            .or_else(|| extension.strip_prefix('.'))
+            .unwrap_or(extension)
+            .to_lowercase();
+        match value.parse::<AttributionGranularity>() {
+            Ok(parsed) => {
+                granularity.insert(extension, parsed);
+            }
+            Err(e) => eprintln!("Warning: attribution_granularity.{}: {}", extension, e),
+        }
+    }
+    granularity
+}
+
 fn build_feature_flags(file_cfg: &Option<FileConfig>) -> FeatureFlags {
     let file_flags_value = file_cfg.as_ref().and_then(|c| c.feature_flags.as_ref());
 
@@ -681,10 +1294,39 @@ fn load_file_config() -> Option<FileConfig> {
     serde_json::from_slice::<FileConfig>(&data).ok()
 }
 
+fn config_file_mtime() -> Option<SystemTime> {
+    fs::metadata(config_file_path()?).ok()?.modified().ok()
+}
+
+fn repo_config_mtime(workdir: &Path) -> Option<SystemTime> {
+    fs::metadata(repo_config_path(workdir))
+        .ok()?
+        .modified()
+        .ok()
+}
+
 fn config_file_path() -> Option<PathBuf> {
     Some(home_dir().join(".git-ai").join("config.json"))
 }
 
+/// `.git-ai.toml` at the root of the working tree at `workdir`
+pub fn repo_config_path(workdir: &Path) -> PathBuf {
+    workdir.join(REPO_CONFIG_FILE_NAME)
+}
+
+/// Parse the `.git-ai.toml` of the working tree at `workdir`; `Ok(None)` when it has none
+pub fn load_repo_file_config(workdir: &Path) -> Result<Option<RepoFileConfig>, String> {
+    let path = repo_config_path(workdir);
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    toml::from_str(&data)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
 /// Public accessor for config file path
 #[allow(dead_code)]
 pub fn config_file_path_public() -> Option<PathBuf> {
@@ -831,6 +1473,27 @@ fn apply_test_config_patch(config: &mut Config) {
         if let Some(disable_auto_updates) = patch.disable_auto_updates {
             config.disable_auto_updates = disable_auto_updates;
         }
+        if let Some(budget_ms) = patch.hook_network_budget_ms {
+            config.hook_network_budget = Duration::from_millis(budget_ms);
+        }
+        if let Some(threshold) = patch.note_details_threshold {
+            config.note_details_threshold = Some(threshold);
+        }
+        if let Some(interval) = patch.note_delta_interval {
+            config.note_delta_interval = Some(interval);
+        }
+        if let Some(commits) = patch.attribution_neutral_commits {
+            config.attribution_neutral_commits = commits;
+        }
+        if let Some(neutral_whitespace_commits) = patch.neutral_whitespace_commits {
+            config.neutral_whitespace_commits = neutral_whitespace_commits;
+        }
+        if let Some(preserve) = patch.preserve_whitespace_attribution {
+            config.preserve_whitespace_attribution = preserve;
+        }
+        if let Some(granularity) = patch.attribution_granularity {
+            config.attribution_granularity = parse_attribution_granularity(&granularity);
+        }
         if let Some(prompt_storage) = patch.prompt_storage {
             // Validate the value
             if matches!(prompt_storage.as_str(), "default" | "notes" | "local") {
@@ -876,9 +1539,61 @@ mod tests {
             default_prompt_storage: None,
             api_key: None,
             quiet: false,
+            work_item_pattern: DEFAULT_WORK_ITEM_PATTERN.to_string(),
+            test_path_patterns: default_test_path_patterns(),
+            fully_ai_threshold: DEFAULT_FULLY_AI_THRESHOLD,
+            ai_assisted_threshold: DEFAULT_AI_ASSISTED_THRESHOLD,
+            hook_network_budget: Duration::from_millis(DEFAULT_HOOK_NETWORK_BUDGET_MS),
+            usage_telemetry: false,
+            attribution_neutral_commits: Vec::new(),
+            neutral_whitespace_commits: false,
+            preserve_whitespace_attribution: false,
+            attribution_granularity: BTreeMap::new(),
+            trusted_provenance_identities: Vec::new(),
+            no_network: false,
+            ca_bundle: None,
+            tls_insecure: false,
+            ci_comment: false,
+            ci_check_run: false,
+            security_scan_command: None,
+            note_details_threshold: None,
+            note_delta_interval: None,
+            ignore_patterns: Vec::new(),
+            allowed_agents: Vec::new(),
         }
     }
 
+    #[test]
+    fn test_attribution_granularity_by_extension() {
+        let mut config = create_test_config(vec![], vec![]);
+        config.attribution_granularity = parse_attribution_granularity(&BTreeMap::from([
+            ("md".to_string(), "word".to_string()),
+            ("*.YAML".to_string(), "line".to_string()),
+            (".rs".to_string(), "statement".to_string()),
+            ("txt".to_string(), "paragraph".to_string()),
+        ]));
+        assert_eq!(
+            config.attribution_granularity_for("docs/README.md"),
+            AttributionGranularity::Word
+        );
+        assert_eq!(
+            config.attribution_granularity_for("ci/deploy.yaml"),
+            AttributionGranularity::Line
+        );
+        assert_eq!(
+            config.attribution_granularity_for("src/Main.RS"),
+            AttributionGranularity::Statement
+        );
+        assert_eq!(
+            config.attribution_granularity_for("notes.txt"),
+            AttributionGranularity::Token
+        );
+        assert_eq!(
+            config.attribution_granularity_for("Makefile"),
+            AttributionGranularity::Token
+        );
+    }
+
     #[test]
     fn test_exclusion_takes_precedence_over_allow() {
         let config = create_test_config(
@@ -983,6 +1698,27 @@ mod tests {
             default_prompt_storage: None,
             api_key: None,
             quiet: false,
+            work_item_pattern: DEFAULT_WORK_ITEM_PATTERN.to_string(),
+            test_path_patterns: default_test_path_patterns(),
+            fully_ai_threshold: DEFAULT_FULLY_AI_THRESHOLD,
+            ai_assisted_threshold: DEFAULT_AI_ASSISTED_THRESHOLD,
+            hook_network_budget: Duration::from_millis(DEFAULT_HOOK_NETWORK_BUDGET_MS),
+            usage_telemetry: false,
+            attribution_neutral_commits: Vec::new(),
+            neutral_whitespace_commits: false,
+            preserve_whitespace_attribution: false,
+            attribution_granularity: BTreeMap::new(),
+            trusted_provenance_identities: Vec::new(),
+            no_network: false,
+            ca_bundle: None,
+            tls_insecure: false,
+            ci_comment: false,
+            ci_check_run: false,
+            security_scan_command: None,
+            note_details_threshold: None,
+            note_delta_interval: None,
+            ignore_patterns: Vec::new(),
+            allowed_agents: Vec::new(),
         }
     }
 
@@ -1099,6 +1835,27 @@ mod tests {
             default_prompt_storage: default_prompt_storage.map(|s| s.to_string()),
             api_key: None,
             quiet: false,
+            work_item_pattern: DEFAULT_WORK_ITEM_PATTERN.to_string(),
+            test_path_patterns: default_test_path_patterns(),
+            fully_ai_threshold: DEFAULT_FULLY_AI_THRESHOLD,
+            ai_assisted_threshold: DEFAULT_AI_ASSISTED_THRESHOLD,
+            hook_network_budget: Duration::from_millis(DEFAULT_HOOK_NETWORK_BUDGET_MS),
+            usage_telemetry: false,
+            attribution_neutral_commits: Vec::new(),
+            neutral_whitespace_commits: false,
+            preserve_whitespace_attribution: false,
+            attribution_granularity: BTreeMap::new(),
+            trusted_provenance_identities: Vec::new(),
+            no_network: false,
+            ca_bundle: None,
+            tls_insecure: false,
+            ci_comment: false,
+            ci_check_run: false,
+            security_scan_command: None,
+            note_details_threshold: None,
+            note_delta_interval: None,
+            ignore_patterns: Vec::new(),
+            allowed_agents: Vec::new(),
         }
     }
 
@@ -1295,4 +2052,39 @@ mod tests {
         config.quiet = true;
         assert!(config.is_quiet());
     }
+
+    #[test]
+    fn test_repo_file_config_parses_all_sections() {
+        let config: RepoFileConfig = toml::from_str(
+            r#"
+            ignore = ["*.lock"]
+
+            [agents]
+            allow = ["claude"]
+
+            [policy]
+            fully_ai_threshold = 90.0
+            attribution_granularity = { md = "line" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.ignore, Some(vec!["*.lock".to_string()]));
+        assert_eq!(
+            config.agents.unwrap().allow,
+            Some(vec!["claude".to_string()])
+        );
+        let policy = config.policy.unwrap();
+        assert_eq!(policy.fully_ai_threshold, Some(90.0));
+        assert_eq!(policy.attribution_granularity.unwrap()["md"], "line");
+        assert!(policy.work_item_pattern.is_none());
+    }
+
+    #[test]
+    fn test_agent_allow_list_is_case_insensitive_and_empty_allows_all() {
+        let mut config = create_test_config(vec![], vec![]);
+        assert!(config.is_agent_allowed("cursor"));
+        config.allowed_agents = vec!["Claude".to_string()];
+        assert!(config.is_agent_allowed("claude"));
+        assert!(!config.is_agent_allowed("cursor"));
+    }
 }
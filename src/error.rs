@@ -1,3 +1,4 @@
+use crate::observability::scrub::scrub_secrets;
 use std::fmt;
 
 #[derive(Debug)]
@@ -18,33 +19,67 @@ pub enum GitAiError {
     FromUtf8Error(std::string::FromUtf8Error),
     PresetError(String),
     SqliteError(rusqlite::Error),
+    /// Network access is off (`--no-network`) and `operation` would have needed it
+    NetworkDisabled {
+        operation: String,
+    },
+    /// An HTTP request kept failing with a rate limit, server error or connection
+    /// error until it ran out of attempts
+    HttpRequestFailed {
+        what: String,
+        attempts: u32,
+        /// Status of the last response, None if the last attempt couldn't connect
+        status: Option<i32>,
+        message: String,
+    },
+    /// The CI deadline passed before `operation` could start
+    DeadlineExceeded {
+        budget: std::time::Duration,
+        operation: String,
+    },
     Generic(String),
 }
 
 impl fmt::Display for GitAiError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
+        let message = match self {
             #[cfg(feature = "test-support")]
-            GitAiError::GitError(e) => write!(f, "Git error: {}", e),
-            GitAiError::IoError(e) => write!(f, "IO error: {}", e),
+            GitAiError::GitError(e) => format!("Git error: {}", e),
+            GitAiError::IoError(e) => format!("IO error: {}", e),
             GitAiError::GitCliError { code, stderr, args } => match code {
-                Some(c) => write!(
-                    f,
+                Some(c) => format!(
                     "Git CLI ({}) failed with exit code {}: {}",
                     args.join(" "),
                     c,
                     stderr
                 ),
-                None => write!(f, "Git CLI ({}) failed: {}", args.join(" "), stderr),
+                None => format!("Git CLI ({}) failed: {}", args.join(" "), stderr),
             },
-            GitAiError::JsonError(e) => write!(f, "JSON error: {}", e),
-            GitAiError::Utf8Error(e) => write!(f, "UTF-8 error: {}", e),
-            GitAiError::FromUtf8Error(e) => write!(f, "From UTF-8 error: {}", e),
-            GitAiError::PresetError(e) => write!(f, "{}", e),
-            GitAiError::SqliteError(e) => write!(f, "SQLite error: {}", e),
-            GitAiError::Generic(e) => write!(f, "Generic error: {}", e),
-            GitAiError::GixError(e) => write!(f, "Gix error: {}", e),
-        }
+            GitAiError::JsonError(e) => format!("JSON error: {}", e),
+            GitAiError::Utf8Error(e) => format!("UTF-8 error: {}", e),
+            GitAiError::FromUtf8Error(e) => format!("From UTF-8 error: {}", e),
+            GitAiError::PresetError(e) => e.to_string(),
+            GitAiError::SqliteError(e) => format!("SQLite error: {}", e),
+            GitAiError::NetworkDisabled { operation } => format!(
+                "Network access is disabled (--no-network); refusing to {}",
+                operation
+            ),
+            GitAiError::HttpRequestFailed {
+                what,
+                attempts,
+                message,
+                ..
+            } => format!("{} failed after {} attempt(s): {}", what, attempts, message),
+            GitAiError::DeadlineExceeded { budget, operation } => format!(
+                "CI deadline of {} exceeded; stopped before {}",
+                humantime::format_duration(*budget),
+                operation
+            ),
+            GitAiError::Generic(e) => format!("Generic error: {}", e),
+            GitAiError::GixError(e) => format!("Gix error: {}", e),
+        };
+        // Git args and stderr can contain authenticated URLs or tokens
+        write!(f, "{}", scrub_secrets(&message))
     }
 }
 
@@ -105,6 +140,24 @@ impl Clone for GitAiError {
             GitAiError::FromUtf8Error(e) => GitAiError::FromUtf8Error(e.clone()),
             GitAiError::PresetError(s) => GitAiError::PresetError(s.clone()),
             GitAiError::SqliteError(e) => GitAiError::Generic(format!("SQLite error: {}", e)),
+            GitAiError::NetworkDisabled { operation } => GitAiError::NetworkDisabled {
+                operation: operation.clone(),
+            },
+            GitAiError::HttpRequestFailed {
+                what,
+                attempts,
+                status,
+                message,
+            } => GitAiError::HttpRequestFailed {
+                what: what.clone(),
+                attempts: *attempts,
+                status: *status,
+                message: message.clone(),
+            },
+            GitAiError::DeadlineExceeded { budget, operation } => GitAiError::DeadlineExceeded {
+                budget: *budget,
+                operation: operation.clone(),
+            },
             GitAiError::Generic(s) => GitAiError::Generic(s.clone()),
             GitAiError::GixError(e) => GitAiError::Generic(format!("Gix error: {}", e)),
         }
@@ -69,6 +69,27 @@ pub fn commits_have_authorship_notes(
         .any(|(_, commit_sha)| commit_set.contains(commit_sha.as_str())))
 }
 
+/// Read every authorship note as (commit_sha, note_content) pairs
+pub fn read_all_notes(repo: &Repository) -> Result<Vec<(String, String)>, GitAiError> {
+    let global_args = repo.global_args_for_exec();
+    let note_mappings = get_notes_list(&global_args)?;
+    let blob_shas: Vec<String> = note_mappings
+        .iter()
+        .map(|(note_sha, _)| note_sha.clone())
+        .collect();
+    let contents = batch_read_blobs(&global_args, &blob_shas)?;
+    if contents.len() != note_mappings.len() {
+        return Err(GitAiError::Generic(
+            "Some authorship note blobs are missing".to_string(),
+        ));
+    }
+    Ok(note_mappings
+        .into_iter()
+        .map(|(_, commit_sha)| commit_sha)
+        .zip(contents)
+        .collect())
+}
+
 /// Get all notes as (note_blob_sha, commit_sha) pairs
 fn get_notes_list(global_args: &[String]) -> Result<Vec<(String, String)>, GitAiError> {
     let mut args = global_args.to_vec();
@@ -191,8 +212,14 @@ fn parse_cat_file_batch_output(data: &[u8]) -> Result<Vec<String>, GitAiError> {
 
 /// Extract file paths from a note blob content
 fn extract_file_paths_from_note(content: &str, files: &mut HashSet<String>) {
-    // Find the divider and slice before it, then add minimal metadata to make it parseable
-    if let Some(divider_pos) = content.find("\n---\n") {
+    // Find the divider and slice before it, then add minimal metadata to make it parseable.
+    // A note without attestations, like a delta that left them all out, starts with it
+    let divider_pos = if content.starts_with("---\n") {
+        Some(0)
+    } else {
+        content.find("\n---\n")
+    };
+    if let Some(divider_pos) = divider_pos {
         let attestation_section = &content[..divider_pos];
         // Create a complete parseable format with empty metadata
         let parseable = format!(
@@ -205,6 +232,19 @@ fn extract_file_paths_from_note(content: &str, files: &mut HashSet<String>) {
                 files.insert(attestation.file_path);
             }
         }
+
+        // A summary lists its files in the metadata instead, and a delta the files it
+        // left out because its parent's note has them
+        if (content.contains("\"details\"") || content.contains("\"delta\""))
+            && let Ok(log) = AuthorshipLog::deserialize_from_string(content)
+        {
+            if let Some(details) = log.metadata.details {
+                files.extend(details.files);
+            }
+            if let Some(delta) = log.metadata.delta {
+                files.extend(delta.unchanged_files.into_keys());
+            }
+        }
     }
 }
 
@@ -291,4 +331,25 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn test_extract_file_paths_from_delta_without_attestations() {
+        use crate::authorship::note_delta::NoteDelta;
+
+        let mut log = AuthorshipLog::new();
+        log.metadata.delta = Some(Box::new(NoteDelta {
+            parent: "parent".to_string(),
+            parent_note: "blob".to_string(),
+            depth: 1,
+            unchanged: Vec::new(),
+            messages_from: Default::default(),
+            unchanged_files: [("src/lib.rs".to_string(), 0)].into_iter().collect(),
+        }));
+        let content = log.serialize_to_string().unwrap();
+        assert!(content.starts_with("---\n"), "note: {}", content);
+
+        let mut files = HashSet::new();
+        extract_file_paths_from_note(&content, &mut files);
+        assert_eq!(files, HashSet::from(["src/lib.rs".to_string()]));
+    }
 }
@@ -0,0 +1,244 @@
+//! Ancestry queries answered from the commit-graph file (`git commit-graph write`,
+//! also kept up to date by `git maintenance` and `gc`).
+//!
+//! The commit-graph stores each commit's parents and generation number, so walks that
+//! would otherwise spawn a git process per commit (or per ancestry check) run in-process.
+//! A commit's generation is strictly greater than its ancestors', which bounds how far
+//! down an ancestry walk needs to go. Commits made since the graph was last written
+//! aren't in it; callers fall back to git for those.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use gix_commitgraph::{Graph, Position};
+use gix_hash::ObjectId;
+
+use crate::git::repository::Repository;
+use crate::git::shallow;
+
+pub struct CommitGraph {
+    graph: Graph,
+}
+
+impl CommitGraph {
+    /// Load the repository's commit-graph. `None` when there isn't one, or when git
+    /// itself wouldn't trust it: it's disabled, or history is rewritten by grafts,
+    /// replace refs, or a shallow boundary.
+    pub fn open(repo: &Repository) -> Option<Self> {
+        if repo
+            .config_get_str("core.commitGraph")
+            .ok()
+            .flatten()
+            .is_some_and(|value| value.eq_ignore_ascii_case("false"))
+        {
+            return None;
+        }
+        let common_dir = common_dir(repo);
+        if shallow::is_shallow(repo)
+            || common_dir.join("info").join("grafts").exists()
+            || has_replace_refs(&common_dir)
+        {
+            return None;
+        }
+        let graph = Graph::from_info_dir(&common_dir.join("objects").join("info")).ok()?;
+        Some(CommitGraph { graph })
+    }
+
+    /// Parents of `commit`, in order, if it's in the graph
+    pub fn parents(&self, commit: &str) -> Option<Vec<String>> {
+        let position = self.lookup(commit)?;
+        self.parent_positions(position)
+            .map(|parents| parents.into_iter().map(|p| self.hex_at(p)).collect())
+    }
+
+    /// Whether `ancestor` is reachable from `descendant` (a commit is its own ancestor),
+    /// or `None` if either isn't in the graph
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Option<bool> {
+        let target = self.lookup(ancestor)?;
+        let start = self.lookup(descendant)?;
+        if target == start {
+            return Some(true);
+        }
+        let min_generation = self.generation(target);
+
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(position) = stack.pop() {
+            for parent in self.parent_positions(position)? {
+                if parent == target {
+                    return Some(true);
+                }
+                // Nothing below the target's generation can reach it
+                if self.generation(parent) > min_generation && seen.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+        Some(false)
+    }
+
+    /// The merge base of two commits, as `git merge-base` would pick it. `None` if
+    /// either commit isn't in the graph or there's more than one best common ancestor,
+    /// where git's choice depends on its walk order.
+    pub fn merge_base(&self, one: &str, two: &str) -> Option<String> {
+        const ONE: u8 = 1;
+        const TWO: u8 = 2;
+        const STALE: u8 = 4;
+
+        let one = self.lookup(one)?;
+        let two = self.lookup(two)?;
+        if one == two {
+            return Some(self.hex_at(one));
+        }
+
+        // Paint down from both sides, highest generation first, so a commit is only
+        // visited after every commit above it that could reach it. Stops once only
+        // commits below a found base (stale ones) are left.
+        let mut flags: HashMap<Position, u8> = HashMap::from([(one, ONE), (two, TWO)]);
+        let mut queue = BinaryHeap::from([
+            (self.generation(one), Reverse(one.0), false),
+            (self.generation(two), Reverse(two.0), false),
+        ]);
+        let mut non_stale = queue.len();
+        let mut bases = Vec::new();
+        while non_stale > 0 {
+            let Some((_, Reverse(raw), stale)) = queue.pop() else {
+                break;
+            };
+            if !stale {
+                non_stale -= 1;
+            }
+            let position = Position(raw);
+            let mut paint = flags[&position] & (ONE | TWO | STALE);
+            if paint == ONE | TWO {
+                bases.push(position);
+                paint |= STALE;
+                flags.insert(position, paint);
+            }
+            for parent in self.parent_positions(position)? {
+                let parent_flags = flags.entry(parent).or_insert(0);
+                if *parent_flags & paint == paint {
+                    continue;
+                }
+                *parent_flags |= paint;
+                let stale = paint & STALE != 0;
+                if !stale {
+                    non_stale += 1;
+                }
+                queue.push((self.generation(parent), Reverse(parent.0), stale));
+            }
+        }
+
+        match bases.as_slice() {
+            [base] => Some(self.hex_at(*base)),
+            _ => None,
+        }
+    }
+
+    fn lookup(&self, commit: &str) -> Option<Position> {
+        let id = ObjectId::from_hex(commit.as_bytes()).ok()?;
+        self.graph.lookup(id)
+    }
+
+    fn generation(&self, position: Position) -> u32 {
+        self.graph.commit_at(position).generation()
+    }
+
+    fn parent_positions(&self, position: Position) -> Option<Vec<Position>> {
+        self.graph
+            .commit_at(position)
+            .iter_parents()
+            .collect::<Result<_, _>>()
+            .ok()
+    }
+
+    fn hex_at(&self, position: Position) -> String {
+        self.graph.id_at(position).to_hex().to_string()
+    }
+}
+
+/// Whether `value` is a full object id rather than a ref or revision expression. Only
+/// those can be looked up in the graph without asking git to resolve them.
+pub fn is_full_oid(value: &str) -> bool {
+    matches!(value.len(), 40 | 64) && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The directory shared by all worktrees, which holds objects and refs
+fn common_dir(repo: &Repository) -> PathBuf {
+    let git_dir = repo.path();
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+fn has_replace_refs(common_dir: &std::path::Path) -> bool {
+    let loose = std::fs::read_dir(common_dir.join("refs").join("replace"))
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    loose
+        || std::fs::read_to_string(common_dir.join("packed-refs"))
+            .map(|packed| packed.contains(" refs/replace/"))
+            .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_utils::TmpRepo;
+
+    #[test]
+    fn test_commit_graph_answers_match_git() {
+        let (tmp_repo, mut lines, _alphabet) = TmpRepo::new_with_base_commit().unwrap();
+        let base = tmp_repo.head_commit_sha().unwrap();
+        let main_branch = tmp_repo.current_branch().unwrap();
+
+        tmp_repo.create_branch("feature").unwrap();
+        lines.append("feature line\n").unwrap();
+        tmp_repo
+            .trigger_checkpoint_with_author("test_user")
+            .unwrap();
+        tmp_repo.commit_with_message("feature commit").unwrap();
+        let feature = tmp_repo.head_commit_sha().unwrap();
+
+        tmp_repo.switch_branch(&main_branch).unwrap();
+        let mut other = tmp_repo.write_file("other.txt", "other\n", true).unwrap();
+        tmp_repo
+            .trigger_checkpoint_with_author("test_user")
+            .unwrap();
+        tmp_repo.commit_with_message("main commit").unwrap();
+        let main = tmp_repo.head_commit_sha().unwrap();
+        tmp_repo.merge_branch("feature", "merge feature").unwrap();
+        let merge = tmp_repo.head_commit_sha().unwrap();
+
+        let repo = tmp_repo.gitai_repo();
+        assert!(CommitGraph::open(repo).is_none());
+        repo.git(&["commit-graph", "write", "--reachable"]).unwrap();
+        let graph = CommitGraph::open(repo).expect("commit-graph should load");
+
+        assert_eq!(
+            graph.parents(&merge),
+            Some(vec![main.clone(), feature.clone()])
+        );
+        assert_eq!(graph.is_ancestor(&base, &merge), Some(true));
+        assert_eq!(graph.is_ancestor(&feature, &merge), Some(true));
+        assert_eq!(graph.is_ancestor(&merge, &merge), Some(true));
+        assert_eq!(graph.is_ancestor(&feature, &main), Some(false));
+        assert_eq!(graph.is_ancestor(&merge, &base), Some(false));
+        assert_eq!(graph.merge_base(&feature, &main), Some(base.clone()));
+        assert_eq!(graph.merge_base(&merge, &feature), Some(feature.clone()));
+        assert_eq!(graph.merge_base(&base, &base), Some(base.clone()));
+
+        // Commits made after the graph was written aren't in it
+        other.append("more\n").unwrap();
+        tmp_repo
+            .trigger_checkpoint_with_author("test_user")
+            .unwrap();
+        tmp_repo.commit_with_message("after graph").unwrap();
+        let after = tmp_repo.head_commit_sha().unwrap();
+        assert_eq!(graph.parents(&after), None);
+        assert_eq!(graph.is_ancestor(&base, &after), None);
+        assert_eq!(graph.merge_base(&after, &feature), None);
+    }
+}
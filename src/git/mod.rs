@@ -1,4 +1,5 @@
 pub mod cli_parser;
+pub mod commit_graph;
 pub mod diff_tree_to_tree;
 pub mod refs;
 pub mod repository;
@@ -10,8 +11,10 @@ pub use repository::{
     find_repository, find_repository_for_file, find_repository_in_path, from_bare_repository,
     group_files_by_repository,
 };
+pub mod partial_clone;
 pub mod repo_storage;
 pub mod rewrite_log;
+pub mod shallow;
 pub mod status;
 pub mod sync_authorship;
 
@@ -0,0 +1,115 @@
+//! Partial clone (`git clone --filter=blob:none`) support.
+//!
+//! Git fetches blobs missing from a partial clone one at a time, on first read, and a
+//! read that can't reach the remote fails. Attribution over a range reads many blobs, so
+//! the ones it needs are fetched up front in a single batch instead.
+
+use std::collections::HashSet;
+
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git, exec_git_stdin};
+use crate::utils::debug_log;
+
+/// The remote missing objects can be fetched from, if this is a partial clone
+pub fn promisor_remote(repo: &Repository) -> Option<String> {
+    let promisors = repo.config_get_regexp(r"^remote\..+\.promisor$").ok()?;
+    let mut remotes: Vec<String> = promisors
+        .into_iter()
+        .filter(|(_, value)| value.eq_ignore_ascii_case("true"))
+        .filter_map(|(key, _)| {
+            key.strip_prefix("remote.")?
+                .strip_suffix(".promisor")
+                .map(str::to_string)
+        })
+        .collect();
+    remotes.sort();
+    remotes.into_iter().next()
+}
+
+/// Fetch the blobs that differ between `from` and `to` and aren't present locally, in one
+/// round trip. A no-op outside partial clones. Returns how many blobs were fetched.
+pub fn prefetch_blobs_for_range(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+) -> Result<usize, GitAiError> {
+    let Some(remote) = promisor_remote(repo) else {
+        return Ok(0);
+    };
+
+    let changed = changed_blob_oids(repo, from, to)?;
+    if changed.is_empty() {
+        return Ok(0);
+    }
+    let missing: Vec<String> = missing_objects(repo, &[from, to])?
+        .intersection(&changed)
+        .cloned()
+        .collect();
+    if missing.is_empty() {
+        return Ok(0);
+    }
+
+    debug_log(&format!(
+        "partial clone: fetching {} missing blobs from '{}'",
+        missing.len(),
+        remote
+    ));
+    // The same request git makes for a single lazily fetched object
+    let mut args = repo.global_args_for_exec();
+    args.extend(
+        [
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            &remote,
+            "--no-tags",
+            "--no-write-fetch-head",
+            "--recurse-submodules=no",
+            "--filter=blob:none",
+            "--stdin",
+        ]
+        .map(str::to_string),
+    );
+    exec_git_stdin(&args, format!("{}\n", missing.join("\n")).as_bytes())?;
+    Ok(missing.len())
+}
+
+/// Blob ids on either side of the changes between two commits. Trees are always present
+/// in a blobless clone, so this never fetches.
+fn changed_blob_oids(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+) -> Result<HashSet<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["diff-tree", "-r", "--no-renames", from, to].map(str::to_string));
+    let output = exec_git(&args)?;
+    let null_oid = |oid: &&str| oid.chars().all(|c| c == '0');
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        // :<old mode> <new mode> <old oid> <new oid> <status>\t<path>
+        .filter_map(|line| line.split('\t').next())
+        .flat_map(|meta| {
+            meta.split_whitespace()
+                .skip(2)
+                .take(2)
+                .filter(|oid| !null_oid(oid))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+/// Objects reachable from `commits`' trees that aren't in the local object store.
+/// `--missing=print` reports them without triggering a lazy fetch for each.
+fn missing_objects(repo: &Repository, commits: &[&str]) -> Result<HashSet<String>, GitAiError> {
+    let mut args = repo.global_args_for_exec();
+    args.extend(["rev-list", "--objects", "--missing=print", "--no-walk"].map(str::to_string));
+    args.extend(commits.iter().map(|commit| commit.to_string()));
+    let output = exec_git(&args)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix('?'))
+        .map(|oid| oid.trim().to_string())
+        .collect())
+}
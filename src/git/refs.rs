@@ -1,7 +1,14 @@
-use crate::authorship::authorship_log_serialization::{AUTHORSHIP_LOG_VERSION, AuthorshipLog};
+use crate::authorship::authorship_log_serialization::{
+    AUTHORSHIP_LOG_VERSION, AuthorshipLog, IncompatibleNoteError,
+};
+use crate::authorship::note_delta::{encode, resolve};
+use crate::authorship::note_details::{is_summary, summarize};
 use crate::authorship::working_log::Checkpoint;
+use crate::config::Config;
 use crate::error::GitAiError;
 use crate::git::repository::{Repository, exec_git, exec_git_stdin};
+use crate::git::sync_authorship::fetch_note_details;
+use crate::observability::timings::{self, Phase};
 use crate::utils::debug_log;
 use serde_json;
 use std::collections::{HashMap, HashSet};
@@ -10,14 +17,84 @@ use std::collections::{HashMap, HashSet};
 pub const AI_AUTHORSHIP_REFNAME: &str = "ai";
 pub const AI_AUTHORSHIP_PUSH_REFSPEC: &str = "refs/notes/ai:refs/notes/ai";
 
+/// Notes ref holding the full notes of the summaries on refs/notes/ai (see
+/// [`crate::authorship::note_details`])
+pub const AI_DETAILS_REFNAME: &str = "ai-details";
+pub const AI_DETAILS_REF: &str = "refs/notes/ai-details";
+pub const AI_DETAILS_PUSH_REFSPEC: &str = "refs/notes/ai-details:refs/notes/ai-details";
+
+/// Write `note_content` as `commit_sha`'s note. With `note_delta_interval` set, it's
+/// written as a delta against its parent's where that's smaller. Past
+/// `note_details_threshold`, only its summary goes on refs/notes/ai and the full note on
+/// refs/notes/ai-details.
 pub fn notes_add(
     repo: &Repository,
     commit_sha: &str,
     note_content: &str,
+) -> Result<(), GitAiError> {
+    let config = Config::get();
+    let delta = config
+        .note_delta_interval()
+        .and_then(|interval| encode_delta(repo, commit_sha, note_content, interval));
+    let note_content = delta.as_deref().unwrap_or(note_content);
+    let summary = config
+        .note_details_threshold()
+        .filter(|threshold| note_content.len() as u64 > *threshold)
+        .and_then(|_| summarize(note_content));
+    if summary.is_some() {
+        add_note(repo, AI_DETAILS_REFNAME, commit_sha, note_content)?;
+    }
+    add_note(
+        repo,
+        AI_AUTHORSHIP_REFNAME,
+        commit_sha,
+        summary.as_deref().unwrap_or(note_content),
+    )
+}
+
+/// `note_content` encoded against the note of `commit_sha`'s first parent, None to write
+/// it in full: at every `interval`th note of a chain, or when there's nothing to save
+fn encode_delta(
+    repo: &Repository,
+    commit_sha: &str,
+    note_content: &str,
+    interval: u32,
+) -> Option<String> {
+    let mut log = AuthorshipLog::deserialize_from_string(note_content).ok()?;
+    if log.metadata.delta.is_some() {
+        return None;
+    }
+    let parent_sha = repo
+        .find_commit(commit_sha.to_string())
+        .ok()?
+        .parent(0)
+        .ok()?
+        .id();
+    let (parent_content, parent_depth) = load_note(repo, &parent_sha, u32::MAX)?;
+    let depth = parent_depth + 1;
+    if depth >= interval {
+        return None;
+    }
+    let parent = AuthorshipLog::deserialize_from_string(&parent_content)
+        .ok()
+        .filter(|parent| parent.metadata.delta.is_none())?;
+    let parent_note = note_blob_oid(repo, &parent_sha)?;
+    if !encode(&mut log, &parent_sha, &parent_note, &parent, depth) {
+        return None;
+    }
+    let encoded = log.serialize_to_string().ok()?;
+    (encoded.len() < note_content.len()).then_some(encoded)
+}
+
+fn add_note(
+    repo: &Repository,
+    refname: &str,
+    commit_sha: &str,
+    note_content: &str,
 ) -> Result<(), GitAiError> {
     let mut args = repo.global_args_for_exec();
     args.push("notes".to_string());
-    args.push("--ref=ai".to_string());
+    args.push(format!("--ref={}", refname));
     args.push("add".to_string());
     args.push("-f".to_string()); // Always force overwrite
     args.push("-F".to_string());
@@ -25,10 +102,28 @@ pub fn notes_add(
     args.push(commit_sha.to_string());
 
     // Use stdin to provide the note content to avoid command line length limits
+    let _timing = timings::phase(Phase::NoteWrite);
     exec_git_stdin(&args, note_content.as_bytes())?;
     Ok(())
 }
 
+pub fn notes_remove(repo: &Repository, commit_sha: &str) -> Result<(), GitAiError> {
+    for refname in [AI_AUTHORSHIP_REFNAME, AI_DETAILS_REFNAME] {
+        if refname == AI_DETAILS_REFNAME && !ref_exists(repo, AI_DETAILS_REF) {
+            continue;
+        }
+        let mut args = repo.global_args_for_exec();
+        args.push("notes".to_string());
+        args.push(format!("--ref={}", refname));
+        args.push("remove".to_string());
+        args.push("--ignore-missing".to_string());
+        args.push(commit_sha.to_string());
+
+        exec_git(&args)?;
+    }
+    Ok(())
+}
+
 // Check which commits from the given list have authorship notes.
 // Uses git cat-file --batch-check to efficiently check multiple commits in one invocation.
 // Returns a Vec of CommitAuthorship for each commit.
@@ -42,7 +137,7 @@ pub enum CommitAuthorship {
     Log {
         sha: String,
         git_author: String,
-        authorship_log: AuthorshipLog,
+        authorship_log: Box<AuthorshipLog>,
     },
 }
 pub fn get_commits_with_notes_from_list(
@@ -103,7 +198,7 @@ pub fn get_commits_with_notes_from_list(
             result.push(CommitAuthorship::Log {
                 sha: sha.clone(),
                 git_author,
-                authorship_log,
+                authorship_log: Box::new(authorship_log),
             });
         } else {
             result.push(CommitAuthorship::NoLog {
@@ -117,10 +212,113 @@ pub fn get_commits_with_notes_from_list(
 }
 
 // Show an authorship note and return its JSON content if found, or None if it doesn't exist.
+// A summary is resolved to the full note on refs/notes/ai-details, which is fetched the
+// first time one is missing; without it, the summary is returned. A delta is resolved
+// against its parent's note.
 pub fn show_authorship_note(repo: &Repository, commit_sha: &str) -> Option<String> {
+    load_note(repo, commit_sha, u32::MAX).map(|(content, _)| content)
+}
+
+/// `commit_sha`'s note with its delta resolved, and how many deltas from a full note it
+/// was stored. A delta more than `max_depth` deep is left unresolved, so a chain of
+/// parents always ends. A delta whose parent's note was rewritten since, or can't be
+/// resolved itself, is returned as stored with its `delta` marking what's missing, and
+/// the next note written on top of it is full.
+fn load_note(repo: &Repository, commit_sha: &str, max_depth: u32) -> Option<(String, u32)> {
+    let content = show_full_note(repo, commit_sha)?;
+    if !content.contains("\"delta\"") {
+        return Some((content, 0));
+    }
+    let Ok(mut log) = AuthorshipLog::deserialize_from_string(&content) else {
+        return Some((content, 0));
+    };
+    let Some((parent_sha, depth)) = log
+        .metadata
+        .delta
+        .as_ref()
+        .map(|delta| (delta.parent.clone(), delta.depth))
+    else {
+        return Some((content, 0));
+    };
+    let parent_note = (depth <= max_depth && depth > 0)
+        .then(|| note_blob_oid(repo, &parent_sha))
+        .flatten();
+    let parent = match parent_note {
+        Some(blob) => {
+            let Some(parent) = load_note(repo, &parent_sha, depth - 1)
+                .and_then(|(parent, _)| AuthorshipLog::deserialize_from_string(&parent).ok())
+                .filter(|parent| parent.metadata.delta.is_none())
+            else {
+                // Nor can anything left out of this one that the parent's note had
+                warn_unresolvable_delta(
+                    commit_sha,
+                    &GitAiError::Generic(format!("the note of {} can't be fully read", parent_sha)),
+                );
+                return Some((content, depth));
+            };
+            Some((blob, parent))
+        }
+        None => None,
+    };
+    if let Err(e) = resolve(
+        &mut log,
+        parent
+            .as_ref()
+            .map(|(blob, parent)| (blob.as_str(), parent)),
+    ) {
+        warn_unresolvable_delta(commit_sha, &e);
+        return Some((content, depth));
+    }
+    match log.serialize_to_string() {
+        Ok(resolved) => Some((resolved, depth)),
+        Err(_) => Some((content, depth)),
+    }
+}
+
+/// The blob holding `commit_sha`'s note on refs/notes/ai
+fn note_blob_oid(repo: &Repository, commit_sha: &str) -> Option<String> {
+    let mut args = repo.global_args_for_exec();
+    args.push("notes".to_string());
+    args.push(format!("--ref={}", AI_AUTHORSHIP_REFNAME));
+    args.push("list".to_string());
+    args.push(commit_sha.to_string());
+
+    let output = exec_git(&args).ok()?;
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Commands that read many notes would otherwise repeat the same warning per commit
+fn warn_unresolvable_delta(commit_sha: &str, error: &GitAiError) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        eprintln!(
+            "Warning: reading the authorship note of {} without what it shares with its parent's: {}",
+            commit_sha, error
+        )
+    });
+}
+
+/// `commit_sha`'s note with a summary swapped for the full note
+fn show_full_note(repo: &Repository, commit_sha: &str) -> Option<String> {
+    let content = show_note(repo, AI_AUTHORSHIP_REFNAME, commit_sha)?;
+    if !is_summary(&content) {
+        return Some(content);
+    }
+    show_note(repo, AI_DETAILS_REFNAME, commit_sha)
+        .or_else(|| {
+            fetch_note_details(repo);
+            show_note(repo, AI_DETAILS_REFNAME, commit_sha)
+        })
+        .or(Some(content))
+}
+
+fn show_note(repo: &Repository, refname: &str, commit_sha: &str) -> Option<String> {
     let mut args = repo.global_args_for_exec();
     args.push("notes".to_string());
-    args.push("--ref=ai".to_string());
+    args.push(format!("--ref={}", refname));
     args.push("show".to_string());
     args.push(commit_sha.to_string());
 
@@ -137,8 +335,21 @@ pub fn show_authorship_note(repo: &Repository, commit_sha: &str) -> Option<Strin
 // Show an authorship note and return its JSON content if found, or None if it doesn't exist.
 pub fn get_authorship(repo: &Repository, commit_sha: &str) -> Option<AuthorshipLog> {
     let content = show_authorship_note(repo, commit_sha)?;
-    let authorship_log = AuthorshipLog::deserialize_from_string(&content).ok()?;
-    Some(authorship_log)
+    match AuthorshipLog::deserialize_from_string(&content) {
+        Ok(authorship_log) => Some(authorship_log),
+        Err(e) => {
+            if let Some(incompatible) = e.downcast_ref::<IncompatibleNoteError>() {
+                warn_incompatible_note(incompatible);
+            }
+            None
+        }
+    }
+}
+
+/// Commands that read many notes would otherwise repeat the same upgrade hint per commit
+fn warn_incompatible_note(error: &IncompatibleNoteError) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| eprintln!("Warning: {}", error));
 }
 
 #[allow(dead_code)]
@@ -162,6 +373,9 @@ pub fn get_reference_as_authorship_log_v3(
     // Try to deserialize as AuthorshipLog
     let authorship_log = match AuthorshipLog::deserialize_from_string(&content) {
         Ok(log) => log,
+        Err(e) if e.is::<IncompatibleNoteError>() => {
+            return Err(GitAiError::Generic(e.to_string()));
+        }
         Err(_) => {
             return Err(GitAiError::Generic(
                 "Failed to parse authorship log".to_string(),
@@ -169,8 +383,9 @@ pub fn get_reference_as_authorship_log_v3(
         }
     };
 
-    // Check version compatibility
-    if authorship_log.metadata.schema_version != AUTHORSHIP_LOG_VERSION {
+    // Newer 3.x notes only add fields, which are ignored; older majors aren't readable
+    let major = |schema: &str| schema.split('.').next().map(str::to_string);
+    if major(&authorship_log.metadata.schema_version) != major(AUTHORSHIP_LOG_VERSION) {
         return Err(GitAiError::Generic(format!(
             "Unsupported authorship log version: {} (expected: {})",
             authorship_log.metadata.schema_version, AUTHORSHIP_LOG_VERSION
@@ -219,12 +434,29 @@ pub fn ref_exists(repo: &Repository, ref_name: &str) -> bool {
     exec_git(&args).is_ok()
 }
 
+/// Tracking ref for a remote's refs/notes/ai-details, like [`tracking_ref_for_remote`]
+pub fn details_tracking_ref_for_remote(remote_name: &str) -> String {
+    format!(
+        "refs/notes/ai-details-remote/{}",
+        sanitize_remote_name(remote_name)
+    )
+}
+
 /// Merge notes from a source ref into refs/notes/ai
 /// Uses the 'ours' strategy to combine notes without data loss
 pub fn merge_notes_from_ref(repo: &Repository, source_ref: &str) -> Result<(), GitAiError> {
+    merge_notes_into(repo, AI_AUTHORSHIP_REFNAME, source_ref)
+}
+
+/// Merge notes from a source ref into refs/notes/`refname`, keeping ours on conflict
+pub fn merge_notes_into(
+    repo: &Repository,
+    refname: &str,
+    source_ref: &str,
+) -> Result<(), GitAiError> {
     let mut args = repo.global_args_for_exec();
     args.push("notes".to_string());
-    args.push(format!("--ref={}", AI_AUTHORSHIP_REFNAME));
+    args.push(format!("--ref={}", refname));
     args.push("merge".to_string());
     args.push("-s".to_string());
     args.push("ours".to_string());
@@ -232,8 +464,8 @@ pub fn merge_notes_from_ref(repo: &Repository, source_ref: &str) -> Result<(), G
     args.push(source_ref.to_string());
 
     debug_log(&format!(
-        "Merging notes from {} into refs/notes/ai",
-        source_ref
+        "Merging notes from {} into refs/notes/{}",
+        source_ref, refname
     ));
     exec_git(&args)?;
     Ok(())
@@ -4,12 +4,14 @@ use crate::authorship::authorship_log_serialization::generate_short_hash;
 use crate::authorship::working_log::{CHECKPOINT_API_VERSION, Checkpoint, CheckpointKind};
 use crate::error::GitAiError;
 use crate::git::rewrite_log::{RewriteLogEvent, append_event_to_file};
+use crate::git::sync_authorship::NotesSyncOp;
 use crate::utils::{debug_log, normalize_to_posix};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 /// Initial attributions data structure stored in the INITIAL file
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -20,6 +22,34 @@ pub struct InitialAttributions {
     pub prompts: HashMap<String, PromptRecord>,
 }
 
+/// Serializes read-modify-write of the pending-sync and notes-push-blocked files across
+/// parallel remote syncs
+static PENDING_SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+/// A notes fetch/push that failed for lack of network, waiting to be retried
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingSync {
+    pub op: NotesSyncOp,
+    pub remote: String,
+    /// Unix timestamp of the first failure
+    pub queued_at: i64,
+    /// Unix timestamp of the most recent attempt
+    pub last_attempt: i64,
+    pub attempts: u32,
+}
+
+/// A remote whose server refused a push to `refs/notes/ai`, e.g. by ref policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockedNotesPush {
+    pub remote: String,
+    /// The server's rejection line
+    pub reason: String,
+    /// Unix timestamp of the rejection
+    pub since: i64,
+    /// Whether the user has been told; the rejection may happen in a background sync
+    pub warned: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct RepoStorage {
     pub repo_path: PathBuf,
@@ -27,6 +57,8 @@ pub struct RepoStorage {
     pub working_logs: PathBuf,
     pub rewrite_log: PathBuf,
     pub logs: PathBuf,
+    pub pending_sync: PathBuf,
+    pub blocked_notes_pushes: PathBuf,
 }
 
 impl RepoStorage {
@@ -35,6 +67,8 @@ impl RepoStorage {
         let working_logs_dir = ai_dir.join("working_logs");
         let rewrite_log_file = ai_dir.join("rewrite_log");
         let logs_dir = ai_dir.join("logs");
+        let pending_sync_file = ai_dir.join("pending-sync");
+        let blocked_notes_pushes_file = ai_dir.join("notes-push-blocked");
 
         let config = RepoStorage {
             repo_path: repo_path.to_path_buf(),
@@ -42,6 +76,8 @@ impl RepoStorage {
             working_logs: working_logs_dir,
             rewrite_log: rewrite_log_file,
             logs: logs_dir,
+            pending_sync: pending_sync_file,
+            blocked_notes_pushes: blocked_notes_pushes_file,
         };
 
         config.ensure_config_directory().unwrap();
@@ -124,6 +160,113 @@ impl RepoStorage {
         Ok(())
     }
 
+    /* Pending Notes Sync Queue */
+
+    /// Read queued notes operations (one JSON object per line). Malformed lines are skipped.
+    pub fn read_pending_sync(&self) -> Vec<PendingSync> {
+        fs::read_to_string(&self.pending_sync)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    pub fn write_pending_sync(&self, entries: &[PendingSync]) -> Result<(), GitAiError> {
+        if entries.is_empty() {
+            if self.pending_sync.exists() {
+                fs::remove_file(&self.pending_sync)?;
+            }
+            return Ok(());
+        }
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        fs::write(&self.pending_sync, content)?;
+        Ok(())
+    }
+
+    /// Queue (or re-queue) an operation. A remote has at most one entry per operation.
+    pub fn queue_pending_sync(&self, op: NotesSyncOp, remote: &str) -> Result<(), GitAiError> {
+        let _guard = PENDING_SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let now = chrono::Utc::now().timestamp();
+        let mut entries = self.read_pending_sync();
+        match entries
+            .iter_mut()
+            .find(|entry| entry.op == op && entry.remote == remote)
+        {
+            Some(entry) => {
+                entry.last_attempt = now;
+                entry.attempts += 1;
+            }
+            None => entries.push(PendingSync {
+                op,
+                remote: remote.to_string(),
+                queued_at: now,
+                last_attempt: now,
+                attempts: 1,
+            }),
+        }
+        self.write_pending_sync(&entries)
+    }
+
+    pub fn remove_pending_sync(&self, op: NotesSyncOp, remote: &str) -> Result<(), GitAiError> {
+        let _guard = PENDING_SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.read_pending_sync();
+        let before = entries.len();
+        entries.retain(|entry| !(entry.op == op && entry.remote == remote));
+        if entries.len() == before {
+            return Ok(());
+        }
+        self.write_pending_sync(&entries)
+    }
+
+    /* Blocked Notes Pushes */
+
+    /// Remotes known to reject notes pushes (one JSON object per line)
+    pub fn read_blocked_notes_pushes(&self) -> Vec<BlockedNotesPush> {
+        fs::read_to_string(&self.blocked_notes_pushes)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    pub fn blocked_notes_push(&self, remote: &str) -> Option<BlockedNotesPush> {
+        self.read_blocked_notes_pushes()
+            .into_iter()
+            .find(|entry| entry.remote == remote)
+    }
+
+    /// Insert or replace `entry`, or drop `remote`'s entry when `entry` is `None`
+    pub fn set_blocked_notes_push(
+        &self,
+        remote: &str,
+        entry: Option<BlockedNotesPush>,
+    ) -> Result<(), GitAiError> {
+        let _guard = PENDING_SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.read_blocked_notes_pushes();
+        let before = entries.clone();
+        entries.retain(|existing| existing.remote != remote);
+        entries.extend(entry);
+        if entries == before {
+            return Ok(());
+        }
+        if entries.is_empty() {
+            fs::remove_file(&self.blocked_notes_pushes)?;
+            return Ok(());
+        }
+        let mut content = String::new();
+        for entry in &entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        fs::write(&self.blocked_notes_pushes, content)?;
+        Ok(())
+    }
+
     /* Rewrite Log Persistance */
 
     /// Append a rewrite event to the rewrite log file and return the full log
@@ -561,7 +704,9 @@ impl PersistedWorkingLog {
             prompts,
         };
 
-        let json = serde_json::to_string_pretty(&initial_data)?;
+        // Going through a `Value` sorts the maps' keys, so the file doesn't change
+        // from run to run with HashMap iteration order
+        let json = serde_json::to_string_pretty(&serde_json::to_value(&initial_data)?)?;
         fs::write(&self.initial_file, json)?;
 
         Ok(())
@@ -4,11 +4,13 @@ use crate::authorship::authorship_log_serialization::AuthorshipLog;
 use crate::authorship::rebase_authorship::rewrite_authorship_if_needed;
 use crate::config;
 use crate::error::GitAiError;
+use crate::git::commit_graph::{CommitGraph, is_full_oid};
 use crate::git::refs::get_authorship;
 use crate::git::repo_storage::RepoStorage;
 use crate::git::rewrite_log::RewriteLogEvent;
 use crate::git::status::MAX_PATHSPEC_ARGS;
 use crate::git::sync_authorship::{fetch_authorship_notes, push_authorship_notes};
+use crate::observability::timings::{self, Phase};
 #[cfg(windows)]
 use crate::utils::is_interactive_terminal;
 
@@ -159,51 +161,36 @@ impl<'a> CommitRange<'a> {
         self.repo.find_commit(self.end_oid.clone())?;
 
         // Check that both commits exist on the refname
-        // Use git merge-base --is-ancestor <commit> <refname>
+        // Resolve the refname once so the ancestry checks can use the commit-graph
         // Skip merge-base check for empty tree hash since it's not part of commit history
-        if self.start_oid != EMPTY_TREE_HASH {
-            let mut args = self.repo.global_args_for_exec();
-            args.push("merge-base".to_string());
-            args.push("--is-ancestor".to_string());
-            args.push(self.start_oid.clone());
-            args.push(self.refname.clone());
-
-            exec_git(&args).map_err(|_| {
-                GitAiError::Generic(format!(
-                    "Commit {} is not reachable from refname {}",
-                    self.start_oid, self.refname
-                ))
-            })?;
+        let ref_tip = self
+            .repo
+            .revparse_single(&self.refname)
+            .map(|object| object.id())
+            .unwrap_or_else(|_| self.refname.clone());
+        if self.start_oid != EMPTY_TREE_HASH && !self.repo.is_ancestor(&self.start_oid, &ref_tip) {
+            return Err(GitAiError::Generic(format!(
+                "Commit {} is not reachable from refname {}",
+                self.start_oid, self.refname
+            )));
         }
 
-        let mut args = self.repo.global_args_for_exec();
-        args.push("merge-base".to_string());
-        args.push("--is-ancestor".to_string());
-        args.push(self.end_oid.clone());
-        args.push(self.refname.clone());
-
-        exec_git(&args).map_err(|_| {
-            GitAiError::Generic(format!(
+        if !self.repo.is_ancestor(&self.end_oid, &ref_tip) {
+            return Err(GitAiError::Generic(format!(
                 "Commit {} is not reachable from refname {}",
                 self.end_oid, self.refname
-            ))
-        })?;
+            )));
+        }
 
         // Check that start is an ancestor of end (direct path between them)
         // Skip for empty tree hash - it's not part of the commit DAG
-        if self.start_oid != EMPTY_TREE_HASH {
-            let mut args = self.repo.global_args_for_exec();
-            args.push("merge-base".to_string());
-            args.push("--is-ancestor".to_string());
-            args.push(self.start_oid.clone());
-            args.push(self.end_oid.clone());
-
-            exec_git(&args).map_err(|_| {
-                GitAiError::Generic(format!(
-                    "Commit {} is not an ancestor of {}",
-                    self.start_oid, self.end_oid
-                ))
-            })?;
+        if self.start_oid != EMPTY_TREE_HASH
+            && !self.repo.is_ancestor(&self.start_oid, &self.end_oid)
+        {
+            return Err(GitAiError::Generic(format!(
+                "Commit {} is not an ancestor of {}",
+                self.start_oid, self.end_oid
+            )));
         }
 
         Ok(())
@@ -584,19 +571,15 @@ impl<'a> Commit<'a> {
             }
         };
 
+        let ref_tip = self
+            .repo
+            .revparse_single(&fq_refname)
+            .map(|object| object.id())
+            .unwrap_or(fq_refname);
+
         // Iterate through parents and find the first one that's on the refname
         for parent in self.parents() {
-            let parent_sha = parent.id();
-
-            // Check if this parent is an ancestor of the refname
-            // git merge-base --is-ancestor <parent> <refname>
-            let mut args = self.repo.global_args_for_exec();
-            args.push("merge-base".to_string());
-            args.push("--is-ancestor".to_string());
-            args.push(parent_sha.clone());
-            args.push(fq_refname.clone());
-
-            if exec_git(&args).is_ok() {
+            if self.repo.is_ancestor(&parent.id(), &ref_tip) {
                 return Ok(parent);
             }
         }
@@ -1189,6 +1172,13 @@ impl Repository {
     }
     // Find a merge base between two commits
     pub fn merge_base(&self, one: String, two: String) -> Result<String, GitAiError> {
+        if is_full_oid(&one)
+            && is_full_oid(&two)
+            && let Some(base) =
+                CommitGraph::open(self).and_then(|graph| graph.merge_base(&one, &two))
+        {
+            return Ok(base);
+        }
         let mut args = self.global_args_for_exec();
         args.push("merge-base".to_string());
         args.push(one.to_string());
@@ -1197,6 +1187,24 @@ impl Repository {
         Ok(String::from_utf8(output.stdout)?.trim().to_string())
     }
 
+    /// Whether `ancestor` is reachable from `descendant` (`git merge-base --is-ancestor`).
+    /// Answered from the commit-graph when both are full ids it contains.
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> bool {
+        if is_full_oid(ancestor)
+            && is_full_oid(descendant)
+            && let Some(answer) =
+                CommitGraph::open(self).and_then(|graph| graph.is_ancestor(ancestor, descendant))
+        {
+            return answer;
+        }
+        let mut args = self.global_args_for_exec();
+        args.push("merge-base".to_string());
+        args.push("--is-ancestor".to_string());
+        args.push(ancestor.to_string());
+        args.push(descendant.to_string());
+        exec_git(&args).is_ok()
+    }
+
     // Merge two trees, producing an index that reflects the result of the merge. The index may be written as-is to the working directory or checked out. If the index is to be converted to a tree, the caller should resolve any conflicts that arose as part of the merge.
     #[allow(dead_code)]
     pub fn merge_trees_favor_ours(
@@ -1630,7 +1638,9 @@ impl Repository {
         const MAX_CONCURRENT: usize = 30;
 
         let repo_global_args = self.global_args_for_exec();
-        let semaphore = Arc::new(smol::lock::Semaphore::new(MAX_CONCURRENT));
+        let semaphore = Arc::new(smol::lock::Semaphore::new(crate::memory::concurrency(
+            MAX_CONCURRENT,
+        )));
 
         let futures: Vec<_> = file_paths
             .iter()
@@ -1765,10 +1775,7 @@ impl Repository {
             false
         };
 
-        let output = exec_git(&args)?;
-        let diff_output = String::from_utf8(output.stdout)?;
-
-        let mut result = parse_diff_added_lines(&diff_output)?;
+        let mut result = exec_git_diff_added_lines(&args)?;
 
         if needs_post_filter && let Some(paths) = pathspecs {
             result.retain(|path, _| paths.contains(path));
@@ -1791,6 +1798,7 @@ impl Repository {
         args.push(from_ref.to_string());
         args.push(to_ref.to_string());
 
+        let _timing = timings::phase(Phase::Diff);
         let output = exec_git(&args)?;
 
         // With -z, output is NUL-separated. The output may contain a trailing NUL.
@@ -1808,7 +1816,6 @@ impl Repository {
     /// Returns a HashMap of file paths to vectors of added line numbers
     ///
     /// Similar to diff_added_lines but compares against the working directory
-    #[allow(dead_code)]
     pub fn diff_workdir_added_lines(
         &self,
         from_ref: &str,
@@ -1840,10 +1847,7 @@ impl Repository {
             false
         };
 
-        let output = exec_git(&args)?;
-        let diff_output = String::from_utf8(output.stdout)?;
-
-        let mut result = parse_diff_added_lines(&diff_output)?;
+        let mut result = exec_git_diff_added_lines(&args)?;
 
         if needs_post_filter && let Some(paths) = pathspecs {
             result.retain(|path, _| paths.contains(path));
@@ -1852,57 +1856,6 @@ impl Repository {
         Ok(result)
     }
 
-    /// Get added line ranges from git diff between a commit and the working directory,
-    /// along with information about which lines are pure insertions (old_count=0).
-    ///
-    /// Returns (all_added_lines, pure_insertion_lines)
-    /// Pure insertions are lines that were added without modifying existing lines at that position.
-    #[allow(clippy::type_complexity)]
-    pub fn diff_workdir_added_lines_with_insertions(
-        &self,
-        from_ref: &str,
-        pathspecs: Option<&HashSet<String>>,
-    ) -> Result<(HashMap<String, Vec<u32>>, HashMap<String, Vec<u32>>), GitAiError> {
-        let mut args = self.global_args_for_exec();
-        args.push("diff".to_string());
-        args.push("-U0".to_string()); // Zero context lines
-        args.push("--no-color".to_string());
-        args.push(from_ref.to_string());
-
-        // Add pathspecs if provided (only as CLI args when under threshold)
-        let needs_post_filter = if let Some(paths) = pathspecs {
-            // for case where pathspec filter provided BUT not pathspecs.
-            // otherwise it would default to full repo
-            if paths.is_empty() {
-                return Ok((HashMap::new(), HashMap::new()));
-            }
-            if paths.len() > MAX_PATHSPEC_ARGS {
-                true
-            } else {
-                args.push("--".to_string());
-                for path in paths {
-                    args.push(path.clone());
-                }
-                false
-            }
-        } else {
-            false
-        };
-
-        let output = exec_git(&args)?;
-        let diff_output = String::from_utf8(output.stdout)?;
-
-        let (mut all_added, mut pure_insertions) =
-            parse_diff_added_lines_with_insertions(&diff_output)?;
-
-        if needs_post_filter && let Some(paths) = pathspecs {
-            all_added.retain(|path, _| paths.contains(path));
-            pure_insertions.retain(|path, _| paths.contains(path));
-        }
-
-        Ok((all_added, pure_insertions))
-    }
-
     pub fn fetch_branch(&self, branch_name: &str, remote_name: &str) -> Result<(), GitAiError> {
         let mut args = self.global_args_for_exec();
         args.push("fetch".to_string());
@@ -2152,6 +2105,7 @@ pub fn group_files_by_repository(
 
 /// Helper to execute a git command
 pub fn exec_git(args: &[String]) -> Result<Output, GitAiError> {
+    crate::network::ensure_git_allowed(args)?;
     // TODO Make sure to handle process signals, etc.
     let mut cmd = Command::new(config::Config::get().git_cmd());
     cmd.args(args);
@@ -2180,6 +2134,7 @@ pub fn exec_git(args: &[String]) -> Result<Output, GitAiError> {
 
 /// Helper to execute a git command with data provided on stdin
 pub fn exec_git_stdin(args: &[String], stdin_data: &[u8]) -> Result<Output, GitAiError> {
+    crate::network::ensure_git_allowed(args)?;
     // TODO Make sure to handle process signals, etc.
     let mut cmd = Command::new(config::Config::get().git_cmd());
     cmd.args(args)
@@ -2225,6 +2180,7 @@ pub fn exec_git_stdin_with_env(
     env: &[(String, String)],
     stdin_data: &[u8],
 ) -> Result<Output, GitAiError> {
+    crate::network::ensure_git_allowed(args)?;
     // TODO Make sure to handle process signals, etc.
     let mut cmd = Command::new(config::Config::get().git_cmd());
     cmd.args(args)
@@ -2302,10 +2258,23 @@ fn parse_git_version(version_str: &str) -> Option<(u32, u32, u32)> {
 /// This means: old file line 10 (2 lines), new file line 15 (5 lines)
 /// We extract the "new file" line numbers to know which lines were added.
 fn parse_diff_added_lines(diff_output: &str) -> Result<HashMap<String, Vec<u32>>, GitAiError> {
-    let mut result: HashMap<String, Vec<u32>> = HashMap::new();
-    let mut current_file: Option<String> = None;
-
+    let mut parser = DiffAddedLinesParser::default();
     for line in diff_output.lines() {
+        parser.push_line(line);
+    }
+    Ok(parser.finish())
+}
+
+/// Incremental form of [`parse_diff_added_lines`], fed one line at a time so a diff
+/// can be parsed as git streams it
+#[derive(Default)]
+struct DiffAddedLinesParser {
+    result: HashMap<String, Vec<u32>>,
+    current_file: Option<String>,
+}
+
+impl DiffAddedLinesParser {
+    fn push_line(&mut self, line: &str) {
         // Track current file being diffed
         // Git outputs paths in two formats:
         // 1. Unquoted: +++ b/path/to/file.txt (or w/ for workdir diffs)
@@ -2314,11 +2283,11 @@ fn parse_diff_added_lines(diff_output: &str) -> Result<HashMap<String, Vec<u32>>
             // Unquoted path (ASCII only)
             // Note: Git adds trailing tab after filenames with spaces, so we trim_end
             let file_path = crate::utils::unescape_git_path(raw_path.trim_end());
-            current_file = Some(file_path);
+            self.current_file = Some(file_path);
         } else if let Some(raw_path) = line.strip_prefix("+++ w/") {
             // Workdir diff uses w/ prefix instead of b/
             let file_path = crate::utils::unescape_git_path(raw_path.trim_end());
-            current_file = Some(file_path);
+            self.current_file = Some(file_path);
         } else if line.starts_with("+++ \"") {
             // Quoted path (non-ASCII chars) - unescape the entire quoted portion after "+++ "
             if let Some(quoted_suffix) = line.strip_prefix("+++ ") {
@@ -2332,105 +2301,104 @@ fn parse_diff_added_lines(diff_output: &str) -> Result<HashMap<String, Vec<u32>>
                 } else {
                     unescaped
                 };
-                current_file = Some(file_path);
+                self.current_file = Some(file_path);
             }
         } else if line.starts_with("+++ /dev/null") {
             // File was deleted
-            current_file = None;
+            self.current_file = None;
         } else if line.starts_with("@@ ") {
             // Parse hunk header: @@ -old_start,old_count +new_start,new_count @@
-            if let Some(ref file) = current_file
+            if let Some(ref file) = self.current_file
                 && let Some((added_lines, _is_pure_insertion)) = parse_hunk_header(line)
             {
-                result.entry(file.clone()).or_default().extend(added_lines);
+                self.result
+                    .entry(file.clone())
+                    .or_default()
+                    .extend(added_lines);
             }
         }
     }
 
-    // Sort and deduplicate line numbers for each file
-    for lines in result.values_mut() {
-        lines.sort_unstable();
-        lines.dedup();
+    fn finish(mut self) -> HashMap<String, Vec<u32>> {
+        // Sort and deduplicate line numbers for each file
+        for lines in self.result.values_mut() {
+            lines.sort_unstable();
+            lines.dedup();
+        }
+        self.result
     }
+}
 
-    Ok(result)
+/// Run a `git diff -U0` and collect the added lines per file. In low-memory mode the
+/// diff is parsed as git streams it rather than buffered whole; only its headers matter.
+fn exec_git_diff_added_lines(args: &[String]) -> Result<HashMap<String, Vec<u32>>, GitAiError> {
+    let _timing = timings::phase(Phase::Diff);
+    if !crate::memory::is_low_memory() {
+        let output = exec_git(args)?;
+        let diff_output = String::from_utf8(output.stdout)?;
+        return parse_diff_added_lines(&diff_output);
+    }
+    let mut parser = DiffAddedLinesParser::default();
+    exec_git_lines(args, |line| parser.push_line(line))?;
+    Ok(parser.finish())
 }
 
-/// Parses the unified diff output to extract line numbers of added lines,
-/// along with information about which are pure insertions (old_count=0).
-///
-/// Returns (all_added_lines, pure_insertion_lines)
-#[allow(clippy::type_complexity)]
-fn parse_diff_added_lines_with_insertions(
-    diff_output: &str,
-) -> Result<(HashMap<String, Vec<u32>>, HashMap<String, Vec<u32>>), GitAiError> {
-    let mut all_lines: HashMap<String, Vec<u32>> = HashMap::new();
-    let mut insertion_lines: HashMap<String, Vec<u32>> = HashMap::new();
-    let mut current_file: Option<String> = None;
+/// Execute a git command, handing each line of stdout to `on_line` as it's read
+fn exec_git_lines(args: &[String], mut on_line: impl FnMut(&str)) -> Result<(), GitAiError> {
+    crate::network::ensure_git_allowed(args)?;
+    use std::io::{BufRead, Read};
 
-    for line in diff_output.lines() {
-        // Track current file being diffed
-        // Git outputs paths in two formats:
-        // 1. Unquoted: +++ b/path/to/file.txt (or w/ for workdir diffs)
-        // 2. Quoted (for non-ASCII): +++ "b/path/to/file.txt" (with octal escapes inside)
-        if let Some(raw_path) = line.strip_prefix("+++ b/") {
-            // Unquoted path (ASCII only)
-            // Note: Git adds trailing tab after filenames with spaces, so we trim_end
-            let file_path = crate::utils::unescape_git_path(raw_path.trim_end());
-            current_file = Some(file_path);
-        } else if let Some(raw_path) = line.strip_prefix("+++ w/") {
-            // Workdir diff uses w/ prefix instead of b/
-            let file_path = crate::utils::unescape_git_path(raw_path.trim_end());
-            current_file = Some(file_path);
-        } else if line.starts_with("+++ \"") {
-            // Quoted path (non-ASCII chars) - unescape the entire quoted portion after "+++ "
-            if let Some(quoted_suffix) = line.strip_prefix("+++ ") {
-                let unescaped = crate::utils::unescape_git_path(quoted_suffix);
-                // Strip the prefix (b/ or w/) after unescaping
-                let file_path = if let Some(stripped) = unescaped
-                    .strip_prefix("b/")
-                    .or(unescaped.strip_prefix("w/"))
-                {
-                    stripped.to_string()
-                } else {
-                    unescaped
-                };
-                current_file = Some(file_path);
-            }
-        } else if line.starts_with("+++ /dev/null") {
-            // File was deleted
-            current_file = None;
-        } else if line.starts_with("@@ ") {
-            // Parse hunk header: @@ -old_start,old_count +new_start,new_count @@
-            if let Some(ref file) = current_file
-                && let Some((added_lines, is_pure_insertion)) = parse_hunk_header(line)
-            {
-                all_lines
-                    .entry(file.clone())
-                    .or_default()
-                    .extend(added_lines.clone());
+    let mut cmd = Command::new(config::Config::get().git_cmd());
+    cmd.args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
 
-                if is_pure_insertion {
-                    insertion_lines
-                        .entry(file.clone())
-                        .or_default()
-                        .extend(added_lines);
-                }
-            }
+    #[cfg(windows)]
+    {
+        if !is_interactive_terminal() {
+            cmd.creation_flags(CREATE_NO_WINDOW);
         }
     }
 
-    // Sort and deduplicate line numbers for each file
-    for lines in all_lines.values_mut() {
-        lines.sort_unstable();
-        lines.dedup();
-    }
-    for lines in insertion_lines.values_mut() {
-        lines.sort_unstable();
-        lines.dedup();
+    let mut child = cmd.spawn().map_err(GitAiError::IoError)?;
+    // Drain stderr alongside stdout so a chatty command can't block on a full pipe
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = std::io::BufReader::new(stdout);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader
+                .read_until(b'\n', &mut line)
+                .map_err(GitAiError::IoError)?
+                == 0
+            {
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            on_line(text.trim_end_matches(['\n', '\r']));
+        }
     }
 
-    Ok((all_lines, insertion_lines))
+    let status = child.wait().map_err(GitAiError::IoError)?;
+    let stderr = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    if !status.success() {
+        return Err(GitAiError::GitCliError {
+            code: status.code(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+            args: args.to_vec(),
+        });
+    }
+    Ok(())
 }
 
 /// Parse a hunk header line to extract added line numbers and whether it's a pure insertion
@@ -2585,69 +2553,4 @@ mod tests {
             files
         );
     }
-
-    #[test]
-    fn test_parse_diff_added_lines_with_insertions_standard_prefix() {
-        // Test diff with standard b/ prefix (commit-to-commit diff)
-        let diff = r#"diff --git a/test.txt b/test.txt
-index 0000000..abc1234 100644
---- a/test.txt
-+++ b/test.txt
-@@ -0,0 +1,2 @@
-+line 1
-+line 2"#;
-
-        let (added_lines, insertion_lines) = parse_diff_added_lines_with_insertions(diff).unwrap();
-        assert_eq!(added_lines.get("test.txt"), Some(&vec![1, 2]));
-        assert_eq!(insertion_lines.get("test.txt"), Some(&vec![1, 2]));
-    }
-
-    #[test]
-    fn test_parse_diff_added_lines_with_insertions_workdir_prefix() {
-        // Test diff with w/ prefix (commit-to-workdir diff)
-        let diff = r#"diff --git c/test.txt w/test.txt
-index a751413..8adaa6c 100644
---- c/test.txt
-+++ w/test.txt
-@@ -0,0 +1,2 @@
-+// AI added line 1
-+// AI added line 2"#;
-
-        let (added_lines, insertion_lines) = parse_diff_added_lines_with_insertions(diff).unwrap();
-        assert_eq!(added_lines.get("test.txt"), Some(&vec![1, 2]));
-        assert_eq!(insertion_lines.get("test.txt"), Some(&vec![1, 2]));
-    }
-
-    #[test]
-    fn test_parse_diff_added_lines_with_insertions_quoted_paths() {
-        // Test diff with quoted paths containing spaces
-        let diff = r#"diff --git "a/my file.txt" "b/my file.txt"
-index 0000000..abc1234 100644
---- "a/my file.txt"
-+++ "b/my file.txt"
-@@ -0,0 +1,3 @@
-+line 1
-+line 2
-+line 3"#;
-
-        let (added_lines, insertion_lines) = parse_diff_added_lines_with_insertions(diff).unwrap();
-        assert_eq!(added_lines.get("my file.txt"), Some(&vec![1, 2, 3]));
-        assert_eq!(insertion_lines.get("my file.txt"), Some(&vec![1, 2, 3]));
-    }
-
-    #[test]
-    fn test_parse_diff_added_lines_with_insertions_quoted_workdir_paths() {
-        // Test diff with quoted w/ paths
-        let diff = r#"diff --git "c/my file.txt" "w/my file.txt"
-index 0000000..abc1234 100644
---- "c/my file.txt"
-+++ "w/my file.txt"
-@@ -0,0 +1,2 @@
-+line 1
-+line 2"#;
-
-        let (added_lines, insertion_lines) = parse_diff_added_lines_with_insertions(diff).unwrap();
-        assert_eq!(added_lines.get("my file.txt"), Some(&vec![1, 2]));
-        assert_eq!(insertion_lines.get("my file.txt"), Some(&vec![1, 2]));
-    }
 }
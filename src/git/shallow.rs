@@ -0,0 +1,65 @@
+//! Shallow clone (`git clone --depth`) awareness.
+//!
+//! History stops at the shallow boundary: those commits are present but their parents
+//! aren't, so revisions past it don't resolve and diffs against a boundary commit's
+//! "parent" see the whole tree as added.
+
+use crate::error::GitAiError;
+use crate::git::repository::{Repository, exec_git};
+
+/// Depth fetched by the first `--deepen` step; each further step doubles it
+const INITIAL_DEEPEN_STEP: u32 = 64;
+/// Give up deepening after this many fetches
+const MAX_DEEPEN_STEPS: usize = 8;
+
+pub fn is_shallow(repo: &Repository) -> bool {
+    repo.path().join("shallow").is_file()
+}
+
+/// Commits at the shallow boundary, whose parents are missing
+pub fn shallow_boundary(repo: &Repository) -> Vec<String> {
+    std::fs::read_to_string(repo.path().join("shallow"))
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// The boundary commit `rev`'s available history stops at, if any
+pub fn boundary_ancestor_of(repo: &Repository, rev: &str) -> Option<String> {
+    shallow_boundary(repo).into_iter().find(|boundary| {
+        let mut args = repo.global_args_for_exec();
+        args.extend(["merge-base", "--is-ancestor", boundary, rev].map(str::to_string));
+        exec_git(&args).is_ok()
+    })
+}
+
+/// Fetch more history from `remote`, doubling the depth each step, until `is_available`
+/// holds or the clone is no longer shallow. Returns whether `is_available` was satisfied.
+pub fn deepen_until(
+    repo: &Repository,
+    remote: &str,
+    mut is_available: impl FnMut() -> bool,
+) -> Result<bool, GitAiError> {
+    let mut step = INITIAL_DEEPEN_STEP;
+    for _ in 0..MAX_DEEPEN_STEPS {
+        if is_available() {
+            return Ok(true);
+        }
+        if !is_shallow(repo) {
+            return Ok(false);
+        }
+        eprintln!(
+            "Fetching {} more commits of history from {}...",
+            step, remote
+        );
+        let mut args = repo.global_args_for_exec();
+        args.extend(
+            ["fetch", "--no-tags", &format!("--deepen={}", step), remote].map(str::to_string),
+        );
+        exec_git(&args)?;
+        step = step.saturating_mul(2);
+    }
+    Ok(is_available())
+}
@@ -1,6 +1,6 @@
 use crate::error::GitAiError;
 use crate::git::repository::{Repository, exec_git};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::str;
 
 /// Maximum number of pathspec arguments to pass on the command line.
@@ -87,6 +87,39 @@ impl Repository {
         Ok(filenames)
     }
 
+    /// Index content of every staged file that still exists in the index.
+    /// Binary files are skipped, since they can't be attributed line by line.
+    pub fn get_staged_file_contents(&self) -> Result<HashMap<String, String>, GitAiError> {
+        let mut args = self.global_args_for_exec();
+        args.push("diff".to_string());
+        args.push("--cached".to_string());
+        args.push("--name-only".to_string());
+        args.push("--diff-filter=d".to_string());
+        args.push("-z".to_string());
+
+        let output = exec_git(&args)?;
+
+        let mut contents = HashMap::new();
+        for bytes in output.stdout.split(|&b| b == 0) {
+            let Ok(filename) = str::from_utf8(bytes) else {
+                continue;
+            };
+            if filename.is_empty() {
+                continue;
+            }
+
+            let mut show_args = self.global_args_for_exec();
+            show_args.push("show".to_string());
+            show_args.push(format!(":{}", filename));
+            let output = exec_git(&show_args)?;
+            if let Ok(content) = String::from_utf8(output.stdout) {
+                contents.insert(filename.to_string(), content);
+            }
+        }
+
+        Ok(contents)
+    }
+
     // Get status for tracked files that changed
     pub fn get_staged_and_unstaged_filenames(&self) -> Result<HashSet<String>, GitAiError> {
         let mut args = self.global_args_for_exec();
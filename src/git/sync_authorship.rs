@@ -1,13 +1,23 @@
 use crate::git::refs::{
-    AI_AUTHORSHIP_PUSH_REFSPEC, copy_ref, merge_notes_from_ref, ref_exists, tracking_ref_for_remote,
+    AI_AUTHORSHIP_PUSH_REFSPEC, AI_DETAILS_PUSH_REFSPEC, AI_DETAILS_REF, AI_DETAILS_REFNAME,
+    copy_ref, details_tracking_ref_for_remote, merge_notes_from_ref, merge_notes_into, ref_exists,
+    tracking_ref_for_remote,
 };
+use crate::observability::timings::{self, Phase};
 use crate::{
     error::GitAiError,
     git::{cli_parser::ParsedGitInvocation, repository::exec_git},
     utils::debug_log,
 };
 
-use super::repository::Repository;
+use super::repo_storage::BlockedNotesPush;
+use super::repository::{Repository, find_repository};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 /// Result of checking for authorship notes on a remote
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +28,404 @@ pub enum NotesExistence {
     NotFound,
 }
 
+/// A notes network operation that hooks run in the background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotesSyncOp {
+    Fetch,
+    Push,
+}
+
+impl NotesSyncOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotesSyncOp::Fetch => "fetch",
+            NotesSyncOp::Push => "push",
+        }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        match input {
+            "fetch" => Some(NotesSyncOp::Fetch),
+            "push" => Some(NotesSyncOp::Push),
+            _ => None,
+        }
+    }
+}
+
+/// How long a queued operation waits before a wrapped git command retries it
+const PENDING_SYNC_RETRY_INTERVAL_SECS: i64 = 60;
+
+/// Run a notes fetch or push against `remote_name`. If it fails because the remote can't
+/// be reached, the operation is queued in `.git/ai/pending-sync` to be retried later;
+/// any other outcome drops a queued copy.
+pub fn run_notes_sync(
+    repository: &Repository,
+    op: NotesSyncOp,
+    remote_name: &str,
+) -> Result<(), GitAiError> {
+    let result = match op {
+        NotesSyncOp::Fetch => fetch_authorship_notes(repository, remote_name).map(|_| ()),
+        NotesSyncOp::Push => push_authorship_notes(repository, remote_name),
+    };
+    record_notes_sync(repository, op, remote_name, result)
+}
+
+/// Queue or drop the retry of a notes sync, and track notes push blocks, by its result
+fn record_notes_sync(
+    repository: &Repository,
+    op: NotesSyncOp,
+    remote_name: &str,
+    result: Result<(), GitAiError>,
+) -> Result<(), GitAiError> {
+    let storage = &repository.storage;
+    let queue_result = match &result {
+        Ok(()) => storage.remove_pending_sync(op, remote_name),
+        Err(e) if is_network_error(e) => {
+            debug_log(&format!(
+                "remote '{}' unreachable, queued authorship notes {} for retry",
+                remote_name,
+                op.as_str()
+            ));
+            storage.queue_pending_sync(op, remote_name)
+        }
+        // Reached the remote but it failed anyway; retrying won't help
+        Err(_) => storage.remove_pending_sync(op, remote_name),
+    };
+    if let Err(e) = queue_result {
+        debug_log(&format!("failed to update pending notes sync queue: {}", e));
+    }
+
+    if op == NotesSyncOp::Push {
+        let blocked = match &result {
+            Ok(()) => None,
+            Err(e) => notes_push_rejection(e).map(|reason| BlockedNotesPush {
+                remote: remote_name.to_string(),
+                reason,
+                since: chrono::Utc::now().timestamp(),
+                warned: false,
+            }),
+        };
+        // Any other failure says nothing about the policy, so a known block is kept
+        if (result.is_ok() || blocked.is_some())
+            && let Err(e) = storage.set_blocked_notes_push(remote_name, blocked)
+        {
+            debug_log(&format!("failed to update blocked notes push state: {}", e));
+        }
+    }
+
+    result
+}
+
+/// The server's rejection line if a notes push failed because the host refuses updates
+/// to `refs/notes/*` (ref policy, protected or hidden refs, a declining hook), as opposed
+/// to a non-fast-forward or connection problem.
+pub fn notes_push_rejection(error: &GitAiError) -> Option<String> {
+    const POLICY_REJECTIONS: &[&str] = &[
+        "[remote rejected]",
+        "hook declined",
+        "hidden ref",
+        "refusing to update",
+        "not allowed to push",
+    ];
+    let GitAiError::GitCliError { stderr, .. } = error else {
+        return None;
+    };
+    stderr
+        .lines()
+        .find(|line| {
+            let line = line.to_lowercase();
+            line.contains("refs/notes/")
+                && POLICY_REJECTIONS.iter().any(|needle| line.contains(needle))
+        })
+        .map(|line| line.trim().to_string())
+}
+
+/// Tell the user, once, that `remote_name` blocks notes pushes. Called from foreground
+/// hooks since the rejection itself may have happened in a background sync.
+pub fn warn_if_notes_push_blocked(repository: &Repository, remote_name: &str) {
+    let Some(mut blocked) = repository.storage.blocked_notes_push(remote_name) else {
+        return;
+    };
+    if blocked.warned {
+        return;
+    }
+    eprintln!(
+        "warning: {} rejected the push of git-ai authorship notes:\n  {}\n\
+         The host may not allow refs/notes/* pushes, so git-ai will stop pushing notes there.\n\
+         To share them another way:  git bundle create ai-notes.bundle refs/notes/ai\n\
+         Once the host allows it, retry with:  git-ai sync push {}",
+        remote_name, blocked.reason, remote_name
+    );
+    blocked.warned = true;
+    if let Err(e) = repository
+        .storage
+        .set_blocked_notes_push(remote_name, Some(blocked))
+    {
+        debug_log(&format!("failed to update blocked notes push state: {}", e));
+    }
+}
+
+/// Whether a git failure means the remote couldn't be reached (offline, DNS, refused
+/// connection) rather than being rejected by it.
+pub fn is_network_error(error: &GitAiError) -> bool {
+    const NETWORK_ERRORS: &[&str] = &[
+        "could not resolve host",
+        "could not resolve hostname",
+        "temporary failure in name resolution",
+        "failed to connect",
+        "connection refused",
+        "connection timed out",
+        "operation timed out",
+        "connection reset",
+        "network is unreachable",
+        "no route to host",
+        "ssh: connect to host",
+    ];
+    match error {
+        GitAiError::GitCliError { stderr, .. } => {
+            let stderr = stderr.to_lowercase();
+            NETWORK_ERRORS.iter().any(|needle| stderr.contains(needle))
+        }
+        _ => false,
+    }
+}
+
+/// Retry queued notes operations from a detached `git-ai sync --flush` if any are due.
+/// Called after wrapped git commands so a reconnect is picked up without user action.
+pub fn maybe_flush_pending_sync(repository: &Repository) {
+    let mut entries = repository.storage.read_pending_sync();
+    let now = chrono::Utc::now().timestamp();
+    if !entries
+        .iter()
+        .any(|entry| now - entry.last_attempt >= PENDING_SYNC_RETRY_INTERVAL_SECS)
+    {
+        return;
+    }
+
+    // Claim the retry so concurrent git commands don't each spawn a flush
+    for entry in entries.iter_mut() {
+        entry.last_attempt = now;
+    }
+    if let Err(e) = repository.storage.write_pending_sync(&entries) {
+        debug_log(&format!("failed to update pending notes sync queue: {}", e));
+        return;
+    }
+    spawn_detached_sync(repository, &["--flush"]);
+}
+
+/// Upper bound on concurrent notes fetch/push operations when syncing several remotes
+const MAX_PARALLEL_NOTES_SYNCS: usize = 4;
+
+/// Run `op` against every remote, a few at a time. Each worker thread opens its own
+/// repository handle from `repository`'s global args. Fetches only land in the remotes'
+/// tracking refs there; merging them into refs/notes/ai updates the same ref, so that's
+/// done afterwards, one remote at a time. Results are in `remotes` order.
+pub fn run_notes_sync_all(
+    repository: &Repository,
+    op: NotesSyncOp,
+    remotes: &[String],
+) -> Vec<(String, Result<(), GitAiError>)> {
+    if remotes.len() <= 1 {
+        return remotes
+            .iter()
+            .map(|remote| (remote.clone(), run_notes_sync(repository, op, remote)))
+            .collect();
+    }
+
+    let global_args = repository.global_args_for_exec();
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<(), GitAiError>>>> =
+        Mutex::new(remotes.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..remotes.len().min(MAX_PARALLEL_NOTES_SYNCS) {
+            scope.spawn(|| {
+                let repo = find_repository(&global_args);
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(remote) = remotes.get(index) else {
+                        break;
+                    };
+                    let result = match &repo {
+                        Ok(repo) if op == NotesSyncOp::Fetch => {
+                            let fetched = fetch_notes_to_tracking_ref(repo, remote).map(|_| ());
+                            record_notes_sync(repo, op, remote, fetched)
+                        }
+                        Ok(repo) => run_notes_sync(repo, op, remote),
+                        Err(e) => Err(GitAiError::Generic(format!(
+                            "failed to open repository: {}",
+                            e
+                        ))),
+                    };
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    remotes
+        .iter()
+        .cloned()
+        .zip(results.into_inner().unwrap())
+        .map(|(remote, result)| {
+            let result = result.unwrap_or_else(|| {
+                Err(GitAiError::Generic(
+                    "authorship notes sync thread panicked".to_string(),
+                ))
+            });
+            if op == NotesSyncOp::Fetch && result.is_ok() {
+                merge_fetched_notes(repository, &remote);
+            }
+            (remote, result)
+        })
+        .collect()
+}
+
+/// A notes fetch/push running alongside the user's git command, as a `git-ai sync` child
+/// process so it can outlive this one without being started a second time.
+pub struct NotesSyncHandle {
+    child: Result<Child, GitAiError>,
+    op: NotesSyncOp,
+    remotes: Vec<String>,
+    started: Instant,
+}
+
+impl NotesSyncHandle {
+    pub fn spawn(repository: &Repository, op: NotesSyncOp, remotes: Vec<String>) -> Self {
+        debug_log(&format!(
+            "started {} of authorship notes with remotes: {}",
+            op.as_str(),
+            remotes.join(", ")
+        ));
+        let mut args = vec![op.as_str()];
+        args.extend(remotes.iter().map(String::as_str));
+        let child = spawn_sync_process(repository, &args);
+        NotesSyncHandle {
+            child,
+            op,
+            remotes,
+            started: Instant::now(),
+        }
+    }
+
+    /// Wait for the operation, but no longer than the hook network budget (counted from
+    /// when it started). Past the budget the `git-ai sync` process is left to finish in
+    /// the background, so a slow remote never holds up the terminal. Returns `None` when
+    /// the work was deferred.
+    pub fn wait_or_defer(self, repository: &Repository) -> Option<Result<(), GitAiError>> {
+        let deadline = self.started + crate::config::Config::get().hook_network_budget();
+        let mut child = match self.child {
+            Ok(child) => child,
+            Err(e) => {
+                debug_log(&format!(
+                    "failed to start authorship {}: {}",
+                    self.op.as_str(),
+                    e
+                ));
+                return Some(Err(e));
+            }
+        };
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() >= deadline => {
+                    debug_log(&format!(
+                        "authorship notes {} with {} exceeded the hook budget; continuing in background",
+                        self.op.as_str(),
+                        self.remotes.join(", ")
+                    ));
+                    return None;
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                Err(e) => return Some(Err(GitAiError::IoError(e))),
+            }
+        };
+
+        if status.success() {
+            return Some(Ok(()));
+        }
+        let error = GitAiError::Generic(format!(
+            "authorship notes {} with {} failed (see {})",
+            self.op.as_str(),
+            self.remotes.join(", "),
+            repository.storage.logs.join("sync.log").display()
+        ));
+        debug_log(&error.to_string());
+        Some(Err(error))
+    }
+}
+
+/// Start `git-ai sync <args>` detached from the current process, for retries nothing
+/// waits on.
+fn spawn_detached_sync(repository: &Repository, args: &[&str]) {
+    if let Err(e) = spawn_sync_process(repository, args) {
+        debug_log(&format!(
+            "failed to spawn background authorship sync: {}",
+            e
+        ));
+    }
+}
+
+/// Start `git-ai sync <args>` with its output appended to `.git/ai/logs/sync.log`. It
+/// keeps running if this process exits first.
+fn spawn_sync_process(repository: &Repository, args: &[&str]) -> Result<Child, GitAiError> {
+    let exe = crate::utils::current_git_ai_exe()?;
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(repository.storage.logs.join("sync.log"));
+    let (stdout, stderr) = match log_file.and_then(|f| Ok((f.try_clone()?, f))) {
+        Ok((out, err)) => (Stdio::from(out), Stdio::from(err)),
+        Err(_) => (Stdio::null(), Stdio::null()),
+    };
+    let dir = repository
+        .workdir()
+        .unwrap_or_else(|_| repository.path().to_path_buf());
+
+    Ok(Command::new(exe)
+        .arg("sync")
+        .args(args)
+        .current_dir(dir)
+        // Run as git-ai even when this process was started in git-wrapper mode
+        .env_remove("GIT_AI")
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()?)
+}
+
+/// Remotes a fetch/pull talks to: every remote for `--all`, the named remotes for
+/// `--multiple`, otherwise the single remote from [`fetch_remote_from_args`].
+pub fn fetch_remotes_from_args(
+    repository: &Repository,
+    parsed_args: &ParsedGitInvocation,
+) -> Result<Vec<String>, GitAiError> {
+    let args = &parsed_args.command_args;
+    let remote_names: Vec<String> = repository
+        .remotes()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    if args.iter().any(|a| a == "--all") && !remote_names.is_empty() {
+        return Ok(remote_names);
+    }
+    if args.iter().any(|a| a == "--multiple") {
+        let named: Vec<String> = args
+            .iter()
+            .filter(|a| remote_names.contains(a))
+            .cloned()
+            .collect();
+        if !named.is_empty() {
+            return Ok(named);
+        }
+    }
+
+    fetch_remote_from_args(repository, parsed_args).map(|remote| vec![remote])
+}
+
 pub fn fetch_remote_from_args(
     repository: &Repository,
     parsed_args: &ParsedGitInvocation,
@@ -65,6 +473,20 @@ pub fn fetch_authorship_notes(
     repository: &Repository,
     remote_name: &str,
 ) -> Result<NotesExistence, GitAiError> {
+    let existence = fetch_notes_to_tracking_ref(repository, remote_name)?;
+    if existence == NotesExistence::Found {
+        merge_fetched_notes(repository, remote_name);
+    }
+    Ok(existence)
+}
+
+/// Fetch `remote_name`'s notes into its tracking ref, without merging them into
+/// refs/notes/ai
+fn fetch_notes_to_tracking_ref(
+    repository: &Repository,
+    remote_name: &str,
+) -> Result<NotesExistence, GitAiError> {
+    let _timing = timings::phase(Phase::Fetch);
     // Generate tracking ref for this remote
     let tracking_ref = tracking_ref_for_remote(remote_name);
 
@@ -102,6 +524,17 @@ pub fn fetch_authorship_notes(
                 "found authorship notes on remote '{}'",
                 remote_name
             ));
+
+            // The tracking ref already points at the remote's notes commit, so there is
+            // nothing to transfer. It's still merged in case that failed last time.
+            let remote_sha = result.split_whitespace().next().unwrap_or_default();
+            if resolve_ref(repository, &tracking_ref).as_deref() == Some(remote_sha) {
+                debug_log(&format!(
+                    "authorship notes from '{}' already up to date at {}",
+                    remote_name, remote_sha
+                ));
+                return Ok(NotesExistence::Found);
+            }
         }
         Err(e) => {
             debug_log(&format!(
@@ -114,21 +547,7 @@ pub fn fetch_authorship_notes(
     }
 
     // Now fetch the notes to the tracking ref with explicit refspec
-    let fetch_refspec = format!("+refs/notes/ai:{}", tracking_ref);
-
-    // Build the internal authorship fetch with explicit flags and disabled hooks
-    // IMPORTANT: use repository.global_args_for_exec() to ensure -C flag is present for bare repos
-    let mut fetch_authorship: Vec<String> = repository.global_args_for_exec();
-    fetch_authorship.push("-c".to_string());
-    fetch_authorship.push("core.hooksPath=/dev/null".to_string());
-    fetch_authorship.push("fetch".to_string());
-    fetch_authorship.push("--no-tags".to_string());
-    fetch_authorship.push("--recurse-submodules=no".to_string());
-    fetch_authorship.push("--no-write-fetch-head".to_string());
-    fetch_authorship.push("--no-write-commit-graph".to_string());
-    fetch_authorship.push("--no-auto-maintenance".to_string());
-    fetch_authorship.push(remote_name.to_string());
-    fetch_authorship.push(fetch_refspec.clone());
+    let fetch_authorship = notes_fetch_args(repository, remote_name, &tracking_ref);
 
     debug_log(&format!("fetch command: {:?}", fetch_authorship));
 
@@ -149,17 +568,202 @@ pub fn fetch_authorship_notes(
         }
     }
 
-    // After successful fetch, merge the tracking ref into refs/notes/ai
+    Ok(NotesExistence::Found)
+}
+
+/// Fetch the default remote's refs/notes/ai-details, which plain notes syncs leave out,
+/// and merge it into the local one. Only tried once per process: a summary whose full
+/// note is still missing after that has none to fetch.
+pub fn fetch_note_details(repository: &Repository) {
+    static FETCHED: AtomicBool = AtomicBool::new(false);
+    if FETCHED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let Ok(Some(remote_name)) = repository.get_default_remote() else {
+        return;
+    };
+    if let Err(e) = fetch_details_ref(repository, &remote_name) {
+        debug_log(&format!(
+            "failed to fetch note details from '{}': {}",
+            remote_name, e
+        ));
+    }
+}
+
+/// Fetch `remote_name`'s refs/notes/ai-details into its tracking ref and merge it into
+/// the local one
+fn fetch_details_ref(repository: &Repository, remote_name: &str) -> Result<(), GitAiError> {
+    let _timing = timings::phase(Phase::Fetch);
+    let tracking_ref = details_tracking_ref_for_remote(remote_name);
+    let mut args = repository.global_args_for_exec();
+    args.extend(
+        [
+            "-c",
+            "core.hooksPath=/dev/null",
+            "fetch",
+            "--no-tags",
+            "--recurse-submodules=no",
+            "--no-write-fetch-head",
+            "--no-write-commit-graph",
+            "--no-auto-maintenance",
+            remote_name,
+        ]
+        .map(str::to_string),
+    );
+    args.push(format!("+{}:{}", AI_DETAILS_REF, tracking_ref));
+    exec_git(&args)?;
+
+    if ref_exists(repository, AI_DETAILS_REF) {
+        merge_notes_into(repository, AI_DETAILS_REFNAME, &tracking_ref)
+    } else {
+        copy_ref(repository, &tracking_ref, AI_DETAILS_REF)
+    }
+}
+
+/// Build the hook-free `git fetch` of the remote's notes into `tracking_ref`.
+///
+/// Negotiation is limited to the local notes refs: notes commits never share history
+/// with code, so advertising branch tips as "haves" only slows the exchange down. With
+/// the previous tracking ref as a tip, an incremental fetch transfers only new notes.
+fn notes_fetch_args(repository: &Repository, remote_name: &str, tracking_ref: &str) -> Vec<String> {
+    // IMPORTANT: use repository.global_args_for_exec() to ensure -C flag is present for bare repos
+    let mut args: Vec<String> = repository.global_args_for_exec();
+    args.push("-c".to_string());
+    args.push("core.hooksPath=/dev/null".to_string());
+    args.push("fetch".to_string());
+    args.push("--no-tags".to_string());
+    args.push("--recurse-submodules=no".to_string());
+    args.push("--no-write-fetch-head".to_string());
+    args.push("--no-write-commit-graph".to_string());
+    args.push("--no-auto-maintenance".to_string());
+    for tip in [tracking_ref, "refs/notes/ai"] {
+        if ref_exists(repository, tip) {
+            args.push(format!("--negotiation-tip={}", tip));
+        }
+    }
+    args.push(remote_name.to_string());
+    args.push(format!("+refs/notes/ai:{}", tracking_ref));
+    args
+}
+
+/// Fetch refspec that lands `remote_name`'s notes in its tracking ref. Fetching into the
+/// tracking ref rather than `refs/notes/ai` keeps a plain `git fetch` from overwriting
+/// local notes that haven't been pushed yet; the next notes sync merges them.
+pub fn notes_fetch_refspec(remote_name: &str) -> String {
+    format!("+refs/notes/ai:{}", tracking_ref_for_remote(remote_name))
+}
+
+/// Whether `remote_name` has [`notes_fetch_refspec`] configured, so a plain `git fetch`
+/// of it already brings its notes
+pub fn has_notes_fetch_refspec(repository: &Repository, remote_name: &str) -> bool {
+    remote_refspecs(repository, remote_name, "fetch").contains(&notes_fetch_refspec(remote_name))
+}
+
+/// Merge the notes a plain `git fetch` of `remote_name` landed in its tracking ref
+pub fn merge_fetched_notes(repository: &Repository, remote_name: &str) {
+    merge_tracking_ref_into_local_notes(repository, &tracking_ref_for_remote(remote_name));
+}
+
+/// Refspecs configured in `remote.<remote_name>.<direction>` (`fetch` or `push`)
+pub fn remote_refspecs(repository: &Repository, remote_name: &str, direction: &str) -> Vec<String> {
+    let mut args = repository.global_args_for_exec();
+    args.extend([
+        "config".to_string(),
+        "--get-all".to_string(),
+        format!("remote.{}.{}", remote_name, direction),
+    ]);
+    // Exits 1 when the key is unset, which just means there are none
+    exec_git(&args)
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Append `refspec` to `remote.<remote_name>.<direction>` unless it is already
+/// configured. Returns whether the config changed.
+pub fn ensure_remote_refspec(
+    repository: &Repository,
+    remote_name: &str,
+    direction: &str,
+    refspec: &str,
+) -> Result<bool, GitAiError> {
+    if remote_refspecs(repository, remote_name, direction)
+        .iter()
+        .any(|existing| existing == refspec)
+    {
+        return Ok(false);
+    }
+
+    let mut args = repository.global_args_for_exec();
+    args.extend([
+        "config".to_string(),
+        "--add".to_string(),
+        format!("remote.{}.{}", remote_name, direction),
+        refspec.to_string(),
+    ]);
+    exec_git(&args)?;
+    Ok(true)
+}
+
+/// Notes refspecs `remote_name` is missing, as `(direction, refspec)` pairs
+///
+/// Only the fetch side is configured: a `remote.<name>.push` entry would replace git's
+/// default of pushing the current branch, so notes are pushed by the wrapper's
+/// background sync (or `git-ai sync push`) instead.
+pub fn missing_notes_refspecs(
+    repository: &Repository,
+    remote_name: &str,
+) -> Vec<(&'static str, String)> {
+    let refspec = notes_fetch_refspec(remote_name);
+    if has_notes_fetch_refspec(repository, remote_name) {
+        Vec::new()
+    } else {
+        vec![("fetch", refspec)]
+    }
+}
+
+/// Configure `remote_name` so a plain `git fetch` brings authorship notes along without
+/// the wrapper. Returns the refspecs that were added.
+pub fn ensure_notes_refspecs(
+    repository: &Repository,
+    remote_name: &str,
+) -> Result<Vec<(&'static str, String)>, GitAiError> {
+    let missing = missing_notes_refspecs(repository, remote_name);
+    for (direction, refspec) in &missing {
+        ensure_remote_refspec(repository, remote_name, direction, refspec)?;
+    }
+    Ok(missing)
+}
+
+fn resolve_ref(repository: &Repository, ref_name: &str) -> Option<String> {
+    let mut args = repository.global_args_for_exec();
+    args.push("rev-parse".to_string());
+    args.push("--verify".to_string());
+    args.push("--quiet".to_string());
+    args.push(ref_name.to_string());
+    exec_git(&args)
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Merge (or initialize) refs/notes/ai from a fetched tracking ref. Failures are logged,
+/// not returned: a fetched but unmerged tracking ref is picked up on the next sync.
+fn merge_tracking_ref_into_local_notes(repository: &Repository, tracking_ref: &str) {
     let local_notes_ref = "refs/notes/ai";
 
-    if crate::git::refs::ref_exists(repository, &tracking_ref) {
+    if crate::git::refs::ref_exists(repository, tracking_ref) {
         if crate::git::refs::ref_exists(repository, local_notes_ref) {
             // Both exist - merge them
             debug_log(&format!(
                 "merging authorship notes from {} into {}",
                 tracking_ref, local_notes_ref
             ));
-            if let Err(e) = merge_notes_from_ref(repository, &tracking_ref) {
+            if let Err(e) = merge_notes_from_ref(repository, tracking_ref) {
                 debug_log(&format!("notes merge failed: {}", e));
                 // Don't fail on merge errors, just log and continue
             }
@@ -169,7 +773,7 @@ pub fn fetch_authorship_notes(
                 "initializing {} from tracking ref {}",
                 local_notes_ref, tracking_ref
             ));
-            if let Err(e) = copy_ref(repository, &tracking_ref, local_notes_ref) {
+            if let Err(e) = copy_ref(repository, tracking_ref, local_notes_ref) {
                 debug_log(&format!("notes copy failed: {}", e));
                 // Don't fail on copy errors, just log and continue
             }
@@ -180,27 +784,25 @@ pub fn fetch_authorship_notes(
             tracking_ref
         ));
     }
-
-    Ok(NotesExistence::Found)
 }
+
 // for use with post-push hook
 pub fn push_authorship_notes(repository: &Repository, remote_name: &str) -> Result<(), GitAiError> {
+    push_authorship_notes_with(repository, remote_name, |_| Ok(()))
+}
+
+/// Like [`push_authorship_notes`], running `before_push` on the notes ref once the
+/// remote's notes are merged in, so it sees the tip that will be pushed
+pub fn push_authorship_notes_with(
+    repository: &Repository,
+    remote_name: &str,
+    before_push: impl FnOnce(&Repository) -> Result<(), GitAiError>,
+) -> Result<(), GitAiError> {
+    let _timing = timings::phase(Phase::Push);
     // STEP 1: Fetch remote notes into tracking ref and merge before pushing
     // This ensures we don't lose notes from other branches/clones
     let tracking_ref = tracking_ref_for_remote(remote_name);
-    let fetch_refspec = format!("+refs/notes/ai:{}", tracking_ref);
-
-    let mut fetch_before_push: Vec<String> = repository.global_args_for_exec();
-    fetch_before_push.push("-c".to_string());
-    fetch_before_push.push("core.hooksPath=/dev/null".to_string());
-    fetch_before_push.push("fetch".to_string());
-    fetch_before_push.push("--no-tags".to_string());
-    fetch_before_push.push("--recurse-submodules=no".to_string());
-    fetch_before_push.push("--no-write-fetch-head".to_string());
-    fetch_before_push.push("--no-write-commit-graph".to_string());
-    fetch_before_push.push("--no-auto-maintenance".to_string());
-    fetch_before_push.push(remote_name.to_string());
-    fetch_before_push.push(fetch_refspec);
+    let fetch_before_push = notes_fetch_args(repository, remote_name, &tracking_ref);
 
     debug_log(&format!(
         "pre-push authorship fetch: {:?}",
@@ -235,6 +837,15 @@ pub fn push_authorship_notes(repository: &Repository, remote_name: &str) -> Resu
         }
     }
 
+    // Summaries' full notes go along, merged with the remote's the same way
+    let push_details = ref_exists(repository, AI_DETAILS_REF);
+    if push_details && let Err(e) = fetch_details_ref(repository, remote_name) {
+        // The remote may not have any yet
+        debug_log(&format!("pre-push note details fetch failed: {}", e));
+    }
+
+    before_push(repository)?;
+
     // STEP 2: Push notes without force (requires fast-forward)
     let mut push_authorship: Vec<String> = repository.global_args_for_exec();
     push_authorship.push("-c".to_string());
@@ -246,6 +857,9 @@ pub fn push_authorship_notes(repository: &Repository, remote_name: &str) -> Resu
     push_authorship.push("--no-signed".to_string());
     push_authorship.push(remote_name.to_string());
     push_authorship.push(AI_AUTHORSHIP_PUSH_REFSPEC.to_string());
+    if push_details {
+        push_authorship.push(AI_DETAILS_PUSH_REFSPEC.to_string());
+    }
 
     debug_log(&format!(
         "pushing authorship refs (no force): {:?}",
@@ -367,6 +367,7 @@ impl TmpRepo {
             true,
             None, // agent_run_result
             false,
+            None, // inherit
         )
     }
 
@@ -413,6 +414,7 @@ impl TmpRepo {
             edited_filepaths: None,
             will_edit_filepaths: None,
             dirty_files: None,
+            token_usage: None,
         };
 
         checkpoint(
@@ -424,6 +426,7 @@ impl TmpRepo {
             true,
             Some(agent_run_result),
             false,
+            None, // inherit
         )
     }
 
@@ -446,6 +449,7 @@ impl TmpRepo {
             true,  // quiet
             agent_run_result,
             false,
+            None, // inherit
         )
     }
 
@@ -8,7 +8,13 @@ pub mod error;
 pub mod feature_flags;
 pub mod git;
 pub mod mdm;
+pub mod memory;
 pub mod metrics;
+pub mod model;
+pub mod network;
 pub mod observability;
 pub mod repo_url;
+#[cfg(feature = "test-support")]
+pub mod testkit;
+pub mod tls;
 pub mod utils;
@@ -8,9 +8,13 @@ mod error;
 mod feature_flags;
 mod git;
 mod mdm;
+mod memory;
 mod metrics;
+mod model;
+mod network;
 mod observability;
 mod repo_url;
+mod tls;
 mod utils;
 
 use clap::Parser;
@@ -38,6 +42,8 @@ fn main() {
         })
         .unwrap_or("git-ai".to_string());
 
+    observability::crash::install_panic_hook();
+
     let cli = Cli::parse();
 
     #[cfg(debug_assertions)]
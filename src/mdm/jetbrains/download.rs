@@ -18,6 +18,7 @@ pub fn download_plugin_from_marketplace(
 
     debug_log(&format!("JetBrains: Downloading plugin from {}", url));
 
+    crate::network::ensure_allowed("download the JetBrains plugin")?;
     let response = minreq::get(&url)
         .with_timeout(120) // 120 second timeout for plugin download
         .send()
@@ -0,0 +1,127 @@
+//! Memory budget for constrained environments such as small CI runners.
+//!
+//! The limit comes from `--max-memory`, then `GIT_AI_MAX_MEMORY`, then the cgroup the
+//! process runs in. Below [`LOW_MEMORY_THRESHOLD`] git-ai runs in low-memory mode:
+//! blames and file reads run one at a time, rewrites attribute changed files in
+//! batches rather than all at once, and diffs are parsed as git streams them.
+
+use std::sync::OnceLock;
+
+/// Limits below this switch to low-memory mode
+pub const LOW_MEMORY_THRESHOLD: u64 = 1024 * 1024 * 1024;
+/// Files attributed together per batch in low-memory mode
+pub const LOW_MEMORY_BATCH_FILES: usize = 32;
+
+/// cgroup reports "no limit" as a huge number rather than "max" in some setups
+const UNLIMITED: u64 = 1 << 60;
+
+static MAX_MEMORY_OVERRIDE: OnceLock<u64> = OnceLock::new();
+static MEMORY_LIMIT: OnceLock<Option<u64>> = OnceLock::new();
+
+/// Set the limit from `--max-memory`. Must be called before anything reads the limit.
+pub fn set_max_memory(bytes: u64) {
+    let _ = MAX_MEMORY_OVERRIDE.set(bytes);
+}
+
+/// Parse a size like `512M`, `2G`, `1.5GiB` or a plain byte count. Suffixes are binary.
+pub fn parse_memory_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid memory size '{}'", value))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        _ => return Err(format!("invalid memory size '{}'", value)),
+    };
+    let bytes = number * multiplier as f64;
+    if bytes < 1.0 {
+        return Err(format!("invalid memory size '{}'", value));
+    }
+    Ok(bytes as u64)
+}
+
+/// The memory available to this process, if it's bounded
+pub fn memory_limit() -> Option<u64> {
+    *MEMORY_LIMIT.get_or_init(|| {
+        if let Some(bytes) = MAX_MEMORY_OVERRIDE.get() {
+            return Some(*bytes);
+        }
+        if let Ok(value) = std::env::var("GIT_AI_MAX_MEMORY")
+            && let Ok(bytes) = parse_memory_size(&value)
+        {
+            return Some(bytes);
+        }
+        cgroup_memory_limit()
+    })
+}
+
+pub fn is_low_memory() -> bool {
+    memory_limit().is_some_and(|limit| limit < LOW_MEMORY_THRESHOLD)
+}
+
+/// How many blames or file reads to run at once: `default`, or one in low-memory mode
+pub fn concurrency(default: usize) -> usize {
+    if is_low_memory() { 1 } else { default }
+}
+
+/// How many files to attribute together out of `total`: all of them, or
+/// [`LOW_MEMORY_BATCH_FILES`] in low-memory mode
+pub fn file_batch_size(total: usize) -> usize {
+    if is_low_memory() {
+        LOW_MEMORY_BATCH_FILES
+    } else {
+        total.max(1)
+    }
+}
+
+/// The limit of the cgroup this process runs in (v2, then v1)
+fn cgroup_memory_limit() -> Option<u64> {
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    let mut candidates = Vec::new();
+    // cgroup v2 lists a single "0::<path>" entry; containers usually see their own
+    // cgroup mounted at the root
+    if let Some(path) = read("/proc/self/cgroup").and_then(|cgroups| {
+        cgroups
+            .lines()
+            .find_map(|line| line.strip_prefix("0::").map(str::to_string))
+    }) {
+        candidates.push(format!(
+            "/sys/fs/cgroup{}/memory.max",
+            path.trim_end_matches('/')
+        ));
+    }
+    candidates.push("/sys/fs/cgroup/memory.max".to_string());
+    candidates.push("/sys/fs/cgroup/memory/memory.limit_in_bytes".to_string());
+
+    candidates.iter().find_map(|path| {
+        let value = read(path)?;
+        let bytes: u64 = value.trim().parse().ok()?;
+        (bytes < UNLIMITED).then_some(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!(parse_memory_size("1048576"), Ok(1 << 20));
+        assert_eq!(parse_memory_size("512M"), Ok(512 << 20));
+        assert_eq!(parse_memory_size("512mb"), Ok(512 << 20));
+        assert_eq!(parse_memory_size("2G"), Ok(2 << 30));
+        assert_eq!(parse_memory_size("1.5GiB"), Ok(3 << 29));
+        assert_eq!(parse_memory_size("64k"), Ok(64 << 10));
+        assert!(parse_memory_size("").is_err());
+        assert!(parse_memory_size("lots").is_err());
+        assert!(parse_memory_size("12T").is_err());
+        assert!(parse_memory_size("0").is_err());
+    }
+}
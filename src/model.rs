@@ -0,0 +1,205 @@
+//! Public serde types for git-ai's machine-readable output.
+//!
+//! `query`, `show --json`, `export`, the editor host protocol and the authorship note
+//! format all describe attribution with these types rather than ad-hoc maps, so
+//! downstream tools can deserialize any of them with `git_ai::model`. Fields are only ever added, with
+//! serde defaults so older output still parses; anything incompatible bumps the major
+//! part of [`MODEL_SCHEMA_VERSION`].
+
+use crate::authorship::authorship_log::LineRange;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const MODEL_SCHEMA_VERSION: &str = "model/1.0.0";
+
+fn default_schema_version() -> String {
+    MODEL_SCHEMA_VERSION.to_string()
+}
+
+/// The agent behind a prompt: the tool, its session id, and the model it ran. The same
+/// type is stored as `agent_id` in authorship notes and checkpoints.
+pub use crate::authorship::working_log::AgentId as AgentInfo;
+
+/// A run of AI-authored lines and the agent that wrote them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribution {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub prompt_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Line attribution for one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAttribution {
+    #[serde(default = "default_schema_version")]
+    pub schema_version: String,
+    pub file: String,
+    /// Commit the attribution is relative to (HEAD for working-tree queries)
+    pub head: String,
+    /// AI-authored line ranges; all other lines are human or unknown
+    pub ranges: Vec<Attribution>,
+}
+
+impl FileAttribution {
+    pub fn new(file: String, head: String, ranges: Vec<Attribution>) -> Self {
+        Self {
+            schema_version: default_schema_version(),
+            file,
+            head,
+            ranges,
+        }
+    }
+}
+
+/// What a commit's authorship note records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSummary {
+    #[serde(default = "default_schema_version")]
+    pub schema_version: String,
+    pub commit: String,
+    /// Whether the commit has an authorship note at all. Without one, `agents` and
+    /// `files` are empty because nothing is known, not because every line is human.
+    pub has_authorship: bool,
+    /// Agents that wrote lines in this commit, keyed by prompt id
+    #[serde(default)]
+    pub agents: BTreeMap<String, AgentInfo>,
+    /// Files with AI-authored lines, sorted by path
+    #[serde(default)]
+    pub files: Vec<FileAttribution>,
+}
+
+impl CommitSummary {
+    /// A commit with no authorship note
+    pub fn without_authorship(commit: &str) -> Self {
+        Self {
+            schema_version: default_schema_version(),
+            commit: commit.to_string(),
+            has_authorship: false,
+            agents: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn from_authorship_log(commit: &str, log: &AuthorshipLog) -> Self {
+        let agents: BTreeMap<String, AgentInfo> = log
+            .metadata
+            .prompts
+            .iter()
+            .map(|(prompt_id, record)| (prompt_id.clone(), record.agent_id.clone()))
+            .collect();
+
+        let mut files: Vec<FileAttribution> = log
+            .attestations
+            .iter()
+            .map(|attestation| {
+                let mut ranges: Vec<Attribution> = attestation
+                    .entries
+                    .iter()
+                    .flat_map(|entry| {
+                        let agent = agents.get(&entry.hash);
+                        entry.line_ranges.iter().map(move |range| {
+                            let (start_line, end_line) = match range {
+                                LineRange::Single(line) => (*line, *line),
+                                LineRange::Range(start, end) => (*start, *end),
+                            };
+                            Attribution {
+                                start_line,
+                                end_line,
+                                prompt_id: entry.hash.clone(),
+                                tool: agent.map(|a| a.tool.clone()),
+                                model: agent.map(|a| a.model.clone()),
+                            }
+                        })
+                    })
+                    .collect();
+                ranges.sort_by_key(|range| (range.start_line, range.end_line));
+                FileAttribution::new(attestation.file_path.clone(), commit.to_string(), ranges)
+            })
+            .filter(|file| !file.ranges.is_empty())
+            .collect();
+        files.sort_by(|a, b| a.file.cmp(&b.file));
+
+        Self {
+            schema_version: default_schema_version(),
+            commit: commit.to_string(),
+            has_authorship: true,
+            agents,
+            files,
+        }
+    }
+}
+
+/// One line of `git-ai export --format jsonl`. Events are flat so warehouses can load
+/// them without unnesting, and carry a deterministic `event_id` so re-exporting an
+/// overlapping window can be deduplicated on load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ExportEvent {
+    Commit(CommitEvent),
+    Hunk(HunkEvent),
+}
+
+/// A commit and its line totals
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitEvent {
+    #[serde(default = "default_schema_version")]
+    pub schema_version: String,
+    pub event_id: String,
+    /// Normalized URL of the default remote, when there is one
+    #[serde(default)]
+    pub repo: Option<String>,
+    pub commit: String,
+    #[serde(default)]
+    pub parents: Vec<String>,
+    pub author_name: String,
+    pub author_email: String,
+    /// RFC 3339, UTC
+    pub authored_at: String,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub committed_at: String,
+    pub subject: String,
+    pub has_authorship: bool,
+    pub added_lines: u32,
+    pub ai_lines: u32,
+    pub human_lines: u32,
+    #[serde(default)]
+    pub git_ai_version: Option<String>,
+    #[serde(default)]
+    pub work_item: Option<String>,
+    /// `fully_ai`, `ai_assisted` or `human`, when the note records it
+    #[serde(default)]
+    pub classification: Option<String>,
+}
+
+/// A run of AI-authored lines in a commit, with the agent behind it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkEvent {
+    #[serde(default = "default_schema_version")]
+    pub schema_version: String,
+    pub event_id: String,
+    #[serde(default)]
+    pub repo: Option<String>,
+    pub commit: String,
+    pub author_email: String,
+    pub committed_at: String,
+    pub file: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub line_count: u32,
+    pub prompt_id: String,
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Who was driving the agent
+    #[serde(default)]
+    pub human_author: Option<String>,
+}
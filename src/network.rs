@@ -0,0 +1,123 @@
+//! Air-gapped mode (`git-ai --no-network <command>`, `GIT_AI_NO_NETWORK=1` or the
+//! `no_network` config). The shared HTTP layer ([`crate::ci::http`]) and the git exec
+//! functions check in here before every request and remote git operation, failing fast
+//! with [`GitAiError::NetworkDisabled`] instead of reaching out.
+
+use crate::config::Config;
+use crate::error::GitAiError;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Environment variable that turns network access off, inherited by the git-ai
+/// processes a command spawns (background flushes, deferred notes sync)
+pub const NO_NETWORK_ENV_VAR: &str = "GIT_AI_NO_NETWORK";
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// git subcommands that talk to a remote
+const REMOTE_GIT_SUBCOMMANDS: &[&str] = &["fetch", "pull", "push", "clone", "ls-remote"];
+
+/// Whether `--no-network` was passed
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "--no-network")
+}
+
+/// Turn off network access for this process and the git-ai processes it spawns
+pub fn disable() {
+    DISABLED.store(true, Ordering::SeqCst);
+    // SAFETY: called while parsing arguments, before any threads are started
+    unsafe {
+        std::env::set_var(NO_NETWORK_ENV_VAR, "1");
+    }
+}
+
+/// Whether network access is off, by flag, environment or config
+pub fn is_disabled() -> bool {
+    DISABLED.load(Ordering::Relaxed)
+        || std::env::var(NO_NETWORK_ENV_VAR)
+            .is_ok_and(|value| !value.is_empty() && value != "0" && value != "false")
+        || Config::get().no_network()
+}
+
+/// Fail with [`GitAiError::NetworkDisabled`] if network access is off. `operation`
+/// says what would have reached the network, e.g. "query the GitLab API".
+pub fn ensure_allowed(operation: &str) -> Result<(), GitAiError> {
+    if is_disabled() {
+        return Err(GitAiError::NetworkDisabled {
+            operation: operation.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Check a git invocation (global options first, as passed to git) before running it,
+/// failing if it talks to a remote while network access is off or after the CI deadline
+pub fn ensure_git_allowed(args: &[String]) -> Result<(), GitAiError> {
+    match remote_git_subcommand(args) {
+        Some(subcommand) => {
+            let operation = format!("run git {}", subcommand);
+            ensure_allowed(&operation)?;
+            crate::ci::deadline::check(&operation)
+        }
+        None => Ok(()),
+    }
+}
+
+/// The subcommand of a git invocation, if it's one that talks to a remote
+fn remote_git_subcommand(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // Global options that take their value as the next argument
+            "-C" | "-c" | "--git-dir" | "--work-tree" | "--namespace" | "--config-env" => {
+                args.next();
+            }
+            option if option.starts_with('-') => {}
+            subcommand => {
+                return REMOTE_GIT_SUBCOMMANDS
+                    .contains(&subcommand)
+                    .then_some(subcommand);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_remote_git_subcommand() {
+        assert_eq!(
+            remote_git_subcommand(&args(&[
+                "-C",
+                "/repo",
+                "--no-pager",
+                "-c",
+                "core.hooksPath=/dev/null",
+                "push",
+                "origin",
+                "refs/notes/ai"
+            ])),
+            Some("push")
+        );
+        assert_eq!(
+            remote_git_subcommand(&args(&["ls-remote", "origin"])),
+            Some("ls-remote")
+        );
+        // A value of -C named like a subcommand isn't one
+        assert_eq!(
+            remote_git_subcommand(&args(&["-C", "fetch", "notes", "--ref=ai", "show"])),
+            None
+        );
+        assert_eq!(
+            remote_git_subcommand(&args(&["--no-pager", "rev-parse", "HEAD"])),
+            None
+        );
+        assert_eq!(remote_git_subcommand(&args(&[])), None);
+    }
+}
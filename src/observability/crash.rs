@@ -0,0 +1,172 @@
+//! Local crash reports.
+//!
+//! Hook panics are caught so the user's git command still completes, but without a
+//! report there is nothing to attach to a bug. The panic hook installed here writes a
+//! JSON bundle (scrubbed args, version, backtrace, repository state) to
+//! `.git/ai/crash/` — or `~/.git-ai/internal/crash/` outside a repository — and prints
+//! its path.
+
+use serde::Serialize;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::authorship::authorship_log_serialization::GIT_AI_VERSION;
+use crate::config;
+use crate::observability::scrub::scrub_secrets;
+
+/// Older bundles beyond this many are deleted when a new one is written
+const MAX_CRASH_REPORTS: usize = 20;
+
+#[derive(Serialize)]
+struct CrashReport {
+    git_ai_version: &'static str,
+    timestamp: String,
+    os: &'static str,
+    arch: &'static str,
+    args: Vec<String>,
+    thread: Option<String>,
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+    repository: Option<RepoSummary>,
+}
+
+#[derive(Serialize)]
+struct RepoSummary {
+    git_dir: PathBuf,
+    head: Option<String>,
+    branch: Option<String>,
+    /// Sequencer operation in progress, if any (rebase, merge, cherry-pick, ...)
+    operation: Option<&'static str>,
+}
+
+/// Replace the default panic hook with one that writes a crash report. Must not panic
+/// itself: a panic inside the hook aborts the process.
+pub fn install_panic_hook() {
+    // Resolved now: loading the config from inside the hook could panic again
+    let git_cmd = config::Config::get().git_cmd().to_string();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = panic_message(info);
+        match write_crash_report(info, &message, &git_cmd) {
+            Some(path) => eprintln!(
+                "git-ai crashed: {}\nCrash report written to {}",
+                message,
+                path.display()
+            ),
+            None => eprintln!("git-ai crashed: {}", message),
+        }
+    }));
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    };
+    scrub_secrets(&message)
+}
+
+fn write_crash_report(info: &PanicHookInfo<'_>, message: &str, git_cmd: &str) -> Option<PathBuf> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let repository = repo_summary(git_cmd, &args);
+    let dir = match &repository {
+        Some(repo) => repo.git_dir.join("ai").join("crash"),
+        None => config::internal_dir_path()?.join("crash"),
+    };
+
+    let report = CrashReport {
+        git_ai_version: GIT_AI_VERSION,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        args: args.iter().map(|arg| scrub_secrets(arg)).collect(),
+        thread: std::thread::current().name().map(str::to_string),
+        message: message.to_string(),
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        repository,
+    };
+
+    std::fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!(
+        "crash-{}-{}.json",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        std::process::id()
+    ));
+    let json = serde_json::to_string_pretty(&report).ok()?;
+    std::fs::write(&path, json).ok()?;
+    prune_crash_reports(&dir);
+    Some(path)
+}
+
+/// Summarize the repository the command ran in. Shells out directly rather than going
+/// through `Repository` so a panic in repository code can't recur inside the hook.
+fn repo_summary(git_cmd: &str, args: &[String]) -> Option<RepoSummary> {
+    let mut chdir_args: Vec<&str> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-C"
+            && let Some(path) = iter.next()
+        {
+            chdir_args.extend(["-C", path.as_str()]);
+        }
+    }
+
+    let git = |git_args: &[&str]| -> Option<String> {
+        let output = Command::new(git_cmd)
+            .args(&chdir_args)
+            .args(git_args)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let git_dir = PathBuf::from(git(&["rev-parse", "--absolute-git-dir"])?);
+    let operation = operation_in_progress(&git_dir);
+    Some(RepoSummary {
+        head: git(&["rev-parse", "--verify", "-q", "HEAD"]),
+        branch: git(&["symbolic-ref", "-q", "--short", "HEAD"]),
+        operation,
+        git_dir,
+    })
+}
+
+fn operation_in_progress(git_dir: &Path) -> Option<&'static str> {
+    [
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("MERGE_HEAD", "merge"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+        ("BISECT_LOG", "bisect"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| git_dir.join(marker).exists())
+    .map(|(_, operation)| operation)
+}
+
+fn prune_crash_reports(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    // Names start with a sortable timestamp, so name order is age order
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    reports.sort();
+    let excess = reports.len().saturating_sub(MAX_CRASH_REPORTS);
+    for path in &reports[..excess] {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -3,6 +3,7 @@ use crate::config::{Config, get_or_create_distinct_id};
 use crate::git::find_repository_in_path;
 use crate::metrics::db::MetricsDatabase;
 use crate::metrics::{MetricEvent, MetricsBatch};
+use crate::observability::usage;
 use futures::stream::{self, StreamExt};
 use serde_json::{Value, json};
 use std::collections::BTreeMap;
@@ -58,6 +59,10 @@ pub fn handle_flush_logs(args: &[String]) {
         .filter(|s| !s.is_empty())
         .unwrap_or_else(|| "https://us.i.posthog.com".to_string());
 
+    if !skip_non_metrics {
+        flush_usage_spool(&config, posthog_api_key.as_deref(), &posthog_host);
+    }
+
     // Get the global logs directory
     let Some(logs_dir) = get_logs_directory() else {
         // No logs directory - nothing to do, exit successfully
@@ -383,6 +388,7 @@ impl SentryClient {
 
         let body = serde_json::to_string(&event)?;
 
+        crate::network::ensure_allowed("send an error report to Sentry")?;
         let response = minreq::post(&self.endpoint)
             .with_header("X-Sentry-Auth", auth_header)
             .with_header("Content-Type", "application/json")
@@ -416,6 +422,7 @@ impl PostHogClient {
     fn send_event(&self, event: Value) -> Result<(), Box<dyn std::error::Error>> {
         let body = serde_json::to_string(&event)?;
 
+        crate::network::ensure_allowed("send an event to PostHog")?;
         let response = minreq::post(&self.endpoint)
             .with_header("Content-Type", "application/json")
             .with_body(body)
@@ -431,6 +438,53 @@ impl PostHogClient {
     }
 }
 
+/// Upload opted-in usage events and empty the spool. Events spooled before the user
+/// opted out are discarded rather than sent.
+fn flush_usage_spool(config: &Config, posthog_api_key: Option<&str>, posthog_host: &str) {
+    if !config.usage_telemetry_enabled() {
+        usage::clear_spool();
+        return;
+    }
+    // Keep the spool for the next flush that may reach the network
+    let Some(api_key) = posthog_api_key.filter(|_| !crate::network::is_disabled()) else {
+        return;
+    };
+    let events = usage::read_spool();
+    if events.is_empty() {
+        return;
+    }
+
+    // Usage events are deliberately not tied to the install's distinct_id
+    let distinct_id = uuid::Uuid::new_v4().to_string();
+    let batch: Vec<Value> = events
+        .iter()
+        .map(|event| {
+            json!({
+                "event": "command_usage",
+                "distinct_id": distinct_id,
+                "properties": {
+                    "$process_person_profile": false,
+                    "kind": event.kind,
+                    "command": event.command,
+                    "duration_ms": event.duration_ms,
+                    "repo_size": event.repo_size,
+                    "version": event.version,
+                    "os": event.os,
+                    "date": event.date,
+                },
+            })
+        })
+        .collect();
+    let body = json!({ "api_key": api_key, "batch": batch }).to_string();
+    let sent = minreq::post(format!("{}/batch/", posthog_host.trim_end_matches('/')))
+        .with_header("Content-Type", "application/json")
+        .with_body(body)
+        .send();
+    if sent.is_ok_and(|response| (200..300).contains(&response.status_code)) {
+        usage::clear_spool();
+    }
+}
+
 /// Handles metrics upload via the API or fallback to SQLite
 struct MetricsUploader {
     client: Option<ApiClient>,
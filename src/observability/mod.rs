@@ -8,7 +8,11 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::metrics::{METRICS_API_VERSION, MetricEvent};
 
+pub mod crash;
 pub mod flush;
+pub mod scrub;
+pub mod timings;
+pub mod usage;
 pub mod wrapper_performance_targets;
 
 /// Maximum events per metrics envelope
@@ -127,13 +131,18 @@ fn append_envelope(envelope: LogEnvelope) {
     }
 }
 
+fn scrubbed_context(mut context: serde_json::Value) -> serde_json::Value {
+    scrub::scrub_json(&mut context);
+    context
+}
+
 /// Log an error to Sentry
 pub fn log_error(error: &dyn std::error::Error, context: Option<serde_json::Value>) {
     let envelope = ErrorEnvelope {
         event_type: "error".to_string(),
         timestamp: chrono::Utc::now().to_rfc3339(),
-        message: error.to_string(),
-        context,
+        message: scrub::scrub_secrets(&error.to_string()),
+        context: context.map(scrubbed_context),
     };
 
     append_envelope(LogEnvelope::Error(envelope));
@@ -151,7 +160,7 @@ pub fn log_performance(
         timestamp: chrono::Utc::now().to_rfc3339(),
         operation: operation.to_string(),
         duration_ms: duration.as_millis(),
-        context,
+        context: context.map(scrubbed_context),
         tags,
     };
 
@@ -164,9 +173,9 @@ pub fn log_message(message: &str, level: &str, context: Option<serde_json::Value
     let envelope = MessageEnvelope {
         event_type: "message".to_string(),
         timestamp: chrono::Utc::now().to_rfc3339(),
-        message: message.to_string(),
+        message: scrub::scrub_secrets(message),
         level: level.to_string(),
-        context,
+        context: context.map(scrubbed_context),
     };
 
     append_envelope(LogEnvelope::Message(envelope));
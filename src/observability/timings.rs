@@ -0,0 +1,168 @@
+//! Opt-in per-phase timing report (`git-ai --timings <command>` or `GIT_AI_TIMINGS=1`).
+//!
+//! Instrumented code holds a [`phase`] guard while it runs; durations and call counts
+//! add up per phase and the report is printed to stderr when the process exits, however
+//! the command exits. Phases that run on parallel workers (blame) can add up to more
+//! than the wall-clock total.
+
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Clone,
+    Fetch,
+    Api,
+    Diff,
+    Blame,
+    NoteWrite,
+    Push,
+}
+
+impl Phase {
+    const ALL: [Phase; 7] = [
+        Phase::Clone,
+        Phase::Fetch,
+        Phase::Api,
+        Phase::Diff,
+        Phase::Blame,
+        Phase::NoteWrite,
+        Phase::Push,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Clone => "clone",
+            Phase::Fetch => "fetch",
+            Phase::Api => "api",
+            Phase::Diff => "diff",
+            Phase::Blame => "blame",
+            Phase::NoteWrite => "note write",
+            Phase::Push => "push",
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static TOTALS: Mutex<[(Duration, u32); Phase::ALL.len()]> =
+    Mutex::new([(Duration::ZERO, 0); Phase::ALL.len()]);
+
+/// Start collecting timings and print the report when the process exits
+pub fn enable() {
+    if ENABLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = STARTED.set(Instant::now());
+    #[cfg(unix)]
+    unsafe {
+        // `std::process::exit` runs atexit handlers, so this covers commands that exit
+        // from deep inside their handler as well as ones that return
+        let _ = libc::atexit(print_report_at_exit);
+    }
+}
+
+/// Whether `--timings` was passed or `GIT_AI_TIMINGS` is set
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "--timings")
+        || std::env::var("GIT_AI_TIMINGS")
+            .is_ok_and(|value| !value.is_empty() && value != "0" && value != "false")
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Time the rest of the enclosing scope as `phase`
+pub fn phase(phase: Phase) -> PhaseTimer {
+    PhaseTimer {
+        phase,
+        started: is_enabled().then(Instant::now),
+    }
+}
+
+pub struct PhaseTimer {
+    phase: Phase,
+    started: Option<Instant>,
+}
+
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        let Some(started) = self.started else {
+            return;
+        };
+        let index = Phase::ALL.iter().position(|p| *p == self.phase).unwrap();
+        if let Ok(mut totals) = TOTALS.lock() {
+            totals[index].0 += started.elapsed();
+            totals[index].1 += 1;
+        }
+    }
+}
+
+/// The report, one line per phase that ran, or `None` when timings are off
+pub fn report() -> Option<String> {
+    let total = STARTED.get()?.elapsed();
+    let totals = *TOTALS.lock().ok()?;
+
+    let mut lines = vec![format!(
+        "git-ai timings (total {}):",
+        format_duration(total)
+    )];
+    let mut accounted = Duration::ZERO;
+    for (phase, (duration, calls)) in Phase::ALL.iter().zip(totals) {
+        if calls == 0 {
+            continue;
+        }
+        if *phase != Phase::Blame {
+            accounted += duration;
+        }
+        lines.push(format!(
+            "  {:<11} {:>10}  {} {}",
+            phase.label(),
+            format_duration(duration),
+            calls,
+            if calls == 1 { "call" } else { "calls" }
+        ));
+    }
+    lines.push(format!(
+        "  {:<11} {:>10}",
+        "other",
+        format_duration(total.saturating_sub(accounted))
+    ));
+    Some(lines.join("\n"))
+}
+
+#[cfg(unix)]
+extern "C" fn print_report_at_exit() {
+    if let Some(report) = report() {
+        eprintln!("{}", report);
+    }
+}
+
+/// `850ms`, `12.4s` or `8m 03.2s`
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        return format!("{}ms", millis);
+    }
+    let secs = duration.as_secs_f64();
+    if secs < 60.0 {
+        return format!("{:.1}s", secs);
+    }
+    let minutes = (secs / 60.0).floor();
+    format!("{}m {:04.1}s", minutes as u64, secs - minutes * 60.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(12_400)), "12.4s");
+        assert_eq!(format_duration(Duration::from_millis(483_200)), "8m 03.2s");
+    }
+}
@@ -0,0 +1,159 @@
+//! Opt-in anonymous usage telemetry.
+//!
+//! Off unless the user runs `git-ai telemetry on`. Each event records only which
+//! command ran, how long it took, and a coarse repository size bucket — never paths,
+//! remotes, arguments, or file content. Events are spooled locally to
+//! `~/.git-ai/internal/usage-spool.jsonl` and uploaded by `flush-logs`.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{self, Config};
+use crate::git::repository::Repository;
+
+/// Stop spooling once the file reaches this size, e.g. when uploads keep failing
+const MAX_SPOOL_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageEvent {
+    /// `git-ai` for git-ai subcommands, `git` for wrapped git commands
+    pub kind: String,
+    pub command: String,
+    pub duration_ms: u64,
+    pub repo_size: String,
+    pub version: String,
+    pub os: String,
+    /// Day only, so events can't be correlated with individual commits
+    pub date: String,
+}
+
+pub fn spool_path() -> Option<PathBuf> {
+    config::internal_dir_path().map(|dir| dir.join("usage-spool.jsonl"))
+}
+
+/// Spool a usage event if the user opted in. Never fails the calling command.
+pub fn record_command(kind: &str, command: &str, duration: Duration, repo: Option<&Repository>) {
+    if !Config::get().usage_telemetry_enabled() {
+        return;
+    }
+    // The command may itself have been `git-ai telemetry off`
+    Config::reload_if_changed();
+    if !Config::get().usage_telemetry_enabled() {
+        return;
+    }
+    let Some(path) = spool_path() else {
+        return;
+    };
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_SPOOL_BYTES) {
+        return;
+    }
+
+    let event = UsageEvent {
+        kind: kind.to_string(),
+        command: sanitize_command(command),
+        duration_ms: duration.as_millis() as u64,
+        repo_size: repo
+            .and_then(|repo| index_entry_count(repo.path()))
+            .map(repo_size_bucket)
+            .unwrap_or("none")
+            .to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+    };
+
+    let Ok(line) = serde_json::to_string(&event) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = file.write_all(format!("{}\n", line).as_bytes());
+    }
+}
+
+/// Read spooled events, skipping lines that don't parse
+pub fn read_spool() -> Vec<UsageEvent> {
+    let Some(path) = spool_path() else {
+        return Vec::new();
+    };
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+pub fn clear_spool() {
+    if let Some(path) = spool_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Git aliases and typos mean the command name can be arbitrary user text, so only
+/// short command-like names are reported as-is
+fn sanitize_command(command: &str) -> String {
+    let looks_like_command = !command.is_empty()
+        && command.len() <= 32
+        && command
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if looks_like_command {
+        command.to_string()
+    } else {
+        "other".to_string()
+    }
+}
+
+/// Number of entries in the index, read from its header. Much cheaper than walking the
+/// tree and a good proxy for repository size.
+fn index_entry_count(git_dir: &Path) -> Option<u32> {
+    let mut header = [0u8; 12];
+    std::fs::File::open(git_dir.join("index"))
+        .ok()?
+        .read_exact(&mut header)
+        .ok()?;
+    if &header[0..4] != b"DIRC" {
+        return None;
+    }
+    Some(u32::from_be_bytes([
+        header[8], header[9], header[10], header[11],
+    ]))
+}
+
+fn repo_size_bucket(files: u32) -> &'static str {
+    match files {
+        0..100 => "<100",
+        100..1_000 => "<1k",
+        1_000..10_000 => "<10k",
+        10_000..100_000 => "<100k",
+        _ => "100k+",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_command_keeps_only_command_names() {
+        assert_eq!(sanitize_command("commit"), "commit");
+        assert_eq!(sanitize_command("cherry-pick"), "cherry-pick");
+        assert_eq!(sanitize_command("/home/me/secret-project"), "other");
+        assert_eq!(sanitize_command("Fix login bug"), "other");
+        assert_eq!(sanitize_command(""), "other");
+    }
+
+    #[test]
+    fn test_repo_size_bucket_boundaries() {
+        assert_eq!(repo_size_bucket(0), "<100");
+        assert_eq!(repo_size_bucket(99), "<100");
+        assert_eq!(repo_size_bucket(100), "<1k");
+        assert_eq!(repo_size_bucket(9_999), "<10k");
+        assert_eq!(repo_size_bucket(250_000), "100k+");
+    }
+}
@@ -0,0 +1,30 @@
+//! Test harness for git-ai's own integration tests and for tools built on top of it,
+//! such as editor plugins and CI wrappers.
+//!
+//! Enabled by the `test-support` feature. A [`TestRepo`] is a throwaway repository in the
+//! OS temp dir that runs a real git-ai binary as both `git-ai` and the `git` wrapper, with
+//! its own config and database so tests don't touch the user's. Mock agents write files
+//! and checkpoint them as AI or human edits, the way an agent integration would, and
+//! [`test_file::TestFile`] asserts each line's attribution through `git-ai blame`.
+//!
+//! The binary is taken from `GIT_AI_TESTKIT_BIN`; without it, git-ai is built from this
+//! crate's sources with `--features test-support`, which the config and database
+//! isolation need.
+//!
+//! ```ignore
+//! let repo = git_ai::testkit::TestRepo::new();
+//! repo.mock_ai_edit("src/lib.rs", "fn answer() -> u32 { 42 }\n")?;
+//! let commit = repo.stage_all_and_commit("Add answer")?;
+//! assert_eq!(commit.authorship_log.attestations.len(), 1);
+//! ```
+
+pub mod test_file;
+pub mod test_repo;
+
+pub use test_repo::{NewCommit, TestRepo};
+
+/// Environment variable naming the git-ai binary tests run
+pub const BINARY_ENV: &str = "GIT_AI_TESTKIT_BIN";
+
+/// Preset name of the mock agent checkpoint
+pub const MOCK_AI_PRESET: &str = "mock_ai";
@@ -1,5 +1,3 @@
-#![allow(dead_code)]
-
 use std::{fs, path::PathBuf};
 
 use insta::assert_debug_snapshot;
@@ -734,10 +732,10 @@ impl<'a> TestFile<'a> {
 
     fn write_and_checkpoint(&self, author_type: &AuthorType) {
         // Create parent directories if they don't exist (important for nested paths)
-        if let Some(parent) = self.file_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).expect("failed to create parent directories");
-            }
+        if let Some(parent) = self.file_path.parent()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent).expect("failed to create parent directories");
         }
         let contents = self.contents();
         fs::write(&self.file_path, contents).unwrap();
@@ -750,10 +748,10 @@ impl<'a> TestFile<'a> {
 
     fn write_and_checkpoint_with_contents(&self, contents: &str, author_type: &AuthorType) {
         // Create parent directories if they don't exist (important for nested paths like src/模块/组件.ts)
-        if let Some(parent) = self.file_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).expect("failed to create parent directories");
-            }
+        if let Some(parent) = self.file_path.parent()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent).expect("failed to create parent directories");
         }
         fs::write(&self.file_path, contents).unwrap();
 
@@ -776,10 +774,10 @@ impl<'a> TestFile<'a> {
 
     fn write_and_checkpoint_no_stage(&self, contents: &str, author_type: &AuthorType) {
         // Create parent directories if they don't exist (important for nested paths)
-        if let Some(parent) = self.file_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).expect("failed to create parent directories");
-            }
+        if let Some(parent) = self.file_path.parent()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent).expect("failed to create parent directories");
         }
         fs::write(&self.file_path, contents).unwrap();
 
@@ -801,7 +799,7 @@ impl<'a> TestFile<'a> {
 macro_rules! lines {
     ($($line:expr),* $(,)?) => {{
         {
-            use $crate::repos::test_file::ExpectedLine;
+            use $crate::testkit::test_file::ExpectedLine;
             let v: Vec<ExpectedLine> = vec![$(Into::into($line)),*];
             v
         }
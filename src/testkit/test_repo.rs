@@ -1,12 +1,10 @@
-#![allow(dead_code)]
-
-use git_ai::authorship::authorship_log_serialization::AuthorshipLog;
-use git_ai::authorship::stats::CommitStats;
-use git_ai::config::ConfigPatch;
-use git_ai::feature_flags::FeatureFlags;
-use git_ai::git::repo_storage::PersistedWorkingLog;
-use git_ai::git::repository as GitAiRepository;
-use git_ai::observability::wrapper_performance_targets::BenchmarkResult;
+use crate::authorship::authorship_log_serialization::AuthorshipLog;
+use crate::authorship::stats::CommitStats;
+use crate::config::ConfigPatch;
+use crate::feature_flags::FeatureFlags;
+use crate::git::repo_storage::PersistedWorkingLog;
+use crate::git::repository as GitAiRepository;
+use crate::observability::wrapper_performance_targets::BenchmarkResult;
 use git2::Repository;
 use insta::assert_debug_snapshot;
 use rand::Rng;
@@ -17,16 +15,16 @@ use std::sync::OnceLock;
 use std::time::Duration;
 
 use super::test_file::TestFile;
+use super::{BINARY_ENV, MOCK_AI_PRESET};
 
 #[derive(Clone, Debug)]
 pub struct TestRepo {
     path: PathBuf,
     pub feature_flags: FeatureFlags,
-    pub(crate) config_patch: Option<ConfigPatch>,
+    config_patch: Option<ConfigPatch>,
     test_db_path: PathBuf,
 }
 
-#[allow(dead_code)]
 impl Default for TestRepo {
     fn default() -> Self {
         Self::new()
@@ -215,6 +213,11 @@ impl TestRepo {
         self.config_patch = Some(patch);
     }
 
+    /// The config patch every command in this repo runs with
+    pub fn config_patch(&self) -> Option<&ConfigPatch> {
+        self.config_patch.as_ref()
+    }
+
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
@@ -396,10 +399,28 @@ impl TestRepo {
     }
 
     pub fn git_ai_with_env(&self, args: &[&str], envs: &[(&str, &str)]) -> Result<String, String> {
+        self.git_ai_in_dir(&self.path, args, envs)
+    }
+
+    /// Run a git-ai command from `working_dir`, which may be outside the repository
+    pub fn git_ai_from_working_dir(
+        &self,
+        working_dir: &std::path::Path,
+        args: &[&str],
+    ) -> Result<String, String> {
+        self.git_ai_in_dir(working_dir, args, &[])
+    }
+
+    fn git_ai_in_dir(
+        &self,
+        working_dir: &std::path::Path,
+        args: &[&str],
+        envs: &[(&str, &str)],
+    ) -> Result<String, String> {
         let binary_path = get_binary_path();
 
         let mut command = Command::new(binary_path);
-        command.args(args).current_dir(&self.path);
+        command.args(args).current_dir(working_dir);
 
         // Add config patch as environment variable if present
         if let Some(patch) = &self.config_patch
@@ -563,7 +584,7 @@ impl TestRepo {
 
                 // Get the authorship log for the new commit
                 let authorship_log =
-                    match git_ai::git::refs::show_authorship_note(&repo, &head_commit) {
+                    match crate::git::refs::show_authorship_note(&repo, &head_commit) {
                         Some(content) => AuthorshipLog::deserialize_from_string(&content)
                             .map_err(|e| format!("Failed to parse authorship log: {}", e))?,
                         None => {
@@ -585,6 +606,53 @@ impl TestRepo {
         let file_path = self.path.join(filename);
         fs::read_to_string(&file_path).ok()
     }
+
+    /// Write a file relative to the repository root, creating parent directories
+    pub fn write_file(&self, filename: &str, contents: &str) -> Result<(), String> {
+        let file_path = self.path.join(filename);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(file_path, contents).map_err(|e| e.to_string())
+    }
+
+    /// Checkpoint the given files (or every changed file) as written by the mock agent
+    pub fn checkpoint_mock_ai(&self, files: &[&str]) -> Result<String, String> {
+        let mut args = vec!["checkpoint", MOCK_AI_PRESET];
+        args.extend(files);
+        self.git_ai(&args)
+    }
+
+    /// Checkpoint every changed file as a human edit
+    pub fn checkpoint_human(&self) -> Result<String, String> {
+        self.git_ai(&["checkpoint"])
+    }
+
+    /// Have the mock agent write `contents` to `filename`
+    pub fn mock_ai_edit(&self, filename: &str, contents: &str) -> Result<(), String> {
+        // Checkpoint whatever a human changed first, so only this write is the agent's
+        self.checkpoint_human()?;
+        self.write_file(filename, contents)?;
+        self.checkpoint_mock_ai(&[filename]).map(|_| ())
+    }
+
+    /// Write `contents` to `filename` as a human
+    pub fn human_edit(&self, filename: &str, contents: &str) -> Result<(), String> {
+        self.write_file(filename, contents)?;
+        self.checkpoint_human().map(|_| ())
+    }
+
+    /// The authorship note attached to `commit`, if any
+    pub fn authorship_log(&self, commit: &str) -> Result<Option<AuthorshipLog>, String> {
+        let repo = GitAiRepository::find_repository_in_path(self.path.to_str().unwrap())
+            .map_err(|e| format!("Failed to find repository: {}", e))?;
+        crate::git::refs::show_authorship_note(&repo, commit)
+            .map(|content| {
+                AuthorshipLog::deserialize_from_string(&content)
+                    .map_err(|e| format!("Failed to parse authorship log: {}", e))
+            })
+            .transpose()
+    }
 }
 
 impl Drop for TestRepo {
@@ -637,8 +705,14 @@ fn compile_binary() -> PathBuf {
     println!("Compiling git-ai binary for tests...");
 
     let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    // Build the binary with the optional features the tests were built with
+    let features = if cfg!(feature = "reqwest") {
+        "test-support,reqwest"
+    } else {
+        "test-support"
+    };
     let output = Command::new("cargo")
-        .args(["build", "--bin", "git-ai", "--features", "test-support"])
+        .args(["build", "--bin", "git-ai", "--features", features])
         .current_dir(manifest_dir)
         .output()
         .expect("Failed to compile git-ai binary");
@@ -661,6 +735,10 @@ fn compile_binary() -> PathBuf {
     PathBuf::from(target_dir).join("debug/git-ai")
 }
 
-pub(crate) fn get_binary_path() -> &'static PathBuf {
-    COMPILED_BINARY.get_or_init(compile_binary)
+/// The git-ai binary tests run: `GIT_AI_TESTKIT_BIN`, else one built from these sources
+pub fn get_binary_path() -> &'static PathBuf {
+    COMPILED_BINARY.get_or_init(|| match std::env::var_os(BINARY_ENV) {
+        Some(binary) => PathBuf::from(binary),
+        None => compile_binary(),
+    })
 }
@@ -0,0 +1,175 @@
+//! TLS overrides for self-hosted forges: extra CA certificates to trust
+//! (`GIT_AI_CA_BUNDLE` or the `ca_bundle` config), or no certificate verification at all
+//! (`GIT_AI_TLS_INSECURE=1` or `tls_insecure`). Forge API requests pick them up through
+//! [`client_config`], and the git commands `git-ai ci` runs against the forge through
+//! [`git_config_args`]; other git commands keep the user's own TLS settings.
+
+use crate::config::Config;
+use crate::error::GitAiError;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Whether any TLS override is configured; without one the default HTTP client is used
+pub fn is_customized() -> bool {
+    let config = Config::get();
+    config.ca_bundle().is_some() || config.tls_insecure()
+}
+
+/// rustls client configuration honoring the overrides: the public web roots plus the
+/// CA bundle's certificates, or no verification when insecure
+pub fn client_config() -> Result<ClientConfig, GitAiError> {
+    let config = Config::get();
+    if config.tls_insecure() {
+        warn_insecure();
+    }
+    build_client_config(config.ca_bundle().map(Path::new), config.tls_insecure())
+}
+
+fn build_client_config(
+    ca_bundle: Option<&Path>,
+    insecure: bool,
+) -> Result<ClientConfig, GitAiError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| GitAiError::Generic(format!("Failed to configure TLS: {}", e)))?;
+    if insecure {
+        return Ok(builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(
+                provider.signature_verification_algorithms,
+            )))
+            .with_no_client_auth());
+    }
+
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = ca_bundle {
+        for certificate in load_ca_bundle(path)? {
+            roots.add(certificate).map_err(|e| {
+                GitAiError::Generic(format!(
+                    "Invalid certificate in CA bundle {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+    }
+    Ok(builder.with_root_certificates(roots).with_no_client_auth())
+}
+
+/// The certificates in a PEM bundle, failing if it has none
+fn load_ca_bundle(path: &Path) -> Result<Vec<CertificateDer<'static>>, GitAiError> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        GitAiError::Generic(format!(
+            "Failed to read CA bundle {}: {}",
+            path.display(),
+            e
+        ))
+    };
+    let certificates = CertificateDer::pem_file_iter(path)
+        .map_err(|e| invalid(&e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(&e))?;
+    if certificates.is_empty() {
+        return Err(invalid(&"no PEM certificates found"));
+    }
+    Ok(certificates)
+}
+
+/// Global git args (`-c ...`) for the fetches, pushes and clones `git-ai ci` runs against
+/// the forge, so they trust the same certificates as its API requests. A CA bundle
+/// replaces git's default roots (it's `http.sslCAInfo`), so it should include the public
+/// CAs the forge's remotes need; a GIT_SSL_CAINFO already in the environment is left alone.
+pub fn git_config_args() -> Vec<String> {
+    let config = Config::get();
+    if config.tls_insecure() {
+        warn_insecure();
+        vec!["-c".to_string(), "http.sslVerify=false".to_string()]
+    } else if let Some(ca_bundle) = config.ca_bundle()
+        && std::env::var_os("GIT_SSL_CAINFO").is_none()
+    {
+        vec!["-c".to_string(), format!("http.sslCAInfo={}", ca_bundle)]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Say, once per process, that forge certificates aren't being checked
+fn warn_insecure() {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        eprintln!(
+            "Warning: TLS certificate verification is disabled for forge connections \
+             (GIT_AI_TLS_INSECURE / tls_insecure)"
+        )
+    });
+}
+
+/// Accepts any server certificate; handshake signatures are still checked so the
+/// connection is at least consistent with the certificate presented
+#[derive(Debug)]
+struct NoCertificateVerification(WebPkiSupportedAlgorithms);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_ca_bundle() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+        assert_eq!(load_ca_bundle(&fixtures.join("ca.pem")).unwrap().len(), 1);
+
+        // A private key is PEM, but not a certificate
+        let error = load_ca_bundle(&fixtures.join("server.key")).unwrap_err();
+        assert!(
+            error.to_string().contains("no PEM certificates found"),
+            "{}",
+            error
+        );
+        assert!(load_ca_bundle(&fixtures.join("missing.pem")).is_err());
+
+        assert!(build_client_config(Some(&fixtures.join("ca.pem")), false).is_ok());
+        assert!(build_client_config(None, true).is_ok());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::GitAiError;
 use crate::git::diff_tree_to_tree::Diff;
+use crate::observability::scrub::scrub_secrets;
 use std::io::IsTerminal;
 use std::path::PathBuf;
 
@@ -54,7 +55,7 @@ pub fn debug_performance_log_structured(json: serde_json::Value) {
 /// * `msg` - The debug message to print
 pub fn debug_log(msg: &str) {
     if is_debug_enabled() {
-        eprintln!("\x1b[1;33m[git-ai]\x1b[0m {}", msg);
+        eprintln!("\x1b[1;33m[git-ai]\x1b[0m {}", scrub_secrets(msg));
     }
 }
 
@@ -441,3 +441,39 @@ fn test_amend_with_unstaged_middle_section() {
         "// AI section 3 line 2".ai()
     ]);
 }
+
+#[test]
+fn test_amend_detected_without_literal_flag_and_old_note_removed() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("test.txt");
+
+    file.set_contents(lines!["line 1", "line 2"]);
+    let original = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    file.insert_at(2, lines!["// AI line".ai()]);
+    repo.git(&["add", "-A"]).unwrap();
+
+    // Abbreviated option: detection must rely on the commit parents, not argv
+    repo.git(&["commit", "--amen", "-m", "Initial commit (amended)"])
+        .unwrap();
+
+    let amended_sha = repo.git(&["rev-parse", "HEAD"]).unwrap().trim().to_string();
+    assert_ne!(amended_sha, original.commit_sha);
+
+    file.assert_lines_and_blame(lines![
+        "line 1".human(),
+        "line 2".human(),
+        "// AI line".ai()
+    ]);
+
+    // The rewritten-away commit is unreachable, so its note should be gone
+    assert!(
+        repo.git(&["notes", "--ref=ai", "show", &original.commit_sha])
+            .is_err(),
+        "original commit's note should be removed after amend"
+    );
+    assert!(
+        repo.git(&["notes", "--ref=ai", "show", &amended_sha])
+            .is_ok()
+    );
+}
@@ -0,0 +1,85 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn stats_json(repo: &TestRepo, commit: &str) -> serde_json::Value {
+    let output = repo.git_ai(&["stats", commit, "--json"]).unwrap();
+    let json = output
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .expect("stats should print JSON");
+    serde_json::from_str(json).unwrap()
+}
+
+/// An AI function, then a formatter run that only re-wraps it
+fn repo_with_formatter_run(repo: &TestRepo) -> String {
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines!["fn human() {}".human(), ""]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    file.set_contents(lines![
+        "fn human() {}".human(),
+        "fn add(a: u32, b: u32) -> u32 { a + b }".ai(),
+        "",
+    ]);
+    repo.stage_all_and_commit("Add add").unwrap();
+
+    std::fs::write(
+        repo.path().join("lib.rs"),
+        "fn human() {}\nfn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n",
+    )
+    .unwrap();
+    repo.stage_all_and_commit("cargo fmt").unwrap().commit_sha
+}
+
+#[test]
+fn test_whitespace_only_commit_is_neutral_when_enabled() {
+    let mut repo = TestRepo::new();
+    let fmt_sha = repo_with_formatter_run(&repo);
+
+    // By default the formatter run takes over the lines it touched
+    let mut file = repo.filename("lib.rs");
+    file.assert_lines_and_blame(lines![
+        "fn human() {}".human(),
+        "fn add(a: u32, b: u32) -> u32 {".human(),
+        "    a + b".human(),
+        "}".human(),
+    ]);
+    assert!(
+        stats_json(&repo, &fmt_sha)
+            .get("attribution_neutral")
+            .is_none()
+    );
+
+    repo.patch_git_ai_config(|patch| {
+        patch.neutral_whitespace_commits = Some(true);
+    });
+    let mut file = repo.filename("lib.rs");
+    file.assert_lines_and_blame(lines![
+        "fn human() {}".human(),
+        "fn add(a: u32, b: u32) -> u32 {".ai(),
+        "    a + b".ai(),
+        "}".ai(),
+    ]);
+
+    let stats = stats_json(&repo, &fmt_sha);
+    assert_eq!(stats["attribution_neutral"], true);
+    assert_eq!(stats["human_additions"], 0);
+    assert_eq!(stats["ai_additions"], 0);
+}
+
+#[test]
+fn test_listed_commit_is_neutral() {
+    let mut repo = TestRepo::new();
+    let fmt_sha = repo_with_formatter_run(&repo);
+
+    repo.patch_git_ai_config(|patch| {
+        patch.attribution_neutral_commits = Some(vec![fmt_sha[..10].to_string()]);
+    });
+    let stats = stats_json(&repo, &fmt_sha);
+    assert_eq!(stats["attribution_neutral"], true);
+
+    let text = repo.git_ai(&["stats", &fmt_sha]).unwrap();
+    assert!(text.contains("attribution-neutral"), "{}", text);
+}
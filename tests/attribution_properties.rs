@@ -0,0 +1,206 @@
+//! Property tests for the diff-to-attribution mapping (`attribute_edit`): random edit
+//! scripts applied by alternating authors must keep the attribution invariants that
+//! checkpoints and notes rely on.
+
+use git_ai::authorship::attribution_tracker::{Attribution, LineAttribution, attribute_edit};
+use proptest::prelude::*;
+
+const HUMAN: &str = "human";
+const AUTHORS: [&str; 3] = ["ai_one", "ai_two", HUMAN];
+
+/// Lines that repeat, so edits exercise move detection and ambiguous diffs
+const COMMON_LINES: [&str; 6] = [
+    "fn main() {",
+    "    let x = 1;",
+    "}",
+    "",
+    "// note",
+    "x += 1;",
+];
+
+#[derive(Debug, Clone)]
+enum Op {
+    /// Insert a line nobody has written before
+    InsertFresh(usize),
+    /// Insert a copy of a common line
+    InsertCommon(usize, usize),
+    Delete(usize),
+    /// Replace a line with one nobody has written before
+    ReplaceFresh(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Edit {
+    author: usize,
+    ops: Vec<Op>,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        any::<usize>().prop_map(Op::InsertFresh),
+        (any::<usize>(), 0..COMMON_LINES.len()).prop_map(|(at, line)| Op::InsertCommon(at, line)),
+        any::<usize>().prop_map(Op::Delete),
+        any::<usize>().prop_map(Op::ReplaceFresh),
+    ]
+}
+
+fn edit() -> impl Strategy<Value = Edit> {
+    (0..AUTHORS.len(), prop::collection::vec(op(), 0..6))
+        .prop_map(|(author, ops)| Edit { author, ops })
+}
+
+fn initial_lines() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec(
+        (0..COMMON_LINES.len()).prop_map(|i| COMMON_LINES[i].to_string()),
+        0..10,
+    )
+}
+
+fn render(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Apply an edit's ops, returning the fresh lines it wrote that are still there
+fn apply(lines: &mut Vec<String>, edit: &Edit, step: usize) -> Vec<String> {
+    let mut fresh = Vec::new();
+    for (k, op) in edit.ops.iter().enumerate() {
+        let fresh_line = format!("fresh_{}_{}", step, k);
+        match op {
+            Op::InsertFresh(at) => {
+                lines.insert(at % (lines.len() + 1), fresh_line.clone());
+                fresh.push(fresh_line);
+            }
+            Op::InsertCommon(at, line) => {
+                lines.insert(at % (lines.len() + 1), COMMON_LINES[*line].to_string());
+            }
+            Op::Delete(at) if !lines.is_empty() => {
+                let at = at % lines.len();
+                lines.remove(at);
+            }
+            Op::ReplaceFresh(at) if !lines.is_empty() => {
+                let at = at % lines.len();
+                lines[at] = fresh_line.clone();
+                fresh.push(fresh_line);
+            }
+            Op::Delete(_) | Op::ReplaceFresh(_) => {}
+        }
+    }
+    fresh.retain(|line| lines.contains(line));
+    fresh
+}
+
+fn author_of_line(line_attributions: &[LineAttribution], line: u32) -> &str {
+    line_attributions
+        .iter()
+        .find(|attr| attr.start_line <= line && line <= attr.end_line)
+        .map(|attr| attr.author_id.as_str())
+        .unwrap_or(HUMAN)
+}
+
+fn check_invariants(
+    content: &str,
+    lines: &[String],
+    attributions: &[Attribution],
+    line_attributions: &[LineAttribution],
+    fresh: &[String],
+    author: &str,
+) -> Result<(), TestCaseError> {
+    // Character attributions stay inside the content and cover every non-whitespace char
+    for attr in attributions {
+        prop_assert!(attr.start <= attr.end && attr.end <= content.len());
+    }
+    for (idx, ch) in content.char_indices() {
+        if !ch.is_whitespace() {
+            prop_assert!(
+                attributions.iter().any(|a| a.start <= idx && idx < a.end),
+                "char {:?} at {} is unattributed",
+                ch,
+                idx
+            );
+        }
+    }
+
+    // Line attributions are in order, in range, and never claim a line twice
+    let mut previous_end = 0;
+    for attr in line_attributions {
+        prop_assert!(attr.start_line >= 1 && attr.start_line <= attr.end_line);
+        prop_assert!(attr.end_line as usize <= lines.len());
+        prop_assert!(
+            attr.start_line > previous_end,
+            "overlapping line attributions: {:?}",
+            line_attributions
+        );
+        previous_end = attr.end_line;
+    }
+
+    // Every line the author just wrote is theirs
+    for fresh_line in fresh {
+        let line = lines.iter().position(|l| l == fresh_line).unwrap() as u32 + 1;
+        prop_assert_eq!(
+            author_of_line(line_attributions, line),
+            author,
+            "line {} ({}) in {:?}",
+            line,
+            fresh_line,
+            line_attributions
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn edit_scripts_preserve_attribution_invariants(
+        initial in initial_lines(),
+        edits in prop::collection::vec(edit(), 1..6),
+    ) {
+        let mut lines = initial;
+        let mut content = render(&lines);
+        let mut attributions: Vec<Attribution> = Vec::new();
+
+        for (step, edit) in edits.iter().enumerate() {
+            let author = AUTHORS[edit.author];
+            let ts = 1000 + step as u128 * 10;
+            let fresh = apply(&mut lines, edit, step);
+            let new_content = render(&lines);
+
+            let (new_attributions, line_attributions) =
+                attribute_edit(&content, &attributions, &new_content, author, ts).unwrap();
+            check_invariants(
+                &new_content,
+                &lines,
+                &new_attributions,
+                &line_attributions,
+                &fresh,
+                author,
+            )?;
+
+            // The mapping is a pure function of its inputs
+            let again = attribute_edit(&content, &attributions, &new_content, author, ts).unwrap();
+            prop_assert_eq!(&again.1, &line_attributions);
+
+            content = new_content;
+            attributions = new_attributions;
+        }
+    }
+
+    #[test]
+    fn unchanged_content_keeps_its_attribution(
+        initial in initial_lines(),
+        edit in edit(),
+        author in 0..AUTHORS.len(),
+    ) {
+        let mut lines = initial;
+        let before = render(&lines);
+        apply(&mut lines, &edit, 0);
+        let content = render(&lines);
+
+        let (attributions, line_attributions) =
+            attribute_edit(&before, &[], &content, AUTHORS[edit.author], 1000).unwrap();
+        let (_, unchanged) =
+            attribute_edit(&content, &attributions, &content, AUTHORS[author], 1010).unwrap();
+        prop_assert_eq!(unchanged, line_attributions);
+    }
+}
@@ -1185,3 +1185,35 @@ fn test_blame_ai_human_author() {
         ]
     );
 }
+
+#[test]
+fn test_blame_index_matches_full_blame_as_history_grows() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("test.txt");
+    // --since bypasses the blame index, so git blame walks the full history
+    let assert_matches_full_blame = |args: &[&str]| {
+        let mut blame_args = vec!["blame"];
+        blame_args.extend(args);
+        blame_args.push("test.txt");
+        let indexed = repo.git_ai(&blame_args).unwrap();
+        blame_args.splice(1..1, ["--since", "1970-01-02T00:00:00Z"]);
+        let full = repo.git_ai(&blame_args).unwrap();
+        assert_eq!(indexed, full, "blame {:?} should match a full blame", args);
+    };
+
+    file.set_contents(lines!["a", "b", "c", "d", "e", "f"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+    file.set_contents(lines!["a", "b", "C".ai(), "c2".ai(), "d", "e", "f"]);
+    repo.stage_all_and_commit("Second commit").unwrap();
+    assert_matches_full_blame(&["--porcelain"]);
+
+    // Blamed again on top of the indexed commit
+    file.set_contents(lines!["b", "C".ai(), "c2".ai(), "d", "E", "f", "g".ai()]);
+    repo.stage_all_and_commit("Third commit").unwrap();
+    assert_matches_full_blame(&["--porcelain"]);
+    assert_matches_full_blame(&["--porcelain", "-L", "2,5"]);
+
+    // Uncommitted changes are blamed on top of HEAD's index
+    std::fs::write(repo.path().join("test.txt"), "b\nC\nnew\nd\nE\nf\ng\n").unwrap();
+    assert_matches_full_blame(&["-l"]);
+}
@@ -0,0 +1,62 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_check_completeness_detects_commits_without_notes() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines!["fn a() {}".ai(), "fn b() {}".human()]);
+    let attributed = repo.stage_all_and_commit("Add lib").unwrap();
+
+    let range = format!("{}..{}", base.commit_sha, attributed.commit_sha);
+    let output = repo
+        .git_ai(&["check", "--completeness", &range])
+        .expect("range with notes on every commit should pass");
+    assert!(output.contains("have complete attribution"));
+
+    // Commit made without git-ai hooks, so no note is written
+    std::fs::write(repo.path().join("notes.txt"), "untracked change\n").unwrap();
+    repo.git_og(&["add", "-A"]).unwrap();
+    repo.git_og(&["commit", "-m", "Bypass hooks"]).unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    assert!(
+        repo.git_ai(&["check", "--completeness", &range]).is_err(),
+        "range with a commit missing its note should fail"
+    );
+}
+
+#[test]
+fn test_check_completeness_respects_none_needed_trailer() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    std::fs::write(repo.path().join("vendor.txt"), "vendored\n").unwrap();
+    repo.git_og(&["add", "-A"]).unwrap();
+    repo.git_og(&[
+        "commit",
+        "-m",
+        "Import vendored code",
+        "-m",
+        "Git-AI-Attribution: none",
+    ])
+    .unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let output = repo
+        .git_ai(&["check", "--completeness", &range, "--json"])
+        .expect("commits marked as not needing attribution should pass");
+    let report: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(report["total"], 1);
+    assert_eq!(report["missing"], 0);
+    assert_eq!(report["commits"][0]["status"], "not_needed");
+}
@@ -0,0 +1,70 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_check_staged_fails_on_files_without_checkpoints() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    // Nothing staged: nothing to check
+    let output = repo.git_ai(&["check"]).unwrap();
+    assert!(output.contains("No staged files"));
+
+    let mut lib = repo.filename("lib.rs");
+    lib.set_contents(lines!["fn a() {}".ai(), "fn b() {}".human()]);
+    repo.git_og(&["add", "lib.rs"]).unwrap();
+
+    let output = repo
+        .git_ai(&["check", "--staged"])
+        .expect("checkpointed staged file should pass");
+    assert!(output.contains("recorded by a checkpoint"));
+
+    // Written behind git-ai's back, so no checkpoint knows about it
+    std::fs::write(repo.path().join("untracked.rs"), "fn c() {}\n").unwrap();
+    repo.git_og(&["add", "untracked.rs"]).unwrap();
+
+    assert!(
+        repo.git_ai(&["check"]).is_err(),
+        "staged file without a checkpoint should fail"
+    );
+    assert!(
+        repo.git_ai(&["status", "--fail-on-unattributed"]).is_err(),
+        "status hook mode should fail the same way"
+    );
+}
+
+#[test]
+fn test_check_staged_json_output() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut lib = repo.filename("lib.rs");
+    lib.set_contents(lines!["fn a() {}".ai()]);
+    std::fs::write(repo.path().join("untracked.rs"), "fn c() {}\n").unwrap();
+    repo.git_og(&["add", "-A"]).unwrap();
+
+    // The report is still printed when the check fails
+    let mut command = std::process::Command::new(repos::test_repo::get_binary_path());
+    command
+        .args(["check", "--json"])
+        .current_dir(repo.path())
+        .env("GIT_AI_TEST_DB_PATH", repo.test_db_path());
+    let output = command.output().unwrap();
+    assert!(!output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["total"], 2);
+    assert_eq!(report["unattributed"], 1);
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files[0]["file"], "lib.rs");
+    assert_eq!(files[0]["status"], "attributed");
+    assert_eq!(files[1]["file"], "untracked.rs");
+    assert_eq!(files[1]["status"], "unattributed");
+}
@@ -0,0 +1,253 @@
+mod repos;
+use git_ai::ci::mock_forge::{MockForge, MockMergeRequest, MockPullRequest};
+use repos::test_repo::{TestRepo, get_binary_path};
+use std::process::{Command, Output};
+
+/// An upstream with one commit on `main` and a merged request whose head is at `head_ref`.
+/// Returns (mirror, upstream, sha).
+fn upstream_with_request(head_ref: &str) -> (TestRepo, TestRepo, String) {
+    let (local, upstream) = TestRepo::new_with_remote();
+    local.filename("README.md").set_contents(vec!["# Project"]);
+    let sha = local
+        .stage_all_and_commit("Initial commit")
+        .unwrap()
+        .commit_sha;
+    local.git(&["branch", "-M", "main"]).unwrap();
+    local.git_og(&["push", "origin", "main"]).unwrap();
+    upstream.git_og(&["update-ref", head_ref, &sha]).unwrap();
+    (local, upstream, sha)
+}
+
+fn run_selftest(args: &[&str], envs: Vec<(String, String)>) -> (Output, String) {
+    let output = Command::new(get_binary_path())
+        .args(["ci", "selftest"])
+        .args(args)
+        .envs(envs)
+        .env_remove("GITLAB_TOKEN")
+        .env_remove("GITHUB_ACTIONS")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    (output, stdout)
+}
+
+fn gitlab_forge(upstream: &TestRepo, sha: &str) -> MockForge {
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_project_id("42", "group/project");
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(7, "feature", "main", sha, sha),
+    );
+    forge
+}
+
+#[test]
+fn test_ci_selftest_gitlab_passes_with_full_access() {
+    let (_local, upstream, sha) = upstream_with_request("refs/merge-requests/7/head");
+    let forge = gitlab_forge(&upstream, &sha);
+
+    let (output, stdout) = run_selftest(
+        &[],
+        forge.gitlab_ci_env("42", "group/project", &sha, "job-token"),
+    );
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(stdout.contains("GitLab project group/project (token: CI_JOB_TOKEN)"));
+    assert!(stdout.contains("PASS  list merged requests"));
+    assert!(stdout.contains("latest #7"));
+    assert!(stdout.contains("PASS  read commit"));
+    assert!(stdout.contains("PASS  fetch merge request ref    refs/merge-requests/7/head"));
+    assert!(stdout.contains("PASS  push notes (dry run)"));
+    assert!(stdout.contains("4 passed, 0 failed, 0 skipped"));
+
+    // Nothing was written to the forge
+    assert!(
+        upstream
+            .git_og(&["show-ref", "refs/notes/git-ai-selftest"])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_ci_selftest_reports_denied_push() {
+    let (_local, upstream, sha) = upstream_with_request("refs/merge-requests/7/head");
+    let forge = gitlab_forge(&upstream, &sha);
+    forge.deny_push();
+
+    let (output, stdout) = run_selftest(
+        &["--provider", "gitlab"],
+        forge.gitlab_ci_env("42", "group/project", &sha, "job-token"),
+    );
+    assert!(!output.status.success());
+    assert!(stdout.contains("PASS  fetch merge request ref"));
+    assert!(stdout.contains("FAIL  push notes (dry run)"));
+    assert!(stdout.contains("write_repository"));
+    assert!(stdout.contains("3 passed, 1 failed, 0 skipped"));
+}
+
+#[test]
+fn test_ci_selftest_github_against_mock_forge() {
+    let (_local, upstream, sha) = upstream_with_request("refs/pull/3/head");
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_pull_request(
+        "acme/widgets",
+        MockPullRequest {
+            number: 3,
+            title: "Add widgets".to_string(),
+            head_ref: "widgets".to_string(),
+            head_sha: sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: sha.clone(),
+            merged: true,
+            merge_commit_sha: Some(sha.clone()),
+        },
+    );
+    forge.require_token("gh-token");
+
+    let (output, stdout) = run_selftest(
+        &["--provider", "github"],
+        forge.github_actions_env("acme/widgets", &sha, "gh-token"),
+    );
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(stdout.contains("GitHub repository acme/widgets (token: GITHUB_TOKEN)"));
+    assert!(stdout.contains("PASS  fetch merge request ref    pull/3/head"));
+    assert!(stdout.contains("4 passed, 0 failed, 0 skipped"));
+
+    // A commit the forge doesn't have fails the read check
+    let (output, stdout) = run_selftest(
+        &["--provider", "github"],
+        forge.github_actions_env("acme/widgets", &"0".repeat(40), "gh-token"),
+    );
+    assert!(!output.status.success());
+    assert!(stdout.contains("FAIL  read commit                HTTP 404: 404 Not Found"));
+}
+
+#[test]
+fn test_ci_selftest_github_app_mints_installation_token() {
+    let (_local, upstream, sha) = upstream_with_request("refs/pull/3/head");
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_github_app("12345", 99);
+    // Only tokens the app mints are accepted
+    forge.require_token("unused-token");
+
+    let key_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/github_app_test_key.pem");
+    let mut envs: Vec<(String, String)> = forge
+        .github_actions_env("acme/widgets", &sha, "")
+        .into_iter()
+        .filter(|(key, _)| key != "GITHUB_TOKEN")
+        .collect();
+    envs.push(("GITHUB_APP_ID".to_string(), "12345".to_string()));
+    envs.push((
+        "GITHUB_APP_PRIVATE_KEY_PATH".to_string(),
+        key_path.to_string_lossy().to_string(),
+    ));
+
+    let (output, stdout) = run_selftest(&["--provider", "github"], envs);
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(stdout.contains("GitHub repository acme/widgets (token: GitHub App 12345)"));
+    assert!(stdout.contains("PASS  read commit"));
+    assert_eq!(forge.installation_tokens(), vec!["ghs_mock1".to_string()]);
+
+    let requests = forge.requests();
+    assert!(
+        requests
+            .iter()
+            .any(|r| r.method == "GET" && r.path == "/api/v3/repos/acme/widgets/installation")
+    );
+    assert!(
+        requests.iter().any(|r| {
+            r.method == "POST" && r.path == "/api/v3/app/installations/99/access_tokens"
+        })
+    );
+    let commit_request = requests
+        .iter()
+        .find(|r| r.path.starts_with("/api/v3/repos/acme/widgets/commits/"))
+        .expect("the commit was read");
+    assert_eq!(
+        commit_request
+            .headers
+            .get("authorization")
+            .map(String::as_str),
+        Some("Bearer ghs_mock1")
+    );
+}
+
+/// An unsigned ID token as GitLab would issue for `id_tokens: GIT_AI_ID_TOKEN`
+const GITLAB_ID_TOKEN: &str =
+    "eyJhbGciOiJSUzI1NiJ9.eyJpc3MiOiJodHRwczovL2dpdGxhYi5leGFtcGxlLmNvbSIsImF1ZCI6ImdpdC1haSJ9.sig";
+
+#[test]
+fn test_ci_selftest_gitlab_exchanges_id_token() {
+    let (_local, upstream, sha) = upstream_with_request("refs/merge-requests/7/head");
+    let forge = gitlab_forge(&upstream, &sha);
+    forge.enable_oidc("request-token");
+    // Only exchanged tokens are accepted, not the job token
+    forge.require_token("unused-token");
+
+    let mut envs = forge.gitlab_ci_env("42", "group/project", &sha, "job-token");
+    envs.push((
+        "GIT_AI_OIDC_EXCHANGE_URL".to_string(),
+        forge.oidc_exchange_url(),
+    ));
+
+    // The exchange is configured but the job didn't declare the ID token
+    let (output, _) = run_selftest(&[], envs.clone());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("add it under `id_tokens`"));
+
+    envs.push(("GIT_AI_ID_TOKEN".to_string(), GITLAB_ID_TOKEN.to_string()));
+    let (output, stdout) = run_selftest(&[], envs);
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(stdout.contains("GitLab project group/project (token: GIT_AI_OIDC_TOKEN)"));
+    assert!(stdout.contains("4 passed, 0 failed, 0 skipped"));
+    assert_eq!(
+        forge.exchanged_tokens(),
+        vec!["oidc-exchanged1".to_string()]
+    );
+
+    let exchange = forge
+        .requests()
+        .into_iter()
+        .find(|r| r.path == "/oidc/exchange")
+        .expect("the ID token was exchanged");
+    assert_eq!(exchange.method, "POST");
+    assert_eq!(
+        exchange.headers.get("authorization").map(String::as_str),
+        Some(format!("Bearer {}", GITLAB_ID_TOKEN).as_str())
+    );
+}
+
+#[test]
+fn test_ci_selftest_github_exchanges_actions_oidc_token() {
+    let (_local, upstream, sha) = upstream_with_request("refs/pull/3/head");
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.enable_oidc("request-token");
+    forge.require_token("unused-token");
+
+    let mut envs: Vec<(String, String)> = forge
+        .github_actions_env("acme/widgets", &sha, "")
+        .into_iter()
+        .filter(|(key, _)| key != "GITHUB_TOKEN")
+        .chain(forge.github_oidc_env("request-token"))
+        .collect();
+    envs.push((
+        "GIT_AI_OIDC_EXCHANGE_URL".to_string(),
+        forge.oidc_exchange_url(),
+    ));
+    envs.push(("GIT_AI_OIDC_AUDIENCE".to_string(), "git-ai".to_string()));
+
+    let (output, stdout) = run_selftest(&["--provider", "github"], envs);
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(stdout.contains("GitHub repository acme/widgets (token: GIT_AI_OIDC_TOKEN)"));
+    assert!(stdout.contains("PASS  read commit"));
+    assert!(
+        forge
+            .requests()
+            .iter()
+            .any(|r| { r.path == "/oidc/github-id-token?api-version=2.0&audience=git-ai" })
+    );
+}
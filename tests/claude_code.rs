@@ -366,3 +366,35 @@ fn test_user_text_content_blocks_are_parsed_correctly() {
         "Second message should be Assistant"
     );
 }
+
+#[test]
+fn test_token_usage_sums_assistant_entries() {
+    use git_ai::authorship::working_log::TokenUsage;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    let jsonl_content = r#"{"type":"user","message":{"role":"user","content":"Add a test"},"timestamp":"2025-01-01T00:00:00Z"}
+{"type":"assistant","message":{"model":"claude-sonnet-4-20250514","role":"assistant","content":[{"type":"text","text":"Sure"}],"usage":{"input_tokens":10,"cache_read_input_tokens":100,"output_tokens":5}},"timestamp":"2025-01-01T00:00:01Z"}
+{"type":"assistant","message":{"model":"claude-sonnet-4-20250514","role":"assistant","content":[{"type":"text","text":"Done"}],"usage":{"input_tokens":3,"cache_creation_input_tokens":20,"output_tokens":7}},"timestamp":"2025-01-01T00:00:02Z"}"#;
+
+    let mut temp_file = NamedTempFile::new().unwrap();
+    temp_file.write_all(jsonl_content.as_bytes()).unwrap();
+
+    assert_eq!(
+        ClaudePreset::token_usage_from_claude_code_jsonl(temp_file.path().to_str().unwrap()),
+        Some(TokenUsage {
+            input_tokens: 133,
+            output_tokens: 12,
+        })
+    );
+
+    // Transcripts without usage report none rather than zero
+    let mut no_usage = NamedTempFile::new().unwrap();
+    no_usage
+        .write_all(br#"{"type":"assistant","message":{"role":"assistant","content":[]}}"#)
+        .unwrap();
+    assert_eq!(
+        ClaudePreset::token_usage_from_claude_code_jsonl(no_usage.path().to_str().unwrap()),
+        None
+    );
+}
@@ -0,0 +1,59 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn extract_json_object(output: &str) -> String {
+    let start = output.find('{').unwrap_or(0);
+    let end = output.rfind('}').unwrap_or(output.len().saturating_sub(1));
+    output[start..=end].to_string()
+}
+
+fn compare_json(repo: &TestRepo, args: &[&str]) -> serde_json::Value {
+    let mut full_args = vec!["compare", "--json"];
+    full_args.extend_from_slice(args);
+    let output = repo.git_ai(&full_args).expect("compare should succeed");
+    serde_json::from_str(&extract_json_object(&output)).expect("compare should print JSON")
+}
+
+#[test]
+fn test_compare_counts_branch_lines_from_merge_base() {
+    let repo = TestRepo::new();
+    let mut lib = repo.filename("src/lib.rs");
+    lib.set_contents(lines!["fn base() {}".human()]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+    repo.git(&["branch", "-M", "main"]).unwrap();
+
+    repo.git(&["checkout", "-b", "feature"]).unwrap();
+    lib.insert_at(0, lines!["fn ai_one() {}".ai(), "fn ai_two() {}".ai()]);
+    repo.stage_all_and_commit("AI functions").unwrap();
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Feature".human()]);
+    repo.stage_all_and_commit("Docs").unwrap();
+
+    // Work landing on main after the branch point isn't part of the comparison
+    repo.git(&["checkout", "main"]).unwrap();
+    let mut other = repo.filename("other.rs");
+    other.set_contents(lines!["fn other() {}".ai()]);
+    repo.stage_all_and_commit("Unrelated").unwrap();
+
+    let result = compare_json(&repo, &["main..feature"]);
+    assert_eq!(result["commits"], 2);
+    assert_eq!(result["commits_without_notes"], 0);
+    assert_eq!(result["totals"]["added_lines"], 3);
+    assert_eq!(result["totals"]["ai_lines"], 2);
+    assert_eq!(result["totals"]["human_lines"], 1);
+    assert_eq!(result["files"]["src/lib.rs"]["ai_lines"], 2);
+    assert_eq!(result["files"]["README.md"]["human_lines"], 1);
+    assert!(result["files"].get("other.rs").is_none());
+    let agents = result["totals"]["by_agent"].as_object().unwrap();
+    assert_eq!(agents.len(), 1);
+    assert_eq!(agents.values().next().unwrap(), 2);
+
+    let ignored = compare_json(&repo, &["main", "feature", "--ignore", "*.md"]);
+    assert_eq!(ignored["totals"]["added_lines"], 2);
+    assert!(ignored["files"].get("README.md").is_none());
+
+    let text = repo.git_ai(&["compare", "main..feature"]).unwrap();
+    assert!(text.contains("main..feature: 2 commit(s) since merge base"));
+    assert!(text.contains("src/lib.rs"));
+}
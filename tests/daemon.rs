@@ -0,0 +1,194 @@
+#![cfg(unix)]
+
+mod repos;
+use git_ai::authorship::heatmap::Heatmap;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::{TestRepo, get_binary_path};
+use serde_json::Value;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Kills the daemon if the test fails before stopping it
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !done() {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "timed out waiting for {}",
+            what
+        );
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn cached_head(path: &Path) -> Option<String> {
+    Heatmap::load(path)
+        .ok()
+        .flatten()
+        .map(|heatmap| heatmap.head)
+}
+
+#[test]
+fn test_daemon_refreshes_watched_heatmap_after_commits() {
+    let repo = TestRepo::new();
+    let socket_dir = tempfile::tempdir().unwrap();
+    let socket = socket_dir.path().join("daemon.sock");
+    let socket_env = [("GIT_AI_DAEMON_SOCKET", socket.to_str().unwrap())];
+
+    let mut file = repo.filename("src/lib.rs");
+    file.set_contents(lines!["fn human() {}".human(), "fn ai() {}".ai()]);
+    let first = repo.stage_all_and_commit("Add lib").unwrap();
+
+    let mut daemon = Daemon(
+        Command::new(get_binary_path())
+            .args(["daemon", "run", "--poll-interval-ms", "100"])
+            .current_dir(repo.path())
+            .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+            .env("GIT_AI_DAEMON_SOCKET", &socket)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    wait_for("the daemon to listen", || socket.exists());
+    assert_eq!(
+        std::fs::metadata(&socket).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+
+    let output = repo
+        .git_ai_with_env(&["daemon", "watch"], &socket_env)
+        .expect("watch should succeed");
+    assert!(output.contains("Watching"), "{}", output);
+
+    let cache = repo.path().join(".git").join("ai").join("heatmap");
+    wait_for("the first refresh", || {
+        cached_head(&cache).as_deref() == Some(first.commit_sha.as_str())
+    });
+    assert!(Heatmap::load(&cache).unwrap().unwrap().files["src/lib.rs"].is_ai(2));
+
+    let output = repo
+        .git_ai_with_env(&["daemon", "status", "--json"], &socket_env)
+        .expect("status should succeed while the daemon runs");
+    let status: Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(status["running"], true);
+    assert_eq!(status["pid"], daemon.0.id());
+    assert!(status["git_ai_version"].is_string());
+    let repositories = status["repositories"].as_array().unwrap();
+    assert_eq!(repositories.len(), 1);
+    assert_eq!(repositories[0]["heatmap_head"], first.commit_sha.as_str());
+    assert_eq!(repositories[0]["cache"]["heatmap_files"], 1);
+    assert_eq!(
+        repositories[0]["cache"]["heatmap_bytes"],
+        std::fs::metadata(&cache).unwrap().len()
+    );
+
+    file.set_contents(lines![
+        "fn human() {}".human(),
+        "fn ai() {}".ai(),
+        "fn more_ai() {}".ai()
+    ]);
+    let second = repo.stage_all_and_commit("Extend lib").unwrap();
+    wait_for("the refresh after the second commit", || {
+        cached_head(&cache).as_deref() == Some(second.commit_sha.as_str())
+    });
+    assert_eq!(
+        Heatmap::load(&cache).unwrap().unwrap().files["src/lib.rs"].ai_line_count(),
+        2
+    );
+
+    repo.git_ai_with_env(&["daemon", "stop"], &socket_env)
+        .expect("stop should succeed");
+    let status = daemon.0.wait().unwrap();
+    assert!(status.success());
+    assert!(!socket.exists());
+    assert!(
+        repo.git_ai_with_env(&["daemon", "stop"], &socket_env)
+            .is_err(),
+        "stop should fail once the daemon is gone"
+    );
+    assert!(
+        repo.git_ai_with_env(&["daemon", "status"], &socket_env)
+            .is_err(),
+        "status should fail once the daemon is gone"
+    );
+}
+
+#[test]
+fn test_daemon_status_reports_unresponsive_daemon() {
+    let repo = TestRepo::new();
+    let socket_dir = tempfile::tempdir().unwrap();
+    let socket = socket_dir.path().join("daemon.sock");
+
+    // Accepts connections (through the backlog) but never answers
+    let _listener = UnixListener::bind(&socket).unwrap();
+    let error = repo
+        .git_ai_with_env(
+            &["daemon", "status"],
+            &[("GIT_AI_DAEMON_SOCKET", socket.to_str().unwrap())],
+        )
+        .expect_err("status should fail when the daemon doesn't answer");
+    assert!(error.contains("did not answer"), "{}", error);
+}
+
+#[test]
+fn test_daemon_rebuilds_heatmap_when_repo_config_changes() {
+    let repo = TestRepo::new();
+    let socket_dir = tempfile::tempdir().unwrap();
+    let socket = socket_dir.path().join("daemon.sock");
+    let socket_env = [("GIT_AI_DAEMON_SOCKET", socket.to_str().unwrap())];
+
+    let mut first = repo.filename("a.rs");
+    first.set_contents(lines!["fn a() {}".ai()]);
+    let mut second = repo.filename("b.rs");
+    second.set_contents(lines!["fn b() {}".ai()]);
+    repo.stage_all_and_commit("Add files").unwrap();
+    repo.git_ai(&["heatmap"]).expect("heatmap should succeed");
+
+    let _daemon = Daemon(
+        Command::new(get_binary_path())
+            .args(["daemon", "run", "--poll-interval-ms", "100"])
+            .current_dir(repo.path())
+            .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+            .env("GIT_AI_DAEMON_SOCKET", &socket)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    wait_for("the daemon to listen", || socket.exists());
+    repo.git_ai_with_env(&["daemon", "watch"], &socket_env)
+        .expect("watch should succeed");
+
+    let last_refresh = || {
+        let output = repo
+            .git_ai_with_env(&["daemon", "status", "--json"], &socket_env)
+            .ok()?;
+        let status: Value = serde_json::from_str(output.trim()).ok()?;
+        let refresh = &status["repositories"][0]["last_refresh"];
+        Some((refresh["reused"].as_u64()?, refresh["computed"].as_u64()?))
+    };
+    // The first refresh reuses the cache
+    wait_for("the first refresh", || last_refresh() == Some((2, 0)));
+
+    std::fs::write(
+        repo.path().join(".git-ai.toml"),
+        "[policy]\nneutral_whitespace_commits = true\n",
+    )
+    .unwrap();
+    wait_for("the rebuild after the config change", || {
+        last_refresh() == Some((0, 2))
+    });
+}
@@ -794,3 +794,55 @@ fn test_diff_range_multiple_commits() {
         "Should have attribution markers"
     );
 }
+
+#[test]
+fn test_diff_staged_uses_index_and_working_log() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("test.txt");
+    file.set_contents(lines!["Line 1".human()]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    file.set_contents(lines![
+        "Line 1".human(),
+        "AI line".ai(),
+        "Human line".human()
+    ]);
+    repo.git(&["add", "test.txt"]).unwrap();
+
+    // Unstaged edits must not show up in the staged diff
+    std::fs::write(
+        repo.path().join("test.txt"),
+        "Line 1\nAI line\nHuman line\nUnstaged line\n",
+    )
+    .unwrap();
+
+    let output = repo.git_ai(&["diff", "--staged"]).unwrap();
+    let lines = parse_diff_output(&output);
+
+    assert_diff_lines_exact(
+        &lines,
+        &[
+            // The committed file had no trailing newline
+            ("-", "Line 1", None),
+            ("+", "Line 1", Some("human:")),
+            ("+", "AI line", Some("ai:mock_ai")),
+            ("+", "Human line", Some("human:")),
+        ],
+    );
+
+    let output = repo.git_ai(&["diff", "--staged", "--json"]).unwrap();
+    // Debug logging may follow the JSON in the captured output
+    let json: serde_json::Value = serde_json::Deserializer::from_str(&output)
+        .into_iter()
+        .next()
+        .unwrap()
+        .unwrap();
+    let annotations = json["files"]["test.txt"]["annotations"]
+        .as_object()
+        .unwrap();
+    assert_eq!(annotations.len(), 1);
+    let (prompt_id, ranges) = annotations.iter().next().unwrap();
+    assert_eq!(ranges, &serde_json::json!([2]));
+    assert_eq!(json["prompts"][prompt_id]["agent_id"]["tool"], "mock_ai");
+}
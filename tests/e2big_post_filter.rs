@@ -403,89 +403,7 @@ fn test_diff_added_lines_post_filter_correct_line_numbers() {
 }
 
 // ============================================================
-// Test Group D: diff_workdir_added_lines_with_insertions()
-// ============================================================
-
-#[test]
-fn test_diff_workdir_insertions_post_filter_equivalence() {
-    let repo = TestRepo::new();
-
-    // Commit 3 files
-    let filenames = create_files(&repo, 3, |i| format!("line1_{}\n", i));
-    repo.git_og(&["add", "-A"]).unwrap();
-    repo.git_og(&["commit", "-m", "initial"]).unwrap();
-
-    // Modify 2 in working dir (don't commit)
-    std::fs::write(
-        repo.path().join(&filenames[0]),
-        format!("line1_0\nline2_0\n"),
-    )
-    .unwrap();
-    std::fs::write(
-        repo.path().join(&filenames[1]),
-        format!("line1_1\nline2_1\n"),
-    )
-    .unwrap();
-
-    let gitai_repo = find_repository_in_path(repo.path().to_str().unwrap()).unwrap();
-    let head_sha = gitai_repo.head().unwrap().target().unwrap();
-
-    // Small pathspec
-    let small: HashSet<String> = filenames.iter().cloned().collect();
-    let (all_small, ins_small) = gitai_repo
-        .diff_workdir_added_lines_with_insertions(&head_sha, Some(&small))
-        .unwrap();
-
-    // Padded pathspec
-    let refs: Vec<&str> = filenames.iter().map(|s| s.as_str()).collect();
-    let large = padded_pathspecs(&refs);
-    let (all_large, ins_large) = gitai_repo
-        .diff_workdir_added_lines_with_insertions(&head_sha, Some(&large))
-        .unwrap();
-
-    assert_eq!(all_small, all_large, "all_added should be identical");
-    assert_eq!(ins_small, ins_large, "pure_insertions should be identical");
-}
-
-#[test]
-fn test_diff_workdir_insertions_both_maps_filtered() {
-    let repo = TestRepo::new();
-
-    // Commit a.txt and b.txt
-    std::fs::write(repo.path().join("a.txt"), "a_line1\n").unwrap();
-    std::fs::write(repo.path().join("b.txt"), "b_line1\n").unwrap();
-    repo.git_og(&["add", "-A"]).unwrap();
-    repo.git_og(&["commit", "-m", "initial"]).unwrap();
-
-    // Append lines to both in working dir
-    std::fs::write(repo.path().join("a.txt"), "a_line1\na_line2\n").unwrap();
-    std::fs::write(repo.path().join("b.txt"), "b_line1\nb_line2\n").unwrap();
-
-    let gitai_repo = find_repository_in_path(repo.path().to_str().unwrap()).unwrap();
-    let head_sha = gitai_repo.head().unwrap().target().unwrap();
-
-    // Padded pathspec containing only "a.txt"
-    let pathspecs = padded_pathspecs(&["a.txt"]);
-    let (all_added, pure_insertions) = gitai_repo
-        .diff_workdir_added_lines_with_insertions(&head_sha, Some(&pathspecs))
-        .unwrap();
-
-    assert!(
-        all_added.contains_key("a.txt"),
-        "all_added should have a.txt"
-    );
-    assert!(
-        !all_added.contains_key("b.txt"),
-        "all_added should NOT have b.txt"
-    );
-    assert!(
-        !pure_insertions.contains_key("b.txt"),
-        "pure_insertions should NOT have b.txt"
-    );
-}
-
-// ============================================================
-// Test Group E: diff_tree_to_tree()
+// Test Group D: diff_tree_to_tree()
 // ============================================================
 
 #[test]
@@ -597,7 +515,7 @@ fn test_diff_tree_to_tree_post_filter_exclusion() {
 }
 
 // ============================================================
-// Test Group F: Boundary & edge cases
+// Test Group E: Boundary & edge cases
 // ============================================================
 
 #[test]
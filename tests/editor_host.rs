@@ -0,0 +1,370 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::{TestRepo, get_binary_path};
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+fn parse_lines(output: &str) -> Vec<Value> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[test]
+fn test_editor_host_requests() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("src/lib.rs");
+    file.set_contents(lines!["fn human() {}".human(), "fn ai() {}".ai()]);
+    repo.stage_all_and_commit("Add lib").unwrap();
+
+    let requests = [
+        json!({"id": 1, "method": "initialize", "params": {"protocol_version": 1}}),
+        json!({"id": 2, "method": "attribution", "params": {"file": "src/lib.rs"}}),
+        json!({"id": 3, "method": "initialize", "params": {"protocol_version": 99}}),
+        json!({"id": 4, "method": "bogus"}),
+        json!({"id": 5, "method": "shutdown"}),
+    ];
+    let stdin: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+    let output = repo
+        .git_ai_with_stdin(&["editor-host"], stdin.as_bytes())
+        .expect("editor-host should exit cleanly");
+    let responses = parse_lines(&output);
+    assert_eq!(responses.len(), 5);
+
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["result"]["protocol_version"], 1);
+
+    let ranges = responses[1]["result"]["ranges"].as_array().unwrap();
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0]["start_line"], 2);
+    assert_eq!(ranges[0]["end_line"], 2);
+    assert_eq!(ranges[0]["tool"], "mock_ai");
+
+    assert_eq!(
+        responses[2]["error"]["code"],
+        "unsupported_protocol_version"
+    );
+    assert_eq!(responses[3]["error"]["code"], "unknown_method");
+    assert_eq!(responses[4]["id"], 5);
+    assert!(responses[4]["result"].is_null());
+}
+
+#[test]
+fn test_editor_host_notifies_subscribers_on_commit() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("notes.txt");
+    file.set_contents(lines!["first".human()]);
+    repo.stage_all_and_commit("Initial").unwrap();
+
+    let mut child = Command::new(get_binary_path())
+        .args(["editor-host", "--poll-interval-ms", "50"])
+        .current_dir(repo.path())
+        .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn editor-host");
+
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Ok(value) = serde_json::from_str::<Value>(&line) {
+                let _ = tx.send(value);
+            }
+        }
+    });
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(
+        stdin,
+        "{}",
+        json!({"id": 1, "method": "subscribe", "params": {"file": "notes.txt"}})
+    )
+    .unwrap();
+    let subscribed = rx.recv_timeout(Duration::from_secs(30)).unwrap();
+    assert_eq!(subscribed["id"], 1);
+    assert!(
+        subscribed["result"]["ranges"]
+            .as_array()
+            .unwrap()
+            .is_empty()
+    );
+
+    file.set_contents(lines!["first".human(), "second".ai()]);
+    repo.stage_all_and_commit("Add AI line").unwrap();
+
+    let notification = loop {
+        let message = rx
+            .recv_timeout(Duration::from_secs(30))
+            .expect("expected an attribution_changed notification");
+        if message["method"] == "attribution_changed" && message["params"]["reason"] == "commit" {
+            break message;
+        }
+    };
+    let ranges = notification["params"]["attribution"]["ranges"]
+        .as_array()
+        .unwrap();
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0]["start_line"], 2);
+
+    writeln!(stdin, "{}", json!({"id": 2, "method": "shutdown"})).unwrap();
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn test_editor_host_health_and_status() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("notes.txt");
+    file.set_contents(lines!["first".human()]);
+    repo.stage_all_and_commit("Initial").unwrap();
+
+    let stdin = format!(
+        "{}\n{}\n",
+        json!({"id": 1, "method": "health"}),
+        json!({"id": 2, "method": "shutdown"})
+    );
+    let output = repo
+        .git_ai_with_stdin(&["editor-host"], stdin.as_bytes())
+        .expect("editor-host should exit cleanly");
+    let responses = parse_lines(&output);
+    let health = &responses[0]["result"];
+    assert_eq!(health["status"], "ok");
+    assert_eq!(health["protocol_version"], 1);
+    assert_eq!(health["subscriptions"], 0);
+    assert!(health["git_ai_version"].is_string());
+    assert!(health["cache"]["working_log_bytes"].is_u64());
+
+    // No host left running once the one-off host shut down
+    assert!(repo.git_ai(&["editor-host", "--status"]).is_err());
+
+    let mut child = Command::new(get_binary_path())
+        .args(["editor-host", "--poll-interval-ms", "50"])
+        .current_dir(repo.path())
+        .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn editor-host");
+
+    let mut status = Value::Null;
+    for _ in 0..100 {
+        if let Ok(output) = repo.git_ai(&["editor-host", "--status", "--json"]) {
+            status = parse_lines(&output).pop().unwrap_or(Value::Null);
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let hosts = status["hosts"].as_array().expect("expected a running host");
+    assert_eq!(hosts.len(), 1);
+    assert_eq!(hosts[0]["pid"], child.id());
+    assert_eq!(hosts[0]["status"], "running");
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "{}", json!({"id": 1, "method": "shutdown"})).unwrap();
+    drop(stdin);
+    let _ = child.wait();
+
+    assert!(repo.git_ai(&["editor-host", "--status"]).is_err());
+}
+
+#[test]
+fn test_editor_host_reloads_config_without_restart() {
+    let repo = TestRepo::new();
+    let home = tempfile::tempdir().unwrap();
+
+    let mut file = repo.filename("notes.txt");
+    file.set_contents(lines!["first".human()]);
+    repo.stage_all_and_commit("Initial").unwrap();
+
+    let mut child = Command::new(get_binary_path())
+        .args(["editor-host", "--poll-interval-ms", "50"])
+        .current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn editor-host");
+
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Ok(value) = serde_json::from_str::<Value>(&line) {
+                let _ = tx.send(value);
+            }
+        }
+    });
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(
+        stdin,
+        "{}",
+        json!({"id": 1, "method": "subscribe", "params": {"file": "notes.txt"}})
+    )
+    .unwrap();
+    let subscribed = rx.recv_timeout(Duration::from_secs(30)).unwrap();
+    assert_eq!(subscribed["id"], 1);
+
+    let config_dir = home.path().join(".git-ai");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.json"),
+        json!({"test_path_patterns": ["spec/**"]}).to_string(),
+    )
+    .unwrap();
+
+    let notification = loop {
+        let message = rx
+            .recv_timeout(Duration::from_secs(30))
+            .expect("expected a config attribution_changed notification");
+        if message["method"] == "attribution_changed" {
+            break message;
+        }
+    };
+    assert_eq!(notification["params"]["reason"], "config");
+    assert_eq!(notification["params"]["attribution"]["file"], "notes.txt");
+
+    writeln!(stdin, "{}", json!({"id": 2, "method": "shutdown"})).unwrap();
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn test_editor_host_reports_config_reload_during_commit_note_grace() {
+    let repo = TestRepo::new();
+    let home = tempfile::tempdir().unwrap();
+
+    let mut file = repo.filename("notes.txt");
+    file.set_contents(lines!["first".human()]);
+    repo.stage_all_and_commit("Initial").unwrap();
+
+    let mut child = Command::new(get_binary_path())
+        .args(["editor-host", "--poll-interval-ms", "50"])
+        .current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn editor-host");
+
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Ok(value) = serde_json::from_str::<Value>(&line) {
+                let _ = tx.send(value);
+            }
+        }
+    });
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(
+        stdin,
+        "{}",
+        json!({"id": 1, "method": "subscribe", "params": {"file": "notes.txt"}})
+    )
+    .unwrap();
+    let subscribed = rx.recv_timeout(Duration::from_secs(30)).unwrap();
+    assert_eq!(subscribed["id"], 1);
+
+    // Plain git writes no authorship note, so the host waits out the whole grace period,
+    // and the config changes while it waits
+    std::fs::write(repo.path().join("notes.txt"), "first\nsecond\n").unwrap();
+    repo.git_og(&["commit", "-am", "Without a note"]).unwrap();
+    let config_dir = home.path().join(".git-ai");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.json"),
+        json!({"test_path_patterns": ["spec/**"]}).to_string(),
+    )
+    .unwrap();
+
+    let mut reasons = Vec::new();
+    while !reasons.contains(&"commit".to_string()) {
+        let message = rx
+            .recv_timeout(Duration::from_secs(30))
+            .expect("expected a commit attribution_changed notification");
+        if message["method"] == "attribution_changed" {
+            reasons.push(message["params"]["reason"].as_str().unwrap().to_string());
+        }
+    }
+    assert_eq!(reasons, vec!["config", "commit"]);
+
+    writeln!(stdin, "{}", json!({"id": 2, "method": "shutdown"})).unwrap();
+    drop(stdin);
+    let _ = child.wait();
+}
+
+#[test]
+fn test_editor_host_reloads_repo_config_without_restart() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("notes.txt");
+    file.set_contents(lines!["first".human()]);
+    repo.stage_all_and_commit("Initial").unwrap();
+
+    let mut child = Command::new(get_binary_path())
+        .args(["editor-host", "--poll-interval-ms", "50"])
+        .current_dir(repo.path())
+        .env("GIT_AI_TEST_DB_PATH", repo.test_db_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn editor-host");
+
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Ok(value) = serde_json::from_str::<Value>(&line) {
+                let _ = tx.send(value);
+            }
+        }
+    });
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(
+        stdin,
+        "{}",
+        json!({"id": 1, "method": "subscribe", "params": {"file": "notes.txt"}})
+    )
+    .unwrap();
+    let subscribed = rx.recv_timeout(Duration::from_secs(30)).unwrap();
+    assert_eq!(subscribed["id"], 1);
+
+    std::fs::write(
+        repo.path().join(".git-ai.toml"),
+        "[policy]\nneutral_whitespace_commits = true\n",
+    )
+    .unwrap();
+
+    let notification = loop {
+        let message = rx
+            .recv_timeout(Duration::from_secs(30))
+            .expect("expected a config attribution_changed notification");
+        if message["method"] == "attribution_changed" {
+            break message;
+        }
+    };
+    assert_eq!(notification["params"]["reason"], "config");
+
+    writeln!(stdin, "{}", json!({"id": 2, "method": "shutdown"})).unwrap();
+    drop(stdin);
+    let _ = child.wait();
+}
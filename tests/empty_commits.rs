@@ -0,0 +1,74 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn head_sha(repo: &TestRepo) -> String {
+    repo.git(&["rev-parse", "HEAD"]).unwrap().trim().to_string()
+}
+
+/// Notes are an attestation section followed by `---` and the JSON metadata.
+fn assert_empty_note(note: &str) {
+    let (attestations, metadata) = note.split_once("---").expect("note separator");
+    assert!(
+        attestations.trim().is_empty(),
+        "unexpected attestations: {}",
+        note
+    );
+    let metadata: serde_json::Value = serde_json::from_str(metadata.trim()).unwrap();
+    assert_eq!(metadata["prompts"], serde_json::json!({}));
+    assert!(metadata.get("classification").is_none());
+}
+
+#[test]
+fn test_allow_empty_commit_writes_empty_note_and_keeps_pending_ai() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("test.txt");
+
+    file.set_contents(lines!["line 1", "line 2"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+    let base = head_sha(&repo);
+
+    // AI edits are pending but nothing is staged
+    file.insert_at(2, lines!["// AI line".ai()]);
+
+    repo.git(&["commit", "--allow-empty", "-m", "Empty commit"])
+        .unwrap();
+    let empty_sha = head_sha(&repo);
+
+    let note = repo
+        .git(&["notes", "--ref=ai", "show", &empty_sha])
+        .expect("empty commit should still get a note");
+    assert_empty_note(&note);
+
+    repo.git_ai(&["check", "--completeness", &format!("{}..HEAD", base)])
+        .expect("completeness check should pass");
+
+    repo.stage_all_and_commit("Add AI line").unwrap();
+    file.assert_lines_and_blame(lines![
+        "line 1".human(),
+        "line 2".human(),
+        "// AI line".ai()
+    ]);
+}
+
+#[test]
+fn test_mode_only_commit_writes_empty_note() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("script.sh");
+
+    file.set_contents(lines!["echo hi".ai()]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    repo.git(&["update-index", "--chmod=+x", "script.sh"])
+        .unwrap();
+    repo.git(&["commit", "-m", "Make executable"]).unwrap();
+    let sha = head_sha(&repo);
+
+    let note = repo
+        .git(&["notes", "--ref=ai", "show", &sha])
+        .expect("mode-only commit should still get a note");
+    assert_empty_note(&note);
+
+    file.assert_lines_and_blame(lines!["echo hi".ai()]);
+}
@@ -0,0 +1,70 @@
+#[macro_use]
+mod repos;
+use git_ai::model::ExportEvent;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn parse_events(output: &str) -> Vec<ExportEvent> {
+    output
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_export_jsonl_emits_commit_and_hunk_events() {
+    let repo = TestRepo::new();
+    let mut lib = repo.filename("src/lib.rs");
+    lib.set_contents(lines![
+        "fn human() {}".human(),
+        "fn ai_one() {}".ai(),
+        "fn ai_two() {}".ai()
+    ]);
+    let first = repo.stage_all_and_commit("Add lib").unwrap();
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project".human()]);
+    let second = repo.stage_all_and_commit("Docs").unwrap();
+
+    let output = repo
+        .git_ai(&["export", "--format", "jsonl", "--since", "1d"])
+        .unwrap();
+    let events = parse_events(&output);
+    assert_eq!(events.len(), 3, "output: {}", output);
+
+    // Newest commit first, each followed by its hunks
+    let ExportEvent::Commit(docs) = &events[0] else {
+        panic!("expected a commit event");
+    };
+    assert_eq!(docs.commit, second.commit_sha);
+    assert_eq!(docs.subject, "Docs");
+    assert_eq!(
+        (docs.added_lines, docs.ai_lines, docs.human_lines),
+        (1, 0, 1)
+    );
+    assert_eq!(docs.parents, vec![first.commit_sha.clone()]);
+
+    let ExportEvent::Commit(lib_commit) = &events[1] else {
+        panic!("expected a commit event");
+    };
+    assert_eq!(lib_commit.commit, first.commit_sha);
+    assert!(lib_commit.has_authorship);
+    assert_eq!((lib_commit.ai_lines, lib_commit.human_lines), (2, 1));
+    assert!(lib_commit.authored_at.ends_with('Z'));
+
+    let ExportEvent::Hunk(hunk) = &events[2] else {
+        panic!("expected a hunk event");
+    };
+    assert_eq!(hunk.commit, first.commit_sha);
+    assert_eq!(hunk.file, "src/lib.rs");
+    assert_eq!((hunk.start_line, hunk.end_line, hunk.line_count), (2, 3, 2));
+    assert!(hunk.tool.is_some());
+    assert!(hunk.session_id.is_some());
+
+    // Re-exporting gives the same event ids, for deduplication
+    let again = parse_events(&repo.git_ai(&["export"]).unwrap());
+    assert_eq!(again, events);
+
+    let output = repo.git_ai(&["export", "--until", "2000-01-01"]).unwrap();
+    assert!(parse_events(&output).is_empty(), "output: {}", output);
+}
@@ -0,0 +1,78 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn fetch_missing_restores_reconstructs_and_reports_unknown() {
+    let repo = TestRepo::new();
+    let has_note = |sha: &str| repo.git_og(&["notes", "--ref=ai", "show", sha]).is_ok();
+
+    let mut base = repo.filename("base.rs");
+    base.set_contents(lines!["fn base() {}".ai()]);
+    let first = repo.stage_all_and_commit("Add base").unwrap();
+
+    let mut feature = repo.filename("feature.rs");
+    feature.set_contents(lines!["fn feature() {}".ai(), "fn helper() {}".ai()]);
+    let annotated = repo.stage_all_and_commit("Add feature").unwrap();
+
+    // A teammate's notes were fetched into the tracking ref, but ours lost one
+    repo.git_og(&["update-ref", "refs/notes/ai-remote/origin", "refs/notes/ai"])
+        .unwrap();
+    repo.git_og(&["notes", "--ref=ai", "remove", &first.commit_sha])
+        .unwrap();
+
+    // Commits made while hooks weren't running: a plain commit and a cherry-pick
+    repo.git_og(&["checkout", "-q", "-b", "copy", &first.commit_sha])
+        .unwrap();
+    std::fs::write(repo.path().join("notes.txt"), "by hand\n").unwrap();
+    repo.git_og(&["add", "notes.txt"]).unwrap();
+    repo.git_og(&["commit", "-q", "-m", "Add notes by hand"])
+        .unwrap();
+    let manual = repo
+        .git_og(&["rev-parse", "HEAD"])
+        .unwrap()
+        .trim()
+        .to_string();
+    repo.git_og(&["cherry-pick", &annotated.commit_sha])
+        .unwrap();
+    let picked = repo
+        .git_og(&["rev-parse", "HEAD"])
+        .unwrap()
+        .trim()
+        .to_string();
+
+    let report: serde_json::Value = serde_json::from_str(
+        &repo
+            .git_ai(&["fetch-missing", "--offline", "--dry-run", "--json"])
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(report["commits"], 3);
+    assert_eq!(report["missing"], 3);
+    assert_eq!(report["restored"][0]["commit"], first.commit_sha.as_str());
+    assert_eq!(
+        report["restored"][0]["source"],
+        "refs/notes/ai-remote/origin"
+    );
+    assert_eq!(report["reconstructed"][0]["commit"], picked.as_str());
+    assert_eq!(
+        report["reconstructed"][0]["source"],
+        annotated.commit_sha.as_str()
+    );
+    assert_eq!(report["unknown"][0]["commit"], manual.as_str());
+    assert_eq!(report["unknown"][0]["subject"], "Add notes by hand");
+    assert!(!has_note(&first.commit_sha));
+    assert!(!has_note(&picked));
+
+    let output = repo.git_ai(&["fetch-missing", "--offline"]).unwrap();
+    assert!(output.contains("3 of 3 commits in HEAD have no authorship note"));
+    assert!(output.contains("1 restored from remote notes"));
+    assert!(output.contains("1 reconstructed from commits with the same patch"));
+    assert!(output.contains("1 unknown"));
+    assert!(has_note(&first.commit_sha));
+    assert!(has_note(&picked));
+    assert!(!has_note(&manual));
+
+    feature.assert_lines_and_blame(lines!["fn feature() {}".ai(), "fn helper() {}".ai()]);
+}
@@ -0,0 +1,56 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn grep_json(repo: &TestRepo, args: &[&str]) -> serde_json::Value {
+    let output = repo
+        .git_ai(&[&["grep", "--json"], args].concat())
+        .expect("grep should succeed");
+    // Debug builds log to stderr, which is captured too
+    let json = output
+        .lines()
+        .find(|line| line.starts_with('['))
+        .expect("grep should print JSON");
+    serde_json::from_str(json).expect("grep should print JSON")
+}
+
+#[test]
+fn test_grep_attributes_matching_lines() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("run.py");
+    file.set_contents(lines![
+        "import os".human(),
+        "os.system('ls')".human(),
+        "def run(cmd):".ai(),
+        "    os.system(cmd)".ai(),
+    ]);
+    repo.stage_all_and_commit("Add runner").unwrap();
+
+    let matches = grep_json(&repo, &["os.system"]);
+    let matches = matches.as_array().unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0]["line"], 2);
+    assert_eq!(matches[0]["attribution"], "human");
+    assert_eq!(matches[1]["file"], "run.py");
+    assert_eq!(matches[1]["line"], 4);
+    assert_eq!(matches[1]["text"], "    os.system(cmd)");
+    assert_eq!(matches[1]["attribution"], "ai");
+    assert_eq!(matches[1]["agent"], "mock_ai");
+    assert!(matches[1]["session"].is_string());
+
+    let ai_only = grep_json(&repo, &["--ai", "os.system"]);
+    assert_eq!(ai_only.as_array().unwrap().len(), 1);
+    assert_eq!(ai_only[0]["line"], 4);
+
+    // An older revision, with pathspecs after `--`
+    std::fs::write(repo.path().join("run.py"), "import os\n").unwrap();
+    repo.stage_all_and_commit("Remove runner").unwrap();
+    assert!(repo.git_ai(&["grep", "os.system"]).is_err());
+    let old = grep_json(
+        &repo,
+        &["--rev", "HEAD~1", "--ai", "os.system", "--", "run.py"],
+    );
+    assert_eq!(old.as_array().unwrap().len(), 1);
+    assert_eq!(old[0]["file"], "run.py");
+}
@@ -0,0 +1,92 @@
+mod repos;
+use git_ai::authorship::heatmap::Heatmap;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn export(repo: &TestRepo) -> serde_json::Value {
+    let output = repo
+        .git_ai(&["heatmap", "--json"])
+        .expect("heatmap should succeed");
+    serde_json::from_str(output.trim()).expect("heatmap should print JSON")
+}
+
+#[test]
+fn test_heatmap_export_marks_ai_lines() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("src/lib.rs");
+    file.set_contents(lines![
+        "fn human() {}".human(),
+        "fn ai_one() {}".ai(),
+        "fn ai_two() {}".ai()
+    ]);
+    let mut other = repo.filename("README.md");
+    other.set_contents(lines!["# Readme".human()]);
+    let commit = repo.stage_all_and_commit("Add lib").unwrap();
+
+    let summary = export(&repo);
+    assert_eq!(summary["files"], 1);
+    assert_eq!(summary["computed"], 1);
+
+    let path = repo.path().join(".git").join("ai").join("heatmap");
+    let heatmap = Heatmap::load(&path).unwrap().expect("cache should exist");
+    assert_eq!(heatmap.head, commit.commit_sha);
+    let lib = &heatmap.files["src/lib.rs"];
+    assert_eq!(lib.line_count, 3);
+    assert!(!lib.is_ai(1));
+    assert!(lib.is_ai(2));
+    assert!(lib.is_ai(3));
+    assert!(!heatmap.files.contains_key("README.md"));
+}
+
+#[test]
+fn test_heatmap_refresh_reuses_unchanged_files() {
+    let repo = TestRepo::new();
+
+    let mut first = repo.filename("a.rs");
+    first.set_contents(lines!["fn a() {}".ai()]);
+    let mut second = repo.filename("b.rs");
+    second.set_contents(lines!["fn b() {}".ai()]);
+    repo.stage_all_and_commit("Add files").unwrap();
+    export(&repo);
+
+    second.set_contents(lines!["fn b() {}".ai(), "fn c() {}".ai()]);
+    repo.stage_all_and_commit("Extend b").unwrap();
+
+    let summary = export(&repo);
+    assert_eq!(summary["reused"], 1);
+    assert_eq!(summary["computed"], 1);
+
+    let path = repo.path().join(".git").join("ai").join("heatmap");
+    let heatmap = Heatmap::load(&path).unwrap().unwrap();
+    assert_eq!(heatmap.files["b.rs"].ai_line_count(), 2);
+}
+
+#[test]
+fn test_heatmap_refresh_keeps_earlier_ai_files_across_new_and_rewritten_commits() {
+    let repo = TestRepo::new();
+
+    let mut ai = repo.filename("a.rs");
+    ai.set_contents(lines!["fn a() {}".ai()]);
+    repo.stage_all_and_commit("Add a").unwrap();
+    export(&repo);
+
+    // Only the new commit is walked; a.rs comes from the cache
+    let mut human = repo.filename("notes.txt");
+    human.set_contents(lines!["notes".human()]);
+    repo.stage_all_and_commit("Add notes").unwrap();
+    let summary = export(&repo);
+    assert_eq!(summary["files"], 1);
+    assert_eq!(summary["reused"], 1);
+    assert_eq!(summary["computed"], 0);
+
+    // The cached head is no longer an ancestor, so the whole history is walked again
+    repo.git(&["commit", "--amend", "-m", "Add notes, reworded"])
+        .unwrap();
+    let summary = export(&repo);
+    assert_eq!(summary["files"], 1);
+
+    let path = repo.path().join(".git").join("ai").join("heatmap");
+    let heatmap = Heatmap::load(&path).unwrap().unwrap();
+    assert!(heatmap.files["a.rs"].is_ai(1));
+}
@@ -0,0 +1,113 @@
+mod repos;
+use git_ai::model::CommitSummary;
+use repos::test_repo::TestRepo;
+
+/// Two commits made without git-ai, so neither has a note
+fn repo_without_notes() -> (TestRepo, String, String) {
+    let repo = TestRepo::new();
+    std::fs::write(
+        repo.path().join("lib.rs"),
+        "fn a() {}\nfn b() {}\nfn c() {}\n",
+    )
+    .unwrap();
+    repo.git_og(&["add", "-A"]).unwrap();
+    repo.git_og(&["commit", "-m", "Add lib"]).unwrap();
+    let first = repo
+        .git_og(&["rev-parse", "HEAD"])
+        .unwrap()
+        .trim()
+        .to_string();
+
+    std::fs::write(repo.path().join("main.rs"), "fn main() {}\n").unwrap();
+    repo.git_og(&["add", "-A"]).unwrap();
+    repo.git_og(&["commit", "-m", "Add main"]).unwrap();
+    let second = repo
+        .git_og(&["rev-parse", "HEAD"])
+        .unwrap()
+        .trim()
+        .to_string();
+    (repo, first, second)
+}
+
+fn summary(repo: &TestRepo, commit: &str) -> CommitSummary {
+    let output = repo.git_ai(&["show", "--json", commit]).unwrap();
+    serde_json::from_str(&output).unwrap()
+}
+
+#[test]
+fn test_import_csv_writes_notes_for_ai_records() {
+    let (repo, first, second) = repo_without_notes();
+    let csv = repo.path().join("history.csv");
+    std::fs::write(
+        &csv,
+        format!(
+            "revision,path,lines,tool,model,author\n\
+             {first},lib.rs,1-3,cursor,gpt-4o,Jane\n\
+             {first},lib.rs,2,human,,Jane\n\
+             HEAD,main.rs,,human,,Jane\n",
+        ),
+    )
+    .unwrap();
+
+    repo.git_ai(&["import", csv.to_str().unwrap()])
+        .expect("import should succeed");
+
+    let first_summary = summary(&repo, &first);
+    assert!(first_summary.has_authorship);
+    assert_eq!(first_summary.files.len(), 1);
+    let ranges: Vec<(u32, u32)> = first_summary.files[0]
+        .ranges
+        .iter()
+        .map(|range| (range.start_line, range.end_line))
+        .collect();
+    // The later human record takes line 2 back
+    assert_eq!(ranges, vec![(1, 1), (3, 3)]);
+    assert_eq!(
+        first_summary.files[0].ranges[0].tool.as_deref(),
+        Some("cursor")
+    );
+
+    // Human-only commits get a note without AI lines
+    let second_summary = summary(&repo, &second);
+    assert!(second_summary.has_authorship);
+    assert!(second_summary.files.is_empty());
+
+    // Existing notes are kept unless --overwrite is given
+    let jsonl = repo.path().join("more.jsonl");
+    std::fs::write(
+        &jsonl,
+        format!(r#"{{"commit": "{first}", "file": "lib.rs", "line": "2", "tool": "claude"}}"#),
+    )
+    .unwrap();
+    let output = repo.git_ai(&["import", jsonl.to_str().unwrap()]).unwrap();
+    assert!(output.contains("skipped"), "output: {}", output);
+    assert_eq!(summary(&repo, &first).files[0].ranges.len(), 2);
+
+    repo.git_ai(&["import", jsonl.to_str().unwrap(), "--overwrite"])
+        .unwrap();
+    let ranges = &summary(&repo, &first).files[0].ranges;
+    assert_eq!(ranges.len(), 1);
+    assert_eq!((ranges[0].start_line, ranges[0].end_line), (2, 2));
+    assert_eq!(ranges[0].tool.as_deref(), Some("claude"));
+}
+
+#[test]
+fn test_import_validates_every_record_before_writing() {
+    let (repo, first, second) = repo_without_notes();
+    let csv = repo.path().join("history.csv");
+    std::fs::write(
+        &csv,
+        format!(
+            "revision,path,lines,tool\n\
+             {second},main.rs,1,cursor\n\
+             {first},lib.rs,2-9,cursor\n",
+        ),
+    )
+    .unwrap();
+
+    let error = repo
+        .git_ai(&["import", csv.to_str().unwrap()])
+        .expect_err("line 9 is past the end of lib.rs");
+    assert!(error.contains("record 2"), "error: {}", error);
+    assert!(!summary(&repo, &second).has_authorship);
+}
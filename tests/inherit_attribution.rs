@@ -0,0 +1,115 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+const ORIGINAL: &str = "fn compute_total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n\nlet total = compute_total(&prices);\n";
+const RENAMED: &str = "fn sum_prices(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n\nlet total = sum_prices(&prices);\nprintln!(\"{}\", total);\n";
+
+fn rename_with_agent(flags: &[&str]) -> TestRepo {
+    let repo = TestRepo::new();
+    let file_path = repo.path().join("lib.rs");
+    std::fs::write(&file_path, ORIGINAL).unwrap();
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    std::fs::write(&file_path, RENAMED).unwrap();
+    let mut args = vec!["checkpoint", "mock_ai"];
+    args.extend_from_slice(flags);
+    args.push("lib.rs");
+    repo.git_ai(&args).unwrap();
+    repo.stage_all_and_commit("Rename compute_total").unwrap();
+    repo
+}
+
+#[test]
+fn test_renamed_lines_become_ai_by_default() {
+    let repo = rename_with_agent(&[]);
+    let mut file = repo.filename("lib.rs");
+    file.assert_lines_and_blame(lines![
+        "fn sum_prices(items: &[u32]) -> u32 {".ai(),
+        "    items.iter().sum()".human(),
+        "}".human(),
+        "".human(),
+        "let total = sum_prices(&prices);".ai(),
+        "println!(\"{}\", total);".ai(),
+    ]);
+}
+
+#[test]
+fn test_inherit_attribution_keeps_renamed_lines_human() {
+    let repo = rename_with_agent(&["--inherit-attribution"]);
+    let mut file = repo.filename("lib.rs");
+    file.assert_lines_and_blame(lines![
+        "fn sum_prices(items: &[u32]) -> u32 {".human(),
+        "    items.iter().sum()".human(),
+        "}".human(),
+        "".human(),
+        "let total = sum_prices(&prices);".human(),
+        "println!(\"{}\", total);".ai(),
+    ]);
+
+    // A threshold above the rename's similarity leaves the lines with the agent
+    let repo = rename_with_agent(&["--inherit-attribution=95"]);
+    let mut file = repo.filename("lib.rs");
+    file.assert_lines_and_blame(lines![
+        "fn sum_prices(items: &[u32]) -> u32 {".ai(),
+        "    items.iter().sum()".human(),
+        "}".human(),
+        "".human(),
+        "let total = sum_prices(&prices);".ai(),
+        "println!(\"{}\", total);".ai(),
+    ]);
+}
+
+const UNFORMATTED: &str = "def f():\n  x = load()\n  return x\n";
+const FORMATTED: &str = "def f():\n    x = load()\n\n    return x\n\n\ndef g():\n    return 3\n";
+
+/// An agent reformats a human function and adds one of its own
+fn reformat_with_agent(preserve: bool) -> TestRepo {
+    let mut repo = TestRepo::new();
+    if preserve {
+        repo.patch_git_ai_config(|patch| {
+            patch.preserve_whitespace_attribution = Some(true);
+        });
+    }
+    let file_path = repo.path().join("app.py");
+    std::fs::write(&file_path, UNFORMATTED).unwrap();
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    std::fs::write(&file_path, FORMATTED).unwrap();
+    repo.git_ai(&["checkpoint", "mock_ai", "app.py"]).unwrap();
+    repo.stage_all_and_commit("Format and add h").unwrap();
+    repo
+}
+
+#[test]
+fn test_reformatted_lines_become_ai_by_default() {
+    let repo = reformat_with_agent(false);
+    let mut file = repo.filename("app.py");
+    file.assert_lines_and_blame(lines![
+        "def f():".human(),
+        "    x = load()".human(),
+        "".ai(),
+        "    return x".human(),
+        "".ai(),
+        "".ai(),
+        "def g():".ai(),
+        "    return 3".ai(),
+    ]);
+}
+
+#[test]
+fn test_preserve_whitespace_attribution_keeps_reformatted_lines_human() {
+    let repo = reformat_with_agent(true);
+    let mut file = repo.filename("app.py");
+    file.assert_lines_and_blame(lines![
+        "def f():".human(),
+        "    x = load()".human(),
+        "".human(),
+        "    return x".human(),
+        "".ai(),
+        "".ai(),
+        "def g():".ai(),
+        "    return 3".ai(),
+    ]);
+}
@@ -0,0 +1,145 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::{TestRepo, get_binary_path};
+use std::process::Command;
+
+#[test]
+fn init_writes_starter_config_and_publishes_notes_to_remote() {
+    let (local, upstream) = TestRepo::new_with_remote();
+    let home = tempfile::tempdir().unwrap();
+    let env = [("HOME", home.path().to_str().unwrap())];
+    let config_path = local.path().join(".git-ai.toml");
+
+    let mut file = local.filename("lib.rs");
+    file.set_contents(lines!["fn ai() {}".ai()]);
+    local.stage_all_and_commit("Add lib").unwrap();
+
+    let output = local
+        .git_ai_with_env(&["init", "--skip-hooks", "--remote", "origin"], &env)
+        .expect("init should succeed");
+    assert!(output.contains("wrote starter config"));
+    assert!(output.contains("refs/notes/ai is in sync with origin"));
+    assert!(output.contains("added notes refspecs to origin"));
+    assert!(output.contains("Next steps:"));
+    // Push refspecs would change what a plain `git push` sends, so they're left alone
+    assert!(
+        local
+            .git_og(&["config", "--get-all", "remote.origin.push"])
+            .is_err()
+    );
+    assert!(
+        local
+            .git_og(&["config", "--get-all", "remote.origin.fetch"])
+            .unwrap()
+            .contains("+refs/notes/ai:refs/notes/ai-remote/origin")
+    );
+
+    let starter = std::fs::read_to_string(&config_path).unwrap();
+    assert!(starter.contains("# [policy]"), "{}", starter);
+    assert!(!home.path().join(".git-ai/config.json").exists());
+    assert!(
+        upstream
+            .git_og(&["rev-parse", "--verify", "refs/notes/ai"])
+            .is_ok()
+    );
+
+    // Re-running never clobbers the repository's config
+    std::fs::write(&config_path, "ignore = [\"*.lock\"]\n").unwrap();
+    let output = local
+        .git_ai_with_env(&["init", "--skip-hooks"], &env)
+        .unwrap();
+    assert!(output.contains("existing .git-ai.toml kept"));
+    assert!(output.contains("notes refspecs already configured"));
+    assert_eq!(
+        std::fs::read_to_string(&config_path).unwrap(),
+        "ignore = [\"*.lock\"]\n"
+    );
+
+    // A dry run only reports the file it would write
+    std::fs::remove_file(&config_path).unwrap();
+    let output = local
+        .git_ai_with_env(&["init", "--skip-hooks", "--dry-run"], &env)
+        .unwrap();
+    assert!(output.contains("would write"), "{}", output);
+    assert!(!config_path.exists());
+
+    let err = local
+        .git_ai_with_env(&["init", "--skip-hooks", "--remote", "nope"], &env)
+        .expect_err("unknown remote should fail");
+    assert!(err.contains("no remote named 'nope'"));
+}
+
+#[test]
+fn init_org_installs_ci_templates_and_reports_each_repo() {
+    let github_repo = TestRepo::new();
+    let gitlab_repo = TestRepo::new();
+    github_repo
+        .git_og(&["remote", "add", "origin", "https://github.com/acme/app.git"])
+        .unwrap();
+    gitlab_repo
+        .git_og(&["remote", "add", "origin", "git@gitlab.com:acme/svc.git"])
+        .unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let env = [("HOME", home.path().to_str().unwrap())];
+
+    let list = home.path().join("repos.txt");
+    std::fs::write(
+        &list,
+        format!(
+            "# platform repos\n{}\n\n{}\n",
+            github_repo.path().display(),
+            gitlab_repo.path().display()
+        ),
+    )
+    .unwrap();
+
+    let output = github_repo
+        .git_ai_with_env(
+            &["init", "--org", "--repos", list.to_str().unwrap(), "--json"],
+            &env,
+        )
+        .expect("org init should succeed");
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["succeeded"], 2);
+    assert_eq!(report["failed"], 0);
+    assert!(
+        github_repo
+            .path()
+            .join(".github/workflows/git-ai.yaml")
+            .exists()
+    );
+    assert!(
+        std::fs::read_to_string(gitlab_repo.path().join(".gitlab-ci.yml"))
+            .unwrap()
+            .contains(".gitlab/ci/git-ai.gitlab-ci.yml")
+    );
+    for repo in [&github_repo, &gitlab_repo] {
+        assert!(repo.path().join(".git-ai.toml").exists());
+    }
+    assert!(
+        report["repos"][0]["actions"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("wrote .git-ai.toml"))
+    );
+
+    // A bad entry is reported without stopping the rest
+    let missing = home.path().join("missing");
+    std::fs::write(
+        &list,
+        format!("{}\n{}\n", missing.display(), github_repo.path().display()),
+    )
+    .unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["init", "--org", "--repos", list.to_str().unwrap()])
+        .current_dir(github_repo.path())
+        .env("HOME", home.path())
+        .env("GIT_AI_TEST_DB_PATH", github_repo.test_db_path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("failed  {}", missing.display())));
+    assert!(stdout.contains("1 of 2 repositories set up, 1 failed"));
+}
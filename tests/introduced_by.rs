@@ -0,0 +1,61 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn introduced_by_json(repo: &TestRepo, location: &str) -> serde_json::Value {
+    let output = repo
+        .git_ai(&["introduced-by", location, "--json"])
+        .expect("introduced-by should succeed");
+    // Debug builds log to stderr, which is captured too
+    let json = output
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .expect("introduced-by should print JSON");
+    serde_json::from_str(json).expect("introduced-by should print JSON")
+}
+
+#[test]
+fn test_introduced_by_reports_commit_and_attribution() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("app.py");
+    file.set_contents(lines!["def main():".human(), "    pass".human()]);
+    let first = repo.stage_all_and_commit("Add app").unwrap();
+
+    file.set_contents(lines![
+        "def main():".human(),
+        "    pass".human(),
+        "def helper():".ai(),
+        "    return 1".ai(),
+    ]);
+    let second = repo.stage_all_and_commit("Add helper").unwrap();
+
+    let human = introduced_by_json(&repo, "app.py:1");
+    assert_eq!(human["commit"], first.commit_sha);
+    assert_eq!(human["attribution"], "human");
+    assert!(human.get("agent").is_none());
+
+    let ai = introduced_by_json(&repo, "app.py:4");
+    assert_eq!(ai["commit"], second.commit_sha);
+    assert_eq!(ai["attribution"], "ai");
+    assert_eq!(ai["agent"], "mock_ai");
+    assert!(ai["session"].is_string());
+
+    // Stack trace frames are accepted as locations
+    let from_trace = introduced_by_json(&repo, r#"  File "app.py", line 3, in helper"#);
+    assert_eq!(from_trace["commit"], second.commit_sha);
+    assert_eq!(from_trace["line"], 3);
+}
+
+#[test]
+fn test_introduced_by_unknown_without_note() {
+    let repo = TestRepo::new();
+
+    std::fs::write(repo.path().join("script.sh"), "echo hi\n").unwrap();
+    repo.git_og(&["add", "-A"]).unwrap();
+    repo.git_og(&["commit", "-m", "Add script"]).unwrap();
+
+    let result = introduced_by_json(&repo, "script.sh:1");
+    assert_eq!(result["attribution"], "unknown");
+    assert_eq!(result["summary"], "Add script");
+}
@@ -0,0 +1,89 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn lineage_json(repo: &TestRepo, hunk: &str) -> serde_json::Value {
+    let output = repo
+        .git_ai(&["lineage", hunk, "--json"])
+        .expect("lineage should succeed");
+    serde_json::from_str(output.trim()).expect("lineage should print JSON")
+}
+
+#[test]
+fn test_lineage_reports_ai_lines_then_human_edit() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("app.py");
+    file.set_contents(lines!["def main():".human(), "    pass".human(), ""]);
+    repo.stage_all_and_commit("Add app").unwrap();
+
+    file.set_contents(lines![
+        "def main():".human(),
+        "    pass".human(),
+        "def helper():".ai(),
+        "    return 1".ai(),
+        "",
+    ]);
+    let ai_commit = repo.stage_all_and_commit("Add helper").unwrap();
+
+    file.set_contents(lines![
+        "def main():".human(),
+        "    pass".human(),
+        "def helper():".human(),
+        "    return 2".human(),
+        "",
+    ]);
+    let human_commit = repo.stage_all_and_commit("Tweak helper").unwrap();
+
+    let lineage = lineage_json(&repo, "app.py:3-4");
+    let events = lineage["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2, "{}", lineage);
+
+    assert_eq!(events[0]["kind"], "ai_edit");
+    assert_eq!(events[0]["commit"], ai_commit.commit_sha);
+    assert_eq!(events[0]["ai_lines"], 2);
+    assert_eq!(events[0]["agents"][0]["tool"], "mock_ai");
+
+    assert_eq!(events[1]["kind"], "human_edit");
+    assert_eq!(events[1]["commit"], human_commit.commit_sha);
+    assert_eq!(events[1]["lines"], serde_json::json!([4]));
+    assert_eq!(events[1]["human_lines"], 1);
+}
+
+#[test]
+fn test_lineage_follows_squash_merge_to_branch_checkpoint() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("main.txt");
+    file.set_contents(lines!["line 1", "line 2", ""]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+    let default_branch = repo.current_branch();
+
+    repo.git(&["checkout", "-b", "feature"]).unwrap();
+    file.insert_at(2, lines!["// AI added feature".ai()]);
+    let feature_commit = repo.stage_all_and_commit("Add AI feature").unwrap();
+
+    repo.git(&["checkout", &default_branch]).unwrap();
+    repo.git(&["merge", "--squash", "feature"]).unwrap();
+    let squash_commit = repo.commit("Squashed feature").unwrap();
+
+    let lineage = lineage_json(&repo, "main.txt:3");
+    let events = lineage["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2, "{}", lineage);
+
+    assert_eq!(events[0]["kind"], "ai_checkpoint");
+    assert_eq!(events[0]["commit"], feature_commit.commit_sha);
+    assert_eq!(events[0]["lines"], serde_json::json!([3]));
+
+    assert_eq!(events[1]["kind"], "squash");
+    assert_eq!(events[1]["commit"], squash_commit.commit_sha);
+    assert_eq!(
+        events[1]["rewritten_from"],
+        serde_json::json!([feature_commit.commit_sha])
+    );
+    assert_eq!(events[1]["ai_lines"], 1);
+
+    let text = repo.git_ai(&["lineage", "main.txt:3"]).unwrap();
+    assert!(text.contains("ai checkpoint"), "{}", text);
+    assert!(text.contains("squash"), "{}", text);
+}
@@ -0,0 +1,63 @@
+#[macro_use]
+mod repos;
+use git_ai::git::repository as GitAiRepository;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+/// Squash merge rewrite in low-memory mode, touching more files than fit in one batch.
+/// Kept in its own test binary: the memory limit is process-wide.
+#[test]
+fn test_ci_squash_merge_low_memory_batches_files() {
+    git_ai::memory::set_max_memory(256 << 20);
+    assert!(git_ai::memory::is_low_memory());
+
+    let file_count = git_ai::memory::LOW_MEMORY_BATCH_FILES + 8;
+    let repo = TestRepo::new();
+    let write_files = |contents: &dyn Fn(usize) -> String| {
+        for i in 0..file_count {
+            std::fs::write(repo.path().join(format!("file{}.js", i)), contents(i)).unwrap();
+        }
+    };
+
+    write_files(&|i| format!("// File {} original\n", i));
+    repo.stage_all_and_commit("Initial commit").unwrap();
+    repo.git(&["branch", "-M", "main"]).unwrap();
+
+    repo.git(&["checkout", "-b", "feature"]).unwrap();
+    write_files(&|i| format!("// File {} original\nconst feature{} = 'ai';\n", i, i));
+    repo.git_ai(&["checkpoint", "mock_ai"]).unwrap();
+    let feature_sha = repo
+        .stage_all_and_commit("Add AI features")
+        .unwrap()
+        .commit_sha;
+
+    // Simulate CI squash merge
+    repo.git(&["checkout", "main"]).unwrap();
+    write_files(&|i| format!("// File {} original\nconst feature{} = 'ai';\n", i, i));
+    let merge_sha = repo
+        .stage_all_and_commit("Merge feature via squash")
+        .unwrap()
+        .commit_sha;
+
+    let git_ai_repo = GitAiRepository::find_repository_in_path(repo.path().to_str().unwrap())
+        .expect("Failed to find repository");
+    use git_ai::authorship::rebase_authorship::rewrite_authorship_after_squash_or_rebase;
+    rewrite_authorship_after_squash_or_rebase(
+        &git_ai_repo,
+        "feature",
+        "main",
+        &feature_sha,
+        &merge_sha,
+        false,
+    )
+    .unwrap();
+
+    // Files from every batch keep their AI attribution
+    for i in 0..file_count {
+        let mut file = repo.filename(&format!("file{}.js", i));
+        file.assert_lines_and_blame(lines![
+            format!("// File {} original", i).human(),
+            format!("const feature{} = 'ai';", i).ai()
+        ]);
+    }
+}
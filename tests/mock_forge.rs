@@ -0,0 +1,2340 @@
+#[macro_use]
+mod repos;
+use git_ai::ci::mock_forge::{
+    MockForge, MockGerritChange, MockMergeRequest, MockMergeTrainCar, MockPipeline, MockPullRequest,
+};
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::{TestRepo, get_binary_path};
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// An upstream with a squash-merged MR: `feature` adds AI lines, and `main` has a squash
+/// commit whose note was never pushed. Returns (mirror, upstream, feature_sha, merge_sha).
+fn squash_merged_upstream() -> (TestRepo, TestRepo, String, String) {
+    let (local, upstream) = TestRepo::new_with_remote();
+    let mut file = local.filename("feature.js");
+
+    file.set_contents(lines!["// Original code", "function original() {}"]);
+    local.stage_all_and_commit("Initial commit").unwrap();
+    local.git(&["branch", "-M", "main"]).unwrap();
+    local.git(&["push", "-u", "origin", "main"]).unwrap();
+
+    local.git(&["checkout", "-b", "feature"]).unwrap();
+    file.insert_at(
+        2,
+        lines![
+            "function aiFeature() {".ai(),
+            "  return 'ai code';".ai(),
+            "}".ai()
+        ],
+    );
+    let feature_sha = local
+        .stage_all_and_commit("Add AI feature")
+        .unwrap()
+        .commit_sha;
+    local.git(&["push", "origin", "feature"]).unwrap();
+
+    local.git(&["checkout", "main"]).unwrap();
+    file.set_contents(lines![
+        "// Original code",
+        "function original() {}",
+        "function aiFeature() {",
+        "  return 'ai code';",
+        "}"
+    ]);
+    let merge_sha = local
+        .stage_all_and_commit("Squash feature")
+        .unwrap()
+        .commit_sha;
+    // Plain git push, so the squash commit's note stays local like a forge-side merge
+    local.git_og(&["push", "origin", "main"]).unwrap();
+
+    upstream
+        .git_og(&["update-ref", "refs/merge-requests/1/head", &feature_sha])
+        .unwrap();
+    (local, upstream, feature_sha, merge_sha)
+}
+
+fn run_ci_gitlab(forge: &MockForge, commit_sha: &str, job_token: &str) -> Output {
+    run_ci_gitlab_with_env(forge, commit_sha, job_token, &[])
+}
+
+fn run_ci_gitlab_with_env(
+    forge: &MockForge,
+    commit_sha: &str,
+    job_token: &str,
+    envs: &[(&str, &str)],
+) -> Output {
+    run_in_gitlab_ci(forge, &["ci", "gitlab", "run"], commit_sha, job_token, envs)
+}
+
+/// Run `git-ai <args>` in a GitLab CI job for project 42 (`group/project`)
+fn run_in_gitlab_ci(
+    forge: &MockForge,
+    args: &[&str],
+    commit_sha: &str,
+    job_token: &str,
+    envs: &[(&str, &str)],
+) -> Output {
+    let workdir = tempfile::tempdir().unwrap();
+    run_in_gitlab_ci_at(workdir.path(), forge, args, commit_sha, job_token, envs)
+}
+
+/// [`run_in_gitlab_ci`] in `workdir`, such as the job's checkout
+fn run_in_gitlab_ci_at(
+    workdir: &Path,
+    forge: &MockForge,
+    args: &[&str],
+    commit_sha: &str,
+    job_token: &str,
+    envs: &[(&str, &str)],
+) -> Output {
+    Command::new(get_binary_path())
+        .args(args)
+        .current_dir(workdir)
+        .envs(forge.gitlab_ci_env("42", "group/project", commit_sha, job_token))
+        .env_remove("GITLAB_TOKEN")
+        .env_remove("GITLAB_GROUP_TOKEN")
+        .env_remove("GITLAB_DEPLOY_USER")
+        .env_remove("GITLAB_DEPLOY_TOKEN")
+        .env_remove("CI_DEPLOY_USER")
+        .env_remove("CI_DEPLOY_PASSWORD")
+        .env_remove("CI_PIPELINE_SOURCE")
+        .env_remove("CI_MERGE_REQUEST_IID")
+        .env_remove("CI_MERGE_REQUEST_REF_PATH")
+        .env_remove("CI_MERGE_REQUEST_EVENT_TYPE")
+        .env_remove("CI_PIPELINE_URL")
+        .env_remove("CI_PIPELINE_CREATED_AT")
+        .env_remove("GIT_AI_CI_MERGE_WINDOW")
+        .env_remove("GIT_AI_CI_SIGN")
+        .env_remove("GIT_AI_CI_COMMENT")
+        .env_remove("GIT_AI_NO_NETWORK")
+        .env_remove("GIT_AI_HTTP_MAX_ATTEMPTS")
+        .env_remove("GIT_AI_HTTP_BACKEND")
+        .env_remove("GIT_AI_HTTP_TIMEOUT")
+        .env_remove("GIT_AI_CI_DEADLINE")
+        .env_remove("GIT_AI_CI_CLONE")
+        .env_remove("GIT_AI_CI_CLONE_DEPTH")
+        .env_remove("HTTPS_PROXY")
+        .env_remove("https_proxy")
+        .env_remove("HTTP_PROXY")
+        .env_remove("http_proxy")
+        .env_remove("ALL_PROXY")
+        .env_remove("all_proxy")
+        .env_remove("NO_PROXY")
+        .env_remove("no_proxy")
+        .env_remove("GIT_AI_CA_BUNDLE")
+        .env_remove("GIT_AI_TLS_INSECURE")
+        .env_remove("GIT_SSL_CAINFO")
+        .env_remove("GIT_SSL_NO_VERIFY")
+        // Provenance detection checks these first; the test runner may be an Actions job
+        .env_remove("GITHUB_ACTIONS")
+        .env_remove("GITEA_ACTIONS")
+        .envs(envs.iter().copied())
+        .env("GIT_AI_TEST_DB_PATH", workdir.join("db"))
+        // Writing notes in the CI clone needs an identity, which runners may not have
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_ci_gitlab_run_against_mock_forge() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    let output = run_ci_gitlab(&forge, &merge_sha, "job-token");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+
+    // The rewritten note was pushed back to the forge
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+
+    let api_request = forge
+        .requests()
+        .into_iter()
+        .find(|request| {
+            request
+                .path
+                .starts_with("/api/v4/projects/42/merge_requests?")
+        })
+        .expect("merge requests were queried");
+    assert!(api_request.path.contains("state=merged"));
+    assert_eq!(
+        api_request.headers.get("job-token").map(String::as_str),
+        Some("job-token")
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_with_no_network_fails_before_reaching_forge() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    let output = run_in_gitlab_ci(
+        &forge,
+        &["--no-network", "ci", "gitlab", "run"],
+        &merge_sha,
+        "job-token",
+        &[],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Network access is disabled"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(forge.requests().is_empty(), "{:?}", forge.requests());
+
+    // The environment variable works the same way
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_NO_NETWORK", "1")],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Network access is disabled"));
+    assert!(forge.requests().is_empty());
+}
+
+#[test]
+fn test_ci_gitlab_run_with_partial_clone_deepens_to_merge_base() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    // One commit of each side leaves out the commit the feature branched from
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[
+            ("GIT_AI_CI_CLONE", "partial"),
+            ("GIT_AI_CI_CLONE_DEPTH", "1"),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Cloning the last 1 commits without file contents"));
+    assert!(stdout.contains("Deepening the clone by 1 commits"));
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+}
+
+#[test]
+fn test_ci_gitlab_run_in_place_reuses_shallow_job_checkout() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    // A runner's default checkout: shallow, detached at the pipeline's commit
+    let job_dir = tempfile::tempdir().unwrap();
+    let checkout = job_dir.path().join("project");
+    let clone_url = format!("{}/group/project.git", forge.url());
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--depth=1", "--branch=main", &clone_url])
+        .arg(&checkout)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = Command::new("git")
+        .args(["checkout", "--quiet", "--detach", &merge_sha])
+        .current_dir(&checkout)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = run_in_gitlab_ci_at(
+        &checkout,
+        &forge,
+        &["ci", "gitlab", "run", "--in-place"],
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_CI_CLONE_DEPTH", "1")],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Using the job's shallow checkout instead of cloning"));
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    assert!(!checkout.join("git-ai-ci-clone").exists());
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+}
+
+#[test]
+fn test_ci_gitlab_run_batch_processes_merges_since_last_successful_pipeline() {
+    let (local, upstream, first_head, first_merge) = squash_merged_upstream();
+    let base_sha = upstream
+        .git_og(&["rev-parse", &format!("{}^", first_merge)])
+        .unwrap()
+        .trim()
+        .to_string();
+
+    // A second MR squash-merged right after the first, whose pipeline is the one running
+    local.git(&["checkout", "-b", "second"]).unwrap();
+    let mut file = local.filename("second.js");
+    file.set_contents(lines!["function second() {".ai(), "}".ai()]);
+    let second_head = local
+        .stage_all_and_commit("Add second feature")
+        .unwrap()
+        .commit_sha;
+    local.git(&["push", "origin", "second"]).unwrap();
+    // That push synced the local note of the first squash too; its pipeline never ran
+    upstream
+        .git_og(&[
+            "-c",
+            "user.name=Test",
+            "-c",
+            "user.email=test@example.com",
+            "notes",
+            "--ref=ai",
+            "remove",
+            &first_merge,
+        ])
+        .unwrap();
+    local.git(&["checkout", "main"]).unwrap();
+    file.set_contents(lines!["function second() {", "}"]);
+    let second_merge = local
+        .stage_all_and_commit("Squash second")
+        .unwrap()
+        .commit_sha;
+    local.git_og(&["push", "origin", "main"]).unwrap();
+    upstream
+        .git_og(&["update-ref", "refs/merge-requests/2/head", &second_head])
+        .unwrap();
+
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &first_head, &first_merge),
+    );
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(2, "second", "main", &second_head, &second_merge),
+    );
+    forge.add_pipeline(
+        "42",
+        MockPipeline {
+            id: 7,
+            ref_name: "main".to_string(),
+            sha: base_sha.clone(),
+            status: "success".to_string(),
+        },
+    );
+
+    let output = run_in_gitlab_ci(
+        &forge,
+        &["ci", "gitlab", "run", "--batch"],
+        &second_merge,
+        "job-token",
+        &[],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains(&format!(
+        "Looking for unprocessed merges on main since {}",
+        base_sha
+    )));
+    assert!(
+        stdout.contains("GitLab CI earlier merges: 1 commit(s) on main"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("request #1: authorship rewritten"),
+        "stdout: {}",
+        stdout
+    );
+    for (merge_sha, file) in [(&first_merge, "feature.js"), (&second_merge, "second.js")] {
+        let note = upstream
+            .git_og(&["notes", "--ref=ai", "show", merge_sha])
+            .unwrap();
+        assert!(note.contains(file), "note for {}: {}", merge_sha, note);
+    }
+}
+
+#[test]
+fn test_ci_gitlab_run_retries_rate_limits_and_server_errors() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    forge.fail_api_requests(1, 503, None);
+    forge.fail_api_requests(1, 429, Some("0"));
+
+    let output = run_ci_gitlab(&forge, &merge_sha, "job-token");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        stderr
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    assert!(stderr.contains("GitLab API request failed (503); retrying in 1s (attempt 2 of 4)"));
+    assert!(stderr.contains("GitLab API request failed (429); retrying in 0s (attempt 3 of 4)"));
+}
+
+#[test]
+fn test_ci_gitlab_run_through_pooled_http_backend() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    forge.fail_api_requests(1, 503, Some("0"));
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_HTTP_BACKEND", "ureq")],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_HTTP_BACKEND", "curl")],
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Unknown GIT_AI_HTTP_BACKEND 'curl'; expected minreq, ureq or reqwest")
+    );
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_HTTP_BACKEND", "reqwest")],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if cfg!(feature = "reqwest") {
+        assert!(output.status.success(), "stderr: {}", stderr);
+    } else {
+        assert!(!output.status.success());
+        assert!(stderr.contains("needs git-ai built with the reqwest feature"));
+    }
+}
+
+#[test]
+fn test_ci_gitlab_run_gives_up_after_max_attempts() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    forge.fail_api_requests(3, 502, Some("0"));
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_HTTP_MAX_ATTEMPTS", "2")],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("GitLab API request failed after 2 attempt(s): status 502"),
+        "stderr: {}",
+        stderr
+    );
+    let api_requests = forge
+        .requests()
+        .iter()
+        .filter(|request| request.path.starts_with("/api/"))
+        .count();
+    assert_eq!(api_requests, 2);
+}
+
+#[test]
+fn test_ci_gitlab_run_stops_at_deadline_instead_of_waiting_to_retry() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    forge.fail_api_requests(1, 503, Some("30"));
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_CI_DEADLINE", "10s")],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("CI deadline of 10s exceeded; stopped before retrying GitLab API request"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!stderr.contains("retrying in 30s"));
+}
+
+#[test]
+fn test_ci_gitlab_run_sends_api_requests_through_proxy() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    // Nothing listens on port 9, so requests sent through the proxy fail to connect
+    let proxy = [
+        ("HTTP_PROXY", "http://127.0.0.1:9"),
+        ("GIT_AI_HTTP_MAX_ATTEMPTS", "1"),
+    ];
+
+    let output = run_ci_gitlab_with_env(&forge, &merge_sha, "job-token", &proxy);
+    assert!(!output.status.success());
+    assert!(
+        forge
+            .requests()
+            .iter()
+            .all(|request| !request.path.starts_with("/api/"))
+    );
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[proxy[0], proxy[1], ("NO_PROXY", "localhost,127.0.0.1")],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+}
+
+#[test]
+fn test_ci_gitlab_run_against_forge_behind_internal_ca() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+    let forge =
+        MockForge::start_tls(&fixtures.join("server.pem"), &fixtures.join("server.key")).unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    // The internal CA isn't trusted by default, so nothing gets past the handshake
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_HTTP_MAX_ATTEMPTS", "1")],
+    );
+    assert!(!output.status.success());
+    assert!(forge.requests().is_empty(), "{:?}", forge.requests());
+
+    let ca_bundle = fixtures.join("ca.pem");
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_CA_BUNDLE", ca_bundle.to_str().unwrap())],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    // Both the API and git's clone and notes push went over HTTPS
+    let requests = forge.requests();
+    assert!(requests.iter().any(|r| r.path.starts_with("/api/v4/")));
+    assert!(
+        requests
+            .iter()
+            .any(|r| r.path.ends_with("/git-receive-pack"))
+    );
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GIT_AI_TLS_INSECURE", "1")],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert!(
+        stderr.contains("TLS certificate verification is disabled"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_looks_up_merge_request_named_by_pipeline() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    // Another MR merged the same content; only the named one should be used
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(2, "other", "main", &feature_sha, &merge_sha),
+    );
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("CI_MERGE_REQUEST_IID", "1")],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Merge request !1 (from CI_MERGE_REQUEST_IID)"));
+    assert!(stdout.contains("head_ref=feature"), "stdout: {}", stdout);
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+
+    let paths: Vec<String> = forge.requests().into_iter().map(|r| r.path).collect();
+    assert!(paths.contains(&"/api/v4/projects/42/merge_requests/1".to_string()));
+    assert!(
+        !paths
+            .iter()
+            .any(|path| path.starts_with("/api/v4/projects/42/merge_requests?")),
+        "no time-window search: {:?}",
+        paths
+    );
+
+    // A pipeline for an MR that isn't merged yet has nothing to rewrite
+    let mut open = MockMergeRequest::merged(3, "wip", "main", &feature_sha, &merge_sha);
+    open.state = "opened".to_string();
+    open.merge_commit_sha = None;
+    open.squash_commit_sha = None;
+    forge.add_merge_request("42", open);
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("CI_MERGE_REQUEST_REF_PATH", "refs/merge-requests/3/head")],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("MR !3 is opened, not merged"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_resolves_merge_train_commit() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    // The train pipeline built its own ref, which matches none of the MR's SHAs
+    forge.add_merge_train_car(
+        "42",
+        MockMergeTrainCar {
+            merge_request_iid: 1,
+            status: "merged".to_string(),
+            pipeline_sha: feature_sha.clone(),
+        },
+    );
+
+    let train_env = [
+        ("CI_MERGE_REQUEST_EVENT_TYPE", "merge_train"),
+        ("CI_MERGE_REQUEST_REF_PATH", "refs/merge-requests/1/train"),
+    ];
+    let output = run_ci_gitlab_with_env(&forge, &feature_sha, "job-token", &train_env);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains(&format!("Merge train merged MR !1 as {}", merge_sha)),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+    let paths: Vec<String> = forge.requests().into_iter().map(|r| r.path).collect();
+    assert!(paths.contains(&"/api/v4/projects/42/merge_trains/merge_requests/1".to_string()));
+
+    // A car still on the train hasn't landed anything to rewrite
+    let mut riding = MockMergeRequest::merged(2, "next", "main", &feature_sha, &merge_sha);
+    riding.state = "opened".to_string();
+    riding.merge_commit_sha = None;
+    forge.add_merge_request("42", riding);
+    forge.add_merge_train_car(
+        "42",
+        MockMergeTrainCar {
+            merge_request_iid: 2,
+            status: "fresh".to_string(),
+            pipeline_sha: feature_sha.clone(),
+        },
+    );
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &feature_sha,
+        "job-token",
+        &[
+            ("CI_MERGE_REQUEST_EVENT_TYPE", "merge_train"),
+            ("CI_MERGE_REQUEST_IID", "2"),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("MR !2 is fresh on its merge train, not merged"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_signs_notes_and_records_pipeline() {
+    let (local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    let keys = tempfile::tempdir().unwrap();
+    let key = keys.path().join("ci_key");
+    let keygen = Command::new("ssh-keygen")
+        .args([
+            "-q",
+            "-t",
+            "ed25519",
+            "-N",
+            "",
+            "-C",
+            "ci@example.com",
+            "-f",
+        ])
+        .arg(&key)
+        .output();
+    if !keygen.is_ok_and(|output| output.status.success()) {
+        eprintln!("ssh-keygen unavailable; skipping");
+        return;
+    }
+    let sign = format!("ssh:{}", key.display());
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[
+            (
+                "CI_PIPELINE_URL",
+                "https://gitlab.example.com/group/project/-/pipelines/7",
+            ),
+            ("GIT_AI_CI_SIGN", &sign),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Signing notes commit with ssh"),
+        "stdout: {}",
+        stdout
+    );
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains(r#""ci": "gitlab-ci""#), "note: {}", note);
+    assert!(
+        note.contains(
+            r#""pipeline_url": "https://gitlab.example.com/group/project/-/pipelines/7""#
+        ),
+        "note: {}",
+        note
+    );
+    assert!(note.contains(r#""signed_with": "ssh""#), "note: {}", note);
+
+    let public_key = std::fs::read_to_string(key.with_extension("pub")).unwrap();
+    let allowed_signers = keys.path().join("allowed_signers");
+    std::fs::write(
+        &allowed_signers,
+        format!("ci@example.com {}", public_key.trim()),
+    )
+    .unwrap();
+    let verify = Command::new("git")
+        .arg("-C")
+        .arg(upstream.path())
+        .arg("-c")
+        .arg(format!(
+            "gpg.ssh.allowedSignersFile={}",
+            allowed_signers.display()
+        ))
+        .args(["verify-commit", "refs/notes/ai"])
+        .output()
+        .unwrap();
+    assert!(
+        verify.status.success(),
+        "notes tip should carry a valid signature: {}",
+        String::from_utf8_lossy(&verify.stderr)
+    );
+
+    // The CI-signed note verifies; the feature commit's developer-written note doesn't
+    local
+        .git_og(&["fetch", "origin", "+refs/notes/ai:refs/notes/ai"])
+        .unwrap();
+    local
+        .git_og(&[
+            "config",
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().unwrap(),
+        ])
+        .unwrap();
+    let verified = local
+        .git_ai(&["verify", "--provenance", &merge_sha])
+        .unwrap();
+    assert!(
+        verified.contains("signed by trusted automation"),
+        "{}",
+        verified
+    );
+    let rejected = Command::new(get_binary_path())
+        .args(["verify", "--provenance", "--json", &feature_sha])
+        .current_dir(local.path())
+        .env("GIT_AI_TEST_DB_PATH", local.test_db_path())
+        .output()
+        .unwrap();
+    let rejected_stdout = String::from_utf8_lossy(&rejected.stdout);
+    assert!(!rejected.status.success(), "{}", rejected_stdout);
+    assert!(
+        rejected_stdout.contains(r#""status":"no_provenance""#),
+        "{}",
+        rejected_stdout
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_pages_through_recently_merged_mrs() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    // More MRs merged since than fit on the first page
+    for iid in 100..220 {
+        forge.add_merge_request(
+            "42",
+            MockMergeRequest::merged(
+                iid,
+                &format!("other-{}", iid),
+                "main",
+                &"a".repeat(40),
+                &"b".repeat(40),
+            ),
+        );
+    }
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    let output = run_ci_gitlab(&forge, &merge_sha, "job-token");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("recently merged MRs on page 2"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    assert!(forge.requests().iter().any(|request| {
+        request
+            .path
+            .starts_with("/api/v4/projects/42/merge_requests?")
+            && request.path.ends_with("&page=2")
+    }));
+}
+
+#[test]
+fn test_ci_gitlab_run_with_group_and_deploy_tokens() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    // A deploy token only covers git, so the API falls through to the group token
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[
+            ("GITLAB_GROUP_TOKEN", "group-token"),
+            ("CI_DEPLOY_USER", "gitlab+deploy-token-7"),
+            ("CI_DEPLOY_PASSWORD", "deploy-token"),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Using GITLAB_GROUP_TOKEN for git operations"));
+
+    let api_request = forge
+        .requests()
+        .into_iter()
+        .find(|request| request.path.starts_with("/api/v4/"))
+        .expect("the API was queried");
+    assert_eq!(
+        api_request.headers.get("private-token").map(String::as_str),
+        Some("group-token")
+    );
+    assert!(!api_request.headers.contains_key("job-token"));
+
+    // Without the group token, git uses the deploy token and the API the job token
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[
+            ("CI_DEPLOY_USER", "gitlab+deploy-token-7"),
+            ("CI_DEPLOY_PASSWORD", "deploy-token"),
+        ],
+    );
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains("Using CI_DEPLOY_PASSWORD for git operations")
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_comments_attribution_summary_on_merge_request() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    forge.set_token_scopes(&["api", "write_repository"]);
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GITLAB_TOKEN", "api-token"), ("GIT_AI_CI_COMMENT", "1")],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Commented the attribution summary on MR !1"));
+
+    let comment = forge
+        .requests()
+        .into_iter()
+        .find(|request| {
+            request.method == "POST" && request.path == "/api/v4/projects/42/merge_requests/1/notes"
+        })
+        .expect("the MR was commented on");
+    assert_eq!(
+        comment.headers.get("private-token").map(String::as_str),
+        Some("api-token")
+    );
+    let body: serde_json::Value = serde_json::from_str(&comment.body).unwrap();
+    let body = body["body"].as_str().unwrap();
+    assert!(
+        body.contains("**75%** of the 4 lines added were written by AI (3 AI, 1 human)"),
+        "comment: {}",
+        body
+    );
+    assert!(
+        body.contains("| `feature.js` | 3 | 1 |"),
+        "comment: {}",
+        body
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_checks_token_scope_before_commenting() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    forge.set_token_scopes(&["read_api", "write_repository"]);
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &merge_sha,
+        "job-token",
+        &[("GITLAB_TOKEN", "narrow-token"), ("GIT_AI_CI_COMMENT", "1")],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // The rewrite itself still succeeds; only the comment is skipped
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert!(
+        stderr.contains(
+            "GITLAB_TOKEN has the read_api, write_repository scope(s) but needs api to comment on merge requests"
+        ),
+        "stderr: {}",
+        stderr
+    );
+    assert!(
+        !forge
+            .requests()
+            .iter()
+            .any(|request| request.method == "POST" && request.path.ends_with("/notes")),
+        "the MR was commented on despite the missing scope"
+    );
+}
+
+/// `ci github run` for pull request #7 once squash-merged, with `extra_env` set
+fn run_ci_github_on_merged_pull_request(extra_env: &[(&str, &str)]) -> (MockForge, Output) {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    upstream
+        .git_og(&["update-ref", "refs/pull/7/head", &feature_sha])
+        .unwrap();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_pull_request(
+        "acme/widgets",
+        MockPullRequest {
+            number: 7,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: merge_sha.clone(),
+            merged: true,
+            merge_commit_sha: Some(merge_sha.clone()),
+        },
+    );
+
+    let payload = forge.github_event_payload("acme/widgets", 7).unwrap();
+    let output = run_ci_github(&forge, &merge_sha, "pull_request", &payload, extra_env);
+    (forge, output)
+}
+
+/// `ci github run` for a `event_name` event with `payload`, asserting it succeeds
+fn run_ci_github(
+    forge: &MockForge,
+    commit_sha: &str,
+    event_name: &str,
+    payload: &serde_json::Value,
+    extra_env: &[(&str, &str)],
+) -> Output {
+    let workdir = tempfile::tempdir().unwrap();
+    let event_path = workdir.path().join("event.json");
+    std::fs::write(&event_path, payload.to_string()).unwrap();
+    let output =
+        github_actions_command(forge, &["ci", "github", "run"], commit_sha, workdir.path())
+            .env("GITHUB_EVENT_NAME", event_name)
+            .env("GITHUB_EVENT_PATH", &event_path)
+            .envs(extra_env.iter().copied())
+            .output()
+            .unwrap();
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// `git-ai <args>` in a GitHub Actions job for `acme/widgets` building `commit_sha`
+fn github_actions_command(
+    forge: &MockForge,
+    args: &[&str],
+    commit_sha: &str,
+    workdir: &Path,
+) -> Command {
+    let mut command = Command::new(get_binary_path());
+    command
+        .args(args)
+        .current_dir(workdir)
+        .envs(forge.github_actions_env("acme/widgets", commit_sha, "gh-token"))
+        .env_remove("GIT_AI_CI_COMMENT")
+        .env_remove("GIT_AI_CI_CHECK_RUN")
+        .env_remove("GITHUB_APP_ID")
+        .env_remove("GITHUB_APP_PRIVATE_KEY")
+        .env_remove("GITHUB_APP_PRIVATE_KEY_PATH")
+        .env_remove("GIT_AI_OIDC_EXCHANGE_URL")
+        .env_remove("GIT_AI_GITHUB_APP_TOKEN")
+        .env_remove("GIT_AI_OIDC_TOKEN")
+        .env_remove("GIT_AI_CI_SIGN")
+        .env_remove("GIT_AI_NO_NETWORK")
+        .env_remove("GIT_AI_CI_NOTES_ARTIFACT")
+        .env_remove("GIT_AI_CI_ATTRIBUTION_ARTIFACT")
+        .env("GIT_AI_TEST_DB_PATH", workdir.join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com");
+    command
+}
+
+#[test]
+fn test_ci_github_fork_run_hands_notes_to_ingest_job() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    upstream
+        .git_og(&["update-ref", "refs/pull/7/head", &feature_sha])
+        .unwrap();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_pull_request(
+        "acme/widgets",
+        MockPullRequest {
+            number: 7,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: merge_sha.clone(),
+            merged: true,
+            merge_commit_sha: Some(merge_sha.clone()),
+        },
+    );
+
+    // The fork's run rewrites the notes into the artifact and pushes nothing
+    let artifact_dir = tempfile::tempdir().unwrap();
+    let artifact = artifact_dir.path().join("notes.json");
+    let payload = forge.github_event_payload("acme/widgets", 7).unwrap();
+    let output = run_ci_github(
+        &forge,
+        &merge_sha,
+        "pull_request",
+        &payload,
+        &[("GIT_AI_CI_NOTES_ARTIFACT", artifact.to_str().unwrap())],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("notes of 1 commit(s) written to"),
+        "{}",
+        stdout
+    );
+    assert!(
+        upstream
+            .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+            .is_err()
+    );
+    let contents: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&artifact).unwrap()).unwrap();
+    assert_eq!(contents["merge_request"], 7);
+    assert!(contents["notes"][&merge_sha].is_string());
+
+    let ingest = |artifact: &Path| {
+        let workdir = tempfile::tempdir().unwrap();
+        github_actions_command(
+            &forge,
+            &["ci", "github", "ingest", artifact.to_str().unwrap()],
+            &merge_sha,
+            workdir.path(),
+        )
+        .env("GITHUB_EVENT_NAME", "workflow_run")
+        .output()
+        .unwrap()
+    };
+
+    // A note for a commit the merge didn't land is rejected without pushing anything
+    let mut tampered = contents.clone();
+    tampered["notes"][&feature_sha] = contents["notes"][&merge_sha].clone();
+    let tampered_path = artifact_dir.path().join("tampered.json");
+    std::fs::write(&tampered_path, tampered.to_string()).unwrap();
+    let output = ingest(&tampered_path);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("did not land"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        upstream
+            .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+            .is_err()
+    );
+
+    // The base repository's job pushes them, stamped with its own run
+    let output = ingest(&artifact);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .expect("the ingest job should push the merge commit's note");
+    assert!(note.contains("github-actions"), "{}", note);
+
+    // Ingesting again keeps what's there
+    let output = ingest(&artifact);
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("authorship already exists"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn test_ci_github_run_carries_notes_over_rebase_merged_commits() {
+    let (local, upstream) = TestRepo::new_with_remote();
+    let mut file = local.filename("app.js");
+    file.set_contents(lines!["// App v1"]);
+    local.stage_all_and_commit("Initial commit").unwrap();
+    local.git(&["branch", "-M", "main"]).unwrap();
+    local.git(&["push", "-u", "origin", "main"]).unwrap();
+
+    local.git(&["checkout", "-b", "feature"]).unwrap();
+    file.insert_at(1, lines!["function ai1() {}".ai()]);
+    let first = local.stage_all_and_commit("Add ai1").unwrap();
+    file.insert_at(2, lines!["function ai2() {}".ai(), "// human note"]);
+    let second = local.stage_all_and_commit("Add ai2").unwrap();
+    local.git(&["push", "origin", "feature"]).unwrap();
+
+    // Main moves on, then "Rebase and merge" replays both commits on top of it with
+    // new SHAs. Plain git, so the replayed commits have no notes yet.
+    local.git(&["checkout", "main"]).unwrap();
+    std::fs::write(local.path().join("README.md"), "# App\n").unwrap();
+    local.git_og(&["add", "README.md"]).unwrap();
+    local.git_og(&["commit", "-m", "Add readme"]).unwrap();
+    local
+        .git_og(&["cherry-pick", &first.commit_sha, &second.commit_sha])
+        .unwrap();
+    let rebased_head = local
+        .git_og(&["rev-parse", "HEAD"])
+        .unwrap()
+        .trim()
+        .to_string();
+    let rebased_first = local
+        .git_og(&["rev-parse", "HEAD~1"])
+        .unwrap()
+        .trim()
+        .to_string();
+    local.git_og(&["push", "origin", "main"]).unwrap();
+    upstream
+        .git_og(&["update-ref", "refs/pull/7/head", &second.commit_sha])
+        .unwrap();
+
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_pull_request(
+        "acme/widgets",
+        MockPullRequest {
+            number: 7,
+            title: "Add AI functions".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: second.commit_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: rebased_head.clone(),
+            merged: true,
+            merge_commit_sha: Some(rebased_head.clone()),
+        },
+    );
+    let payload = forge.github_event_payload("acme/widgets", 7).unwrap();
+    let output = run_ci_github(&forge, &rebased_head, "pull_request", &payload, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Detected rebase merge: 2 original -> 2 new commits"),
+        "{}",
+        stdout
+    );
+
+    // Each replayed commit gets the note of the commit it replays
+    for (original, rebased) in [
+        (&first.commit_sha, &rebased_first),
+        (&second.commit_sha, &rebased_head),
+    ] {
+        assert!(
+            stdout.contains(&format!(
+                "Carried over the authorship of {} to {}",
+                original, rebased
+            )),
+            "{}",
+            stdout
+        );
+        let note = upstream
+            .git_og(&["notes", "--ref=ai", "show", rebased])
+            .expect("the rebased commit should have an authorship note");
+        let original_note = local
+            .git_og(&["notes", "--ref=ai", "show", original])
+            .unwrap();
+        let attestations = |note: &str| note.split("---").next().unwrap().to_string();
+        assert_eq!(attestations(&note), attestations(&original_note));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ci_github_run_rescans_rewritten_commit_for_security_findings() {
+    let scanner =
+        r#"cat >/dev/null; echo '[{"path": "feature.js", "start_line": 4, "tags": ["CWE-94"]}]'"#;
+    let (_forge, output) =
+        run_ci_github_on_merged_pull_request(&[("GIT_AI_SECURITY_SCAN_COMMAND", scanner)]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Tagged 1 security finding(s)"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_ci_github_run_rewrites_merge_queue_commit_when_it_lands() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    // The queue built the squash on its own branch; main hasn't moved to it yet
+    let base_sha = upstream
+        .git_og(&["rev-parse", &format!("{}^", merge_sha)])
+        .unwrap()
+        .trim()
+        .to_string();
+    let queue_ref = format!("refs/heads/gh-readonly-queue/main/pr-7-{}", base_sha);
+    upstream
+        .git_og(&["update-ref", &queue_ref, &merge_sha])
+        .unwrap();
+    upstream
+        .git_og(&["update-ref", "refs/heads/main", &base_sha])
+        .unwrap();
+    upstream
+        .git_og(&["update-ref", "refs/pull/7/head", &feature_sha])
+        .unwrap();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_pull_request(
+        "acme/widgets",
+        MockPullRequest {
+            number: 7,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: base_sha.clone(),
+            merged: false,
+            merge_commit_sha: None,
+        },
+    );
+
+    // The merge_group run only records which pull request the queue's commit is for
+    let payload = serde_json::json!({
+        "action": "checks_requested",
+        "merge_group": {
+            "head_sha": merge_sha,
+            "head_ref": queue_ref,
+            "base_sha": base_sha,
+            "base_ref": "refs/heads/main",
+        },
+    });
+    let output = run_ci_github(&forge, &merge_sha, "merge_group", &payload, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "Recorded {} as PR #7's merge queue commit",
+            merge_sha
+        )),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        upstream
+            .git_og(&["rev-parse", "refs/git-ai/merge-queue/pr-7"])
+            .unwrap()
+            .trim(),
+        merge_sha
+    );
+    assert!(
+        upstream
+            .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+            .is_err(),
+        "the queued commit may still be dropped from the queue"
+    );
+
+    // The queue lands it and deletes its branch; the push rewrites it from the PR
+    upstream
+        .git_og(&["update-ref", "refs/heads/main", &merge_sha])
+        .unwrap();
+    upstream.git_og(&["update-ref", "-d", &queue_ref]).unwrap();
+    let payload = serde_json::json!({
+        "ref": "refs/heads/main",
+        "before": base_sha,
+        "after": merge_sha,
+        "repository": {
+            "clone_url": format!("{}/acme/widgets.git", forge.url()),
+        },
+    });
+    let output = run_ci_github(&forge, &merge_sha, "push", &payload, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Looking up queued PR #7"), "{}", stdout);
+    assert!(
+        forge
+            .requests()
+            .iter()
+            .any(|request| request.method == "GET"
+                && request.path == "/api/v3/repos/acme/widgets/pulls/7")
+    );
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .expect("the landed commit should have an authorship note");
+    assert!(note.contains("feature.js"), "{}", note);
+}
+
+#[test]
+fn test_ci_github_run_comments_attribution_summary_on_pull_request() {
+    let (forge, output) = run_ci_github_on_merged_pull_request(&[("GIT_AI_CI_COMMENT", "1")]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Commented the attribution summary on PR #7"));
+
+    let comment = forge
+        .requests()
+        .into_iter()
+        .find(|request| {
+            request.method == "POST"
+                && request.path == "/api/v3/repos/acme/widgets/issues/7/comments"
+        })
+        .expect("the PR was commented on");
+    assert_eq!(
+        comment.headers.get("authorization").map(String::as_str),
+        Some("Bearer gh-token")
+    );
+    assert!(
+        comment.body.contains("| `feature.js` | 3 | 1 |"),
+        "body: {}",
+        comment.body
+    );
+}
+
+#[test]
+fn test_ci_github_run_writes_attribution_artifact() {
+    let out_dir = tempfile::tempdir().unwrap();
+    let path = out_dir.path().join("git-ai-attribution.json");
+    let (_forge, output) = run_ci_github_on_merged_pull_request(&[(
+        "GIT_AI_CI_ATTRIBUTION_ARTIFACT",
+        path.to_str().unwrap(),
+    )]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("wrote attribution to"), "{}", stdout);
+
+    let artifact: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(artifact["version"], 1);
+    assert_eq!(artifact["result"], "rewritten");
+    assert_eq!(artifact["merge_request"], 7);
+    let commits = artifact["commits"].as_array().unwrap();
+    assert_eq!(commits.len(), 1);
+    assert_eq!(commits[0]["sha"], artifact["commit"]);
+    assert_eq!(commits[0]["note_sha"].as_str().unwrap().len(), 40);
+    assert_eq!(
+        commits[0]["files"]["feature.js"],
+        serde_json::json!({ "ai": [[3, 5]], "human": [[2, 2]] })
+    );
+}
+
+#[test]
+fn test_ci_github_dry_run_prints_notes_without_pushing() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    upstream
+        .git_og(&["update-ref", "refs/pull/7/head", &feature_sha])
+        .unwrap();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("acme/widgets", upstream.path());
+    forge.add_pull_request(
+        "acme/widgets",
+        MockPullRequest {
+            number: 7,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: merge_sha.clone(),
+            merged: true,
+            merge_commit_sha: Some(merge_sha.clone()),
+        },
+    );
+
+    let workdir = tempfile::tempdir().unwrap();
+    let event_path = workdir.path().join("event.json");
+    let payload = forge.github_event_payload("acme/widgets", 7).unwrap();
+    std::fs::write(&event_path, payload.to_string()).unwrap();
+    let output = github_actions_command(
+        &forge,
+        &["ci", "github", "run", "--dry-run"],
+        &merge_sha,
+        workdir.path(),
+    )
+    .env("GITHUB_EVENT_NAME", "pull_request")
+    .env("GITHUB_EVENT_PATH", &event_path)
+    .output()
+    .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains(&format!("Would write the note of {}:", merge_sha)),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("feature.js"), "{}", stdout);
+    assert!(
+        stdout.contains("dry run, notes of 1 commit(s) not pushed"),
+        "{}",
+        stdout
+    );
+    assert!(
+        upstream
+            .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_ci_github_run_annotates_ai_lines_in_check_run() {
+    let (forge, output) = run_ci_github_on_merged_pull_request(&[("GIT_AI_CI_CHECK_RUN", "1")]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Adding a check run with 1 annotation(s)"),
+        "{}",
+        stdout
+    );
+
+    let requests = forge.requests();
+    let check_run = requests
+        .iter()
+        .find(|request| {
+            request.method == "POST" && request.path == "/api/v3/repos/acme/widgets/check-runs"
+        })
+        .expect("a check run was created");
+    let body: serde_json::Value = serde_json::from_str(&check_run.body).unwrap();
+    assert_eq!(body["conclusion"], "neutral");
+    let annotations = body["output"]["annotations"].as_array().unwrap();
+    assert_eq!(annotations.len(), 1);
+    assert_eq!(annotations[0]["path"], "feature.js");
+    assert_eq!(annotations[0]["start_line"], 3);
+    assert_eq!(annotations[0]["end_line"], 5);
+    assert!(
+        annotations[0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Written by AI")
+    );
+    // Commenting is a separate opt-in
+    assert!(
+        !requests
+            .iter()
+            .any(|request| request.path.ends_with("/comments"))
+    );
+}
+
+#[test]
+fn test_ci_gitlab_run_in_downstream_pipeline() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_project_id("42", "group/project");
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    // The job runs in another project's pipeline, triggered by the one that merged
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &"0".repeat(40),
+        "job-token",
+        &[
+            ("CI_PROJECT_ID", "99"),
+            ("CI_PROJECT_PATH", "group/downstream"),
+            ("CI_PIPELINE_SOURCE", "pipeline"),
+            ("UPSTREAM_PROJECT_ID", "42"),
+            ("UPSTREAM_COMMIT_SHA", &merge_sha),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Project ID: 42 (from UPSTREAM_PROJECT_ID)"));
+    assert!(stdout.contains("Project path: group/project"));
+    assert!(stdout.contains("GitLab CI: authorship rewritten successfully"));
+    assert!(
+        forge
+            .requests()
+            .iter()
+            .any(|request| request.path == "/api/v4/projects/42")
+    );
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+}
+
+#[test]
+fn test_ci_gitlab_run_checks_direct_push() {
+    let (local, upstream, _feature_sha, merge_sha) = squash_merged_upstream();
+    let mut file = local.filename("hotfix.txt");
+    file.set_contents(lines!["hotfix".ai()]);
+    let hotfix_sha = local.stage_all_and_commit("Hotfix").unwrap().commit_sha;
+    // Pushed through git-ai, so its note goes along
+    local.git(&["push", "origin", "main"]).unwrap();
+    file.set_contents(lines!["hotfix".ai(), "followup"]);
+    let followup_sha = local.stage_all_and_commit("Follow-up").unwrap().commit_sha;
+    // Pushed with plain git, like a machine without git-ai
+    local.git_og(&["push", "origin", "main"]).unwrap();
+
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+
+    let output = run_ci_gitlab_with_env(
+        &forge,
+        &followup_sha,
+        "job-token",
+        &[
+            ("CI_PIPELINE_SOURCE", "push"),
+            ("CI_COMMIT_BRANCH", "main"),
+            ("CI_COMMIT_BEFORE_SHA", &merge_sha),
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Checking 2 commit(s) pushed to main"));
+    assert!(stdout.contains(&format!("{} has no authorship note", followup_sha)));
+    assert!(!stdout.contains(&format!("{} has no authorship note", hotfix_sha)));
+    assert!(
+        stdout.contains("GitLab CI: 1 of 2 pushed commit(s) have no authorship note"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_ci_sweep_processes_missed_squash_merge() {
+    let (local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    // A direct push after the merge, which no MR produced
+    local
+        .filename("notes.txt")
+        .set_contents(lines!["direct push"]);
+    let direct_sha = local
+        .stage_all_and_commit("Direct push")
+        .unwrap()
+        .commit_sha;
+    local.git_og(&["push", "origin", "main"]).unwrap();
+
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_project_id("42", "group/project");
+    forge.add_merge_request(
+        "42",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+    let sweep = |extra: &[&str]| {
+        let mut args = vec!["ci", "sweep", "--provider", "gitlab", "--since", "1d"];
+        args.extend(extra);
+        let output = run_in_gitlab_ci(
+            &forge,
+            &args,
+            &direct_sha,
+            "job-token",
+            &[("CI_DEFAULT_BRANCH", "main")],
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        assert!(
+            output.status.success(),
+            "stdout: {}\nstderr: {}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        stdout
+    };
+
+    let stdout = sweep(&["--dry-run"]);
+    assert!(stdout.contains(&format!(
+        "TODO  {}  would process request #1",
+        &merge_sha[..12]
+    )));
+    assert!(stdout.contains(&format!(
+        "SKIP  {}  not from a merged request",
+        &direct_sha[..12]
+    )));
+    assert!(
+        upstream
+            .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+            .is_err()
+    );
+
+    let stdout = sweep(&[]);
+    assert!(stdout.contains(&format!(
+        "FIXED {}  request #1: authorship rewritten",
+        &merge_sha[..12]
+    )));
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+
+    // Nothing is left to do on the next sweep
+    let stdout = sweep(&[]);
+    assert!(stdout.contains(&format!("OK    {}  already attributed", &merge_sha[..12])));
+    assert!(stdout.contains(" 0 processed, 0 to process,"));
+}
+
+#[test]
+fn test_ci_backfill_resumes_from_saved_state() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("group/project", upstream.path());
+    forge.add_merge_request(
+        "group/project",
+        MockMergeRequest::merged(1, "feature", "main", &feature_sha, &merge_sha),
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let repos = dir.path().join("repos.txt");
+    std::fs::write(&repos, "# everything\ngitlab group/project main\n").unwrap();
+    let state = dir.path().join("state.json");
+    let backfill = || {
+        let output = run_in_gitlab_ci(
+            &forge,
+            &[
+                "ci",
+                "backfill",
+                "--repos",
+                repos.to_str().unwrap(),
+                "--state",
+                state.to_str().unwrap(),
+                "--rate",
+                &format!("{}=100/min", forge.url().trim_start_matches("http://")),
+            ],
+            &merge_sha,
+            "job-token",
+            &[],
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        assert!(
+            output.status.success(),
+            "stdout: {}\nstderr: {}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        stdout
+    };
+
+    let stdout = backfill();
+    assert!(
+        stdout.contains("request #1: authorship rewritten"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("  DONE  gitlab:group/project  1 processed\n"));
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&state).unwrap()).unwrap();
+    assert_eq!(saved["repos"]["gitlab:group/project"]["complete"], true);
+    // Only where it got to is kept, not every commit it looked at
+    assert_eq!(
+        saved["repos"]["gitlab:group/project"]["high_water_mark"],
+        merge_sha.as_str()
+    );
+    assert!(saved["repos"]["gitlab:group/project"]["settled"].is_null());
+
+    // A restarted run skips what's done without asking the forge again
+    let api_requests = |forge: &MockForge| {
+        forge
+            .requests()
+            .iter()
+            .filter(|request| request.path.starts_with("/api/"))
+            .count()
+    };
+    let before = api_requests(&forge);
+    let stdout = backfill();
+    assert!(stdout.contains("gitlab:group/project was completed by an earlier run"));
+    assert_eq!(api_requests(&forge), before);
+
+    // A run stopped mid-repository resumes after the mark
+    let mut saved = saved;
+    saved["repos"]["gitlab:group/project"]["complete"] = false.into();
+    std::fs::write(&state, saved.to_string()).unwrap();
+    let stdout = backfill();
+    assert!(stdout.contains("  DONE  gitlab:group/project  1 processed\n"));
+    assert_eq!(api_requests(&forge), before);
+}
+
+#[test]
+fn test_ci_gitlab_run_reports_rejected_token() {
+    let forge = MockForge::start().unwrap();
+    forge.require_token("expected-token");
+
+    let output = run_ci_gitlab(&forge, "0000000000000000000000000000000000000000", "wrong");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("GitLab API returned status 401"));
+    assert!(stderr.contains(
+        "CI_JOB_TOKEN can't list merge requests; set GITLAB_TOKEN to a token with the read_api scope"
+    ));
+}
+
+#[test]
+fn test_ci_bitbucket_run_matches_abbreviated_merge_commit() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("team/project", upstream.path());
+    forge.add_pull_request(
+        "team/project",
+        MockPullRequest {
+            number: 5,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: String::new(),
+            merged: true,
+            merge_commit_sha: Some(merge_sha.clone()),
+        },
+    );
+    forge.require_token("bb-token");
+    // Closed on merge, so the head has to be fetched by hash
+    upstream.git_og(&["branch", "-D", "feature"]).unwrap();
+
+    let workdir = tempfile::tempdir().unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["ci", "bitbucket", "run"])
+        .current_dir(workdir.path())
+        .envs(forge.bitbucket_pipelines_env("team/project", &merge_sha, "bb-token"))
+        .env_remove("BITBUCKET_PR_ID")
+        .env_remove("BITBUCKET_USERNAME")
+        .env_remove("BITBUCKET_APP_PASSWORD")
+        .env("GIT_AI_TEST_DB_PATH", workdir.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Found matching PR #5"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("Bitbucket Pipelines: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+    assert!(forge.requests().iter().any(|request| {
+        request
+            .path
+            .starts_with("/api/bitbucket/2.0/repositories/team/project/pullrequests?state=MERGED")
+            && request.headers.get("authorization").map(String::as_str) == Some("Bearer bb-token")
+    }));
+}
+
+#[test]
+fn test_ci_gitea_run_matches_merged_pull_request() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("org/project", upstream.path());
+    forge.add_pull_request(
+        "org/project",
+        MockPullRequest {
+            number: 7,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: String::new(),
+            merged: true,
+            merge_commit_sha: Some(merge_sha.clone()),
+        },
+    );
+    forge.require_token("gitea-token");
+    upstream
+        .git_og(&["update-ref", "refs/pull/7/head", &feature_sha])
+        .unwrap();
+    upstream.git_og(&["branch", "-D", "feature"]).unwrap();
+
+    let workdir = tempfile::tempdir().unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["ci", "gitea", "run"])
+        .current_dir(workdir.path())
+        .envs(forge.gitea_actions_env("org/project", &merge_sha, "gitea-token"))
+        .env_remove("GITEA_API_URL")
+        .env_remove("GITHUB_TOKEN")
+        .env("GIT_AI_TEST_DB_PATH", workdir.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Found matching PR #7"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("Gitea Actions: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+    assert!(forge.requests().iter().any(|request| {
+        request
+            .path
+            .starts_with("/api/v1/repos/org/project/pulls?state=closed")
+            && request.headers.get("authorization").map(String::as_str) == Some("token gitea-token")
+    }));
+}
+
+#[test]
+fn test_ci_gerrit_run_reads_reviewed_patch_set_of_rebased_change() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("gerrit/org/project", upstream.path());
+    // Gerrit rebased patch set 1 on submit, creating patch set 2 as the merged commit
+    forge.add_gerrit_change(MockGerritChange {
+        number: 1234,
+        change_id: "I0123456789abcdef0123456789abcdef01234567".to_string(),
+        project: "org/project".to_string(),
+        branch: "main".to_string(),
+        subject: "Add AI feature".to_string(),
+        status: "MERGED".to_string(),
+        patch_sets: vec![
+            (feature_sha.clone(), "REWORK".to_string()),
+            (merge_sha.clone(), "TRIVIAL_REBASE".to_string()),
+        ],
+    });
+    forge.require_token("gerrit-http-password");
+    for (patch_set, sha) in [(1, &feature_sha), (2, &merge_sha)] {
+        upstream
+            .git_og(&[
+                "update-ref",
+                &format!("refs/changes/34/1234/{}", patch_set),
+                sha,
+            ])
+            .unwrap();
+    }
+    upstream.git_og(&["branch", "-D", "feature"]).unwrap();
+
+    let workdir = tempfile::tempdir().unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["ci", "gerrit", "run"])
+        .current_dir(workdir.path())
+        .envs(forge.gerrit_trigger_env("org/project", &merge_sha, "ci-bot", "gerrit-http-password"))
+        .env("GIT_AI_TEST_DB_PATH", workdir.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("patch set 1 was reviewed"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("Gerrit: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+    assert!(
+        forge
+            .requests()
+            .iter()
+            .any(|request| request.path.starts_with("/gerrit/a/changes/?q=commit:"))
+    );
+}
+
+#[test]
+fn test_ci_codebuild_run_finds_merged_codecommit_pull_request() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("v1/repos/app", upstream.path());
+    for number in [7, 8] {
+        // Pull request 8 was closed later without merging
+        forge.add_pull_request(
+            "app",
+            MockPullRequest {
+                number,
+                title: format!("Pull request {}", number),
+                head_ref: "feature".to_string(),
+                head_sha: feature_sha.clone(),
+                base_ref: "main".to_string(),
+                base_sha: String::new(),
+                merged: number == 7,
+                merge_commit_sha: (number == 7).then(|| merge_sha.clone()),
+            },
+        );
+    }
+    forge.require_token("AKIAMOCKACCESSKEY");
+
+    let workdir = tempfile::tempdir().unwrap();
+    let output = Command::new(get_binary_path())
+        .args(["ci", "codebuild", "run"])
+        .current_dir(workdir.path())
+        .envs(forge.codebuild_env("app", &merge_sha, "AKIAMOCKACCESSKEY"))
+        .env("GIT_AI_TEST_DB_PATH", workdir.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Pull request 7 was merged as"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("CodeBuild: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+    let requests = forge.requests();
+    let codecommit: Vec<_> = requests
+        .iter()
+        .filter(|request| request.path == "/codecommit/")
+        .collect();
+    assert!(!codecommit.is_empty());
+    assert!(codecommit.iter().all(|request| {
+        request.headers.get("authorization").is_some_and(|value| {
+            value.starts_with("AWS4-HMAC-SHA256 Credential=AKIAMOCKACCESSKEY/")
+                && value.contains("/us-east-1/codecommit/aws4_request")
+        })
+    }));
+}
+
+#[test]
+fn test_ci_jenkins_run_finds_squashed_branch_in_checkout() {
+    let (_local, upstream, _feature_sha, merge_sha) = squash_merged_upstream();
+
+    // Like a Jenkins checkout: only the built branch
+    let workspace = tempfile::tempdir().unwrap();
+    let checkout = workspace.path().join("checkout");
+    let clone = Command::new("git")
+        .args(["clone", "--single-branch", "--branch", "main"])
+        .arg(upstream.path())
+        .arg(&checkout)
+        .output()
+        .unwrap();
+    assert!(clone.status.success());
+
+    let output = Command::new(get_binary_path())
+        .args(["ci", "jenkins", "run"])
+        .current_dir(&checkout)
+        .env("GIT_COMMIT", &merge_sha)
+        .env("BRANCH_NAME", "main")
+        .env_remove("CHANGE_ID")
+        .env_remove("CHANGE_BRANCH")
+        .env_remove("CHANGE_TARGET")
+        .env("GIT_AI_TEST_DB_PATH", workspace.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("was merged as"), "stdout: {}", stdout);
+    assert!(stdout.contains("Jenkins: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+}
+
+#[test]
+fn test_ci_generic_rewrites_merge_given_as_flags() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+
+    // A checkout of only the built branch; file:// so the feature head isn't copied too
+    let workspace = tempfile::tempdir().unwrap();
+    let checkout = workspace.path().join("checkout");
+    let clone = Command::new("git")
+        .args(["clone", "--single-branch", "--branch", "main"])
+        .arg(format!("file://{}", upstream.path().display()))
+        .arg(&checkout)
+        .output()
+        .unwrap();
+    assert!(clone.status.success());
+
+    let output = Command::new(get_binary_path())
+        .args([
+            "ci",
+            "generic",
+            "--merge-sha",
+            &merge_sha,
+            "--head-ref",
+            "feature",
+            "--head-sha",
+            &feature_sha,
+            "--base-ref",
+            "main",
+        ])
+        .current_dir(&checkout)
+        .env("GIT_AI_TEST_DB_PATH", workspace.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Fetching head branch feature"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("Generic CI: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+}
+
+#[test]
+fn test_ci_circleci_run_finds_squashed_branch_through_pipelines() {
+    let (_local, upstream, feature_sha, merge_sha) = squash_merged_upstream();
+    let forge = MockForge::start().unwrap();
+    forge.add_repo("org/project", upstream.path());
+    // CircleCI built the feature branch before it merged
+    forge.add_pull_request(
+        "org/project",
+        MockPullRequest {
+            number: 9,
+            title: "Add AI feature".to_string(),
+            head_ref: "feature".to_string(),
+            head_sha: feature_sha.clone(),
+            base_ref: "main".to_string(),
+            base_sha: String::new(),
+            merged: true,
+            merge_commit_sha: Some(merge_sha.clone()),
+        },
+    );
+    forge.require_token("circle-token");
+    // Deleted on merge, so only the pipeline remembers the branch head
+    upstream.git_og(&["branch", "-D", "feature"]).unwrap();
+
+    let workspace = tempfile::tempdir().unwrap();
+    let checkout = workspace.path().join("project");
+    let clone = Command::new("git")
+        .args(["clone", "--branch", "main"])
+        .arg(forge.repo_url("org/project"))
+        .arg(&checkout)
+        .output()
+        .unwrap();
+    assert!(clone.status.success());
+
+    let output = Command::new(get_binary_path())
+        .args(["ci", "circleci", "run"])
+        .current_dir(&checkout)
+        .env("CIRCLECI", "true")
+        .env("CIRCLE_SHA1", &merge_sha)
+        .env("CIRCLE_BRANCH", "main")
+        .env("CIRCLE_PROJECT_USERNAME", "org")
+        .env("CIRCLE_PROJECT_REPONAME", "project")
+        .env("CIRCLECI_API_URL", forge.circleci_api_url())
+        .env("CIRCLECI_TOKEN", "circle-token")
+        .env_remove("CIRCLE_PULL_REQUEST")
+        .env_remove("CIRCLECI_PROJECT_SLUG")
+        .env("GIT_AI_TEST_DB_PATH", workspace.path().join("db"))
+        .env("GIT_COMMITTER_NAME", "CI")
+        .env("GIT_COMMITTER_EMAIL", "ci@example.com")
+        .env("GIT_AUTHOR_NAME", "CI")
+        .env("GIT_AUTHOR_EMAIL", "ci@example.com")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("feature was merged as"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("CircleCI: authorship rewritten successfully"));
+
+    let note = upstream
+        .git_og(&["notes", "--ref=ai", "show", &merge_sha])
+        .unwrap();
+    assert!(note.contains("feature.js"), "note: {}", note);
+    assert!(forge.requests().iter().any(|request| {
+        request.path == "/api/circleci/v2/project/gh/org/project/pipeline"
+            && request.headers.get("circle-token").map(String::as_str) == Some("circle-token")
+    }));
+}
@@ -0,0 +1,99 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn doctor_reports_capabilities_and_notes_needing_newer_git_ai() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines!["fn human() {}".human(), "fn ai() {}".ai()]);
+    let commit = repo.stage_all_and_commit("Add lib").unwrap();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&repo.git_ai(&["doctor", "--json"]).unwrap()).unwrap();
+    assert!(
+        report["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("min_reader_version"))
+    );
+    assert_eq!(report["notes"]["total"], 1);
+    assert_eq!(report["notes"]["incompatible"], 0);
+
+    // Simulate a note written by a future git-ai that older readers must not trust
+    let note = repo
+        .git_og(&["notes", "--ref=ai", "show", &commit.commit_sha])
+        .unwrap();
+    let future_note = note.replacen(
+        "\"schema_version\"",
+        "\"min_reader_version\":\"999.0.0\",\"schema_version\"",
+        1,
+    );
+    repo.git_og(&[
+        "notes",
+        "--ref=ai",
+        "add",
+        "-f",
+        "-m",
+        &future_note,
+        &commit.commit_sha,
+    ])
+    .unwrap();
+
+    // Doctor prints the report to stdout and fails so scripts notice
+    assert!(repo.git_ai(&["doctor"]).is_err());
+
+    let stats = repo
+        .git_ai(&["stats", &commit.commit_sha])
+        .unwrap_or_else(|e| e);
+    assert!(stats.contains("requires git-ai 999.0.0 or newer"));
+    assert!(stats.contains("please upgrade"));
+}
+
+#[test]
+fn doctor_fix_configures_notes_refspecs_for_plain_git_fetch() {
+    let (local, upstream) = TestRepo::new_with_remote();
+
+    let mut file = local.filename("lib.rs");
+    file.set_contents(lines!["fn ai() {}".ai()]);
+    let commit = local.stage_all_and_commit("Add lib").unwrap();
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&local.git_ai(&["doctor", "--json"]).unwrap()).unwrap();
+    assert_eq!(report["remotes"][0]["name"], "origin");
+    assert_eq!(
+        report["remotes"][0]["missing_refspecs"],
+        serde_json::json!(["fetch +refs/notes/ai:refs/notes/ai-remote/origin"])
+    );
+
+    let output = local.git_ai(&["doctor", "--fix"]).unwrap();
+    assert!(output.contains("added fetch"), "doctor output: {}", output);
+    let output = local.git_ai(&["doctor"]).unwrap();
+    assert!(output.contains("notes refspecs configured"));
+    assert!(
+        local
+            .git_og(&["config", "--get-all", "remote.origin.push"])
+            .is_err(),
+        "a push refspec would change what plain git push sends"
+    );
+
+    // Vanilla git, bypassing the wrapper, now fetches the remote's notes
+    local
+        .git_og(&["update-ref", "-d", "refs/notes/ai-remote/origin"])
+        .unwrap();
+    local.git_og(&["fetch", "origin"]).unwrap();
+    assert_eq!(
+        local
+            .git_og(&["rev-parse", "refs/notes/ai-remote/origin"])
+            .unwrap(),
+        upstream.git_og(&["rev-parse", "refs/notes/ai"]).unwrap()
+    );
+    assert!(
+        local
+            .git_og(&["notes", "--ref=ai", "show", &commit.commit_sha])
+            .is_ok()
+    );
+}
@@ -0,0 +1,223 @@
+#[macro_use]
+mod repos;
+
+use git_ai::authorship::authorship_log_serialization::AuthorshipLog;
+use git_ai::authorship::note_delta::encode;
+use git_ai::authorship::transcript::{AiTranscript, Message};
+use git_ai::git::refs::show_authorship_note;
+use git_ai::git::repository::find_repository_in_path;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+use std::fs;
+
+/// Checkpoint `file` as edited in one long-running conversation that has reached `turns`
+fn checkpoint_turns(repo: &TestRepo, file: &str, turns: &[&str]) {
+    let mut transcript = AiTranscript::new();
+    for turn in turns {
+        transcript.add_message(Message::user(turn.to_string(), None));
+        transcript.add_message(Message::assistant(format!("Done: {}", turn), None));
+    }
+    let hook_input = serde_json::json!({
+        "type": "ai_agent",
+        "repo_working_dir": repo.path().to_str().unwrap(),
+        "edited_filepaths": [file],
+        "transcript": transcript,
+        "agent_name": "test-agent",
+        "model": "test-model",
+        "conversation_id": "long-conversation",
+    });
+    repo.git_ai(&[
+        "checkpoint",
+        "agent-v1",
+        "--hook-input",
+        &serde_json::to_string(&hook_input).unwrap(),
+    ])
+    .expect("checkpoint should succeed");
+}
+
+#[test]
+fn notes_along_a_session_are_deltas_between_full_snapshots() {
+    let mut repo = TestRepo::new();
+    repo.patch_git_ai_config(|patch| {
+        patch.exclude_prompts_in_repositories = Some(vec![]);
+        patch.prompt_storage = Some("notes".to_string());
+        patch.note_delta_interval = Some(2);
+    });
+    fs::write(repo.path().join("README.md"), "# Test Repo\n").unwrap();
+    repo.git(&["add", "-A"]).unwrap();
+    repo.git(&["commit", "-m", "initial commit"]).unwrap();
+
+    let mut turns = Vec::new();
+    let mut commits = Vec::new();
+    for (i, file) in ["a.txt", "b.txt", "c.txt"].iter().enumerate() {
+        let turn = format!("write {} {}", file, "and explain each step ".repeat(10));
+        turns.push(turn);
+        fs::write(repo.path().join(file), format!("AI line {}\n", i)).unwrap();
+        checkpoint_turns(
+            &repo,
+            file,
+            &turns.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        commits.push(repo.stage_all_and_commit(file).unwrap());
+    }
+    let stored = |sha: &str| repo.git_og(&["notes", "--ref=ai", "show", sha]).unwrap();
+
+    // The first note is full, the second only has the turn it added
+    assert!(!stored(&commits[0].commit_sha).contains("\"delta\""));
+    let delta = stored(&commits[1].commit_sha);
+    assert!(delta.contains("\"delta\""), "note: {}", delta);
+    assert!(delta.contains(&commits[0].commit_sha), "note: {}", delta);
+    assert!(delta.contains("write b.txt"), "note: {}", delta);
+    assert!(!delta.contains("write a.txt"), "note: {}", delta);
+
+    // Read back, it has the whole conversation so far
+    let prompt = commits[1]
+        .authorship_log
+        .metadata
+        .prompts
+        .values()
+        .next()
+        .unwrap();
+    assert_eq!(prompt.messages.len(), 4);
+    assert!(
+        matches!(&prompt.messages[0], Message::User { text, .. } if text.starts_with("write a.txt"))
+    );
+    assert!(commits[1].authorship_log.metadata.delta.is_none());
+
+    // With an interval of 2, the third starts a new chain
+    let full = stored(&commits[2].commit_sha);
+    assert!(!full.contains("\"delta\""), "note: {}", full);
+    assert!(full.contains("write a.txt"), "note: {}", full);
+}
+
+#[test]
+fn delta_against_a_rewritten_parent_note_reads_unresolved() {
+    let mut repo = TestRepo::new();
+    repo.patch_git_ai_config(|patch| {
+        patch.exclude_prompts_in_repositories = Some(vec![]);
+        patch.prompt_storage = Some("notes".to_string());
+        patch.note_delta_interval = Some(3);
+    });
+    fs::write(repo.path().join("README.md"), "# Test Repo\n").unwrap();
+    repo.git(&["add", "-A"]).unwrap();
+    repo.git(&["commit", "-m", "initial commit"]).unwrap();
+
+    let mut turns = Vec::new();
+    let mut commits = Vec::new();
+    for (i, file) in ["a.txt", "b.txt"].iter().enumerate() {
+        turns.push(format!(
+            "write {} {}",
+            file,
+            "and explain each step ".repeat(10)
+        ));
+        fs::write(repo.path().join(file), format!("AI line {}\n", i)).unwrap();
+        checkpoint_turns(
+            &repo,
+            file,
+            &turns.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        commits.push(repo.stage_all_and_commit(file).unwrap());
+    }
+    let stored = |sha: &str| repo.git_og(&["notes", "--ref=ai", "show", sha]).unwrap();
+    assert!(stored(&commits[1].commit_sha).contains("\"delta\""));
+
+    // Rewrite the parent's note under the delta
+    let parent = &commits[0].commit_sha;
+    let rewritten = stored(parent).replace("write a.txt", "write a.txt again");
+    repo.git_og(&["notes", "--ref=ai", "add", "-f", "-m", &rewritten, parent])
+        .unwrap();
+
+    let git_ai_repo = find_repository_in_path(repo.path().to_str().unwrap()).unwrap();
+    assert!(show_authorship_note(&git_ai_repo, parent).is_some());
+    // Only the delta's own content comes back, still marked as a delta
+    let unresolved = show_authorship_note(&git_ai_repo, &commits[1].commit_sha)
+        .expect("the delta's own content is still readable");
+    assert!(unresolved.contains("\"delta\""));
+    assert!(unresolved.contains("b.txt"));
+    assert!(
+        !unresolved.contains("write a.txt again"),
+        "a delta resolved against the rewritten note would carry its prompts"
+    );
+
+    // The next note can't build on it, so it's written in full
+    turns.push("write c.txt".to_string());
+    fs::write(repo.path().join("c.txt"), "AI line 2\n").unwrap();
+    checkpoint_turns(
+        &repo,
+        "c.txt",
+        &turns.iter().map(String::as_str).collect::<Vec<_>>(),
+    );
+    let next = repo.stage_all_and_commit("c.txt").unwrap();
+    assert!(!stored(&next.commit_sha).contains("\"delta\""));
+}
+
+#[test]
+fn rebasing_a_commit_whose_note_is_a_delta_keeps_its_attribution() {
+    let mut repo = TestRepo::new();
+    repo.patch_git_ai_config(|patch| {
+        patch.exclude_prompts_in_repositories = Some(vec![]);
+        patch.prompt_storage = Some("notes".to_string());
+        patch.note_delta_interval = Some(10);
+    });
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Test Repo"]);
+    repo.stage_all_and_commit("initial commit").unwrap();
+    let default_branch = repo.current_branch();
+
+    let mut lib = repo.filename("lib.rs");
+    lib.set_contents(lines!["fn a() {}".ai(), "fn b() {}".ai()]);
+    let add = repo.stage_all_and_commit("Add lib").unwrap();
+    repo.git(&["checkout", "-b", "feature"]).unwrap();
+    lib.set_contents(lines!["fn a() { 1 }".ai(), "fn b() { 2 }".ai()]);
+    let rewrite = repo.stage_all_and_commit("Rewrite lib").unwrap();
+
+    // Store the rewrite's note as a delta that leaves lib.rs out: its parent's note
+    // attributes the file the same way, as when a session rewrites its own lines
+    let stored = |sha: &str| repo.git_og(&["notes", "--ref=ai", "show", sha]).unwrap();
+    let mut parent = AuthorshipLog::deserialize_from_string(&stored(&add.commit_sha)).unwrap();
+    let mut log = AuthorshipLog::deserialize_from_string(&stored(&rewrite.commit_sha)).unwrap();
+    parent.attestations = log.attestations.clone();
+    parent.metadata.prompts.extend(log.metadata.prompts.clone());
+    let parent_content = parent.serialize_to_string().unwrap();
+    repo.git_og(&[
+        "notes",
+        "--ref=ai",
+        "add",
+        "-f",
+        "-m",
+        &parent_content,
+        &add.commit_sha,
+    ])
+    .unwrap();
+    let parent_note = repo
+        .git_og(&["notes", "--ref=ai", "list", &add.commit_sha])
+        .unwrap();
+    assert!(encode(
+        &mut log,
+        &add.commit_sha,
+        parent_note.trim(),
+        &parent,
+        1
+    ));
+    assert!(log.attestations.is_empty());
+    let delta = log.serialize_to_string().unwrap();
+    repo.git_og(&[
+        "notes",
+        "--ref=ai",
+        "add",
+        "-f",
+        "-m",
+        &delta,
+        &rewrite.commit_sha,
+    ])
+    .unwrap();
+
+    repo.git(&["checkout", &default_branch]).unwrap();
+    let mut other = repo.filename("other.txt");
+    other.set_contents(lines!["other content"]);
+    repo.stage_all_and_commit("Main advances").unwrap();
+
+    repo.git(&["checkout", "feature"]).unwrap();
+    repo.git(&["rebase", &default_branch]).unwrap();
+    lib.assert_lines_and_blame(lines!["fn a() { 1 }".ai(), "fn b() { 2 }".ai()]);
+}
@@ -83,3 +83,469 @@ fn push_after_branch_set_upstream_pushes_authorship_notes() {
         "expected authorship notes to be pushed after setting upstream with git branch -u"
     );
 }
+
+#[test]
+fn clone_over_hook_budget_defers_notes_fetch_to_background() {
+    let (mut local, upstream) = TestRepo::new_with_remote();
+
+    let mut file = local.filename("deferred.rs");
+    file.set_contents(vec!["fn deferred() {}".ai()]);
+    let commit = local
+        .stage_all_and_commit("add deferred feature")
+        .expect("commit should succeed");
+    local
+        .git(&["push", "-u", "origin", "HEAD"])
+        .expect("push should succeed");
+    assert!(read_remote_authorship_note(&upstream, &commit.commit_sha).is_some());
+
+    // A zero budget means the clone hook never waits on the notes fetch
+    local.patch_git_ai_config(|patch| {
+        patch.hook_network_budget_ms = Some(0);
+    });
+    let clone_dir = std::env::temp_dir().join(format!(
+        "{}-deferred-clone",
+        local.path().file_name().unwrap().to_string_lossy()
+    ));
+    let output = local
+        .git(&[
+            "clone",
+            upstream.path().to_str().unwrap(),
+            clone_dir.to_str().unwrap(),
+        ])
+        .expect("clone should succeed");
+    assert!(
+        output.contains("continuing in background"),
+        "clone output: {}",
+        output
+    );
+
+    let sync_log = clone_dir
+        .join(".git")
+        .join("ai")
+        .join("logs")
+        .join("sync.log");
+    let mut log = String::new();
+    for _ in 0..100 {
+        log = std::fs::read_to_string(&sync_log).unwrap_or_default();
+        if log.contains("fetch origin: ok") {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(log.contains("fetch origin: ok"), "sync.log: {}", log);
+
+    let note = Command::new("git")
+        .args(["-C", clone_dir.to_str().unwrap()])
+        .args(["notes", "--ref=ai", "show", &commit.commit_sha])
+        .output()
+        .expect("failed to run git notes show in clone");
+    assert!(
+        note.status.success(),
+        "expected the deferred background fetch to bring notes into the clone"
+    );
+
+    let _ = std::fs::remove_dir_all(&clone_dir);
+}
+
+#[test]
+fn unreachable_remote_queues_notes_push_until_flushed() {
+    let (local, upstream) = TestRepo::new_with_remote();
+    let upstream_path = upstream.path().to_str().unwrap().to_string();
+
+    let mut file = local.filename("offline.rs");
+    file.set_contents(vec!["fn offline() {}".ai()]);
+    let commit = local
+        .stage_all_and_commit("add offline feature")
+        .expect("commit should succeed");
+
+    // Nothing listens on the discard port, so the connection is refused
+    local
+        .git_og(&[
+            "remote",
+            "set-url",
+            "origin",
+            "http://127.0.0.1:9/upstream.git",
+        ])
+        .unwrap();
+    assert!(local.git(&["push", "origin", "HEAD"]).is_err());
+
+    let pending_sync = local.path().join(".git").join("ai").join("pending-sync");
+    let queued = std::fs::read_to_string(&pending_sync).expect("push should be queued");
+    assert!(queued.contains("\"op\":\"push\""), "queue: {}", queued);
+    assert!(
+        queued.contains("\"remote\":\"origin\""),
+        "queue: {}",
+        queued
+    );
+
+    // Back online
+    local
+        .git_og(&["remote", "set-url", "origin", &upstream_path])
+        .unwrap();
+    let output = local
+        .git_ai(&["sync", "--flush"])
+        .expect("flush should succeed once the remote is reachable");
+    assert!(output.contains("push origin: ok"), "output: {}", output);
+
+    assert!(!pending_sync.exists(), "queue should be empty after flush");
+    assert!(
+        read_remote_authorship_note(&upstream, &commit.commit_sha).is_some(),
+        "expected flushed notes on the remote"
+    );
+}
+
+#[test]
+fn notes_fetch_is_incremental_and_skips_when_up_to_date() {
+    let (local, _upstream) = TestRepo::new_with_remote();
+    let has_note = |sha: &str| local.git_og(&["notes", "--ref=ai", "show", sha]).is_ok();
+    let tracking_sha = || {
+        local
+            .git_og(&["rev-parse", "refs/notes/ai-remote/origin"])
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+    let remote_sha = || {
+        local
+            .git_og(&["ls-remote", "origin", "refs/notes/ai"])
+            .unwrap()[..40]
+            .to_string()
+    };
+
+    let mut file = local.filename("feature.rs");
+    file.set_contents(vec!["fn first() {}".ai()]);
+    let first = local.stage_all_and_commit("first").unwrap();
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+
+    // Drop local notes as a fresh clone would have none
+    local
+        .git_og(&["update-ref", "-d", "refs/notes/ai"])
+        .unwrap();
+    assert!(!has_note(&first.commit_sha));
+    local.git_ai(&["sync", "fetch", "origin"]).unwrap();
+    assert!(has_note(&first.commit_sha));
+    assert_eq!(tracking_sha(), remote_sha());
+
+    // Nothing new on the remote: the tracking ref stays put and notes are intact
+    local.git_ai(&["sync", "fetch", "origin"]).unwrap();
+    assert!(has_note(&first.commit_sha));
+    assert_eq!(tracking_sha(), remote_sha());
+
+    // New notes on the remote are fetched on top of the previous tracking ref
+    file.set_contents(vec!["fn first() {}".ai(), "fn second() {}".ai()]);
+    let second = local.stage_all_and_commit("second").unwrap();
+    local.git(&["push"]).unwrap();
+    local
+        .git_og(&["update-ref", "-d", "refs/notes/ai"])
+        .unwrap();
+    local.git_ai(&["sync", "fetch", "origin"]).unwrap();
+    assert!(has_note(&first.commit_sha));
+    assert!(has_note(&second.commit_sha));
+    assert_eq!(tracking_sha(), remote_sha());
+}
+
+#[test]
+fn sync_all_remotes_in_parallel_and_aggregate_failures() {
+    let (local, upstream) = TestRepo::new_with_remote();
+    let mirror = TestRepo::new_bare();
+    local
+        .git_og(&["remote", "add", "mirror", mirror.path().to_str().unwrap()])
+        .unwrap();
+    local
+        .git_og(&[
+            "remote",
+            "add",
+            "offline",
+            "http://127.0.0.1:9/upstream.git",
+        ])
+        .unwrap();
+
+    let mut file = local.filename("multi.rs");
+    file.set_contents(vec!["fn multi() {}".ai()]);
+    let commit = local.stage_all_and_commit("add multi").unwrap();
+
+    let err = local
+        .git_ai(&["sync", "push", "--all"])
+        .expect_err("unreachable remote should fail the sync");
+    assert!(err.contains("push offline: failed"), "stderr: {}", err);
+    assert!(read_remote_authorship_note(&upstream, &commit.commit_sha).is_some());
+    assert!(read_remote_authorship_note(&mirror, &commit.commit_sha).is_some());
+
+    // A wrapped `fetch --all` syncs notes with every remote
+    local.git_og(&["remote", "remove", "offline"]).unwrap();
+    for notes_ref in [
+        "refs/notes/ai",
+        "refs/notes/ai-remote/origin",
+        "refs/notes/ai-remote/mirror",
+    ] {
+        let _ = local.git_og(&["update-ref", "-d", notes_ref]);
+    }
+    local.git(&["fetch", "--all"]).unwrap();
+    for notes_ref in ["refs/notes/ai-remote/origin", "refs/notes/ai-remote/mirror"] {
+        assert!(
+            local.git_og(&["rev-parse", "--verify", notes_ref]).is_ok(),
+            "expected {} after fetch --all",
+            notes_ref
+        );
+    }
+    assert!(
+        local
+            .git_og(&["notes", "--ref=ai", "show", &commit.commit_sha])
+            .is_ok()
+    );
+}
+
+#[test]
+fn single_branch_clone_persists_notes_fetch_refspec() {
+    let (local, upstream) = TestRepo::new_with_remote();
+
+    let mut file = local.filename("narrow.rs");
+    file.set_contents(vec!["fn first() {}".ai()]);
+    local.stage_all_and_commit("first").unwrap();
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+
+    let clone_dir = std::env::temp_dir().join(format!(
+        "{}-single-branch-clone",
+        local.path().file_name().unwrap().to_string_lossy()
+    ));
+    local
+        .git(&[
+            "clone",
+            "--single-branch",
+            "--no-tags",
+            upstream.path().to_str().unwrap(),
+            clone_dir.to_str().unwrap(),
+        ])
+        .expect("clone should succeed");
+
+    let clone_git = |args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&clone_dir)
+            .args(args)
+            .output()
+            .expect("failed to run git in clone");
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let fetch_refspecs = clone_git(&["config", "--get-all", "remote.origin.fetch"]);
+    assert!(
+        fetch_refspecs
+            .lines()
+            .any(|line| line == "+refs/notes/ai:refs/notes/ai-remote/origin"),
+        "fetch refspecs: {}",
+        fetch_refspecs
+    );
+
+    // A plain git fetch, without the git-ai wrapper, now brings new notes along
+    file.set_contents(vec!["fn first() {}".ai(), "fn second() {}".ai()]);
+    local.stage_all_and_commit("second").unwrap();
+    local.git(&["push"]).unwrap();
+    clone_git(&["fetch"]);
+    let remote_notes = local
+        .git_og(&["ls-remote", "origin", "refs/notes/ai"])
+        .unwrap()[..40]
+        .to_string();
+    assert_eq!(
+        clone_git(&["rev-parse", "refs/notes/ai-remote/origin"]),
+        remote_notes
+    );
+}
+
+#[test]
+fn fetch_with_notes_refspec_merges_the_notes_it_brought() {
+    let (local, _upstream) = TestRepo::new_with_remote();
+
+    let mut file = local.filename("refspec.rs");
+    file.set_contents(vec!["fn fetched() {}".ai()]);
+    let commit = local.stage_all_and_commit("fetched").unwrap();
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+
+    for notes_ref in ["refs/notes/ai", "refs/notes/ai-remote/origin"] {
+        let _ = local.git_og(&["update-ref", "-d", notes_ref]);
+    }
+    local
+        .git_og(&[
+            "config",
+            "--add",
+            "remote.origin.fetch",
+            "+refs/notes/ai:refs/notes/ai-remote/origin",
+        ])
+        .unwrap();
+
+    // The fetch itself brings the notes; the wrapper only merges them
+    local.git(&["fetch", "origin"]).unwrap();
+    assert!(
+        local
+            .git_og(&["notes", "--ref=ai", "show", &commit.commit_sha])
+            .is_ok()
+    );
+}
+
+#[test]
+fn notes_push_blocked_by_server_warns_once_and_stops_retrying() {
+    let (local, upstream) = TestRepo::new_with_remote();
+
+    // Reject refs/notes/* the way a host's ref policy would, while allowing branches
+    let hook = upstream.path().join("hooks").join("update");
+    std::fs::write(
+        &hook,
+        "#!/bin/sh\ncase \"$1\" in refs/notes/*) echo 'notes refs are not allowed' >&2; exit 1;; esac\n",
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let mut file = local.filename("blocked.rs");
+    file.set_contents(vec!["fn first() {}".ai()]);
+    let first = local.stage_all_and_commit("first").unwrap();
+    let output = local
+        .git(&["push", "-u", "origin", "HEAD"])
+        .expect("branch push should still succeed");
+    assert!(
+        output.contains("rejected the push of git-ai authorship notes"),
+        "push output: {}",
+        output
+    );
+    assert!(output.contains("git bundle create ai-notes.bundle refs/notes/ai"));
+    assert!(read_remote_authorship_note(&upstream, &first.commit_sha).is_none());
+
+    // Later pushes neither retry the notes nor repeat the warning
+    file.set_contents(vec!["fn first() {}".ai(), "fn second() {}".ai()]);
+    local.stage_all_and_commit("second").unwrap();
+    let output = local.git(&["push"]).expect("push should succeed");
+    assert!(
+        !output.contains("rejected the push"),
+        "push output: {}",
+        output
+    );
+    let blocked = local
+        .path()
+        .join(".git")
+        .join("ai")
+        .join("notes-push-blocked");
+    assert!(blocked.exists());
+
+    // An explicit sync still tries, and clears the block once the host allows notes
+    assert!(local.git_ai(&["sync", "push", "origin"]).is_err());
+    std::fs::remove_file(&hook).unwrap();
+    local.git_ai(&["sync", "push", "origin"]).unwrap();
+    assert!(!blocked.exists());
+    assert!(read_remote_authorship_note(&upstream, &first.commit_sha).is_some());
+}
+
+#[test]
+fn large_notes_keep_summaries_on_ai_and_line_maps_on_details_ref() {
+    let (mut local, upstream) = TestRepo::new_with_remote();
+    local.patch_git_ai_config(|patch| {
+        patch.note_details_threshold = Some(64);
+    });
+
+    // Alternating lines make a line map long enough to be worth splitting off
+    let interleaved = || {
+        (0..200)
+            .map(|i| {
+                if i % 2 == 0 {
+                    format!("fn ai_{}() {{}}", i).ai()
+                } else {
+                    format!("fn human_{}() {{}}", i).human()
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut file = local.filename("tiered.rs");
+    file.set_contents(interleaved());
+    let commit = local.stage_all_and_commit("add tiered").unwrap();
+
+    let summary = local
+        .git_og(&["notes", "--ref=ai", "show", &commit.commit_sha])
+        .unwrap();
+    let full = local
+        .git_og(&["notes", "--ref=ai-details", "show", &commit.commit_sha])
+        .unwrap();
+    assert!(summary.starts_with("---"), "summary: {}", summary);
+    assert!(summary.contains("\"details\""), "summary: {}", summary);
+    assert!(full.starts_with("tiered.rs"), "full note: {}", full);
+    file.assert_lines_and_blame(interleaved());
+
+    // Pushing sends both refs
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+    let upstream_details = Command::new("git")
+        .args(["--git-dir", upstream.path().to_str().unwrap()])
+        .args(["notes", "--ref=ai-details", "show", &commit.commit_sha])
+        .output()
+        .unwrap();
+    assert!(upstream_details.status.success());
+
+    // Without the details locally, blame fetches them on demand
+    local
+        .git_og(&["update-ref", "-d", "refs/notes/ai-details"])
+        .unwrap();
+    let blame = local.git_ai(&["blame", "tiered.rs"]).unwrap();
+    assert!(
+        blame
+            .lines()
+            .any(|line| line.contains("mock_ai") && line.ends_with("fn ai_198() {}")),
+        "blame: {}",
+        blame
+    );
+    assert!(
+        local
+            .git_og(&["notes", "--ref=ai-details", "show", &commit.commit_sha])
+            .is_ok()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn push_over_hook_budget_pushes_notes_exactly_once() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (mut local, upstream) = TestRepo::new_with_remote();
+
+    // The remote logs every notes update and is slow to accept it, so the push is still
+    // running when the hook budget runs out
+    let received = upstream.path().join("notes-received.log");
+    let hook = upstream.path().join("hooks").join("pre-receive");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(
+        &hook,
+        format!(
+            "#!/bin/sh\nwhile read old new ref; do\n  if [ \"$ref\" = refs/notes/ai ]; then\n    echo \"$ref\" >> '{}'\n    sleep 2\n  fi\ndone\n",
+            received.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut file = local.filename("slow_remote.rs");
+    file.set_contents(vec!["fn slow_remote() {}".ai()]);
+    let commit = local
+        .stage_all_and_commit("add slow remote feature")
+        .expect("commit should succeed");
+
+    local.patch_git_ai_config(|patch| {
+        patch.hook_network_budget_ms = Some(0);
+    });
+    local
+        .git(&["push", "origin", "HEAD"])
+        .expect("push should succeed");
+
+    for _ in 0..100 {
+        if read_remote_authorship_note(&upstream, &commit.commit_sha).is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(
+        read_remote_authorship_note(&upstream, &commit.commit_sha).is_some(),
+        "expected the notes push to finish in the background"
+    );
+    // Give a second, racing push time to show up
+    std::thread::sleep(std::time::Duration::from_secs(3));
+    let received = std::fs::read_to_string(&received).unwrap();
+    assert_eq!(received.lines().count(), 1, "notes updates: {}", received);
+}
@@ -0,0 +1,64 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+use serde_json::json;
+
+#[test]
+fn test_query_single_file() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("src/lib.rs");
+    file.set_contents(lines!["fn ai() {}".ai(), "fn human() {}".human()]);
+    repo.stage_all_and_commit("Add lib").unwrap();
+
+    let output = repo.git_ai(&["query", "src/lib.rs"]).unwrap();
+    let result: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    let ranges = result["attribution"]["ranges"].as_array().unwrap();
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0]["start_line"], 1);
+    assert_eq!(ranges[0]["end_line"], 1);
+}
+
+#[test]
+fn test_query_batch_mixes_revisions_and_reports_errors_per_query() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("app.py");
+    file.set_contents(lines!["print('a')".human()]);
+    let first = repo.stage_all_and_commit("Initial").unwrap();
+
+    file.set_contents(lines!["print('a')".human(), "print('b')".ai()]);
+    let mut other = repo.filename("util.py");
+    other.set_contents(lines!["x = 1".ai()]);
+    repo.stage_all_and_commit("Add AI code").unwrap();
+
+    let queries = json!([
+        {"file": "app.py"},
+        {"file": "app.py", "rev": first.commit_sha},
+        {"file": "./util.py", "rev": "HEAD"},
+        {"file": "missing.py", "rev": "HEAD"},
+    ]);
+    let output = repo
+        .git_ai_with_stdin(&["query", "--batch"], queries.to_string().as_bytes())
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    let results = document["results"].as_array().unwrap();
+    assert_eq!(results.len(), 4);
+
+    assert_eq!(results[0]["file"], "app.py");
+    assert!(results[0].get("rev").is_none());
+    assert_eq!(results[0]["attribution"]["ranges"][0]["start_line"], 2);
+
+    assert_eq!(results[1]["rev"], first.commit_sha);
+    assert!(
+        results[1]["attribution"]["ranges"]
+            .as_array()
+            .unwrap()
+            .is_empty()
+    );
+
+    assert_eq!(results[2]["attribution"]["ranges"][0]["start_line"], 1);
+
+    assert!(results[3]["attribution"].is_null());
+    assert!(results[3]["error"].as_str().unwrap().contains("not found"));
+}
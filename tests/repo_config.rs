@@ -0,0 +1,92 @@
+#[macro_use]
+mod repos;
+use git_ai::authorship::stats::CommitStats;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn commit_repo_config(repo: &TestRepo, contents: &str) {
+    std::fs::write(repo.path().join(".git-ai.toml"), contents).unwrap();
+    repo.git_og(&["add", ".git-ai.toml"]).unwrap();
+    repo.git_og(&["commit", "-m", "Add .git-ai.toml"]).unwrap();
+}
+
+fn stats(repo: &TestRepo) -> CommitStats {
+    let raw = repo.git_ai(&["stats", "--json"]).unwrap();
+    let start = raw.find('{').unwrap();
+    let end = raw.rfind('}').unwrap();
+    serde_json::from_str(&raw[start..=end]).unwrap()
+}
+
+#[test]
+fn test_repo_config_ignore_patterns_apply_to_stats() {
+    let repo = TestRepo::new();
+    commit_repo_config(&repo, "ignore = [\"*.lock\"]\n");
+
+    let mut lib = repo.filename("lib.rs");
+    lib.set_contents(lines!["fn ai() {}".ai()]);
+    let mut lock = repo.filename("deps.lock");
+    lock.set_contents(lines!["a = 1".ai(), "b = 2".ai()]);
+    repo.stage_all_and_commit("Add lib and lockfile").unwrap();
+
+    assert_eq!(stats(&repo).git_diff_added_lines, 1);
+
+    // Without the config file the lockfile counts again
+    std::fs::remove_file(repo.path().join(".git-ai.toml")).unwrap();
+    assert_eq!(stats(&repo).git_diff_added_lines, 3);
+}
+
+#[test]
+fn test_repo_config_agents_allow_list_skips_other_agents() {
+    let repo = TestRepo::new();
+    commit_repo_config(&repo, "[agents]\nallow = [\"claude\"]\n");
+
+    // The test harness checkpoints as mock_ai, which isn't allowed
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines!["fn human() {}".human(), "fn ai() {}".ai()]);
+    repo.stage_all_and_commit("Add lib").unwrap();
+    file.assert_lines_and_blame(lines!["fn human() {}".human(), "fn ai() {}".human()]);
+}
+
+#[test]
+fn test_repo_config_agents_allow_list_applies_to_checkpoints_from_outside_the_repo() {
+    let repo = TestRepo::new();
+    commit_repo_config(&repo, "[agents]\nallow = [\"claude\"]\n");
+
+    // The hook runs wherever the agent does; the repository comes from its input
+    std::fs::write(repo.path().join("lib.rs"), "fn ai() {}\n").unwrap();
+    let hook_input = serde_json::json!({
+        "type": "ai_agent",
+        "repo_working_dir": repo.path().to_str().unwrap(),
+        "edited_filepaths": ["lib.rs"],
+        "transcript": { "messages": [] },
+        "agent_name": "test-agent",
+        "model": "test-model",
+        "conversation_id": "outside",
+    });
+    let elsewhere = tempfile::tempdir().unwrap();
+    repo.git_ai_from_working_dir(
+        elsewhere.path(),
+        &[
+            "checkpoint",
+            "agent-v1",
+            "--hook-input",
+            &serde_json::to_string(&hook_input).unwrap(),
+        ],
+    )
+    .expect("checkpoint should succeed");
+    let commit = repo.stage_all_and_commit("Add lib").unwrap();
+    assert!(
+        commit.authorship_log.attestations.is_empty(),
+        "{:?}",
+        commit.authorship_log.attestations
+    );
+}
+
+#[test]
+fn test_repo_config_that_does_not_parse_is_reported() {
+    let repo = TestRepo::new();
+    commit_repo_config(&repo, "ignore = [\n");
+
+    let output = repo.git_ai(&["stats", "--json"]).unwrap();
+    assert!(output.contains("Failed to parse"), "{}", output);
+}
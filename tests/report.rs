@@ -0,0 +1,234 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn report_json(repo: &TestRepo, args: &[&str]) -> serde_json::Value {
+    let mut full_args = vec!["report", "--json"];
+    full_args.extend_from_slice(args);
+    let output = repo.git_ai(&full_args).expect("report should succeed");
+    serde_json::from_str(output.trim()).expect("report should print JSON")
+}
+
+#[test]
+fn test_report_groups_aliases_by_identity_map() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines![
+        "fn a() {}".ai(),
+        "fn b() {}".ai(),
+        "fn c() {}".human()
+    ]);
+    repo.stage_all_and_commit("Add lib").unwrap();
+
+    std::fs::write(repo.path().join("notes.txt"), "one\ntwo\n").unwrap();
+    repo.git(&["add", "-A"]).unwrap();
+    repo.git(&[
+        "-c",
+        "user.name=Jane Personal",
+        "-c",
+        "user.email=jane@personal.example",
+        "commit",
+        "-m",
+        "Add notes",
+    ])
+    .unwrap();
+
+    let author_email = repo.git(&["log", "-1", "--format=%ae", "HEAD~1"]).unwrap();
+    let identity_map = repo.path().join("identities.txt");
+    std::fs::write(
+        &identity_map,
+        format!(
+            "[Platform]\nJane Doe <{}> <jane@personal.example>\n",
+            author_email.trim()
+        ),
+    )
+    .unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let report = report_json(
+        &repo,
+        &[&range, "--identity-map", identity_map.to_str().unwrap()],
+    );
+
+    assert_eq!(report["totals"]["commits"], 2);
+    assert_eq!(report["totals"]["ai_lines"], 2);
+    assert_eq!(report["totals"]["human_lines"], 3);
+
+    let authors = report["by_author"].as_object().unwrap();
+    assert_eq!(authors.len(), 1, "aliases should merge: {:?}", authors);
+    assert_eq!(report["by_team"]["Platform"]["commits"], 2);
+    assert_eq!(report["by_team"]["Platform"]["added_lines"], 5);
+}
+
+#[test]
+fn test_report_unassigned_without_identity_map() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines!["fn a() {}".ai()]);
+    repo.stage_all_and_commit("Add lib").unwrap();
+
+    let report = report_json(&repo, &["HEAD"]);
+    assert_eq!(report["totals"]["commits"], 1);
+    assert_eq!(report["by_team"]["(unassigned)"]["ai_lines"], 1);
+}
+
+#[test]
+fn test_report_by_package_uses_workspace_manifest() {
+    let repo = TestRepo::new();
+
+    let mut manifest = repo.filename("Cargo.toml");
+    manifest.set_contents(lines!["[workspace]", "members = [\"crates/*\"]"]);
+    std::fs::create_dir_all(repo.path().join("crates/core")).unwrap();
+    std::fs::create_dir_all(repo.path().join("crates/cli")).unwrap();
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut core = repo.filename("crates/core/lib.rs");
+    core.set_contents(lines!["pub fn a() {}".ai(), "pub fn b() {}".ai()]);
+    let mut cli = repo.filename("crates/cli/main.rs");
+    cli.set_contents(lines!["fn main() {}".human()]);
+    repo.stage_all_and_commit("Add crates").unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let report = report_json(&repo, &[&range, "--by-package"]);
+
+    assert_eq!(report["by_package"]["crates/core"]["ai_lines"], 2);
+    assert_eq!(report["by_package"]["crates/cli"]["ai_lines"], 0);
+    assert_eq!(report["by_package"]["crates/cli"]["human_lines"], 1);
+    assert!(report["by_package"].get("(root)").is_none());
+}
+
+#[test]
+fn test_report_by_language() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut lib = repo.filename("src/lib.rs");
+    lib.set_contents(lines!["pub fn a() {}".human(), "pub fn b() {}".human()]);
+    let mut script = repo.filename("bin/deploy");
+    script.set_contents(lines!["#!/usr/bin/env python3".ai(), "print('hi')".ai()]);
+    repo.stage_all_and_commit("Add code").unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let report = report_json(&repo, &[&range, "--by-language"]);
+
+    assert_eq!(report["by_language"]["Rust"]["ai_lines"], 0);
+    assert_eq!(report["by_language"]["Rust"]["human_lines"], 2);
+    assert_eq!(report["by_language"]["Python"]["ai_lines"], 2);
+}
+
+#[test]
+fn test_report_split_tests() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut handler = repo.filename("pkg/handler.go");
+    handler.set_contents(lines!["package pkg".human(), "func A() {}".human()]);
+    let mut handler_test = repo.filename("pkg/handler_test.go");
+    handler_test.set_contents(lines!["package pkg".ai(), "func TestA() {}".ai()]);
+    let mut fixture = repo.filename("tests/fixture.txt");
+    fixture.set_contents(lines!["data".ai()]);
+    repo.stage_all_and_commit("Add handler").unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let report = report_json(&repo, &[&range, "--split-tests"]);
+
+    assert_eq!(report["by_code_kind"]["test"]["ai_lines"], 3);
+    assert_eq!(report["by_code_kind"]["test"]["commits"], 1);
+    assert_eq!(report["by_code_kind"]["production"]["ai_lines"], 0);
+    assert_eq!(report["by_code_kind"]["production"]["human_lines"], 2);
+}
+
+#[test]
+fn test_report_classifies_commits_and_records_class_in_note() {
+    use git_ai::authorship::commit_class::CommitClass;
+
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut generated = repo.filename("src/generated.rs");
+    generated.set_contents(lines!["fn a() {}".ai(), "fn b() {}".ai()]);
+    let fully_ai = repo.stage_all_and_commit("Generated code").unwrap();
+    assert_eq!(
+        fully_ai.authorship_log.metadata.classification,
+        Some(CommitClass::FullyAi)
+    );
+
+    let mut mixed = repo.filename("src/mixed.rs");
+    mixed.set_contents(lines!["fn c() {}".ai(), "fn d() {}".human()]);
+    let assisted = repo.stage_all_and_commit("Mixed code").unwrap();
+    assert_eq!(
+        assisted.authorship_log.metadata.classification,
+        Some(CommitClass::AiAssisted)
+    );
+
+    let mut manual = repo.filename("src/manual.rs");
+    manual.set_contents(lines!["fn e() {}".human()]);
+    repo.stage_all_and_commit("Manual code").unwrap();
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let report = report_json(&repo, &[&range, "--period", "day"]);
+
+    assert_eq!(report["by_classification"]["fully_ai"], 1);
+    assert_eq!(report["by_classification"]["ai_assisted"], 1);
+    assert_eq!(report["by_classification"]["human"], 1);
+    let periods = report["classification_over_time"].as_object().unwrap();
+    let total: u64 = periods
+        .values()
+        .flat_map(|counts| counts.as_object().unwrap().values())
+        .map(|count| count.as_u64().unwrap())
+        .sum();
+    assert_eq!(total, 3);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_report_counts_security_findings_from_scan_command() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("run.py");
+    file.set_contents(lines![
+        "import os".human(),
+        "os.system(cmd)".ai(),
+        "print('done')".ai()
+    ]);
+    repo.git(&["add", "-A"]).unwrap();
+
+    // Flags a human line too, which isn't an AI hunk and is dropped
+    let scanner = r#"cat >/dev/null; echo '[{"path": "run.py", "start_line": 2, "rule": "shell-injection", "tags": ["CWE-78"]}, {"path": "run.py", "start_line": 1, "tags": ["CWE-20"]}]'"#;
+    let commit = repo
+        .commit_with_env(
+            "Add runner",
+            &[("GIT_AI_SECURITY_SCAN_COMMAND", scanner)],
+            None,
+        )
+        .unwrap();
+    let findings = &commit
+        .authorship_log
+        .metadata
+        .security_scan
+        .as_ref()
+        .expect("the commit was scanned")
+        .findings;
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].start_line, 2);
+    assert_eq!(findings[0].end_line, 2);
+    assert_eq!(findings[0].rule.as_deref(), Some("shell-injection"));
+
+    let report = report_json(&repo, &["HEAD"]);
+    assert_eq!(report["by_security_tag"]["CWE-78"], 1);
+    assert!(report["by_security_tag"].get("CWE-20").is_none());
+}
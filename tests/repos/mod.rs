@@ -1,5 +1,14 @@
-pub mod test_file;
-pub mod test_repo;
+// Each test binary uses its own subset of the harness
+#[allow(unused_imports)]
+pub use git_ai::testkit::{test_file, test_repo};
+
+/// `git_ai::lines!`, in scope in every test without importing it
+#[macro_export]
+macro_rules! lines {
+    ($($line:expr),* $(,)?) => {
+        git_ai::lines![$($line),*]
+    };
+}
 
 #[macro_export]
 macro_rules! subdir_test_variants {
@@ -46,7 +55,7 @@ macro_rules! subdir_test_variants {
                         command.env("GIT_AI", "git");
 
                         // Add config patch if present
-                        if let Some(patch) = &self.inner.config_patch {
+                        if let Some(patch) = self.inner.config_patch() {
                             if let Ok(patch_json) = serde_json::to_string(patch) {
                                 command.env("GIT_AI_TEST_CONFIG_PATCH", patch_json);
                             }
@@ -91,7 +100,7 @@ macro_rules! subdir_test_variants {
                             command.args(&full_args);
                             command.env("GIT_AI", "git");
 
-                            if let Some(patch) = &self.inner.config_patch {
+                            if let Some(patch) = self.inner.config_patch() {
                                 if let Ok(patch_json) = serde_json::to_string(patch) {
                                     command.env("GIT_AI_TEST_CONFIG_PATCH", patch_json);
                                 }
@@ -0,0 +1,109 @@
+mod repos;
+
+use repos::test_repo::TestRepo;
+use std::fs;
+
+/// An agent-v1 checkpoint of `file` by session `conversation_id`
+fn agent_checkpoint(repo: &TestRepo, file: &str, conversation_id: &str) {
+    let hook_input = serde_json::json!({
+        "type": "ai_agent",
+        "repo_working_dir": repo.path().to_str().unwrap(),
+        "edited_filepaths": [file],
+        "transcript": { "messages": [] },
+        "agent_name": "test-agent",
+        "model": "test-model",
+        "conversation_id": conversation_id,
+    });
+    repo.git_ai(&[
+        "checkpoint",
+        "agent-v1",
+        "--hook-input",
+        &hook_input.to_string(),
+    ])
+    .expect("checkpoint should succeed");
+}
+
+fn session_show_json(repo: &TestRepo, args: &[&str]) -> serde_json::Value {
+    let output = repo
+        .git_ai(&[&["session", "show"], args].concat())
+        .expect("session show should succeed");
+    // Debug builds log to stderr, which is captured too
+    let json = output
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .expect("session show should print JSON");
+    serde_json::from_str(json).expect("session show should print JSON")
+}
+
+#[test]
+fn test_session_show_replays_commits_then_checkpoints() {
+    let repo = TestRepo::new();
+    let path = repo.path().join("app.py");
+    fs::write(&path, "def main():\n    pass\n").unwrap();
+    repo.stage_all_and_commit("Add app").unwrap();
+
+    fs::write(
+        &path,
+        "def main():\n    pass\ndef helper():\n    return 1\n",
+    )
+    .unwrap();
+    agent_checkpoint(&repo, "app.py", "replay-session");
+    let first = repo.stage_all_and_commit("Add helper").unwrap();
+
+    // Another session's edit is not part of the replay
+    fs::write(
+        &path,
+        "def main():\n    pass\ndef helper():\n    return 1\n# other\n",
+    )
+    .unwrap();
+    agent_checkpoint(&repo, "app.py", "other-session");
+
+    fs::write(
+        &path,
+        "def main():\n    run()\ndef helper():\n    return 1\n# other\n",
+    )
+    .unwrap();
+    agent_checkpoint(&repo, "app.py", "replay-session");
+
+    let replay = session_show_json(&repo, &["replay-session", "--json"]);
+    assert_eq!(replay["agent"], "test-agent");
+    assert_eq!(replay["model"], "test-model");
+    let edits = replay["edits"].as_array().unwrap();
+    assert_eq!(edits.len(), 2, "{}", replay);
+
+    assert_eq!(edits[0]["commit"], first.commit_sha);
+    assert_eq!(edits[0]["summary"], "Add helper");
+    let committed = &edits[0]["files"][0];
+    assert_eq!(committed["file"], "app.py");
+    assert_eq!(
+        committed["added"],
+        serde_json::json!([
+            { "line": 3, "text": "def helper():" },
+            { "line": 4, "text": "    return 1" },
+        ])
+    );
+
+    assert!(edits[1]["commit"].is_null());
+    let uncommitted = &edits[1]["files"][0];
+    assert_eq!(uncommitted["removed"], 1);
+    assert_eq!(
+        uncommitted["added"],
+        serde_json::json!([{ "line": 2, "text": "    run()" }])
+    );
+
+    // As of a revision, only its commits
+    let as_of_commit = session_show_json(&repo, &["replay-session", "--rev", "HEAD", "--json"]);
+    assert_eq!(as_of_commit["edits"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_session_show_unknown_session_fails() {
+    let repo = TestRepo::new();
+    fs::write(repo.path().join("a.txt"), "a\n").unwrap();
+    repo.stage_all_and_commit("Initial").unwrap();
+
+    assert!(
+        repo.git_ai(&["session", "show", "no-such-session"])
+            .is_err()
+    );
+}
@@ -0,0 +1,66 @@
+#[macro_use]
+mod repos;
+use git_ai::model::{CommitSummary, FileAttribution, MODEL_SCHEMA_VERSION};
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_show_json_deserializes_into_commit_summary() {
+    let repo = TestRepo::new();
+
+    let mut lib = repo.filename("src/lib.rs");
+    lib.set_contents(lines![
+        "fn human() {}".human(),
+        "fn ai_one() {}".ai(),
+        "fn ai_two() {}".ai()
+    ]);
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project".human()]);
+    let first = repo.stage_all_and_commit("Add lib").unwrap();
+
+    let output = repo.git_ai(&["show", "--json", &first.commit_sha]).unwrap();
+    let summary: CommitSummary = serde_json::from_str(&output).unwrap();
+    assert_eq!(summary.schema_version, MODEL_SCHEMA_VERSION);
+    assert_eq!(summary.commit, first.commit_sha);
+    assert!(summary.has_authorship);
+    assert_eq!(summary.agents.len(), 1);
+
+    // Only files with AI lines are listed
+    assert_eq!(summary.files.len(), 1);
+    let file = &summary.files[0];
+    assert_eq!(file.file, "src/lib.rs");
+    assert_eq!(file.head, first.commit_sha);
+    assert_eq!(file.ranges.len(), 1);
+    assert_eq!((file.ranges[0].start_line, file.ranges[0].end_line), (2, 3));
+    let agent = &summary.agents[&file.ranges[0].prompt_id];
+    assert_eq!(file.ranges[0].tool.as_deref(), Some(agent.tool.as_str()));
+
+    // Ranges give an array, including commits without notes
+    readme.set_contents(lines!["# Project".human(), "More docs".human()]);
+    let second = repo.stage_all_and_commit("Docs").unwrap();
+    let output = repo
+        .git_ai(&["show", "--json", &format!("{}..HEAD", first.commit_sha)])
+        .unwrap();
+    let summaries: Vec<CommitSummary> = serde_json::from_str(&output).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].commit, second.commit_sha);
+    assert!(summaries[0].files.is_empty());
+}
+
+#[test]
+fn test_query_output_deserializes_into_file_attribution() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("app.js");
+    file.set_contents(lines!["const a = 1;".human(), "const b = 2;".ai()]);
+    repo.stage_all_and_commit("Add app").unwrap();
+
+    let output = repo.git_ai(&["query", "app.js"]).unwrap();
+    let result: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    let attribution: FileAttribution =
+        serde_json::from_value(result["attribution"].clone()).unwrap();
+    assert_eq!(attribution.schema_version, MODEL_SCHEMA_VERSION);
+    assert_eq!(attribution.file, "app.js");
+    assert_eq!(attribution.ranges.len(), 1);
+    assert_eq!(attribution.ranges[0].start_line, 2);
+}
@@ -278,6 +278,33 @@ fn test_partial_staging_filters_unstaged_lines() {
     ]);
 }
 
+#[test]
+fn test_partial_staging_with_unstaged_deletion_above_ai_lines() {
+    // An unstaged deletion shifts working-tree line numbers relative to the commit;
+    // AI lines below it must still land on the right committed lines
+    let repo = TestRepo::new();
+    let mut file = repo.filename("shift.ts");
+
+    file.set_contents(lines!["line1", "line2", "line3"]);
+
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    file.insert_at(2, lines!["ai_line".ai()]);
+    file.stage();
+
+    // Human deletes the first line but doesn't stage the deletion
+    file.delete_at(0);
+
+    let commit = repo.commit("Commit AI line only").unwrap();
+    assert_eq!(commit.authorship_log.attestations.len(), 1);
+    assert_eq!(
+        commit.authorship_log.attestations[0].entries[0].line_ranges,
+        vec![git_ai::authorship::authorship_log::LineRange::Single(3)]
+    );
+
+    file.assert_committed_lines(lines!["line2".human(), "ai_line".ai(), "line3".human()]);
+}
+
 #[test]
 fn test_human_stages_some_ai_lines() {
     // Test where AI adds multiple lines but human only stages some of them
@@ -1271,3 +1298,23 @@ fn test_ai_edits_file_with_spaces_in_filename() {
         "Line 3".human(),
     ]);
 }
+
+#[test]
+fn test_authorship_note_lists_files_in_sorted_order() {
+    let repo = TestRepo::new();
+    for name in ["zeta.js", "alpha.js", "src/mid.js", "beta.js"] {
+        let mut file = repo.filename(name);
+        file.set_contents(lines!["// human".human(), format!("// ai {}", name).ai()]);
+    }
+    let commit = repo.stage_all_and_commit("Add files").unwrap();
+
+    let note = repo
+        .git_og(&["notes", "--ref=ai", "show", &commit.commit_sha])
+        .unwrap();
+    let files: Vec<&str> = note
+        .lines()
+        .take_while(|line| *line != "---")
+        .filter(|line| !line.starts_with(' '))
+        .collect();
+    assert_eq!(files, ["alpha.js", "beta.js", "src/mid.js", "zeta.js"]);
+}
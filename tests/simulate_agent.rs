@@ -0,0 +1,71 @@
+mod repos;
+use repos::test_repo::TestRepo;
+
+const SCRIPT: &str = r##"{"steps": [
+    {"human": {"files": {"app.py": ["import os"]}}},
+    {"commit": "Initial commit"},
+    {"ai": {"tool": "claude", "model": "sonnet", "files": {"app.py": ["import os", "print(os.getcwd())", "print(os.sep)"]}}},
+    {"human": {"author": "Reviewer", "files": {"app.py": ["import os", "print(os.getcwd())", "print(os.sep)", "# reviewed"]}}},
+    {"commit": "Print the working directory"},
+    {"expect": {"file": "app.py", "ai_lines": "2-3", "human_lines": "1,4"}},
+    {"expect": {"ai_additions": 2, "human_additions": 1}}
+]}"##;
+
+fn report_json(output: &str) -> serde_json::Value {
+    let line = output
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .expect("simulate-agent should print a JSON report");
+    serde_json::from_str(line).unwrap()
+}
+
+#[test]
+fn test_simulate_agent_replays_script_and_checks_expectations() {
+    let repo = TestRepo::new();
+    let script = repo.path().join("script.json");
+    std::fs::write(&script, SCRIPT).unwrap();
+
+    let output = repo
+        .git_ai(&["simulate-agent", script.to_str().unwrap(), "--json"])
+        .expect("all expectations should pass");
+    let report = report_json(&output);
+    assert_eq!(report["failed_expectations"], 0);
+    assert_eq!(report["steps"].as_array().unwrap().len(), 7);
+    assert_eq!(report["steps"][5]["passed"], true);
+
+    // The scratch repository is removed unless kept
+    let scratch = report["repo"].as_str().unwrap();
+    assert!(!std::path::Path::new(scratch).exists());
+}
+
+#[test]
+fn test_simulate_agent_fails_on_unmet_expectation() {
+    let repo = TestRepo::new();
+    let script = repo.path().join("script.json");
+    std::fs::write(
+        &script,
+        SCRIPT.replace(r#""ai_lines": "2-3""#, r#""ai_lines": "1-3""#),
+    )
+    .unwrap();
+    let scratch = tempfile::tempdir().unwrap();
+    let dir = scratch.path().join("simulation");
+
+    repo.git_ai(&[
+        "simulate-agent",
+        script.to_str().unwrap(),
+        "--dir",
+        dir.to_str().unwrap(),
+    ])
+    .expect_err("an unmet expectation should fail the run");
+
+    // --dir keeps the repository for inspection
+    let log = std::process::Command::new("git")
+        .args(["log", "--format=%s"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&log.stdout),
+        "Print the working directory\nInitial commit\n"
+    );
+}
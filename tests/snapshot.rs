@@ -0,0 +1,90 @@
+mod repos;
+use git_ai::authorship::heatmap::Heatmap;
+use git_ai::authorship::snapshot::Snapshot;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_snapshot_records_ai_lines_of_whole_tree() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("src/lib.rs");
+    file.set_contents(lines![
+        "fn human() {}".human(),
+        "fn ai_one() {}".ai(),
+        "fn ai_two() {}".ai()
+    ]);
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Readme".human()]);
+    let first = repo.stage_all_and_commit("Add lib").unwrap();
+
+    file.set_contents(lines![
+        "fn human() {}".human(),
+        "fn ai_one() {}".ai(),
+        "fn ai_two() {}".ai(),
+        "fn later() {}".human()
+    ]);
+    repo.stage_all_and_commit("Extend lib").unwrap();
+
+    // An older revision, with history resolved up to it only
+    let output = repo
+        .git_ai(&["snapshot", &first.commit_sha, "--json"])
+        .expect("snapshot should succeed");
+    let summary: serde_json::Value =
+        serde_json::from_str(output.trim().lines().last().unwrap()).unwrap();
+    assert_eq!(summary["commit"], first.commit_sha);
+    assert_eq!(summary["files"], 2);
+    assert_eq!(summary["ai_files"], 1);
+    assert_eq!(summary["ai_lines"], 2);
+
+    let path = repo
+        .path()
+        .join(".git")
+        .join("ai")
+        .join("snapshots")
+        .join(format!("{}.json", first.commit_sha));
+    let snapshot = Snapshot::load(&path).unwrap();
+    assert_eq!(snapshot.commit, first.commit_sha);
+    let lib = &snapshot.files["src/lib.rs"];
+    assert_eq!(lib.lines, Some(3));
+    assert_eq!(lib.ai_lines(), vec![2, 3]);
+    let prompt = &snapshot.prompts[&lib.ai[0].prompt];
+    assert!(!prompt.tool.is_empty());
+    let readme = &snapshot.files["README.md"];
+    assert_eq!(readme.lines, Some(1));
+    assert!(readme.ai.is_empty());
+}
+
+#[test]
+fn test_heatmap_reuses_snapshot_of_head() {
+    let repo = TestRepo::new();
+
+    let mut first = repo.filename("a.rs");
+    first.set_contents(lines!["fn a() {}".ai()]);
+    let mut second = repo.filename("b.rs");
+    second.set_contents(lines!["fn b() {}".ai(), "fn c() {}".human()]);
+    repo.stage_all_and_commit("Add files").unwrap();
+
+    let snapshot_path = repo.path().join("snapshot.json");
+    repo.git_ai(&["snapshot", "--output", snapshot_path.to_str().unwrap()])
+        .expect("snapshot should succeed");
+
+    let output = repo
+        .git_ai(&[
+            "heatmap",
+            "--snapshot",
+            snapshot_path.to_str().unwrap(),
+            "--json",
+        ])
+        .expect("heatmap should succeed");
+    let summary: serde_json::Value =
+        serde_json::from_str(output.trim().lines().last().unwrap()).unwrap();
+    assert_eq!(summary["reused"], 2);
+    assert_eq!(summary["computed"], 0);
+
+    let path = repo.path().join(".git").join("ai").join("heatmap");
+    let heatmap = Heatmap::load(&path).unwrap().unwrap();
+    assert!(heatmap.files["b.rs"].is_ai(1));
+    assert!(!heatmap.files["b.rs"].is_ai(2));
+    assert_eq!(heatmap.files["a.rs"].ai_line_count(), 1);
+}
@@ -0,0 +1,59 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_checkpoint_staged_attributes_only_staged_hunks() {
+    let repo = TestRepo::new();
+
+    let mut file = repo.filename("lib.rs");
+    file.set_contents(lines!["fn base() {}"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    // The agent writes and stages its change...
+    std::fs::write(
+        repo.path().join("lib.rs"),
+        "fn base() {}\nfn from_agent() {}\n",
+    )
+    .unwrap();
+    repo.git_og(&["add", "lib.rs"]).unwrap();
+
+    // ...then the human keeps editing in the working tree before the agent's
+    // checkpoint runs
+    std::fs::write(
+        repo.path().join("lib.rs"),
+        "fn base() {}\nfn from_agent() {}\nfn from_human() {}\n",
+    )
+    .unwrap();
+
+    repo.git_ai(&["checkpoint", "mock_ai", "--staged"]).unwrap();
+    repo.stage_all_and_commit("Agent and human changes")
+        .unwrap();
+
+    file.assert_lines_and_blame(lines![
+        "fn base() {}".human(),
+        "fn from_agent() {}".ai(),
+        "fn from_human() {}".human(),
+    ]);
+}
+
+#[test]
+fn test_checkpoint_staged_ignores_unstaged_files() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    std::fs::write(repo.path().join("staged.rs"), "fn staged() {}\n").unwrap();
+    std::fs::write(repo.path().join("unstaged.rs"), "fn unstaged() {}\n").unwrap();
+    repo.git_og(&["add", "staged.rs"]).unwrap();
+
+    repo.git_ai(&["checkpoint", "mock_ai", "--staged"]).unwrap();
+    repo.stage_all_and_commit("Add files").unwrap();
+
+    let mut staged = repo.filename("staged.rs");
+    staged.assert_lines_and_blame(lines!["fn staged() {}".ai()]);
+    let mut unstaged = repo.filename("unstaged.rs");
+    unstaged.assert_lines_and_blame(lines!["fn unstaged() {}".human()]);
+}
@@ -164,6 +164,38 @@ fn test_stats_cli_range() {
     );
 }
 
+#[test]
+fn test_stats_cli_by_language() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let base = repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let mut lib = repo.filename("src/lib.rs");
+    lib.set_contents(lines!["pub fn a() {}".human(), "pub fn b() {}".human()]);
+    let mut script = repo.filename("bin/deploy");
+    script.set_contents(lines!["#!/usr/bin/env python3".ai(), "print('hi')".ai()]);
+    repo.stage_all_and_commit("Add code").unwrap();
+
+    let raw = repo
+        .git_ai(&["stats", "--by-language", "--json"])
+        .expect("git-ai stats --by-language should succeed");
+    let stats: serde_json::Value = serde_json::from_str(&extract_json_object(&raw)).unwrap();
+    assert_eq!(stats["by_language"]["Rust"]["human_lines"], 2);
+    assert_eq!(stats["by_language"]["Python"]["ai_lines"], 2);
+    assert!(stats["by_language"].get("Markdown").is_none());
+
+    let range = format!("{}..HEAD", base.commit_sha);
+    let raw = repo
+        .git_ai(&["stats", &range, "--by-language", "--json"])
+        .expect("git-ai stats range --by-language should succeed");
+    let stats: serde_json::Value = serde_json::from_str(&extract_json_object(&raw)).unwrap();
+    assert_eq!(stats["authorship_stats"]["total_commits"], 1);
+    assert_eq!(stats["by_language"]["Rust"]["ai_lines"], 0);
+    assert_eq!(stats["by_language"]["Python"]["ai_lines"], 2);
+}
+
 #[test]
 fn test_stats_cli_empty_tree_range() {
     let repo = TestRepo::new();
@@ -220,6 +252,7 @@ fn test_markdown_stats_deletion_only() {
         git_diff_deleted_lines: 5,
         git_diff_added_lines: 0,
         tool_model_breakdown: BTreeMap::new(),
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
@@ -243,6 +276,7 @@ fn test_markdown_stats_all_human() {
         git_diff_deleted_lines: 0,
         git_diff_added_lines: 10,
         tool_model_breakdown: BTreeMap::new(),
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
@@ -266,6 +300,7 @@ fn test_markdown_stats_all_ai() {
         git_diff_deleted_lines: 0,
         git_diff_added_lines: 15,
         tool_model_breakdown: BTreeMap::new(),
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
@@ -289,6 +324,7 @@ fn test_markdown_stats_mixed() {
         git_diff_deleted_lines: 5,
         git_diff_added_lines: 30,
         tool_model_breakdown: BTreeMap::new(),
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
@@ -312,6 +348,7 @@ fn test_markdown_stats_no_mixed() {
         git_diff_deleted_lines: 0,
         git_diff_added_lines: 20,
         tool_model_breakdown: BTreeMap::new(),
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
@@ -336,6 +373,7 @@ fn test_markdown_stats_minimal_human() {
         git_diff_deleted_lines: 0,
         git_diff_added_lines: 100,
         tool_model_breakdown: BTreeMap::new(),
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
@@ -372,9 +410,130 @@ fn test_markdown_stats_formatting() {
         git_diff_deleted_lines: 2,
         git_diff_added_lines: 13,
         tool_model_breakdown,
+        attribution_neutral: false,
     };
 
     let markdown = write_stats_to_markdown(&stats);
     println!("{}", markdown);
     assert_debug_snapshot!(markdown);
 }
+
+#[test]
+fn test_stats_cli_range_in_blobless_clone() {
+    let (local, upstream) = TestRepo::new_with_remote();
+
+    let mut file = local.filename("lazy.txt");
+    file.set_contents(lines!["Line 1".human()]);
+    let first = local.stage_all_and_commit("Initial human").unwrap();
+    file.set_contents(lines!["Line 1".human(), "Line 2".ai()]);
+    let second = local.stage_all_and_commit("AI adds line").unwrap();
+    file.set_contents(lines!["Line 1".human(), "Line 2".ai(), "Line 3".human()]);
+    local.stage_all_and_commit("Human adds line").unwrap();
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+
+    upstream
+        .git_og(&["config", "uploadpack.allowFilter", "true"])
+        .unwrap();
+    upstream
+        .git_og(&["config", "uploadpack.allowAnySHA1InWant", "true"])
+        .unwrap();
+    let clone_path = std::env::temp_dir().join(format!(
+        "{}-blobless",
+        local.path().file_name().unwrap().to_string_lossy()
+    ));
+    let status = std::process::Command::new("git")
+        .args(["clone", "--filter=blob:none"])
+        .arg(format!("file://{}", upstream.path().display()))
+        .arg(&clone_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let clone = TestRepo::new_at_path(&clone_path);
+    clone
+        .git_og(&["fetch", "origin", "refs/notes/ai:refs/notes/ai"])
+        .unwrap();
+
+    // The checkout only brought the tip's blobs; the range start's version is missing
+    let old_blob = clone
+        .git_og(&["rev-parse", &format!("{}:lazy.txt", first.commit_sha)])
+        .unwrap()
+        .trim()
+        .to_string();
+    let missing = || {
+        clone
+            .git_og(&["rev-list", "--objects", "--missing=print", "--all"])
+            .unwrap()
+            .lines()
+            .any(|line| line == format!("?{}", old_blob))
+    };
+    assert!(missing());
+
+    let range = format!("{}..{}", first.commit_sha, second.commit_sha);
+    let raw = clone
+        .git_ai(&["stats", &range, "--json"])
+        .expect("stats should succeed in a blobless clone");
+    let stats: git_ai::authorship::range_authorship::RangeAuthorshipStats =
+        serde_json::from_str(&extract_json_object(&raw)).unwrap();
+    assert_eq!(stats.authorship_stats.total_commits, 1);
+    assert_eq!(stats.range_stats.ai_additions, 1);
+    assert!(!missing());
+}
+
+#[test]
+fn test_stats_cli_range_in_shallow_clone_clamps_or_deepens() {
+    let (local, upstream) = TestRepo::new_with_remote();
+
+    let mut file = local.filename("shallow.txt");
+    file.set_contents(lines!["Line 1".human()]);
+    let first = local.stage_all_and_commit("Initial human").unwrap();
+    file.set_contents(lines!["Line 1".human(), "Line 2".ai()]);
+    local.stage_all_and_commit("AI adds line").unwrap();
+    file.set_contents(lines!["Line 1".human(), "Line 2".ai(), "Line 3".human()]);
+    local.stage_all_and_commit("Human adds line").unwrap();
+    file.set_contents(lines![
+        "Line 1".human(),
+        "Line 2".ai(),
+        "Line 3".human(),
+        "Line 4".ai()
+    ]);
+    local.stage_all_and_commit("AI adds another line").unwrap();
+    local.git(&["push", "-u", "origin", "HEAD"]).unwrap();
+
+    let clone_path = std::env::temp_dir().join(format!(
+        "{}-shallow",
+        local.path().file_name().unwrap().to_string_lossy()
+    ));
+    let status = std::process::Command::new("git")
+        .args(["clone", "--depth", "2"])
+        .arg(format!("file://{}", upstream.path().display()))
+        .arg(&clone_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let clone = TestRepo::new_at_path(&clone_path);
+    clone
+        .git_og(&["fetch", "origin", "refs/notes/ai:refs/notes/ai"])
+        .unwrap();
+
+    // The range starts before the shallow boundary: stats cover what's available
+    let range = format!("{}..HEAD", first.commit_sha);
+    let raw = clone
+        .git_ai(&["stats", &range, "--json"])
+        .expect("stats should clamp the range");
+    assert!(raw.contains("is not available in this shallow clone"));
+    assert!(raw.contains("--deepen"));
+    let stats: git_ai::authorship::range_authorship::RangeAuthorshipStats =
+        serde_json::from_str(&extract_json_object(&raw)).unwrap();
+    assert_eq!(stats.authorship_stats.total_commits, 1);
+    assert_eq!(stats.range_stats.ai_additions, 1);
+
+    // --deepen fetches the history the range needs instead
+    let raw = clone
+        .git_ai(&["stats", &range, "--json", "--deepen"])
+        .expect("stats should deepen the clone");
+    assert!(!raw.contains("is not available in this shallow clone"));
+    let stats: git_ai::authorship::range_authorship::RangeAuthorshipStats =
+        serde_json::from_str(&extract_json_object(&raw)).unwrap();
+    assert_eq!(stats.authorship_stats.total_commits, 3);
+    assert_eq!(stats.range_stats.ai_additions, 2);
+}
@@ -0,0 +1,57 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn usage_telemetry_is_opt_in_and_spools_only_command_metadata() {
+    let repo = TestRepo::new();
+    let home = tempfile::tempdir().unwrap();
+    let home_path = home.path().to_str().unwrap();
+    let env = [("HOME", home_path)];
+    let spool = home.path().join(".git-ai/internal/usage-spool.jsonl");
+
+    let mut file = repo.filename("secret-project-plan.txt");
+    file.set_contents(lines!["first".human()]);
+
+    // Off by default: nothing is spooled
+    repo.git_with_env(&["add", "-A"], &env, None).unwrap();
+    let status = repo
+        .git_ai_with_env(&["telemetry", "status"], &env)
+        .unwrap();
+    assert!(status.contains("Usage telemetry: disabled"));
+    assert!(!spool.exists());
+
+    repo.git_ai_with_env(&["telemetry", "on"], &env).unwrap();
+    repo.git_with_env(&["commit", "-m", "Add plan"], &env, None)
+        .unwrap();
+    repo.git_ai_with_env(&["stats", "--json"], &env).unwrap();
+
+    let contents = std::fs::read_to_string(&spool).unwrap();
+    let events: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(
+        events
+            .iter()
+            .any(|e| e["kind"] == "git" && e["command"] == "commit" && e["repo_size"] == "<100")
+    );
+    assert!(
+        events
+            .iter()
+            .any(|e| e["kind"] == "git-ai" && e["command"] == "stats")
+    );
+    assert!(!contents.contains("secret-project-plan"));
+    assert!(!contents.contains("Add plan"));
+    assert!(!contents.contains(repo.path().to_str().unwrap()));
+
+    let status = repo
+        .git_ai_with_env(&["telemetry", "status"], &env)
+        .unwrap();
+    assert!(status.contains("Usage telemetry: enabled"));
+
+    // Opting out discards anything not yet uploaded
+    repo.git_ai_with_env(&["telemetry", "off"], &env).unwrap();
+    assert!(!spool.exists());
+}
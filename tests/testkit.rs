@@ -0,0 +1,44 @@
+//! The mock agent helpers of `git_ai::testkit`, used the way a downstream plugin would
+use git_ai::testkit::TestRepo;
+
+fn test_repo() -> TestRepo {
+    TestRepo::new()
+}
+
+#[test]
+fn test_testkit_mock_ai_edit_is_attributed() {
+    let repo = test_repo();
+    repo.human_edit("README.md", "# Project\n").unwrap();
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    repo.mock_ai_edit("src/lib.rs", "pub fn answer() -> u32 {\n    42\n}\n")
+        .unwrap();
+    let commit = repo.stage_all_and_commit("Add answer").unwrap();
+
+    let files: Vec<&str> = commit
+        .authorship_log
+        .attestations
+        .iter()
+        .map(|attestation| attestation.file_path.as_str())
+        .collect();
+    assert_eq!(files, vec!["src/lib.rs"]);
+    assert_eq!(
+        repo.authorship_log(&commit.commit_sha)
+            .unwrap()
+            .unwrap()
+            .attestations
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_testkit_human_commit_has_no_ai_lines() {
+    let repo = test_repo();
+    repo.human_edit("notes.txt", "written by hand\n").unwrap();
+    let commit = repo.stage_all_and_commit("Human commit").unwrap();
+
+    assert!(commit.authorship_log.attestations.is_empty());
+    assert_eq!(repo.read_file("notes.txt").unwrap(), "written by hand\n");
+    assert!(repo.git_ai(&["blame", "notes.txt"]).is_ok());
+}
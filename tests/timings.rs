@@ -0,0 +1,27 @@
+#[macro_use]
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_timings_report_lists_phases_that_ran() {
+    let repo = TestRepo::new();
+    let mut file = repo.filename("app.js");
+    file.set_contents(lines!["const a = 1;".human(), "const b = 2;".ai()]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    let output = repo.git_ai(&["--timings", "blame", "app.js"]).unwrap();
+    assert!(output.contains("const b = 2;"), "{}", output);
+    assert!(output.contains("git-ai timings (total"), "{}", output);
+    assert!(output.contains("  blame"), "{}", output);
+    assert!(output.contains("  other"), "{}", output);
+    assert!(!output.contains("  push"), "{}", output);
+
+    let output = repo
+        .git_ai_with_env(&["blame", "app.js"], &[("GIT_AI_TIMINGS", "1")])
+        .unwrap();
+    assert!(output.contains("git-ai timings (total"), "{}", output);
+
+    let output = repo.git_ai(&["blame", "app.js"]).unwrap();
+    assert!(!output.contains("git-ai timings"), "{}", output);
+}
@@ -0,0 +1,67 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+fn top_json(repo: &TestRepo, args: &[&str]) -> serde_json::Value {
+    let mut full_args = vec!["top", "--json"];
+    full_args.extend_from_slice(args);
+    let output = repo.git_ai(&full_args).expect("top should succeed");
+    serde_json::from_str(output.trim()).expect("top should print JSON")
+}
+
+fn paths(list: &serde_json::Value) -> Vec<String> {
+    list.as_array()
+        .unwrap()
+        .iter()
+        .map(|h| h["path"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_top_ranks_files_by_ai_lines_and_share() {
+    let repo = TestRepo::new();
+
+    let mut mostly_ai = repo.filename("src/mostly_ai.rs");
+    mostly_ai.set_contents(lines![
+        "fn a() {}".ai(),
+        "fn b() {}".ai(),
+        "fn c() {}".ai(),
+        "fn d() {}".human()
+    ]);
+    let mut all_ai = repo.filename("src/all_ai.rs");
+    all_ai.set_contents(lines!["fn e() {}".ai(), "fn f() {}".ai()]);
+    let mut human = repo.filename("docs/notes.md");
+    human.set_contents(lines!["notes".human(), "more notes".human()]);
+    repo.stage_all_and_commit("Add files").unwrap();
+
+    let result = top_json(&repo, &["--min-lines", "2"]);
+
+    assert_eq!(
+        paths(&result["by_ai_lines"]),
+        vec!["src/mostly_ai.rs", "src/all_ai.rs"]
+    );
+    assert_eq!(result["by_ai_lines"][0]["ai_lines"], 3);
+    assert_eq!(
+        paths(&result["by_ai_share"]),
+        vec!["src/all_ai.rs", "src/mostly_ai.rs"]
+    );
+
+    let dirs = top_json(&repo, &["--dirs", "--min-lines", "2"]);
+    assert_eq!(paths(&dirs["by_ai_lines"]), vec!["src"]);
+    assert_eq!(dirs["by_ai_lines"][0]["ai_lines"], 5);
+}
+
+#[test]
+fn test_top_skips_deleted_files() {
+    let repo = TestRepo::new();
+
+    let mut scratch = repo.filename("scratch.rs");
+    scratch.set_contents(lines!["fn a() {}".ai()]);
+    repo.stage_all_and_commit("Add scratch").unwrap();
+
+    repo.git(&["rm", "-q", "scratch.rs"]).unwrap();
+    repo.git(&["commit", "-q", "-m", "Remove scratch"]).unwrap();
+
+    let result = top_json(&repo, &["--min-lines", "0"]);
+    assert!(paths(&result["by_ai_lines"]).is_empty());
+}
@@ -0,0 +1,50 @@
+mod repos;
+use repos::test_file::ExpectedLineExt;
+use repos::test_repo::TestRepo;
+
+#[test]
+fn test_work_item_parsed_from_branch_name() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    let initial = repo.stage_all_and_commit("Initial commit").unwrap();
+    assert_eq!(initial.authorship_log.metadata.work_item, None);
+
+    repo.git(&["checkout", "-b", "feature/PROJ-42-add-login"])
+        .unwrap();
+
+    let mut file = repo.filename("login.rs");
+    file.set_contents(lines!["fn login() {}".ai()]);
+    let commit = repo.stage_all_and_commit("Add login").unwrap();
+
+    assert_eq!(
+        commit.authorship_log.metadata.work_item,
+        Some("PROJ-42".to_string())
+    );
+}
+
+#[test]
+fn test_work_item_from_env_overrides_branch() {
+    let repo = TestRepo::new();
+
+    let mut readme = repo.filename("README.md");
+    readme.set_contents(lines!["# Project"]);
+    repo.stage_all_and_commit("Initial commit").unwrap();
+
+    repo.git(&["checkout", "-b", "feature/PROJ-42-add-login"])
+        .unwrap();
+
+    std::fs::write(repo.path().join("login.rs"), "fn login() {}\n").unwrap();
+    repo.git_ai_with_env(
+        &["checkpoint", "mock_ai", "login.rs"],
+        &[("GIT_AI_WORK_ITEM", "OPS-7")],
+    )
+    .unwrap();
+    let commit = repo.stage_all_and_commit("Add login").unwrap();
+
+    assert_eq!(
+        commit.authorship_log.metadata.work_item,
+        Some("OPS-7".to_string())
+    );
+}